@@ -20,15 +20,27 @@ impl crate::checkpoint::AgentContext for Agent {
         self.chat_formatter
             .get_messages()
             .iter()
-            .map(|msg| ChatMessage {
-                role: msg.role.to_string(),
-                content: msg.content.clone(),
-                reasoning: msg.reasoning_content.clone(),
-                timestamp: chrono::Utc::now(), // TODO: Track actual message timestamps
-                token_count: Some(self.estimate_token_count(&msg.content)),
-                tool_calls: msg.tool_calls.clone(),
-                tool_call_id: msg.tool_call_id.clone(),
-                name: msg.name.clone(),
+            .map(|msg| {
+                // Signed thinking blocks ride in the history's reasoning text;
+                // checkpoints keep them apart from the readable reasoning
+                let (reasoning, thinking_blocks) = match msg.reasoning_content.as_deref() {
+                    Some(r) => {
+                        let (text, blocks) = crate::provider::thinking::split(r);
+                        (Some(text.to_string()).filter(|t| !t.is_empty()), blocks)
+                    }
+                    None => (None, None),
+                };
+                ChatMessage {
+                    role: msg.role.to_string(),
+                    content: msg.content.clone(),
+                    reasoning,
+                    thinking_blocks,
+                    timestamp: chrono::Utc::now(), // TODO: Track actual message timestamps
+                    token_count: Some(self.estimate_token_count(&msg.content)),
+                    tool_calls: msg.tool_calls.clone(),
+                    tool_call_id: msg.tool_call_id.clone(),
+                    name: msg.name.clone(),
+                }
             })
             .collect()
     }
@@ -75,6 +87,24 @@ impl crate::checkpoint::AgentContext for Agent {
         }
     }
 
+    fn add_assistant_message_with_thinking(
+        &mut self,
+        content: String,
+        reasoning: Option<String>,
+        thinking_blocks: Option<Vec<JsonValue>>,
+        tool_calls: Option<Vec<umf::ToolCall>>,
+        name: Option<String>,
+    ) {
+        let reasoning = match thinking_blocks {
+            Some(blocks) => Some(crate::provider::thinking::encode(
+                reasoning.as_deref().unwrap_or_default(),
+                &blocks,
+            )),
+            None => reasoning,
+        };
+        self.add_assistant_message_with_reasoning(content, reasoning, tool_calls, name);
+    }
+
     fn add_tool_message(&mut self, content: String, tool_call_id: String, name: String) {
         self.chat_formatter
             .add_tool_message(content, tool_call_id, name);
@@ -197,6 +227,41 @@ impl crate::checkpoint::AgentContext for Agent {
         self.provider.default_model().to_string()
     }
 
+    fn fill_usage_stats(&self, stats: &mut crate::checkpoint::ConversationStats) {
+        let totals = self.usage_tracker.totals();
+        stats.api_calls = totals.api_calls;
+        stats.estimated_cost = totals.estimated_cost;
+        stats.token_usage = crate::checkpoint::TokenUsageStats {
+            prompt_tokens: totals.usage.prompt_tokens,
+            completion_tokens: totals.usage.completion_tokens,
+            cached_tokens: totals.usage.cached_tokens,
+            reasoning_tokens: totals.usage.reasoning_tokens,
+        };
+    }
+
+    fn restore_usage_stats(&mut self, stats: &crate::checkpoint::ConversationStats) {
+        self.usage_tracker.restore(crate::provider::UsageTotals {
+            usage: crate::provider::TokenUsage {
+                prompt_tokens: stats.token_usage.prompt_tokens,
+                completion_tokens: stats.token_usage.completion_tokens,
+                cached_tokens: stats.token_usage.cached_tokens,
+                reasoning_tokens: stats.token_usage.reasoning_tokens,
+            },
+            api_calls: stats.api_calls,
+            estimated_cost: stats.estimated_cost,
+            // Not checkpointed; calls after resume are counted again
+            unreported_calls: 0,
+        });
+    }
+
+    fn workspace_tracker(&self) -> Option<&crate::checkpoint::WorkspaceTracker> {
+        Some(&self.workspace_tracker)
+    }
+
+    fn restore_workspace_tracker(&mut self, tracker: crate::checkpoint::WorkspaceTracker) {
+        self.workspace_tracker = tracker;
+    }
+
     // ========================================================================
     // Logging
     // ========================================================================
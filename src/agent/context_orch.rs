@@ -62,7 +62,7 @@ impl AgentContext for super::Agent {
         self.provider.provider_name().to_string()
     }
     fn default_model(&self) -> String {
-        self.provider.default_model()
+        self.active_model()
     }
     
     // LLM generation
@@ -89,7 +89,7 @@ impl AgentContext for super::Agent {
 
         // Build configuration
         let config = GenerateConfig {
-            model: None, // Use provider's default model
+            model: self.model_override.clone(), // None = provider's default model
             temperature: 0.7,
             max_tokens: Some(max_tokens),
             tools: internal_tools,
@@ -100,16 +100,28 @@ impl AgentContext for super::Agent {
 
         // Call provider based on streaming mode
         let response = if streaming_enabled {
-            let stream = self.provider.generate_stream(messages, &config).await?;
+            let request_started = std::time::Instant::now();
+            let stream = self.provider.generate_stream(messages, &config).await;
+            self.emit_provider_fallbacks();
+            let stream = stream?;
             
             use futures_util::StreamExt;
             let mut pinned_stream = Box::pin(stream);
             let mut accumulator = umf::StreamingAccumulator::new();
             let mut saw_reasoning = false;
             let mut saw_content = false;
+            let mut saw_chunk = false;
 
             while let Some(chunk_result) = pinned_stream.next().await {
                 let chunk = chunk_result?;
+                if !saw_chunk {
+                    saw_chunk = true;
+                    crate::observability::metrics::record_time_to_first_token(
+                        self.provider.provider_name(),
+                        &self.provider.default_model(),
+                        request_started.elapsed(),
+                    );
+                }
                 // Emit streaming chunks to output sink for TUI/CLI consumers.
                 // Track reasoning→content transitions to insert line breaks.
                 match &chunk {
@@ -129,7 +141,10 @@ impl AgentContext for super::Agent {
                             },
                         );
                     }
-                    umf::StreamChunk::Reasoning(delta) if !delta.is_empty() => {
+                    // Signed thinking trailers are kept for the history, not shown
+                    umf::StreamChunk::Reasoning(delta)
+                        if !delta.is_empty() && !crate::provider::thinking::is_trailer(delta) =>
+                    {
                         saw_reasoning = true;
                         self.output_sink().emit(
                             crate::orchestration::output::OutputEvent::ReasoningChunk {
@@ -165,11 +180,14 @@ impl AgentContext for super::Agent {
                 }
             }
         } else {
-            let provider_response = self.provider.generate(messages, &config).await?;
+            let provider_response = self.provider.generate(messages, &config).await;
+            self.emit_provider_fallbacks();
+            let provider_response = provider_response?;
             
             match provider_response {
                 GenerateResponse::Content { text, reasoning } => umf::GenerateResult::Content { text, reasoning },
                 GenerateResponse::ToolCalls { calls: invocations, reasoning } => {
+                    let reasoning = crate::provider::thinking::history_reasoning(reasoning, &invocations);
                     let tool_calls = invocations
                         .into_iter()
                         .map(|inv| umf::ToolCall {
@@ -186,6 +204,7 @@ impl AgentContext for super::Agent {
             }
         };
 
+        self.record_token_usage();
         Ok(response)
     }
     
@@ -194,19 +213,98 @@ impl AgentContext for super::Agent {
         -> Result<Vec<crate::orchestration::agent_orchestration::ToolExecutionResult>> {
         // Call the existing method and convert types
         let results = self.execute_tool_calls_structured(tool_calls).await?;
-        Ok(results.into_iter().map(|r| crate::orchestration::agent_orchestration::ToolExecutionResult {
-            tool_call_id: r.tool_call_id,
-            tool_name: r.tool_name,
-            content: r.content,
-            success: r.success,
-            description: r.description,
-        }).collect())
+        Ok(results.into_iter().map(to_orchestration_result).collect())
+    }
+
+    async fn execute_tool_calls_reporting(
+        &mut self,
+        tool_calls: Vec<umf::ToolCall>,
+        on_complete: &(dyn Fn(&crate::orchestration::agent_orchestration::ToolExecutionResult, std::time::Instant) + Send + Sync),
+    ) -> Result<Vec<crate::orchestration::agent_orchestration::ToolExecutionResult>> {
+        let report = |r: &crate::agent::types::ToolExecutionResult, started: std::time::Instant| {
+            on_complete(&to_orchestration_result(r.clone()), started)
+        };
+        let results = self.execute_tool_calls_reporting(tool_calls, &report).await?;
+        Ok(results.into_iter().map(to_orchestration_result).collect())
     }
     
     fn generate_assistant_content_for_tools(&self, tool_calls: &[umf::ToolCall]) -> String {
         self.generate_assistant_content_for_tools(tool_calls)
     }
     
+    async fn refresh_tool_sources(&mut self) {
+        #[cfg(feature = "registry-mcp")]
+        if let Some(ref mut mcp) = self.mcp_tools {
+            mcp.refresh_changed().await;
+        }
+    }
+
+    fn context_compactor_mut(&mut self) -> Option<&mut crate::orchestration::ContextCompactor> {
+        self.context_compactor.as_mut()
+    }
+
+    fn on_context_compacted(&mut self, outcome: &crate::orchestration::CompactionOutcome) {
+        let Some(ref session_manager) = self.session_manager else {
+            return;
+        };
+        let payload = serde_json::to_value(outcome).unwrap_or_default();
+        if let Err(e) = session_manager.record_event(crate::checkpoint::EventType::ContextCompaction, payload) {
+            crate::observability::tee_eprintln(&format!("[checkpoint] Warning: Failed to record compaction event: {}", e));
+        }
+    }
+
+    fn context_injector_mut(&mut self) -> Option<&mut crate::orchestration::ContextInjector> {
+        self.context_injector.as_mut()
+    }
+
+    fn user_input_mut(&mut self) -> Option<&mut crate::orchestration::UserInputQueue> {
+        self.user_input.as_mut()
+    }
+
+    fn tracer(&self) -> crate::observability::Tracer {
+        self.tracer.clone()
+    }
+
+    fn metrics_textfile(&self) -> Option<std::path::PathBuf> {
+        self.metrics_textfile.clone()
+    }
+
+    fn approval_gate_mut(&mut self) -> Option<&mut crate::orchestration::ApprovalGate> {
+        if !self.requires_tool_approval() {
+            return None;
+        }
+        self.approval_gate.as_mut()
+    }
+
+    fn tool_approval_required(&self) -> bool {
+        self.requires_tool_approval()
+    }
+
+    fn usage_totals(&self) -> crate::provider::UsageTotals {
+        self.usage_tracker.totals().clone()
+    }
+
+    fn budget_guard_mut(&mut self) -> Option<&mut crate::orchestration::BudgetGuard> {
+        self.budget_guard.as_mut()
+    }
+
+    fn budget_approver(&self) -> Option<crate::orchestration::SharedApprover> {
+        self.approval_gate.as_ref().map(|gate| gate.approver().clone())
+    }
+
+    fn switch_to_utility_model(&mut self) -> bool {
+        let utility_model = self.config.config.llm.as_ref()
+            .and_then(|l| l.utility.as_ref())
+            .and_then(|u| u.model.clone());
+        match utility_model {
+            Some(model) => {
+                self.model_override = Some(model);
+                true
+            }
+            None => false,
+        }
+    }
+    
     fn get_tool_schemas(&self) -> Vec<serde_json::Value> {
         // Start with CATS tools — filter by enabled/disabled tool config
         let mut schemas: Vec<serde_json::Value> = self.tool_registry
@@ -236,6 +334,16 @@ impl AgentContext for super::Agent {
                 }
             }
         }
+
+        // Add tools contributed by WASM extensions (also subject to filtering)
+        #[cfg(feature = "registry-extension")]
+        if let Some(ref extension_tools) = self.extension_tools {
+            for descriptor in extension_tools.all_schemas() {
+                if self.is_tool_allowed(&descriptor.name) {
+                    schemas.push(descriptor.to_openai_schema());
+                }
+            }
+        }
         
         schemas
     }
@@ -311,8 +419,13 @@ impl AgentContext for super::Agent {
             return false;
         }
         
-        // Get checkpoint interval from config (default to 1 if not set)
-        let interval = self.config.get_u64("checkpointing.auto_checkpoint_interval").unwrap_or(1) as u32;
+        // Get checkpoint interval from the project, then config (default to 1 if not set)
+        let interval = self.session_manager
+            .as_ref()
+            .and_then(|sm| sm.checkpoint_interval())
+            .unwrap_or_else(|| {
+                self.config.get_u64("checkpointing.auto_checkpoint_interval").unwrap_or(1) as u32
+            });
         
         // Checkpoint every N iterations (including iteration 0 for initial state)
         self.current_iteration % interval == 0
@@ -389,3 +502,15 @@ impl AgentContext for super::Agent {
         self.extract_tool_calls(response)
     }
 }
+
+fn to_orchestration_result(
+    r: crate::agent::types::ToolExecutionResult,
+) -> crate::orchestration::agent_orchestration::ToolExecutionResult {
+    crate::orchestration::agent_orchestration::ToolExecutionResult {
+        tool_call_id: r.tool_call_id,
+        tool_name: r.tool_name,
+        content: r.content,
+        success: r.success,
+        description: r.description,
+    }
+}
@@ -6,7 +6,7 @@
 #[cfg(feature = "registry-mcp")]
 use crate::config::{McpConfig, McpCredentialConfig};
 #[cfg(feature = "registry-mcp")]
-use crate::registry::{McpClient, McpServerConfig as RegistryServerConfig, ToolRegistry};
+use crate::registry::{McpClient, McpProgress, McpServerConfig as RegistryServerConfig, McpStdioConfig, ToolRegistry};
 #[cfg(feature = "registry-mcp")]
 use anyhow::Result;
 #[cfg(feature = "registry-mcp")]
@@ -35,7 +35,7 @@ pub struct McpToolLoader {
     pub tool_count: usize,
     /// Server configurations indexed by server name
     server_configs: HashMap<String, RegistryServerConfig>,
-    /// HTTP client for making tool calls
+    /// Client for making tool calls (owns any stdio server processes)
     client: McpClient,
     /// Per-server connection status collected during `new()`.
     /// Used by the agent to emit `OutputEvent::McpServerStatus` events
@@ -109,21 +109,33 @@ impl McpToolLoader {
         let mut server_statuses = Vec::new();
 
         for server in &config.servers {
-            if server.transport != "http" {
-                crate::observability::tee_eprintln(
-                    &format!("Warning: MCP server '{}' uses unsupported transport '{}', skipping",
-                    server.name, server.transport)
-                );
-                continue;
-            }
-
-            let client_config = build_registry_config(
-                &server.name,
-                &server.url,
-                server.auth_token.as_deref(),
-                server.credentials.as_deref(),
-                &config.credentials,
-            ).await;
+            let client_config = match server.transport.as_str() {
+                "http" | "streamable-http" => {
+                    let client_config = build_registry_config(
+                        &server.name,
+                        &server.url,
+                        server.auth_token.as_deref(),
+                        server.credentials.as_deref(),
+                        &config.credentials,
+                    ).await;
+                    if server.transport == "streamable-http" {
+                        client_config.with_streamable_http()
+                    } else {
+                        client_config
+                    }
+                }
+                "stdio" => match build_stdio_config(server, config.timeout_seconds) {
+                    Some(client_config) => client_config,
+                    None => continue,
+                },
+                other => {
+                    crate::observability::tee_eprintln(
+                        &format!("Warning: MCP server '{}' uses unsupported transport '{}', skipping",
+                        server.name, other)
+                    );
+                    continue;
+                }
+            };
 
             server_configs.insert(server.name.clone(), client_config.clone());
 
@@ -210,23 +222,35 @@ impl McpToolLoader {
         let mut server_statuses = Vec::new();
 
         for server in &config.servers {
-            if server.transport != "http" {
-                crate::observability::tee_eprintln(
-                    &format!("Warning: MCP server '{}' uses unsupported transport '{}', skipping",
-                    server.name, server.transport)
-                );
-                continue;
-            }
-
             // Build registry config from server config + credentials
-            let client_config = build_registry_config(
-                &server.name,
-                &server.url,
-                server.auth_token.as_deref(),
-                server.credentials.as_deref(),
-                &config.credentials,
-                token_store.clone(),
-            ).await;
+            let client_config = match server.transport.as_str() {
+                "http" | "streamable-http" => {
+                    let client_config = build_registry_config(
+                        &server.name,
+                        &server.url,
+                        server.auth_token.as_deref(),
+                        server.credentials.as_deref(),
+                        &config.credentials,
+                        token_store.clone(),
+                    ).await;
+                    if server.transport == "streamable-http" {
+                        client_config.with_streamable_http()
+                    } else {
+                        client_config
+                    }
+                }
+                "stdio" => match build_stdio_config(server, config.timeout_seconds) {
+                    Some(client_config) => client_config,
+                    None => continue,
+                },
+                other => {
+                    crate::observability::tee_eprintln(
+                        &format!("Warning: MCP server '{}' uses unsupported transport '{}', skipping",
+                        server.name, other)
+                    );
+                    continue;
+                }
+            };
 
             // Store the server config for later tool calls
             server_configs.insert(server.name.clone(), client_config.clone());
@@ -330,6 +354,56 @@ impl McpToolLoader {
         self.registry.find(tool_name).and_then(|t| t.origin().map(String::from))
     }
 
+    /// Check if a tool may run concurrently with other tool calls.
+    ///
+    /// A tool is parallel-safe when its server annotated it with
+    /// `readOnlyHint` or `idempotentHint`. Unannotated tools are not.
+    pub fn is_parallel_safe(&self, tool_name: &str) -> bool {
+        self.registry
+            .find(tool_name)
+            .and_then(|t| t.tool().get_metadata("mcp_annotations").cloned())
+            .and_then(|v| serde_json::from_value::<umf::McpToolAnnotations>(v).ok())
+            .map(|a| a.read_only_hint == Some(true) || a.idempotent_hint == Some(true))
+            .unwrap_or(false)
+    }
+
+    /// Re-fetch tools from servers that sent `notifications/tools/list_changed`.
+    ///
+    /// Returns the names of the servers that were refreshed.
+    pub async fn refresh_changed(&mut self) -> Vec<String> {
+        let mut refreshed = Vec::new();
+        for server in self.client.take_changed_servers() {
+            let Some(server_config) = self.server_configs.get(&server) else {
+                continue;
+            };
+            match self.client.fetch_tools(server_config).await {
+                Ok(tools) => {
+                    let removed = self.registry.remove_server_tools(&server);
+                    let registered = self.registry.register_mcp_batch(tools, &server).unwrap_or(0);
+                    self.tool_count = self.tool_count.saturating_sub(removed) + registered;
+                    if let Some(status) = self.server_statuses.iter_mut().find(|s| s.name == server) {
+                        status.tool_count = registered;
+                    }
+                    crate::observability::tee_println(
+                        &format!("✓ Reloaded {} tools from MCP server '{}'", registered, server)
+                    );
+                    refreshed.push(server);
+                }
+                Err(e) => {
+                    crate::observability::tee_eprintln(
+                        &format!("Warning: Failed to reload tools from MCP server '{}': {}", server, e)
+                    );
+                }
+            }
+        }
+        refreshed
+    }
+
+    /// Stop all stdio server processes and notification streams.
+    pub async fn shutdown(&self) {
+        self.client.shutdown().await;
+    }
+
     /// Execute an MCP tool by calling the remote server.
     ///
     /// # Arguments
@@ -342,6 +416,17 @@ impl McpToolLoader {
         &self,
         tool_name: &str,
         arguments: &str,
+    ) -> Result<McpToolExecutionResult> {
+        self.execute_tool_with_progress(tool_name, arguments, &|_| {}).await
+    }
+
+    /// Execute an MCP tool, reporting server progress notifications through
+    /// `on_progress` while it runs.
+    pub async fn execute_tool_with_progress(
+        &self,
+        tool_name: &str,
+        arguments: &str,
+        on_progress: &(dyn Fn(&McpProgress) + Send + Sync),
     ) -> Result<McpToolExecutionResult> {
         // Find which server this tool belongs to
         let server_name = self
@@ -361,7 +446,7 @@ impl McpToolLoader {
         // Call the tool
         let result = self
             .client
-            .call_tool(server_config, tool_name, args)
+            .call_tool_with_progress(server_config, tool_name, args, on_progress)
             .await
             .map_err(|e| anyhow::anyhow!("MCP tool call failed: {}", e))?;
 
@@ -385,6 +470,32 @@ pub struct McpToolExecutionResult {
 // Credential resolution
 // ---------------------------------------------------------------------------
 
+/// Build a stdio `RegistryServerConfig` from a `transport = "stdio"` server entry.
+///
+/// The server's `timeout_seconds` overrides `default_timeout` (`mcp.timeout_seconds`).
+/// Returns `None` (after warning) when `command` is missing.
+#[cfg(feature = "registry-mcp")]
+fn build_stdio_config(server: &crate::config::McpServerConfig, default_timeout: u64) -> Option<RegistryServerConfig> {
+    let Some(ref command) = server.command else {
+        crate::observability::tee_eprintln(
+            &format!("Warning: MCP server '{}' uses stdio transport but has no `command`, skipping",
+            server.name)
+        );
+        return None;
+    };
+
+    let mut stdio = McpStdioConfig::new(command).with_args(server.args.iter().cloned());
+    for (key, value) in &server.env {
+        stdio = stdio.with_env(key, resolve_env_var(value));
+    }
+    if let Some(ref cwd) = server.cwd {
+        stdio = stdio.with_cwd(cwd);
+    }
+    let seconds = server.timeout_seconds.unwrap_or(default_timeout);
+    stdio = stdio.with_timeout(std::time::Duration::from_secs(seconds));
+    Some(RegistryServerConfig::stdio(&server.name, stdio))
+}
+
 /// Build a `RegistryServerConfig` from server config fields and the credentials registry.
 ///
 /// Priority:
@@ -666,4 +777,33 @@ mod tests {
         assert_eq!(loader.tool_count, 0);
         assert!(!loader.has_tools());
     }
+
+    #[test]
+    fn test_build_stdio_config() {
+        let mut server: crate::config::McpServerConfig = toml::from_str(
+            r#"
+name = "fs"
+transport = "stdio"
+command = "npx"
+args = ["-y", "@modelcontextprotocol/server-filesystem", "."]
+env = { ROOT = "/tmp" }
+"#,
+        )
+        .unwrap();
+
+        let config = build_stdio_config(&server, 30).unwrap();
+        let stdio = config.stdio.unwrap();
+        assert_eq!(stdio.command, "npx");
+        assert_eq!(stdio.args.len(), 3);
+        assert_eq!(stdio.env.get("ROOT"), Some(&"/tmp".to_string()));
+        assert!(stdio.cwd.is_none());
+        assert_eq!(stdio.timeout, std::time::Duration::from_secs(30));
+
+        server.timeout_seconds = Some(5);
+        let stdio = build_stdio_config(&server, 30).unwrap().stdio.unwrap();
+        assert_eq!(stdio.timeout, std::time::Duration::from_secs(5));
+
+        server.command = None;
+        assert!(build_stdio_config(&server, 30).is_none());
+    }
 }
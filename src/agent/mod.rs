@@ -65,6 +65,9 @@ pub struct Agent {
     // MCP tools loaded from external servers
     #[cfg(feature = "registry-mcp")]
     mcp_tools: Option<McpToolLoader>,
+    // Tools contributed by WASM extensions
+    #[cfg(feature = "registry-extension")]
+    extension_tools: Option<crate::registry::UnifiedRegistry>,
     // Session management (replaces checkpoint_storage_manager, current_session, and classification state)
     // Wrapped in Option to allow taking ownership during delegation calls
     session_manager: Option<crate::checkpoint::SessionManager>,
@@ -96,6 +99,43 @@ pub struct Agent {
     // When None (non-TUI mode), sends are no-ops.
     on_checkpoint: Option<tokio::sync::mpsc::UnboundedSender<Option<crate::cli::ResumeInfo>>>,
 
+    // Tool approval: consulted before each tool call when the current mode
+    // has `auto_execute = false`. None = no approver, such tool calls are denied.
+    approval_gate: Option<crate::orchestration::ApprovalGate>,
+
+    // Mid-run user input: messages a front-end queues while the workflow runs,
+    // appended to the conversation before the next LLM call. None = no input.
+    user_input: Option<crate::orchestration::UserInputQueue>,
+
+    // Context compaction: summarizes older turns once the conversation passes
+    // `execution.compaction.trigger_tokens`. None = only `max_history` applies.
+    context_compactor: Option<crate::orchestration::ContextCompactor>,
+
+    // Context from `context` extensions, inserted before LLM calls per
+    // `execution.context`. None = no injection.
+    context_injector: Option<crate::orchestration::ContextInjector>,
+
+    // Structured spans from `logging.tracing` (JSONL and/or OTLP).
+    // Disabled when the section is absent.
+    tracer: crate::observability::Tracer,
+
+    // Prometheus textfile written at the end of each run (`logging.metrics`).
+    metrics_textfile: Option<PathBuf>,
+
+    // Provider-reported token usage for this session, priced with `[llm.pricing]`.
+    usage_tracker: crate::provider::UsageTracker,
+
+    // Session budget from `[execution.budget]`. None = no token/cost/time ceilings.
+    budget_guard: Option<crate::orchestration::BudgetGuard>,
+
+    // Model used instead of the provider default after a budget limit
+    // switched the session to `[llm.utility].model`.
+    model_override: Option<String>,
+
+    // Original content of files edited through write/edit/multiedit this
+    // session, snapshotted into each checkpoint for `restore_files`.
+    workspace_tracker: crate::checkpoint::WorkspaceTracker,
+
     // Tool filtering: None = all tools allowed, Some(set) = only those tools.
     // Set once at init from config.tools.enabled_tools for O(1) lookup.
     enabled_tools_filter: Option<HashSet<String>>,
@@ -141,7 +181,8 @@ impl Agent {
         let lifecycle = crate::lifecycle::find_lifecycle_plugin_with_config(lifecycle_enabled, system_template).await
             .context("Failed to load lifecycle")?;
 
-        let provider = ProviderFactory::create(&env).await
+        let routing = config_loader.config.llm.as_ref().and_then(|l| l.routing.as_ref());
+        let provider = ProviderFactory::create_with_routing(&env, routing).await
             .context("Failed to create LLM provider")?;
 
         let timeout_seconds = config_loader.get_u64("execution.timeout_seconds").unwrap_or(120);
@@ -178,13 +219,20 @@ impl Agent {
             config_value.unwrap_or(false)
         };
 
-        let session_manager = Some(
-            crate::checkpoint::SessionManager::with_agent_name(
-                checkpointing_enabled,
-                Some(&config_loader.config.agent.name),
-            )
-            .context("Failed to initialize session manager")?,
-        );
+        let mut session_manager = crate::checkpoint::SessionManager::with_agent_name(
+            checkpointing_enabled,
+            Some(&config_loader.config.agent.name),
+        )
+        .context("Failed to initialize session manager")?;
+        if let Some(ref checkpointing) = config_loader.config.checkpointing {
+            session_manager.set_compression(crate::checkpoint::CompressionSettings::from_config(checkpointing));
+            let cipher = crate::checkpoint::CheckpointCipher::from_config(&checkpointing.security)
+                .context("Failed to load checkpoint encryption key")?;
+            session_manager.set_cipher(cipher.map(std::sync::Arc::new));
+            session_manager.set_git_integration(checkpointing.git_integration.clone());
+            session_manager.set_pruning(crate::checkpoint::CheckpointPruning::from_config(checkpointing));
+        }
+        let session_manager = Some(session_manager);
 
         #[cfg(feature = "registry-mcp")]
         let mcp_tools = {
@@ -214,6 +262,21 @@ impl Agent {
             }
         };
 
+        #[cfg(feature = "registry-extension")]
+        let extension_tools = {
+            let agent_name = std::env::var("ABK_AGENT_NAME")
+                .unwrap_or_else(|_| config_loader.config.agent.name.clone());
+            let extensions_dir = crate::get_home_dir()
+                .ok()
+                .map(|home| Path::new(&home).join(format!(".{}/extensions", agent_name)));
+            let registry = crate::registry::build_extension_registry(
+                &config_loader.config.tool_sources,
+                extensions_dir.as_deref(),
+            )
+            .await;
+            (registry.source_count() > 0).then_some(registry)
+        };
+
         let open_window_size = config_loader.config.tools.open_file_window_size;
 
         // Capture tool filter config before moving config_loader into the struct
@@ -226,7 +289,33 @@ impl Agent {
         // Capture agent name before moving config_loader into the struct
         let agent_name = config_loader.config.agent.name.clone();
 
-        Ok(Self {
+        let context_compactor = config_loader.config.execution.compaction.clone()
+            .filter(|c| c.enabled)
+            .map(|c| {
+                let utility = config_loader.config.llm.as_ref().and_then(|l| l.utility.clone());
+                crate::orchestration::ContextCompactor::new(c, utility)
+            });
+
+        let tracer = match config_loader.config.logging.tracing {
+            Some(ref tracing) => build_tracer(tracing, logger.log_file(), &agent_name),
+            None => crate::observability::Tracer::disabled(),
+        };
+
+        let metrics_textfile = config_loader.config.logging.metrics.as_ref().and_then(start_metrics);
+
+        let context_injector = match config_loader.config.execution.context.clone() {
+            Some(context) if context.enabled => load_context_injector(context, &agent_name).await,
+            _ => None,
+        };
+
+        let usage_tracker = crate::provider::UsageTracker::new(crate::provider::PriceTable::new(
+            config_loader.config.llm.as_ref().map(|l| l.pricing.clone()).unwrap_or_default(),
+        ));
+
+        let budget_guard = config_loader.config.execution.budget.clone()
+            .map(crate::orchestration::BudgetGuard::new);
+
+        let mut agent = Self {
             env,
             config: config_loader,
             chat_formatter,
@@ -245,6 +334,8 @@ impl Agent {
             execution_mode: ExecutionMode::Hybrid,
             #[cfg(feature = "registry-mcp")]
             mcp_tools,
+            #[cfg(feature = "registry-extension")]
+            extension_tools,
             session_manager,
             checkpoint_storage_manager: None,
             current_session: None,
@@ -258,13 +349,25 @@ impl Agent {
             turn_request_count: 0,
             output_sink: crate::orchestration::output::stdout_sink(),
             on_checkpoint: None,
+            approval_gate: None,
+            user_input: None,
+            context_compactor,
+            context_injector,
+            tracer,
+            metrics_textfile,
+            usage_tracker,
+            budget_guard,
+            model_override: None,
+            workspace_tracker: crate::checkpoint::WorkspaceTracker::new(),
             enabled_tools_filter,
             disabled_tools_filter,
             run_context: crate::context::RunContext {
                 agent_name: Some(agent_name),
                 ..Default::default()
             },
-        })
+        };
+        agent.apply_execution_backend();
+        Ok(agent)
     }
 
     /// Set the interaction mode.
@@ -274,6 +377,7 @@ impl Agent {
     pub fn set_mode(&mut self, mode: AgentMode) -> Result<()> {
         let old_mode = self.current_mode.to_string();
         self.current_mode = mode.clone();
+        self.apply_execution_backend();
         self.logger.log_mode_change(&old_mode, &mode.to_string())?;
         Ok(())
     }
@@ -519,6 +623,21 @@ impl Agent {
     #[cfg(not(feature = "registry-mcp"))]
     pub fn emit_mcp_server_statuses(&self) {}
 
+    /// Stop stdio MCP server processes started by this agent.
+    ///
+    /// Processes are also killed when the agent is dropped; calling this
+    /// first gives them a chance to exit cleanly.
+    #[cfg(feature = "registry-mcp")]
+    pub async fn shutdown_mcp_servers(&self) {
+        if let Some(ref mcp) = self.mcp_tools {
+            mcp.shutdown().await;
+        }
+    }
+
+    /// No-op when `registry-mcp` feature is disabled.
+    #[cfg(not(feature = "registry-mcp"))]
+    pub async fn shutdown_mcp_servers(&self) {}
+
     /// Set the output sink used for structured events.
     ///
     /// Call this after construction to override the default `StdoutSink`.
@@ -551,6 +670,83 @@ impl Agent {
         self.on_checkpoint = sender;
     }
 
+    /// Set the approver consulted before tool calls.
+    ///
+    /// The approver is only asked when the current mode's `auto_execute` is
+    /// `false` (see [`Agent::requires_tool_approval`]). Passing `None` removes
+    /// the approver, so tool calls in such modes are denied and the model is
+    /// told why.
+    pub fn set_tool_approver(&mut self, approver: Option<crate::orchestration::SharedApprover>) {
+        self.approval_gate = approver.map(crate::orchestration::ApprovalGate::new);
+    }
+
+    /// Accept messages from the user while a workflow is running.
+    ///
+    /// Returns the sender a TUI or web front-end uses to steer the agent;
+    /// queued messages are added to the conversation as user turns before the
+    /// next LLM call and reported as `OutputEvent::UserMessageInjected`.
+    /// Replaces any previously created queue.
+    pub fn enable_user_input(&mut self) -> crate::orchestration::UserInputSender {
+        let (queue, sender) = crate::orchestration::UserInputQueue::new();
+        self.user_input = Some(queue);
+        sender
+    }
+
+    /// Check whether the current mode requires approval before tool execution.
+    ///
+    /// Reads `modes.<mode>.auto_execute` from the configuration.
+    pub fn requires_tool_approval(&self) -> bool {
+        self.config
+            .config
+            .modes
+            .for_mode(&self.current_mode.to_string())
+            .map(|m| !m.auto_execute)
+            .unwrap_or(false)
+    }
+
+    /// Where shell commands run in the current mode (`modes.<mode>.execution_backend`).
+    pub fn execution_backend_kind(&self) -> crate::config::ExecutionBackendKind {
+        self.config
+            .config
+            .modes
+            .for_mode(&self.current_mode.to_string())
+            .map(|m| m.execution_backend)
+            .unwrap_or_default()
+    }
+
+    /// Point the command executor at the current mode's execution backend.
+    fn apply_execution_backend(&mut self) {
+        use crate::config::ExecutionBackendKind;
+        use crate::executor::{BubblewrapBackend, HostBackend, SandboxPolicy};
+
+        let backend: crate::executor::SharedBackend = match self.execution_backend_kind() {
+            ExecutionBackendKind::Host => std::sync::Arc::new(HostBackend),
+            ExecutionBackendKind::Sandbox => {
+                let sandbox = self.config.config.execution.sandbox.clone().unwrap_or_default();
+                let home = std::env::var_os("HOME").map(PathBuf::from);
+                let expand = |paths: &[String]| -> Vec<PathBuf> {
+                    paths
+                        .iter()
+                        .map(|p| match (p.strip_prefix("~/"), &home) {
+                            (Some(rest), Some(home)) => home.join(rest),
+                            _ => PathBuf::from(p),
+                        })
+                        .collect()
+                };
+                let policy = SandboxPolicy {
+                    writable_paths: expand(&sandbox.writable_paths),
+                    readable_paths: expand(&sandbox.readable_paths),
+                    env_passthrough: sandbox.env_passthrough.clone(),
+                    allow_network: sandbox.allow_network,
+                    cpu_seconds: Some(sandbox.cpu_seconds).filter(|s| *s > 0),
+                    memory_mb: Some(sandbox.memory_mb).filter(|m| *m > 0),
+                };
+                std::sync::Arc::new(BubblewrapBackend::new(sandbox.bwrap_path, policy))
+            }
+        };
+        self.executor.set_backend(backend);
+    }
+
     /// Get the current mode.
     pub fn current_mode(&self) -> &AgentMode {
         &self.current_mode
@@ -566,6 +762,51 @@ impl Agent {
         self.current_iteration
     }
 
+    /// Get the token usage and estimated cost accumulated this session.
+    pub fn token_usage(&self) -> &crate::provider::UsageTotals {
+        self.usage_tracker.totals()
+    }
+
+    /// Model the next LLM call goes to: the utility model after a budget
+    /// switch, otherwise the provider default.
+    pub fn active_model(&self) -> String {
+        self.model_override
+            .clone()
+            .unwrap_or_else(|| self.provider.default_model())
+    }
+
+    /// Count one LLM call against the session totals and report its usage.
+    ///
+    /// Called after every completed provider call; calls whose provider
+    /// doesn't report usage still count toward `api_calls`.
+    pub(crate) fn record_token_usage(&mut self) {
+        let model = self.active_model();
+        let usage = self.provider.take_usage();
+        self.usage_tracker.record(&model, usage);
+
+        if let Some(usage) = usage {
+            self.output_sink.emit(crate::orchestration::output::OutputEvent::TokenUsage {
+                usage,
+                totals: self.usage_tracker.totals().clone(),
+            });
+        }
+    }
+
+    /// Report backend fallbacks of a routing provider as output events.
+    pub(crate) fn emit_provider_fallbacks(&mut self) {
+        for fallback in self.provider.take_fallbacks() {
+            self.logger.info(&format!(
+                "Provider fallback: {} → {} ({})",
+                fallback.from, fallback.to, fallback.reason
+            ));
+            self.output_sink.emit(crate::orchestration::output::OutputEvent::ProviderFallback {
+                from: fallback.from,
+                to: fallback.to,
+                reason: fallback.reason,
+            });
+        }
+    }
+
     /// Get the tool registry.
     pub fn get_tool_registry(&self) -> &ToolRegistry {
         &self.tool_registry
@@ -664,12 +905,29 @@ impl Agent {
                 .unwrap_or(false);
             if checkpointing_enabled {
                 let agent_name = ctx.resolve_agent_name("trustee");
+                let compression = self.session_manager
+                    .as_ref()
+                    .map(|sm| sm.compression())
+                    .unwrap_or_default();
+                let cipher = self.session_manager.as_ref().and_then(|sm| sm.cipher());
+                let git_integration = self.session_manager
+                    .as_ref()
+                    .map(|sm| sm.git_integration())
+                    .unwrap_or_default();
+                let pruning = self.session_manager
+                    .as_ref()
+                    .map(|sm| sm.pruning())
+                    .unwrap_or_default();
                 match crate::checkpoint::SessionManager::with_home_dir(
                     true,
                     home_dir.clone(),
                     &agent_name,
                 ) {
-                    Ok(sm) => {
+                    Ok(mut sm) => {
+                        sm.set_compression(compression);
+                        sm.set_cipher(cipher);
+                        sm.set_git_integration(git_integration);
+                        sm.set_pruning(pruning);
                         self.session_manager = Some(sm);
                     }
                     Err(e) => {
@@ -702,3 +960,129 @@ impl Drop for Agent {
         }
     }
 }
+
+/// Build the span tracer configured in `[logging.tracing]`.
+///
+/// Exporters that can't be created are skipped with a warning.
+fn build_tracer(
+    config: &crate::config::TracingConfig,
+    log_file: &Path,
+    #[cfg_attr(not(feature = "observability-otlp"), allow(unused_variables))] agent_name: &str,
+) -> crate::observability::Tracer {
+    use crate::observability::{JsonlExporter, SpanExporter, Tracer};
+
+    let mut exporters: Vec<Box<dyn SpanExporter>> = Vec::new();
+    if config.jsonl {
+        let path = config
+            .jsonl_path
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| log_file.with_extension("trace.jsonl"));
+        match JsonlExporter::new(&path) {
+            Ok(exporter) => exporters.push(Box::new(exporter)),
+            Err(e) => crate::observability::tee_eprintln(&format!("Warning: JSONL tracing disabled: {:#}", e)),
+        }
+    }
+
+    if let Some(ref endpoint) = config.otlp_endpoint {
+        #[cfg(feature = "observability-otlp")]
+        {
+            let service_name = config.service_name.clone().unwrap_or_else(|| agent_name.to_string());
+            let mut exporter = crate::observability::OtlpHttpExporter::new(endpoint, service_name);
+            for (name, value) in &config.otlp_headers {
+                let value = match value.strip_prefix("${").and_then(|v| v.strip_suffix('}')) {
+                    Some(var) => std::env::var(var).unwrap_or_else(|_| value.clone()),
+                    None => value.clone(),
+                };
+                exporter = exporter.with_header(name, value);
+            }
+            exporters.push(Box::new(exporter));
+        }
+        #[cfg(not(feature = "observability-otlp"))]
+        crate::observability::tee_eprintln(&format!(
+            "Warning: OTLP export to {} requires the observability-otlp feature",
+            endpoint
+        ));
+    }
+
+    Tracer::new(exporters)
+}
+
+/// Start the metrics endpoint configured in `[logging.metrics]` and return
+/// the textfile path, if any.
+///
+/// The endpoint is started once per process; later agents share it.
+fn start_metrics(config: &crate::config::MetricsConfig) -> Option<PathBuf> {
+    static ENDPOINT: std::sync::OnceLock<()> = std::sync::OnceLock::new();
+
+    if let Some(ref listen) = config.listen {
+        ENDPOINT.get_or_init(|| {
+            if let Err(e) = crate::observability::metrics::serve(listen) {
+                crate::observability::tee_eprintln(&format!("Warning: Metrics endpoint disabled: {:#}", e));
+            }
+        });
+    }
+
+    config.textfile.as_ref().map(|p| match (p.strip_prefix("~/"), crate::get_home_dir()) {
+        (Some(rest), Ok(home)) => Path::new(&home).join(rest),
+        _ => PathBuf::from(p),
+    })
+}
+
+/// Load the context providers listed in `[execution.context]`.
+///
+/// Without `extensions`, every installed extension with the `context`
+/// capability in `~/.{agent_name}/extensions` is used. Extensions that fail
+/// to load are skipped with a warning.
+#[cfg(feature = "extension")]
+async fn load_context_injector(
+    config: crate::config::ContextInjectionConfig,
+    agent_name: &str,
+) -> Option<crate::orchestration::ContextInjector> {
+    use crate::orchestration::{ExtensionContextProvider, SharedContextProvider};
+
+    let home = crate::get_home_dir().ok();
+    let dirs: Vec<PathBuf> = if config.extensions.is_empty() {
+        let agent_name = std::env::var("ABK_AGENT_NAME").unwrap_or_else(|_| agent_name.to_string());
+        let home = home.as_ref()?;
+        ExtensionContextProvider::discover(&Path::new(home).join(format!(".{}/extensions", agent_name)))
+    } else {
+        config
+            .extensions
+            .iter()
+            .map(|p| match (p.strip_prefix("~/"), &home) {
+                (Some(rest), Some(home)) => Path::new(home).join(rest),
+                _ => PathBuf::from(p),
+            })
+            .collect()
+    };
+
+    let mut providers: Vec<SharedContextProvider> = Vec::new();
+    for dir in dirs {
+        match ExtensionContextProvider::load(&dir).await {
+            Ok(provider) => providers.push(std::sync::Arc::new(provider)),
+            Err(e) => crate::observability::tee_eprintln(&format!(
+                "Warning: Failed to load context extension {}: {}",
+                dir.display(),
+                e
+            )),
+        }
+    }
+    if providers.is_empty() {
+        return None;
+    }
+    Some(crate::orchestration::ContextInjector::new(config, providers))
+}
+
+/// Context providers are WASM extensions; without the `extension` feature
+/// `[execution.context]` has no effect.
+#[cfg(not(feature = "extension"))]
+async fn load_context_injector(
+    _config: crate::config::ContextInjectionConfig,
+    _agent_name: &str,
+) -> Option<crate::orchestration::ContextInjector> {
+    crate::observability::tee_eprintln(
+        "Warning: [execution.context] requires the extension feature; no context will be injected",
+    );
+    None
+}
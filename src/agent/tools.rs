@@ -3,9 +3,22 @@
 use crate::agent::types::ToolExecutionResult;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use umf::ToolCall;
 use anyhow::Result;
 
+/// Default for `tools.max_parallel`.
+const DEFAULT_MAX_PARALLEL: usize = 4;
+
+/// Native tools treated as parallel-safe when `tools.parallel_safe_tools` is unset.
+const DEFAULT_PARALLEL_SAFE_TOOLS: &[&str] = &["read", "glob", "grep", "list"];
+
+/// Local tools that run shell commands; sandboxed modes route them through the execution backend.
+const SHELL_TOOLS: &[&str] = &["bash", "run_command"];
+
+/// Local tools whose targets are recorded in the workspace tracker.
+const EDIT_TOOLS: &[&str] = &["write", "edit", "multiedit"];
+
 struct LoggerCallback<'a> {
     logger: &'a crate::observability::Logger,
 }
@@ -38,14 +51,161 @@ impl super::Agent {
     }
 
     pub async fn execute_tool_calls_structured(&mut self, tool_calls: Vec<ToolCall>) -> Result<Vec<ToolExecutionResult>> {
-        let mut results = Vec::new();
+        self.execute_tool_calls_reporting(tool_calls, &|_, _| {}).await
+    }
 
-        for tc in &tool_calls {
+    /// Execute tool calls, reporting each result through `on_complete` as soon as it finishes.
+    ///
+    /// `on_complete` also receives the instant the call started running, so
+    /// latency is measured per call even when calls overlap.
+    ///
+    /// Parallel-safe calls are collected until the next unsafe call and
+    /// dispatched concurrently, at most `tools.max_parallel` at a time: MCP
+    /// tools annotated `readOnlyHint`/`idempotentHint`, and native tools named
+    /// in `tools.parallel_safe_tools` (default `read`, `glob`, `grep`, `list`).
+    /// Batched native calls run on a blocking thread with their own cats
+    /// registry, sharing only the working directory. Shell, file-editing and
+    /// extension tools always run one at a time through the agent's registry.
+    /// The returned results are always in call order.
+    pub async fn execute_tool_calls_reporting(
+        &mut self,
+        tool_calls: Vec<ToolCall>,
+        on_complete: &(dyn Fn(&ToolExecutionResult, Instant) + Send + Sync),
+    ) -> Result<Vec<ToolExecutionResult>> {
+        let max_parallel = self.max_parallel_tools();
+        let mut results: Vec<Option<ToolExecutionResult>> = vec![None; tool_calls.len()];
+        let mut batch: Vec<usize> = Vec::new();
+
+        for (idx, tc) in tool_calls.iter().enumerate() {
+            if max_parallel > 1 && self.is_concurrent_call(tc) {
+                batch.push(idx);
+                continue;
+            }
+            if !self.is_parallel_safe(tc) {
+                self.flush_tool_batch(&tool_calls, &mut batch, max_parallel, &mut results, on_complete)
+                    .await;
+            }
+            let started = Instant::now();
             let result = self.execute_single_tool(tc).await?;
-            results.push(result);
+            on_complete(&result, started);
+            results[idx] = Some(result);
+        }
+        self.flush_tool_batch(&tool_calls, &mut batch, max_parallel, &mut results, on_complete)
+            .await;
+
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    /// Maximum concurrent tool calls per turn (`tools.max_parallel`, default 4).
+    fn max_parallel_tools(&self) -> usize {
+        self.config.config.tools.max_parallel.unwrap_or(DEFAULT_MAX_PARALLEL).max(1)
+    }
+
+    /// Check whether a tool call may overlap with other parallel-safe calls.
+    fn is_parallel_safe(&self, tc: &ToolCall) -> bool {
+        if !self.is_tool_allowed(&tc.function.name) {
+            return false;
         }
+        #[cfg(feature = "registry-mcp")]
+        if let Some(ref mcp_tools) = self.mcp_tools {
+            if mcp_tools.is_mcp_tool(&tc.function.name) {
+                return mcp_tools.is_parallel_safe(&tc.function.name);
+            }
+        }
+        match self.config.config.tools.parallel_safe_tools {
+            Some(ref names) => names.iter().any(|n| n == &tc.function.name),
+            None => DEFAULT_PARALLEL_SAFE_TOOLS.contains(&tc.function.name.as_str()),
+        }
+    }
+
+    /// Check whether a parallel-safe call can join a concurrent batch.
+    fn is_concurrent_call(&self, tc: &ToolCall) -> bool {
+        if !self.is_parallel_safe(tc) {
+            return false;
+        }
+        let name = tc.function.name.as_str();
+        #[cfg(feature = "registry-mcp")]
+        if self.mcp_tools.as_ref().is_some_and(|m| m.is_mcp_tool(name)) {
+            return true;
+        }
+        #[cfg(feature = "registry-extension")]
+        if self.extension_tools.as_ref().is_some_and(|e| e.has_tool(name)) {
+            return false;
+        }
+        !SHELL_TOOLS.contains(&name) && !EDIT_TOOLS.contains(&name) && self.tool_registry.get_tool(name).is_some()
+    }
+
+    /// Dispatch the pending batch concurrently, storing results by call index.
+    async fn flush_tool_batch(
+        &self,
+        tool_calls: &[ToolCall],
+        batch: &mut Vec<usize>,
+        max_parallel: usize,
+        results: &mut [Option<ToolExecutionResult>],
+        on_complete: &(dyn Fn(&ToolExecutionResult, Instant) + Send + Sync),
+    ) {
+        if batch.is_empty() {
+            return;
+        }
+        #[cfg(feature = "registry-mcp")]
+        let mcp_tools = self.mcp_tools.as_ref();
+        let working_dir = self.get_working_directory();
+        let result_config = self.result_handler_config();
+        let open_window_size = self.config.config.tools.open_file_window_size;
+
+        let run = |idx: usize| {
+            let tc = &tool_calls[idx];
+            #[cfg(feature = "registry-mcp")]
+            let mcp = mcp_tools
+                .filter(|m| m.is_mcp_tool(&tc.function.name))
+                .map(|m| (m, self.mcp_progress_reporter(&tc.function.name)));
+            let working_dir = working_dir.clone();
+            let result_config = result_config.clone();
+            async move {
+                let started = Instant::now();
+                #[cfg(feature = "registry-mcp")]
+                if let Some((mcp_tools, report)) = mcp {
+                    let r = mcp_tools
+                        .execute_tool_with_progress(&tc.function.name, &tc.function.arguments, &report)
+                        .await;
+                    return (started, BatchOutcome::Mcp(r));
+                }
+                let call = tc.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    execute_isolated_cats_tool(working_dir, open_window_size, &call, &result_config)
+                })
+                .await
+                .unwrap_or_else(|e| ToolExecutionResult {
+                    tool_call_id: tc.id.clone(),
+                    tool_name: tc.function.name.clone(),
+                    content: format!("Tool '{}' failed: {}", tc.function.name, e),
+                    success: false,
+                    description: None,
+                });
+                (started, BatchOutcome::Native(result))
+            }
+        };
+
+        let finish = |idx: usize, (started, outcome): (Instant, BatchOutcome)| {
+            let tc = &tool_calls[idx];
+            let result = match outcome {
+                #[cfg(feature = "registry-mcp")]
+                BatchOutcome::Mcp(r) => self.mcp_execution_result(tc, r),
+                BatchOutcome::Native(result) => {
+                    let _ = self.logger.log_tool_execution(
+                        &tc.function.name,
+                        &tc.function.arguments,
+                        &result.content,
+                        result.success,
+                    );
+                    result
+                }
+            };
+            on_complete(&result, started);
+            results[idx] = Some(result);
+        };
 
-        Ok(results)
+        run_concurrently(batch.drain(..), max_parallel, run, finish).await;
     }
 
     /// Execute a single tool call, routing to MCP or CATS as appropriate.
@@ -74,50 +234,205 @@ impl super::Agent {
         if let Some(ref mcp_tools) = self.mcp_tools {
             if mcp_tools.is_mcp_tool(&tc.function.name) {
                 // Execute via MCP
+                let report = self.mcp_progress_reporter(&tc.function.name);
                 let mcp_result = mcp_tools
-                    .execute_tool(&tc.function.name, &tc.function.arguments)
+                    .execute_tool_with_progress(&tc.function.name, &tc.function.arguments, &report)
                     .await;
+                return Ok(self.mcp_execution_result(tc, mcp_result));
+            }
+        }
 
-                let (content, success) = match mcp_result {
-                    Ok(r) => (r.content, r.success),
-                    Err(e) => (format!("MCP tool error: {}", e), false),
-                };
-
-                // Log the execution
+        // Check if a WASM extension provides this tool
+        #[cfg(feature = "registry-extension")]
+        if let Some(ref extension_tools) = self.extension_tools {
+            if extension_tools.has_tool(&tc.function.name) {
+                let result = execute_extension_tool(extension_tools, tc).await;
                 let _ = self.logger.log_tool_execution(
                     &tc.function.name,
                     &tc.function.arguments,
-                    &content,
-                    success,
+                    &result.content,
+                    result.success,
                 );
-
-                // Extract description from MCP tool arguments
-                let description: Option<String> = serde_json::from_str::<serde_json::Value>(&tc.function.arguments)
-                    .ok()
-                    .and_then(|v| v.get("description").and_then(|d| d.as_str()).map(String::from));
-
-                return Ok(ToolExecutionResult {
-                    tool_call_id: tc.id.clone(),
-                    tool_name: tc.function.name.clone(),
-                    content,
-                    success,
-                    description,
-                });
+                return Ok(result);
             }
         }
 
+        // Shell commands can change any file: record the workspace before
+        // and track what changed. They go through the sandbox when the mode
+        // requires it.
+        if SHELL_TOOLS.contains(&tc.function.name.as_str()) {
+            let watch = self.start_shell_watch().await;
+            let result = if self.execution_backend_kind() == crate::config::ExecutionBackendKind::Sandbox {
+                Ok(self.execute_sandboxed_bash(tc).await)
+            } else {
+                self.execute_cats_tool(tc).await
+            };
+            self.finish_shell_watch(watch).await;
+            return result;
+        }
+
+        // Remember the original content of files the tool is about to change
+        self.track_file_edit(tc);
+
         // Execute via CATS (local tool)
         self.execute_cats_tool(tc).await
     }
 
+    /// Record the target of a `write`/`edit`/`multiedit` call in the workspace tracker.
+    fn track_file_edit(&mut self, tc: &ToolCall) {
+        if !EDIT_TOOLS.contains(&tc.function.name.as_str()) {
+            return;
+        }
+        let args: serde_json::Value = serde_json::from_str(&tc.function.arguments).unwrap_or_default();
+        let Some(file_path) = args
+            .get("file_path")
+            .or_else(|| args.get("filePath"))
+            .and_then(|p| p.as_str())
+        else {
+            return;
+        };
+        let path = self.executor.working_dir().join(file_path);
+        self.workspace_tracker.record(&path);
+    }
+
+    /// Record the workspace before a shell command, if a checkpoint session
+    /// with shadow snapshots is active.
+    async fn start_shell_watch(&self) -> Option<crate::checkpoint::ShellCommandWatch> {
+        let repo = self.session_manager.as_ref()?.workspace_repo()?;
+        match tokio::task::spawn_blocking(move || crate::checkpoint::ShellCommandWatch::start(repo)).await {
+            Ok(Ok(watch)) => Some(watch),
+            Ok(Err(e)) => {
+                self.logger.info(&format!("Shell command changes won't be tracked: {}", e));
+                None
+            }
+            Err(_) => None,
+        }
+    }
+
+    /// Add the files a shell command changed to the workspace tracker.
+    async fn finish_shell_watch(&mut self, watch: Option<crate::checkpoint::ShellCommandWatch>) {
+        let Some(watch) = watch else {
+            return;
+        };
+        match tokio::task::spawn_blocking(move || watch.changed_files()).await {
+            Ok(Ok(files)) => {
+                for (path, baseline) in files {
+                    self.workspace_tracker.record_baseline(&path, baseline);
+                }
+            }
+            Ok(Err(e)) => self.logger.info(&format!("Could not track shell command changes: {}", e)),
+            Err(_) => {}
+        }
+    }
+
+    /// Run a `bash` or `run_command` tool call through the command executor's
+    /// sandbox backend.
+    ///
+    /// Accepts the same arguments as the cats `bash` tool (`command`,
+    /// `timeout` in ms, `workdir`, `description`). Sandbox and validation
+    /// errors, and commands exiting non-zero, are returned to the model as
+    /// failed results.
+    async fn execute_sandboxed_bash(&mut self, tc: &ToolCall) -> ToolExecutionResult {
+        let args: serde_json::Value = serde_json::from_str(&tc.function.arguments).unwrap_or_default();
+        let description = args.get("description").and_then(|d| d.as_str()).map(String::from);
+        let command = args.get("command").and_then(|c| c.as_str()).unwrap_or_default();
+        let timeout_secs = args
+            .get("timeout")
+            .and_then(|t| t.as_u64())
+            .map(|ms| ms.div_ceil(1000).max(1));
+        let workdir = args
+            .get("workdir")
+            .and_then(|w| w.as_str())
+            .filter(|w| !w.is_empty())
+            .map(|w| self.executor.working_dir().join(w))
+            .unwrap_or_else(|| self.get_working_directory());
+
+        let (valid, reason) = self.executor.validate_command(command);
+        let (content, success) = if !valid {
+            (reason, false)
+        } else {
+            match self.executor.execute_command_in(command, &workdir, timeout_secs).await {
+                Ok(result) => {
+                    let mut output = result.stdout;
+                    if !result.stderr.is_empty() {
+                        if !output.is_empty() && !output.ends_with('\n') {
+                            output.push('\n');
+                        }
+                        output.push_str(&result.stderr);
+                    }
+                    if result.return_code != 0 {
+                        output.push_str(&format!("\n\nExit code: {}", result.return_code));
+                    }
+                    (output, result.return_code == 0)
+                }
+                Err(e) => (e.to_string(), false),
+            }
+        };
+
+        let _ = self.logger.log_tool_execution(&tc.function.name, &tc.function.arguments, &content, success);
+        ToolExecutionResult {
+            tool_call_id: tc.id.clone(),
+            tool_name: tc.function.name.clone(),
+            content,
+            success,
+            description,
+        }
+    }
+
+    /// Forward MCP progress notifications for `tool_name` as `ToolProgress` events.
+    #[cfg(feature = "registry-mcp")]
+    fn mcp_progress_reporter(&self, tool_name: &str) -> impl Fn(&crate::registry::McpProgress) + Send + Sync {
+        let sink = self.output_sink.clone();
+        let tool_name = tool_name.to_string();
+        move |p| {
+            sink.emit(crate::orchestration::output::OutputEvent::ToolProgress {
+                tool_name: tool_name.clone(),
+                progress: p.progress,
+                total: p.total,
+                message: p.message.clone(),
+            })
+        }
+    }
+
+    /// Log an MCP tool execution and convert it to a `ToolExecutionResult`.
+    #[cfg(feature = "registry-mcp")]
+    fn mcp_execution_result(
+        &self,
+        tc: &ToolCall,
+        mcp_result: Result<super::mcp::McpToolExecutionResult>,
+    ) -> ToolExecutionResult {
+        let (content, success) = match mcp_result {
+            Ok(r) => (r.content, r.success),
+            Err(e) => (format!("MCP tool error: {}", e), false),
+        };
+
+        // Log the execution
+        let _ = self.logger.log_tool_execution(
+            &tc.function.name,
+            &tc.function.arguments,
+            &content,
+            success,
+        );
+
+        // Extract description from MCP tool arguments
+        let description: Option<String> = serde_json::from_str::<serde_json::Value>(&tc.function.arguments)
+            .ok()
+            .and_then(|v| v.get("description").and_then(|d| d.as_str()).map(String::from));
+
+        ToolExecutionResult {
+            tool_call_id: tc.id.clone(),
+            tool_name: tc.function.name.clone(),
+            content,
+            success,
+            description,
+        }
+    }
+
     /// Execute a tool via CATS (local tool registry).
     async fn execute_cats_tool(&mut self, tc: &ToolCall) -> Result<ToolExecutionResult> {
         let mut cb = LoggerCallback { logger: &self.logger };
         let req = cats::ToolCallRequest::new(&tc.id, &tc.function.name, &tc.function.arguments);
-        let cfg = cats::ResultHandlerConfig {
-            max_size_bytes: self.config.get_u64("tools.max_tool_result_size_bytes").unwrap_or(256000) as usize,
-            truncate_enabled: self.config.get_bool("tools.truncate_large_results").unwrap_or(true),
-        };
+        let cfg = self.result_handler_config();
 
         let cats_results = cats::execute_tool_calls_structured(
             &mut self.tool_registry,
@@ -155,6 +470,14 @@ impl super::Agent {
         })
     }
 
+    /// Size limits applied to cats tool results.
+    fn result_handler_config(&self) -> cats::ResultHandlerConfig {
+        cats::ResultHandlerConfig {
+            max_size_bytes: self.config.get_u64("tools.max_tool_result_size_bytes").unwrap_or(256000) as usize,
+            truncate_enabled: self.config.get_bool("tools.truncate_large_results").unwrap_or(true),
+        }
+    }
+
     pub fn generate_assistant_content_for_tools(&self, tool_calls: &[ToolCall]) -> String {
         let infos: Vec<_> = tool_calls.iter().map(|tc| 
             cats::ToolCallInfo::new(&tc.function.name, &tc.function.arguments)
@@ -184,3 +507,252 @@ impl super::Agent {
             .unwrap_or_else(|_| Arc::new(AtomicBool::new(false)))
     }
 }
+
+/// Result of one call in a concurrent batch, before logging.
+enum BatchOutcome {
+    #[cfg(feature = "registry-mcp")]
+    Mcp(Result<super::mcp::McpToolExecutionResult>),
+    Native(ToolExecutionResult),
+}
+
+/// Run the calls at `indices` with at most `max_parallel` in flight.
+///
+/// `finish` receives each outcome with its call index as soon as it
+/// completes, so completion order may differ from call order.
+async fn run_concurrently<I, F, Fut, T>(
+    indices: I,
+    max_parallel: usize,
+    run: F,
+    mut finish: impl FnMut(usize, T),
+) where
+    I: IntoIterator<Item = usize>,
+    F: Fn(usize) -> Fut,
+    Fut: std::future::Future<Output = T>,
+{
+    use futures_util::stream::{self, StreamExt};
+
+    let mut in_flight = stream::iter(indices)
+        .map(|idx| {
+            let fut = run(idx);
+            async move { (idx, fut.await) }
+        })
+        .buffer_unordered(max_parallel.max(1));
+    while let Some((idx, outcome)) = in_flight.next().await {
+        finish(idx, outcome);
+    }
+}
+
+/// Run a native tool call on a registry of its own, rooted at `working_dir`
+/// and built with the agent's `open_window_size`.
+///
+/// Used for parallel-safe tools, which only read the working directory from
+/// the cats tool state.
+fn execute_isolated_cats_tool(
+    working_dir: std::path::PathBuf,
+    open_window_size: Option<usize>,
+    tc: &ToolCall,
+    result_config: &cats::ResultHandlerConfig,
+) -> ToolExecutionResult {
+    let mut registry = cats::create_tool_registry_with_open_window_size(open_window_size);
+    registry.set_working_directory(working_dir);
+    let req = cats::ToolCallRequest::new(&tc.id, &tc.function.name, &tc.function.arguments);
+    let description = serde_json::from_str::<serde_json::Value>(&tc.function.arguments)
+        .ok()
+        .and_then(|v| v.get("description").and_then(|d| d.as_str()).map(String::from));
+
+    let (content, success) =
+        match cats::execute_tool_calls_structured(&mut registry, vec![req], result_config, &mut cats::NoOpCallback) {
+            Ok(results) => match results.into_iter().next() {
+                Some(r) => (r.content, r.success),
+                None => ("No result from CATS tool execution".to_string(), false),
+            },
+            Err(e) => (e.to_string(), false),
+        };
+
+    ToolExecutionResult {
+        tool_call_id: tc.id.clone(),
+        tool_name: tc.function.name.clone(),
+        content,
+        success,
+        description,
+    }
+}
+
+/// Run a tool call on the extension registry and convert the outcome.
+///
+/// Invalid arguments and extension errors are returned to the model as
+/// failed results.
+#[cfg(feature = "registry-extension")]
+async fn execute_extension_tool(
+    registry: &crate::registry::UnifiedRegistry,
+    tc: &ToolCall,
+) -> ToolExecutionResult {
+    let (content, success, description) =
+        match serde_json::from_str::<serde_json::Value>(&tc.function.arguments) {
+            Ok(args) => {
+                let description = args.get("description").and_then(|d| d.as_str()).map(String::from);
+                match registry.execute(&tc.function.name, args).await {
+                    Ok(r) => (r.content, r.success, description),
+                    Err(e) => (format!("Extension tool error: {}", e), false, description),
+                }
+            }
+            Err(e) => (format!("Invalid arguments for tool '{}': {}", tc.function.name, e), false, None),
+        };
+
+    ToolExecutionResult {
+        tool_call_id: tc.id.clone(),
+        tool_name: tc.function.name.clone(),
+        content,
+        success,
+        description,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "registry-extension")]
+    use crate::registry::{ToolDescriptor, ToolResult, ToolSourceProvider, UnifiedRegistry};
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    fn call(id: &str, name: &str, arguments: &str) -> ToolCall {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "type": "function",
+            "function": {"name": name, "arguments": arguments}
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_batched_calls_overlap_up_to_max_parallel() {
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let mut completed = Vec::new();
+        let mut results: Vec<Option<usize>> = vec![None; 6];
+
+        run_concurrently(
+            0..6,
+            3,
+            |idx| {
+                let (in_flight, peak) = (&in_flight, &peak);
+                async move {
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    // Earlier calls take longer, so they finish out of order
+                    tokio::time::sleep(Duration::from_millis(60 - 10 * idx as u64)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    idx * 10
+                }
+            },
+            |idx, value| {
+                completed.push(idx);
+                results[idx] = Some(value);
+            },
+        )
+        .await;
+
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+        assert_ne!(completed, (0..6).collect::<Vec<_>>());
+        let results: Vec<usize> = results.into_iter().flatten().collect();
+        assert_eq!(results, vec![0, 10, 20, 30, 40, 50]);
+    }
+
+    #[tokio::test]
+    async fn test_isolated_native_tools_run_concurrently() {
+        let dir = tempfile::TempDir::new().unwrap();
+        for i in 0..4 {
+            std::fs::write(dir.path().join(format!("file{}.txt", i)), format!("contents {}\n", i)).unwrap();
+        }
+        let calls: Vec<ToolCall> = (0..4)
+            .map(|i| call(&format!("call_{}", i), "read", &format!(r#"{{"filePath":"file{}.txt"}}"#, i)))
+            .collect();
+        let config = cats::ResultHandlerConfig {
+            max_size_bytes: 256000,
+            truncate_enabled: true,
+        };
+
+        let mut results: Vec<Option<ToolExecutionResult>> = vec![None; calls.len()];
+        run_concurrently(
+            0..calls.len(),
+            4,
+            |idx| {
+                let (working_dir, tc, config) = (dir.path().to_path_buf(), calls[idx].clone(), config.clone());
+                async move {
+                    tokio::task::spawn_blocking(move || execute_isolated_cats_tool(working_dir, Some(1000), &tc, &config))
+                        .await
+                        .unwrap()
+                }
+            },
+            |idx, result| results[idx] = Some(result),
+        )
+        .await;
+
+        for (i, result) in results.into_iter().enumerate() {
+            let result = result.unwrap();
+            assert!(result.success, "{}", result.content);
+            assert_eq!(result.tool_call_id, format!("call_{}", i));
+            assert!(result.content.contains(&format!("contents {}", i)));
+        }
+    }
+
+    /// Stands in for a loaded `ExtensionToolSource`
+    #[cfg(feature = "registry-extension")]
+    struct EchoExtension;
+
+    #[cfg(feature = "registry-extension")]
+    #[async_trait::async_trait]
+    impl ToolSourceProvider for EchoExtension {
+        fn name(&self) -> &str {
+            "ext-echo"
+        }
+
+        fn tool_descriptors(&self) -> Vec<ToolDescriptor> {
+            vec![ToolDescriptor::new(
+                "echo",
+                "Echo the text back",
+                serde_json::json!({"type": "object", "properties": {"text": {"type": "string"}}}),
+                "ext-echo",
+            )]
+        }
+
+        async fn execute(&self, _tool_name: &str, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+            match args.get("text").and_then(|t| t.as_str()) {
+                Some(text) => Ok(ToolResult::success(text)),
+                None => anyhow::bail!("missing text"),
+            }
+        }
+
+        fn has_tool(&self, name: &str) -> bool {
+            name == "echo"
+        }
+    }
+
+    #[cfg(feature = "registry-extension")]
+    fn tool_call(arguments: &str) -> ToolCall {
+        call("call_1", "echo", arguments)
+    }
+
+    #[cfg(feature = "registry-extension")]
+    #[tokio::test]
+    async fn test_execute_extension_tool() {
+        let mut registry = UnifiedRegistry::new();
+        registry.add_source(Arc::new(EchoExtension));
+        assert_eq!(registry.to_openai_schemas()[0]["function"]["name"], "echo");
+
+        let result = execute_extension_tool(&registry, &tool_call(r#"{"text":"hi","description":"Say hi"}"#)).await;
+        assert!(result.success);
+        assert_eq!(result.content, "hi");
+        assert_eq!(result.tool_call_id, "call_1");
+        assert_eq!(result.description.as_deref(), Some("Say hi"));
+
+        let result = execute_extension_tool(&registry, &tool_call("{}")).await;
+        assert!(!result.success);
+        assert!(result.content.contains("missing text"));
+
+        let result = execute_extension_tool(&registry, &tool_call("not json")).await;
+        assert!(!result.success);
+        assert!(result.content.starts_with("Invalid arguments"));
+    }
+}
@@ -6,7 +6,8 @@
 //! The trait allows any agent to use the session management infrastructure by
 //! implementing a standard interface for messages, configuration, state, and logging.
 
-use crate::checkpoint::models::{ChatMessage, SystemInfo, WorkflowStep};
+use crate::checkpoint::file_snapshots::WorkspaceTracker;
+use crate::checkpoint::models::{ChatMessage, ConversationStats, SystemInfo, WorkflowStep};
 use anyhow::Result;
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
@@ -112,6 +113,29 @@ pub trait AgentContext {
         name: Option<String>,
     );
 
+    /// Add an assistant message restored from a checkpoint, with the signed
+    /// thinking blocks its provider expects back.
+    ///
+    /// The default drops `thinking_blocks` and calls
+    /// [`add_assistant_message_with_reasoning`](Self::add_assistant_message_with_reasoning).
+    ///
+    /// # Arguments
+    /// * `content` - The message content
+    /// * `reasoning` - Optional readable reasoning/thinking content
+    /// * `thinking_blocks` - Optional signed thinking blocks (`ChatMessage::thinking_blocks`)
+    /// * `tool_calls` - Optional vector of tool calls made by the assistant
+    /// * `name` - Optional assistant identifier
+    fn add_assistant_message_with_thinking(
+        &mut self,
+        content: String,
+        reasoning: Option<String>,
+        _thinking_blocks: Option<Vec<serde_json::Value>>,
+        tool_calls: Option<Vec<umf::ToolCall>>,
+        name: Option<String>,
+    ) {
+        self.add_assistant_message_with_reasoning(content, reasoning, tool_calls, name);
+    }
+
     /// Add a tool result message to the conversation.
     ///
     /// # Arguments
@@ -226,6 +250,35 @@ pub trait AgentContext {
     /// Get the model name (e.g., "gpt-4", "claude-3-5-sonnet").
     fn get_model_name(&self) -> String;
 
+    /// Fill provider-reported usage into a checkpoint's conversation stats.
+    ///
+    /// Agents that track usage set `api_calls`, `token_usage` and
+    /// `estimated_cost` to their session totals.
+    ///
+    /// Default: leaves the stats untouched.
+    fn fill_usage_stats(&self, _stats: &mut ConversationStats) {}
+
+    /// Continue usage totals from a resumed checkpoint's stats.
+    ///
+    /// Default: no-op.
+    fn restore_usage_stats(&mut self, _stats: &ConversationStats) {}
+
+    /// Files the agent modified this session, with their original content.
+    ///
+    /// When present, each checkpoint snapshots these files so they can be
+    /// rolled back with `SessionManager::restore_files`.
+    ///
+    /// Default: `None` (no file snapshots).
+    fn workspace_tracker(&self) -> Option<&WorkspaceTracker> {
+        None
+    }
+
+    /// Replace the workspace tracker, e.g. with one rebuilt from the
+    /// checkpoint a session resumes from.
+    ///
+    /// Default: no-op.
+    fn restore_workspace_tracker(&mut self, _tracker: WorkspaceTracker) {}
+
     // ========================================================================
     // Logging
     // ========================================================================
@@ -330,6 +383,7 @@ mod tests {
                 role: "system".to_string(),
                 content,
                 reasoning: None,
+                thinking_blocks: None,
                 timestamp: chrono::Utc::now(),
                 token_count: None,
                 tool_calls: None,
@@ -343,6 +397,7 @@ mod tests {
                 role: "user".to_string(),
                 content,
                 reasoning: None,
+                thinking_blocks: None,
                 timestamp: chrono::Utc::now(),
                 token_count: None,
                 tool_calls: None,
@@ -356,6 +411,7 @@ mod tests {
                 role: "assistant".to_string(),
                 content,
                 reasoning: None,
+                thinking_blocks: None,
                 timestamp: chrono::Utc::now(),
                 token_count: None,
                 tool_calls: None,
@@ -374,6 +430,7 @@ mod tests {
                 role: "assistant".to_string(),
                 content,
                 reasoning: None,
+                thinking_blocks: None,
                 timestamp: chrono::Utc::now(),
                 token_count: None,
                 tool_calls: Some(tool_calls),
@@ -393,6 +450,7 @@ mod tests {
                 role: "assistant".to_string(),
                 content,
                 reasoning,
+                thinking_blocks: None,
                 timestamp: chrono::Utc::now(),
                 token_count: None,
                 tool_calls,
@@ -406,6 +464,7 @@ mod tests {
                 role: "tool".to_string(),
                 content: format!("[{}] {}", name, content),
                 reasoning: None,
+                thinking_blocks: None,
                 timestamp: chrono::Utc::now(),
                 token_count: None,
                 tool_calls: None,
@@ -0,0 +1,1132 @@
+//! Portable project archives
+//!
+//! A project archive is a single tar file holding everything stored for one
+//! project: `project_metadata.json`, every session directory (session
+//! metadata, checkpoint index, agent/conversation files and the v2
+//! `events.jsonl` log) and the `blobs/` store that workspace file snapshots
+//! point into. The first entry is `manifest.json`, which records the archive
+//! format version and the size and SHA-256 digest of every other entry, so
+//! truncated or modified archives are rejected on import.
+//!
+//! Importing remaps the source project ID and path to the target project,
+//! carries renamed session IDs over to the sessions forked from them, and
+//! reports sessions whose IDs already exist according to a
+//! [`SessionCollisionPolicy`]. Entries are streamed to and from the tar
+//! file, never held in memory as a whole, and encrypted data is sealed again
+//! for the session it lands in.
+
+use super::compression::{self, CompressionSettings};
+use super::encryption::{self, CheckpointCipher};
+use super::storage::PLAIN_SESSION_FILES;
+use super::{CheckpointError, CheckpointResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, BufRead, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Format identifier stored in every manifest
+pub const ARCHIVE_FORMAT: &str = "abk-project-archive";
+
+/// Current archive format version (2 added `blobs/`)
+pub const ARCHIVE_VERSION: u32 = 2;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const PROJECT_METADATA_ENTRY: &str = "project_metadata.json";
+const SESSIONS_DIR: &str = "sessions";
+const BLOBS_DIR: &str = "blobs";
+
+/// Manifest describing the contents of a project archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    /// Always [`ARCHIVE_FORMAT`]
+    pub format: String,
+    /// Archive format version
+    pub version: u32,
+    /// When the archive was written
+    pub created_at: DateTime<Utc>,
+    /// Version of abk that wrote the archive
+    pub abk_version: String,
+    /// Project ID on the exporting machine
+    pub project_id: String,
+    /// Project path on the exporting machine
+    pub project_path: PathBuf,
+    /// IDs of the sessions contained in the archive
+    pub sessions: Vec<String>,
+    /// Every archive entry except the manifest itself
+    pub entries: Vec<ArchiveEntry>,
+}
+
+/// One file stored in a project archive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    /// Path relative to the project storage directory, `/`-separated
+    pub path: String,
+    /// Size in bytes
+    pub size: u64,
+    /// Lowercase hex SHA-256 digest of the contents
+    pub sha256: String,
+}
+
+/// What to do when an imported session ID already exists in the target project
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SessionCollisionPolicy {
+    /// Keep the existing session and skip the imported one
+    #[default]
+    Skip,
+    /// Import under a new ID (`<id>_imported`, `<id>_imported_2`, ...)
+    Rename,
+    /// Replace the existing session
+    Overwrite,
+    /// Abort the import before anything is written
+    Fail,
+}
+
+/// Outcome of importing a project archive
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveImportReport {
+    /// Project ID recorded in the archive
+    pub source_project_id: String,
+    /// Sessions written to the target project (under their final IDs)
+    pub imported_sessions: Vec<String>,
+    /// Sessions whose ID already existed and were left untouched
+    pub skipped_sessions: Vec<String>,
+    /// Sessions imported under a new ID, as `(original, new)`
+    pub renamed_sessions: Vec<(String, String)>,
+    /// Sessions that replaced an existing session with the same ID
+    pub overwritten_sessions: Vec<String>,
+}
+
+/// Archive contents after integrity verification, unpacked to a staging
+/// directory that is removed when the archive is dropped
+pub(crate) struct VerifiedArchive {
+    pub(crate) manifest: ArchiveManifest,
+    staging: StagingDir,
+}
+
+impl VerifiedArchive {
+    /// Contents of the archived `project_metadata.json`, if there is one
+    pub(crate) fn project_metadata(&self) -> CheckpointResult<Option<Vec<u8>>> {
+        let path = self.staging.0.join(PROJECT_METADATA_ENTRY);
+        if path.is_file() {
+            Ok(Some(fs::read(path)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Temporary directory deleted on drop
+struct StagingDir(PathBuf);
+
+impl StagingDir {
+    /// Create a hidden directory under `parent`, which is never exported
+    fn new(parent: &Path) -> CheckpointResult<Self> {
+        let path = parent.join(format!(".import-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&path)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Reader that counts and hashes the bytes read through it
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+    len: u64,
+}
+
+impl<R: Read> HashingReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+            len: 0,
+        }
+    }
+
+    /// Whether the bytes read so far are exactly those described by `entry`
+    fn matches(self, entry: &ArchiveEntry) -> bool {
+        self.len == entry.size && format!("{:x}", self.hasher.finalize()) == entry.sha256
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.len += n as u64;
+        Ok(n)
+    }
+}
+
+/// Write the project stored under `storage_path` to a tar archive.
+///
+/// Files are hashed for the manifest first and then streamed into the
+/// archive, so nothing is held in memory. A file that changes in between
+/// fails the export. The `cache/` directory and temp files left in
+/// `blobs/` are not exported.
+pub(crate) fn write_archive(
+    storage_path: &Path,
+    project_id: &str,
+    project_path: &Path,
+    archive_path: &Path,
+) -> CheckpointResult<ArchiveManifest> {
+    let mut files: Vec<(String, PathBuf)> = Vec::new();
+
+    let project_metadata = storage_path.join(PROJECT_METADATA_ENTRY);
+    if project_metadata.is_file() {
+        files.push((PROJECT_METADATA_ENTRY.to_string(), project_metadata));
+    }
+
+    let mut sessions = Vec::new();
+    let sessions_dir = storage_path.join(SESSIONS_DIR);
+    if sessions_dir.is_dir() {
+        for session_dir in sorted_entries(&sessions_dir)? {
+            if !session_dir.is_dir() {
+                continue;
+            }
+            let session_id = session_dir.file_name().unwrap_or_default().to_string_lossy().to_string();
+            collect_files(&session_dir, &format!("{}/{}", SESSIONS_DIR, session_id), &mut files)?;
+            sessions.push(session_id);
+        }
+    }
+
+    let blobs_dir = storage_path.join(BLOBS_DIR);
+    if blobs_dir.is_dir() {
+        let mut blobs = Vec::new();
+        collect_files(&blobs_dir, BLOBS_DIR, &mut blobs)?;
+        files.extend(blobs.into_iter().filter(|(path, _)| blob_checksum(path).is_some()));
+    }
+
+    let entries = files
+        .iter()
+        .map(|(path, source)| {
+            let mut reader = HashingReader::new(fs::File::open(source)?);
+            io::copy(&mut reader, &mut io::sink())?;
+            Ok(ArchiveEntry {
+                path: path.clone(),
+                size: reader.len,
+                sha256: format!("{:x}", reader.hasher.finalize()),
+            })
+        })
+        .collect::<CheckpointResult<Vec<_>>>()?;
+
+    let manifest = ArchiveManifest {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        created_at: Utc::now(),
+        abk_version: env!("CARGO_PKG_VERSION").to_string(),
+        project_id: project_id.to_string(),
+        project_path: project_path.to_path_buf(),
+        sessions,
+        entries,
+    };
+
+    if let Some(parent) = archive_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let temp_path = archive_path.with_extension("tmp");
+    let result = (|| -> CheckpointResult<()> {
+        let mut builder = tar::Builder::new(io::BufWriter::new(fs::File::create(&temp_path)?));
+        let mtime = manifest.created_at.timestamp().max(0) as u64;
+        append_entry(&mut builder, MANIFEST_ENTRY, &serde_json::to_vec_pretty(&manifest)?, mtime)?;
+        for (entry, (_, source)) in manifest.entries.iter().zip(&files) {
+            let mut reader = HashingReader::new(fs::File::open(source)?.take(entry.size));
+            append_stream(&mut builder, &entry.path, entry.size, &mut reader, mtime)?;
+            if !reader.matches(entry) {
+                return Err(CheckpointError::storage(format!(
+                    "{} changed while the project was being exported",
+                    source.display()
+                )));
+            }
+        }
+        let file = builder.into_inner()?.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        fs::rename(&temp_path, archive_path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result.map(|_| manifest)
+}
+
+/// Read a project archive, verifying every entry against its manifest.
+///
+/// Entries are streamed to a staging directory under `staging_parent` and
+/// hashed on the way; nothing outside it is written.
+pub(crate) fn read_archive(archive_path: &Path, staging_parent: &Path) -> CheckpointResult<VerifiedArchive> {
+    let staging = StagingDir::new(staging_parent)?;
+    let mut archive = tar::Archive::new(io::BufReader::new(fs::File::open(archive_path)?));
+    let mut manifest: Option<ArchiveManifest> = None;
+    let mut pending: BTreeMap<String, ArchiveEntry> = BTreeMap::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry_path(&entry.path()?)?;
+
+        if manifest.is_none() {
+            if path != MANIFEST_ENTRY {
+                return Err(CheckpointError::corrupted(format!(
+                    "{} is not a project archive: first entry is '{}'",
+                    archive_path.display(),
+                    path
+                )));
+            }
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            let parsed = parse_manifest(&data)?;
+            for expected in &parsed.entries {
+                if pending.insert(expected.path.clone(), expected.clone()).is_some() {
+                    return Err(CheckpointError::corrupted(format!(
+                        "Duplicate manifest entry '{}'",
+                        expected.path
+                    )));
+                }
+            }
+            manifest = Some(parsed);
+            continue;
+        }
+
+        let expected = pending.remove(&path).ok_or_else(|| {
+            CheckpointError::corrupted(format!(
+                "Archive entry '{}' is duplicated or not listed in its manifest",
+                path
+            ))
+        })?;
+        if entry.header().size()? != expected.size {
+            return Err(CheckpointError::corrupted(format!(
+                "Archive entry '{}' does not match its checksum",
+                path
+            )));
+        }
+        let target = staging.0.join(&path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut reader = HashingReader::new(&mut entry);
+        io::copy(&mut reader, &mut fs::File::create(&target)?)?;
+        if !reader.matches(&expected) {
+            return Err(CheckpointError::corrupted(format!(
+                "Archive entry '{}' does not match its checksum",
+                path
+            )));
+        }
+    }
+
+    let manifest = manifest.ok_or_else(|| {
+        CheckpointError::corrupted(format!("{} is empty", archive_path.display()))
+    })?;
+    if let Some(missing) = pending.keys().next() {
+        return Err(CheckpointError::corrupted(format!("Archive entry '{}' is missing", missing)));
+    }
+
+    Ok(VerifiedArchive { manifest, staging })
+}
+
+/// Write the sessions and blobs of a verified archive into the project
+/// stored under `storage_path`, rewriting project IDs, paths under the
+/// exported project directory and session IDs as needed.
+///
+/// Encrypted payloads, event lines and blobs are opened with `cipher` and
+/// sealed again for their new location; an archive that `cipher` can't open
+/// is rejected. Every blob is checked against its checksum. Sessions and
+/// blobs are prepared in the staging directory before the first one is moved
+/// into place, so a failed import leaves the project as it was.
+pub(crate) fn import_archive(
+    archive: &VerifiedArchive,
+    storage_path: &Path,
+    target_project_id: &str,
+    target_project_path: &Path,
+    cipher: Option<&CheckpointCipher>,
+    policy: SessionCollisionPolicy,
+) -> CheckpointResult<ArchiveImportReport> {
+    // Session IDs become directory names below; never trust them blindly
+    validate_layout(&archive.manifest)?;
+
+    let sessions_dir = storage_path.join(SESSIONS_DIR);
+    let source_project_id = &archive.manifest.project_id;
+    let mut report = ArchiveImportReport {
+        source_project_id: source_project_id.clone(),
+        ..Default::default()
+    };
+
+    let collisions: Vec<&String> = archive
+        .manifest
+        .sessions
+        .iter()
+        .filter(|id| sessions_dir.join(id).exists())
+        .collect();
+    if policy == SessionCollisionPolicy::Fail && !collisions.is_empty() {
+        return Err(CheckpointError::storage(format!(
+            "Sessions already exist in the target project: {}",
+            collisions.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(", ")
+        )));
+    }
+
+    // Decide every target ID first, so forks can follow a renamed parent
+    let mut targets = Vec::new();
+    for session_id in &archive.manifest.sessions {
+        let mut target_id = session_id.clone();
+        let mut overwrite = false;
+        if collisions.contains(&session_id) {
+            match policy {
+                SessionCollisionPolicy::Skip | SessionCollisionPolicy::Fail => {
+                    report.skipped_sessions.push(session_id.clone());
+                    continue;
+                }
+                SessionCollisionPolicy::Rename => {
+                    target_id = unused_session_id(&sessions_dir, session_id);
+                    report.renamed_sessions.push((session_id.clone(), target_id.clone()));
+                }
+                SessionCollisionPolicy::Overwrite => {
+                    overwrite = true;
+                    report.overwritten_sessions.push(session_id.clone());
+                }
+            }
+        }
+        targets.push((session_id, target_id, overwrite));
+    }
+    let renamed: HashMap<&str, &str> = report
+        .renamed_sessions
+        .iter()
+        .map(|(from, to)| (from.as_str(), to.as_str()))
+        .collect();
+
+    let prepared_dir = archive.staging.0.join(".prepared");
+    let prepared_blobs = prepare_blobs(archive, &prepared_dir.join(BLOBS_DIR), cipher)?;
+    let mut prepared = Vec::new();
+    for (session_id, target_id, overwrite) in targets {
+        let remap = IdRemap {
+            from_project: source_project_id,
+            to_project: target_project_id,
+            from_path: &archive.manifest.project_path,
+            to_path: target_project_path,
+            from_session: session_id,
+            to_session: &target_id,
+            renamed: &renamed,
+            cipher,
+        };
+        let source_dir = archive.staging.0.join(SESSIONS_DIR).join(session_id);
+        let session_dir = prepared_dir.join(SESSIONS_DIR).join(&target_id);
+        fs::create_dir_all(&session_dir)?;
+        if source_dir.is_dir() {
+            remap.copy_dir(&source_dir, &session_dir, "")?;
+        }
+        prepared.push((session_dir, target_id, overwrite));
+    }
+
+    // Blobs are content-addressed: one that exists already is the same file
+    for (blob, relative) in prepared_blobs {
+        let target = storage_path.join(&relative);
+        if !target.exists() {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(&blob, &target)?;
+        }
+    }
+
+    fs::create_dir_all(&sessions_dir)?;
+    for (session_dir, target_id, overwrite) in prepared {
+        let target_dir = sessions_dir.join(&target_id);
+        if overwrite {
+            fs::remove_dir_all(&target_dir)?;
+        }
+        fs::rename(&session_dir, &target_dir)?;
+        report.imported_sessions.push(target_id);
+    }
+
+    Ok(report)
+}
+
+/// Verify the archived blobs and seal them for the importing project
+///
+/// Returns each prepared blob with its path below the project directory.
+fn prepare_blobs(
+    archive: &VerifiedArchive,
+    prepared_dir: &Path,
+    cipher: Option<&CheckpointCipher>,
+) -> CheckpointResult<Vec<(PathBuf, String)>> {
+    let mut prepared = Vec::new();
+    for entry in &archive.manifest.entries {
+        let Some(checksum) = blob_checksum(&entry.path) else {
+            continue;
+        };
+        let data = fs::read(archive.staging.0.join(&entry.path))?;
+        let stored = if encryption::is_encrypted(&data) {
+            let cipher = cipher.ok_or_else(|| CheckpointError::EncryptionKeyMissing {
+                message: format!(
+                    "Archived file snapshot {} is encrypted; configure the key it was exported with to import it",
+                    checksum
+                ),
+            })?;
+            cipher.decrypt(&data, checksum)?
+        } else {
+            data
+        };
+
+        // Same rule as BlobStore::get: zstd files may be stored as they are
+        let content = if compression::is_compressed(&stored) && sha256_hex(&stored) != checksum {
+            compression::decompress(&stored)?.into_owned()
+        } else {
+            stored.clone()
+        };
+        if sha256_hex(&content) != checksum {
+            return Err(CheckpointError::corrupted(format!(
+                "Archived file snapshot {} does not match its checksum",
+                checksum
+            )));
+        }
+
+        let path = prepared_dir.join(checksum);
+        fs::create_dir_all(prepared_dir)?;
+        match cipher {
+            Some(cipher) => fs::write(&path, cipher.encrypt(&stored, checksum)?)?,
+            None => fs::write(&path, &stored)?,
+        }
+        prepared.push((path, entry.path.clone()));
+    }
+    Ok(prepared)
+}
+
+/// Project, path and session ID substitutions applied to imported files
+struct IdRemap<'a> {
+    from_project: &'a str,
+    to_project: &'a str,
+    /// Project directory on the exporting machine
+    from_path: &'a Path,
+    /// Project directory on this machine
+    to_path: &'a Path,
+    from_session: &'a str,
+    to_session: &'a str,
+    /// Every session of the archive imported under a new ID
+    renamed: &'a HashMap<&'a str, &'a str>,
+    /// Opens encrypted data and seals it for the new session
+    cipher: Option<&'a CheckpointCipher>,
+}
+
+impl IdRemap<'_> {
+    fn is_identity(&self) -> bool {
+        self.from_project == self.to_project
+            && self.from_session == self.to_session
+            && self.from_path == self.to_path
+            && self.renamed.is_empty()
+    }
+
+    /// `path` moved from the exported project directory to this one
+    fn remap_path(&self, path: &str) -> Option<String> {
+        let rest = Path::new(path).strip_prefix(self.from_path).ok()?;
+        let moved = if rest.as_os_str().is_empty() { self.to_path.to_path_buf() } else { self.to_path.join(rest) };
+        Some(moved.to_string_lossy().into_owned())
+    }
+
+    /// Copy the session files under `source` to `dest`, rewriting them with
+    /// [`copy_file`](Self::copy_file); `relative` is the path below the
+    /// session directory.
+    fn copy_dir(&self, source: &Path, dest: &Path, relative: &str) -> CheckpointResult<()> {
+        for path in sorted_entries(source)? {
+            let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let relative = if relative.is_empty() { name.clone() } else { format!("{}/{}", relative, name) };
+            if path.is_dir() {
+                fs::create_dir_all(dest.join(&name))?;
+                self.copy_dir(&path, &dest.join(&name), &relative)?;
+            } else {
+                self.copy_file(&relative, &path, &dest.join(&name))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Rewrite `.json` and `.jsonl` files; other files are moved verbatim.
+    ///
+    /// Compressed `.json` files are rewritten and compressed again, and
+    /// encrypted ones are sealed again for the target session. With a
+    /// cipher, plain payloads are sealed too, since the importer rejects
+    /// unsealed data once encryption is enabled.
+    fn copy_file(&self, relative: &str, source: &Path, dest: &Path) -> CheckpointResult<()> {
+        if relative.ends_with(".jsonl") {
+            return self.copy_lines(relative, source, dest);
+        }
+        if !relative.ends_with(".json") {
+            fs::rename(source, dest)?;
+            return Ok(());
+        }
+
+        let data = fs::read(source)?;
+        let sealed = encryption::is_encrypted(&data);
+        let seal = sealed || (self.cipher.is_some() && !PLAIN_SESSION_FILES.contains(&relative));
+        let data = if sealed {
+            self.cipher(relative)?
+                .decrypt(&data, &encryption::payload_context(self.from_session, relative))?
+        } else {
+            data
+        };
+        let data = if self.is_identity() { data } else { self.rewrite_json(data) };
+        let data = if seal {
+            self.cipher(relative)?
+                .encrypt(&data, &encryption::payload_context(self.to_session, relative))?
+        } else {
+            data
+        };
+        fs::write(dest, data)?;
+        Ok(())
+    }
+
+    /// Rewrite an `events.jsonl` log line by line
+    fn copy_lines(&self, relative: &str, source: &Path, dest: &Path) -> CheckpointResult<()> {
+        let from_context = encryption::events_context(self.from_session);
+        let to_context = encryption::events_context(self.to_session);
+        let mut out = io::BufWriter::new(fs::File::create(dest)?);
+        for line in io::BufReader::new(fs::File::open(source)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let sealed = line.starts_with(encryption::ENCRYPTED_LINE_PREFIX);
+            let mut text = if sealed {
+                encryption::open_line(&line, Some(self.cipher(relative)?), &from_context)?.into_owned()
+            } else {
+                line
+            };
+            if !self.is_identity() {
+                if let Ok(mut value) = serde_json::from_str::<Value>(&text) {
+                    self.rewrite(&mut value);
+                    text = value.to_string();
+                }
+            }
+            if sealed || self.cipher.is_some() {
+                text = self.cipher(relative)?.encrypt_line(&text, &to_context)?;
+            }
+            writeln!(out, "{}", text)?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Rewrite the IDs in a plain or compressed JSON document; data that
+    /// doesn't parse is kept as it is
+    fn rewrite_json(&self, data: Vec<u8>) -> Vec<u8> {
+        if compression::is_compressed(&data) {
+            let settings = CompressionSettings { enabled: true, ..Default::default() };
+            match compression::decode_json::<Value>(&data) {
+                Ok(mut value) => {
+                    self.rewrite(&mut value);
+                    settings.encode_json(&value).map(|blob| blob.bytes).unwrap_or(data)
+                }
+                Err(_) => data,
+            }
+        } else {
+            match serde_json::from_slice::<Value>(&data) {
+                Ok(mut value) => {
+                    self.rewrite(&mut value);
+                    serde_json::to_vec_pretty(&value).unwrap_or(data)
+                }
+                Err(_) => data,
+            }
+        }
+    }
+
+    /// The importer's cipher, needed to open the encrypted entry `relative`
+    fn cipher(&self, relative: &str) -> CheckpointResult<&CheckpointCipher> {
+        self.cipher.ok_or_else(|| CheckpointError::EncryptionKeyMissing {
+            message: format!(
+                "Archived session {} has encrypted data ({}); configure the key it was exported with to import it",
+                self.from_session, relative
+            ),
+        })
+    }
+
+    fn rewrite(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    match (key.as_str(), value) {
+                        ("project_hash" | "project_id", Value::String(id)) if id == self.from_project => {
+                            *id = self.to_project.to_string();
+                        }
+                        ("session_id", Value::String(id)) if id == self.from_session => {
+                            *id = self.to_session.to_string();
+                        }
+                        ("parent_session_id", Value::String(id)) => {
+                            if let Some(renamed) = self.renamed.get(id.as_str()) {
+                                *id = renamed.to_string();
+                            }
+                        }
+                        ("working_directory" | "project_path", Value::String(path)) => {
+                            if let Some(moved) = self.remap_path(path) {
+                                *path = moved;
+                            }
+                        }
+                        (_, value) => self.rewrite(value),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.rewrite(item)),
+            _ => {}
+        }
+    }
+}
+
+fn parse_manifest(data: &[u8]) -> CheckpointResult<ArchiveManifest> {
+    let manifest: ArchiveManifest = serde_json::from_slice(data)?;
+    if manifest.format != ARCHIVE_FORMAT {
+        return Err(CheckpointError::corrupted(format!(
+            "Unknown archive format '{}'",
+            manifest.format
+        )));
+    }
+    if manifest.version > ARCHIVE_VERSION {
+        return Err(CheckpointError::VersionMismatch {
+            expected: ARCHIVE_VERSION.to_string(),
+            found: manifest.version.to_string(),
+        });
+    }
+    validate_layout(&manifest)?;
+    Ok(manifest)
+}
+
+/// Check that session IDs are plain directory names and that every entry
+/// is the project metadata, a blob or belongs to a listed session.
+fn validate_layout(manifest: &ArchiveManifest) -> CheckpointResult<()> {
+    for session_id in &manifest.sessions {
+        let mut components = Path::new(session_id).components();
+        let plain = matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(name)), None) if name == session_id.as_str()
+        );
+        if !plain {
+            return Err(CheckpointError::corrupted(format!(
+                "Unsafe session ID '{}' in archive manifest",
+                session_id
+            )));
+        }
+    }
+
+    for entry in &manifest.entries {
+        if entry.path == PROJECT_METADATA_ENTRY || blob_checksum(&entry.path).is_some() {
+            continue;
+        }
+        let session_id = entry
+            .path
+            .strip_prefix(SESSIONS_DIR)
+            .and_then(|rest| rest.strip_prefix('/'))
+            .and_then(|rest| rest.split_once('/'))
+            .map(|(id, _)| id);
+        if !session_id.is_some_and(|id| manifest.sessions.iter().any(|s| s == id)) {
+            return Err(CheckpointError::corrupted(format!(
+                "Archive entry '{}' does not belong to a listed session",
+                entry.path
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Checksum of a `blobs/<sha[..2]>/<sha>` entry, or `None` for other paths
+fn blob_checksum(path: &str) -> Option<&str> {
+    let (prefix, checksum) = path.strip_prefix(BLOBS_DIR)?.strip_prefix('/')?.split_once('/')?;
+    let valid = checksum.len() == 64
+        && checksum.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+        && checksum.starts_with(prefix)
+        && prefix.len() == 2;
+    valid.then_some(checksum)
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Validate an archive entry path: relative, no `..`, `/`-separated.
+fn entry_path(path: &Path) -> CheckpointResult<String> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            Component::CurDir => {}
+            _ => {
+                return Err(CheckpointError::corrupted(format!(
+                    "Unsafe archive entry path '{}'",
+                    path.display()
+                )))
+            }
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+fn append_entry<W: Write>(
+    builder: &mut tar::Builder<W>,
+    path: &str,
+    data: &[u8],
+    mtime: u64,
+) -> CheckpointResult<()> {
+    append_stream(builder, path, data.len() as u64, data, mtime)
+}
+
+fn append_stream<W: Write, R: Read>(
+    builder: &mut tar::Builder<W>,
+    path: &str,
+    size: u64,
+    data: R,
+    mtime: u64,
+) -> CheckpointResult<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(size);
+    header.set_mode(0o644);
+    header.set_mtime(mtime);
+    header.set_cksum();
+    builder.append_data(&mut header, path, data)?;
+    Ok(())
+}
+
+/// Recursively collect regular files under `dir` as (archive path, file path).
+fn collect_files(dir: &Path, prefix: &str, files: &mut Vec<(String, PathBuf)>) -> CheckpointResult<()> {
+    for path in sorted_entries(dir)? {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let archive_path = format!("{}/{}", prefix, name);
+        let file_type = fs::symlink_metadata(&path)?.file_type();
+        if file_type.is_dir() {
+            collect_files(&path, &archive_path, files)?;
+        } else if file_type.is_file() {
+            files.push((archive_path, path));
+        }
+    }
+    Ok(())
+}
+
+fn sorted_entries(dir: &Path) -> CheckpointResult<Vec<PathBuf>> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    Ok(entries)
+}
+
+fn unused_session_id(sessions_dir: &Path, session_id: &str) -> String {
+    let mut candidate = format!("{}_imported", session_id);
+    let mut n = 2;
+    while sessions_dir.join(&candidate).exists() {
+        candidate = format!("{}_imported_{}", session_id, n);
+        n += 1;
+    }
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn sample_project(root: &Path) {
+        write(&root.join("project_metadata.json"), r#"{"project_hash":"src"}"#);
+        write(&root.join("cache/ignored.bin"), "x");
+        let session = root.join("sessions/s1");
+        write(
+            &session.join("session_metadata.json"),
+            &json!({"session_id": "s1", "project_hash": "src"}).to_string(),
+        );
+        write(
+            &session.join("checkpoints.json"),
+            &json!({"001": {"session_id": "s1", "project_hash": "src"}}).to_string(),
+        );
+        write(
+            &session.join("events.jsonl"),
+            "{\"session_id\":\"s1\",\"seq\":1}\n{\"session_id\":\"s1\",\"seq\":2}\n",
+        );
+    }
+
+    #[test]
+    fn test_round_trip_remaps_ids() {
+        let tmp = TempDir::new().unwrap();
+        let source = tmp.path().join("source");
+        sample_project(&source);
+        let archive_path = tmp.path().join("out/project.tar");
+
+        let manifest = write_archive(&source, "src", Path::new("/work/p"), &archive_path).unwrap();
+        assert_eq!(manifest.sessions, vec!["s1".to_string()]);
+        assert_eq!(manifest.entries.len(), 4);
+        assert!(!manifest.entries.iter().any(|e| e.path.starts_with("cache/")));
+
+        let target = tmp.path().join("target");
+        let archive = read_archive(&archive_path, &target).unwrap();
+        assert_eq!(archive.manifest.project_id, "src");
+        assert_eq!(archive.project_metadata().unwrap().unwrap(), br#"{"project_hash":"src"}"#);
+
+        let report = import_archive(&archive, &target, "dst", Path::new("/home/p"), None, SessionCollisionPolicy::Skip).unwrap();
+        assert_eq!(report.imported_sessions, vec!["s1".to_string()]);
+
+        // The staging directory goes away with the archive
+        drop(archive);
+        assert_eq!(fs::read_dir(&target).unwrap().count(), 1);
+
+        let metadata: Value =
+            serde_json::from_slice(&fs::read(target.join("sessions/s1/session_metadata.json")).unwrap()).unwrap();
+        assert_eq!(metadata["project_hash"], "dst");
+        let index: Value =
+            serde_json::from_slice(&fs::read(target.join("sessions/s1/checkpoints.json")).unwrap()).unwrap();
+        assert_eq!(index["001"]["project_hash"], "dst");
+    }
+
+    #[test]
+    fn test_session_collisions() {
+        let tmp = TempDir::new().unwrap();
+        let source = tmp.path().join("source");
+        sample_project(&source);
+        let archive_path = tmp.path().join("project.tar");
+        write_archive(&source, "src", Path::new("/work/p"), &archive_path).unwrap();
+        let target = tmp.path().join("target");
+        let archive = read_archive(&archive_path, &target).unwrap();
+
+        write(&target.join("sessions/s1/session_metadata.json"), "{}");
+
+        let report = import_archive(&archive, &target, "dst", Path::new("/home/p"), None, SessionCollisionPolicy::Skip).unwrap();
+        assert_eq!(report.skipped_sessions, vec!["s1".to_string()]);
+        assert!(report.imported_sessions.is_empty());
+
+        assert!(import_archive(&archive, &target, "dst", Path::new("/home/p"), None, SessionCollisionPolicy::Fail).is_err());
+
+        let report = import_archive(&archive, &target, "dst", Path::new("/home/p"), None, SessionCollisionPolicy::Rename).unwrap();
+        assert_eq!(report.renamed_sessions, vec![("s1".to_string(), "s1_imported".to_string())]);
+        let events = fs::read_to_string(target.join("sessions/s1_imported/events.jsonl")).unwrap();
+        assert!(events.lines().all(|line| line.contains("\"session_id\":\"s1_imported\"")));
+    }
+
+    #[test]
+    fn test_import_moves_paths_and_follows_renamed_parents() {
+        let tmp = TempDir::new().unwrap();
+        let source = tmp.path().join("source");
+        sample_project(&source);
+        write(
+            &source.join("sessions/s2/session_metadata.json"),
+            &json!({"session_id": "s2", "parent_session_id": "s1", "project_hash": "src"}).to_string(),
+        );
+        write(
+            &source.join("sessions/s2/001_files.json"),
+            &json!({"working_directory": "/work/p/crates/core", "tracked_files": []}).to_string(),
+        );
+        write(
+            &source.join("sessions/s2/session_agent.json"),
+            &json!({"working_directory": "/elsewhere"}).to_string(),
+        );
+        let archive_path = tmp.path().join("project.tar");
+        write_archive(&source, "src", Path::new("/work/p"), &archive_path).unwrap();
+
+        let target = tmp.path().join("target");
+        write(&target.join("sessions/s1/session_metadata.json"), "{}");
+        let archive = read_archive(&archive_path, &target).unwrap();
+        import_archive(&archive, &target, "dst", Path::new("/home/p"), None, SessionCollisionPolicy::Rename).unwrap();
+
+        let read = |path: &str| -> Value { serde_json::from_slice(&fs::read(target.join(path)).unwrap()).unwrap() };
+        assert_eq!(read("sessions/s2/session_metadata.json")["parent_session_id"], "s1_imported");
+        assert_eq!(read("sessions/s2/001_files.json")["working_directory"], "/home/p/crates/core");
+        // Paths outside the exported project are left alone
+        assert_eq!(read("sessions/s2/session_agent.json")["working_directory"], "/elsewhere");
+    }
+
+    #[test]
+    fn test_blobs_are_exported_and_verified_on_import() {
+        let tmp = TempDir::new().unwrap();
+        let source = tmp.path().join("source");
+        sample_project(&source);
+        let content = b"fn main() {}\n";
+        let checksum = sha256_hex(content);
+        let blob = format!("blobs/{}/{}", &checksum[..2], checksum);
+        write(&source.join(&blob), "fn main() {}\n");
+        write(&source.join(format!("{}.tmp-1", blob)), "partial");
+        let archive_path = tmp.path().join("project.tar");
+        let manifest = write_archive(&source, "src", Path::new("/work/p"), &archive_path).unwrap();
+        let blobs: Vec<_> = manifest.entries.iter().filter(|e| e.path.starts_with("blobs/")).collect();
+        assert_eq!(blobs.len(), 1);
+        assert_eq!(blobs[0].path, blob);
+
+        let target = tmp.path().join("target");
+        let archive = read_archive(&archive_path, &target).unwrap();
+        let cipher = CheckpointCipher::from_key([5u8; 32]);
+        import_archive(&archive, &target, "dst", Path::new("/home/p"), Some(&cipher), SessionCollisionPolicy::Skip).unwrap();
+        let stored = fs::read(target.join(&blob)).unwrap();
+        assert_eq!(cipher.decrypt(&stored, &checksum).unwrap(), content);
+
+        // A blob whose content doesn't match its name is refused
+        let other = tmp.path().join("other");
+        let archive = read_archive(&archive_path, &other).unwrap();
+        fs::write(archive.staging.0.join(&blob), "tampered").unwrap();
+        assert!(matches!(
+            import_archive(&archive, &other, "dst", Path::new("/home/p"), None, SessionCollisionPolicy::Skip),
+            Err(CheckpointError::CorruptedData { .. })
+        ));
+        assert!(!other.join("sessions/s1").exists());
+
+        assert_eq!(blob_checksum(&blob), Some(checksum.as_str()));
+        for path in ["blobs/zz/zz", "blobs/../x", &format!("blobs/00/{}", checksum)] {
+            assert_eq!(blob_checksum(path), None);
+        }
+    }
+
+    #[test]
+    fn test_rejects_unsafe_session_ids() {
+        let tmp = TempDir::new().unwrap();
+        let storage = tmp.path().join("home/projects/dst");
+        write(&storage.join("sessions/s1/session_metadata.json"), "{}");
+        write(&tmp.path().join("home/keep.txt"), "precious");
+
+        let data = br#"{"session_id":"s1"}"#.to_vec();
+        let entry = ArchiveEntry {
+            path: "sessions/s1/session_metadata.json".to_string(),
+            size: data.len() as u64,
+            sha256: sha256_hex(&data),
+        };
+        let manifest = ArchiveManifest {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            created_at: Utc::now(),
+            abk_version: "0".to_string(),
+            project_id: "src".to_string(),
+            project_path: PathBuf::from("/work/p"),
+            sessions: vec!["../..".to_string(), "s1".to_string()],
+            entries: vec![entry.clone()],
+        };
+
+        let archive_path = tmp.path().join("evil.tar");
+        let mut builder = tar::Builder::new(fs::File::create(&archive_path).unwrap());
+        append_entry(&mut builder, MANIFEST_ENTRY, &serde_json::to_vec(&manifest).unwrap(), 0).unwrap();
+        append_entry(&mut builder, &entry.path, &data, 0).unwrap();
+        builder.into_inner().unwrap();
+        assert!(matches!(
+            read_archive(&archive_path, &storage),
+            Err(CheckpointError::CorruptedData { .. })
+        ));
+
+        // Even a manifest that skipped verification never reaches the filesystem
+        for sessions in [vec!["../..".to_string()], vec!["/".to_string()], vec!["a/b".to_string()]] {
+            let staging = StagingDir::new(&storage).unwrap();
+            write(&staging.0.join(&entry.path), r#"{"session_id":"s1"}"#);
+            let archive = VerifiedArchive {
+                manifest: ArchiveManifest { sessions, ..manifest.clone() },
+                staging,
+            };
+            assert!(import_archive(&archive, &storage, "dst", Path::new("/home/p"), None, SessionCollisionPolicy::Overwrite).is_err());
+        }
+        assert!(tmp.path().join("home/keep.txt").exists());
+        assert!(storage.join("sessions/s1/session_metadata.json").exists());
+
+        // Entries outside the listed sessions are rejected too
+        let stray = ArchiveManifest {
+            sessions: vec!["s2".to_string()],
+            ..manifest.clone()
+        };
+        assert!(validate_layout(&stray).is_err());
+    }
+
+    #[test]
+    fn test_rejects_tampered_archive() {
+        let tmp = TempDir::new().unwrap();
+        let source = tmp.path().join("source");
+        sample_project(&source);
+        let archive_path = tmp.path().join("project.tar");
+        write_archive(&source, "src", Path::new("/work/p"), &archive_path).unwrap();
+
+        // Flip the recorded digest of one entry
+        let mut bytes = fs::read(&archive_path).unwrap();
+        let needle = sha256_hex(br#"{"project_hash":"src"}"#);
+        let pos = bytes
+            .windows(needle.len())
+            .position(|w| w == needle.as_bytes())
+            .unwrap();
+        bytes[pos] = if bytes[pos] == b'0' { b'1' } else { b'0' };
+        fs::write(&archive_path, bytes).unwrap();
+
+        let target = tmp.path().join("target");
+        fs::create_dir_all(&target).unwrap();
+        assert!(matches!(
+            read_archive(&archive_path, &target),
+            Err(CheckpointError::CorruptedData { .. })
+        ));
+        assert_eq!(fs::read_dir(&target).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_plain_sessions_are_sealed_for_an_encrypted_target() {
+        let tmp = TempDir::new().unwrap();
+        let source = tmp.path().join("source");
+        sample_project(&source);
+        write(&source.join("sessions/s1/001_conversation.json"), r#"{"session_id":"s1"}"#);
+        let archive_path = tmp.path().join("project.tar");
+        write_archive(&source, "src", Path::new("/work/p"), &archive_path).unwrap();
+
+        let target = tmp.path().join("target");
+        let archive = read_archive(&archive_path, &target).unwrap();
+        let cipher = CheckpointCipher::from_key([5u8; 32]);
+        import_archive(&archive, &target, "dst", Path::new("/home/p"), Some(&cipher), SessionCollisionPolicy::Skip).unwrap();
+
+        let session = target.join("sessions/s1");
+        let stored = fs::read(session.join("001_conversation.json")).unwrap();
+        let context = encryption::payload_context("s1", "001_conversation.json");
+        assert!(encryption::open(&stored, Some(&cipher), &context).is_ok());
+        // Metadata and the index stay readable without the key
+        assert!(!encryption::is_encrypted(&fs::read(session.join("checkpoints.json")).unwrap()));
+        let events = fs::read_to_string(session.join("events.jsonl")).unwrap();
+        for line in events.lines() {
+            assert!(encryption::open_line(line, Some(&cipher), &encryption::events_context("s1")).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_encrypted_sessions_are_resealed_or_rejected() {
+        let tmp = TempDir::new().unwrap();
+        let source = tmp.path().join("source");
+        let cipher = CheckpointCipher::from_key([5u8; 32]);
+        let conversation = cipher
+            .encrypt(br#"{"session_id":"s1"}"#, &encryption::payload_context("s1", "001_conversation.json"))
+            .unwrap();
+        write(&source.join("sessions/s1/session_metadata.json"), r#"{"session_id":"s1"}"#);
+        fs::write(source.join("sessions/s1/001_conversation.json"), conversation).unwrap();
+        let line = cipher
+            .encrypt_line(r#"{"session_id":"s1"}"#, &encryption::events_context("s1"))
+            .unwrap();
+        write(&source.join("sessions/s1/events.jsonl"), &format!("{}
+", line));
+        let archive_path = tmp.path().join("project.tar");
+        write_archive(&source, "src", Path::new("/work/p"), &archive_path).unwrap();
+
+        let target = tmp.path().join("target");
+        write(&target.join("sessions/s1/session_metadata.json"), "{}");
+        let archive = read_archive(&archive_path, &target).unwrap();
+
+        // Without the key nothing is written
+        assert!(matches!(
+            import_archive(&archive, &target, "dst", Path::new("/home/p"), None, SessionCollisionPolicy::Rename),
+            Err(CheckpointError::EncryptionKeyMissing { .. })
+        ));
+        assert!(!target.join("sessions/s1_imported").exists());
+
+        let report = import_archive(&archive, &target, "dst", Path::new("/home/p"), Some(&cipher), SessionCollisionPolicy::Rename).unwrap();
+        assert_eq!(report.imported_sessions, vec!["s1_imported".to_string()]);
+        let session = target.join("sessions/s1_imported");
+        let stored = fs::read(session.join("001_conversation.json")).unwrap();
+        let context = encryption::payload_context("s1_imported", "001_conversation.json");
+        let conversation: Value = serde_json::from_slice(&cipher.decrypt(&stored, &context).unwrap()).unwrap();
+        assert_eq!(conversation["session_id"], "s1_imported");
+        let events = fs::read_to_string(session.join("events.jsonl")).unwrap();
+        let event = encryption::open_line(events.trim(), Some(&cipher), &encryption::events_context("s1_imported")).unwrap();
+        assert!(event.contains("\"session_id\":\"s1_imported\""));
+    }
+}
@@ -24,6 +24,11 @@ impl AtomicFileWriter {
 
     /// Write content to the file atomically
     pub fn write_content(&self, content: &str) -> CheckpointResult<()> {
+        self.write_bytes(content.as_bytes())
+    }
+
+    /// Write raw bytes to the file atomically
+    pub fn write_bytes(&self, content: &[u8]) -> CheckpointResult<()> {
         // Ensure parent directory exists
         if let Some(parent) = self.target_path.parent() {
             fs::create_dir_all(parent)?;
@@ -97,6 +102,12 @@ impl AtomicOps {
         writer.write_content(content)
     }
 
+    /// Atomically write raw bytes to a file
+    pub fn write_bytes(path: &Path, content: &[u8]) -> CheckpointResult<()> {
+        let writer = AtomicFileWriter::new(path)?;
+        writer.write_bytes(content)
+    }
+
     /// Atomically write JSON data to a file
     pub fn write_json<T: serde::Serialize>(path: &Path, data: &T) -> CheckpointResult<()> {
         let writer = AtomicFileWriter::new(path)?;
@@ -0,0 +1,144 @@
+//! Compression of checkpoint data
+//!
+//! With `compression_enabled` set, checkpoint payloads (agent state,
+//! conversation and `{id}_files.json` file snapshots), V2 agent state and
+//! conversation files, and the blobs holding snapshotted file contents are
+//! stored as zstd frames. Files keep their `.json` names; readers detect the
+//! format from the zstd magic number, so checkpoints written before
+//! compression was turned on (or after it was turned off) load the same way.
+//! Blobs are only compressed when that makes them smaller.
+
+use super::{AtomicOps, CheckpointError, CheckpointResult, GlobalCheckpointConfig};
+use std::borrow::Cow;
+use std::path::Path;
+
+/// Magic number at the start of every zstd frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Highest zstd level used; levels above this are slow and gain little
+const MAX_ZSTD_LEVEL: u32 = 19;
+
+/// How checkpoint data is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionSettings {
+    /// Write zstd-compressed blobs instead of plain JSON
+    pub enabled: bool,
+    /// zstd level; 0 selects the zstd default
+    pub level: u32,
+}
+
+impl Default for CompressionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            level: 6,
+        }
+    }
+}
+
+impl CompressionSettings {
+    /// Read `compression_enabled` and `performance.compression_level`
+    pub fn from_config(config: &GlobalCheckpointConfig) -> Self {
+        Self {
+            enabled: config.compression_enabled,
+            level: config.performance.compression_level,
+        }
+    }
+
+    /// Serialize `value` as pretty JSON, compressing it when enabled
+    pub fn encode_json<T: serde::Serialize>(&self, value: &T) -> CheckpointResult<EncodedBlob> {
+        let json = serde_json::to_vec_pretty(value)?;
+        let uncompressed_size = json.len() as u64;
+        let bytes = if self.enabled {
+            compress(&json, self.level)?
+        } else {
+            json
+        };
+        Ok(EncodedBlob {
+            bytes,
+            uncompressed_size,
+        })
+    }
+}
+
+/// Serialized checkpoint data ready to be written
+#[derive(Debug, Clone)]
+pub struct EncodedBlob {
+    /// Bytes to store (zstd frame or plain JSON)
+    pub bytes: Vec<u8>,
+    /// Size of the JSON before compression
+    pub uncompressed_size: u64,
+}
+
+impl EncodedBlob {
+    /// Size of the stored bytes
+    pub fn stored_size(&self) -> u64 {
+        self.bytes.len() as u64
+    }
+}
+
+/// Whether `data` is a zstd frame
+pub fn is_compressed(data: &[u8]) -> bool {
+    data.starts_with(&ZSTD_MAGIC)
+}
+
+/// Compress `data` into a single zstd frame
+pub fn compress(data: &[u8], level: u32) -> CheckpointResult<Vec<u8>> {
+    zstd::bulk::compress(data, level.min(MAX_ZSTD_LEVEL) as i32)
+        .map_err(|e| CheckpointError::storage(format!("Failed to compress checkpoint data: {}", e)))
+}
+
+/// Return the plain bytes of `data`, decompressing zstd frames
+pub fn decompress(data: &[u8]) -> CheckpointResult<Cow<'_, [u8]>> {
+    if !is_compressed(data) {
+        return Ok(Cow::Borrowed(data));
+    }
+    zstd::stream::decode_all(data)
+        .map(Cow::Owned)
+        .map_err(|e| CheckpointError::storage(format!("Failed to decompress checkpoint data: {}", e)))
+}
+
+/// Deserialize JSON that may be zstd-compressed
+pub fn decode_json<T: serde::de::DeserializeOwned>(data: &[u8]) -> CheckpointResult<T> {
+    Ok(serde_json::from_slice(&decompress(data)?)?)
+}
+
+/// Read a JSON file that may be zstd-compressed
+pub fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> CheckpointResult<T> {
+    decode_json(&std::fs::read(path)?)
+}
+
+/// Atomically write an encoded blob to `path`
+pub fn write_blob(path: &Path, blob: &EncodedBlob) -> CheckpointResult<()> {
+    AtomicOps::write_bytes(path, &blob.bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_compressed_roundtrip_and_plain_fallback() {
+        let tmp = TempDir::new().unwrap();
+        let value = serde_json::json!({
+            "messages": vec!["the same line of conversation"; 200],
+        });
+
+        let compressed = CompressionSettings { enabled: true, level: 6 }
+            .encode_json(&value)
+            .unwrap();
+        assert!(is_compressed(&compressed.bytes));
+        assert!(compressed.stored_size() < compressed.uncompressed_size);
+        let path = tmp.path().join("001_conversation.json");
+        write_blob(&path, &compressed).unwrap();
+        assert_eq!(read_json::<serde_json::Value>(&path).unwrap(), value);
+
+        // Files written without compression (or before it existed) still load
+        let plain = CompressionSettings::default().encode_json(&value).unwrap();
+        assert!(!is_compressed(&plain.bytes));
+        assert_eq!(plain.stored_size(), plain.uncompressed_size);
+        std::fs::write(&path, serde_json::to_string_pretty(&value).unwrap()).unwrap();
+        assert_eq!(read_json::<serde_json::Value>(&path).unwrap(), value);
+    }
+}
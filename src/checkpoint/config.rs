@@ -265,8 +265,22 @@ fn default_max_checkpoints() -> u32 {
     50
 }
 
+/// Project-local checkpoint configuration file, relative to the project root
+pub const PROJECT_CONFIG_FILE: &str = ".abk/checkpoint.toml";
+
 /// Per-project checkpoint configuration
+///
+/// Loaded from [`PROJECT_CONFIG_FILE`] and merged over the global settings
+/// when the project's storage is opened. Omitted keys keep their defaults;
+/// an omitted or empty `include_patterns` snapshots every file not excluded.
+///
+/// A project file may override `enabled`, `auto_checkpoint_interval`,
+/// `max_checkpoints_per_session`, `compression_enabled`, `retention` and
+/// `git_integration` except `shadow_repo_location` and
+/// `auto_commit_before_checkpoint`, which only the global configuration sets
+/// (see [`merge_project_file_with_global`](Self::merge_project_file_with_global)).
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
 pub struct ProjectCheckpointConfig {
     pub enabled: Option<bool>, // Override global enabled setting
     pub auto_checkpoint_interval: Option<u32>, // Override global interval
@@ -275,6 +289,7 @@ pub struct ProjectCheckpointConfig {
     pub retention: Option<RetentionPolicy>, // Override global retention
     pub git_integration: Option<GitIntegrationConfig>, // Override git settings
     pub exclude_patterns: Vec<String>, // Files/dirs to exclude from checkpoints
+    #[serde(default)]
     pub include_patterns: Vec<String>, // Files/dirs to specifically include
     pub custom_tags: Vec<String>, // Default tags for this project
     pub description_template: Option<String>, // Template for checkpoint descriptions
@@ -296,12 +311,7 @@ impl Default for ProjectCheckpointConfig {
                 "*.log".to_string(),
                 "*.tmp".to_string(),
             ],
-            include_patterns: vec![
-                "src/**".to_string(),
-                "*.rs".to_string(),
-                "*.toml".to_string(),
-                "*.md".to_string(),
-            ],
+            include_patterns: Vec::new(),
             custom_tags: Vec::new(),
             description_template: None,
         }
@@ -371,7 +381,7 @@ fn default_commit_message_template() -> String {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(default)]
 pub struct PerformanceConfig {
-    pub compression_level: u32,           // Compression level (0-9)
+    pub compression_level: u32,           // zstd level (0 = zstd default, max 19)
     pub enable_lazy_loading: bool,        // Load checkpoints on demand
     pub enable_async_operations: bool,    // Use async I/O where possible
     pub max_concurrent_operations: u32,   // Maximum concurrent operations
@@ -399,7 +409,9 @@ impl Default for PerformanceConfig {
 #[serde(default)]
 pub struct SecurityConfig {
     pub enable_encryption: bool,              // Encrypt checkpoint data
-    pub encryption_key_derivation: String,    // Key derivation method
+    pub encryption_key_derivation: String,    // Key derivation: "argon2id" or "pbkdf2" (passphrase), or "raw" (32-byte key)
+    pub encryption_key_env: String,           // Env var holding the passphrase or key
+    pub encryption_key_file: Option<PathBuf>, // File holding the passphrase or key
     pub filter_sensitive_env_vars: bool,      // Filter sensitive environment variables
     pub sensitive_env_patterns: Vec<String>,  // Patterns for sensitive env vars
     pub file_permission_strict: bool,         // Strict file permission checks
@@ -412,7 +424,9 @@ impl Default for SecurityConfig {
     fn default() -> Self {
         Self {
             enable_encryption: false,
-            encryption_key_derivation: "pbkdf2".to_string(),
+            encryption_key_derivation: "argon2id".to_string(),
+            encryption_key_env: "ABK_CHECKPOINT_KEY".to_string(),
+            encryption_key_file: None,
             filter_sensitive_env_vars: true,
             sensitive_env_patterns: vec![
                 "PASSWORD".to_string(),
@@ -551,8 +565,90 @@ fn get_default_storage_location() -> PathBuf {
     }
 }
 
-/// Merge project config with global config
 impl ProjectCheckpointConfig {
+    /// Load the [`PROJECT_CONFIG_FILE`] of the project containing `project_path`, if any
+    ///
+    /// Searches `project_path` and its parents up to the enclosing git
+    /// repository's root, so a run started in a subdirectory still finds the
+    /// repository's config. Outside a git repository only `project_path` is checked.
+    pub fn discover(project_path: &Path) -> super::errors::CheckpointResult<Option<Self>> {
+        let git_root = project_path.ancestors().find(|dir| dir.join(".git").exists());
+        for dir in project_path.ancestors() {
+            let path = dir.join(PROJECT_CONFIG_FILE);
+            if path.is_file() {
+                return Self::load(&path).map(Some);
+            }
+            if git_root.is_none() || git_root == Some(dir) {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    /// Load and validate a project configuration file
+    pub fn load(path: &Path) -> super::errors::CheckpointResult<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            super::errors::CheckpointError::config(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        let config: Self = toml::from_str(&content).map_err(|e| {
+            super::errors::CheckpointError::config(format!(
+                "Failed to parse {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        let errors = ProjectConfigManager::validate_project_config(&config);
+        if !errors.is_empty() {
+            return Err(super::errors::CheckpointError::config(format!(
+                "Invalid {}: {}",
+                path.display(),
+                errors.join("; ")
+            )));
+        }
+        Ok(config)
+    }
+
+    /// Render `description_template` for a checkpoint
+    ///
+    /// Supports `{checkpoint_id}`, `{session_id}` (of the session saving
+    /// the checkpoint), `{workflow_step}`, `{iteration}` and `{description}`,
+    /// the description the checkpoint was created with.
+    pub fn render_description(
+        &self,
+        metadata: &super::models::CheckpointMetadata,
+        session_id: &str,
+    ) -> Option<String> {
+        let template = self.description_template.as_ref()?;
+        Some(
+            template
+                .replace("{checkpoint_id}", &metadata.checkpoint_id)
+                .replace("{session_id}", session_id)
+                .replace("{workflow_step}", &metadata.workflow_step.to_string())
+                .replace("{iteration}", &metadata.iteration.to_string())
+                .replace("{description}", metadata.description.as_deref().unwrap_or_default()),
+        )
+    }
+
+    /// Merge a config read from the repository being worked on with the global config
+    ///
+    /// Like [`merge_with_global`](Self::merge_with_global), but the file
+    /// comes from a possibly untrusted clone: `shadow_repo_location` and
+    /// `auto_commit_before_checkpoint` keep their global values, so it can't
+    /// make the agent create repositories elsewhere on the host or commit to
+    /// the user's own repository.
+    pub fn merge_project_file_with_global(&self, global: &GlobalCheckpointConfig) -> GlobalCheckpointConfig {
+        let mut config = self.merge_with_global(global);
+        config.git_integration.shadow_repo_location = global.git_integration.shadow_repo_location.clone();
+        config.git_integration.auto_commit_before_checkpoint = global.git_integration.auto_commit_before_checkpoint;
+        config
+    }
+
+    /// Merge project config with global config
     pub fn merge_with_global(&self, global: &GlobalCheckpointConfig) -> GlobalCheckpointConfig {
         GlobalCheckpointConfig {
             enabled: self.enabled.unwrap_or(global.enabled),
@@ -0,0 +1,754 @@
+//! Encryption at rest for checkpoint data
+//!
+//! When `security.enable_encryption` is set, checkpoint payloads (agent state,
+//! conversation and file snapshots) and every line of `events.jsonl` are
+//! sealed with AES-256-GCM. Session metadata and the checkpoint index stay
+//! readable so sessions can be listed without the key.
+//!
+//! The key comes from the env var named by `security.encryption_key_env` or
+//! from `security.encryption_key_file`. With `encryption_key_derivation =
+//! "argon2id"` (the default) it is a passphrase stretched with Argon2id and a
+//! random salt; `"pbkdf2"` uses PBKDF2-HMAC-SHA256 instead, and `"raw"` takes
+//! a 32-byte key written as hex or base64.
+//!
+//! Every sealed blob starts with a header that is authenticated along with
+//! the ciphertext and a context naming where the data belongs (see
+//! [`payload_context`], [`events_context`] and the content hash for file
+//! snapshot blobs), so data moved to another file, checkpoint or session
+//! fails to open as [`CheckpointError::TamperedData`]:
+//!
+//! ```text
+//! "ABKE" | version | kdf | key fingerprint (8) | [kdf params | salt (16)] | nonce (12)
+//! ```
+//!
+//! PBKDF2 params are the iteration count (4 bytes); Argon2id params are
+//! memory in KiB, passes and lanes (4 bytes each).
+//!
+//! The fingerprint lets readers tell a wrong key
+//! ([`CheckpointError::WrongEncryptionKey`]) from modified data
+//! ([`CheckpointError::TamperedData`]). Once a cipher is configured, unsealed
+//! data is rejected as tampered too, so nobody can swap a sealed file for a
+//! plain one; use [`CheckpointStorageManager::reencrypt_all`] to seal existing
+//! sessions after turning encryption on, or to rotate the key.
+//!
+//! [`CheckpointStorageManager::reencrypt_all`]: super::CheckpointStorageManager::reencrypt_all
+
+use super::config::SecurityConfig;
+use super::{CheckpointError, CheckpointResult};
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Env var read for the previous key by [`CheckpointCipher::previous_from_config`]
+pub const OLD_KEY_ENV: &str = "ABK_CHECKPOINT_OLD_KEY";
+
+/// Prefix of encrypted lines in `events.jsonl`
+pub const ENCRYPTED_LINE_PREFIX: &str = "enc:";
+
+const MAGIC: [u8; 4] = *b"ABKE";
+const FORMAT_VERSION: u8 = 1;
+const KDF_RAW: u8 = 0;
+const KDF_PBKDF2: u8 = 1;
+const KDF_ARGON2ID: u8 = 2;
+const FINGERPRINT_LEN: usize = 8;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
+/// PBKDF2-HMAC-SHA256 iterations for new passphrase-derived keys
+pub const PBKDF2_ITERATIONS: u32 = 600_000;
+
+/// Argon2id memory cost in KiB for new passphrase-derived keys
+pub const ARGON2_MEMORY_KIB: u32 = 19 * 1024;
+
+/// Argon2id passes for new passphrase-derived keys
+pub const ARGON2_ITERATIONS: u32 = 2;
+
+/// Argon2id lanes for new passphrase-derived keys
+pub const ARGON2_PARALLELISM: u32 = 1;
+
+/// Largest Argon2id memory cost accepted from a header (1 GiB), so corrupted
+/// data cannot make a reader allocate without bound
+const ARGON2_MAX_MEMORY_KIB: u32 = 1024 * 1024;
+
+/// Largest Argon2id pass count accepted from a header
+const ARGON2_MAX_ITERATIONS: u32 = 64;
+
+/// Largest Argon2id lane count accepted from a header
+const ARGON2_MAX_PARALLELISM: u32 = 64;
+
+/// Largest PBKDF2 iteration count accepted from a header, so corrupted data
+/// cannot keep a reader busy for minutes
+const PBKDF2_MAX_ITERATIONS: u32 = 10_000_000;
+
+/// Whether `data` was sealed by [`CheckpointCipher`]
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+/// Context that a checkpoint payload stored as `file_name` in the session
+/// directory is sealed with
+pub fn payload_context(session_id: &str, file_name: &str) -> String {
+    format!("{}/{}", session_id, file_name)
+}
+
+/// Context that the `events.jsonl` lines of a session are sealed with
+pub fn events_context(session_id: &str) -> String {
+    payload_context(session_id, "events.jsonl")
+}
+
+/// Return the plaintext of `data`, unsealing it when it is encrypted
+///
+/// `context` must match the one the data was sealed with. Fails with [`CheckpointError::EncryptionKeyMissing`] when `data` is
+/// encrypted and no cipher is configured, and with [`CheckpointError::TamperedData`]
+/// when a cipher is configured but `data` is not sealed.
+pub fn open<'a>(data: &'a [u8], cipher: Option<&CheckpointCipher>, context: &str) -> CheckpointResult<Cow<'a, [u8]>> {
+    if cipher.is_some() && !is_encrypted(data) {
+        return Err(unsealed(context));
+    }
+    open_allowing_plaintext(data, cipher, context)
+}
+
+/// Like [`open`], but passes unsealed data through even when a cipher is set
+///
+/// Only for migrating data written before encryption was enabled; every
+/// other reader must use [`open`].
+pub fn open_allowing_plaintext<'a>(
+    data: &'a [u8],
+    cipher: Option<&CheckpointCipher>,
+    context: &str,
+) -> CheckpointResult<Cow<'a, [u8]>> {
+    if !is_encrypted(data) {
+        return Ok(Cow::Borrowed(data));
+    }
+    match cipher {
+        Some(cipher) => cipher.decrypt(data, context).map(Cow::Owned),
+        None => Err(CheckpointError::EncryptionKeyMissing {
+            message: "Checkpoint data is encrypted but no encryption key is configured".to_string(),
+        }),
+    }
+}
+
+/// Return the plain JSON of an `events.jsonl` line
+///
+/// Like [`open`], plain lines are rejected when a cipher is configured.
+pub fn open_line<'a>(line: &'a str, cipher: Option<&CheckpointCipher>, context: &str) -> CheckpointResult<Cow<'a, str>> {
+    if cipher.is_some() && !line.starts_with(ENCRYPTED_LINE_PREFIX) {
+        return Err(unsealed(context));
+    }
+    open_line_allowing_plaintext(line, cipher, context)
+}
+
+/// Like [`open_line`], but passes plain lines through even when a cipher is
+/// set; only for migrating existing data
+pub fn open_line_allowing_plaintext<'a>(
+    line: &'a str,
+    cipher: Option<&CheckpointCipher>,
+    context: &str,
+) -> CheckpointResult<Cow<'a, str>> {
+    let Some(encoded) = line.strip_prefix(ENCRYPTED_LINE_PREFIX) else {
+        return Ok(Cow::Borrowed(line));
+    };
+    let sealed = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| CheckpointError::corrupted(format!("Invalid encrypted event line: {}", e)))?;
+    let plain = open_allowing_plaintext(&sealed, cipher, context)?;
+    String::from_utf8(plain.into_owned())
+        .map(Cow::Owned)
+        .map_err(|e| CheckpointError::corrupted(format!("Invalid encrypted event line: {}", e)))
+}
+
+/// Error for plain data found where encryption requires sealed data
+fn unsealed(context: &str) -> CheckpointError {
+    CheckpointError::TamperedData {
+        message: format!(
+            "{} is not encrypted although encryption is enabled; run `checkpoints --reencrypt` to seal data written before it was turned on",
+            context
+        ),
+    }
+}
+
+/// Key material and parameters used to seal new data
+struct SealingKey {
+    key: [u8; KEY_LEN],
+    /// Key derivation and salt; `None` for raw keys
+    kdf: Option<KdfParams>,
+}
+
+/// Passphrase key derivation function and its cost parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kdf {
+    /// PBKDF2-HMAC-SHA256
+    Pbkdf2 {
+        /// HMAC iterations
+        iterations: u32,
+    },
+    /// Argon2id (version 0x13)
+    Argon2id {
+        /// Memory cost in KiB
+        memory_kib: u32,
+        /// Number of passes
+        iterations: u32,
+        /// Number of lanes
+        parallelism: u32,
+    },
+}
+
+impl Kdf {
+    /// Argon2id with the default costs
+    pub fn argon2id() -> Self {
+        Kdf::Argon2id {
+            memory_kib: ARGON2_MEMORY_KIB,
+            iterations: ARGON2_ITERATIONS,
+            parallelism: ARGON2_PARALLELISM,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Kdf::Pbkdf2 { .. } => "pbkdf2",
+            Kdf::Argon2id { .. } => "argon2id",
+        }
+    }
+}
+
+/// Key derivation and salt a key was derived with
+type KdfParams = (Kdf, [u8; SALT_LEN]);
+
+enum Secret {
+    Key([u8; KEY_LEN]),
+    Passphrase(Vec<u8>),
+}
+
+/// Authenticated encryption of checkpoint data (AES-256-GCM)
+pub struct CheckpointCipher {
+    secret: Secret,
+    sealing: SealingKey,
+    /// Keys derived for salts found in existing data
+    derived: Mutex<HashMap<KdfParams, [u8; KEY_LEN]>>,
+    rng: SystemRandom,
+}
+
+impl std::fmt::Debug for CheckpointCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CheckpointCipher")
+            .field("kdf", &self.sealing.kdf.map_or("raw", |(kdf, _)| kdf.name()))
+            .finish_non_exhaustive()
+    }
+}
+
+impl CheckpointCipher {
+    /// Use a 32-byte key as is
+    pub fn from_key(key: [u8; KEY_LEN]) -> Self {
+        Self {
+            secret: Secret::Key(key),
+            sealing: SealingKey { key, kdf: None },
+            derived: Mutex::new(HashMap::new()),
+            rng: SystemRandom::new(),
+        }
+    }
+
+    /// Derive the key from a passphrase with Argon2id
+    pub fn from_passphrase(passphrase: &str) -> CheckpointResult<Self> {
+        Self::from_passphrase_with_kdf(passphrase, Kdf::argon2id())
+    }
+
+    /// Derive the key from a passphrase with PBKDF2-HMAC-SHA256 and a custom
+    /// iteration count
+    pub fn from_passphrase_with_iterations(passphrase: &str, iterations: u32) -> CheckpointResult<Self> {
+        Self::from_passphrase_with_kdf(passphrase, Kdf::Pbkdf2 { iterations })
+    }
+
+    /// Derive the key from a passphrase with `kdf`
+    pub fn from_passphrase_with_kdf(passphrase: &str, kdf: Kdf) -> CheckpointResult<Self> {
+        if passphrase.is_empty() {
+            return Err(CheckpointError::config("Encryption passphrase is empty"));
+        }
+        let rng = SystemRandom::new();
+        let mut salt = [0u8; SALT_LEN];
+        rng.fill(&mut salt)
+            .map_err(|_| CheckpointError::storage("Failed to generate encryption salt"))?;
+        let key = derive_key(passphrase.as_bytes(), kdf, &salt)?;
+        let derived = HashMap::from([((kdf, salt), key)]);
+        Ok(Self {
+            secret: Secret::Passphrase(passphrase.as_bytes().to_vec()),
+            sealing: SealingKey {
+                key,
+                kdf: Some((kdf, salt)),
+            },
+            derived: Mutex::new(derived),
+            rng,
+        })
+    }
+
+    /// Build a cipher from key material interpreted per `derivation`
+    ///
+    /// `"argon2id"` and `"pbkdf2"` treat the material as a passphrase, `"raw"`
+    /// as a 32-byte key in hex or base64. Surrounding whitespace is ignored.
+    pub fn from_key_material(material: &str, derivation: &str) -> CheckpointResult<Self> {
+        let material = material.trim();
+        match derivation.to_ascii_lowercase().as_str() {
+            "argon2id" => Self::from_passphrase(material),
+            "pbkdf2" => Self::from_passphrase_with_iterations(material, PBKDF2_ITERATIONS),
+            "raw" => parse_raw_key(material).map(Self::from_key),
+            other => Err(CheckpointError::config(format!(
+                "Unsupported encryption_key_derivation '{}' (expected \"argon2id\", \"pbkdf2\" or \"raw\")",
+                other
+            ))),
+        }
+    }
+
+    /// Build the cipher configured in `security`, or `None` when encryption is off
+    ///
+    /// The key is read from the `encryption_key_env` variable, falling back to
+    /// `encryption_key_file`.
+    pub fn from_config(security: &SecurityConfig) -> CheckpointResult<Option<Self>> {
+        if !security.enable_encryption {
+            return Ok(None);
+        }
+        let material = read_key_material(&security.encryption_key_env, security.encryption_key_file.as_deref())?
+            .ok_or_else(|| CheckpointError::EncryptionKeyMissing {
+                message: format!(
+                    "Checkpoint encryption is enabled but neither ${} nor encryption_key_file is set",
+                    security.encryption_key_env
+                ),
+            })?;
+        Self::from_key_material(&material, &security.encryption_key_derivation).map(Some)
+    }
+
+    /// Build the cipher for the key being rotated out
+    ///
+    /// Reads `key_file` when given, otherwise [`OLD_KEY_ENV`]. Returns `None`
+    /// when neither is set (the existing data is unencrypted).
+    pub fn previous_from_config(security: &SecurityConfig, key_file: Option<&Path>) -> CheckpointResult<Option<Self>> {
+        match read_key_material(OLD_KEY_ENV, key_file)? {
+            Some(material) => Self::from_key_material(&material, &security.encryption_key_derivation).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Seal `plaintext` for `context` with a fresh random nonce
+    pub fn encrypt(&self, plaintext: &[u8], context: &str) -> CheckpointResult<Vec<u8>> {
+        let mut out = header(&self.sealing);
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| CheckpointError::storage("Failed to generate encryption nonce"))?;
+
+        let mut in_out = plaintext.to_vec();
+        aead_key(&self.sealing.key)
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(aad(&out, context)), &mut in_out)
+            .map_err(|_| CheckpointError::storage("Failed to encrypt checkpoint data"))?;
+
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&in_out);
+        Ok(out)
+    }
+
+    /// Unseal data produced by [`encrypt`](Self::encrypt) for the same `context`
+    pub fn decrypt(&self, data: &[u8], context: &str) -> CheckpointResult<Vec<u8>> {
+        let header = parse_header(data)?;
+        let key = self.key_for(&header)?;
+        let (nonce, ciphertext) = data[header.len..].split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| CheckpointError::corrupted("Invalid encryption nonce"))?;
+
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = aead_key(&key)
+            .open_in_place(nonce, Aad::from(aad(&data[..header.len], context)), &mut in_out)
+            .map_err(|_| CheckpointError::TamperedData {
+                message: format!("authentication tag does not match for {}", context),
+            })?;
+        Ok(plaintext.to_vec())
+    }
+
+    /// Whether `data` is sealed with this cipher's key
+    pub fn can_decrypt(&self, data: &[u8]) -> bool {
+        parse_header(data)
+            .and_then(|header| self.key_for(&header))
+            .is_ok()
+    }
+
+    /// Seal an `events.jsonl` line for `context`
+    pub fn encrypt_line(&self, line: &str, context: &str) -> CheckpointResult<String> {
+        let sealed = self.encrypt(line.as_bytes(), context)?;
+        Ok(format!(
+            "{}{}",
+            ENCRYPTED_LINE_PREFIX,
+            base64::engine::general_purpose::STANDARD.encode(sealed)
+        ))
+    }
+
+    /// Whether an `events.jsonl` line is sealed with this cipher's key
+    pub fn can_decrypt_line(&self, line: &str) -> bool {
+        line.strip_prefix(ENCRYPTED_LINE_PREFIX)
+            .and_then(|encoded| base64::engine::general_purpose::STANDARD.decode(encoded.trim()).ok())
+            .is_some_and(|sealed| self.can_decrypt(&sealed))
+    }
+
+    /// Key for the parameters in `header`, checked against its fingerprint
+    fn key_for(&self, header: &Header) -> CheckpointResult<[u8; KEY_LEN]> {
+        let key = match (header.kdf, &self.secret) {
+            (None, Secret::Key(key)) => *key,
+            (Some((kdf, salt)), Secret::Passphrase(passphrase)) => {
+                let mut derived = self.derived.lock().unwrap_or_else(|e| e.into_inner());
+                match derived.get(&(kdf, salt)) {
+                    Some(key) => *key,
+                    None => {
+                        let key = derive_key(passphrase, kdf, &salt)?;
+                        derived.insert((kdf, salt), key);
+                        key
+                    }
+                }
+            }
+            (None, Secret::Passphrase(_)) => {
+                return Err(CheckpointError::WrongEncryptionKey {
+                    message: "data was encrypted with a raw key, but a passphrase is configured".to_string(),
+                })
+            }
+            (Some(_), Secret::Key(_)) => {
+                return Err(CheckpointError::WrongEncryptionKey {
+                    message: "data was encrypted with a passphrase, but a raw key is configured".to_string(),
+                })
+            }
+        };
+        if fingerprint(&key) != header.fingerprint {
+            return Err(CheckpointError::WrongEncryptionKey {
+                message: "data was encrypted with a different key".to_string(),
+            });
+        }
+        Ok(key)
+    }
+}
+
+/// Parsed blob header
+struct Header {
+    kdf: Option<KdfParams>,
+    fingerprint: [u8; FINGERPRINT_LEN],
+    /// Header length in bytes (the nonce follows)
+    len: usize,
+}
+
+fn header(sealing: &SealingKey) -> Vec<u8> {
+    let mut out = Vec::with_capacity(64);
+    out.extend_from_slice(&MAGIC);
+    out.push(FORMAT_VERSION);
+    out.push(match sealing.kdf {
+        None => KDF_RAW,
+        Some((Kdf::Pbkdf2 { .. }, _)) => KDF_PBKDF2,
+        Some((Kdf::Argon2id { .. }, _)) => KDF_ARGON2ID,
+    });
+    out.extend_from_slice(&fingerprint(&sealing.key));
+    match sealing.kdf {
+        None => {}
+        Some((Kdf::Pbkdf2 { iterations }, salt)) => {
+            out.extend_from_slice(&iterations.to_be_bytes());
+            out.extend_from_slice(&salt);
+        }
+        Some((Kdf::Argon2id { memory_kib, iterations, parallelism }, salt)) => {
+            for param in [memory_kib, iterations, parallelism] {
+                out.extend_from_slice(&param.to_be_bytes());
+            }
+            out.extend_from_slice(&salt);
+        }
+    }
+    out
+}
+
+fn parse_header(data: &[u8]) -> CheckpointResult<Header> {
+    let truncated = || CheckpointError::TamperedData {
+        message: "encrypted data is truncated".to_string(),
+    };
+    if !is_encrypted(data) {
+        return Err(CheckpointError::corrupted("Data is not encrypted"));
+    }
+    let fixed = MAGIC.len() + 2 + FINGERPRINT_LEN;
+    if data.len() < fixed {
+        return Err(truncated());
+    }
+    if data[4] != FORMAT_VERSION {
+        return Err(CheckpointError::VersionMismatch {
+            expected: FORMAT_VERSION.to_string(),
+            found: data[4].to_string(),
+        });
+    }
+    let mut fingerprint = [0u8; FINGERPRINT_LEN];
+    fingerprint.copy_from_slice(&data[6..fixed]);
+
+    let param_count = match data[5] {
+        KDF_RAW => 0,
+        KDF_PBKDF2 => 1,
+        KDF_ARGON2ID => 3,
+        other => {
+            return Err(CheckpointError::corrupted(format!("Unknown key derivation id {}", other)))
+        }
+    };
+    let (kdf, len) = if param_count == 0 {
+        (None, fixed)
+    } else {
+        let salt_start = fixed + 4 * param_count;
+        let len = salt_start + SALT_LEN;
+        if data.len() < len {
+            return Err(truncated());
+        }
+        let param = |i: usize| u32::from_be_bytes(data[fixed + 4 * i..fixed + 4 * i + 4].try_into().expect("4 bytes"));
+        let kdf = match data[5] {
+            KDF_PBKDF2 => Kdf::Pbkdf2 { iterations: param(0) },
+            _ => Kdf::Argon2id {
+                memory_kib: param(0),
+                iterations: param(1),
+                parallelism: param(2),
+            },
+        };
+        check_kdf_limits(kdf)?;
+        let mut salt = [0u8; SALT_LEN];
+        salt.copy_from_slice(&data[salt_start..len]);
+        (Some((kdf, salt)), len)
+    };
+    if data.len() < len + NONCE_LEN + AES_256_GCM.tag_len() {
+        return Err(truncated());
+    }
+    Ok(Header { kdf, fingerprint, len })
+}
+
+/// Additional authenticated data: the header followed by the context
+fn aad(header: &[u8], context: &str) -> Vec<u8> {
+    [header, context.as_bytes()].concat()
+}
+
+fn aead_key(key: &[u8; KEY_LEN]) -> LessSafeKey {
+    LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key).expect("32-byte AES-256 key"))
+}
+
+/// Reject key derivation costs above the `*_MAX_*` limits
+fn check_kdf_limits(kdf: Kdf) -> CheckpointResult<()> {
+    let exceeded = |what: &str, value: u32, max: u32| {
+        Err(CheckpointError::corrupted(format!("{} {} exceeds the limit of {}", what, value, max)))
+    };
+    match kdf {
+        Kdf::Pbkdf2 { iterations } if iterations > PBKDF2_MAX_ITERATIONS => {
+            exceeded("PBKDF2 iteration count", iterations, PBKDF2_MAX_ITERATIONS)
+        }
+        Kdf::Argon2id { memory_kib, .. } if memory_kib > ARGON2_MAX_MEMORY_KIB => {
+            exceeded("Argon2id memory cost (KiB)", memory_kib, ARGON2_MAX_MEMORY_KIB)
+        }
+        Kdf::Argon2id { iterations, .. } if iterations > ARGON2_MAX_ITERATIONS => {
+            exceeded("Argon2id pass count", iterations, ARGON2_MAX_ITERATIONS)
+        }
+        Kdf::Argon2id { parallelism, .. } if parallelism > ARGON2_MAX_PARALLELISM => {
+            exceeded("Argon2id lane count", parallelism, ARGON2_MAX_PARALLELISM)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn derive_key(passphrase: &[u8], kdf: Kdf, salt: &[u8; SALT_LEN]) -> CheckpointResult<[u8; KEY_LEN]> {
+    check_kdf_limits(kdf)?;
+    let mut key = [0u8; KEY_LEN];
+    match kdf {
+        Kdf::Pbkdf2 { iterations } => {
+            let iterations = NonZeroU32::new(iterations)
+                .ok_or_else(|| CheckpointError::corrupted("PBKDF2 iteration count is zero"))?;
+            ring::pbkdf2::derive(ring::pbkdf2::PBKDF2_HMAC_SHA256, iterations, salt, passphrase, &mut key);
+        }
+        Kdf::Argon2id { memory_kib, iterations, parallelism } => {
+            let params = argon2::Params::new(memory_kib, iterations, parallelism, Some(KEY_LEN))
+                .map_err(|e| CheckpointError::corrupted(format!("Invalid Argon2id parameters: {}", e)))?;
+            argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+                .hash_password_into(passphrase, salt, &mut key)
+                .map_err(|e| CheckpointError::storage(format!("Argon2id key derivation failed: {}", e)))?;
+        }
+    }
+    Ok(key)
+}
+
+fn fingerprint(key: &[u8; KEY_LEN]) -> [u8; FINGERPRINT_LEN] {
+    let digest = Sha256::new()
+        .chain_update(b"abk-checkpoint-key-check\0")
+        .chain_update(key)
+        .finalize();
+    let mut out = [0u8; FINGERPRINT_LEN];
+    out.copy_from_slice(&digest[..FINGERPRINT_LEN]);
+    out
+}
+
+fn parse_raw_key(material: &str) -> CheckpointResult<[u8; KEY_LEN]> {
+    let bytes = if material.len() == KEY_LEN * 2 && material.bytes().all(|b| b.is_ascii_hexdigit()) {
+        (0..KEY_LEN)
+            .map(|i| u8::from_str_radix(&material[2 * i..2 * i + 2], 16).expect("hex digits"))
+            .collect()
+    } else {
+        base64::engine::general_purpose::STANDARD
+            .decode(material)
+            .map_err(|_| CheckpointError::config("Raw encryption key must be 32 bytes in hex or base64"))?
+    };
+    bytes
+        .try_into()
+        .map_err(|_| CheckpointError::config("Raw encryption key must be 32 bytes in hex or base64"))
+}
+
+fn read_key_material(env_var: &str, key_file: Option<&Path>) -> CheckpointResult<Option<String>> {
+    if let Ok(value) = std::env::var(env_var) {
+        if !value.trim().is_empty() {
+            return Ok(Some(value));
+        }
+    }
+    match key_file {
+        Some(path) => std::fs::read_to_string(path).map(Some).map_err(|e| {
+            CheckpointError::config(format!("Failed to read encryption key file {}: {}", path.display(), e))
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Counts of data rewritten by a re-encryption pass
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReencryptReport {
+    /// Sessions visited
+    pub sessions: u32,
+    /// Payload files rewritten
+    pub files: u32,
+    /// `events.jsonl` lines rewritten
+    pub events: u32,
+    /// File snapshot blobs rewritten
+    pub blobs: u32,
+    /// Payloads rewritten in the remote backend
+    pub remote_files: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CTX: &str = "session-1/ckpt-1_conversation.json";
+
+    #[test]
+    fn test_wrong_key_and_tampering_are_reported() {
+        let cipher = CheckpointCipher::from_key([7u8; KEY_LEN]);
+        let sealed = cipher.encrypt(b"{\"secret\":\"hunter2\"}", CTX).unwrap();
+        assert!(is_encrypted(&sealed));
+        assert_eq!(cipher.decrypt(&sealed, CTX).unwrap(), b"{\"secret\":\"hunter2\"}");
+        assert_eq!(&*open(b"{}", None, CTX).unwrap(), b"{}");
+        assert!(matches!(open(&sealed, None, CTX), Err(CheckpointError::EncryptionKeyMissing { .. })));
+
+        let other = CheckpointCipher::from_key([8u8; KEY_LEN]);
+        assert!(!other.can_decrypt(&sealed));
+        assert!(matches!(other.decrypt(&sealed, CTX), Err(CheckpointError::WrongEncryptionKey { .. })));
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(matches!(cipher.decrypt(&tampered, CTX), Err(CheckpointError::TamperedData { .. })));
+        assert!(matches!(
+            cipher.decrypt(&sealed[..sealed.len() - 20], CTX),
+            Err(CheckpointError::TamperedData { .. })
+        ));
+    }
+
+    #[test]
+    fn test_data_is_bound_to_its_context() {
+        let cipher = CheckpointCipher::from_key([7u8; KEY_LEN]);
+        let sealed = cipher.encrypt(b"{}", &payload_context("session-1", "ckpt-1_conversation.json")).unwrap();
+        for context in [
+            payload_context("session-1", "ckpt-2_conversation.json"),
+            payload_context("session-2", "ckpt-1_conversation.json"),
+            events_context("session-1"),
+        ] {
+            assert!(matches!(cipher.decrypt(&sealed, &context), Err(CheckpointError::TamperedData { .. })));
+        }
+
+        let line = cipher.encrypt_line("{}", &events_context("session-1")).unwrap();
+        assert!(matches!(
+            open_line(&line, Some(&cipher), &events_context("session-2")),
+            Err(CheckpointError::TamperedData { .. })
+        ));
+    }
+
+    #[test]
+    fn test_passphrase_keys_and_event_lines() {
+        let writer = CheckpointCipher::from_passphrase_with_iterations("correct horse", 1_000).unwrap();
+        let line = writer.encrypt_line("{\"sequence\":1}", CTX).unwrap();
+        assert!(line.starts_with(ENCRYPTED_LINE_PREFIX));
+
+        // A later process derives a new salt but can still read old lines
+        let reader = CheckpointCipher::from_passphrase_with_iterations("correct horse", 1_000).unwrap();
+        assert_eq!(open_line(&line, Some(&reader), CTX).unwrap(), "{\"sequence\":1}");
+        assert_eq!(open_line("{\"plain\":true}", None, CTX).unwrap(), "{\"plain\":true}");
+
+        let wrong = CheckpointCipher::from_passphrase_with_iterations("battery staple", 1_000).unwrap();
+        assert!(matches!(open_line(&line, Some(&wrong), CTX), Err(CheckpointError::WrongEncryptionKey { .. })));
+    }
+
+    #[test]
+    fn test_unsealed_data_is_rejected_when_encryption_is_enabled() {
+        let cipher = CheckpointCipher::from_key([7u8; KEY_LEN]);
+
+        // Replacing a sealed payload or event line with plaintext is a downgrade
+        assert!(matches!(open(b"{\"forged\":true}", Some(&cipher), CTX), Err(CheckpointError::TamperedData { .. })));
+        assert!(matches!(
+            open_line("{\"forged\":true}", Some(&cipher), CTX),
+            Err(CheckpointError::TamperedData { .. })
+        ));
+
+        // Only the migration path accepts it
+        assert_eq!(&*open_allowing_plaintext(b"{}", Some(&cipher), CTX).unwrap(), b"{}");
+        assert_eq!(open_line_allowing_plaintext("{}", Some(&cipher), CTX).unwrap(), "{}");
+        let sealed = cipher.encrypt(b"{}", CTX).unwrap();
+        assert_eq!(&*open_allowing_plaintext(&sealed, Some(&cipher), CTX).unwrap(), b"{}");
+    }
+
+    #[test]
+    fn test_key_material_parsing() {
+        let hex = "00".repeat(31) + "ff";
+        assert!(CheckpointCipher::from_key_material(&hex, "raw").is_ok());
+        let b64 = base64::engine::general_purpose::STANDARD.encode([1u8; KEY_LEN]);
+        assert!(CheckpointCipher::from_key_material(&format!("{}\n", b64), "RAW").is_ok());
+        assert!(CheckpointCipher::from_key_material("too short", "raw").is_err());
+        assert!(CheckpointCipher::from_key_material("secret", "scrypt").is_err());
+    }
+
+    #[test]
+    fn test_argon2id_is_the_default_kdf() {
+        let cipher = CheckpointCipher::from_key_material("correct horse", "argon2id").unwrap();
+        let sealed = cipher.encrypt(b"payload", CTX).unwrap();
+        assert_eq!(sealed[5], KDF_ARGON2ID);
+        assert_eq!(format!("{:?}", cipher), "CheckpointCipher { kdf: \"argon2id\", .. }");
+
+        // Costs travel in the header, so cheaper test parameters still round-trip
+        let kdf = Kdf::Argon2id { memory_kib: 64, iterations: 1, parallelism: 1 };
+        let writer = CheckpointCipher::from_passphrase_with_kdf("correct horse", kdf).unwrap();
+        let sealed = writer.encrypt(b"payload", CTX).unwrap();
+        let reader = CheckpointCipher::from_passphrase_with_iterations("correct horse", 1_000).unwrap();
+        assert_eq!(reader.decrypt(&sealed, CTX).unwrap(), b"payload");
+        let wrong = CheckpointCipher::from_passphrase_with_kdf("battery staple", kdf).unwrap();
+        assert!(matches!(wrong.decrypt(&sealed, CTX), Err(CheckpointError::WrongEncryptionKey { .. })));
+
+        // PBKDF2 data stays readable after switching to Argon2id
+        assert_eq!(writer.decrypt(&reader.encrypt(b"old", CTX).unwrap(), CTX).unwrap(), b"old");
+    }
+
+    #[test]
+    fn test_headers_with_excessive_kdf_costs_are_rejected() {
+        let cipher = CheckpointCipher::from_passphrase_with_iterations("correct horse", 1_000).unwrap();
+        let mut sealed = cipher.encrypt(b"payload", CTX).unwrap();
+        let params = MAGIC.len() + 2 + FINGERPRINT_LEN;
+        sealed[params..params + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(!cipher.can_decrypt(&sealed));
+        assert!(matches!(cipher.decrypt(&sealed, CTX), Err(CheckpointError::CorruptedData { .. })));
+
+        let kdf = Kdf::Argon2id { memory_kib: 64, iterations: 1, parallelism: 1 };
+        let cipher = CheckpointCipher::from_passphrase_with_kdf("correct horse", kdf).unwrap();
+        for (offset, value) in [(0, ARGON2_MAX_MEMORY_KIB + 1), (4, ARGON2_MAX_ITERATIONS + 1), (8, ARGON2_MAX_PARALLELISM + 1)] {
+            let mut sealed = cipher.encrypt(b"payload", CTX).unwrap();
+            sealed[params + offset..params + offset + 4].copy_from_slice(&value.to_be_bytes());
+            assert!(matches!(cipher.decrypt(&sealed, CTX), Err(CheckpointError::CorruptedData { .. })));
+        }
+
+        let excessive = Kdf::Pbkdf2 { iterations: PBKDF2_MAX_ITERATIONS + 1 };
+        assert!(CheckpointCipher::from_passphrase_with_kdf("correct horse", excessive).is_err());
+    }
+}
@@ -48,6 +48,15 @@ pub enum CheckpointError {
     #[error("Corrupted checkpoint data: {message}")]
     CorruptedData { message: String },
 
+    #[error("Encryption key required: {message}")]
+    EncryptionKeyMissing { message: String },
+
+    #[error("Wrong encryption key: {message}")]
+    WrongEncryptionKey { message: String },
+
+    #[error("Encrypted data failed authentication (tampered or truncated): {message}")]
+    TamperedData { message: String },
+
     #[error("Invalid checkpoint ID format: {checkpoint_id}")]
     InvalidCheckpointId { checkpoint_id: String },
 
@@ -145,6 +154,8 @@ impl CheckpointError {
                 | CheckpointError::InvalidCheckpointId { .. }
                 | CheckpointError::InvalidSessionId { .. }
                 | CheckpointError::Validation { .. }
+                | CheckpointError::EncryptionKeyMissing { .. }
+                | CheckpointError::WrongEncryptionKey { .. }
         )
     }
 
@@ -187,6 +198,12 @@ impl CheckpointError {
             CheckpointError::CorruptedData { message } => {
                 format!("Corrupted checkpoint data: {}. You may need to delete and recreate this checkpoint.", message)
             }
+            CheckpointError::EncryptionKeyMissing { message } => {
+                format!("{}. Set the checkpoint encryption key (checkpointing.security.encryption_key_env or encryption_key_file).", message)
+            }
+            CheckpointError::WrongEncryptionKey { message } => {
+                format!("{}. If the key was rotated, run '{} checkpoints --reencrypt' with the old key.", message, agent_name)
+            }
             _ => self.to_string(),
         }
     }
@@ -0,0 +1,1032 @@
+//! Workspace file snapshots and rollback
+//!
+//! Before the agent modifies a file, a [`WorkspaceTracker`] remembers the
+//! file's original content (its *baseline*). Files changed by shell commands
+//! are found afterwards with a
+//! [`ShellCommandWatch`](super::shadow_git::ShellCommandWatch), and a resumed
+//! session rebuilds its tracker from the checkpoint
+//! ([`WorkspaceTracker::from_snapshot`]). Each checkpoint then stores a
+//! content-addressed copy of every touched file in the project's
+//! [`BlobStore`], keyed by SHA-256, so unchanged content is stored once no
+//! matter how many checkpoints or sessions reference it:
+//!
+//! - `tracked_files` lists touched files that exist at checkpoint time,
+//!   with their `checksum`
+//! - `modified_files` records each touched file's change relative to its
+//!   baseline (`old_checksum`, `None` = the agent created it)
+//!
+//! [`plan_file_restore`] compares a target checkpoint against the latest
+//! one and the working tree, and produces a [`FileRestorePlan`] with a
+//! unified diff per file. Files first touched after the target are rolled
+//! back to their baseline. A restore only ever writes inside the working
+//! directory: paths that are absolute, contain `..` or lead out of it
+//! through a symlink are refused when the restore is planned.
+//!
+//! A project's `include_patterns`/`exclude_patterns` become a
+//! [`SnapshotFilter`]; files it rejects are left out of snapshots.
+//!
+//! With compression enabled, blobs are stored as zstd frames when that makes
+//! them smaller.
+//!
+//! With encryption enabled, blobs are sealed with the checkpoint cipher like
+//! every other payload; checksums always refer to the plain content.
+//!
+//! Blobs no file snapshot refers to any more, e.g. after pruning, are removed
+//! by [`BlobStore::collect_garbage`] once they are older than
+//! [`BLOB_GC_MIN_AGE`].
+
+use super::compression::{self, CompressionSettings};
+use super::config::ProjectCheckpointConfig;
+use super::encryption::{self, CheckpointCipher};
+use super::models::{
+    FileChange, FileChangeType, FilePermissions, FileSystemSnapshot, TrackedFile,
+};
+use super::{CheckpointError, CheckpointResult};
+use chrono::{DateTime, Utc};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// Files larger than this are not tracked.
+pub const MAX_TRACKED_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Unreferenced blobs younger than this are kept: another session may have
+/// stored them for a checkpoint it has not written yet.
+pub const BLOB_GC_MIN_AGE: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Context lines around each diff hunk.
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// Diffs above this many line comparisons fall back to a full replacement hunk.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Content-addressed file store: `<root>/<sha[..2]>/<sha>`.
+#[derive(Debug, Clone)]
+pub struct BlobStore {
+    root: PathBuf,
+    cipher: Option<Arc<CheckpointCipher>>,
+    compression: CompressionSettings,
+}
+
+impl BlobStore {
+    /// Create a store rooted at `root` (created on first write).
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            cipher: None,
+            compression: CompressionSettings::default(),
+        }
+    }
+
+    /// Compress new blobs as configured; compressed ones are always readable.
+    pub fn with_compression(mut self, compression: CompressionSettings) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Seal new blobs with `cipher` and open sealed ones with it. Blobs are
+    /// sealed for their checksum, so one can't stand in for another.
+    pub fn with_cipher(mut self, cipher: Option<Arc<CheckpointCipher>>) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    /// Path of the blob with `checksum`.
+    pub fn blob_path(&self, checksum: &str) -> PathBuf {
+        let prefix = checksum.get(..2).unwrap_or("00");
+        self.root.join(prefix).join(checksum)
+    }
+
+    /// Check whether a blob is stored.
+    pub fn contains(&self, checksum: &str) -> bool {
+        self.blob_path(checksum).is_file()
+    }
+
+    /// Store `data` and return its checksum. Existing blobs are not rewritten.
+    pub fn put(&self, data: &[u8]) -> CheckpointResult<String> {
+        let checksum = sha256_hex(data);
+        let path = self.blob_path(&checksum);
+        if !path.is_file() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            // Write to a temp file first so readers never see a partial blob
+            let tmp = path.with_extension(format!("tmp-{}", std::process::id()));
+            let mut stored = Cow::Borrowed(data);
+            if self.compression.enabled {
+                let compressed = compression::compress(data, self.compression.level)?;
+                if compressed.len() < data.len() {
+                    stored = Cow::Owned(compressed);
+                }
+            }
+            match self.cipher {
+                Some(ref cipher) => std::fs::write(&tmp, cipher.encrypt(&stored, &checksum)?)?,
+                None => std::fs::write(&tmp, &stored)?,
+            }
+            std::fs::rename(&tmp, &path)?;
+        }
+        Ok(checksum)
+    }
+
+    /// Read a blob, verifying its checksum.
+    pub fn get(&self, checksum: &str) -> CheckpointResult<Vec<u8>> {
+        let path = self.blob_path(checksum);
+        let stored = std::fs::read(&path).map_err(|e| {
+            CheckpointError::storage(format!("File snapshot {} is missing: {}", checksum, e))
+        })?;
+        let mut data = encryption::open(&stored, self.cipher.as_deref(), checksum)?.into_owned();
+        // Files that are zstd frames themselves are stored as they are, so
+        // only decompress when the stored bytes are not the content
+        if compression::is_compressed(&data) && sha256_hex(&data) != checksum {
+            data = compression::decompress(&data)?.into_owned();
+        }
+        if sha256_hex(&data) != checksum {
+            return Err(CheckpointError::corrupted(format!(
+                "File snapshot {} does not match its checksum",
+                checksum
+            )));
+        }
+        Ok(data)
+    }
+
+    /// Delete blobs whose checksum is not in `referenced` and that were
+    /// written more than `min_age` ago. Leftover temp files are removed too.
+    pub fn collect_garbage(
+        &self,
+        referenced: &HashSet<String>,
+        min_age: std::time::Duration,
+    ) -> CheckpointResult<BlobGcReport> {
+        let mut report = BlobGcReport::default();
+        if !self.root.is_dir() {
+            return Ok(report);
+        }
+        for prefix in std::fs::read_dir(&self.root)? {
+            let prefix = prefix?.path();
+            if !prefix.is_dir() {
+                continue;
+            }
+            for entry in std::fs::read_dir(&prefix)? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if referenced.contains(&name) {
+                    continue;
+                }
+                let meta = entry.metadata()?;
+                let age = meta.modified()?.elapsed().unwrap_or_default();
+                if !meta.is_file() || age < min_age {
+                    continue;
+                }
+                std::fs::remove_file(entry.path())?;
+                report.removed += 1;
+                report.freed_bytes += meta.len();
+            }
+            // Drop the prefix directory once it is empty
+            let _ = std::fs::remove_dir(&prefix);
+        }
+        Ok(report)
+    }
+}
+
+/// Checksums of the blobs a file snapshot refers to.
+pub fn referenced_blobs(snapshot: &FileSystemSnapshot) -> impl Iterator<Item = &String> {
+    snapshot
+        .tracked_files
+        .iter()
+        .map(|f| &f.checksum)
+        .chain(
+            snapshot
+                .modified_files
+                .iter()
+                .flat_map(|c| c.old_checksum.iter().chain(c.new_checksum.iter())),
+        )
+}
+
+/// Blobs removed by [`BlobStore::collect_garbage`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlobGcReport {
+    /// Number of blobs deleted
+    pub removed: usize,
+    /// Bytes freed on disk
+    pub freed_bytes: u64,
+}
+
+/// Files the agent modified this session, with their content from before
+/// the first modification.
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceTracker {
+    baselines: BTreeMap<PathBuf, Option<Vec<u8>>>,
+}
+
+impl WorkspaceTracker {
+    /// Create an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `path` (absolute) is about to be modified.
+    ///
+    /// The first call for a path keeps its current content as the baseline
+    /// (`None` if the file doesn't exist yet). Files over
+    /// [`MAX_TRACKED_FILE_BYTES`] and directories are ignored.
+    pub fn record(&mut self, path: &Path) {
+        if self.baselines.contains_key(path) {
+            return;
+        }
+        let baseline = match std::fs::metadata(path) {
+            Ok(meta) if !meta.is_file() || meta.len() > MAX_TRACKED_FILE_BYTES => return,
+            Ok(_) => match std::fs::read(path) {
+                Ok(data) => Some(data),
+                Err(_) => return,
+            },
+            Err(_) => None,
+        };
+        self.baselines.insert(path.to_path_buf(), baseline);
+    }
+
+    /// Record `path` (absolute) with a baseline read before it changed.
+    ///
+    /// Used for changes found after the fact, e.g. by a shell command. A
+    /// path that is already tracked keeps its first baseline, and baselines
+    /// over [`MAX_TRACKED_FILE_BYTES`] are ignored.
+    pub fn record_baseline(&mut self, path: &Path, baseline: Option<Vec<u8>>) {
+        if self.baselines.contains_key(path)
+            || baseline.as_ref().is_some_and(|b| b.len() as u64 > MAX_TRACKED_FILE_BYTES)
+        {
+            return;
+        }
+        self.baselines.insert(path.to_path_buf(), baseline);
+    }
+
+    /// Rebuild the tracker from a checkpoint's file snapshot, e.g. on resume.
+    ///
+    /// Every file the snapshot records as changed is tracked again with its
+    /// original baseline, read from `store`.
+    pub fn from_snapshot(snapshot: &FileSystemSnapshot, store: &BlobStore) -> CheckpointResult<Self> {
+        let mut tracker = Self::new();
+        for change in &snapshot.modified_files {
+            let baseline = change.old_checksum.as_deref().map(|c| store.get(c)).transpose()?;
+            tracker
+                .baselines
+                .insert(snapshot.working_directory.join(&change.path), baseline);
+        }
+        Ok(tracker)
+    }
+
+    /// Tracked paths in sorted order.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.baselines.keys().map(|p| p.as_path())
+    }
+
+    /// Number of tracked files.
+    pub fn len(&self) -> usize {
+        self.baselines.len()
+    }
+
+    /// Check whether no file has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.baselines.is_empty()
+    }
+
+    /// Forget all tracked files.
+    pub fn clear(&mut self) {
+        self.baselines.clear();
+    }
+}
+
+/// Glob patterns selecting the files that are snapshotted.
+///
+/// Paths are matched relative to the project root. A file is selected when
+/// it matches no exclude pattern and, if include patterns are set, at least
+/// one of them. `*` also matches `/`, so `*.log` covers every directory.
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl SnapshotFilter {
+    /// Build a filter; empty pattern lists select everything.
+    pub fn new(include: &[String], exclude: &[String]) -> CheckpointResult<Self> {
+        Ok(Self {
+            include: Self::glob_set(include)?,
+            exclude: Self::glob_set(exclude)?,
+        })
+    }
+
+    /// Build a filter from a project's `include_patterns`/`exclude_patterns`.
+    pub fn from_config(config: &ProjectCheckpointConfig) -> CheckpointResult<Self> {
+        Self::new(&config.include_patterns, &config.exclude_patterns)
+    }
+
+    /// Check whether the filter selects every file.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_none() && self.exclude.is_none()
+    }
+
+    /// Check whether `path` (relative to the project root) is snapshotted.
+    pub fn matches(&self, path: &Path) -> bool {
+        if self.exclude.as_ref().is_some_and(|set| set.is_match(path)) {
+            return false;
+        }
+        self.include.as_ref().is_none_or(|set| set.is_match(path))
+    }
+
+    fn glob_set(patterns: &[String]) -> CheckpointResult<Option<GlobSet>> {
+        if patterns.is_empty() {
+            return Ok(None);
+        }
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = Glob::new(pattern).map_err(|e| {
+                CheckpointError::config(format!("Invalid snapshot pattern '{}': {}", pattern, e))
+            })?;
+            builder.add(glob);
+        }
+        builder
+            .build()
+            .map(Some)
+            .map_err(|e| CheckpointError::config(format!("Invalid snapshot patterns: {}", e)))
+    }
+}
+
+/// Snapshot every file in `tracker` selected by `filter`, storing contents
+/// in `store`.
+///
+/// Paths are recorded relative to `working_dir`; files outside it are left
+/// out, since a restore never writes there. A file is
+/// recorded as deleted only when it no longer exists; files that became
+/// directories or outgrew [`MAX_TRACKED_FILE_BYTES`] are skipped.
+pub fn capture_file_system(
+    working_dir: &Path,
+    tracker: &WorkspaceTracker,
+    store: &BlobStore,
+    filter: &SnapshotFilter,
+) -> CheckpointResult<FileSystemSnapshot> {
+    let mut tracked_files = Vec::new();
+    let mut modified_files = Vec::new();
+    let mut file_permissions = HashMap::new();
+
+    for (path, baseline) in &tracker.baselines {
+        let Ok(rel) = path.strip_prefix(working_dir).map(Path::to_path_buf) else {
+            continue;
+        };
+        if !filter.matches(&rel) {
+            continue;
+        }
+        // Only a missing file counts as deleted; one that became a directory,
+        // grew past the size limit or can't be read is left out
+        let current = match std::fs::metadata(path) {
+            Ok(meta) if meta.is_file() && meta.len() <= MAX_TRACKED_FILE_BYTES => {
+                Some((std::fs::read(path)?, meta))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            _ => continue,
+        };
+        let old_checksum = baseline.as_deref().map(|b| store.put(b)).transpose()?;
+        let old_size = baseline.as_ref().map(|b| b.len() as i64).unwrap_or(0);
+
+        let (new_checksum, new_size) = match current {
+            Some((data, meta)) => {
+                let checksum = store.put(&data)?;
+                let permissions = file_permissions_of(&meta);
+                file_permissions.insert(rel.clone(), permissions.clone());
+                tracked_files.push(TrackedFile {
+                    path: rel.clone(),
+                    size: data.len() as u64,
+                    modified: meta.modified().map(DateTime::<Utc>::from).unwrap_or_else(|_| Utc::now()),
+                    checksum: checksum.clone(),
+                    permissions,
+                });
+                (Some(checksum), data.len() as i64)
+            }
+            None => (None, 0),
+        };
+
+        let change_type = match (&old_checksum, &new_checksum) {
+            (None, None) => continue,
+            (None, Some(_)) => FileChangeType::Created,
+            (Some(_), None) => FileChangeType::Deleted,
+            (Some(_), Some(_)) => FileChangeType::Modified,
+        };
+        modified_files.push(FileChange {
+            path: rel,
+            change_type,
+            old_checksum,
+            new_checksum,
+            size_delta: new_size - old_size,
+        });
+    }
+
+    Ok(FileSystemSnapshot {
+        working_directory: working_dir.to_path_buf(),
+        tracked_files,
+        modified_files,
+        git_status: None,
+        file_permissions,
+    })
+}
+
+/// What restoring a file does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileRestoreAction {
+    /// Recreate a file that no longer exists
+    Create,
+    /// Overwrite the file's current content
+    Overwrite,
+    /// Remove a file that didn't exist at the checkpoint
+    Delete,
+}
+
+/// One file changed by a restore.
+#[derive(Debug, Clone)]
+pub struct FileRestoreChange {
+    /// Path relative to the working directory
+    pub path: PathBuf,
+    /// What the restore does to the file
+    pub action: FileRestoreAction,
+    /// Unified diff from the current content to the restored content
+    pub diff: String,
+    content: Option<Vec<u8>>,
+    mode: Option<u32>,
+}
+
+/// Result of planning (and optionally applying) a file restore.
+#[derive(Debug, Clone)]
+pub struct FileRestorePlan {
+    /// Checkpoint the files are rolled back to
+    pub checkpoint_id: String,
+    /// Directory the paths are relative to
+    pub working_directory: PathBuf,
+    /// Files that differ from the checkpoint
+    pub changes: Vec<FileRestoreChange>,
+    /// Whether the changes were written to disk
+    pub applied: bool,
+}
+
+impl FileRestorePlan {
+    /// Check whether the working tree already matches the checkpoint.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Concatenated diff of every change.
+    pub fn diff(&self) -> String {
+        self.changes.iter().map(|c| c.diff.as_str()).collect()
+    }
+
+    /// Write the planned changes to disk.
+    ///
+    /// Every path is checked again against the working directory, in case
+    /// a symlink appeared since the plan was made.
+    pub fn apply(&mut self) -> CheckpointResult<()> {
+        for change in &self.changes {
+            let path = confined_path(&self.working_directory, &change.path)?;
+            match &change.content {
+                Some(data) => {
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(&path, data)?;
+                    #[cfg(unix)]
+                    if let Some(mode) = change.mode {
+                        use std::os::unix::fs::PermissionsExt;
+                        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))?;
+                    }
+                }
+                None => {
+                    if path.exists() {
+                        std::fs::remove_file(&path)?;
+                    }
+                }
+            }
+        }
+        self.applied = true;
+        Ok(())
+    }
+}
+
+/// Plan rolling the working tree back to `target`.
+///
+/// `latest` is the most recent snapshot of the session; files it tracks
+/// that `target` doesn't know about are restored to their baseline. Fails
+/// if any path would lead outside the working directory.
+pub fn plan_file_restore(
+    checkpoint_id: &str,
+    target: &FileSystemSnapshot,
+    latest: &FileSystemSnapshot,
+    store: &BlobStore,
+) -> CheckpointResult<FileRestorePlan> {
+    let working_dir = if target.working_directory.as_os_str().is_empty() {
+        latest.working_directory.clone()
+    } else {
+        target.working_directory.clone()
+    };
+
+    let target_files: HashMap<&Path, &TrackedFile> =
+        target.tracked_files.iter().map(|f| (f.path.as_path(), f)).collect();
+    let target_touched: BTreeSet<&Path> =
+        target.modified_files.iter().map(|c| c.path.as_path()).collect();
+    // Baselines from the target first, then from later snapshots
+    let mut baselines: HashMap<&Path, Option<&str>> = HashMap::new();
+    for change in latest.modified_files.iter().chain(&target.modified_files) {
+        baselines.insert(change.path.as_path(), change.old_checksum.as_deref());
+    }
+
+    let paths: BTreeSet<&Path> = target_files
+        .keys()
+        .copied()
+        .chain(target_touched.iter().copied())
+        .chain(baselines.keys().copied())
+        .collect();
+
+    let mut changes = Vec::new();
+    for rel in paths {
+        let (desired, mode) = match target_files.get(rel) {
+            Some(file) => (Some(file.checksum.as_str()), Some(file.permissions.mode)),
+            None if target_touched.contains(rel) => (None, None),
+            None => match baselines.get(rel) {
+                Some(baseline) => (*baseline, None),
+                None => continue,
+            },
+        };
+
+        let abs = confined_path(&working_dir, rel)?;
+        let current = std::fs::read(&abs).ok();
+        let desired_content = desired.map(|checksum| store.get(checksum)).transpose()?;
+        if current == desired_content {
+            continue;
+        }
+
+        let action = match (&current, &desired_content) {
+            (None, Some(_)) => FileRestoreAction::Create,
+            (Some(_), Some(_)) => FileRestoreAction::Overwrite,
+            (_, None) => FileRestoreAction::Delete,
+        };
+        let diff = unified_diff(
+            &rel.display().to_string(),
+            current.as_deref(),
+            desired_content.as_deref(),
+        );
+        changes.push(FileRestoreChange {
+            path: rel.to_path_buf(),
+            action,
+            diff,
+            content: desired_content,
+            mode: mode.filter(|m| *m != 0),
+        });
+    }
+
+    Ok(FileRestorePlan {
+        checkpoint_id: checkpoint_id.to_string(),
+        working_directory: working_dir,
+        changes,
+        applied: false,
+    })
+}
+
+/// Resolve `rel` against `working_dir`, refusing paths that could leave it.
+///
+/// `rel` must consist of plain names only (no root, prefix or `..`), and its
+/// nearest existing ancestor, or the file itself if it exists, must resolve
+/// inside `working_dir` once symlinks are followed.
+fn confined_path(working_dir: &Path, rel: &Path) -> CheckpointResult<PathBuf> {
+    let outside = || {
+        CheckpointError::restoration(format!(
+            "Refusing to restore {}: it is outside the working directory {}",
+            rel.display(),
+            working_dir.display()
+        ))
+    };
+    let plain = rel
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !plain || rel.as_os_str().is_empty() {
+        return Err(outside());
+    }
+
+    let root = working_dir.canonicalize().map_err(|e| {
+        CheckpointError::restoration(format!(
+            "Working directory {} is not accessible: {}",
+            working_dir.display(),
+            e
+        ))
+    })?;
+    let path = working_dir.join(rel);
+    let mut existing = path.as_path();
+    while std::fs::symlink_metadata(existing).is_err() {
+        existing = existing.parent().ok_or_else(outside)?;
+    }
+    // A dangling symlink fails to resolve and is refused as well
+    match existing.canonicalize() {
+        Ok(resolved) if resolved.starts_with(&root) => Ok(path),
+        _ => Err(outside()),
+    }
+}
+
+fn file_permissions_of(meta: &std::fs::Metadata) -> FilePermissions {
+    #[cfg(unix)]
+    let mode = {
+        use std::os::unix::fs::PermissionsExt;
+        meta.permissions().mode()
+    };
+    #[cfg(not(unix))]
+    let mode = 0;
+
+    FilePermissions {
+        mode,
+        readable: true,
+        writable: !meta.permissions().readonly(),
+        executable: mode & 0o111 != 0,
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum DiffOp {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Line-based unified diff from `old` to `new` (`None` = file absent).
+fn unified_diff(path: &str, old: Option<&[u8]>, new: Option<&[u8]>) -> String {
+    let old_label = if old.is_some() { format!("a/{}", path) } else { "/dev/null".to_string() };
+    let new_label = if new.is_some() { format!("b/{}", path) } else { "/dev/null".to_string() };
+    let mut out = format!("--- {}\n+++ {}\n", old_label, new_label);
+
+    let (old_text, new_text) = match (
+        std::str::from_utf8(old.unwrap_or_default()),
+        std::str::from_utf8(new.unwrap_or_default()),
+    ) {
+        (Ok(o), Ok(n)) => (o, n),
+        _ => {
+            out.push_str("Binary files differ\n");
+            return out;
+        }
+    };
+    let a: Vec<&str> = old_text.split_inclusive('\n').collect();
+    let b: Vec<&str> = new_text.split_inclusive('\n').collect();
+    let ops = diff_ops(&a, &b);
+
+    let changed: Vec<usize> = (0..ops.len()).filter(|&i| ops[i] != DiffOp::Equal).collect();
+    let mut groups: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        match groups.last_mut() {
+            Some((_, end)) if i <= *end + 2 * DIFF_CONTEXT_LINES => *end = i,
+            _ => groups.push((i, i)),
+        }
+    }
+
+    for (first, last) in groups {
+        let start = first.saturating_sub(DIFF_CONTEXT_LINES);
+        let end = (last + 1 + DIFF_CONTEXT_LINES).min(ops.len());
+        let (mut ai, mut bi) = (0, 0);
+        for op in &ops[..start] {
+            if *op != DiffOp::Insert {
+                ai += 1;
+            }
+            if *op != DiffOp::Delete {
+                bi += 1;
+            }
+        }
+        let old_len = ops[start..end].iter().filter(|op| **op != DiffOp::Insert).count();
+        let new_len = ops[start..end].iter().filter(|op| **op != DiffOp::Delete).count();
+        let hunk_start = |pos: usize, len: usize| if len == 0 { pos } else { pos + 1 };
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            hunk_start(ai, old_len),
+            old_len,
+            hunk_start(bi, new_len),
+            new_len
+        ));
+
+        for op in &ops[start..end] {
+            let (prefix, line) = match op {
+                DiffOp::Equal => {
+                    ai += 1;
+                    bi += 1;
+                    (' ', a[ai - 1])
+                }
+                DiffOp::Delete => {
+                    ai += 1;
+                    ('-', a[ai - 1])
+                }
+                DiffOp::Insert => {
+                    bi += 1;
+                    ('+', b[bi - 1])
+                }
+            };
+            out.push(prefix);
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+    out
+}
+
+/// Edit script turning `a` into `b`, from a longest-common-subsequence table.
+fn diff_ops(a: &[&str], b: &[&str]) -> Vec<DiffOp> {
+    let (n, m) = (a.len(), b.len());
+    if n.saturating_mul(m) > MAX_DIFF_CELLS {
+        let mut ops = vec![DiffOp::Delete; n];
+        ops.extend(std::iter::repeat_n(DiffOp::Insert, m));
+        return ops;
+    }
+
+    // lcs[i][j] = LCS length of a[i..] and b[j..]
+    let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+    let idx = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[idx(i, j)] = if a[i] == b[j] {
+                lcs[idx(i + 1, j + 1)] + 1
+            } else {
+                lcs[idx(i + 1, j)].max(lcs[idx(i, j + 1)])
+            };
+        }
+    }
+
+    let mut ops = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if a[i] == b[j] {
+            ops.push(DiffOp::Equal);
+            i += 1;
+            j += 1;
+        } else if lcs[idx(i + 1, j)] >= lcs[idx(i, j + 1)] {
+            ops.push(DiffOp::Delete);
+            i += 1;
+        } else {
+            ops.push(DiffOp::Insert);
+            j += 1;
+        }
+    }
+    ops.extend(std::iter::repeat_n(DiffOp::Delete, n - i));
+    ops.extend(std::iter::repeat_n(DiffOp::Insert, m - j));
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_blob_store_deduplicates() {
+        let dir = TempDir::new().unwrap();
+        let store = BlobStore::new(dir.path().join("blobs"));
+
+        let a = store.put(b"hello\n").unwrap();
+        let b = store.put(b"hello\n").unwrap();
+        assert_eq!(a, b);
+        assert!(store.contains(&a));
+        assert_eq!(store.get(&a).unwrap(), b"hello\n");
+
+        std::fs::write(store.blob_path(&a), b"tampered").unwrap();
+        assert!(store.get(&a).is_err());
+    }
+
+    #[test]
+    fn test_blob_store_compresses_blobs() {
+        let dir = TempDir::new().unwrap();
+        let store = BlobStore::new(dir.path().join("blobs"))
+            .with_compression(CompressionSettings { enabled: true, level: 3 });
+
+        let text = "the same line of source\n".repeat(200);
+        let checksum = store.put(text.as_bytes()).unwrap();
+        let stored = std::fs::read(store.blob_path(&checksum)).unwrap();
+        assert!(compression::is_compressed(&stored));
+        assert!(stored.len() < text.len());
+        assert_eq!(store.get(&checksum).unwrap(), text.as_bytes());
+
+        // A file that is a zstd frame itself is stored and returned unchanged
+        let frame = compression::compress(text.as_bytes(), 3).unwrap();
+        let checksum = store.put(&frame).unwrap();
+        assert_eq!(std::fs::read(store.blob_path(&checksum)).unwrap(), frame);
+        assert_eq!(store.get(&checksum).unwrap(), frame);
+
+        // Blobs written without compression still load
+        let plain = BlobStore::new(dir.path().join("blobs")).put(b"plain\n").unwrap();
+        assert_eq!(store.get(&plain).unwrap(), b"plain\n");
+    }
+
+    #[test]
+    fn test_blob_store_collects_unreferenced_blobs() {
+        let dir = TempDir::new().unwrap();
+        let store = BlobStore::new(dir.path().join("blobs"));
+        let kept = store.put(b"kept\n").unwrap();
+        let dropped = store.put(b"dropped\n").unwrap();
+        let referenced: HashSet<String> = [kept.clone()].into_iter().collect();
+
+        // Fresh blobs survive the grace period
+        let report = store.collect_garbage(&referenced, BLOB_GC_MIN_AGE).unwrap();
+        assert_eq!(report.removed, 0);
+        assert!(store.contains(&dropped));
+
+        let report = store.collect_garbage(&referenced, std::time::Duration::ZERO).unwrap();
+        assert_eq!(report, BlobGcReport { removed: 1, freed_bytes: 8 });
+        assert!(store.contains(&kept));
+        assert!(!store.contains(&dropped));
+        assert!(!store.blob_path(&dropped).parent().unwrap().exists());
+    }
+
+    #[test]
+    fn test_blob_store_encrypts_blobs() {
+        let dir = TempDir::new().unwrap();
+        let cipher = Arc::new(CheckpointCipher::from_key([3u8; 32]));
+        let store = BlobStore::new(dir.path().join("blobs")).with_cipher(Some(cipher));
+
+        let checksum = store.put(b"API_KEY=hunter2\n").unwrap();
+        assert_eq!(checksum, sha256_hex(b"API_KEY=hunter2\n"));
+        let stored = std::fs::read(store.blob_path(&checksum)).unwrap();
+        assert!(encryption::is_encrypted(&stored));
+        assert_eq!(store.get(&checksum).unwrap(), b"API_KEY=hunter2\n");
+
+        let without_key = BlobStore::new(dir.path().join("blobs"));
+        assert!(matches!(
+            without_key.get(&checksum),
+            Err(CheckpointError::EncryptionKeyMissing { .. })
+        ));
+    }
+
+    #[test]
+    fn test_capture_and_restore_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let work = dir.path().join("work");
+        std::fs::create_dir_all(&work).unwrap();
+        let store = BlobStore::new(dir.path().join("blobs"));
+        let main = work.join("main.rs");
+        let notes = work.join("notes.txt");
+        std::fs::write(&main, "fn main() {\n    println!(\"v0\");\n}\n").unwrap();
+
+        // Checkpoint 1: main.rs edited once
+        let mut tracker = WorkspaceTracker::new();
+        tracker.record(&main);
+        std::fs::write(&main, "fn main() {\n    println!(\"v1\");\n}\n").unwrap();
+        let first = capture_file_system(&work, &tracker, &store, &SnapshotFilter::default()).unwrap();
+        assert_eq!(first.tracked_files.len(), 1);
+        assert_eq!(first.tracked_files[0].path, PathBuf::from("main.rs"));
+
+        // Checkpoint 2: main.rs edited again, notes.txt created
+        tracker.record(&main);
+        tracker.record(&notes);
+        std::fs::write(&main, "fn main() {\n    println!(\"v2\");\n}\n").unwrap();
+        std::fs::write(&notes, "todo\n").unwrap();
+        let latest = capture_file_system(&work, &tracker, &store, &SnapshotFilter::default()).unwrap();
+        assert_eq!(latest.modified_files.len(), 2);
+        assert!(matches!(latest.modified_files[1].change_type, FileChangeType::Created));
+
+        let mut plan = plan_file_restore("001_analyze", &first, &latest, &store).unwrap();
+        assert_eq!(plan.changes.len(), 2);
+        assert_eq!(plan.changes[0].action, FileRestoreAction::Overwrite);
+        assert!(plan.changes[0].diff.contains("-    println!(\"v2\");\n+    println!(\"v1\");\n"));
+        assert_eq!(plan.changes[1].action, FileRestoreAction::Delete);
+
+        // Dry run leaves the tree alone
+        assert!(notes.exists());
+        plan.apply().unwrap();
+        assert!(plan.applied);
+        assert!(!notes.exists());
+        assert!(std::fs::read_to_string(&main).unwrap().contains("v1"));
+
+        let again = plan_file_restore("001_analyze", &first, &latest, &store).unwrap();
+        assert!(again.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_restore_refuses_paths_outside_the_working_directory() {
+        let dir = TempDir::new().unwrap();
+        let work = dir.path().join("work");
+        let outside = dir.path().join("outside");
+        std::fs::create_dir_all(&work).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, work.join("link")).unwrap();
+        let victim = outside.join("victim.txt");
+        std::fs::write(&victim, "precious\n").unwrap();
+        let store = BlobStore::new(dir.path().join("blobs"));
+
+        let file = work.join("main.rs");
+        let mut tracker = WorkspaceTracker::new();
+        tracker.record(&file);
+        std::fs::write(&file, "evil\n").unwrap();
+        let honest = capture_file_system(&work, &tracker, &store, &SnapshotFilter::default()).unwrap();
+
+        // A snapshot, e.g. from an imported archive, pointing outside the tree
+        for path in [PathBuf::from("../outside/victim.txt"), victim.clone(), PathBuf::from("link/victim.txt")] {
+            let mut snapshot = honest.clone();
+            snapshot.tracked_files[0].path = path.clone();
+            snapshot.modified_files[0].path = path.clone();
+            let empty = FileSystemSnapshot { tracked_files: Vec::new(), modified_files: Vec::new(), ..honest.clone() };
+            assert!(
+                matches!(plan_file_restore("001", &snapshot, &snapshot, &store), Err(CheckpointError::Restoration { .. })),
+                "{} was not refused",
+                path.display()
+            );
+            // Deleting through a later snapshot is refused too
+            assert!(plan_file_restore("001", &empty, &snapshot, &store).is_err());
+        }
+        assert_eq!(std::fs::read_to_string(&victim).unwrap(), "precious\n");
+
+        // Files outside the working directory are not captured at all
+        let mut tracker = WorkspaceTracker::new();
+        tracker.record(&victim);
+        let snapshot = capture_file_system(&work, &tracker, &store, &SnapshotFilter::default()).unwrap();
+        assert!(snapshot.tracked_files.is_empty());
+    }
+
+    #[test]
+    fn test_capture_skips_files_it_cannot_snapshot() {
+        let dir = TempDir::new().unwrap();
+        let work = dir.path().join("work");
+        std::fs::create_dir_all(&work).unwrap();
+        let store = BlobStore::new(dir.path().join("blobs"));
+        let (gone, now_dir, large) = (work.join("gone.txt"), work.join("now_dir"), work.join("large.bin"));
+        for path in [&gone, &now_dir, &large] {
+            std::fs::write(path, "before\n").unwrap();
+        }
+
+        let mut tracker = WorkspaceTracker::new();
+        for path in [&gone, &now_dir, &large] {
+            tracker.record(path);
+        }
+        std::fs::remove_file(&gone).unwrap();
+        std::fs::remove_file(&now_dir).unwrap();
+        std::fs::create_dir(&now_dir).unwrap();
+        std::fs::File::create(&large)
+            .unwrap()
+            .set_len(MAX_TRACKED_FILE_BYTES + 1)
+            .unwrap();
+
+        let snapshot = capture_file_system(&work, &tracker, &store, &SnapshotFilter::default()).unwrap();
+        assert_eq!(snapshot.modified_files.len(), 1);
+        assert_eq!(snapshot.modified_files[0].path, PathBuf::from("gone.txt"));
+        assert!(matches!(snapshot.modified_files[0].change_type, FileChangeType::Deleted));
+    }
+
+    #[test]
+    fn test_tracker_rebuilt_from_snapshot() {
+        let dir = TempDir::new().unwrap();
+        let work = dir.path().join("work");
+        std::fs::create_dir_all(&work).unwrap();
+        let store = BlobStore::new(dir.path().join("blobs"));
+        let main = work.join("main.rs");
+        let notes = work.join("notes.txt");
+        std::fs::write(&main, "v0\n").unwrap();
+
+        let mut tracker = WorkspaceTracker::new();
+        tracker.record(&main);
+        tracker.record_baseline(&notes, None);
+        std::fs::write(&main, "v1\n").unwrap();
+        std::fs::write(&notes, "todo\n").unwrap();
+        let snapshot = capture_file_system(&work, &tracker, &store, &SnapshotFilter::default()).unwrap();
+
+        // A resumed session keeps the original baselines
+        let mut resumed = WorkspaceTracker::from_snapshot(&snapshot, &store).unwrap();
+        assert_eq!(resumed.paths().collect::<Vec<_>>(), vec![main.as_path(), notes.as_path()]);
+        resumed.record(&main);
+        std::fs::write(&main, "v2\n").unwrap();
+        let later = capture_file_system(&work, &resumed, &store, &SnapshotFilter::default()).unwrap();
+        assert_eq!(later.modified_files[0].old_checksum, snapshot.modified_files[0].old_checksum);
+        assert_eq!(store.get(later.modified_files[0].old_checksum.as_ref().unwrap()).unwrap(), b"v0\n");
+        assert!(matches!(later.modified_files[1].change_type, FileChangeType::Created));
+    }
+
+    #[test]
+    fn test_snapshot_filter_patterns() {
+        let filter = SnapshotFilter::new(
+            &["src/**".to_string(), "*.toml".to_string()],
+            &["*.log".to_string(), "src/generated/**".to_string()],
+        )
+        .unwrap();
+        assert!(filter.matches(Path::new("src/main.rs")));
+        assert!(filter.matches(Path::new("crates/core/Cargo.toml")));
+        assert!(!filter.matches(Path::new("src/generated/api.rs")));
+        assert!(!filter.matches(Path::new("src/debug.log")));
+        assert!(!filter.matches(Path::new("README.md")));
+
+        assert!(SnapshotFilter::default().matches(Path::new("README.md")));
+        assert!(SnapshotFilter::new(&["src/[".to_string()], &[]).is_err());
+    }
+
+    #[test]
+    fn test_unified_diff_hunks() {
+        let old: String = (1..=20).map(|i| format!("line {}\n", i)).collect();
+        let new = old.replace("line 2\n", "line two\n").replace("line 19\n", "");
+        let diff = unified_diff("f.txt", Some(old.as_bytes()), Some(new.as_bytes()));
+
+        assert!(diff.starts_with("--- a/f.txt\n+++ b/f.txt\n@@ -1,5 +1,5 @@\n line 1\n-line 2\n+line two\n"));
+        assert!(diff.contains("@@ -16,5 +16,4 @@\n"));
+
+        let created = unified_diff("new.txt", None, Some(b"x"));
+        assert!(created.starts_with("--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,1 @@\n+x\n\\ No newline at end of file\n"));
+    }
+}
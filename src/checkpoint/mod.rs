@@ -4,10 +4,15 @@
 //!
 //! This crate provides:
 //! - Session persistence and restoration
-//! - Checkpoint storage with compression
-//! - Retention policies and cleanup
+//! - Checkpoint storage with optional zstd compression
+//! - Optional encryption at rest (AES-256-GCM)
+//! - Retention policies and cleanup, with per-session checkpoint pruning
 //! - Project isolation via hash-based directories
 //! - Atomic file operations
+//! - Portable project archives for moving history between machines
+//! - Content-addressed workspace file snapshots with rollback
+//! - Shadow git snapshots of the working tree per checkpoint
+//! - Per-project overrides from `.abk/checkpoint.toml`
 //!
 //! All data is stored centrally in `~/.{agent_name}/` to avoid project directory pollution.
 //!
@@ -34,15 +39,21 @@
 //! ```
 
 pub mod agent_context;
+pub mod archive;
 pub mod atomic;
 pub mod backend;
 pub mod cleanup;
+pub mod compression;
 pub mod config;
+pub mod encryption;
 pub mod errors;
+pub mod file_snapshots;
 pub mod models;
+pub mod pruning;
 pub mod restoration;
 pub mod resume_tracker;
 pub mod session_manager;
+pub mod shadow_git;
 pub mod size_calc;
 pub mod storage;
 pub mod utils;
@@ -50,25 +61,36 @@ pub mod v2;
 
 // Re-export key types for convenience
 pub use agent_context::AgentContext;
+pub use archive::{
+    ArchiveEntry, ArchiveImportReport, ArchiveManifest, SessionCollisionPolicy,
+};
 pub use atomic::{AtomicFileWriter, AtomicOps, FileLock};
 pub use cleanup::CleanupManager;
+pub use compression::CompressionSettings;
+pub use encryption::{CheckpointCipher, ReencryptReport};
 pub use config::{
     CleanupReport, ConfigMigrator, GlobalCheckpointConfig, MigrationReport,
     ProjectCheckpointConfig, ProjectConfigManager, ProjectStats, RetentionPolicy, SessionStats,
-    StorageBackendConfig, StorageBackendType, StorageMode, StorageStats,
+    StorageBackendConfig, StorageBackendType, StorageMode, StorageStats, PROJECT_CONFIG_FILE,
 };
 pub use errors::{CheckpointError, CheckpointResult};
+pub use file_snapshots::{
+    BlobStore, FileRestoreAction, FileRestoreChange, FileRestorePlan, SnapshotFilter,
+    WorkspaceTracker,
+};
 pub use models::{
     AgentStateSnapshot, Checkpoint, CheckpointMetadata, CheckpointSummary, ConversationSnapshot,
-    EnvironmentSnapshot, FileSystemSnapshot, SessionMetadata, SessionStatus,
-    ToolStateSnapshot, project_id_from_path,
+    ConversationStats, EnvironmentSnapshot, FileSystemSnapshot, SessionMetadata, SessionStatus,
+    TokenUsageStats, ToolStateSnapshot, project_id_from_path,
 };
 pub use restoration::{
     CheckpointRestoration, RestorationMetadata, RestorationResult, RestoredCheckpoint,
     ValidationIssue, ValidationResults, ValidationSeverity,
 };
+pub use pruning::{auto_tags, has_user_tags, CheckpointPruning, PruneReport};
 pub use resume_tracker::{ResumeContext, ResumeTracker};
 pub use session_manager::SessionManager;
+pub use shadow_git::{commit_project_changes, ShadowChange, ShadowDiff, ShadowRepo, ShadowRestore, ShellCommandWatch};
 pub use size_calc::{SizeCategory, SizeInfo, SizeUtils, StorageSizeCalculator};
 pub use storage::{CheckpointStorageManager, ProjectStorage, SessionStorage};
 
@@ -57,6 +57,8 @@ pub struct CheckpointMetadata {
     pub uncompressed_size: u64, // Size of uncompressed checkpoint data
     pub description: Option<String>, // Optional user description
     pub tags: Vec<String>,     // User-defined tags
+    #[serde(default)]
+    pub shadow_commit: Option<String>, // Shadow git commit of the working tree
 }
 
 /// Agent workflow steps
@@ -117,6 +119,8 @@ pub struct ChatMessage {
     pub role: String,                           // "system", "user", "assistant", "tool"
     pub content: String,                        // Message content
     pub reasoning: Option<String>,              // Reasoning/thinking content for thinking models
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking_blocks: Option<Vec<serde_json::Value>>, // Signed thinking blocks to send back (Anthropic)
     pub timestamp: DateTime<Utc>,               // When message was created
     pub token_count: Option<usize>,             // Cached token count
     pub tool_calls: Option<Vec<umf::ToolCall>>, // Tool calls for assistant messages
@@ -142,6 +146,17 @@ pub struct ConversationStats {
     pub total_messages: usize,       // Total messages
     pub estimated_cost: Option<f64>, // Estimated API cost in USD
     pub api_calls: u32,              // Number of API calls made
+    #[serde(default)]
+    pub token_usage: TokenUsageStats, // Provider-reported token counts
+}
+
+/// Token counts reported by the LLM provider, summed over a session
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenUsageStats {
+    pub prompt_tokens: u64,     // Input tokens, including cached
+    pub completion_tokens: u64, // Output tokens, including reasoning
+    pub cached_tokens: u64,     // Input tokens served from the prompt cache
+    pub reasoning_tokens: u64,  // Output tokens spent on reasoning
 }
 
 /// File system state snapshot
@@ -325,6 +340,12 @@ pub struct SessionMetadata {
     pub description: Option<String>,  // Optional description
     pub tags: Vec<String>,            // User-defined tags
     pub size_bytes: u64,              // Total session size
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation_stats: Option<ConversationStats>, // Stats from the latest checkpoint
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_session_id: Option<String>, // Session this one was forked from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_checkpoint_id: Option<String>, // Checkpoint of the parent it was forked at
 }
 
 /// Session status
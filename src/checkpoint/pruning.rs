@@ -0,0 +1,256 @@
+//! Pruning of long sessions
+//!
+//! Once a session holds more than `max_checkpoints_per_session` checkpoints,
+//! older ones are thinned out after each save. Always kept:
+//!
+//! - the latest half of the limit, so recent history stays complete
+//! - the first checkpoint, where the task started, unless the limit is 1
+//! - every checkpoint carrying tags, when `retention.preserve_tagged` is set
+//!
+//! Only tags a user added count: the automatic `[<workflow step>, "iter_N"]`
+//! tags (see [`auto_tags`]) and the project's default tags are on every
+//! checkpoint and protect none.
+//!
+//! The remaining slots go to older checkpoints picked at logarithmically
+//! spaced distances from the newest one: history stays dense near the
+//! present and becomes sparser further back. Untagged checkpoints never
+//! exceed the limit; tagged checkpoints can push a session over it, since they
+//! are never pruned to make room.
+
+use super::{GlobalCheckpointConfig, ProjectCheckpointConfig};
+
+/// Limits applied to the number of checkpoints in a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckpointPruning {
+    /// Checkpoints kept per session; 0 disables pruning
+    pub max_checkpoints: u32,
+    /// Never prune checkpoints that carry tags
+    pub preserve_tagged: bool,
+}
+
+impl Default for CheckpointPruning {
+    fn default() -> Self {
+        Self {
+            max_checkpoints: 50,
+            preserve_tagged: true,
+        }
+    }
+}
+
+/// Checkpoints removed from a session by pruning
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    /// Pruned checkpoint IDs, oldest first
+    pub pruned: Vec<String>,
+    /// Checkpoints left in the session
+    pub remaining: usize,
+}
+
+impl PruneReport {
+    /// Whether anything was pruned
+    pub fn is_empty(&self) -> bool {
+        self.pruned.is_empty()
+    }
+}
+
+/// Tags every checkpoint is created with: its workflow step and `iter_N`
+pub fn auto_tags(workflow_step: &impl std::fmt::Display, iteration: u32) -> Vec<String> {
+    vec![workflow_step.to_string(), format!("iter_{}", iteration)]
+}
+
+/// Whether `tags` hold any tag other than the checkpoint's [`auto_tags`] and
+/// the project's `default_tags`, i.e. one that exempts it from pruning
+pub fn has_user_tags(
+    tags: &[String],
+    workflow_step: &impl std::fmt::Display,
+    iteration: u32,
+    default_tags: &[String],
+) -> bool {
+    let auto = auto_tags(workflow_step, iteration);
+    tags.iter()
+        .any(|tag| !auto.contains(tag) && !default_tags.contains(tag))
+}
+
+impl CheckpointPruning {
+    /// Read `max_checkpoints_per_session` and `retention.preserve_tagged`
+    pub fn from_config(config: &GlobalCheckpointConfig) -> Self {
+        Self {
+            max_checkpoints: config.max_checkpoints_per_session,
+            preserve_tagged: config.retention.preserve_tagged,
+        }
+    }
+
+    /// Apply a project's `max_checkpoints_per_session` and `retention` overrides
+    pub fn with_project_overrides(self, project: &ProjectCheckpointConfig) -> Self {
+        Self {
+            max_checkpoints: project.max_checkpoints_per_session.unwrap_or(self.max_checkpoints),
+            preserve_tagged: project
+                .retention
+                .as_ref()
+                .map_or(self.preserve_tagged, |r| r.preserve_tagged),
+        }
+    }
+
+    /// Number of newest checkpoints that are always kept
+    pub fn keep_latest(&self) -> usize {
+        (self.max_checkpoints as usize).div_ceil(2).max(1)
+    }
+
+    /// Pick the checkpoints to prune from a session
+    ///
+    /// `tagged` holds one entry per checkpoint, oldest first, telling whether
+    /// it carries tags. Returns the indexes to prune, in ascending order.
+    pub fn select(&self, tagged: &[bool]) -> Vec<usize> {
+        let total = tagged.len();
+        let max = self.max_checkpoints as usize;
+        if max == 0 || total <= max {
+            return Vec::new();
+        }
+
+        // `total > max`, so the latest tier never reaches the first checkpoint
+        let latest = self.keep_latest().min(max);
+        let recent_start = total - latest;
+        let keep_first = latest < max;
+        let mut protected = latest + usize::from(keep_first);
+        // Distance from the newest checkpoint of each prunable one, newest first
+        let mut candidates = Vec::new();
+        for (index, &is_tagged) in tagged
+            .iter()
+            .enumerate()
+            .take(recent_start)
+            .skip(usize::from(keep_first))
+            .rev()
+        {
+            if self.preserve_tagged && is_tagged {
+                protected += 1;
+            } else {
+                candidates.push((index, total - 1 - index));
+            }
+        }
+
+        let slots = max.saturating_sub(protected);
+        if slots >= candidates.len() {
+            return Vec::new();
+        }
+
+        let mut keep = vec![false; candidates.len()];
+        if slots > 0 {
+            let nearest = candidates[0].1 as f64;
+            let farthest = candidates[candidates.len() - 1].1 as f64;
+            for slot in 0..slots {
+                // Geometric targets from the nearest to the farthest distance
+                let fraction = if slots == 1 { 1.0 } else { slot as f64 / (slots - 1) as f64 };
+                let target = nearest * (farthest / nearest).powf(fraction);
+                let closest = candidates
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| !keep[*i])
+                    .min_by(|(_, a), (_, b)| {
+                        (a.1 as f64 - target).abs().total_cmp(&(b.1 as f64 - target).abs())
+                    })
+                    .map(|(i, _)| i);
+                if let Some(i) = closest {
+                    keep[i] = true;
+                }
+            }
+        }
+
+        let mut pruned: Vec<usize> = candidates
+            .iter()
+            .zip(keep)
+            .filter(|(_, kept)| !kept)
+            .map(|((index, _), _)| *index)
+            .collect();
+        pruned.sort_unstable();
+        pruned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_and_default_tags_do_not_protect() {
+        let defaults = vec!["backend".to_string()];
+        let auto = auto_tags(&"analyze", 7);
+        assert_eq!(auto, vec!["analyze".to_string(), "iter_7".to_string()]);
+
+        assert!(!has_user_tags(&auto, &"analyze", 7, &defaults));
+        let mut tags = auto.clone();
+        tags.push("backend".to_string());
+        assert!(!has_user_tags(&tags, &"analyze", 7, &defaults));
+
+        tags.push("before-refactor".to_string());
+        assert!(has_user_tags(&tags, &"analyze", 7, &defaults));
+        // Another checkpoint's step or iteration tag was added by a user
+        assert!(has_user_tags(&auto, &"analyze", 8, &defaults));
+    }
+
+    fn tagged(count: usize, tagged: &[usize]) -> Vec<bool> {
+        (0..count).map(|i| tagged.contains(&i)).collect()
+    }
+
+    #[test]
+    fn test_select_keeps_first_latest_tagged_and_spaces_the_rest() {
+        let count = 101;
+        let pruning = CheckpointPruning {
+            max_checkpoints: 20,
+            preserve_tagged: true,
+        };
+        let pruned = pruning.select(&tagged(count, &[5]));
+
+        assert_eq!(count - pruned.len(), 20);
+        assert!(!pruned.contains(&0));
+        assert!(!pruned.contains(&5));
+        assert!(pruned.iter().all(|&i| i < count - pruning.keep_latest()));
+
+        // Kept history gets sparser the further back it goes
+        let kept_old: Vec<usize> = (1..count - pruning.keep_latest())
+            .filter(|i| *i != 5 && !pruned.contains(i))
+            .collect();
+        let gaps: Vec<usize> = kept_old.windows(2).map(|w| w[1] - w[0]).collect();
+        assert!(gaps.first() > gaps.last());
+
+        // Tags are ignored when preserve_tagged is off
+        let unprotected = CheckpointPruning {
+            preserve_tagged: false,
+            ..pruning
+        };
+        assert_eq!(count - unprotected.select(&tagged(count, &[5])).len(), 20);
+
+        // Under the limit, or with pruning disabled, nothing is selected
+        assert!(pruning.select(&tagged(20, &[])).is_empty());
+        let disabled = CheckpointPruning {
+            max_checkpoints: 0,
+            ..pruning
+        };
+        assert!(disabled.select(&tagged(count, &[])).is_empty());
+    }
+
+    #[test]
+    fn test_select_never_keeps_more_untagged_than_the_limit() {
+        let single = CheckpointPruning {
+            max_checkpoints: 1,
+            preserve_tagged: true,
+        };
+        // Only the latest is left, plus tagged checkpoints
+        assert_eq!(single.select(&tagged(2, &[])), vec![0]);
+        assert_eq!(single.select(&tagged(5, &[])), vec![0, 1, 2, 3]);
+        assert_eq!(single.select(&tagged(5, &[2])), vec![0, 1, 3]);
+
+        for max in 1..=12u32 {
+            let pruning = CheckpointPruning {
+                max_checkpoints: max,
+                preserve_tagged: true,
+            };
+            for count in max as usize + 1..60 {
+                let pruned = pruning.select(&tagged(count, &[3]));
+                let kept_untagged = (0..count).filter(|i| *i != 3 && !pruned.contains(i)).count();
+                assert!(kept_untagged <= max as usize, "max {} count {}", max, count);
+                assert!(!pruned.contains(&(count - 1)));
+                assert!(!pruned.contains(&3));
+            }
+        }
+    }
+}
@@ -674,6 +674,7 @@ impl CheckpointRestoration {
                     uncompressed_size: 0,
                     description: Some("Empty checkpoint due to restoration failure".to_string()),
                     tags: vec![],
+                    shadow_commit: None,
                 },
                 agent_state: AgentStateSnapshot {
                     current_mode: "confirm".to_string(),
@@ -703,6 +704,7 @@ impl CheckpointRestoration {
                         total_messages: 0,
                         estimated_cost: Some(0.0),
                         api_calls: 0,
+                        token_usage: Default::default(),
                     },
                 },
                 file_system_state: FileSystemSnapshot {
@@ -759,6 +761,9 @@ impl CheckpointRestoration {
                 description: Some("Failed restoration".to_string()),
                 tags: vec![],
                 size_bytes: 0,
+                conversation_stats: None,
+                parent_session_id: None,
+                parent_checkpoint_id: None,
             },
             restoration_metadata: RestorationMetadata {
                 restored_at: Utc::now(),
@@ -1106,6 +1111,7 @@ mod tests {
                 uncompressed_size: 2048,
                 description: Some("Test checkpoint".to_string()),
                 tags: vec![],
+                shadow_commit: None,
             },
             agent_state: AgentStateSnapshot {
                 current_mode: "confirm".to_string(),
@@ -1135,6 +1141,7 @@ mod tests {
                     total_messages: 0, // Matches empty messages vec
                     estimated_cost: Some(0.01),
                     api_calls: 1,
+                    token_usage: Default::default(),
                 },
             },
             file_system_state: FileSystemSnapshot {
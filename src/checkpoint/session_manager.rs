@@ -26,15 +26,19 @@
 //! ```
 
 use crate::checkpoint::{
-    AgentContext, CheckpointStorageManager, ResumeTracker, SessionStorage,
+    AgentContext, CheckpointCipher, CheckpointStorageManager, CompressionSettings, EventEnvelope,
+    CheckpointPruning, EventType, FileRestorePlan, ResumeTracker, SessionStorage, ShadowDiff,
+    ShadowRepo, ShadowRestore, WorkspaceTracker,
 };
+use crate::checkpoint::config::GitIntegrationConfig;
 use crate::checkpoint::models::{
     AgentStateSnapshot, Checkpoint, CheckpointMetadata, ConversationSnapshot,
     ConversationStats, EnvironmentSnapshot, ExecutionContext, FileSystemSnapshot, ModelConfig,
     ProcessInfo, ResourceUsage, ToolStateSnapshot,
 };
 use anyhow::{Context, Result};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Generic session manager for agent sessions.
 ///
@@ -545,9 +549,10 @@ impl SessionManager {
                     context.add_user_message(msg.content.clone(), msg.name.clone());
                 }
                 "assistant" => {
-                    context.add_assistant_message_with_reasoning(
+                    context.add_assistant_message_with_thinking(
                         msg.content.clone(),
                         msg.reasoning.clone(),
+                        msg.thinking_blocks.clone(),
                         msg.tool_calls.clone(),
                         msg.name.clone(),
                     );
@@ -572,6 +577,15 @@ impl SessionManager {
             }
         }
 
+        context.restore_usage_stats(&checkpoint.conversation_state.conversation_stats);
+
+        // Keep tracking the files changed before the checkpoint, so later
+        // snapshots can still roll them back
+        match WorkspaceTracker::from_snapshot(&checkpoint.file_system_state, &session_storage.blob_store()) {
+            Ok(tracker) => context.restore_workspace_tracker(tracker),
+            Err(e) => context.log_info(&format!("Could not restore tracked workspace files: {}", e)),
+        }
+
         // Initialize checkpoint session with existing session if enabled
         if self.checkpointing_enabled {
             if let Some(ref checkpoint_manager) = self.storage_manager {
@@ -632,11 +646,7 @@ impl SessionManager {
         let (session_id, description) = if let Some(identity) = context.get_session_identity() {
             (identity.id.clone(), identity.name.clone())
         } else {
-            // Generate unique session ID: session_YYYY_MM_DD_HH_MM_{uuid8}
-            let timestamp = chrono::Utc::now().format("%Y_%m_%d_%H_%M");
-            let uuid_suffix = uuid::Uuid::new_v4().simple().to_string();
-            let uuid8 = &uuid_suffix[..8];
-            (format!("session_{}_{}", timestamp, uuid8), Some(task_description.chars().take(80).collect()))
+            (super::utils::generate_session_id(), Some(task_description.chars().take(80).collect()))
         };
 
         // Create the session with description
@@ -704,11 +714,74 @@ impl SessionManager {
         Ok(())
     }
 
+    /// Roll workspace files back to their state at `checkpoint_id` in the
+    /// current session. Use `dry_run` to preview the diff first.
+    pub async fn restore_files(&self, checkpoint_id: &str, dry_run: bool) -> Result<FileRestorePlan> {
+        let session = self
+            .current_session
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No active session for file restore"))?;
+        Ok(session.restore_files(checkpoint_id, dry_run).await?)
+    }
+
+    /// Compare the shadow git snapshots of two checkpoints in the current session.
+    pub fn diff_snapshots(&self, from_checkpoint_id: &str, to_checkpoint_id: &str) -> Result<ShadowDiff> {
+        let session = self
+            .current_session
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No active session for snapshot diff"))?;
+        Ok(session.diff_snapshots(from_checkpoint_id, to_checkpoint_id)?)
+    }
+
+    /// Restore workspace files (all when `paths` is empty) from the shadow git
+    /// snapshot of `checkpoint_id` in the current session.
+    pub fn restore_snapshot(&self, checkpoint_id: &str, paths: &[PathBuf]) -> Result<ShadowRestore> {
+        let session = self
+            .current_session
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No active session for snapshot restore"))?;
+        Ok(session.restore_snapshot(checkpoint_id, paths)?)
+    }
+
+    /// Repository used to find the files a shell command changes, while a
+    /// session is recording checkpoints with shadow snapshots enabled
+    /// (see [`SessionStorage::workspace_repo`]).
+    pub fn workspace_repo(&self) -> Option<ShadowRepo> {
+        if !self.checkpointing_enabled {
+            return None;
+        }
+        self.current_session.as_ref()?.workspace_repo()
+    }
+
     /// Get the current session ID, if any.
     pub fn current_session_id(&self) -> Option<&str> {
         self.current_session.as_ref().map(|s| s.session_id())
     }
 
+    /// Append an event to the current session's `events.jsonl`.
+    ///
+    /// No-op when there is no active session or the session is not stored locally.
+    pub fn record_event(&self, event_type: EventType, payload: serde_json::Value) -> Result<()> {
+        let Some(ref session) = self.current_session else {
+            return Ok(());
+        };
+        if !session.session_path().exists() {
+            return Ok(());
+        }
+
+        let log = session.events_log();
+        let sequence = log.last_sequence()? + 1;
+        let event = EventEnvelope::new(
+            event_type,
+            session.session_id(),
+            session.project_hash(),
+            sequence,
+            payload,
+        );
+        log.append(&event)?;
+        Ok(())
+    }
+
     /// Build a complete checkpoint with all state data.
     async fn build_checkpoint<C: AgentContext>(
         &self,
@@ -741,10 +814,9 @@ impl SessionManager {
                 context.get_current_step(),
                 iteration
             )),
-            tags: vec![
-                context.get_current_step().to_string(),
-                format!("iter_{}", iteration),
-            ],
+            // Automatic tags don't exempt a checkpoint from pruning; user tags do
+            tags: crate::checkpoint::auto_tags(&context.get_current_step(), iteration),
+            shadow_commit: None,
         };
 
         // Capture agent state
@@ -761,7 +833,7 @@ impl SessionManager {
         };
 
         // Capture conversation state
-        let conversation_state = ConversationSnapshot {
+        let mut conversation_state = ConversationSnapshot {
             messages: context.get_messages(),
             system_prompt: "Simpaticoder System".to_string(),
             context_window_size: context.count_tokens(),
@@ -777,9 +849,11 @@ impl SessionManager {
                 total_tokens: context.count_tokens(),
                 total_messages: context.get_message_count(),
                 estimated_cost: None,
-                api_calls: 0, // TODO: track API calls
+                api_calls: 0,
+                token_usage: Default::default(),
             },
         };
+        context.fill_usage_stats(&mut conversation_state.conversation_stats);
 
         // Capture environment snapshot
         let environment_state = EnvironmentSnapshot {
@@ -801,13 +875,24 @@ impl SessionManager {
             },
         };
 
-        // Create file system snapshot (placeholder)
-        let filesystem_state = FileSystemSnapshot {
-            working_directory: context.get_working_directory().to_path_buf(),
-            tracked_files: vec![],
-            modified_files: vec![],
-            git_status: None,
-            file_permissions: std::collections::HashMap::new(),
+        // Snapshot the files the agent touched, if it tracks any
+        let working_directory = context.get_working_directory();
+        let filesystem_state = match (context.workspace_tracker(), &self.current_session) {
+            (Some(tracker), Some(session)) if !tracker.is_empty() => {
+                super::file_snapshots::capture_file_system(
+                    working_directory,
+                    tracker,
+                    &session.blob_store(),
+                    session.snapshot_filter(),
+                )?
+            }
+            _ => FileSystemSnapshot {
+                working_directory: working_directory.to_path_buf(),
+                tracked_files: vec![],
+                modified_files: vec![],
+                git_status: None,
+                file_permissions: std::collections::HashMap::new(),
+            },
         };
 
         // Create tool state snapshot (placeholder)
@@ -857,8 +942,88 @@ impl SessionManager {
     }
 
     /// Get whether checkpointing is enabled.
+    ///
+    /// A project's `.abk/checkpoint.toml` can turn it off for the current session.
     pub fn is_checkpointing_enabled(&self) -> bool {
         self.checkpointing_enabled
+            && self
+                .current_session
+                .as_ref()
+                .and_then(|s| s.project_config().enabled)
+                .unwrap_or(true)
+    }
+
+    /// Checkpoint interval set by the current session's project, if any.
+    pub fn checkpoint_interval(&self) -> Option<u32> {
+        self.current_session
+            .as_ref()
+            .and_then(|s| s.project_config().auto_checkpoint_interval)
+    }
+
+    /// Get the compression applied to new checkpoints.
+    pub fn compression(&self) -> CompressionSettings {
+        self.storage_manager
+            .as_ref()
+            .map(|sm| sm.compression())
+            .unwrap_or_default()
+    }
+
+    /// Set the compression applied to checkpoints saved from now on.
+    pub fn set_compression(&mut self, settings: CompressionSettings) {
+        if let Some(ref mut sm) = self.storage_manager {
+            sm.set_compression(settings);
+        }
+        if let Some(ref mut session) = self.current_session {
+            session.set_compression(settings);
+        }
+    }
+
+    /// Get the cipher used to encrypt checkpoint data, if any.
+    pub fn cipher(&self) -> Option<Arc<CheckpointCipher>> {
+        self.storage_manager.as_ref().and_then(|sm| sm.cipher())
+    }
+
+    /// Set the cipher for checkpoint data and events written from now on.
+    pub fn set_cipher(&mut self, cipher: Option<Arc<CheckpointCipher>>) {
+        if let Some(ref mut sm) = self.storage_manager {
+            sm.set_cipher(cipher.clone());
+        }
+        if let Some(ref mut session) = self.current_session {
+            session.set_cipher(cipher);
+        }
+    }
+
+    /// Get the checkpoint limit applied to sessions.
+    pub fn pruning(&self) -> CheckpointPruning {
+        self.storage_manager
+            .as_ref()
+            .map(|sm| sm.pruning())
+            .unwrap_or_default()
+    }
+
+    /// Set the checkpoint limit for the current and future sessions.
+    pub fn set_pruning(&mut self, pruning: CheckpointPruning) {
+        if let Some(ref mut sm) = self.storage_manager {
+            sm.set_pruning(pruning);
+        }
+        if let Some(ref mut session) = self.current_session {
+            session.set_pruning(pruning);
+        }
+    }
+
+    /// Get the git integration settings used for shadow snapshots.
+    pub fn git_integration(&self) -> GitIntegrationConfig {
+        self.storage_manager
+            .as_ref()
+            .map(|sm| sm.git_integration().clone())
+            .unwrap_or_default()
+    }
+
+    /// Set the git integration settings for sessions started from now on.
+    pub fn set_git_integration(&mut self, config: GitIntegrationConfig) {
+        if let Some(ref mut sm) = self.storage_manager {
+            sm.set_git_integration(config);
+        }
     }
 
     /// Get the current iteration number.
@@ -0,0 +1,721 @@
+//! Shadow git snapshots of the workspace
+//!
+//! With `[checkpointing.git_integration] enabled = true`, every checkpoint
+//! commits the project's working tree into a bare *shadow* repository kept
+//! outside the project — next to the project's checkpoint data, or under
+//! `shadow_repo_location`. The user's own `.git` (index, refs, hooks) is
+//! never touched; `.gitignore` rules are honored unless
+//! `exclude_gitignored_files` is turned off, and a [`SnapshotFilter`] built
+//! from the project's include/exclude patterns narrows the files further.
+//!
+//! The commit id is recorded as the checkpoint's `shadow_commit`, so any two
+//! checkpoints can be compared with [`ShadowRepo::diff`] and files can be put
+//! back with [`ShadowRepo::restore`]. A restore first commits the current
+//! tree, so it can itself be undone.
+//!
+//! Only files the filter selects and no larger than
+//! [`MAX_TRACKED_FILE_BYTES`] are staged, so large build outputs or datasets
+//! never enter the object store.
+//!
+//! A [`ShellCommandWatch`] uses the same object store to find the files a
+//! shell command changed, so they can be added to file snapshots. Watches
+//! only run while shadow snapshots are enabled. The trees they write are not
+//! referenced by any commit; `git gc --auto`, run after each snapshot, packs
+//! the repository and prunes those objects once they are a day old.
+//!
+//! `auto_commit_before_checkpoint` is the one setting that writes to the
+//! project's own repository: [`commit_project_changes`] commits pending
+//! changes there before each checkpoint.
+//!
+//! Snapshots shell out to the `git` binary.
+
+use super::config::GitIntegrationConfig;
+use super::file_snapshots::{SnapshotFilter, MAX_TRACKED_FILE_BYTES};
+use std::collections::HashSet;
+use std::io::Write;
+use super::models::{CheckpointMetadata, FileChangeType};
+use super::{CheckpointError, CheckpointResult};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Directory name of the shadow repository inside the project storage dir
+pub const SHADOW_REPO_DIR: &str = "shadow.git";
+
+/// Identity used for shadow commits
+const SHADOW_AUTHOR_NAME: &str = "abk-checkpoint";
+const SHADOW_AUTHOR_EMAIL: &str = "checkpoint@abk.local";
+
+/// A bare git repository tracking a project's working tree
+#[derive(Debug, Clone)]
+pub struct ShadowRepo {
+    git_dir: PathBuf,
+    work_tree: PathBuf,
+    exclude_gitignored: bool,
+    message_template: String,
+    filter: SnapshotFilter,
+}
+
+/// A file that differs between two snapshots
+#[derive(Debug, Clone)]
+pub struct ShadowChange {
+    /// Path relative to the work tree
+    pub path: PathBuf,
+    /// `Created`, `Modified` or `Deleted` going from the first snapshot to the second
+    pub change_type: FileChangeType,
+}
+
+/// Differences between two snapshots
+#[derive(Debug, Clone)]
+pub struct ShadowDiff {
+    /// Older snapshot
+    pub from_commit: String,
+    /// Newer snapshot
+    pub to_commit: String,
+    /// Files that differ
+    pub changes: Vec<ShadowChange>,
+    /// Unified diff of the changes (binary files are listed, not shown)
+    pub patch: String,
+}
+
+/// Outcome of restoring files from a snapshot
+#[derive(Debug, Clone)]
+pub struct ShadowRestore {
+    /// Snapshot of the tree taken just before the restore
+    pub backup_commit: String,
+    /// Files written back from the snapshot
+    pub restored: Vec<PathBuf>,
+    /// Files removed because they did not exist in the snapshot
+    pub removed: Vec<PathBuf>,
+}
+
+impl ShadowRepo {
+    /// Shadow repository at `git_dir` tracking `work_tree`
+    pub fn new(git_dir: impl Into<PathBuf>, work_tree: impl Into<PathBuf>) -> Self {
+        Self {
+            git_dir: git_dir.into(),
+            work_tree: work_tree.into(),
+            exclude_gitignored: true,
+            message_template: "Checkpoint: {checkpoint_id} - {workflow_step}".to_string(),
+            filter: SnapshotFilter::default(),
+        }
+    }
+
+    /// Only snapshot files selected by `filter`
+    pub fn with_filter(mut self, filter: SnapshotFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Build the shadow repository for a project, if snapshots are enabled
+    ///
+    /// The repository lives at `<shadow_repo_location>/<project_hash>.git`
+    /// when a location is configured, otherwise at `project_dir/shadow.git`.
+    pub fn from_config(
+        config: &GitIntegrationConfig,
+        project_dir: &Path,
+        project_hash: &str,
+        project_path: &Path,
+    ) -> Option<Self> {
+        if !config.enabled || !config.create_git_snapshots {
+            return None;
+        }
+        let git_dir = match config.shadow_repo_location {
+            Some(ref location) => location.join(format!("{}.git", project_hash)),
+            None => project_dir.join(SHADOW_REPO_DIR),
+        };
+        Some(Self {
+            exclude_gitignored: config.exclude_gitignored_files,
+            message_template: config.commit_message_template.clone(),
+            ..Self::new(git_dir, project_path)
+        })
+    }
+
+    /// Path of the bare repository
+    pub fn git_dir(&self) -> &Path {
+        &self.git_dir
+    }
+
+    /// Directory whose contents are snapshotted
+    pub fn work_tree(&self) -> &Path {
+        &self.work_tree
+    }
+
+    /// Commit the working tree for a checkpoint of `session_id` and return
+    /// the commit id
+    pub fn snapshot(&self, metadata: &CheckpointMetadata, session_id: &str) -> CheckpointResult<String> {
+        let message = self
+            .message_template
+            .replace("{checkpoint_id}", &metadata.checkpoint_id)
+            .replace("{session_id}", session_id)
+            .replace("{workflow_step}", &metadata.workflow_step.to_string())
+            .replace("{iteration}", &metadata.iteration.to_string());
+        self.commit(&message)
+    }
+
+    /// Commit the working tree with `message` and return the commit id
+    ///
+    /// A commit is made even when nothing changed, so every checkpoint has
+    /// its own entry in the shadow history.
+    pub fn commit(&self, message: &str) -> CheckpointResult<String> {
+        self.ensure_initialized()?;
+        self.stage_work_tree()?;
+        let tree = self.git(&["write-tree"])?;
+
+        let parent = self.head()?;
+        let mut args = vec!["commit-tree", tree.as_str(), "-m", message];
+        if let Some(ref parent) = parent {
+            args.extend(["-p", parent.as_str()]);
+        }
+        let commit = self.git(&args)?;
+        self.git(&["update-ref", "HEAD", &commit])?;
+        self.collect_garbage()?;
+        Ok(commit)
+    }
+
+    /// Pack the repository and prune unreferenced objects, such as the trees
+    /// written by [`ShellCommandWatch`], once git decides it is worth it
+    ///
+    /// Objects younger than a day are kept so a watch still in progress can
+    /// read its tree.
+    fn collect_garbage(&self) -> CheckpointResult<()> {
+        self.git(&[
+            "-c", "gc.autoDetach=false",
+            "-c", "gc.pruneExpire=1.day.ago",
+            "gc", "--auto", "--quiet",
+        ])?;
+        Ok(())
+    }
+
+    /// Compare the trees of two snapshots
+    pub fn diff(&self, from_commit: &str, to_commit: &str) -> CheckpointResult<ShadowDiff> {
+        let changes = self.changed_paths(&["diff-tree", "-r", "-z", "--no-renames", "--name-status", from_commit, to_commit])?;
+        let patch = self.git_raw(&["diff", "--no-color", "--no-renames", "--no-ext-diff", from_commit, to_commit])?;
+        Ok(ShadowDiff {
+            from_commit: from_commit.to_string(),
+            to_commit: to_commit.to_string(),
+            changes,
+            patch,
+        })
+    }
+
+    /// Put the working tree (or just `paths`) back to a snapshot
+    ///
+    /// Files changed since the snapshot are overwritten, and files created
+    /// since are removed. Paths are relative to the work tree; an empty
+    /// slice restores everything.
+    pub fn restore(&self, commit: &str, paths: &[PathBuf]) -> CheckpointResult<ShadowRestore> {
+        self.git(&["cat-file", "-e", &format!("{}^{{commit}}", commit)])
+            .map_err(|_| CheckpointError::restoration(format!("Snapshot {} not found in shadow repository", commit)))?;
+
+        let backup_commit = self.commit(&format!("Before restoring {}", commit))?;
+
+        let mut args: Vec<String> = ["diff-tree", "-r", "-z", "--no-renames", "--name-status", commit, &backup_commit]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        if !paths.is_empty() {
+            args.push("--".to_string());
+            args.extend(paths.iter().map(|p| p.to_string_lossy().into_owned()));
+        }
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let changes = self.changed_paths(&args)?;
+
+        let mut restored = Vec::new();
+        let mut removed = Vec::new();
+        for change in changes {
+            match change.change_type {
+                // Created after the snapshot
+                FileChangeType::Created => {
+                    let file = self.work_tree.join(&change.path);
+                    if file.is_file() || file.is_symlink() {
+                        std::fs::remove_file(&file)?;
+                    }
+                    removed.push(change.path);
+                }
+                _ => restored.push(change.path),
+            }
+        }
+
+        if !restored.is_empty() {
+            let mut args = vec!["checkout".to_string(), commit.to_string(), "--".to_string()];
+            args.extend(restored.iter().map(|p| p.to_string_lossy().into_owned()));
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            self.git(&args)?;
+        }
+        // Keep the index in step with the working tree for the next snapshot
+        self.stage_work_tree()?;
+
+        Ok(ShadowRestore {
+            backup_commit,
+            restored,
+            removed,
+        })
+    }
+
+    /// Current shadow HEAD, if any snapshot has been taken
+    pub fn head(&self) -> CheckpointResult<Option<String>> {
+        if !self.git_dir.exists() {
+            return Ok(None);
+        }
+        Ok(self.git(&["rev-parse", "-q", "--verify", "HEAD"]).ok())
+    }
+
+    fn ensure_initialized(&self) -> CheckpointResult<()> {
+        if self.git_dir.join("HEAD").is_file() {
+            return Ok(());
+        }
+        std::fs::create_dir_all(&self.git_dir)?;
+        let output = Command::new("git")
+            .args(["init", "-q", "--bare"])
+            .arg(&self.git_dir)
+            .output()
+            .map_err(|e| CheckpointError::storage(format!("Failed to run git: {}", e)))?;
+        if !output.status.success() {
+            return Err(CheckpointError::storage(format!(
+                "Failed to create shadow repository {}: {}",
+                self.git_dir.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        // Never snapshot the shadow repository itself
+        if let Ok(inside) = self.git_dir.strip_prefix(&self.work_tree) {
+            let exclude = self.git_dir.join("info").join("exclude");
+            std::fs::create_dir_all(exclude.parent().unwrap_or(&self.git_dir))?;
+            std::fs::write(&exclude, format!("/{}/\n", inside.to_string_lossy().replace('\\', "/")))?;
+        }
+        Ok(())
+    }
+
+    /// Bring the shadow index in line with the working tree
+    ///
+    /// Files are checked against the filter and the size limit before they
+    /// are staged, so unselected content is never hashed into the object
+    /// store. Files that stopped being selected (or became ignored) since
+    /// they were first snapshotted are dropped from the index.
+    fn stage_work_tree(&self) -> CheckpointResult<()> {
+        let cached = self.git_raw(&["ls-files", "-z", "--cached"])?;
+        let untracked = if self.exclude_gitignored {
+            self.git_raw(&["ls-files", "-z", "--others", "--exclude-standard"])?
+        } else {
+            self.git_raw(&["ls-files", "-z", "--others"])?
+        };
+        let ignored = if self.exclude_gitignored {
+            self.git_raw(&["ls-files", "-z", "--cached", "--ignored", "--exclude-standard"])?
+        } else {
+            String::new()
+        };
+        let ignored: HashSet<&str> = ignored.split('\0').filter(|p| !p.is_empty()).collect();
+
+        let mut stage = Vec::new();
+        let mut unstage = Vec::new();
+        for path in cached.split('\0').filter(|p| !p.is_empty()) {
+            if !ignored.contains(path) && self.selects(path) {
+                stage.push(path);
+            } else {
+                unstage.push(path);
+            }
+        }
+        stage.extend(untracked.split('\0').filter(|p| !p.is_empty() && self.selects(p)));
+
+        self.unstage(&unstage)?;
+        if !stage.is_empty() {
+            let force = if self.exclude_gitignored { "-A" } else { "-Af" };
+            self.git_with_paths(&["add", force, "--pathspec-from-file=-", "--pathspec-file-nul"], &stage)?;
+        }
+        Ok(())
+    }
+
+    /// Whether `path` (relative to the work tree) belongs in a snapshot
+    ///
+    /// A missing file is selected so staging records its removal.
+    fn selects(&self, path: &str) -> bool {
+        if !self.filter.matches(Path::new(path)) {
+            return false;
+        }
+        match std::fs::symlink_metadata(self.work_tree.join(path)) {
+            Ok(meta) => !meta.is_dir() && meta.len() <= MAX_TRACKED_FILE_BYTES,
+            Err(_) => true,
+        }
+    }
+
+    /// Remove `paths` from the shadow index, leaving the files in place
+    fn unstage(&self, paths: &[&str]) -> CheckpointResult<()> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        self.git_with_paths(&["rm", "-q", "--cached", "--pathspec-from-file=-", "--pathspec-file-nul"], paths)?;
+        Ok(())
+    }
+
+    /// Parse `--name-status -z` output
+    fn changed_paths(&self, args: &[&str]) -> CheckpointResult<Vec<ShadowChange>> {
+        let output = self.git_raw(args)?;
+        let mut fields = output.split('\0').filter(|f| !f.is_empty());
+        let mut changes = Vec::new();
+        while let (Some(status), Some(path)) = (fields.next(), fields.next()) {
+            // Statuses describe the second tree relative to the first
+            let change_type = match status.chars().next() {
+                Some('A') => FileChangeType::Created,
+                Some('D') => FileChangeType::Deleted,
+                _ => FileChangeType::Modified,
+            };
+            changes.push(ShadowChange {
+                path: PathBuf::from(path),
+                change_type,
+            });
+        }
+        Ok(changes)
+    }
+
+    /// Run git against the shadow repository and return trimmed stdout
+    fn git(&self, args: &[&str]) -> CheckpointResult<String> {
+        Ok(self.git_raw(args)?.trim().to_string())
+    }
+
+    fn git_raw(&self, args: &[&str]) -> CheckpointResult<String> {
+        Ok(String::from_utf8_lossy(&self.git_bytes(args)?).into_owned())
+    }
+
+    fn git_bytes(&self, args: &[&str]) -> CheckpointResult<Vec<u8>> {
+        self.run_git(args, None)
+    }
+
+    /// Run git with `paths` passed NUL-separated on stdin, for
+    /// `--pathspec-from-file=-`
+    fn git_with_paths(&self, args: &[&str], paths: &[&str]) -> CheckpointResult<Vec<u8>> {
+        let mut input = Vec::new();
+        for path in paths {
+            input.extend_from_slice(path.as_bytes());
+            input.push(0);
+        }
+        self.run_git(args, Some(&input))
+    }
+
+    fn run_git(&self, args: &[&str], input: Option<&[u8]>) -> CheckpointResult<Vec<u8>> {
+        let mut child = Command::new("git")
+            .arg("--git-dir")
+            .arg(&self.git_dir)
+            .arg("--work-tree")
+            .arg(&self.work_tree)
+            .args([
+                "--literal-pathspecs",
+                "-c", "core.quotePath=false",
+                "-c", "core.autocrlf=false",
+                "-c", "commit.gpgSign=false",
+            ])
+            .args(NO_PROJECT_CODE)
+            .args(args)
+            .current_dir(&self.work_tree)
+            .env_remove("GIT_INDEX_FILE")
+            .env_remove("GIT_DIR")
+            .env_remove("GIT_WORK_TREE")
+            .env("GIT_AUTHOR_NAME", SHADOW_AUTHOR_NAME)
+            .env("GIT_AUTHOR_EMAIL", SHADOW_AUTHOR_EMAIL)
+            .env("GIT_COMMITTER_NAME", SHADOW_AUTHOR_NAME)
+            .env("GIT_COMMITTER_EMAIL", SHADOW_AUTHOR_EMAIL)
+            .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| CheckpointError::storage(format!("Failed to run git: {}", e)))?;
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            stdin.write_all(input)?;
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|e| CheckpointError::storage(format!("Failed to run git: {}", e)))?;
+        if !output.status.success() {
+            return Err(CheckpointError::storage(format!(
+                "git {} failed in shadow repository {}: {}",
+                subcommand(args),
+                self.git_dir.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(output.stdout)
+    }
+}
+
+/// Workspace state captured before a shell command
+///
+/// Shell commands can change any file, so their edits can't be predicted
+/// like those of `write`/`edit`. [`start`](Self::start) writes the working
+/// tree to the shadow repository's object store as a tree (no commit is
+/// made and HEAD doesn't move); [`changed_files`](Self::changed_files)
+/// compares the tree after the command against it and returns the content
+/// each changed file had before.
+#[derive(Debug)]
+pub struct ShellCommandWatch {
+    repo: ShadowRepo,
+    tree: String,
+}
+
+impl ShellCommandWatch {
+    /// Capture the working tree of `repo`
+    pub fn start(repo: ShadowRepo) -> CheckpointResult<Self> {
+        repo.ensure_initialized()?;
+        repo.stage_work_tree()?;
+        let tree = repo.git(&["write-tree"])?;
+        Ok(Self { repo, tree })
+    }
+
+    /// Files changed since [`start`](Self::start), as absolute paths with
+    /// their previous content (`None` for files that didn't exist)
+    pub fn changed_files(&self) -> CheckpointResult<Vec<(PathBuf, Option<Vec<u8>>)>> {
+        self.repo.stage_work_tree()?;
+        let after = self.repo.git(&["write-tree"])?;
+        if after == self.tree {
+            return Ok(Vec::new());
+        }
+        let changes = self.repo.changed_paths(&["diff-tree", "-r", "-z", "--no-renames", "--name-status", &self.tree, &after])?;
+        let mut files = Vec::with_capacity(changes.len());
+        for change in changes {
+            let before = match change.change_type {
+                FileChangeType::Created => None,
+                _ => {
+                    let spec = format!("{}:{}", self.tree, change.path.to_string_lossy().replace('\\', "/"));
+                    Some(self.repo.git_bytes(&["cat-file", "blob", &spec])?)
+                }
+            };
+            files.push((self.repo.work_tree.join(&change.path), before));
+        }
+        Ok(files)
+    }
+}
+
+/// The git subcommand in `args`, skipping leading `-c <name>=<value>` pairs
+fn subcommand<'a>(args: &[&'a str]) -> &'a str {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if *arg != "-c" {
+            return arg;
+        }
+        args.next();
+    }
+    ""
+}
+
+/// Git config that keeps a project's hooks and fsmonitor from running.
+///
+/// Sandboxed commands may write to the project, so nothing in it may make
+/// host-side git execute code.
+const NO_PROJECT_CODE: [&str; 4] = ["-c", "core.hooksPath=/dev/null", "-c", "core.fsmonitor=false"];
+
+/// Commit all pending changes in the project's own git repository
+///
+/// Used for `auto_commit_before_checkpoint`. Returns the new commit id, or
+/// `None` when `project_path` is not inside a git work tree or nothing
+/// changed. The commit uses the user's configured git identity; hooks and
+/// fsmonitor are disabled.
+pub fn commit_project_changes(project_path: &Path, message: &str) -> CheckpointResult<Option<String>> {
+    let git = |args: &[&str]| -> CheckpointResult<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(project_path)
+            .args(["-c", "core.quotePath=false"])
+            .args(NO_PROJECT_CODE)
+            .args(args)
+            .env_remove("GIT_INDEX_FILE")
+            .env_remove("GIT_DIR")
+            .env_remove("GIT_WORK_TREE")
+            .output()
+            .map_err(|e| CheckpointError::storage(format!("Failed to run git: {}", e)))?;
+        if !output.status.success() {
+            return Err(CheckpointError::storage(format!(
+                "git {} failed in {}: {}",
+                args.first().copied().unwrap_or_default(),
+                project_path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    if git(&["rev-parse", "--is-inside-work-tree"]).map_or(true, |inside| inside != "true") {
+        return Ok(None);
+    }
+    if git(&["status", "--porcelain"])?.is_empty() {
+        return Ok(None);
+    }
+    git(&["add", "-A", "."])?;
+    git(&["commit", "-q", "-m", message])?;
+    git(&["rev-parse", "HEAD"]).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checkpoint::models::WorkflowStep;
+    use tempfile::TempDir;
+
+    fn metadata(checkpoint_id: &str) -> CheckpointMetadata {
+        CheckpointMetadata {
+            checkpoint_id: checkpoint_id.to_string(),
+            session_id: "session".to_string(),
+            project_hash: "hash".to_string(),
+            created_at: chrono::Utc::now(),
+            iteration: 1,
+            workflow_step: WorkflowStep::Apply,
+            checkpoint_version: "1.0".to_string(),
+            compressed_size: 0,
+            uncompressed_size: 0,
+            description: None,
+            tags: vec![],
+            shadow_commit: None,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_diff_and_restore() {
+        let tmp = TempDir::new().unwrap();
+        let work = tmp.path().join("project");
+        std::fs::create_dir_all(work.join("src")).unwrap();
+        std::fs::write(work.join("src/lib.rs"), "fn one() {}\n").unwrap();
+        std::fs::write(work.join(".gitignore"), "target/\n").unwrap();
+        std::fs::create_dir_all(work.join("target")).unwrap();
+        std::fs::write(work.join("target/out.bin"), "build output").unwrap();
+
+        let repo = ShadowRepo::new(tmp.path().join("storage").join(SHADOW_REPO_DIR), &work);
+        let repo = ShadowRepo {
+            message_template: "{session_id}: {checkpoint_id}".to_string(),
+            ..repo
+        };
+        let first = repo.snapshot(&metadata("001_analyze"), "session-1").unwrap();
+        assert_eq!(repo.git(&["log", "-1", "--format=%s"]).unwrap(), "session-1: 001_analyze");
+
+        std::fs::write(work.join("src/lib.rs"), "fn two() {}\n").unwrap();
+        std::fs::write(work.join("src/new.rs"), "// new\n").unwrap();
+        let second = repo.snapshot(&metadata("002_apply"), "session-1").unwrap();
+        assert_ne!(first, second);
+        assert!(!work.join(".git").exists());
+
+        let diff = repo.diff(&first, &second).unwrap();
+        let mut paths: Vec<_> = diff.changes.iter().map(|c| c.path.to_string_lossy().into_owned()).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["src/lib.rs", "src/new.rs"]);
+        assert!(diff.patch.contains("+fn two() {}"));
+        assert!(!diff.patch.contains("build output"));
+
+        let restore = repo.restore(&first, &[]).unwrap();
+        assert_eq!(std::fs::read_to_string(work.join("src/lib.rs")).unwrap(), "fn one() {}\n");
+        assert!(!work.join("src/new.rs").exists());
+        assert_eq!(restore.removed, vec![PathBuf::from("src/new.rs")]);
+        assert!(work.join("target/out.bin").exists());
+
+        // The restore can be undone from its backup snapshot
+        repo.restore(&restore.backup_commit, &[PathBuf::from("src/new.rs")]).unwrap();
+        assert_eq!(std::fs::read_to_string(work.join("src/new.rs")).unwrap(), "// new\n");
+        assert_eq!(std::fs::read_to_string(work.join("src/lib.rs")).unwrap(), "fn one() {}\n");
+    }
+
+    #[test]
+    fn test_shell_command_watch() {
+        let tmp = TempDir::new().unwrap();
+        let work = tmp.path().join("project");
+        std::fs::create_dir_all(&work).unwrap();
+        std::fs::write(work.join("keep.txt"), "same\n").unwrap();
+        std::fs::write(work.join("edit.txt"), "before\n").unwrap();
+        std::fs::write(work.join("gone.bin"), [0u8, 159, 146, 150]).unwrap();
+
+        let repo = ShadowRepo::new(tmp.path().join("storage").join(SHADOW_REPO_DIR), &work);
+        let watch = ShellCommandWatch::start(repo.clone()).unwrap();
+        assert!(watch.changed_files().unwrap().is_empty());
+
+        // What e.g. `sed -i`, `touch` and `rm` would do
+        std::fs::write(work.join("edit.txt"), "after\n").unwrap();
+        std::fs::write(work.join("new.txt"), "new\n").unwrap();
+        std::fs::remove_file(work.join("gone.bin")).unwrap();
+
+        let mut files = watch.changed_files().unwrap();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                (work.join("edit.txt"), Some(b"before\n".to_vec())),
+                (work.join("gone.bin"), Some(vec![0u8, 159, 146, 150])),
+                (work.join("new.txt"), None),
+            ]
+        );
+        // No commit was made
+        assert_eq!(repo.head().unwrap(), None);
+    }
+
+    #[test]
+    fn test_unselected_files_never_enter_the_object_store() {
+        let tmp = TempDir::new().unwrap();
+        let work = tmp.path().join("project");
+        std::fs::create_dir_all(&work).unwrap();
+        std::fs::write(work.join("main.py"), "print('hi')\n").unwrap();
+        std::fs::write(work.join("debug.log"), "secret log line\n").unwrap();
+        std::fs::File::create(work.join("data.bin"))
+            .unwrap()
+            .set_len(MAX_TRACKED_FILE_BYTES + 1)
+            .unwrap();
+
+        let filter = SnapshotFilter::new(&[], &["*.log".to_string()]).unwrap();
+        let repo = ShadowRepo::new(tmp.path().join("storage").join(SHADOW_REPO_DIR), &work).with_filter(filter);
+        let watch = ShellCommandWatch::start(repo.clone()).unwrap();
+        std::fs::write(work.join("main.py"), "print('bye')\n").unwrap();
+        assert_eq!(watch.changed_files().unwrap().len(), 1);
+        repo.commit("snapshot").unwrap();
+
+        assert_eq!(repo.git(&["ls-tree", "-r", "--name-only", "HEAD"]).unwrap(), "main.py");
+        let sizes = repo
+            .git(&["cat-file", "--batch-all-objects", "--batch-check=%(objectsize)"])
+            .unwrap();
+        assert!(sizes.lines().all(|size| size.parse::<u64>().unwrap() <= MAX_TRACKED_FILE_BYTES));
+        let log_blob = repo.git(&["hash-object", "debug.log"]).unwrap();
+        assert!(repo.git(&["cat-file", "-e", &log_blob]).is_err());
+
+        // A file that outgrows the limit is dropped from later snapshots
+        std::fs::File::options()
+            .write(true)
+            .open(work.join("main.py"))
+            .unwrap()
+            .set_len(MAX_TRACKED_FILE_BYTES + 1)
+            .unwrap();
+        repo.commit("too large").unwrap();
+        assert_eq!(repo.git(&["ls-tree", "-r", "--name-only", "HEAD"]).unwrap(), "");
+    }
+
+    #[test]
+    fn test_commit_project_changes() {
+        let tmp = TempDir::new().unwrap();
+        let work = tmp.path().join("project");
+        std::fs::create_dir_all(&work).unwrap();
+        assert_eq!(commit_project_changes(&work, "not a repository").unwrap(), None);
+
+        let git = |args: &[&str]| {
+            let status = Command::new("git").arg("-C").arg(&work).args(args).status().unwrap();
+            assert!(status.success());
+        };
+        git(&["init", "-q"]);
+        git(&["config", "user.name", "Test"]);
+        git(&["config", "user.email", "test@example.com"]);
+        git(&["config", "commit.gpgSign", "false"]);
+        assert_eq!(commit_project_changes(&work, "nothing yet").unwrap(), None);
+
+        // Neither hooks nor fsmonitor planted in the project may run
+        let marker = tmp.path().join("ran");
+        let script = work.join("planted.sh");
+        std::fs::write(&script, format!("#!/bin/sh\ntouch {}\nexit 1\n", marker.display())).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        std::fs::copy(&script, work.join(".git/hooks/pre-commit")).unwrap();
+        git(&["config", "core.fsmonitor", &script.to_string_lossy()]);
+
+        std::fs::write(work.join("main.rs"), "fn main() {}\n").unwrap();
+        let commit = commit_project_changes(&work, "Before checkpoint 001_analyze").unwrap().unwrap();
+        let log = Command::new("git").arg("-C").arg(&work).args(["log", "-1", "--format=%H %s"]).output().unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&log.stdout).trim(),
+            format!("{} Before checkpoint 001_analyze", commit)
+        );
+        assert_eq!(commit_project_changes(&work, "clean tree").unwrap(), None);
+        assert!(!marker.exists());
+    }
+}
@@ -8,7 +8,17 @@ use super::{
     StorageBackendConfig, StorageBackendType, StorageStats, ToolStateSnapshot,
     project_id_from_path,
 };
-use super::backend::{StorageBackend, StorageBackendExt};
+use super::archive::{self, ArchiveImportReport, ArchiveManifest, SessionCollisionPolicy};
+use super::backend::{ListOptions, StorageBackend, StorageBackendExt, StorageError};
+use super::compression::{self, CompressionSettings, EncodedBlob};
+use super::config::GitIntegrationConfig;
+use super::encryption::{self, CheckpointCipher, ReencryptReport};
+use super::file_snapshots::{
+    plan_file_restore, referenced_blobs, BlobGcReport, BlobStore, FileRestorePlan, SnapshotFilter,
+    BLOB_GC_MIN_AGE,
+};
+use super::pruning::{has_user_tags, CheckpointPruning, PruneReport};
+use super::shadow_git::{commit_project_changes, ShadowDiff, ShadowRepo, ShadowRestore};
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -27,6 +37,8 @@ pub struct CheckpointStorageManager {
     #[allow(dead_code)]
     current_project: Option<String>, // Currently active project ID
     config: GlobalCheckpointConfig,
+    /// Cipher for encryption at rest (`security.enable_encryption`)
+    cipher: Option<Arc<CheckpointCipher>>,
     /// Optional remote storage backend (DocumentDB/MongoDB)
     #[cfg(feature = "storage-documentdb")]
     remote_backend: Option<Arc<dyn StorageBackend + Send + Sync>>,
@@ -71,6 +83,7 @@ impl CheckpointStorageManager {
     pub fn with_home_dir(home_dir: PathBuf, agent_name: &str) -> CheckpointResult<Self> {
         let mut config = GlobalCheckpointConfig::default();
         config.storage_location = home_dir.clone();
+        let cipher = None;
 
         // Ensure storage directories exist
         ensure_global_storage_directories_for(&home_dir)?;
@@ -80,6 +93,7 @@ impl CheckpointStorageManager {
             agent_name: agent_name.to_string(),
             current_project: None,
             config,
+            cipher,
             #[cfg(feature = "storage-documentdb")]
             remote_backend: None,
         })
@@ -90,6 +104,7 @@ impl CheckpointStorageManager {
         let home_dir = config.storage_location.clone();
         let agent_name =
             std::env::var("ABK_AGENT_NAME").unwrap_or_else(|_| "NO_AGENT_NAME".to_string());
+        let cipher = CheckpointCipher::from_config(&config.security)?.map(Arc::new);
 
         // Ensure storage directories exist
         ensure_global_storage_directories_for(&home_dir)?;
@@ -99,6 +114,7 @@ impl CheckpointStorageManager {
             agent_name,
             current_project: None,
             config,
+            cipher,
             #[cfg(feature = "storage-documentdb")]
             remote_backend: None,
         })
@@ -110,6 +126,7 @@ impl CheckpointStorageManager {
         let home_dir = config.storage_location.clone();
         let agent_name =
             std::env::var("ABK_AGENT_NAME").unwrap_or_else(|_| "NO_AGENT_NAME".to_string());
+        let cipher = CheckpointCipher::from_config(&config.security)?.map(Arc::new);
 
         // Ensure storage directories exist
         ensure_global_storage_directories_for(&home_dir)?;
@@ -122,6 +139,7 @@ impl CheckpointStorageManager {
             agent_name,
             current_project: None,
             config,
+            cipher,
             remote_backend,
         })
     }
@@ -145,6 +163,7 @@ impl CheckpointStorageManager {
         let mut config = GlobalCheckpointConfig::default();
         config.storage_location = home_dir.clone();
         config.storage_backend = backend_config;
+        let cipher = None;
 
         // Ensure storage directories exist (always create local dirs, even in Remote mode,
         // because local fallback may be needed)
@@ -158,6 +177,7 @@ impl CheckpointStorageManager {
             agent_name: agent_name.to_string(),
             current_project: None,
             config,
+            cipher,
             remote_backend,
         })
     }
@@ -166,6 +186,85 @@ impl CheckpointStorageManager {
     pub fn storage_backend_config(&self) -> &StorageBackendConfig {
         &self.config.storage_backend
     }
+
+    /// Compression applied to checkpoints written through this manager
+    pub fn compression(&self) -> CompressionSettings {
+        CompressionSettings::from_config(&self.config)
+    }
+
+    /// Override `compression_enabled` and `performance.compression_level`
+    pub fn set_compression(&mut self, settings: CompressionSettings) {
+        self.config.compression_enabled = settings.enabled;
+        self.config.performance.compression_level = settings.level;
+    }
+
+    /// Checkpoint limit applied to sessions opened through this manager
+    pub fn pruning(&self) -> CheckpointPruning {
+        CheckpointPruning::from_config(&self.config)
+    }
+
+    /// Override `max_checkpoints_per_session` and `retention.preserve_tagged`
+    pub fn set_pruning(&mut self, pruning: CheckpointPruning) {
+        self.config.max_checkpoints_per_session = pruning.max_checkpoints;
+        self.config.retention.preserve_tagged = pruning.preserve_tagged;
+    }
+
+    /// Git integration settings applied to projects opened from this manager
+    pub fn git_integration(&self) -> &GitIntegrationConfig {
+        &self.config.git_integration
+    }
+
+    /// Override `git_integration` (shadow git snapshots per checkpoint)
+    pub fn set_git_integration(&mut self, config: GitIntegrationConfig) {
+        self.config.git_integration = config;
+    }
+
+    /// Cipher used to encrypt checkpoint data, if encryption is enabled
+    pub fn cipher(&self) -> Option<Arc<CheckpointCipher>> {
+        self.cipher.clone()
+    }
+
+    /// Enable (`Some`) or disable (`None`) encryption of new checkpoint data
+    pub fn set_cipher(&mut self, cipher: Option<Arc<CheckpointCipher>>) {
+        self.cipher = cipher;
+    }
+
+    /// Re-encrypt the data of every project with the current cipher
+    ///
+    /// Payloads and file snapshot blobs sealed with `previous` (or left
+    /// unencrypted) are rewritten under the current key; without a current
+    /// cipher they are decrypted. Checkpoint payloads in the remote backend,
+    /// when one is configured, are rewritten the same way. Data already
+    /// sealed with the current key is left untouched, so an interrupted
+    /// rotation can simply be run again.
+    pub async fn reencrypt_all(&self, previous: Option<&CheckpointCipher>) -> CheckpointResult<ReencryptReport> {
+        let mut report = ReencryptReport::default();
+        let projects_dir = self.home_dir.join("projects");
+        if projects_dir.exists() {
+            for project in std::fs::read_dir(&projects_dir)? {
+                let project_path = project?.path();
+                let blobs_dir = project_path.join("blobs");
+                if blobs_dir.is_dir() {
+                    reencrypt_blob_dir(&blobs_dir, previous, self.cipher.as_deref(), &mut report)?;
+                }
+                let sessions_dir = project_path.join("sessions");
+                if !sessions_dir.is_dir() {
+                    continue;
+                }
+                for session in std::fs::read_dir(&sessions_dir)? {
+                    let session_path = session?.path();
+                    if session_path.is_dir() {
+                        reencrypt_session_dir(&session_path, previous, self.cipher.as_deref(), &mut report)?;
+                    }
+                }
+            }
+        }
+        #[cfg(feature = "storage-documentdb")]
+        if let Some(ref backend) = self.remote_backend {
+            reencrypt_remote(backend.as_ref(), previous, self.cipher.as_deref(), &mut report).await?;
+        }
+        Ok(report)
+    }
     
     /// Create remote storage backend based on configuration
     #[cfg(feature = "storage-documentdb")]
@@ -222,6 +321,8 @@ impl CheckpointStorageManager {
     /// Get project storage for a given project path.
     ///
     /// Computes a project_id from the canonical path using SHA-256.
+    /// Settings from the project's `.abk/checkpoint.toml` are merged over the
+    /// global configuration.
     pub async fn get_project_storage(
         &self,
         project_path: &Path,
@@ -236,6 +337,9 @@ impl CheckpointStorageManager {
     /// (via [`crate::context::ProjectIdentity`]) where the `id` is used
     /// directly as the directory name (no hashing).
     ///
+    /// Fails if the project's `.abk/checkpoint.toml` cannot be parsed, rather
+    /// than snapshotting files its patterns exclude.
+    ///
     /// # Arguments
     /// * `project_path` - The working directory path (used for metadata)
     /// * `project_id` - Pre-computed project ID (from path hash or explicit identity)
@@ -245,7 +349,7 @@ impl CheckpointStorageManager {
         project_id: &str,
     ) -> CheckpointResult<ProjectStorage> {
         #[cfg(feature = "storage-documentdb")]
-        {
+        let mut storage = {
             let storage_mode = self.config.storage_backend.effective_storage_mode();
             ProjectStorage::with_remote_backend(
                 self.home_dir.clone(),
@@ -254,18 +358,34 @@ impl CheckpointStorageManager {
                 self.remote_backend.clone(),
                 storage_mode,
             )
-            .await
-        }
+            .await?
+        };
         
         #[cfg(not(feature = "storage-documentdb"))]
-        {
+        let mut storage = {
             ProjectStorage::new(
                 self.home_dir.clone(),
                 project_id.to_string(),
                 project_path.to_path_buf(),
             )
-            .await
+            .await?
+        };
+
+        // A project's `.abk/checkpoint.toml` takes precedence over the global settings
+        let project_config = ProjectCheckpointConfig::discover(project_path)?;
+        let config = match project_config {
+            Some(ref project) => project.merge_project_file_with_global(&self.config),
+            None => self.config.clone(),
+        };
+
+        storage.set_compression(CompressionSettings::from_config(&config));
+        storage.set_cipher(self.cipher.clone());
+        storage.set_git_integration(&config.git_integration);
+        storage.set_pruning(CheckpointPruning::from_config(&config));
+        if let Some(project) = project_config {
+            storage.set_project_config(project)?;
         }
+        Ok(storage)
     }
 
     /// List all projects in storage
@@ -530,13 +650,25 @@ pub struct ProjectStorage {
     storage_path: PathBuf, // ~/.{agent_name}/projects/<project_id>/
     #[allow(dead_code)]
     metadata: ProjectMetadata,
-    #[allow(dead_code)]
-    config: ProjectCheckpointConfig,
+    /// Overrides from the project's `.abk/checkpoint.toml`
+    config: Arc<ProjectCheckpointConfig>,
+    /// Files selected for snapshots by the project's patterns
+    snapshot_filter: SnapshotFilter,
     // Cache for session metadata to improve performance
     sessions_cache: std::sync::RwLock<Option<(std::time::Instant, Vec<SessionMetadata>)>>,
     cache_duration: std::time::Duration,
     /// Storage mode for checkpoints (local, remote, or mirror)
     storage_mode: super::config::StorageMode,
+    /// Compression applied to checkpoints of new sessions
+    compression: CompressionSettings,
+    /// Cipher for encryption at rest
+    cipher: Option<Arc<CheckpointCipher>>,
+    /// Shadow git repository snapshotting the project at each checkpoint
+    shadow_repo: Option<Arc<ShadowRepo>>,
+    /// Commit pending changes to the project's own repository before each checkpoint
+    auto_commit: bool,
+    /// Checkpoint limit for sessions, before project overrides
+    pruning: CheckpointPruning,
     /// Optional remote storage backend
     #[cfg(feature = "storage-documentdb")]
     remote_backend: Option<Arc<dyn StorageBackend + Send + Sync>>,
@@ -558,7 +690,7 @@ impl ProjectStorage {
 
         let metadata =
             load_or_create_project_metadata(&storage_path, &project_id, &project_path).await?;
-        let config = ProjectCheckpointConfig::default();
+        let config = Arc::new(ProjectCheckpointConfig::default());
 
         Ok(Self {
             project_id,
@@ -569,6 +701,12 @@ impl ProjectStorage {
             sessions_cache: std::sync::RwLock::new(None),
             cache_duration: std::time::Duration::from_secs(30), // Cache for 30 seconds
             storage_mode: super::config::StorageMode::Local,
+            compression: CompressionSettings::default(),
+            cipher: None,
+            shadow_repo: None,
+            auto_commit: false,
+            pruning: CheckpointPruning::default(),
+            snapshot_filter: SnapshotFilter::default(),
             #[cfg(feature = "storage-documentdb")]
             remote_backend: None,
         })
@@ -592,7 +730,7 @@ impl ProjectStorage {
 
         let metadata =
             load_or_create_project_metadata(&storage_path, &project_id, &project_path).await?;
-        let config = ProjectCheckpointConfig::default();
+        let config = Arc::new(ProjectCheckpointConfig::default());
 
         Ok(Self {
             project_id,
@@ -603,6 +741,12 @@ impl ProjectStorage {
             sessions_cache: std::sync::RwLock::new(None),
             cache_duration: std::time::Duration::from_secs(30),
             storage_mode,
+            compression: CompressionSettings::default(),
+            cipher: None,
+            shadow_repo: None,
+            auto_commit: false,
+            pruning: CheckpointPruning::default(),
+            snapshot_filter: SnapshotFilter::default(),
             remote_backend,
         })
     }
@@ -614,7 +758,7 @@ impl ProjectStorage {
     ) -> CheckpointResult<Self> {
         let project_id = metadata.project_hash.clone();
         let storage_path = base_path.join("projects").join(&project_id);
-        let config = ProjectCheckpointConfig::default();
+        let config = Arc::new(ProjectCheckpointConfig::default());
 
         Ok(Self {
             project_id,
@@ -625,6 +769,12 @@ impl ProjectStorage {
             sessions_cache: std::sync::RwLock::new(None),
             cache_duration: std::time::Duration::from_secs(30), // Cache for 30 seconds
             storage_mode: super::config::StorageMode::Local,
+            compression: CompressionSettings::default(),
+            cipher: None,
+            shadow_repo: None,
+            auto_commit: false,
+            pruning: CheckpointPruning::default(),
+            snapshot_filter: SnapshotFilter::default(),
             #[cfg(feature = "storage-documentdb")]
             remote_backend: None,
         })
@@ -646,6 +796,70 @@ impl ProjectStorage {
         &self.storage_mode
     }
 
+    /// Set the compression used by sessions opened from this project
+    pub fn set_compression(&mut self, settings: CompressionSettings) {
+        self.compression = settings;
+    }
+
+    /// Set the cipher used by sessions opened from this project
+    pub fn set_cipher(&mut self, cipher: Option<Arc<CheckpointCipher>>) {
+        self.cipher = cipher;
+    }
+
+    /// Snapshot the project into a shadow git repository at each checkpoint
+    ///
+    /// Has no effect unless `enabled` and `create_git_snapshots` are set.
+    /// With `enabled` and `auto_commit_before_checkpoint`, pending changes
+    /// are also committed to the project's own repository first.
+    pub fn set_git_integration(&mut self, config: &GitIntegrationConfig) {
+        self.shadow_repo = ShadowRepo::from_config(config, &self.storage_path, &self.project_id, &self.project_path)
+            .map(|repo| Arc::new(repo.with_filter(self.snapshot_filter.clone())));
+        self.auto_commit = config.enabled && config.auto_commit_before_checkpoint;
+    }
+
+    /// Shadow git repository of this project, if snapshots are enabled
+    pub fn shadow_repo(&self) -> Option<&ShadowRepo> {
+        self.shadow_repo.as_deref()
+    }
+
+    /// Set the checkpoint limit for sessions opened from this project
+    ///
+    /// The project's own `max_checkpoints_per_session` and `retention`
+    /// settings take precedence.
+    pub fn set_pruning(&mut self, pruning: CheckpointPruning) {
+        self.pruning = pruning;
+    }
+
+    /// Checkpoint limit applied to this project's sessions
+    pub fn pruning(&self) -> CheckpointPruning {
+        self.pruning.with_project_overrides(&self.config)
+    }
+
+    /// Apply the overrides from the project's `.abk/checkpoint.toml`
+    ///
+    /// Its include/exclude patterns select the files that are snapshotted,
+    /// and its `custom_tags` and `description_template` are applied to
+    /// checkpoints saved by this project's sessions.
+    pub fn set_project_config(&mut self, config: ProjectCheckpointConfig) -> CheckpointResult<()> {
+        self.snapshot_filter = SnapshotFilter::from_config(&config)?;
+        if let Some(ref repo) = self.shadow_repo {
+            let repo = ShadowRepo::clone(repo).with_filter(self.snapshot_filter.clone());
+            self.shadow_repo = Some(Arc::new(repo));
+        }
+        self.config = Arc::new(config);
+        Ok(())
+    }
+
+    /// Project overrides in effect
+    pub fn project_config(&self) -> &ProjectCheckpointConfig {
+        &self.config
+    }
+
+    /// Files selected for snapshots
+    pub fn snapshot_filter(&self) -> &SnapshotFilter {
+        &self.snapshot_filter
+    }
+
     /// Create a new session
     pub async fn create_session(&self, session_id: &str) -> CheckpointResult<SessionStorage> {
         self.create_session_with_description(session_id, None).await
@@ -681,6 +895,9 @@ impl ProjectStorage {
             description,
             tags: Vec::new(),
             size_bytes: 0,
+            conversation_stats: None,
+            parent_session_id: None,
+            parent_checkpoint_id: None,
         };
 
         // Save session metadata using atomic operations (local)
@@ -716,13 +933,193 @@ impl ProjectStorage {
 
         // Create session with remote backend and storage mode if configured
         #[cfg(feature = "storage-documentdb")]
-        {
-            SessionStorage::with_remote_backend(session_path, metadata, self.remote_backend.clone(), self.storage_mode.clone()).await
-        }
+        let mut session = SessionStorage::with_remote_backend(session_path, metadata, self.remote_backend.clone(), self.storage_mode.clone()).await?;
         
         #[cfg(not(feature = "storage-documentdb"))]
-        {
-            SessionStorage::new(session_path, metadata).await
+        let mut session = SessionStorage::new(session_path, metadata).await?;
+
+        session.set_compression(self.compression);
+        session.set_cipher(self.cipher.clone());
+        session.set_shadow_repo(self.shadow_repo.clone());
+        session.set_auto_commit(self.auto_commit.then(|| self.project_path.clone()));
+        session.set_pruning(self.pruning());
+        session.set_project_config(self.config.clone(), self.snapshot_filter.clone());
+        Ok(session)
+    }
+
+    /// Fork a session at one of its checkpoints
+    ///
+    /// Creates a new session holding the parent's checkpoints up to and
+    /// including `checkpoint_id` and the matching prefix of its `events.jsonl`,
+    /// so the run can continue from that point without touching the parent's
+    /// history. The new session records `parent_session_id` and
+    /// `parent_checkpoint_id`; its description defaults to the parent's.
+    pub async fn fork_session(
+        &self,
+        session_id: &str,
+        checkpoint_id: &str,
+        new_description: Option<String>,
+    ) -> CheckpointResult<SessionStorage> {
+        use super::config::StorageMode;
+
+        if matches!(self.storage_mode, StorageMode::Remote) {
+            return Err(CheckpointError::storage(
+                "Forking sessions requires local checkpoint storage",
+            ));
+        }
+
+        let parent_path = self.storage_path.join("sessions").join(session_id);
+        let parent_metadata_path = parent_path.join(SESSION_METADATA_FILENAME);
+        if !parent_metadata_path.exists() {
+            return Err(CheckpointError::SessionNotFound {
+                session_id: session_id.to_string(),
+            });
+        }
+        let parent: SessionMetadata = load_json(&parent_metadata_path).await?;
+
+        let parent_index = load_checkpoint_index(&parent_path).await?;
+        let fork_point = parent_index
+            .get(checkpoint_id)
+            .ok_or_else(|| CheckpointError::CheckpointNotFound {
+                checkpoint_id: checkpoint_id.to_string(),
+                session_id: session_id.to_string(),
+            })?
+            .clone();
+
+        let fork_id = super::utils::generate_session_id();
+        let fork_path = self.storage_path.join("sessions").join(&fork_id);
+        fs::create_dir_all(&fork_path).await?;
+
+        let session_agent = parent_path.join("session_agent.json");
+        if session_agent.exists() {
+            copy_payload(&session_agent, &fork_path.join("session_agent.json"), self.cipher.as_deref())?;
+        }
+
+        // Keep every checkpoint taken up to the fork point, re-homed to the fork
+        let mut index = HashMap::new();
+        for (id, metadata) in parent_index {
+            if metadata.created_at > fork_point.created_at {
+                continue;
+            }
+            for suffix in ["_conversation.json", "_files.json", ".json", "_agent.json", "_metadata.json"] {
+                let file_name = format!("{}{}", id, suffix);
+                let source = parent_path.join(&file_name);
+                if source.exists() {
+                    copy_payload(&source, &fork_path.join(&file_name), self.cipher.as_deref())?;
+                }
+            }
+            let mut metadata = metadata;
+            metadata.session_id = fork_id.clone();
+            index.insert(id, metadata);
+        }
+        AtomicOps::write_json(&fork_path.join("checkpoints.json"), &index)?;
+
+        super::EventsLog::new(&parent_path)
+            .with_cipher(self.cipher.clone())
+            .copy_prefix(&fork_path, &fork_id, fork_point.created_at)?;
+
+        let conversation_stats = read_payload::<ConversationSnapshot>(
+            &parent_path.join(format!("{}_conversation.json", checkpoint_id)),
+            self.cipher.as_deref(),
+        )
+        .ok()
+        .map(|conversation| conversation.conversation_stats);
+
+        let metadata = SessionMetadata {
+            session_id: fork_id,
+            project_hash: self.project_id.clone(),
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            checkpoint_count: index.len() as u32,
+            status: SessionStatus::Active,
+            description: new_description.or(parent.description),
+            tags: parent.tags,
+            size_bytes: 0,
+            conversation_stats,
+            parent_session_id: Some(session_id.to_string()),
+            parent_checkpoint_id: Some(checkpoint_id.to_string()),
+        };
+        AtomicOps::write_json(&fork_path.join(SESSION_METADATA_FILENAME), &metadata)?;
+
+        #[cfg(feature = "storage-documentdb")]
+        if matches!(self.storage_mode, StorageMode::Mirror) {
+            if let Some(ref backend) = self.remote_backend {
+                self.mirror_fork_to_remote(backend, &fork_path, &metadata, &index).await;
+            }
+        }
+
+        // Invalidate cache since we added a new session
+        self.invalidate_sessions_cache();
+
+        #[cfg(feature = "storage-documentdb")]
+        let mut session = SessionStorage::with_remote_backend(fork_path, metadata, self.remote_backend.clone(), self.storage_mode).await?;
+
+        #[cfg(not(feature = "storage-documentdb"))]
+        let mut session = SessionStorage::new(fork_path, metadata).await?;
+
+        session.set_compression(self.compression);
+        session.set_cipher(self.cipher.clone());
+        session.set_shadow_repo(self.shadow_repo.clone());
+        session.set_auto_commit(self.auto_commit.then(|| self.project_path.clone()));
+        session.set_pruning(self.pruning());
+        session.set_project_config(self.config.clone(), self.snapshot_filter.clone());
+        Ok(session)
+    }
+
+    /// Copy a freshly forked session to the remote backend (mirror mode)
+    ///
+    /// The local files are already encoded, so they are uploaded as-is under
+    /// the keys `save_checkpoint` uses. Failures are logged and leave the
+    /// local fork usable, like other mirrored writes.
+    #[cfg(feature = "storage-documentdb")]
+    async fn mirror_fork_to_remote(
+        &self,
+        backend: &Arc<dyn StorageBackend + Send + Sync>,
+        fork_path: &Path,
+        metadata: &SessionMetadata,
+        index: &HashMap<String, CheckpointMetadata>,
+    ) {
+        let warn = |what: &str, e: &dyn std::fmt::Display| {
+            crate::observability::tee_eprintln(&format!(
+                "[checkpoint] Warning: Failed to mirror forked session {} to remote: {}: {}",
+                metadata.session_id, what, e
+            ));
+        };
+        let session_key_prefix = format!("projects/{}/sessions/{}", self.project_id, metadata.session_id);
+
+        let mut files = vec![(
+            fork_path.join("session_agent.json"),
+            format!("{}/session_agent.json", session_key_prefix),
+        )];
+        for id in index.keys() {
+            for suffix in ["_conversation.json", "_files.json"] {
+                files.push((
+                    fork_path.join(format!("{}{}", id, suffix)),
+                    format!("{}/checkpoints/{}{}", session_key_prefix, id, suffix),
+                ));
+            }
+        }
+        for (path, key) in files {
+            // Not every checkpoint has a file snapshot
+            let Ok(bytes) = fs::read(&path).await else {
+                continue;
+            };
+            if let Err(e) = backend.write(&key, &bytes).await {
+                warn(&key, &e);
+            }
+        }
+
+        let project_key = format!("projects/{}/metadata.json", self.project_id);
+        if let Err(e) = backend.write_json(&project_key, &self.metadata).await {
+            warn("project metadata", &e);
+        }
+        let index_key = format!("{}/checkpoints/checkpoints.json", session_key_prefix);
+        if let Err(e) = backend.write_json(&index_key, index).await {
+            warn("checkpoints index", &e);
+        }
+        let metadata_key = format!("{}/metadata.json", session_key_prefix);
+        if let Err(e) = backend.write_json(&metadata_key, metadata).await {
+            warn("session metadata", &e);
         }
     }
 
@@ -851,6 +1248,9 @@ impl ProjectStorage {
                         description,
                         tags: Vec::new(),
                         size_bytes: 0,
+                        conversation_stats: None,
+                        parent_session_id: None,
+                        parent_checkpoint_id: None,
                     });
                 }
                 _ => {
@@ -870,6 +1270,9 @@ impl ProjectStorage {
                         description,
                         tags: Vec::new(),
                         size_bytes: 0,
+                        conversation_stats: None,
+                        parent_session_id: None,
+                        parent_checkpoint_id: None,
                     });
                 }
             }
@@ -956,6 +1359,12 @@ impl ProjectStorage {
 
         if session_path.exists() {
             fs::remove_dir_all(&session_path).await?;
+            if let Err(e) = self.collect_blob_garbage() {
+                crate::observability::tee_eprintln(&format!(
+                    "[checkpoint] Warning: Failed to remove unused file snapshots: {}",
+                    e
+                ));
+            }
         }
 
         // Invalidate cache after deletion
@@ -964,6 +1373,25 @@ impl ProjectStorage {
         Ok(())
     }
 
+    /// Delete workspace file blobs no checkpoint of the project refers to
+    ///
+    /// Blobs written in the last [`BLOB_GC_MIN_AGE`] are kept, as a running
+    /// session may not have recorded them yet. Runs after a session is
+    /// deleted and after pruning drops checkpoints with file snapshots.
+    ///
+    /// Shadow git snapshots are not collected: they form one linear history
+    /// in which every commit is an ancestor of the latest, so the commits of
+    /// pruned checkpoints stay in the shadow repository.
+    ///
+    /// In remote storage mode nothing is removed, since the file snapshots
+    /// referring to the blobs are only stored remotely.
+    pub fn collect_blob_garbage(&self) -> CheckpointResult<BlobGcReport> {
+        if matches!(self.storage_mode, super::config::StorageMode::Remote) {
+            return Ok(BlobGcReport::default());
+        }
+        collect_project_blob_garbage(&self.storage_path, self.cipher.as_ref(), BLOB_GC_MIN_AGE)
+    }
+
     /// Calculate project size
     pub async fn calculate_project_size(&self) -> CheckpointResult<u64> {
         calculate_directory_size(&self.storage_path).await
@@ -986,16 +1414,66 @@ impl ProjectStorage {
         Ok(cleaned_count)
     }
 
-    /// Export project data
-    pub async fn export_project_data(&self, _export_path: &Path) -> CheckpointResult<()> {
-        // TODO: Implement export logic
-        Ok(())
+    /// Export all sessions of this project to a portable archive
+    ///
+    /// The archive is a single tar file (see [`super::archive`]) containing
+    /// project metadata and every session directory, with a manifest holding
+    /// a SHA-256 digest per entry.
+    pub async fn export_project_data(&self, export_path: &Path) -> CheckpointResult<ArchiveManifest> {
+        let storage_path = self.storage_path.clone();
+        let project_id = self.project_id.clone();
+        let project_path = self.project_path.clone();
+        let export_path = export_path.to_path_buf();
+        // Archiving is blocking file I/O; keep it off the async worker threads
+        tokio::task::spawn_blocking(move || {
+            archive::write_archive(&storage_path, &project_id, &project_path, &export_path)
+        })
+        .await
+        .map_err(|e| CheckpointError::storage(format!("Export task failed: {}", e)))?
     }
 
-    /// Import project data
-    pub async fn import_project_data(&self, _import_path: &Path) -> CheckpointResult<()> {
-        // TODO: Implement import logic
-        Ok(())
+    /// Import sessions from a project archive, skipping sessions whose ID
+    /// already exists in this project
+    pub async fn import_project_data(&self, import_path: &Path) -> CheckpointResult<ArchiveImportReport> {
+        self.import_project_data_with_policy(import_path, SessionCollisionPolicy::Skip)
+            .await
+    }
+
+    /// Import sessions from a project archive
+    ///
+    /// The archive is verified against its manifest before anything is
+    /// written. Project IDs in imported metadata and event logs are rewritten
+    /// to this project's ID; `policy` decides what happens to sessions whose
+    /// ID already exists. Encrypted data is re-sealed with this project's
+    /// cipher, which must hold the key the archive was exported with. The
+    /// archived project metadata is merged into this project's, which keeps
+    /// its own ID, path and name.
+    pub async fn import_project_data_with_policy(
+        &self,
+        import_path: &Path,
+        policy: SessionCollisionPolicy,
+    ) -> CheckpointResult<ArchiveImportReport> {
+        let storage_path = self.storage_path.clone();
+        let project_id = self.project_id.clone();
+        let project_path = self.project_path.clone();
+        let cipher = self.cipher.clone();
+        let import_path = import_path.to_path_buf();
+        let report = tokio::task::spawn_blocking(move || {
+            let verified = archive::read_archive(&import_path, &storage_path)?;
+            let report =
+                archive::import_archive(&verified, &storage_path, &project_id, &project_path, cipher.as_deref(), policy)?;
+            if let Some(archived) = verified.project_metadata()? {
+                merge_project_metadata(&storage_path, &project_id, &project_path, &archived)?;
+            }
+            Ok::<_, CheckpointError>(report)
+        })
+        .await
+        .map_err(|e| CheckpointError::storage(format!("Import task failed: {}", e)))??;
+
+        // Invalidate cache since sessions were added
+        self.invalidate_sessions_cache();
+
+        Ok(report)
     }
 }
 
@@ -1008,6 +1486,20 @@ pub struct SessionStorage {
     storage_mode: super::config::StorageMode,
     /// Tracks whether `session_agent.json` has been written to local disk
     session_agent_written: bool,
+    /// Compression applied to conversation, file snapshots and their blobs
+    compression: CompressionSettings,
+    /// Cipher sealing checkpoint payloads and event lines
+    cipher: Option<Arc<CheckpointCipher>>,
+    /// Shadow git repository the working tree is committed to per checkpoint
+    shadow_repo: Option<Arc<ShadowRepo>>,
+    /// Project directory whose own repository gets a commit before each checkpoint
+    auto_commit_path: Option<PathBuf>,
+    /// Limit on the number of checkpoints kept
+    pruning: CheckpointPruning,
+    /// Project overrides supplying default tags and descriptions
+    project_config: Arc<ProjectCheckpointConfig>,
+    /// Files selected for workspace snapshots
+    snapshot_filter: SnapshotFilter,
     /// Optional remote storage backend for mirroring checkpoints
     #[cfg(feature = "storage-documentdb")]
     remote_backend: Option<Arc<dyn StorageBackend + Send + Sync>>,
@@ -1031,6 +1523,13 @@ impl SessionStorage {
             checkpoints,
             storage_mode: super::config::StorageMode::Local,
             session_agent_written,
+            compression: CompressionSettings::default(),
+            cipher: None,
+            shadow_repo: None,
+            auto_commit_path: None,
+            pruning: CheckpointPruning::default(),
+            project_config: Arc::default(),
+            snapshot_filter: SnapshotFilter::default(),
             #[cfg(feature = "storage-documentdb")]
             remote_backend: None,
             #[cfg(feature = "storage-documentdb")]
@@ -1064,6 +1563,13 @@ impl SessionStorage {
             checkpoints,
             storage_mode,
             session_agent_written,
+            compression: CompressionSettings::default(),
+            cipher: None,
+            shadow_repo: None,
+            auto_commit_path: None,
+            pruning: CheckpointPruning::default(),
+            project_config: Arc::default(),
+            snapshot_filter: SnapshotFilter::default(),
             remote_backend,
             session_agent_remote_written: false,
         })
@@ -1085,6 +1591,96 @@ impl SessionStorage {
         &self.storage_mode
     }
 
+    /// Set the compression used for checkpoints saved from now on
+    ///
+    /// Existing checkpoints keep their format; loading detects it per file.
+    pub fn set_compression(&mut self, settings: CompressionSettings) {
+        self.compression = settings;
+    }
+
+    /// Set the cipher for payloads and event lines written from now on
+    pub fn set_cipher(&mut self, cipher: Option<Arc<CheckpointCipher>>) {
+        self.cipher = cipher;
+    }
+
+    /// Commit the working tree to `shadow_repo` on every checkpoint saved
+    pub fn set_shadow_repo(&mut self, shadow_repo: Option<Arc<ShadowRepo>>) {
+        self.shadow_repo = shadow_repo;
+    }
+
+    /// Shadow git repository snapshots are committed to, if enabled
+    pub fn shadow_repo(&self) -> Option<&ShadowRepo> {
+        self.shadow_repo.as_deref()
+    }
+
+    /// Repository whose object store records the workspace before shell commands
+    ///
+    /// The shadow repository, so shell commands are only watched while shadow
+    /// snapshots are enabled; see [`ShellCommandWatch`](super::shadow_git::ShellCommandWatch).
+    pub fn workspace_repo(&self) -> Option<ShadowRepo> {
+        self.shadow_repo.as_deref().cloned()
+    }
+
+    /// Commit pending changes in the repository at `project_path` before every checkpoint saved
+    pub fn set_auto_commit(&mut self, project_path: Option<PathBuf>) {
+        self.auto_commit_path = project_path;
+    }
+
+    /// Set the checkpoint limit enforced after each save
+    pub fn set_pruning(&mut self, pruning: CheckpointPruning) {
+        self.pruning = pruning;
+    }
+
+    /// Apply a project's default tags, description template and snapshot patterns
+    pub fn set_project_config(&mut self, config: Arc<ProjectCheckpointConfig>, filter: SnapshotFilter) {
+        self.project_config = config;
+        self.snapshot_filter = filter;
+    }
+
+    /// Project overrides applied to this session's checkpoints
+    pub fn project_config(&self) -> &ProjectCheckpointConfig {
+        &self.project_config
+    }
+
+    /// Files selected for workspace snapshots
+    pub fn snapshot_filter(&self) -> &SnapshotFilter {
+        &self.snapshot_filter
+    }
+
+    /// Add the project's default tags and render its description template
+    fn apply_project_defaults(&self, metadata: &mut CheckpointMetadata) {
+        for tag in &self.project_config.custom_tags {
+            if !metadata.tags.contains(tag) {
+                metadata.tags.push(tag.clone());
+            }
+        }
+        if let Some(description) = self.project_config.render_description(metadata, &self.metadata.session_id) {
+            metadata.description = Some(description);
+        }
+    }
+
+    /// The session's `events.jsonl`, encrypted like its checkpoints
+    pub fn events_log(&self) -> super::EventsLog {
+        super::EventsLog::new(&self.session_path).with_cipher(self.cipher.clone())
+    }
+
+    /// Serialize the checkpoint payload stored as `file_name`, compressing
+    /// and sealing it as configured
+    fn encode_payload<T: serde::Serialize>(&self, value: &T, file_name: &str) -> CheckpointResult<EncodedBlob> {
+        let mut blob = self.compression.encode_json(value)?;
+        if let Some(ref cipher) = self.cipher {
+            let context = encryption::payload_context(&self.metadata.session_id, file_name);
+            blob.bytes = cipher.encrypt(&blob.bytes, &context)?;
+        }
+        Ok(blob)
+    }
+
+    /// Deserialize a payload read from `key` in the remote backend
+    #[cfg(feature = "storage-documentdb")]
+    fn decode_payload<T: serde::de::DeserializeOwned>(&self, key: &str, data: &[u8]) -> CheckpointResult<T> {
+        compression::decode_json(&encryption::open(data, self.cipher.as_deref(), &remote_payload_context(key))?)
+    }
+
     /// Save a checkpoint using optimized split-file format
     ///
     /// Storage behavior depends on storage_mode:
@@ -1099,27 +1695,91 @@ impl SessionStorage {
     ///
     /// **Per-checkpoint:**
     /// - `{checkpoint_id}_conversation.json` — conversation state (legitimately unique)
+    /// - `{checkpoint_id}_files.json` — workspace file snapshot, only when files were
+    ///   touched (contents live in the project's local blob store)
+    ///
+    /// These payloads are zstd-compressed when compression is enabled and
+    /// sealed with AES-256-GCM when a cipher is set. The stored and
+    /// uncompressed sizes are recorded in the checkpoint's index entry.
     ///
     /// **Index:**
     /// - `checkpoints.json` — contains all `CheckpointMetadata` entries (already existed)
     ///
+    /// With a shadow repository set, the working tree is committed to it first
+    /// and the commit id is stored as the entry's `shadow_commit`. A failed
+    /// snapshot is logged and does not fail the checkpoint.
+    ///
+    /// Sessions over `max_checkpoints_per_session` are pruned afterwards, in
+    /// local and remote storage (see [`prune_checkpoints`](Self::prune_checkpoints)).
+    ///
     /// Old sessions with per-checkpoint `_agent.json` / `_metadata.json` files
     /// remain readable via fallback in `try_load_from_local` / `try_load_from_remote`.
     pub async fn save_checkpoint(&mut self, checkpoint: &Checkpoint) -> CheckpointResult<()> {
         use super::config::StorageMode;
 
         let checkpoint_id = &checkpoint.metadata.checkpoint_id;
-        let session_id = &self.metadata.session_id;
+        let session_id = self.metadata.session_id.clone();
 
         let should_write_local = matches!(self.storage_mode, StorageMode::Local | StorageMode::Mirror);
         let should_write_remote = matches!(self.storage_mode, StorageMode::Remote | StorageMode::Mirror);
 
+        // Encode once for local and remote targets
+        let conversation_blob =
+            self.encode_payload(&checkpoint.conversation_state, &format!("{}_conversation.json", checkpoint_id))?;
+        let fs_state = &checkpoint.file_system_state;
+        let files_blob = if !fs_state.tracked_files.is_empty() || !fs_state.modified_files.is_empty() {
+            Some(self.encode_payload(fs_state, &format!("{}_files.json", checkpoint_id))?)
+        } else {
+            None
+        };
+        let mut metadata = checkpoint.metadata.clone();
+        self.apply_project_defaults(&mut metadata);
+        metadata.uncompressed_size = conversation_blob.uncompressed_size
+            + files_blob.as_ref().map_or(0, |blob| blob.uncompressed_size);
+        metadata.compressed_size = conversation_blob.stored_size()
+            + files_blob.as_ref().map_or(0, |blob| blob.stored_size());
+        if let Some(ref project_path) = self.auto_commit_path {
+            let project_path = project_path.clone();
+            let message = format!("Before checkpoint {}", checkpoint_id);
+            let result = tokio::task::spawn_blocking(move || commit_project_changes(&project_path, &message))
+                .await
+                .map_err(|e| CheckpointError::storage(format!("Auto-commit task failed: {}", e)))
+                .and_then(|result| result);
+            if let Err(e) = result {
+                crate::observability::tee_eprintln(&format!(
+                    "[checkpoint] Warning: Failed to commit project changes before checkpoint {}: {}",
+                    checkpoint_id, e
+                ));
+            }
+        }
+        if let (Some(repo), None) = (&self.shadow_repo, &metadata.shadow_commit) {
+            // git runs synchronously; keep it off the async worker threads
+            let repo = Arc::clone(repo);
+            let snapshot_metadata = metadata.clone();
+            let session_id = session_id.clone();
+            let result = tokio::task::spawn_blocking(move || repo.snapshot(&snapshot_metadata, &session_id))
+                .await
+                .map_err(|e| CheckpointError::storage(format!("Snapshot task failed: {}", e)))
+                .and_then(|result| result);
+            match result {
+                Ok(commit) => metadata.shadow_commit = Some(commit),
+                Err(e) => crate::observability::tee_eprintln(&format!(
+                    "[checkpoint] Warning: Failed to snapshot workspace for checkpoint {}: {}",
+                    checkpoint_id, e
+                )),
+            }
+        }
+
         // Write to local files if configured
         if should_write_local {
+            let write_started = std::time::Instant::now();
+            let mut written = Vec::new();
+
             // 1. Write session_agent.json ONCE (first checkpoint or if file doesn't exist yet)
             if !self.session_agent_written {
                 let agent_file = self.session_path.join("session_agent.json");
-                AtomicOps::write_json(&agent_file, &checkpoint.agent_state)?;
+                compression::write_blob(&agent_file, &self.encode_payload(&checkpoint.agent_state, "session_agent.json")?)?;
+                written.push(agent_file);
                 self.session_agent_written = true;
             }
 
@@ -1127,7 +1787,22 @@ impl SessionStorage {
             let conversation_file = self
                 .session_path
                 .join(format!("{}_conversation.json", checkpoint_id));
-            AtomicOps::write_json(&conversation_file, &checkpoint.conversation_state)?;
+            compression::write_blob(&conversation_file, &conversation_blob)?;
+            written.push(conversation_file);
+
+            // 3. Save workspace file snapshot when the agent touched any files
+            if let Some(ref files_blob) = files_blob {
+                let files_file = self.session_path.join(format!("{}_files.json", checkpoint_id));
+                compression::write_blob(&files_file, files_blob)?;
+                written.push(files_file);
+            }
+
+            let bytes = written
+                .iter()
+                .filter_map(|path| std::fs::metadata(path).ok())
+                .map(|m| m.len())
+                .sum();
+            crate::observability::metrics::record_checkpoint_write(write_started.elapsed(), bytes);
 
             crate::observability::tee_eprintln(
                 &format!("[checkpoint] ✅ Saved checkpoint {} to local storage", checkpoint_id)
@@ -1145,7 +1820,8 @@ impl SessionStorage {
                 // 1. Write session_agent.json to remote ONCE
                 if !self.session_agent_remote_written {
                     let agent_key = format!("{}/session_agent.json", session_key_prefix);
-                    if let Err(e) = backend.write_json(&agent_key, &checkpoint.agent_state).await {
+                    let agent_blob = self.encode_payload(&checkpoint.agent_state, "session_agent.json")?;
+                    if let Err(e) = backend.write(&agent_key, &agent_blob.bytes).await {
                         crate::observability::tee_eprintln(
                             &format!("[checkpoint] Warning: Failed to write session agent state to remote backend: {}", e)
                         );
@@ -1155,12 +1831,21 @@ impl SessionStorage {
                 }
 
                 // 2. Write conversation state (per-checkpoint)
-                if let Err(e) = backend.write_json(&format!("{}_conversation.json", ckpt_key_prefix), &checkpoint.conversation_state).await {
+                if let Err(e) = backend.write(&format!("{}_conversation.json", ckpt_key_prefix), &conversation_blob.bytes).await {
                     crate::observability::tee_eprintln(
                         &format!("[checkpoint] Warning: Failed to write conversation to remote backend: {}", e)
                     );
                 }
 
+                // 3. Write the workspace file snapshot when the agent touched any files
+                if let Some(ref files_blob) = files_blob {
+                    if let Err(e) = backend.write(&format!("{}_files.json", ckpt_key_prefix), &files_blob.bytes).await {
+                        crate::observability::tee_eprintln(
+                            &format!("[checkpoint] Warning: Failed to write file snapshot to remote backend: {}", e)
+                        );
+                    }
+                }
+
                 let mode_str = if matches!(self.storage_mode, StorageMode::Mirror) { "mirrored" } else { "saved" };
                 crate::observability::tee_eprintln(
                     &format!("[checkpoint] ✅ {} checkpoint {} to remote storage", mode_str.to_uppercase(), checkpoint_id)
@@ -1180,10 +1865,16 @@ impl SessionStorage {
         }
 
         // Update checkpoint index (always in memory, persist to local if using local storage)
-        self.checkpoints.insert(
-            checkpoint.metadata.checkpoint_id.clone(),
-            checkpoint.metadata.clone(),
-        );
+        self.checkpoints.insert(checkpoint_id.clone(), metadata);
+        let report = self.prune_index().await?;
+        if !report.is_empty() {
+            crate::observability::tee_eprintln(&format!(
+                "[checkpoint] Pruned {} checkpoint(s) over the limit of {}: {}",
+                report.pruned.len(),
+                self.pruning.max_checkpoints,
+                report.pruned.join(", ")
+            ));
+        }
         if should_write_local {
             self.save_checkpoint_index().await?;
         }
@@ -1191,6 +1882,7 @@ impl SessionStorage {
         // Update session metadata
         self.metadata.checkpoint_count = self.checkpoints.len() as u32;
         self.metadata.last_accessed = Utc::now();
+        self.metadata.conversation_stats = Some(checkpoint.conversation_state.conversation_stats.clone());
         if should_write_local {
             self.save_metadata().await?;
         }
@@ -1264,12 +1956,12 @@ impl SessionStorage {
             .join(format!("{}_conversation.json", checkpoint_id));
 
         if conversation_file.exists() {
-            let conversation_state: ConversationSnapshot = load_json(&conversation_file).await?;
+            let conversation_state: ConversationSnapshot = read_payload(&conversation_file, self.cipher.as_deref())?;
 
             // Load agent state: session_agent.json first, fallback to per-checkpoint
             let session_agent_path = self.session_path.join("session_agent.json");
             let agent_state: AgentStateSnapshot = if session_agent_path.exists() {
-                load_json(&session_agent_path).await?
+                read_payload(&session_agent_path, self.cipher.as_deref())?
             } else {
                 // Legacy: per-checkpoint agent file
                 let agent_file = self
@@ -1278,7 +1970,7 @@ impl SessionStorage {
                 if !agent_file.exists() {
                     return Ok(None); // Missing agent state — not found
                 }
-                load_json(&agent_file).await?
+                read_payload(&agent_file, self.cipher.as_deref())?
             };
 
             // Load metadata: in-memory index first, fallback to per-checkpoint file
@@ -1305,7 +1997,11 @@ impl SessionStorage {
                 }
             };
 
-            return Ok(Some(Self::build_checkpoint(metadata, agent_state, conversation_state)));
+            let mut checkpoint = Self::build_checkpoint(metadata, agent_state, conversation_state);
+            if let Some(fs_state) = self.load_file_snapshot(checkpoint_id).await? {
+                checkpoint.file_system_state = fs_state;
+            }
+            return Ok(Some(checkpoint));
         }
 
         // Fall back to V1 single-file format
@@ -1338,8 +2034,9 @@ impl SessionStorage {
         let ckpt_key_prefix = format!("{}/checkpoints/{}", session_key_prefix, checkpoint_id);
 
         // Load conversation (per-checkpoint — same key format in both old and new)
-        let conversation_state: ConversationSnapshot = match backend.read_json(&format!("{}_conversation.json", ckpt_key_prefix)).await {
-            Ok(c) => c,
+        let conversation_key = format!("{}_conversation.json", ckpt_key_prefix);
+        let conversation_state: ConversationSnapshot = match backend.read(&conversation_key).await {
+            Ok(data) => self.decode_payload(&conversation_key, &data)?,
             Err(StorageError::NotFound(_)) => return Ok(None),
             Err(e) => {
                 crate::observability::tee_eprintln(&format!("[checkpoint] Warning: Failed to read conversation from remote: {}", e));
@@ -1349,12 +2046,13 @@ impl SessionStorage {
 
         // Load agent state: session_agent.json first, fallback to per-checkpoint
         let session_agent_key = format!("{}/session_agent.json", session_key_prefix);
-        let agent_state: AgentStateSnapshot = match backend.read_json(&session_agent_key).await {
-            Ok(a) => a,
+        let agent_state: AgentStateSnapshot = match backend.read(&session_agent_key).await {
+            Ok(data) => self.decode_payload(&session_agent_key, &data)?,
             Err(StorageError::NotFound(_)) => {
                 // Legacy: try per-checkpoint agent file
-                match backend.read_json(&format!("{}_agent.json", ckpt_key_prefix)).await {
-                    Ok(a) => a,
+                let agent_key = format!("{}_agent.json", ckpt_key_prefix);
+                match backend.read(&agent_key).await {
+                    Ok(data) => self.decode_payload(&agent_key, &data)?,
                     Err(StorageError::NotFound(_)) => {
                         crate::observability::tee_eprintln(&format!("[checkpoint] Warning: Agent state missing in remote for checkpoint {}", checkpoint_id));
                         return Ok(None);
@@ -1417,8 +2115,12 @@ impl SessionStorage {
             }
         };
 
+        let mut checkpoint = Self::build_checkpoint(metadata, agent_state, conversation_state);
+        if let Some(fs_state) = self.load_file_snapshot(checkpoint_id).await? {
+            checkpoint.file_system_state = fs_state;
+        }
         crate::observability::tee_eprintln(&format!("[checkpoint] ✅ Loaded checkpoint {} from remote storage", checkpoint_id));
-        Ok(Some(Self::build_checkpoint(metadata, agent_state, conversation_state)))
+        Ok(Some(checkpoint))
     }
     
     /// Build a Checkpoint with default values for optional fields
@@ -1495,6 +2197,77 @@ impl SessionStorage {
     /// (`session_agent.json`, `session_metadata.json`, `checkpoints.json`)
     /// are preserved since they may be needed by other checkpoints.
     pub async fn delete_checkpoint(&mut self, checkpoint_id: &str) -> CheckpointResult<()> {
+        self.remove_checkpoint_files(checkpoint_id).await?;
+
+        self.checkpoints.remove(checkpoint_id);
+        self.save_checkpoint_index().await?;
+
+        // Update session metadata
+        self.metadata.checkpoint_count = self.checkpoints.len() as u32;
+        self.save_metadata().await?;
+
+        Ok(())
+    }
+
+    /// Prune checkpoints beyond the session's limit
+    ///
+    /// Keeps the first checkpoint, the most recent half of the limit, and
+    /// tagged checkpoints when `preserve_tagged` is set; older checkpoints in
+    /// between are thinned out with logarithmic spacing. Runs automatically
+    /// after each local save.
+    pub async fn prune_checkpoints(&mut self) -> CheckpointResult<PruneReport> {
+        let report = self.prune_index().await?;
+        if !report.is_empty() {
+            self.save_checkpoint_index().await?;
+            self.metadata.checkpoint_count = self.checkpoints.len() as u32;
+            self.save_metadata().await?;
+        }
+        Ok(report)
+    }
+
+    /// Remove pruned checkpoints' files and index entries without saving the index
+    async fn prune_index(&mut self) -> CheckpointResult<PruneReport> {
+        let mut ordered: Vec<&CheckpointMetadata> = self.checkpoints.values().collect();
+        ordered.sort_by(|a, b| {
+            a.created_at
+                .cmp(&b.created_at)
+                .then_with(|| a.checkpoint_id.cmp(&b.checkpoint_id))
+        });
+        // Automatic and project default tags are on every checkpoint, so they don't protect one
+        let default_tags = &self.project_config.custom_tags;
+        let tagged: Vec<bool> = ordered
+            .iter()
+            .map(|c| has_user_tags(&c.tags, &c.workflow_step, c.iteration, default_tags))
+            .collect();
+        let pruned: Vec<String> = self
+            .pruning
+            .select(&tagged)
+            .into_iter()
+            .map(|i| ordered[i].checkpoint_id.clone())
+            .collect();
+
+        let mut dropped_file_snapshot = false;
+        for checkpoint_id in &pruned {
+            dropped_file_snapshot |= self.session_path.join(format!("{}_files.json", checkpoint_id)).exists();
+            self.remove_checkpoint_files(checkpoint_id).await?;
+            self.checkpoints.remove(checkpoint_id);
+        }
+        if dropped_file_snapshot {
+            if let Err(e) = self.collect_blob_garbage() {
+                crate::observability::tee_eprintln(&format!(
+                    "[checkpoint] Warning: Failed to remove unused file snapshots: {}",
+                    e
+                ));
+            }
+        }
+        Ok(PruneReport {
+            pruned,
+            remaining: self.checkpoints.len(),
+        })
+    }
+
+    /// Delete a checkpoint's per-checkpoint files
+    async fn remove_checkpoint_files(&self, checkpoint_id: &str) -> CheckpointResult<()> {
         // Delete conversation file (new optimized format)
         let conv_file = self.session_path.join(format!("{}_conversation.json", checkpoint_id));
         if conv_file.exists() {
@@ -1514,15 +2287,46 @@ impl SessionStorage {
         if v1_file.exists() {
             fs::remove_file(&v1_file).await?;
         }
+        let files_file = self.session_path.join(format!("{}_files.json", checkpoint_id));
+        if files_file.exists() {
+            fs::remove_file(&files_file).await?;
+        }
 
-        self.checkpoints.remove(checkpoint_id);
-        self.save_checkpoint_index().await?;
+        #[cfg(feature = "storage-documentdb")]
+        if !matches!(self.storage_mode, super::config::StorageMode::Local) {
+            if let Some(ref backend) = self.remote_backend {
+                let ckpt_key_prefix = format!(
+                    "projects/{}/sessions/{}/checkpoints/{}",
+                    self.metadata.project_hash, self.metadata.session_id, checkpoint_id
+                );
+                for suffix in ["_conversation.json", "_files.json"] {
+                    if let Err(e) = backend.delete(&format!("{}{}", ckpt_key_prefix, suffix)).await {
+                        crate::observability::tee_eprintln(&format!(
+                            "[checkpoint] Warning: Failed to delete checkpoint {} from remote backend: {}",
+                            checkpoint_id, e
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
 
-        // Update session metadata
-        self.metadata.checkpoint_count = self.checkpoints.len() as u32;
-        self.save_metadata().await?;
+    /// Delete workspace file blobs of the project no checkpoint refers to
+    /// (see [`ProjectStorage::collect_blob_garbage`])
+    pub fn collect_blob_garbage(&self) -> CheckpointResult<BlobGcReport> {
+        if matches!(self.storage_mode, super::config::StorageMode::Remote) {
+            return Ok(BlobGcReport::default());
+        }
+        collect_project_blob_garbage(self.project_dir(), self.cipher.as_ref(), BLOB_GC_MIN_AGE)
+    }
 
-        Ok(())
+    /// Project storage directory holding `sessions/` and `blobs/`
+    fn project_dir(&self) -> &Path {
+        self.session_path
+            .parent()
+            .and_then(|sessions| sessions.parent())
+            .unwrap_or(&self.session_path)
     }
 
     /// Save checkpoint index using atomic operations
@@ -1701,6 +2505,16 @@ impl SessionStorage {
         &self.metadata.session_id
     }
 
+    /// Get the session directory (holds `checkpoints.json`, `events.jsonl`, ...)
+    pub fn session_path(&self) -> &Path {
+        &self.session_path
+    }
+
+    /// Get the hash of the project this session belongs to
+    pub fn project_hash(&self) -> &str {
+        &self.metadata.project_hash
+    }
+
     /// Get the current checkpoint count from metadata.
     pub fn checkpoint_count(&self) -> u32 {
         self.metadata.checkpoint_count
@@ -1712,7 +2526,126 @@ impl SessionStorage {
             .max_by_key(|cp| cp.created_at)
             .map(|cp| cp.checkpoint_id.clone())
     }
-}
+
+    /// Content-addressed store for workspace file snapshots, shared by all
+    /// sessions of the project (`<project>/blobs/`).
+    pub fn blob_store(&self) -> BlobStore {
+        BlobStore::new(self.project_dir().join("blobs"))
+            .with_cipher(self.cipher.clone())
+            .with_compression(self.compression)
+    }
+
+    /// Load the workspace file snapshot of a checkpoint, if it touched any files.
+    ///
+    /// Falls back to the remote backend when the snapshot isn't stored locally.
+    pub async fn load_file_snapshot(&self, checkpoint_id: &str) -> CheckpointResult<Option<FileSystemSnapshot>> {
+        let files_file = self.session_path.join(format!("{}_files.json", checkpoint_id));
+        if files_file.exists() {
+            return Ok(Some(read_payload(&files_file, self.cipher.as_deref())?));
+        }
+
+        #[cfg(feature = "storage-documentdb")]
+        if !matches!(self.storage_mode, super::config::StorageMode::Local) {
+            if let Some(ref backend) = self.remote_backend {
+                use crate::checkpoint::backend::StorageError;
+
+                let key = format!(
+                    "projects/{}/sessions/{}/checkpoints/{}_files.json",
+                    self.metadata.project_hash, self.metadata.session_id, checkpoint_id
+                );
+                return match backend.read(&key).await {
+                    Ok(data) => Ok(Some(self.decode_payload(&key, &data)?)),
+                    Err(StorageError::NotFound(_)) => Ok(None),
+                    Err(e) => Err(CheckpointError::storage(format!(
+                        "Failed to read file snapshot of checkpoint {} from remote: {}",
+                        checkpoint_id, e
+                    ))),
+                };
+            }
+        }
+        Ok(None)
+    }
+
+    /// Roll workspace files back to their state at `checkpoint_id`.
+    ///
+    /// Files the agent modified after the checkpoint are restored from the
+    /// blob store; files it created afterwards are deleted. With `dry_run`
+    /// nothing is written and the returned plan only describes the changes.
+    pub async fn restore_files(&self, checkpoint_id: &str, dry_run: bool) -> CheckpointResult<FileRestorePlan> {
+        if !self.checkpoints.contains_key(checkpoint_id) {
+            return Err(CheckpointError::not_found(format!("Checkpoint {} not found", checkpoint_id)));
+        }
+        let empty = FileSystemSnapshot {
+            working_directory: PathBuf::new(),
+            tracked_files: Vec::new(),
+            modified_files: Vec::new(),
+            git_status: None,
+            file_permissions: HashMap::new(),
+        };
+        let target = self.load_file_snapshot(checkpoint_id).await?.unwrap_or_else(|| empty.clone());
+        let latest = match self.latest_checkpoint_id() {
+            Some(latest_id) => self.load_file_snapshot(&latest_id).await?.unwrap_or(empty),
+            None => empty,
+        };
+        if target.working_directory.as_os_str().is_empty() && latest.working_directory.as_os_str().is_empty() {
+            return Err(CheckpointError::restoration(format!(
+                "No workspace file snapshots recorded up to checkpoint {}",
+                checkpoint_id
+            )));
+        }
+
+        let mut plan = plan_file_restore(checkpoint_id, &target, &latest, &self.blob_store())?;
+        if !dry_run {
+            plan.apply()?;
+            crate::observability::tee_eprintln(&format!(
+                "[checkpoint] ✅ Restored {} file(s) to checkpoint {}",
+                plan.changes.len(),
+                checkpoint_id
+            ));
+        }
+        Ok(plan)
+    }
+
+    /// Shadow git commit recorded for a checkpoint
+    fn shadow_commit(&self, checkpoint_id: &str) -> CheckpointResult<(&ShadowRepo, &str)> {
+        let repo = self.shadow_repo.as_deref().ok_or_else(|| {
+            CheckpointError::config("Shadow git snapshots are disabled (checkpointing.git_integration)")
+        })?;
+        let metadata = self
+            .checkpoints
+            .get(checkpoint_id)
+            .ok_or_else(|| CheckpointError::not_found(format!("Checkpoint {} not found", checkpoint_id)))?;
+        let commit = metadata.shadow_commit.as_deref().ok_or_else(|| {
+            CheckpointError::not_found(format!("Checkpoint {} has no shadow git snapshot", checkpoint_id))
+        })?;
+        Ok((repo, commit))
+    }
+
+    /// Compare the working trees snapshotted at two checkpoints
+    pub fn diff_snapshots(&self, from_checkpoint_id: &str, to_checkpoint_id: &str) -> CheckpointResult<ShadowDiff> {
+        let (repo, from) = self.shadow_commit(from_checkpoint_id)?;
+        let (_, to) = self.shadow_commit(to_checkpoint_id)?;
+        repo.diff(from, to)
+    }
+
+    /// Restore workspace files (all of them when `paths` is empty) from the
+    /// shadow git snapshot taken at `checkpoint_id`
+    ///
+    /// Unlike [`restore_files`](Self::restore_files), this covers every file
+    /// in the project, not just the ones the agent was tracking. The current
+    /// tree is committed first; its id is returned as `backup_commit`.
+    pub fn restore_snapshot(&self, checkpoint_id: &str, paths: &[PathBuf]) -> CheckpointResult<ShadowRestore> {
+        let (repo, commit) = self.shadow_commit(checkpoint_id)?;
+        let restore = repo.restore(commit, paths)?;
+        crate::observability::tee_eprintln(&format!(
+            "[checkpoint] ✅ Restored {} file(s) and removed {} from the checkpoint {} snapshot",
+            restore.restored.len(),
+            restore.removed.len(),
+            checkpoint_id
+        ));
+        Ok(restore)
+    }
+}
 
 /// Project metadata
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
@@ -1819,6 +2752,36 @@ async fn load_or_create_project_metadata(
     }
 }
 
+/// Merge project metadata restored from an archive into the project's own
+///
+/// The project keeps its ID, path and name. It takes the earlier creation
+/// time, the later access time and, when it has none, the archived git remote.
+fn merge_project_metadata(
+    storage_path: &Path,
+    project_id: &str,
+    project_path: &Path,
+    archived: &[u8],
+) -> CheckpointResult<()> {
+    let archived: ProjectMetadata = serde_json::from_slice(archived)?;
+    let metadata_path = storage_path.join(PROJECT_METADATA_FILENAME);
+    let metadata = if metadata_path.exists() {
+        let current: ProjectMetadata = AtomicOps::read_json(&metadata_path)?;
+        ProjectMetadata {
+            created_at: current.created_at.min(archived.created_at),
+            last_accessed: current.last_accessed.max(archived.last_accessed),
+            git_remote: current.git_remote.or(archived.git_remote),
+            ..current
+        }
+    } else {
+        ProjectMetadata {
+            project_hash: project_id.to_string(),
+            project_path: project_path.to_path_buf(),
+            ..archived
+        }
+    };
+    AtomicOps::write_json(&metadata_path, &metadata)
+}
+
 /// Load checkpoint index using atomic operations
 async fn load_checkpoint_index(
     session_path: &Path,
@@ -1837,6 +2800,235 @@ async fn load_json<T: serde::de::DeserializeOwned>(path: &Path) -> CheckpointRes
     AtomicOps::read_json(path)
 }
 
+/// Load a checkpoint payload that may be compressed and/or encrypted
+fn read_payload<T: serde::de::DeserializeOwned>(path: &Path, cipher: Option<&CheckpointCipher>) -> CheckpointResult<T> {
+    let data = std::fs::read(path)?;
+    compression::decode_json(&encryption::open(&data, cipher, &local_payload_context(path))?)
+}
+
+/// Encryption context of the payload file at `path` in a session directory
+pub(crate) fn local_payload_context(path: &Path) -> String {
+    let name = |path: Option<&Path>| {
+        path.and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+    encryption::payload_context(&name(path.parent()), &name(Some(path)))
+}
+
+/// Encryption context of the payload at `key` in the remote backend
+/// (`projects/<hash>/sessions/<id>/[checkpoints/]<file>`)
+#[cfg(feature = "storage-documentdb")]
+fn remote_payload_context(key: &str) -> String {
+    let session_id = key
+        .split_once("/sessions/")
+        .and_then(|(_, rest)| rest.split('/').next())
+        .unwrap_or_default();
+    let file_name = key.rsplit('/').next().unwrap_or(key);
+    encryption::payload_context(session_id, file_name)
+}
+
+/// Copy a payload into another session directory, re-sealing it for its
+/// new location when it is encrypted
+pub(crate) fn copy_payload(source: &Path, dest: &Path, cipher: Option<&CheckpointCipher>) -> CheckpointResult<()> {
+    let data = std::fs::read(source)?;
+    if !encryption::is_encrypted(&data) {
+        return AtomicOps::write_bytes(dest, &data);
+    }
+    let Some(cipher) = cipher else {
+        return Err(CheckpointError::EncryptionKeyMissing {
+            message: format!("{} is encrypted but no encryption key is configured", source.display()),
+        });
+    };
+    let plain = cipher.decrypt(&data, &local_payload_context(source))?;
+    AtomicOps::write_bytes(dest, &cipher.encrypt(&plain, &local_payload_context(dest))?)
+}
+
+/// Delete the project's blobs that no session's file snapshot refers to
+/// and that are older than `min_age`
+///
+/// Nothing is deleted if any snapshot can't be read, since its blobs would
+/// be lost.
+fn collect_project_blob_garbage(
+    project_dir: &Path,
+    cipher: Option<&Arc<CheckpointCipher>>,
+    min_age: std::time::Duration,
+) -> CheckpointResult<BlobGcReport> {
+    let mut referenced = std::collections::HashSet::new();
+    let sessions_dir = project_dir.join("sessions");
+    if sessions_dir.is_dir() {
+        for session in std::fs::read_dir(&sessions_dir)? {
+            let session = session?.path();
+            if !session.is_dir() {
+                continue;
+            }
+            for entry in std::fs::read_dir(&session)? {
+                let path = entry?.path();
+                if !path.to_string_lossy().ends_with("_files.json") {
+                    continue;
+                }
+                let snapshot: FileSystemSnapshot = read_payload(&path, cipher.map(|c| c.as_ref()))?;
+                referenced.extend(referenced_blobs(&snapshot).cloned());
+            }
+        }
+    }
+    BlobStore::new(project_dir.join("blobs"))
+        .with_cipher(cipher.cloned())
+        .collect_garbage(&referenced, min_age)
+}
+
+/// Files in a session directory that are never encrypted
+pub(crate) const PLAIN_SESSION_FILES: [&str; 3] = [SESSION_METADATA_FILENAME, "checkpoints.json", "metadata.json"];
+
+/// Rewrite one payload sealed for `context` from `previous` to `current`
+///
+/// Returns `None` when the data is already in the target form.
+fn reencrypt_bytes(
+    data: &[u8],
+    context: &str,
+    previous: Option<&CheckpointCipher>,
+    current: Option<&CheckpointCipher>,
+) -> CheckpointResult<Option<Vec<u8>>> {
+    let plain = if encryption::is_encrypted(data) {
+        if current.is_some_and(|cipher| cipher.can_decrypt(data)) {
+            return Ok(None);
+        }
+        encryption::open(data, previous, context)?.into_owned()
+    } else if current.is_none() {
+        return Ok(None);
+    } else {
+        data.to_vec()
+    };
+    match current {
+        Some(cipher) => cipher.encrypt(&plain, context).map(Some),
+        None => Ok(Some(plain)),
+    }
+}
+
+/// Rewrite the file snapshot blobs of one project (`<project>/blobs/<xx>/<sha>`)
+fn reencrypt_blob_dir(
+    blobs_dir: &Path,
+    previous: Option<&CheckpointCipher>,
+    current: Option<&CheckpointCipher>,
+    report: &mut ReencryptReport,
+) -> CheckpointResult<()> {
+    for prefix in std::fs::read_dir(blobs_dir)? {
+        let prefix = prefix?.path();
+        if !prefix.is_dir() {
+            continue;
+        }
+        for blob in std::fs::read_dir(&prefix)? {
+            let path = blob?.path();
+            // Skip temp files left by interrupted writes
+            if !path.is_file() || path.extension().is_some() {
+                continue;
+            }
+            let checksum = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            if let Some(rewritten) = reencrypt_bytes(&std::fs::read(&path)?, &checksum, previous, current)? {
+                AtomicOps::write_bytes(&path, &rewritten)?;
+                report.blobs += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether a remote key holds a checkpoint payload (as opposed to plain metadata)
+#[cfg(feature = "storage-documentdb")]
+fn is_remote_payload_key(key: &str) -> bool {
+    key.ends_with("/session_agent.json")
+        || (key.contains("/checkpoints/")
+            && ["_conversation.json", "_agent.json", "_files.json"]
+                .iter()
+                .any(|suffix| key.ends_with(suffix)))
+}
+
+/// Rewrite the checkpoint payloads stored in the remote backend
+#[cfg(feature = "storage-documentdb")]
+async fn reencrypt_remote(
+    backend: &(dyn StorageBackend + Send + Sync),
+    previous: Option<&CheckpointCipher>,
+    current: Option<&CheckpointCipher>,
+    report: &mut ReencryptReport,
+) -> CheckpointResult<()> {
+    let remote_error = |e: StorageError| CheckpointError::storage(format!("Remote re-encryption failed: {}", e));
+    let mut options = ListOptions {
+        prefix: Some("projects/".to_string()),
+        ..Default::default()
+    };
+    loop {
+        let page = backend.list(options.clone()).await.map_err(remote_error)?;
+        for item in page.items.iter().filter(|item| is_remote_payload_key(&item.key)) {
+            let data = backend.read(&item.key).await.map_err(remote_error)?;
+            if let Some(rewritten) = reencrypt_bytes(&data, &remote_payload_context(&item.key), previous, current)? {
+                backend.write(&item.key, &rewritten).await.map_err(remote_error)?;
+                report.remote_files += 1;
+            }
+        }
+        match page.continuation_token {
+            Some(token) if page.has_more => options.continuation_token = Some(token),
+            _ => return Ok(()),
+        }
+    }
+}
+
+/// Rewrite the payloads and event lines of one session from `previous` to `current`
+fn reencrypt_session_dir(
+    session_path: &Path,
+    previous: Option<&CheckpointCipher>,
+    current: Option<&CheckpointCipher>,
+    report: &mut ReencryptReport,
+) -> CheckpointResult<()> {
+    let events_context = encryption::events_context(
+        &session_path.file_name().unwrap_or_default().to_string_lossy(),
+    );
+
+    for entry in std::fs::read_dir(session_path)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if !name.ends_with(".json") || PLAIN_SESSION_FILES.contains(&name) {
+            continue;
+        }
+        let context = local_payload_context(&path);
+        if let Some(rewritten) = reencrypt_bytes(&std::fs::read(&path)?, &context, previous, current)? {
+            AtomicOps::write_bytes(&path, &rewritten)?;
+            report.files += 1;
+        }
+    }
+
+    let events_path = session_path.join("events.jsonl");
+    if events_path.exists() {
+        let content = std::fs::read_to_string(&events_path)?;
+        let mut out = String::with_capacity(content.len());
+        let mut changed = 0;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let rewritten = if current.is_some_and(|cipher| cipher.can_decrypt_line(line)) {
+                line.to_string()
+            } else {
+                let plain = encryption::open_line_allowing_plaintext(line, previous, &events_context)?;
+                match current {
+                    Some(cipher) => cipher.encrypt_line(&plain, &events_context)?,
+                    None => plain.into_owned(),
+                }
+            };
+            if rewritten != line {
+                changed += 1;
+            }
+            out.push_str(&rewritten);
+            out.push('\n');
+        }
+        if changed > 0 {
+            AtomicOps::write_file(&events_path, &out)?;
+            report.events += changed;
+        }
+    }
+
+    report.sessions += 1;
+    Ok(())
+}
+
 /// Calculate directory size recursively
 fn calculate_directory_size(
     dir: &Path,
@@ -1917,6 +3109,7 @@ mod tests {
                 uncompressed_size: 2048,
                 description: Some("Test checkpoint".to_string()),
                 tags: vec![],
+                shadow_commit: None,
             },
             agent_state: AgentStateSnapshot {
                 current_mode: "confirm".to_string(),
@@ -1946,6 +3139,7 @@ mod tests {
                     total_messages: 2,
                     estimated_cost: Some(0.01),
                     api_calls: 1,
+                    token_usage: Default::default(),
                 },
             },
             file_system_state: FileSystemSnapshot {
@@ -2005,6 +3199,9 @@ mod tests {
             description: Some("Test session".to_string()),
             tags: vec![],
             size_bytes: 1024,
+            conversation_stats: None,
+            parent_session_id: None,
+            parent_checkpoint_id: None,
         }
     }
 
@@ -2118,6 +3315,733 @@ mod tests {
         assert!(size == 0 || size > 0);
     }
 
+    #[tokio::test]
+    async fn test_export_import_project_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        let source_path = temp_dir.path().join("source_project");
+        let target_path = temp_dir.path().join("target_project");
+        fs::create_dir_all(&source_path).await.unwrap();
+        fs::create_dir_all(&target_path).await.unwrap();
+
+        let source = ProjectStorage::new(
+            temp_dir.path().join("machine_a"),
+            "source_hash".to_string(),
+            source_path,
+        )
+        .await
+        .unwrap();
+        let mut session = source.create_session("test_session").await.unwrap();
+        let mut checkpoint = create_test_checkpoint();
+        checkpoint.metadata.project_hash = "source_hash".to_string();
+        session.save_checkpoint(&checkpoint).await.unwrap();
+
+        let source_metadata_path = source.storage_path.join(PROJECT_METADATA_FILENAME);
+        let mut source_metadata: ProjectMetadata = AtomicOps::read_json(&source_metadata_path).unwrap();
+        source_metadata.created_at = Utc::now() - chrono::Duration::days(30);
+        source_metadata.git_remote = Some("git@example.com:team/repo.git".to_string());
+        AtomicOps::write_json(&source_metadata_path, &source_metadata).unwrap();
+
+        let archive_path = temp_dir.path().join("project.tar");
+        let manifest = source.export_project_data(&archive_path).await.unwrap();
+        assert_eq!(manifest.project_id, "source_hash");
+        assert_eq!(manifest.sessions, vec!["test_session".to_string()]);
+
+        let target = ProjectStorage::new(
+            temp_dir.path().join("machine_b"),
+            "target_hash".to_string(),
+            target_path,
+        )
+        .await
+        .unwrap();
+        let report = target.import_project_data(&archive_path).await.unwrap();
+        assert_eq!(report.source_project_id, "source_hash");
+        assert_eq!(report.imported_sessions, vec!["test_session".to_string()]);
+
+        let sessions = target.list_sessions().await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].project_hash, "target_hash");
+        assert_eq!(sessions[0].checkpoint_count, 1);
+        // Session metadata carries the latest checkpoint's usage stats
+        assert_eq!(sessions[0].conversation_stats.as_ref().unwrap().api_calls, 1);
+
+        // Project metadata is merged; the target keeps its own identity
+        let target_metadata: ProjectMetadata =
+            AtomicOps::read_json(&target.storage_path.join(PROJECT_METADATA_FILENAME)).unwrap();
+        assert_eq!(target_metadata.project_hash, "target_hash");
+        assert_eq!(target_metadata.created_at, source_metadata.created_at);
+        assert_eq!(target_metadata.git_remote, source_metadata.git_remote);
+
+        // Importing again collides with the session that now exists
+        let report = target.import_project_data(&archive_path).await.unwrap();
+        assert_eq!(report.skipped_sessions, vec!["test_session".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_imported_session_restores_files_in_the_target_project() {
+        use crate::checkpoint::file_snapshots::{capture_file_system, WorkspaceTracker};
+
+        let temp_dir = TempDir::new().unwrap();
+        let source_path = temp_dir.path().join("source_project");
+        let target_path = temp_dir.path().join("target_project");
+        fs::create_dir_all(&source_path).await.unwrap();
+        fs::create_dir_all(&target_path).await.unwrap();
+        let cipher = Arc::new(CheckpointCipher::from_key([4u8; 32]));
+
+        let mut source =
+            ProjectStorage::new(temp_dir.path().join("machine_a"), "source_hash".to_string(), source_path.clone())
+                .await
+                .unwrap();
+        source.set_cipher(Some(cipher.clone()));
+        let mut session = source.create_session("test_session").await.unwrap();
+        let main = source_path.join("main.rs");
+        std::fs::write(&main, "fn main() {}\n").unwrap();
+        let mut tracker = WorkspaceTracker::new();
+        tracker.record(&main);
+        std::fs::write(&main, "fn main() { v1() }\n").unwrap();
+        let mut checkpoint = create_test_checkpoint();
+        checkpoint.file_system_state =
+            capture_file_system(&source_path, &tracker, &session.blob_store(), session.snapshot_filter()).unwrap();
+        session.save_checkpoint(&checkpoint).await.unwrap();
+
+        let archive_path = temp_dir.path().join("project.tar");
+        let manifest = source.export_project_data(&archive_path).await.unwrap();
+        assert!(manifest.entries.iter().any(|e| e.path.starts_with("blobs/")));
+
+        let mut target =
+            ProjectStorage::new(temp_dir.path().join("machine_b"), "target_hash".to_string(), target_path.clone())
+                .await
+                .unwrap();
+        target.set_cipher(Some(cipher.clone()));
+        target.import_project_data(&archive_path).await.unwrap();
+
+        let metadata = target.list_sessions().await.unwrap().remove(0);
+        assert_eq!(metadata.project_hash, "target_hash");
+        let mut imported = SessionStorage::new(target.storage_path.join("sessions/test_session"), metadata)
+            .await
+            .unwrap();
+        imported.set_cipher(Some(cipher));
+
+        // The snapshot points at the importing project and its blobs came along
+        let target_main = target_path.join("main.rs");
+        std::fs::write(&target_main, "fn main() { diverged() }\n").unwrap();
+        std::fs::write(&main, "fn main() { source() }\n").unwrap();
+        let plan = imported.restore_files("001_analyze", false).await.unwrap();
+        assert_eq!(plan.working_directory, target_path);
+        assert_eq!(std::fs::read_to_string(&target_main).unwrap(), "fn main() { v1() }\n");
+        assert_eq!(std::fs::read_to_string(&main).unwrap(), "fn main() { source() }\n");
+    }
+
+    #[tokio::test]
+    async fn test_fork_session_from_checkpoint() {
+        use super::super::{EventEnvelope, EventType, EventsLog};
+
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("test_project");
+        fs::create_dir_all(&project_path).await.unwrap();
+
+        let project_storage = ProjectStorage::new(
+            temp_dir.path().join("test_storage"),
+            "test_project_hash".to_string(),
+            project_path,
+        )
+        .await
+        .unwrap();
+        let mut session = project_storage
+            .create_session_with_description("test_session", Some("Fix the bug".to_string()))
+            .await
+            .unwrap();
+
+        let base = Utc::now();
+        let events = EventsLog::new(session.session_path());
+        for (i, id) in ["001_analyze", "002_execute", "003_execute"].iter().enumerate() {
+            let mut event = EventEnvelope::new(
+                EventType::Message,
+                "test_session",
+                "test_project_hash",
+                i as u32 + 1,
+                serde_json::json!({}),
+            );
+            event.timestamp = base + chrono::Duration::seconds(2 * i as i64);
+            events.append(&event).unwrap();
+
+            let mut checkpoint = create_test_checkpoint();
+            checkpoint.metadata.checkpoint_id = id.to_string();
+            checkpoint.metadata.created_at = base + chrono::Duration::seconds(2 * i as i64 + 1);
+            session.save_checkpoint(&checkpoint).await.unwrap();
+        }
+
+        let fork = project_storage
+            .fork_session("test_session", "002_execute", None)
+            .await
+            .unwrap();
+
+        let checkpoints = fork.list_checkpoints().await.unwrap();
+        assert_eq!(checkpoints.len(), 2);
+        assert!(checkpoints.iter().all(|c| c.session_id == fork.session_id()));
+        assert!(fork.load_checkpoint("002_execute").await.is_ok());
+        assert!(fork.load_checkpoint("003_execute").await.is_err());
+        let fork_events = EventsLog::new(fork.session_path()).read_all().unwrap();
+        assert_eq!(fork_events.len(), 2);
+        assert!(fork_events.iter().all(|e| e.session_id == fork.session_id()));
+
+        let sessions = project_storage.list_sessions().await.unwrap();
+        assert_eq!(sessions.len(), 2);
+        let forked = sessions.iter().find(|s| s.session_id == fork.session_id()).unwrap();
+        assert_eq!(forked.parent_session_id.as_deref(), Some("test_session"));
+        assert_eq!(forked.parent_checkpoint_id.as_deref(), Some("002_execute"));
+        assert_eq!(forked.description.as_deref(), Some("Fix the bug"));
+        assert_eq!(forked.checkpoint_count, 2);
+
+        // The parent keeps its full history
+        let parent = sessions.iter().find(|s| s.session_id == "test_session").unwrap();
+        assert_eq!(parent.checkpoint_count, 3);
+        assert_eq!(EventsLog::new(session.session_path()).count().unwrap(), 3);
+
+        assert!(project_storage
+            .fork_session("test_session", "missing", None)
+            .await
+            .is_err());
+    }
+
+    #[cfg(feature = "storage-documentdb")]
+    #[tokio::test]
+    async fn test_fork_session_is_mirrored_to_remote() {
+        use crate::checkpoint::backend::FileStorageBackend;
+
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("test_project");
+        fs::create_dir_all(&project_path).await.unwrap();
+        let remote: Arc<dyn StorageBackend + Send + Sync> =
+            Arc::new(FileStorageBackend::new(temp_dir.path().join("remote")).unwrap());
+
+        let project_storage = ProjectStorage::with_remote_backend(
+            temp_dir.path().join("test_storage"),
+            "test_project_hash".to_string(),
+            project_path,
+            Some(remote.clone()),
+            super::super::config::StorageMode::Mirror,
+        )
+        .await
+        .unwrap();
+        let mut session = project_storage.create_session("test_session").await.unwrap();
+        for id in ["001_analyze", "002_execute"] {
+            let mut checkpoint = create_test_checkpoint();
+            checkpoint.metadata.checkpoint_id = id.to_string();
+            session.save_checkpoint(&checkpoint).await.unwrap();
+        }
+
+        let fork = project_storage
+            .fork_session("test_session", "001_analyze", None)
+            .await
+            .unwrap();
+
+        let prefix = format!("projects/test_project_hash/sessions/{}", fork.session_id());
+        assert!(remote.exists(&format!("{}/session_agent.json", prefix)).await.unwrap());
+        assert!(remote
+            .exists(&format!("{}/checkpoints/001_analyze_conversation.json", prefix))
+            .await
+            .unwrap());
+        assert!(!remote
+            .exists(&format!("{}/checkpoints/002_execute_conversation.json", prefix))
+            .await
+            .unwrap());
+
+        let index: HashMap<String, CheckpointMetadata> = remote
+            .read_json(&format!("{}/checkpoints/checkpoints.json", prefix))
+            .await
+            .unwrap();
+        assert_eq!(index.len(), 1);
+        assert_eq!(index["001_analyze"].session_id, fork.session_id());
+        let metadata: SessionMetadata = remote.read_json(&format!("{}/metadata.json", prefix)).await.unwrap();
+        assert_eq!(metadata.parent_session_id.as_deref(), Some("test_session"));
+    }
+
+    #[tokio::test]
+    async fn test_compressed_checkpoints_load_alongside_plain_ones() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("test_project");
+        fs::create_dir_all(&project_path).await.unwrap();
+
+        let mut project_storage = ProjectStorage::new(
+            temp_dir.path().join("test_storage"),
+            "test_project_hash".to_string(),
+            project_path,
+        )
+        .await
+        .unwrap();
+        let mut session = project_storage.create_session("test_session").await.unwrap();
+
+        let mut plain = create_test_checkpoint();
+        plain.metadata.checkpoint_id = "001_analyze".to_string();
+        session.save_checkpoint(&plain).await.unwrap();
+
+        project_storage.set_compression(CompressionSettings { enabled: true, level: 3 });
+        let mut session = project_storage.create_session("test_session").await.unwrap();
+        let mut compressed = create_test_checkpoint();
+        compressed.metadata.checkpoint_id = "002_execute".to_string();
+        session.save_checkpoint(&compressed).await.unwrap();
+
+        let plain_bytes = std::fs::read(session.get_checkpoint_path("001_analyze")).unwrap();
+        let compressed_bytes = std::fs::read(session.get_checkpoint_path("002_execute")).unwrap();
+        assert!(!compression::is_compressed(&plain_bytes));
+        assert!(compression::is_compressed(&compressed_bytes));
+
+        let checkpoints = session.list_checkpoints().await.unwrap();
+        let plain_meta = checkpoints.iter().find(|c| c.checkpoint_id == "001_analyze").unwrap();
+        let compressed_meta = checkpoints.iter().find(|c| c.checkpoint_id == "002_execute").unwrap();
+        assert_eq!(plain_meta.compressed_size, plain_bytes.len() as u64);
+        assert_eq!(plain_meta.uncompressed_size, plain_meta.compressed_size);
+        assert_eq!(compressed_meta.compressed_size, compressed_bytes.len() as u64);
+        assert_eq!(compressed_meta.uncompressed_size, plain_meta.uncompressed_size);
+
+        for id in ["001_analyze", "002_execute"] {
+            let loaded = session.load_checkpoint(id).await.unwrap();
+            assert_eq!(loaded.conversation_state.system_prompt, plain.conversation_state.system_prompt);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_checkpoints_and_key_rotation() {
+        use crate::checkpoint::{EventEnvelope, EventType};
+
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("test_project");
+        fs::create_dir_all(&project_path).await.unwrap();
+
+        let old_key = CheckpointCipher::from_key([1u8; 32]);
+        let new_key = CheckpointCipher::from_key([2u8; 32]);
+
+        let mut manager =
+            CheckpointStorageManager::with_home_dir(temp_dir.path().join("home"), "test").unwrap();
+        manager.set_cipher(Some(Arc::new(CheckpointCipher::from_key([1u8; 32]))));
+        let project_storage = manager.get_project_storage(&project_path).await.unwrap();
+        let mut session = project_storage.create_session("test_session").await.unwrap();
+
+        let checkpoint = create_test_checkpoint();
+        session.save_checkpoint(&checkpoint).await.unwrap();
+        session
+            .events_log()
+            .append(&EventEnvelope::new(EventType::Message, "test_session", "h", 1, serde_json::json!({"text": "secret"})))
+            .unwrap();
+        let blob = session.blob_store().put(b"TOKEN=secret\n").unwrap();
+        assert!(old_key.can_decrypt(&std::fs::read(session.blob_store().blob_path(&blob)).unwrap()));
+
+        let checkpoint_id = &checkpoint.metadata.checkpoint_id;
+        let stored = std::fs::read(session.get_checkpoint_path(checkpoint_id)).unwrap();
+        assert!(encryption::is_encrypted(&stored));
+        assert!(old_key.can_decrypt(&stored));
+        let events = std::fs::read_to_string(session.session_path().join("events.jsonl")).unwrap();
+        assert!(!events.contains("secret"));
+
+        // Listing needs no key; loading with the wrong one fails cleanly
+        assert_eq!(session.list_checkpoints().await.unwrap().len(), 1);
+        session.set_cipher(Some(Arc::new(CheckpointCipher::from_key([2u8; 32]))));
+        assert!(matches!(
+            session.load_checkpoint(checkpoint_id).await,
+            Err(CheckpointError::WrongEncryptionKey { .. })
+        ));
+
+        // Rotate to the new key; a second pass has nothing left to do
+        manager.set_cipher(Some(Arc::new(CheckpointCipher::from_key([2u8; 32]))));
+        let report = manager.reencrypt_all(Some(&old_key)).await.unwrap();
+        assert_eq!(report.sessions, 1);
+        assert!(report.files >= 2);
+        assert_eq!(report.events, 1);
+        assert_eq!(report.blobs, 1);
+        let again = manager.reencrypt_all(Some(&old_key)).await.unwrap();
+        assert_eq!((again.files, again.events, again.blobs), (0, 0, 0));
+
+        let loaded = session.load_checkpoint(checkpoint_id).await.unwrap();
+        assert_eq!(loaded.conversation_state.system_prompt, checkpoint.conversation_state.system_prompt);
+        assert_eq!(session.events_log().read_all().unwrap()[0].payload["text"], "secret");
+        let stored = std::fs::read(session.get_checkpoint_path(checkpoint_id)).unwrap();
+        assert!(new_key.can_decrypt(&stored));
+        assert_eq!(session.blob_store().get(&blob).unwrap(), b"TOKEN=secret\n");
+
+        // Payloads are bound to their session: a fork re-seals its copies,
+        // while a file moved into another session no longer opens
+        let project_storage = manager.get_project_storage(&project_path).await.unwrap();
+        let fork = project_storage.fork_session("test_session", checkpoint_id, None).await.unwrap();
+        assert!(fork.load_checkpoint(checkpoint_id).await.is_ok());
+        std::fs::copy(session.get_checkpoint_path(checkpoint_id), fork.get_checkpoint_path(checkpoint_id)).unwrap();
+        assert!(matches!(
+            fork.load_checkpoint(checkpoint_id).await,
+            Err(CheckpointError::TamperedData { .. })
+        ));
+    }
+
+    #[cfg(feature = "storage-documentdb")]
+    #[test]
+    fn test_remote_payload_keys() {
+        assert!(is_remote_payload_key("projects/p/sessions/s/checkpoints/001_conversation.json"));
+        assert!(is_remote_payload_key("projects/p/sessions/s/session_agent.json"));
+        assert!(!is_remote_payload_key("projects/p/sessions/s/checkpoints/001_metadata.json"));
+        assert!(!is_remote_payload_key("projects/p/sessions/s/checkpoints/checkpoints.json"));
+    }
+
+    #[tokio::test]
+    async fn test_checkpoints_record_shadow_git_snapshots() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("test_project");
+        fs::create_dir_all(&project_path).await.unwrap();
+        std::fs::write(project_path.join("main.rs"), "fn main() {}\n").unwrap();
+
+        let mut manager =
+            CheckpointStorageManager::with_home_dir(temp_dir.path().join("home"), "test").unwrap();
+        manager.set_git_integration(GitIntegrationConfig {
+            enabled: true,
+            create_git_snapshots: true,
+            exclude_gitignored_files: true,
+            commit_message_template: "{checkpoint_id}".to_string(),
+            ..Default::default()
+        });
+        let project_storage = manager.get_project_storage(&project_path).await.unwrap();
+        assert!(project_storage.shadow_repo().is_some());
+        let mut session = project_storage.create_session("test_session").await.unwrap();
+
+        let mut first = create_test_checkpoint();
+        first.metadata.checkpoint_id = "001_analyze".to_string();
+        session.save_checkpoint(&first).await.unwrap();
+
+        std::fs::write(project_path.join("main.rs"), "fn main() { broken }\n").unwrap();
+        let mut second = create_test_checkpoint();
+        second.metadata.checkpoint_id = "002_apply".to_string();
+        session.save_checkpoint(&second).await.unwrap();
+
+        let checkpoints = session.list_checkpoints().await.unwrap();
+        assert!(checkpoints.iter().all(|c| c.shadow_commit.is_some()));
+        assert!(!project_path.join(".git").exists());
+
+        let diff = session.diff_snapshots("001_analyze", "002_apply").unwrap();
+        assert_eq!(diff.changes.len(), 1);
+        assert_eq!(diff.changes[0].path, PathBuf::from("main.rs"));
+
+        session.restore_snapshot("001_analyze", &[]).unwrap();
+        assert_eq!(std::fs::read_to_string(project_path.join("main.rs")).unwrap(), "fn main() {}\n");
+    }
+
+    #[tokio::test]
+    async fn test_auto_commit_before_checkpoint() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("test_project");
+        fs::create_dir_all(&project_path).await.unwrap();
+        let git = |args: &[&str]| {
+            std::process::Command::new("git").arg("-C").arg(&project_path).args(args).output().unwrap()
+        };
+        git(&["init", "-q"]);
+        git(&["config", "user.name", "Test"]);
+        git(&["config", "user.email", "test@example.com"]);
+        git(&["config", "commit.gpgSign", "false"]);
+        std::fs::write(project_path.join("main.rs"), "fn main() {}\n").unwrap();
+
+        let mut manager =
+            CheckpointStorageManager::with_home_dir(temp_dir.path().join("home"), "test").unwrap();
+        manager.set_git_integration(GitIntegrationConfig {
+            enabled: true,
+            auto_commit_before_checkpoint: true,
+            create_git_snapshots: false,
+            ..Default::default()
+        });
+        let project_storage = manager.get_project_storage(&project_path).await.unwrap();
+        let mut session = project_storage.create_session("test_session").await.unwrap();
+
+        let mut checkpoint = create_test_checkpoint();
+        checkpoint.metadata.checkpoint_id = "001_analyze".to_string();
+        session.save_checkpoint(&checkpoint).await.unwrap();
+
+        let subject = git(&["log", "-1", "--format=%s"]);
+        assert_eq!(String::from_utf8_lossy(&subject.stdout).trim(), "Before checkpoint 001_analyze");
+        assert!(git(&["status", "--porcelain"]).stdout.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_save_checkpoint_prunes_over_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("test_project");
+        fs::create_dir_all(&project_path).await.unwrap();
+
+        let mut manager =
+            CheckpointStorageManager::with_home_dir(temp_dir.path().join("home"), "test").unwrap();
+        manager.set_pruning(CheckpointPruning {
+            max_checkpoints: 6,
+            preserve_tagged: true,
+        });
+        let project_storage = manager.get_project_storage(&project_path).await.unwrap();
+        let mut session = project_storage.create_session("test_session").await.unwrap();
+
+        let blobs = session.blob_store();
+        let mut checksums = Vec::new();
+        for i in 1..=12 {
+            let mut checkpoint = create_test_checkpoint();
+            checkpoint.metadata.checkpoint_id = format!("{:03}_execute", i);
+            checkpoint.metadata.iteration = i;
+            // Automatic tags, as SessionManager sets them, don't protect a checkpoint
+            checkpoint.metadata.tags = crate::checkpoint::auto_tags(&checkpoint.metadata.workflow_step, i);
+            if i == 4 {
+                checkpoint.metadata.tags.push("baseline".to_string());
+            }
+
+            // Each checkpoint snapshots one file version, stored as an old blob
+            let checksum = blobs.put(format!("v{}\n", i).as_bytes()).unwrap();
+            std::fs::File::options()
+                .write(true)
+                .open(blobs.blob_path(&checksum))
+                .unwrap()
+                .set_modified(std::time::SystemTime::now() - 2 * BLOB_GC_MIN_AGE)
+                .unwrap();
+            checkpoint.file_system_state.tracked_files = vec![crate::checkpoint::models::TrackedFile {
+                path: project_path.join("main.rs"),
+                size: 3,
+                modified: Utc::now(),
+                checksum: checksum.clone(),
+                permissions: crate::checkpoint::models::FilePermissions {
+                    mode: 0o644,
+                    readable: true,
+                    writable: true,
+                    executable: false,
+                },
+            }];
+            checksums.push(checksum);
+            session.save_checkpoint(&checkpoint).await.unwrap();
+        }
+
+        let ids: Vec<String> = session
+            .list_checkpoints()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.checkpoint_id)
+            .collect();
+        assert_eq!(ids.len(), 6);
+        assert!(ids.contains(&"001_execute".to_string()));
+        assert!(ids.contains(&"004_execute".to_string()));
+        assert!(ids.contains(&"012_execute".to_string()));
+        for i in 1..=12 {
+            let id = format!("{:03}_execute", i);
+            let conversation = session.session_path.join(format!("{}_conversation.json", id));
+            assert_eq!(conversation.exists(), ids.contains(&id));
+            // Blobs only pruned checkpoints referred to are collected
+            assert_eq!(blobs.contains(&checksums[i as usize - 1]), ids.contains(&id));
+        }
+        assert!(session.load_checkpoint("012_execute").await.is_ok());
+    }
+
+    #[cfg(feature = "storage-documentdb")]
+    #[tokio::test]
+    async fn test_remote_sessions_are_pruned() {
+        use crate::checkpoint::backend::FileStorageBackend;
+
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("test_project");
+        fs::create_dir_all(&project_path).await.unwrap();
+        let remote_dir = temp_dir.path().join("remote");
+        let remote: Arc<dyn StorageBackend + Send + Sync> =
+            Arc::new(FileStorageBackend::new(&remote_dir).unwrap());
+
+        let manager = CheckpointStorageManager::with_home_dir(temp_dir.path().join("home"), "test").unwrap();
+        let project_storage = manager.get_project_storage(&project_path).await.unwrap();
+        let mut session = project_storage.create_session("test_session").await.unwrap();
+        session.set_remote_backend(Some(remote.clone()));
+        session.set_storage_mode(super::super::config::StorageMode::Remote);
+        session.set_pruning(CheckpointPruning {
+            max_checkpoints: 4,
+            preserve_tagged: true,
+        });
+
+        for i in 1..=10 {
+            let mut checkpoint = create_test_checkpoint();
+            checkpoint.metadata.checkpoint_id = format!("{:03}_execute", i);
+            session.save_checkpoint(&checkpoint).await.unwrap();
+        }
+
+        let prefix = format!(
+            "projects/{}/sessions/{}/checkpoints/",
+            session.metadata.project_hash, session.metadata.session_id
+        );
+        let kept: Vec<String> = session.checkpoints.keys().cloned().collect();
+        assert_eq!(kept.len(), 4);
+        for i in 1..=10 {
+            let id = format!("{:03}_execute", i);
+            let key = format!("{}{}_conversation.json", prefix, id);
+            assert_eq!(remote.exists(&key).await.unwrap(), kept.contains(&id), "{}", id);
+        }
+        let index: HashMap<String, CheckpointMetadata> =
+            remote.read_json(&format!("{}checkpoints.json", prefix)).await.unwrap();
+        assert_eq!(index.len(), 4);
+    }
+
+    #[cfg(feature = "storage-documentdb")]
+    #[tokio::test]
+    async fn test_remote_sessions_store_file_snapshots() {
+        use crate::checkpoint::backend::FileStorageBackend;
+        use crate::checkpoint::file_snapshots::{capture_file_system, WorkspaceTracker};
+
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("test_project");
+        fs::create_dir_all(&project_path).await.unwrap();
+        let remote: Arc<dyn StorageBackend + Send + Sync> =
+            Arc::new(FileStorageBackend::new(temp_dir.path().join("remote")).unwrap());
+
+        let manager = CheckpointStorageManager::with_home_dir(temp_dir.path().join("home"), "test").unwrap();
+        let project_storage = manager.get_project_storage(&project_path).await.unwrap();
+        let mut session = project_storage.create_session("test_session").await.unwrap();
+        session.set_remote_backend(Some(remote.clone()));
+        session.set_storage_mode(super::super::config::StorageMode::Remote);
+
+        let main = project_path.join("main.rs");
+        std::fs::write(&main, "fn main() {}\n").unwrap();
+        let mut tracker = WorkspaceTracker::new();
+        tracker.record(&main);
+        std::fs::write(&main, "fn main() { edited() }\n").unwrap();
+        let mut checkpoint = create_test_checkpoint();
+        checkpoint.file_system_state =
+            capture_file_system(&project_path, &tracker, &session.blob_store(), session.snapshot_filter()).unwrap();
+        session.save_checkpoint(&checkpoint).await.unwrap();
+
+        let key = format!(
+            "projects/{}/sessions/{}/checkpoints/001_analyze_files.json",
+            session.metadata.project_hash, session.metadata.session_id
+        );
+        assert!(remote.exists(&key).await.unwrap());
+        assert!(!session.session_path.join("001_analyze_files.json").exists());
+
+        let loaded = session.load_checkpoint("001_analyze").await.unwrap();
+        assert_eq!(loaded.file_system_state.modified_files.len(), 1);
+        assert_eq!(loaded.file_system_state.modified_files[0].path, PathBuf::from("main.rs"));
+        // Blobs of remote-only snapshots are never collected locally
+        assert_eq!(session.collect_blob_garbage().unwrap(), BlobGcReport::default());
+    }
+
+    #[test]
+    fn test_project_config_is_found_up_to_the_git_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let outer = temp_dir.path().join("outer");
+        let repo = outer.join("repo");
+        let subdir = repo.join("crates/core");
+        std::fs::create_dir_all(&subdir).unwrap();
+        std::fs::create_dir_all(outer.join(".abk")).unwrap();
+        std::fs::write(outer.join(super::super::PROJECT_CONFIG_FILE), "custom_tags = [\"outer\"]\n").unwrap();
+
+        // Outside a git repository only the project directory itself is searched
+        assert!(ProjectCheckpointConfig::discover(&subdir).unwrap().is_none());
+
+        // Inside one, the search stops at the repository root
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        assert!(ProjectCheckpointConfig::discover(&subdir).unwrap().is_none());
+
+        std::fs::create_dir_all(repo.join(".abk")).unwrap();
+        std::fs::write(repo.join(super::super::PROJECT_CONFIG_FILE), "custom_tags = [\"repo\"]\n").unwrap();
+        let config = ProjectCheckpointConfig::discover(&subdir).unwrap().unwrap();
+        assert_eq!(config.custom_tags, vec!["repo".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_project_config_without_include_patterns_snapshots_every_file() {
+        use crate::checkpoint::file_snapshots::{capture_file_system, WorkspaceTracker};
+
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("test_project");
+        std::fs::create_dir_all(project_path.join(".abk")).unwrap();
+        std::fs::write(project_path.join(super::super::PROJECT_CONFIG_FILE), "custom_tags = [\"py\"]\n").unwrap();
+        let app = project_path.join("app.py");
+        std::fs::write(&app, "print('one')\n").unwrap();
+
+        let manager = CheckpointStorageManager::with_home_dir(temp_dir.path().join("home"), "test").unwrap();
+        let project_storage = manager.get_project_storage(&project_path).await.unwrap();
+        let session = project_storage.create_session("test_session").await.unwrap();
+        assert!(session.snapshot_filter().matches(Path::new("app.py")));
+
+        let mut tracker = WorkspaceTracker::new();
+        tracker.record(&app);
+        std::fs::write(&app, "print('two')\n").unwrap();
+        let snapshot =
+            capture_file_system(&project_path, &tracker, &session.blob_store(), session.snapshot_filter()).unwrap();
+        assert_eq!(snapshot.modified_files.len(), 1);
+        assert_eq!(snapshot.modified_files[0].path, PathBuf::from("app.py"));
+    }
+
+    #[tokio::test]
+    async fn test_project_config_cannot_redirect_shadow_repo_or_auto_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("test_project");
+        let elsewhere = temp_dir.path().join("elsewhere");
+        std::fs::create_dir_all(project_path.join(".abk")).unwrap();
+        std::fs::write(
+            project_path.join(super::super::PROJECT_CONFIG_FILE),
+            format!(
+                "[git_integration]\nenabled = true\nauto_commit_before_checkpoint = true\nshadow_repo_location = {:?}\n",
+                elsewhere.to_string_lossy()
+            ),
+        )
+        .unwrap();
+
+        let manager = CheckpointStorageManager::with_home_dir(temp_dir.path().join("home"), "test").unwrap();
+        let project_storage = manager.get_project_storage(&project_path).await.unwrap();
+        let shadow = project_storage.shadow_repo().unwrap();
+        assert!(!shadow.git_dir().starts_with(&elsewhere));
+        assert_eq!(shadow.git_dir(), project_storage.storage_path.join(super::super::shadow_git::SHADOW_REPO_DIR));
+        assert!(!project_storage.auto_commit);
+    }
+
+    #[tokio::test]
+    async fn test_project_config_file_overrides_global_settings() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_path = temp_dir.path().join("test_project");
+        std::fs::create_dir_all(project_path.join(".abk")).unwrap();
+        std::fs::create_dir_all(project_path.join("secrets")).unwrap();
+        std::fs::write(project_path.join("main.rs"), "fn main() {}\n").unwrap();
+        std::fs::write(project_path.join("secrets/token"), "one\n").unwrap();
+        let config_file = project_path.join(super::super::PROJECT_CONFIG_FILE);
+        std::fs::write(
+            &config_file,
+            r#"
+max_checkpoints_per_session = 3
+exclude_patterns = [".abk/**", "secrets/**"]
+custom_tags = ["team-a"]
+description_template = "{checkpoint_id}: {description} ({session_id})"
+
+[git_integration]
+enabled = true
+"#,
+        )
+        .unwrap();
+
+        let manager =
+            CheckpointStorageManager::with_home_dir(temp_dir.path().join("home"), "test").unwrap();
+        let project_storage = manager.get_project_storage(&project_path).await.unwrap();
+        assert_eq!(project_storage.pruning().max_checkpoints, 3);
+        assert!(project_storage.shadow_repo().is_some());
+        let mut session = project_storage.create_session("test_session").await.unwrap();
+
+        for i in 1..=5 {
+            if i == 5 {
+                std::fs::write(project_path.join("main.rs"), "fn main() { run() }\n").unwrap();
+                std::fs::write(project_path.join("secrets/token"), "two\n").unwrap();
+            }
+            let mut checkpoint = create_test_checkpoint();
+            checkpoint.metadata.checkpoint_id = format!("{:03}_execute", i);
+            checkpoint.metadata.description = Some("edit".to_string());
+            // The placeholder SessionManager puts in new checkpoints
+            checkpoint.metadata.session_id = format!("session_{:03}_execute", i);
+            session.save_checkpoint(&checkpoint).await.unwrap();
+        }
+
+        // Default tags don't exempt checkpoints from pruning
+        let checkpoints = session.list_checkpoints().await.unwrap();
+        assert_eq!(checkpoints.len(), 3);
+        let latest = checkpoints.iter().find(|c| c.checkpoint_id == "005_execute").unwrap();
+        assert_eq!(latest.tags, vec!["team-a".to_string()]);
+        assert_eq!(latest.description.as_deref(), Some("005_execute: edit (test_session)"));
+
+        // Excluded files stay out of workspace snapshots
+        let diff = session.diff_snapshots("004_execute", "005_execute").unwrap();
+        let paths: Vec<_> = diff.changes.iter().map(|c| c.path.clone()).collect();
+        assert_eq!(paths, vec![PathBuf::from("main.rs")]);
+
+        std::fs::write(&config_file, "max_checkpoints_per_session = \"many\"\n").unwrap();
+        assert!(manager.get_project_storage(&project_path).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_atomic_operations() {
         let temp_dir = TempDir::new().unwrap();
@@ -2217,6 +4141,9 @@ mod tests {
             size_bytes: 1024,
             description: Some("Old session".to_string()),
             tags: vec![],
+            conversation_stats: None,
+            parent_session_id: None,
+            parent_checkpoint_id: None,
         };
 
         let active_session = SessionMetadata {
@@ -2229,6 +4156,9 @@ mod tests {
             size_bytes: 512,
             description: Some("Active session".to_string()),
             tags: vec![],
+            conversation_stats: None,
+            parent_session_id: None,
+            parent_checkpoint_id: None,
         };
 
         let tagged_session = SessionMetadata {
@@ -2241,6 +4171,9 @@ mod tests {
             size_bytes: 256,
             description: Some("Tagged session".to_string()),
             tags: vec!["important".to_string()],
+            conversation_stats: None,
+            parent_session_id: None,
+            parent_checkpoint_id: None,
         };
 
         let retention_policy = RetentionPolicy {
@@ -165,6 +165,13 @@ pub fn estimate_token_count(content: &str) -> usize {
     (content.len() + 3) / 4
 }
 
+/// Generate a unique session ID of the form `session_YYYY_MM_DD_HH_MM_{uuid8}`.
+pub fn generate_session_id() -> String {
+    let timestamp = chrono::Utc::now().format("%Y_%m_%d_%H_%M");
+    let uuid_suffix = uuid::Uuid::new_v4().simple().to_string();
+    format!("session_{}_{}", timestamp, &uuid_suffix[..8])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
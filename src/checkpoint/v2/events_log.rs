@@ -1,13 +1,17 @@
 //! Append-only Events Log
 //!
 //! Implements the `events.jsonl` file format for session-level event tracking.
-//! Uses JSON Lines format for efficient append-only writes.
+//! Uses JSON Lines format for efficient append-only writes. With a cipher,
+//! each line is sealed separately (`enc:<base64>`), so plain and encrypted
+//! lines can be mixed in one file.
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::io::{BufRead, Write};
 use std::path::Path;
+use std::sync::Arc;
 
+use super::super::encryption::{self, CheckpointCipher};
 use super::super::{CheckpointError, CheckpointResult};
 
 /// Event types for the log
@@ -19,6 +23,8 @@ pub enum EventType {
     ToolResult,
     SystemSignal,
     Error,
+    /// Older turns were replaced by a summary
+    ContextCompaction,
 }
 
 /// Event envelope for JSONL storage
@@ -78,16 +84,50 @@ impl EventEnvelope {
 pub struct EventsLog {
     /// Path to events.jsonl file
     path: std::path::PathBuf,
+    /// Cipher sealing appended lines and opening encrypted ones
+    cipher: Option<Arc<CheckpointCipher>>,
+    /// Context lines are sealed with, naming the session directory
+    context: String,
 }
 
 impl EventsLog {
     /// Create new events log handler
     pub fn new(session_path: &Path) -> Self {
+        let session_id = session_path.file_name().unwrap_or_default().to_string_lossy();
         Self {
             path: session_path.join("events.jsonl"),
+            cipher: None,
+            context: encryption::events_context(&session_id),
         }
     }
 
+    /// Encrypt appended events and decrypt encrypted lines with `cipher`
+    pub fn with_cipher(mut self, cipher: Option<Arc<CheckpointCipher>>) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    /// Serialize an event as stored in the file (sealed when a cipher is set)
+    fn encode_line(&self, event: &EventEnvelope) -> CheckpointResult<String> {
+        let line = event.to_json_line()?;
+        match self.cipher {
+            Some(ref cipher) => cipher.encrypt_line(&line, &self.context),
+            None => Ok(line),
+        }
+    }
+
+    /// Parse a stored line, opening it if it is encrypted
+    fn decode_line(&self, line: &str, line_num: usize) -> CheckpointResult<EventEnvelope> {
+        let json = encryption::open_line(line, self.cipher.as_deref(), &self.context)?;
+        serde_json::from_str(&json).map_err(|e| {
+            CheckpointError::storage(format!(
+                "Failed to parse event on line {}: {}",
+                line_num + 1,
+                e
+            ))
+        })
+    }
+
     /// Get the path to the events file
     pub fn path(&self) -> &Path {
         &self.path
@@ -100,7 +140,7 @@ impl EventsLog {
 
     /// Append an event to the log (synchronous for atomicity)
     pub fn append(&self, event: &EventEnvelope) -> CheckpointResult<()> {
-        let line = event.to_json_line()?;
+        let line = self.encode_line(event)?;
 
         // Open file in append mode, create if doesn't exist
         let mut file = std::fs::OpenOptions::new()
@@ -137,7 +177,7 @@ impl EventsLog {
         // Serialize all events first
         let lines: Vec<String> = events
             .iter()
-            .map(|e| e.to_json_line())
+            .map(|e| self.encode_line(e))
             .collect::<CheckpointResult<Vec<_>>>()?;
 
         // Open file in append mode
@@ -199,15 +239,7 @@ impl EventsLog {
                 continue;
             }
 
-            let event: EventEnvelope = serde_json::from_str(&line).map_err(|e| {
-                CheckpointError::storage(format!(
-                    "Failed to parse event on line {}: {}",
-                    line_num + 1,
-                    e
-                ))
-            })?;
-
-            events.push(event);
+            events.push(self.decode_line(&line, line_num)?);
         }
 
         Ok(events)
@@ -261,6 +293,60 @@ impl EventsLog {
         let events = self.read_all()?;
         Ok(events.last().map(|e| e.sequence).unwrap_or(0))
     }
+
+    /// Copy the events recorded up to `until` into the log of another session
+    ///
+    /// Events keep their IDs, sequence numbers and timestamps but are
+    /// re-homed to `session_id`, so a forked session's log reads as its own.
+    /// Returns the number of events copied.
+    pub fn copy_prefix(
+        &self,
+        dest_session_path: &Path,
+        session_id: &str,
+        until: DateTime<Utc>,
+    ) -> CheckpointResult<usize> {
+        if !self.exists() {
+            return Ok(0);
+        }
+
+        let content = std::fs::read_to_string(&self.path).map_err(|e| {
+            CheckpointError::storage(format!(
+                "Failed to read events log {}: {}",
+                self.path.display(),
+                e
+            ))
+        })?;
+
+        // Lines are sealed for the destination session
+        let dest = EventsLog::new(dest_session_path).with_cipher(self.cipher.clone());
+        let mut prefix = String::new();
+        let mut copied = 0;
+        for (line_num, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let mut event = self.decode_line(line, line_num)?;
+            if event.timestamp > until {
+                break;
+            }
+
+            event.session_id = session_id.to_string();
+            prefix.push_str(&dest.encode_line(&event)?);
+            prefix.push('\n');
+            copied += 1;
+        }
+
+        std::fs::write(&dest.path, prefix).map_err(|e| {
+            CheckpointError::storage(format!(
+                "Failed to write events log {}: {}",
+                dest.path.display(),
+                e
+            ))
+        })?;
+
+        Ok(copied)
+    }
 }
 
 #[cfg(test)]
@@ -370,4 +456,30 @@ mod tests {
         assert_eq!(limited.len(), 2);
         assert_eq!(limited[0].sequence, 2);
     }
+
+    #[test]
+    fn test_events_log_copy_prefix() {
+        let tmp = TempDir::new().unwrap();
+        let log = EventsLog::new(tmp.path());
+
+        let mut events: Vec<EventEnvelope> = (1..=4).map(create_test_event).collect();
+        let base = Utc::now();
+        for (i, event) in events.iter_mut().enumerate() {
+            event.timestamp = base + chrono::Duration::seconds(i as i64);
+        }
+        log.append_batch(&events).unwrap();
+
+        let fork_dir = tmp.path().join("fork");
+        std::fs::create_dir_all(&fork_dir).unwrap();
+        let copied = log.copy_prefix(&fork_dir, "session-fork", events[1].timestamp).unwrap();
+        assert_eq!(copied, 2);
+
+        let forked = EventsLog::new(&fork_dir).read_all().unwrap();
+        assert!(forked.iter().all(|e| e.session_id == "session-fork"));
+        assert_eq!(forked[1].event_id, events[1].event_id);
+        assert_eq!(EventsLog::new(&fork_dir).last_sequence().unwrap(), 2);
+
+        // The parent keeps its own session ID
+        assert!(log.read_all().unwrap().iter().all(|e| e.session_id == "session-123"));
+    }
 }
@@ -40,6 +40,10 @@ pub struct CheckpointMetadataV2 {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
 
+    /// User tags; tagged checkpoints can be exempt from pruning
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
     /// References to companion files
     pub refs: CheckpointRefs,
 }
@@ -63,6 +67,7 @@ impl CheckpointMetadataV2 {
             iteration,
             workflow_step,
             description: None,
+            tags: Vec::new(),
             refs: CheckpointRefs::new(&checkpoint_id),
         }
     }
@@ -73,6 +78,12 @@ impl CheckpointMetadataV2 {
         self
     }
 
+    /// Set tags
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
     /// Update refs with counts
     pub fn with_counts(mut self, message_count: usize, token_count: usize) -> Self {
         self.refs.message_count = message_count;
@@ -292,6 +303,14 @@ pub struct SessionMetadataV2 {
     /// Session status
     #[serde(default)]
     pub status: SessionStatusV2,
+
+    /// Session this one was forked from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_session_id: Option<String>,
+
+    /// Checkpoint of the parent session the fork starts from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_checkpoint_id: Option<String>,
 }
 
 impl SessionMetadataV2 {
@@ -311,6 +330,8 @@ impl SessionMetadataV2 {
             checkpoint_count: 0,
             total_events: 0,
             status: SessionStatusV2::Active,
+            parent_session_id: None,
+            parent_checkpoint_id: None,
         }
     }
 
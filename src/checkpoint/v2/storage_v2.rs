@@ -1,12 +1,19 @@
 //! V2 Storage Implementation
 //!
 //! Implements split-file checkpoint storage where each checkpoint
-//! creates multiple focused files.
+//! creates multiple focused files. With compression enabled, agent state and
+//! conversation files are stored as zstd frames; every file loads either way.
+//! With a cipher, they and the lines of `events.jsonl` are sealed like
+//! [`SessionStorage`](super::super::SessionStorage) payloads.
 
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::fs;
 
-use super::super::{AtomicOps, CheckpointError, CheckpointResult};
+use super::super::compression::{self, CompressionSettings, EncodedBlob};
+use super::super::encryption::{self, CheckpointCipher};
+use super::super::storage::{copy_payload, local_payload_context};
+use super::super::{has_user_tags, AtomicOps, CheckpointError, CheckpointPruning, CheckpointResult, PruneReport};
 use super::events_log::EventsLog;
 use super::schemas::*;
 
@@ -26,6 +33,15 @@ pub struct SessionStorageV2 {
 
     /// Tracks whether `session_agent.json` has been written
     session_agent_written: bool,
+
+    /// Limits on the number of checkpoints kept
+    pruning: CheckpointPruning,
+
+    /// Compression of agent state and conversation files
+    compression: CompressionSettings,
+
+    /// Cipher sealing agent state, conversation files and events
+    cipher: Option<Arc<CheckpointCipher>>,
 }
 
 impl SessionStorageV2 {
@@ -52,6 +68,9 @@ impl SessionStorageV2 {
             index,
             events_log,
             session_agent_written,
+            pruning: CheckpointPruning::default(),
+            compression: CompressionSettings::default(),
+            cipher: None,
         })
     }
 
@@ -76,6 +95,9 @@ impl SessionStorageV2 {
             index,
             events_log,
             session_agent_written,
+            pruning: CheckpointPruning::default(),
+            compression: CompressionSettings::default(),
+            cipher: None,
         })
     }
 
@@ -105,6 +127,8 @@ impl SessionStorageV2 {
     ///
     /// Old sessions with per-checkpoint `_agent.json` / `_metadata.json` files
     /// remain readable via fallback in `load_checkpoint`.
+    ///
+    /// Checkpoints over the pruning limit are removed before the index is saved.
     pub async fn save_checkpoint(
         &mut self,
         metadata: CheckpointMetadataV2,
@@ -116,7 +140,7 @@ impl SessionStorageV2 {
         // 1. Write session_agent.json ONCE (first checkpoint or if file doesn't exist yet)
         if !self.session_agent_written {
             let agent_path = self.session_path.join("session_agent.json");
-            AtomicOps::write_json(&agent_path, agent_state)?;
+            compression::write_blob(&agent_path, &self.encode_payload(agent_state, &agent_path)?)?;
             self.session_agent_written = true;
         }
 
@@ -124,10 +148,11 @@ impl SessionStorageV2 {
         let conv_path = self
             .session_path
             .join(format!("{}_conversation.json", checkpoint_id));
-        AtomicOps::write_json(&conv_path, conversation)?;
+        compression::write_blob(&conv_path, &self.encode_payload(conversation, &conv_path)?)?;
 
         // 3. Update index (replaces per-checkpoint _metadata.json)
         self.index.add(metadata);
+        self.prune_index().await?;
         self.save_index().await?;
 
         // 4. Update session metadata
@@ -169,16 +194,16 @@ impl SessionStorageV2 {
         // 2. Load agent state: session_agent.json first, fallback to per-checkpoint
         let session_agent_path = self.session_path.join("session_agent.json");
         let agent_state: AgentStateV2 = if session_agent_path.exists() {
-            Self::load_json(&session_agent_path).await?
+            self.load_payload(&session_agent_path).await?
         } else {
             // Legacy fallback
             let agent_path = self.session_path.join(&metadata.refs.agent_file);
-            Self::load_json(&agent_path).await?
+            self.load_payload(&agent_path).await?
         };
 
         // 3. Load conversation
         let conv_path = self.session_path.join(&metadata.refs.conversation_file);
-        let conversation: ConversationFileV2 = Self::load_json(&conv_path).await?;
+        let conversation: ConversationFileV2 = self.load_payload(&conv_path).await?;
 
         Ok((metadata, agent_state, conversation))
     }
@@ -213,7 +238,7 @@ impl SessionStorageV2 {
         // Try session_agent.json first
         let session_agent_path = self.session_path.join("session_agent.json");
         if session_agent_path.exists() {
-            return Self::load_json(&session_agent_path).await;
+            return self.load_payload(&session_agent_path).await;
         }
 
         // Legacy fallback: per-checkpoint agent file
@@ -228,7 +253,7 @@ impl SessionStorageV2 {
             )));
         }
 
-        Self::load_json(&agent_path).await
+        self.load_payload(&agent_path).await
     }
 
     /// Load only conversation for a checkpoint
@@ -247,7 +272,7 @@ impl SessionStorageV2 {
             )));
         }
 
-        Self::load_json(&conv_path).await
+        self.load_payload(&conv_path).await
     }
 
     /// Delete a checkpoint
@@ -256,19 +281,7 @@ impl SessionStorageV2 {
     /// (`session_agent.json`) are preserved. Legacy per-checkpoint files
     /// (`_agent.json`, `_metadata.json`) are also cleaned up if they exist.
     pub async fn delete_checkpoint(&mut self, checkpoint_id: &str) -> CheckpointResult<()> {
-        // Remove conversation file (new + old format share this naming)
-        let files = [
-            format!("{}_conversation.json", checkpoint_id),
-            format!("{}_metadata.json", checkpoint_id),  // Legacy cleanup
-            format!("{}_agent.json", checkpoint_id),     // Legacy cleanup
-        ];
-
-        for file in &files {
-            let path = self.session_path.join(file);
-            if path.exists() {
-                fs::remove_file(&path).await?;
-            }
-        }
+        self.remove_checkpoint_files(checkpoint_id).await?;
 
         // Update index
         self.index
@@ -284,6 +297,77 @@ impl SessionStorageV2 {
         Ok(())
     }
 
+    /// Set the limits applied when checkpoints are saved
+    pub fn set_pruning(&mut self, pruning: CheckpointPruning) {
+        self.pruning = pruning;
+    }
+
+    /// Set the compression used for checkpoints saved from now on
+    pub fn set_compression(&mut self, settings: CompressionSettings) {
+        self.compression = settings;
+    }
+
+    /// Set the cipher that seals new checkpoints and events and opens
+    /// encrypted ones
+    pub fn set_cipher(&mut self, cipher: Option<Arc<CheckpointCipher>>) {
+        self.events_log = EventsLog::new(&self.session_path).with_cipher(cipher.clone());
+        self.cipher = cipher;
+    }
+
+    /// Prune checkpoints over the session's limit
+    pub async fn prune_checkpoints(&mut self) -> CheckpointResult<PruneReport> {
+        let report = self.prune_index().await?;
+        if !report.is_empty() {
+            self.save_index().await?;
+            self.metadata.checkpoint_count = self.index.len();
+            self.metadata.touch();
+            self.save_metadata().await?;
+        }
+        Ok(report)
+    }
+
+    /// Remove pruned checkpoints from the in-memory index and delete their files
+    async fn prune_index(&mut self) -> CheckpointResult<PruneReport> {
+        let tagged: Vec<bool> = self
+            .index
+            .checkpoints
+            .iter()
+            .map(|c| has_user_tags(&c.tags, &c.workflow_step, c.iteration, &[]))
+            .collect();
+        let selected = self.pruning.select(&tagged);
+
+        let mut pruned = Vec::with_capacity(selected.len());
+        for index in selected.into_iter().rev() {
+            let checkpoint = self.index.checkpoints.remove(index);
+            self.remove_checkpoint_files(&checkpoint.checkpoint_id).await?;
+            pruned.push(checkpoint.checkpoint_id);
+        }
+        pruned.reverse();
+
+        Ok(PruneReport {
+            pruned,
+            remaining: self.index.len(),
+        })
+    }
+
+    /// Remove the per-checkpoint files of a checkpoint
+    async fn remove_checkpoint_files(&self, checkpoint_id: &str) -> CheckpointResult<()> {
+        // Conversation file (new + old format share this naming)
+        let files = [
+            format!("{}_conversation.json", checkpoint_id),
+            format!("{}_metadata.json", checkpoint_id),  // Legacy cleanup
+            format!("{}_agent.json", checkpoint_id),     // Legacy cleanup
+        ];
+
+        for file in &files {
+            let path = self.session_path.join(file);
+            if path.exists() {
+                fs::remove_file(&path).await?;
+            }
+        }
+        Ok(())
+    }
+
     /// List all checkpoints
     pub fn list_checkpoints(&self) -> Vec<&CheckpointMetadataV2> {
         self.index.checkpoints.iter().collect()
@@ -295,8 +379,17 @@ impl SessionStorageV2 {
     }
 
     /// Get next checkpoint ID
+    ///
+    /// Follows the highest existing ID, so IDs are not reused after pruning.
     pub fn next_checkpoint_id(&self) -> String {
-        format!("{:03}", self.index.len() + 1)
+        let last = self
+            .index
+            .checkpoints
+            .iter()
+            .filter_map(|c| c.checkpoint_id.parse::<usize>().ok())
+            .max()
+            .unwrap_or(0);
+        format!("{:03}", last.max(self.index.len()) + 1)
     }
 
     /// Get events log
@@ -314,6 +407,77 @@ impl SessionStorageV2 {
         &self.session_path
     }
 
+    /// Fork this session at `checkpoint_id` into a sibling session directory
+    ///
+    /// The fork receives the checkpoints up to and including `checkpoint_id`
+    /// and the prefix of `events.jsonl` recorded until then.
+    pub async fn fork_session(
+        &self,
+        checkpoint_id: &str,
+        new_session_id: &str,
+        new_description: Option<&str>,
+    ) -> CheckpointResult<SessionStorageV2> {
+        let position = self
+            .index
+            .checkpoints
+            .iter()
+            .position(|c| c.checkpoint_id == checkpoint_id)
+            .ok_or_else(|| CheckpointError::CheckpointNotFound {
+                checkpoint_id: checkpoint_id.to_string(),
+                session_id: self.metadata.session_id.clone(),
+            })?;
+        let fork_point = self.index.checkpoints[position].created_at;
+
+        let fork_path = self
+            .session_path
+            .parent()
+            .map(|sessions| sessions.join(new_session_id))
+            .ok_or_else(|| CheckpointError::storage("Session path has no parent directory"))?;
+        fs::create_dir_all(&fork_path).await?;
+
+        let session_agent = self.session_path.join("session_agent.json");
+        if session_agent.exists() {
+            copy_payload(&session_agent, &fork_path.join("session_agent.json"), self.cipher.as_deref())?;
+        }
+
+        let mut index = CheckpointsIndex::new();
+        for checkpoint in &self.index.checkpoints[..=position] {
+            let files = [
+                checkpoint.refs.conversation_file.clone(),
+                checkpoint.refs.agent_file.clone(),                    // Legacy
+                format!("{}_metadata.json", checkpoint.checkpoint_id), // Legacy
+            ];
+            for file in &files {
+                let source = self.session_path.join(file);
+                if source.exists() {
+                    copy_payload(&source, &fork_path.join(file), self.cipher.as_deref())?;
+                }
+            }
+
+            let mut metadata = checkpoint.clone();
+            metadata.session_id = new_session_id.to_string();
+            index.add(metadata);
+        }
+        AtomicOps::write_json(&fork_path.join("checkpoints.json"), &index)?;
+
+        let total_events = self.events_log.copy_prefix(&fork_path, new_session_id, fork_point)?;
+
+        let mut metadata = SessionMetadataV2::new(
+            new_session_id,
+            &self.metadata.project_hash,
+            new_description.unwrap_or(&self.metadata.task_description),
+        );
+        metadata.checkpoint_count = index.len();
+        metadata.total_events = total_events;
+        metadata.parent_session_id = Some(self.metadata.session_id.clone());
+        metadata.parent_checkpoint_id = Some(checkpoint_id.to_string());
+        AtomicOps::write_json(&fork_path.join("session_metadata.json"), &metadata)?;
+
+        let mut fork = SessionStorageV2::new(fork_path, metadata).await?;
+        fork.set_cipher(self.cipher.clone());
+        Ok(fork)
+    }
+
     /// Save checkpoints index
     async fn save_index(&self) -> CheckpointResult<()> {
         let index_path = self.session_path.join("checkpoints.json");
@@ -326,13 +490,35 @@ impl SessionStorageV2 {
         AtomicOps::write_json(&metadata_path, &self.metadata)
     }
 
-    /// Load JSON file
+    /// Serialize the payload stored at `path`, compressing and sealing it as
+    /// configured
+    fn encode_payload<T: serde::Serialize>(&self, value: &T, path: &Path) -> CheckpointResult<EncodedBlob> {
+        let mut blob = self.compression.encode_json(value)?;
+        if let Some(ref cipher) = self.cipher {
+            blob.bytes = cipher.encrypt(&blob.bytes, &local_payload_context(path))?;
+        }
+        Ok(blob)
+    }
+
+    /// Load a payload file, plain, zstd-compressed or encrypted
+    async fn load_payload<T: serde::de::DeserializeOwned>(&self, path: &Path) -> CheckpointResult<T> {
+        let content = fs::read(path).await.map_err(|e| {
+            CheckpointError::storage(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+        let content = encryption::open(&content, self.cipher.as_deref(), &local_payload_context(path))?;
+
+        compression::decode_json(&content).map_err(|e| {
+            CheckpointError::storage(format!("Failed to parse {}: {}", path.display(), e))
+        })
+    }
+
+    /// Load JSON file, plain or zstd-compressed
     async fn load_json<T: serde::de::DeserializeOwned>(path: &Path) -> CheckpointResult<T> {
-        let content = fs::read_to_string(path).await.map_err(|e| {
+        let content = fs::read(path).await.map_err(|e| {
             CheckpointError::storage(format!("Failed to read {}: {}", path.display(), e))
         })?;
 
-        serde_json::from_str(&content).map_err(|e| {
+        compression::decode_json(&content).map_err(|e| {
             CheckpointError::storage(format!("Failed to parse {}: {}", path.display(), e))
         })
     }
@@ -348,6 +534,15 @@ pub struct ProjectStorageV2 {
 
     /// Storage path (~/.{agent_name}/projects/{hash}/)
     storage_path: PathBuf,
+
+    /// Limits applied to every session's checkpoints
+    pruning: CheckpointPruning,
+
+    /// Compression used by every session's checkpoints
+    compression: CompressionSettings,
+
+    /// Cipher used by every session's checkpoints and events
+    cipher: Option<Arc<CheckpointCipher>>,
 }
 
 impl ProjectStorageV2 {
@@ -368,9 +563,27 @@ impl ProjectStorageV2 {
             project_hash,
             project_path,
             storage_path,
+            pruning: CheckpointPruning::default(),
+            compression: CompressionSettings::default(),
+            cipher: None,
         })
     }
 
+    /// Set the limits applied to sessions' checkpoints
+    pub fn set_pruning(&mut self, pruning: CheckpointPruning) {
+        self.pruning = pruning;
+    }
+
+    /// Set the compression used by sessions opened from this project
+    pub fn set_compression(&mut self, settings: CompressionSettings) {
+        self.compression = settings;
+    }
+
+    /// Set the cipher used by sessions opened from this project
+    pub fn set_cipher(&mut self, cipher: Option<Arc<CheckpointCipher>>) {
+        self.cipher = cipher;
+    }
+
     /// Create a new session
     pub async fn create_session(
         &self,
@@ -386,7 +599,11 @@ impl ProjectStorageV2 {
         let metadata_path = session_path.join("session_metadata.json");
         AtomicOps::write_json(&metadata_path, &metadata)?;
 
-        SessionStorageV2::new(session_path, metadata).await
+        let mut session = SessionStorageV2::new(session_path, metadata).await?;
+        session.set_pruning(self.pruning);
+        session.set_compression(self.compression);
+        session.set_cipher(self.cipher.clone());
+        Ok(session)
     }
 
     /// Load existing session
@@ -399,7 +616,29 @@ impl ProjectStorageV2 {
             });
         }
 
-        SessionStorageV2::load(session_path).await
+        let mut session = SessionStorageV2::load(session_path).await?;
+        session.set_pruning(self.pruning);
+        session.set_compression(self.compression);
+        session.set_cipher(self.cipher.clone());
+        Ok(session)
+    }
+
+    /// Fork a session at one of its checkpoints into a new session
+    ///
+    /// See [`SessionStorageV2::fork_session`].
+    pub async fn fork_session(
+        &self,
+        session_id: &str,
+        checkpoint_id: &str,
+        new_description: Option<&str>,
+    ) -> CheckpointResult<SessionStorageV2> {
+        let parent = self.load_session(session_id).await?;
+        let fork_id = super::super::utils::generate_session_id();
+        let mut fork = parent.fork_session(checkpoint_id, &fork_id, new_description).await?;
+        fork.set_pruning(self.pruning);
+        fork.set_compression(self.compression);
+        fork.set_cipher(self.cipher.clone());
+        Ok(fork)
     }
 
     /// List sessions
@@ -506,6 +745,80 @@ mod tests {
         assert_eq!(loaded_conv.events.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_compressed_session_storage_v2() {
+        let tmp = TempDir::new().unwrap();
+        let mut project = ProjectStorageV2::new(tmp.path().to_path_buf(), "hash-abc", PathBuf::from("/tmp"))
+            .await
+            .unwrap();
+        project.set_compression(CompressionSettings { enabled: true, level: 3 });
+        let mut storage = project.create_session("session-123", "Test task").await.unwrap();
+
+        let ckpt_metadata = CheckpointMetadataV2::new("001", "session-123", "hash-abc", 1, WorkflowStepV2::Analyze);
+        let agent_state = AgentStateV2::new("session-123", "hash-abc", "Test task", PathBuf::from("/tmp"));
+        let conversation = ConversationFileV2::from_events(vec![
+            serde_json::json!({"type": "message", "content": "the same message"});
+            100
+        ]);
+        storage
+            .save_checkpoint(ckpt_metadata, &agent_state, &conversation)
+            .await
+            .unwrap();
+
+        let session_path = tmp.path().join("projects/hash-abc/sessions/session-123");
+        for file in ["session_agent.json", "001_conversation.json"] {
+            let stored = std::fs::read(session_path.join(file)).unwrap();
+            assert!(compression::is_compressed(&stored), "{} is not compressed", file);
+        }
+
+        let (_, _, loaded) = project
+            .load_session("session-123")
+            .await
+            .unwrap()
+            .load_checkpoint("001")
+            .await
+            .unwrap();
+        assert_eq!(loaded.events.len(), 100);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_session_storage_v2() {
+        use super::super::events_log::{EventEnvelope, EventType};
+
+        let tmp = TempDir::new().unwrap();
+        let mut project = ProjectStorageV2::new(tmp.path().to_path_buf(), "hash-abc", PathBuf::from("/tmp"))
+            .await
+            .unwrap();
+        project.set_cipher(Some(Arc::new(CheckpointCipher::from_key([3u8; 32]))));
+        let mut storage = project.create_session("session-123", "secret task").await.unwrap();
+
+        let ckpt_metadata = CheckpointMetadataV2::new("001", "session-123", "hash-abc", 1, WorkflowStepV2::Analyze);
+        let agent_state = AgentStateV2::new("session-123", "hash-abc", "secret task", PathBuf::from("/tmp"));
+        let conversation = ConversationFileV2::from_events(vec![serde_json::json!({"content": "secret"})]);
+        storage
+            .save_checkpoint(ckpt_metadata, &agent_state, &conversation)
+            .await
+            .unwrap();
+        let event = EventEnvelope::new(EventType::Message, "session-123", "hash-abc", 1, serde_json::json!({"text": "secret"}));
+        storage.append_event(&event).unwrap();
+
+        let session_path = tmp.path().join("projects/hash-abc/sessions/session-123");
+        for file in ["session_agent.json", "001_conversation.json"] {
+            assert!(encryption::is_encrypted(&std::fs::read(session_path.join(file)).unwrap()), "{} is plain", file);
+        }
+        assert!(!std::fs::read_to_string(session_path.join("events.jsonl")).unwrap().contains("secret"));
+
+        let session = project.load_session("session-123").await.unwrap();
+        let (_, agent, loaded) = session.load_checkpoint("001").await.unwrap();
+        assert_eq!(agent.task_description, "secret task");
+        assert_eq!(loaded.events[0]["content"], "secret");
+        assert_eq!(session.events_log().read_all().unwrap()[0].payload["text"], "secret");
+
+        // Forks re-seal the copied payloads for the new session
+        let fork = project.fork_session("session-123", "001", None).await.unwrap();
+        assert!(fork.load_checkpoint("001").await.is_ok());
+    }
+
     #[tokio::test]
     async fn test_partial_loading() {
         let tmp = TempDir::new().unwrap();
@@ -596,4 +909,69 @@ mod tests {
         assert_eq!(sessions.len(), 1);
         assert_eq!(sessions[0].session_id, "session-1");
     }
+
+    #[tokio::test]
+    async fn test_fork_session_v2() {
+        use super::super::events_log::{EventEnvelope, EventType};
+
+        let tmp = TempDir::new().unwrap();
+        let project = ProjectStorageV2::new(
+            tmp.path().to_path_buf(),
+            "test-hash",
+            PathBuf::from("/test/project"),
+        )
+        .await
+        .unwrap();
+
+        let mut session = project.create_session("session-1", "Task 1").await.unwrap();
+        let agent_state =
+            AgentStateV2::new("session-1", "test-hash", "Task 1", PathBuf::from("/tmp"));
+        let base = chrono::Utc::now();
+
+        for i in 1..=3u32 {
+            let mut event =
+                EventEnvelope::new(EventType::Message, "session-1", "test-hash", i, serde_json::json!({}));
+            event.timestamp = base + chrono::Duration::seconds(2 * i as i64);
+            session.append_event(&event).unwrap();
+
+            let mut ckpt_metadata = CheckpointMetadataV2::new(
+                session.next_checkpoint_id(),
+                "session-1",
+                "test-hash",
+                i,
+                WorkflowStepV2::Execute,
+            );
+            ckpt_metadata.created_at = base + chrono::Duration::seconds(2 * i as i64 + 1);
+            session
+                .save_checkpoint(ckpt_metadata, &agent_state, &ConversationFileV2::new())
+                .await
+                .unwrap();
+        }
+
+        let fork = project
+            .fork_session("session-1", "002", Some("Try another approach"))
+            .await
+            .unwrap();
+
+        assert_eq!(fork.metadata.parent_session_id.as_deref(), Some("session-1"));
+        assert_eq!(fork.metadata.parent_checkpoint_id.as_deref(), Some("002"));
+        assert_eq!(fork.metadata.task_description, "Try another approach");
+        assert_eq!(fork.metadata.total_events, 2);
+        assert_eq!(fork.list_checkpoints().len(), 2);
+        assert!(fork.list_checkpoints().iter().all(|c| c.session_id == fork.metadata.session_id));
+        assert!(fork
+            .events_log()
+            .read_all()
+            .unwrap()
+            .iter()
+            .all(|e| e.session_id == fork.metadata.session_id));
+        assert!(fork.load_checkpoint("002").await.is_ok());
+        assert!(fork.load_checkpoint("003").await.is_err());
+
+        // The parent is untouched
+        let parent = project.load_session("session-1").await.unwrap();
+        assert_eq!(parent.list_checkpoints().len(), 3);
+        assert_eq!(parent.events_log().count().unwrap(), 3);
+        assert_eq!(project.list_sessions().await.unwrap().len(), 2);
+    }
 }
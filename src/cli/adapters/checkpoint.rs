@@ -2,9 +2,9 @@
 //!
 //! Provides access to checkpoint and session management operations.
 
-use crate::cli::error::CliResult;
+use crate::cli::error::{CliError, CliResult};
 use async_trait::async_trait;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
 
 /// Project metadata information
@@ -25,6 +25,26 @@ pub struct SessionMetadata {
     pub description: Option<String>,
     pub tags: Vec<String>,
     pub checkpoint_count: usize,
+    /// Token usage as of the latest checkpoint, if the agent recorded any
+    #[serde(default)]
+    pub usage: Option<SessionUsage>,
+    /// Session this one was forked from
+    #[serde(default)]
+    pub parent_session_id: Option<String>,
+    /// Checkpoint of the parent session the fork starts from
+    #[serde(default)]
+    pub parent_checkpoint_id: Option<String>,
+}
+
+/// Token usage and estimated cost of a session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cached_tokens: u64,
+    pub reasoning_tokens: u64,
+    pub api_calls: u32,
+    pub estimated_cost: Option<f64>,
 }
 
 /// Session status
@@ -46,6 +66,15 @@ pub struct CheckpointMetadata {
     pub iteration: usize,
     pub description: Option<String>,
     pub tags: Vec<String>,
+    /// Bytes stored on disk (0 when unknown)
+    #[serde(default)]
+    pub compressed_size: u64,
+    /// Bytes of JSON before compression (0 when unknown)
+    #[serde(default)]
+    pub uncompressed_size: u64,
+    /// Shadow git commit of the working tree, when snapshots are enabled
+    #[serde(default)]
+    pub shadow_commit: Option<String>,
 }
 
 /// Full checkpoint data for detailed operations
@@ -141,6 +170,56 @@ pub struct CheckpointDiff {
     pub files_diff: i32,
     pub commands_diff: i32,
     pub working_directory_changed: bool,
+    /// Files that differ between the checkpoints' shadow git snapshots
+    #[serde(default)]
+    pub changed_files: Vec<ChangedFile>,
+}
+
+/// A workspace file that changed between two checkpoints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedFile {
+    /// Path relative to the project root
+    pub path: PathBuf,
+    /// "added", "modified" or "deleted"
+    pub change: String,
+}
+
+/// Result of re-encrypting stored checkpoint data
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReencryptSummary {
+    pub sessions: u32,
+    pub files: u32,
+    pub events: u32,
+    /// File snapshot blobs rewritten
+    pub blobs: u32,
+    /// Payloads rewritten in the remote backend
+    pub remote_files: u32,
+    /// Whether data is now encrypted (false when encryption was turned off)
+    pub encrypted: bool,
+}
+
+/// Result of exporting a project archive
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectExportSummary {
+    /// Sessions written to the archive
+    pub sessions: Vec<String>,
+    /// Files in the archive, file snapshot blobs included
+    pub entries: u32,
+    /// File snapshot blobs in the archive
+    pub blobs: u32,
+}
+
+/// Result of importing a project archive
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectImportSummary {
+    /// Sessions written to the project (under their final IDs)
+    pub imported: Vec<String>,
+    /// Sessions left untouched because their ID already existed
+    pub skipped: Vec<String>,
+    /// Sessions imported under a new ID, as `(original, new)`
+    pub renamed: Vec<(String, String)>,
+    /// Sessions that replaced an existing session with the same ID
+    pub overwritten: Vec<String>,
 }
 
 /// Provides access to checkpoint and session management
@@ -194,6 +273,32 @@ pub trait CheckpointAccess: Send + Sync {
 
     /// Get diff between two checkpoints
     async fn get_checkpoint_diff(&self, project_path: &PathBuf, session_id: &str, from_checkpoint_id: &str, to_checkpoint_id: &str) -> CliResult<CheckpointDiff>;
+
+    /// Fork a session at a checkpoint into a new session, leaving the original untouched
+    async fn fork_session(&self, _project_path: &PathBuf, _session_id: &str, _checkpoint_id: &str, _description: Option<String>) -> CliResult<SessionMetadata> {
+        Err(CliError::CheckpointError("Session forking not supported".to_string()))
+    }
+
+    /// Re-encrypt all stored checkpoint data with the configured key
+    ///
+    /// `old_key_file` holds the key being rotated out; without it the old key
+    /// is read from `ABK_CHECKPOINT_OLD_KEY`, if set.
+    async fn reencrypt_checkpoints(&self, _old_key_file: Option<&Path>) -> CliResult<ReencryptSummary> {
+        Err(CliError::CheckpointError("Checkpoint re-encryption not supported".to_string()))
+    }
+
+    /// Export every session of a project, file snapshots included, to a single archive
+    async fn export_project(&self, _project_path: &PathBuf, _output_path: &Path) -> CliResult<ProjectExportSummary> {
+        Err(CliError::CheckpointError("Project export not supported".to_string()))
+    }
+
+    /// Import a project archive into a project
+    ///
+    /// `on_collision` is one of `skip`, `rename`, `overwrite` or `fail` and
+    /// decides what happens to sessions whose ID already exists.
+    async fn import_project(&self, _project_path: &PathBuf, _input_path: &Path, _on_collision: &str) -> CliResult<ProjectImportSummary> {
+        Err(CliError::CheckpointError("Project import not supported".to_string()))
+    }
 }
 
 /// Provides checkpoint restoration capabilities
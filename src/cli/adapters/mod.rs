@@ -17,9 +17,14 @@ pub use checkpoint::{
     ProjectMetadata,
     SessionMetadata,
     SessionStatus,
+    SessionUsage,
     CheckpointMetadata,
     CheckpointData,
     CheckpointDiff,
+    ChangedFile,
+    ReencryptSummary,
+    ProjectExportSummary,
+    ProjectImportSummary,
     RestoredCheckpoint,
     AgentResult,
     ResumeContext,
@@ -0,0 +1,90 @@
+//! Terminal tool approval
+//!
+//! Interactive [`ToolApprover`] used by `run` in confirm/human modes when no
+//! other approver is supplied.
+
+use async_trait::async_trait;
+use colored::*;
+use std::io::{BufRead, Write};
+
+use crate::orchestration::{ApprovalDecision, ApprovalRequest, ToolApprover};
+
+/// Prompts on the terminal before each tool call.
+///
+/// Answers: `y` approve, `a` approve for the rest of the session,
+/// `n` deny (with an optional reason), `e` edit the JSON arguments.
+/// End of input denies the call.
+#[derive(Debug, Clone, Default)]
+pub struct TerminalApprover;
+
+impl TerminalApprover {
+    /// Create a new terminal approver.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl ToolApprover for TerminalApprover {
+    async fn review(&self, request: &ApprovalRequest) -> ApprovalDecision {
+        let request = request.clone();
+        tokio::task::spawn_blocking(move || prompt_decision(&request))
+            .await
+            .unwrap_or(ApprovalDecision::Deny {
+                reason: Some("Approval prompt failed".to_string()),
+            })
+    }
+}
+
+fn prompt_decision(request: &ApprovalRequest) -> ApprovalDecision {
+    crate::observability::tee_eprintln(&format!(
+        "{} {}",
+        "⏸️  Approve tool call:".yellow().bold(),
+        request.tool_name.bold()
+    ));
+    crate::observability::tee_eprintln(&format!("   {}", format_arguments(&request.arguments)));
+
+    loop {
+        let answer = match read_line("   [y]es / [a]lways / [n]o / [e]dit > ") {
+            Some(answer) => answer,
+            None => return ApprovalDecision::Deny { reason: None },
+        };
+        match answer.to_lowercase().as_str() {
+            "y" | "yes" => return ApprovalDecision::Approve,
+            "a" | "always" => return ApprovalDecision::ApproveForSession,
+            "n" | "no" => {
+                let reason = read_line("   Reason (optional) > ").filter(|r| !r.is_empty());
+                return ApprovalDecision::Deny { reason };
+            }
+            "e" | "edit" => {
+                let arguments = match read_line("   New arguments (JSON) > ") {
+                    Some(arguments) => arguments,
+                    None => return ApprovalDecision::Deny { reason: None },
+                };
+                if serde_json::from_str::<serde_json::Value>(&arguments).is_ok() {
+                    return ApprovalDecision::Edit { arguments };
+                }
+                crate::observability::tee_eprintln(&format!("   {}", "Invalid JSON, try again".red()));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn format_arguments(arguments: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(arguments)
+        .ok()
+        .and_then(|v| serde_json::to_string_pretty(&v).ok())
+        .map(|s| s.replace('\n', "\n   "))
+        .unwrap_or_else(|| arguments.to_string())
+}
+
+fn read_line(prompt: &str) -> Option<String> {
+    eprint!("{}", prompt);
+    let _ = std::io::stderr().flush();
+    let mut line = String::new();
+    match std::io::stdin().lock().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim().to_string()),
+    }
+}
@@ -1,10 +1,13 @@
 //! Checkpoint management commands for ABK
 //!
-//! Provides commands to list, show, delete, diff, and export checkpoints.
+//! Provides commands to list, show, delete, diff, export, and re-encrypt
+//! checkpoints.
 
-use crate::cli::adapters::checkpoint::{CheckpointAccess, CheckpointData, CheckpointDiff};
+use crate::cli::adapters::checkpoint::{
+    CheckpointAccess, CheckpointData, CheckpointDiff, CheckpointMetadata,
+};
 use crate::cli::adapters::context::CommandContext;
-use crate::cli::error::CliResult;
+use crate::cli::error::{CliError, CliResult};
 use std::path::PathBuf;
 
 /// List checkpoints options
@@ -45,6 +48,29 @@ pub struct ExportOptions {
     pub output_path: PathBuf,
 }
 
+/// Re-encrypt checkpoints options
+#[derive(Debug, Clone, Default)]
+pub struct ReencryptOptions {
+    /// File holding the key being rotated out (defaults to `ABK_CHECKPOINT_OLD_KEY`)
+    pub old_key_file: Option<PathBuf>,
+}
+
+/// Export project archive options
+#[derive(Debug, Clone)]
+pub struct ProjectExportOptions {
+    pub project_path: PathBuf,
+    pub output_path: PathBuf,
+}
+
+/// Import project archive options
+#[derive(Debug, Clone)]
+pub struct ProjectImportOptions {
+    pub project_path: PathBuf,
+    pub input_path: PathBuf,
+    /// `skip`, `rename`, `overwrite` or `fail`
+    pub on_collision: String,
+}
+
 /// List checkpoints
 pub async fn list_checkpoints<C, A>(
     ctx: &C,
@@ -83,16 +109,22 @@ where
                 } else {
                     for checkpoint in checkpoints {
                         ctx.log_info(&format!(
-                            "  {} - {} ({})",
+                            "  {} - {} ({}){}",
                             checkpoint.checkpoint_id,
                             checkpoint.workflow_step,
-                            checkpoint.created_at.format("%Y-%m-%d %H:%M:%S")
+                            checkpoint.created_at.format("%Y-%m-%d %H:%M:%S"),
+                            format_checkpoint_size(&checkpoint)
+                                .map(|size| format!(" [{}]", size))
+                                .unwrap_or_default()
                         ));
 
                         if opts.verbose {
                             if let Some(description) = &checkpoint.description {
                                 ctx.log_info(&format!("    Description: {}", description));
                             }
+                            if let Some(commit) = &checkpoint.shadow_commit {
+                                ctx.log_info(&format!("    Snapshot: {}", &commit[..commit.len().min(12)]));
+                            }
                         }
                     }
                 }
@@ -136,6 +168,24 @@ where
     Ok(())
 }
 
+/// Stored size of a checkpoint, with the uncompressed size when compressed
+///
+/// Returns `None` for checkpoints saved before sizes were recorded.
+fn format_checkpoint_size(checkpoint: &CheckpointMetadata) -> Option<String> {
+    if checkpoint.uncompressed_size == 0 {
+        return None;
+    }
+    let stored = crate::cli::utils::format_bytes(checkpoint.compressed_size, true);
+    if checkpoint.compressed_size >= checkpoint.uncompressed_size {
+        return Some(stored);
+    }
+    Some(format!(
+        "{}, {} uncompressed",
+        stored,
+        crate::cli::utils::format_bytes(checkpoint.uncompressed_size, true)
+    ))
+}
+
 /// Show checkpoint details
 pub async fn show_checkpoint<C, A>(
     ctx: &C,
@@ -289,7 +339,7 @@ where
 /// Export checkpoint
 pub async fn export_checkpoint<C, A>(
     ctx: &C,
-    _checkpoint_access: &A,
+    checkpoint_access: &A,
     opts: ExportOptions,
 ) -> CliResult<()>
 where
@@ -303,7 +353,136 @@ where
         opts.output_path.display()
     ));
 
-    ctx.log_warning("Checkpoint export command not yet implemented");
+    let projects = checkpoint_access.list_projects().await?;
+
+    for project_metadata in projects {
+        let sessions = checkpoint_access.list_sessions(&project_metadata.project_path).await?;
+        if !sessions.iter().any(|s| s.session_id == opts.session_id) {
+            continue;
+        }
+
+        let checkpoint = checkpoint_access
+            .load_checkpoint(&project_metadata.project_path, &opts.session_id, &opts.checkpoint_id)
+            .await?;
+
+        let export_data = serde_json::json!({
+            "version": "1.0",
+            "export_date": chrono::Utc::now().to_rfc3339(),
+            "project": {
+                "name": project_metadata.name,
+                "path": project_metadata.project_path,
+                "hash": project_metadata.project_hash
+            },
+            "checkpoint": checkpoint
+        });
+
+        let export_json = serde_json::to_string_pretty(&export_data)
+            .map_err(|e| CliError::SerializationError(e.to_string()))?;
+        std::fs::write(&opts.output_path, export_json).map_err(CliError::IoError)?;
+
+        ctx.log_success(&format!("Checkpoint exported successfully to: {}", opts.output_path.display()));
+        return Ok(());
+    }
+
+    Err(CliError::NotFound(format!("Session '{}' not found", opts.session_id)))
+}
+
+/// Re-encrypt all stored checkpoints with the configured key
+///
+/// Data sealed with the previous key is opened and sealed again with the
+/// current one; plain data is encrypted. When encryption has been turned off,
+/// everything is written back in plain form.
+pub async fn reencrypt_checkpoints<C, A>(
+    ctx: &C,
+    checkpoint_access: &A,
+    opts: ReencryptOptions,
+) -> CliResult<()>
+where
+    C: CommandContext + ?Sized,
+    A: CheckpointAccess + ?Sized,
+{
+    ctx.log_info("🔐 Re-encrypt Checkpoints");
+
+    let summary = checkpoint_access
+        .reencrypt_checkpoints(opts.old_key_file.as_deref())
+        .await?;
+
+    if summary.files == 0 && summary.events == 0 && summary.blobs == 0 && summary.remote_files == 0 {
+        ctx.log_success(&format!("All {} sessions are already up to date", summary.sessions));
+        return Ok(());
+    }
+
+    let action = if summary.encrypted { "Re-encrypted" } else { "Decrypted" };
+    ctx.log_success(&format!(
+        "{} {} files, {} events and {} file snapshots across {} sessions",
+        action, summary.files, summary.events, summary.blobs, summary.sessions
+    ));
+    if summary.remote_files > 0 {
+        ctx.log_success(&format!("{} {} remote payloads", action, summary.remote_files));
+    }
+    Ok(())
+}
+
+/// Export every session of a project to a single archive
+///
+/// Unlike [`export_checkpoint`], the archive carries event logs and file
+/// snapshots, so sessions can be resumed and restored on another machine.
+pub async fn export_project<C, A>(
+    ctx: &C,
+    checkpoint_access: &A,
+    opts: ProjectExportOptions,
+) -> CliResult<()>
+where
+    C: CommandContext + ?Sized,
+    A: CheckpointAccess + ?Sized,
+{
+    ctx.log_info(&format!("📦 Export Project: {}", opts.project_path.display()));
+
+    let summary = checkpoint_access
+        .export_project(&opts.project_path, &opts.output_path)
+        .await?;
+
+    ctx.log_success(&format!(
+        "Exported {} sessions ({} files, {} file snapshots) to {}",
+        summary.sessions.len(),
+        summary.entries,
+        summary.blobs,
+        opts.output_path.display()
+    ));
+    Ok(())
+}
+
+/// Import a project archive into a project
+pub async fn import_project<C, A>(
+    ctx: &C,
+    checkpoint_access: &A,
+    opts: ProjectImportOptions,
+) -> CliResult<()>
+where
+    C: CommandContext + ?Sized,
+    A: CheckpointAccess + ?Sized,
+{
+    ctx.log_info(&format!("📥 Import Project: {}", opts.input_path.display()));
+
+    let summary = checkpoint_access
+        .import_project(&opts.project_path, &opts.input_path, &opts.on_collision)
+        .await?;
+
+    for (original, renamed) in &summary.renamed {
+        ctx.log_info(&format!("  Renamed: {} → {}", original, renamed));
+    }
+    for session_id in &summary.overwritten {
+        ctx.log_warn(&format!("  Overwrote: {}", session_id));
+    }
+    for session_id in &summary.skipped {
+        ctx.log_info(&format!("  Skipped (already exists): {}", session_id));
+    }
+
+    ctx.log_success(&format!(
+        "Imported {} sessions into {}",
+        summary.imported.len(),
+        opts.project_path.display()
+    ));
     Ok(())
 }
 
@@ -402,6 +581,15 @@ fn display_checkpoint_diff<C: CommandContext + ?Sized>(
         ctx.log_info("  Working directory: (unchanged)");
     }
 
+    for file in &diff.changed_files {
+        let marker = match file.change.as_str() {
+            "added" => "+",
+            "deleted" => "-",
+            _ => "~",
+        };
+        ctx.log_info(&format!("    {} {}", marker, file.path.display()));
+    }
+
     // Tool state
     ctx.log_info("\n🔧 Tool State:");
     if diff.commands_diff != 0 {
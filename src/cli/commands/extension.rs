@@ -2,7 +2,7 @@
 //!
 //! Commands for managing ABK extensions:
 //! - `extension list` - List installed extensions
-//! - `extension install <path>` - Install an extension
+//! - `extension install <path>` - Install an extension (after confirming its permissions)
 //! - `extension remove <id>` - Remove an extension
 //! - `extension info <id>` - Show extension details
 
@@ -57,7 +57,15 @@ pub async fn list<C: CommandContext>(ctx: &C) -> CliResult<()> {
 }
 
 /// Install an extension from a directory
-pub async fn install<C: CommandContext>(ctx: &C, source_path: &str, extension_id: Option<&str>) -> CliResult<()> {
+///
+/// Shows the host access the extension's manifest requests and asks for
+/// confirmation unless `assume_yes` is set.
+pub async fn install<C: CommandContext>(
+    ctx: &C,
+    source_path: &str,
+    extension_id: Option<&str>,
+    assume_yes: bool,
+) -> CliResult<()> {
     let source = std::path::PathBuf::from(source_path);
     
     if !source.exists() {
@@ -84,12 +92,10 @@ pub async fn install<C: CommandContext>(ctx: &C, source_path: &str, extension_id
     
     // Parse manifest to get extension ID
     #[cfg(feature = "extension")]
-    let ext_id = {
-        use crate::extension::ExtensionManifest;
-        let manifest = ExtensionManifest::from_file(&manifest_path)
-            .map_err(|e| CliError::ExtensionError(format!("Failed to parse manifest: {}", e)))?;
-        manifest.extension.id
-    };
+    let manifest = crate::extension::ExtensionManifest::from_file(&manifest_path)
+        .map_err(|e| CliError::ExtensionError(format!("Failed to parse manifest: {}", e)))?;
+    #[cfg(feature = "extension")]
+    let ext_id = manifest.extension.id.clone();
     
     #[cfg(not(feature = "extension"))]
     let ext_id = extension_id.map(|s| s.to_string()).unwrap_or_else(|| {
@@ -101,6 +107,25 @@ pub async fn install<C: CommandContext>(ctx: &C, source_path: &str, extension_id
     
     let extensions_dir = get_extensions_dir(ctx)?;
     let target_dir = extensions_dir.join(&ext_id);
+
+    // Show the requested host access and let the user decide
+    #[cfg(feature = "extension")]
+    {
+        let sandbox = crate::extension::ExtensionSandbox::from_manifest(&manifest, &target_dir);
+        println!("Extension '{}' requests:", ext_id);
+        for line in sandbox.describe() {
+            println!("  {}", line);
+        }
+        if !assume_yes {
+            let answer = ctx.read_line("Grant these permissions and install? [y/N]: ")?;
+            if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+                ctx.log_info("Installation cancelled.");
+                return Ok(());
+            }
+        }
+    }
+    #[cfg(not(feature = "extension"))]
+    let _ = assume_yes;
     
     // Create extensions directory if needed
     std::fs::create_dir_all(&extensions_dir)
@@ -183,6 +208,12 @@ pub async fn info<C: CommandContext>(ctx: &C, extension_id: &str) -> CliResult<(
         
         let caps = manifest.list_capabilities();
         println!("\nCapabilities: [{}]", caps.join(", "));
+
+        println!("\nPermissions:");
+        let sandbox = crate::extension::ExtensionSandbox::from_manifest(&manifest, &ext_dir);
+        for line in sandbox.describe() {
+            println!("  {}", line);
+        }
         
         // Show WASM file info if exists
         let wasm_path = ext_dir.join(&manifest.lib.path);
@@ -34,6 +34,10 @@ pub struct RunOptions {
     /// Optional runtime context carrying agent name, token store, and
     /// project/session identity. When `Some`, overrides env var reads.
     pub run_context: Option<crate::context::RunContext>,
+    /// Optional approver consulted before tool calls when the mode has
+    /// `auto_execute = false` (e.g. a `ChannelApprover` answered by a TUI).
+    /// Running such a mode without one fails at startup.
+    pub tool_approver: Option<crate::orchestration::SharedApprover>,
 }
 
 /// Execute an agent workflow
@@ -41,7 +45,7 @@ pub async fn execute_run<C: CommandContext>(
     ctx: &C,
     options: RunOptions,
 ) -> CliResult<TaskResult> {
-    let RunOptions { task, yolo, mode, run_mode, verbose, output_sink, resume_info, on_checkpoint, cancel_token, run_context } = options;
+    let RunOptions { task, yolo, mode, run_mode, verbose, output_sink, resume_info, on_checkpoint, cancel_token, run_context, tool_approver } = options;
 
     // Determine run mode (global or local)
     let run_mode = run_mode.unwrap_or_else(|| "global".to_string());
@@ -121,6 +125,20 @@ pub async fn execute_run<C: CommandContext>(
         agent.set_output_sink(crate::orchestration::output::noop_sink());
     }
 
+    // Install the tool approver. It is only consulted when the selected mode
+    // has `auto_execute = false` (confirm/human by default). Refuse to start
+    // such a mode without one rather than deny every tool call mid-run.
+    if let Some(approver) = tool_approver {
+        agent.set_tool_approver(Some(approver));
+    } else if agent.requires_tool_approval() {
+        return Err(CliError::ConfigError(format!(
+            "Mode '{}' requires tool approval (auto_execute = false) but no tool approver was provided. \
+             Pass one through RunOptions::tool_approver or run_task_from_raw_config_with_approver, \
+             or use a mode that runs tools automatically (e.g. yolo)",
+            agent.current_mode()
+        )));
+    }
+
     // Wire up incremental checkpoint channel so the workflow can send resume_info
     // after each iteration's checkpoint.  Non-TUI callers pass None → no-ops.
     if let Some(ref tx) = on_checkpoint {
@@ -255,6 +273,8 @@ pub async fn execute_run<C: CommandContext>(
         }
     };
 
+    agent.shutdown_mcp_servers().await;
+
     // Send final resume info via channel for ESC safety (only if we have one).
     // Don't send None — the incremental on_checkpoint sends already captured the
     // last valid resume_info. Sending None here could overwrite a valid resume_info
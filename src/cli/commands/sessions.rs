@@ -3,7 +3,7 @@
 //! Provides reusable session management logic for agents with checkpoint systems
 
 use crate::cli::error::{CliError, CliResult};
-use crate::cli::adapters::{CommandContext, CheckpointAccess, SessionMetadata};
+use crate::cli::adapters::{CommandContext, CheckpointAccess, ProjectMetadata, SessionMetadata};
 use std::path::PathBuf;
 
 /// Options for listing sessions
@@ -30,6 +30,14 @@ pub struct DeleteOptions {
     pub confirm: bool,
 }
 
+/// Options for forking a session at a checkpoint
+#[derive(Debug, Clone)]
+pub struct ForkOptions {
+    pub session_id: String,
+    pub checkpoint_id: String,
+    pub description: Option<String>,
+}
+
 /// Options for exporting a session
 #[derive(Debug, Clone)]
 pub struct ExportOptions {
@@ -108,6 +116,7 @@ where
         }
     }
 
+    let all_sessions = order_as_tree(all_sessions);
     let total_count = all_sessions.len();
     
     if total_count == 0 {
@@ -143,12 +152,16 @@ where
     ));
 
     // Display sessions
-    for (project_meta, session) in page_sessions {
+    for (project_meta, session, depth) in page_sessions {
         let project_name = ctx.format_project_name(&project_meta.project_path)?;
         let session_json = serde_json::to_value(session)
             .map_err(|e| CliError::SerializationError(e.to_string()))?;
         let session_info = ctx.format_session_entry(&session_json, &project_name, terminal_width)?;
-        ctx.log_info(&session_info);
+        if *depth == 0 {
+            ctx.log_info(&session_info);
+        } else {
+            ctx.log_info(&format!("{}└─ {}", "   ".repeat(depth - 1), session_info));
+        }
         
         if opts.verbose {
             ctx.log_info(&format!("  Tags: {}", session.tags.join(", ")));
@@ -208,8 +221,30 @@ where
                 ctx.log_info(&format!("  Description: {}", description));
             }
 
+            if let Some(parent) = &session.parent_session_id {
+                ctx.log_info(&format!(
+                    "  Forked From: {}/{}",
+                    parent,
+                    session.parent_checkpoint_id.as_deref().unwrap_or("?")
+                ));
+            }
+
             ctx.log_info(&format!("  Tags: {}", session.tags.join(", ")));
 
+            if let Some(usage) = &session.usage {
+                ctx.log_info(&format!(
+                    "  Tokens: {} prompt ({} cached) / {} completion ({} reasoning)",
+                    usage.prompt_tokens,
+                    usage.cached_tokens,
+                    usage.completion_tokens,
+                    usage.reasoning_tokens
+                ));
+                ctx.log_info(&format!("  API Calls: {}", usage.api_calls));
+                if let Some(cost) = usage.estimated_cost {
+                    ctx.log_info(&format!("  Estimated Cost: ${:.4}", cost));
+                }
+            }
+
             // List checkpoints for this session
             let checkpoints = checkpoint_access
                 .list_checkpoints(&project_metadata.project_path, &opts.session_id)
@@ -241,6 +276,51 @@ where
     Ok(())
 }
 
+/// Fork a session at a checkpoint into a new session
+///
+/// The new session starts from the parent's state at that checkpoint; the
+/// parent session and its later checkpoints are left as they are.
+pub async fn fork_session<C, A>(
+    ctx: &C,
+    checkpoint_access: &A,
+    opts: ForkOptions,
+) -> CliResult<()>
+where
+    C: CommandContext,
+    A: CheckpointAccess,
+{
+    ctx.log_info(&format!("🌿 Fork Session: {} @ {}", opts.session_id, opts.checkpoint_id));
+
+    let projects = checkpoint_access.list_projects().await?;
+
+    for project_metadata in projects {
+        let sessions = checkpoint_access
+            .list_sessions(&project_metadata.project_path)
+            .await?;
+
+        if sessions.iter().any(|s| s.session_id == opts.session_id) {
+            let fork = checkpoint_access
+                .fork_session(
+                    &project_metadata.project_path,
+                    &opts.session_id,
+                    &opts.checkpoint_id,
+                    opts.description,
+                )
+                .await?;
+
+            ctx.log_success(&format!("Session forked: {}", fork.session_id));
+            ctx.log_info(&format!("  Checkpoints: {}", fork.checkpoint_count));
+            if let Some(description) = &fork.description {
+                ctx.log_info(&format!("  Description: {}", description));
+            }
+            ctx.log_info(&format!("💡 Continue the fork with: run --resume {}", fork.session_id));
+            return Ok(());
+        }
+    }
+
+    Err(CliError::NotFound(format!("Session '{}' not found", opts.session_id)))
+}
+
 /// Delete a session and all its checkpoints
 pub async fn delete_session<C, A>(
     ctx: &C,
@@ -682,3 +762,97 @@ where
 
     Ok(())
 }
+
+/// Order sessions so that forks follow their parent session, returning each
+/// entry with its depth in the fork tree
+///
+/// Forks whose parent is not in the list are shown at the top level.
+fn order_as_tree(
+    sessions: Vec<(ProjectMetadata, SessionMetadata)>,
+) -> Vec<(ProjectMetadata, SessionMetadata, usize)> {
+    use std::collections::{HashMap, HashSet};
+
+    let ids: HashSet<String> = sessions.iter().map(|(_, s)| s.session_id.clone()).collect();
+    let mut roots = Vec::new();
+    let mut children: HashMap<String, Vec<(ProjectMetadata, SessionMetadata)>> = HashMap::new();
+    for (project, session) in sessions {
+        match session.parent_session_id.clone() {
+            Some(parent) if parent != session.session_id && ids.contains(&parent) => {
+                children.entry(parent).or_default().push((project, session));
+            }
+            _ => roots.push((project, session)),
+        }
+    }
+
+    let mut ordered = Vec::new();
+    let mut stack: Vec<_> = roots.into_iter().rev().map(|(p, s)| (p, s, 0)).collect();
+    while let Some((project, session, depth)) = stack.pop() {
+        if let Some(forks) = children.remove(&session.session_id) {
+            stack.extend(forks.into_iter().rev().map(|(p, s)| (p, s, depth + 1)));
+        }
+        ordered.push((project, session, depth));
+    }
+
+    // Sessions caught in a parent cycle never reach a root; list them flat
+    for (project, session) in children.into_values().flatten() {
+        ordered.push((project, session, 0));
+    }
+
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::adapters::SessionStatus;
+    use chrono::Utc;
+
+    fn session(id: &str, parent: Option<&str>) -> (ProjectMetadata, SessionMetadata) {
+        let project = ProjectMetadata {
+            name: "project".to_string(),
+            project_path: PathBuf::from("/tmp/project"),
+            project_hash: "hash".to_string(),
+        };
+        let session = SessionMetadata {
+            session_id: id.to_string(),
+            status: SessionStatus::Active,
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            description: None,
+            tags: Vec::new(),
+            checkpoint_count: 0,
+            usage: None,
+            parent_session_id: parent.map(str::to_string),
+            parent_checkpoint_id: parent.map(|_| "002".to_string()),
+        };
+        (project, session)
+    }
+
+    #[test]
+    fn test_order_as_tree() {
+        let ordered = order_as_tree(vec![
+            session("fork-b", Some("root")),
+            session("root", None),
+            session("fork-a1", Some("fork-a")),
+            session("other", None),
+            session("fork-a", Some("root")),
+            session("orphan", Some("deleted")),
+        ]);
+
+        let view: Vec<(&str, usize)> = ordered
+            .iter()
+            .map(|(_, s, depth)| (s.session_id.as_str(), *depth))
+            .collect();
+        assert_eq!(
+            view,
+            vec![
+                ("root", 0),
+                ("fork-b", 1),
+                ("fork-a", 1),
+                ("fork-a1", 2),
+                ("other", 0),
+                ("orphan", 0),
+            ]
+        );
+    }
+}
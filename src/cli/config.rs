@@ -269,6 +269,18 @@ impl CliConfig {
                         trailing: false,
                         choices: None,
                     },
+                    ArgConfig {
+                        name: "yes".to_string(),
+                        help: "Grant the extension's requested permissions without asking".to_string(),
+                        arg_type: ArgType::Bool,
+                        short: Some('y'),
+                        long: Some("yes".to_string()),
+                        required: false,
+                        default: None,
+                        multiple: false,
+                        trailing: false,
+                        choices: None,
+                    },
                     ArgConfig {
                         name: "remove".to_string(),
                         help: "Remove extension by name".to_string(),
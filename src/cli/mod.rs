@@ -37,6 +37,9 @@ pub mod error;
 #[cfg(feature = "cli")]
 pub mod adapters;
 
+#[cfg(feature = "cli")]
+pub mod approval;
+
 #[cfg(feature = "cli")]
 pub mod commands;
 
@@ -59,11 +62,14 @@ pub use error::{CliError, CliResult};
 #[cfg(feature = "cli")]
 pub use adapters::{CommandContext, CheckpointAccess, ProviderFactory, ToolRegistryAdapter};
 
+#[cfg(feature = "cli")]
+pub use approval::TerminalApprover;
+
 #[cfg(feature = "cli")]
 pub use config::*;
 
 #[cfg(feature = "cli")]
-pub use runner::{run_configured_cli, run_from_raw_config, run_task_from_raw_config, generate_session_title, persist_session_title, should_generate_title, RawConfigCommandContext, ResumeInfo, TaskResult};
+pub use runner::{run_configured_cli, run_from_raw_config, run_task_from_raw_config, run_task_from_raw_config_with_approver, generate_session_title, persist_session_title, should_generate_title, RawConfigCommandContext, ResumeInfo, TaskResult};
 
 #[cfg(feature = "cli")]
 pub use utils::*;
@@ -6,15 +6,15 @@
 
 use clap::{Arg, ArgMatches, Command};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::cli::config::{ArgType, CliConfig};
 use crate::cli::error::{CliError, CliResult};
 use crate::cli::adapters::CommandContext;
 use crate::cli::adapters::checkpoint::{
-    CheckpointAccess, RestorationAccess, ProjectMetadata, SessionMetadata, SessionStatus,
+    CheckpointAccess, RestorationAccess, ProjectMetadata, SessionMetadata, SessionStatus, SessionUsage,
     CheckpointMetadata, CheckpointData, CheckpointDiff, RestoredCheckpoint, AgentResult,
-    ResumeContext,
+    ResumeContext, ReencryptSummary, ProjectExportSummary, ProjectImportSummary, ChangedFile,
 };
 use crate::cli::adapters::storage::AbkStorageAccess;
 use async_trait::async_trait;
@@ -168,10 +168,48 @@ pub async fn run_from_raw_config(
 ///   project/session identity. When `Some`, overrides `ABK_AGENT_NAME` env var
 ///   for all path resolution and credential flows.
 ///
+/// Modes with `auto_execute = false` (the default `confirm` mode among them)
+/// need a tool approver and fail to start here; use
+/// [`run_task_from_raw_config_with_approver`] to pass one.
+///
 /// # Returns
 /// A `TaskResult` containing success/failure status and optional `ResumeInfo`
 /// for session continuity on the next call.
 pub async fn run_task_from_raw_config(
+    config_toml: &str,
+    secrets: std::collections::HashMap<String, String>,
+    build_info: Option<crate::cli::config::BuildInfo>,
+    task: &str,
+    output_sink: Option<crate::orchestration::output::SharedSink>,
+    resume_info: Option<super::ResumeInfo>,
+    resume_info_tx: Option<tokio::sync::mpsc::UnboundedSender<Option<super::ResumeInfo>>>,
+    cancel_token: Option<tokio_util::sync::CancellationToken>,
+    ctx: Option<&crate::context::RunContext>,
+) -> Result<super::TaskResult, Box<dyn std::error::Error>> {
+    run_task_from_raw_config_with_approver(
+        config_toml,
+        secrets,
+        build_info,
+        task,
+        output_sink,
+        resume_info,
+        resume_info_tx,
+        cancel_token,
+        None,
+        ctx,
+    )
+    .await
+}
+
+/// Run a specific task directly with a tool approver.
+///
+/// Same as [`run_task_from_raw_config`], plus:
+///
+/// * `tool_approver` - Approver asked before tool calls in modes with
+///   `auto_execute = false` (the default `confirm` mode among them), e.g. a
+///   `ChannelApprover` answered by the TUI. Without one such modes fail to start.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_task_from_raw_config_with_approver(
     config_toml: &str,
     secrets: std::collections::HashMap<String, String>,
     mut build_info: Option<crate::cli::config::BuildInfo>,
@@ -180,6 +218,7 @@ pub async fn run_task_from_raw_config(
     resume_info: Option<super::ResumeInfo>,
     resume_info_tx: Option<tokio::sync::mpsc::UnboundedSender<Option<super::ResumeInfo>>>,
     cancel_token: Option<tokio_util::sync::CancellationToken>,
+    tool_approver: Option<crate::orchestration::SharedApprover>,
     ctx: Option<&crate::context::RunContext>,
 ) -> Result<super::TaskResult, Box<dyn std::error::Error>> {
     // Inject secrets into environment.
@@ -251,6 +290,7 @@ pub async fn run_task_from_raw_config(
         on_checkpoint: resume_info_tx,
         cancel_token,
         run_context: ctx.cloned(),
+        tool_approver,
     };
 
     let result = crate::cli::commands::run::execute_run(&context, options).await?;
@@ -802,8 +842,10 @@ impl AbkCheckpointAccess {
         // If home_dir is set, use it for per-user storage
         if let Some(ref home) = self.home_dir {
             let agent_name = std::env::var("ABK_AGENT_NAME").unwrap_or_else(|_| "trustee".to_string());
-            return crate::checkpoint::CheckpointStorageManager::with_home_dir(home.clone(), &agent_name)
-                .map_err(|e| CliError::CheckpointError(format!("Failed to create storage manager with home_dir: {}", e)));
+            let mut manager = crate::checkpoint::CheckpointStorageManager::with_home_dir(home.clone(), &agent_name)
+                .map_err(|e| CliError::CheckpointError(format!("Failed to create storage manager with home_dir: {}", e)))?;
+            self.apply_checkpoint_settings(&mut manager)?;
+            return Ok(manager);
         }
         if let Some(ref checkpoint_config) = self.checkpoint_config {
             match crate::checkpoint::CheckpointStorageManager::with_config_async(checkpoint_config.clone()).await {
@@ -815,8 +857,10 @@ impl AbkCheckpointAccess {
         }
         
         // Fall back to default storage manager
-        crate::checkpoint::get_storage_manager()
-            .map_err(|e| CliError::CheckpointError(format!("Failed to get storage manager: {}", e)))
+        let mut manager = crate::checkpoint::get_storage_manager()
+            .map_err(|e| CliError::CheckpointError(format!("Failed to get storage manager: {}", e)))?;
+        self.apply_checkpoint_settings(&mut manager)?;
+        Ok(manager)
     }
     
     #[cfg(not(feature = "storage-documentdb"))]
@@ -824,11 +868,58 @@ impl AbkCheckpointAccess {
         // If home_dir is set, use it for per-user storage
         if let Some(ref home) = self.home_dir {
             let agent_name = std::env::var("ABK_AGENT_NAME").unwrap_or_else(|_| "trustee".to_string());
-            return crate::checkpoint::CheckpointStorageManager::with_home_dir(home.clone(), &agent_name)
-                .map_err(|e| CliError::CheckpointError(format!("Failed to create storage manager with home_dir: {}", e)));
+            let mut manager = crate::checkpoint::CheckpointStorageManager::with_home_dir(home.clone(), &agent_name)
+                .map_err(|e| CliError::CheckpointError(format!("Failed to create storage manager with home_dir: {}", e)))?;
+            self.apply_checkpoint_settings(&mut manager)?;
+            return Ok(manager);
+        }
+        let mut manager = crate::checkpoint::get_storage_manager()
+            .map_err(|e| CliError::CheckpointError(format!("Failed to get storage manager: {}", e)))?;
+        self.apply_checkpoint_settings(&mut manager)?;
+        Ok(manager)
+    }
+
+    /// Apply the configured compression, encryption, git integration and
+    /// checkpoint limit to a storage manager that was not built from the
+    /// checkpoint config.
+    fn apply_checkpoint_settings(&self, manager: &mut crate::checkpoint::CheckpointStorageManager) -> CliResult<()> {
+        if let Some(ref checkpoint_config) = self.checkpoint_config {
+            manager.set_compression(crate::checkpoint::CompressionSettings::from_config(checkpoint_config));
+            let cipher = crate::checkpoint::CheckpointCipher::from_config(&checkpoint_config.security)
+                .map_err(|e| CliError::CheckpointError(format!("Failed to load checkpoint encryption key: {}", e)))?;
+            manager.set_cipher(cipher.map(std::sync::Arc::new));
+            manager.set_git_integration(checkpoint_config.git_integration.clone());
+            manager.set_pruning(crate::checkpoint::CheckpointPruning::from_config(checkpoint_config));
         }
-        crate::checkpoint::get_storage_manager()
-            .map_err(|e| CliError::CheckpointError(format!("Failed to get storage manager: {}", e)))
+        Ok(())
+    }
+}
+
+/// Convert checkpoint-module session metadata into the CLI adapter type
+fn cli_session_metadata(s: crate::checkpoint::SessionMetadata) -> SessionMetadata {
+    SessionMetadata {
+        session_id: s.session_id,
+        status: match s.status {
+            crate::checkpoint::SessionStatus::Active => SessionStatus::Active,
+            crate::checkpoint::SessionStatus::Completed => SessionStatus::Completed,
+            crate::checkpoint::SessionStatus::Failed => SessionStatus::Failed,
+            crate::checkpoint::SessionStatus::Archived => SessionStatus::Archived,
+        },
+        created_at: s.created_at,
+        last_accessed: s.last_accessed,
+        description: s.description,
+        tags: s.tags,
+        checkpoint_count: s.checkpoint_count as usize,
+        usage: s.conversation_stats.map(|stats| SessionUsage {
+            prompt_tokens: stats.token_usage.prompt_tokens,
+            completion_tokens: stats.token_usage.completion_tokens,
+            cached_tokens: stats.token_usage.cached_tokens,
+            reasoning_tokens: stats.token_usage.reasoning_tokens,
+            api_calls: stats.api_calls,
+            estimated_cost: stats.estimated_cost,
+        }),
+        parent_session_id: s.parent_session_id,
+        parent_checkpoint_id: s.parent_checkpoint_id,
     }
 }
 
@@ -873,20 +964,7 @@ impl CheckpointAccess for AbkCheckpointAccess {
         let sessions = project_storage.list_sessions().await
             .map_err(|e| CliError::CheckpointError(format!("Failed to list sessions: {}", e)))?;
         
-        Ok(sessions.into_iter().map(|s| SessionMetadata {
-            session_id: s.session_id,
-            status: match s.status {
-                crate::checkpoint::SessionStatus::Active => SessionStatus::Active,
-                crate::checkpoint::SessionStatus::Completed => SessionStatus::Completed,
-                crate::checkpoint::SessionStatus::Failed => SessionStatus::Failed,
-                crate::checkpoint::SessionStatus::Archived => SessionStatus::Archived,
-            },
-            created_at: s.created_at,
-            last_accessed: s.last_accessed,
-            description: s.description,
-            tags: s.tags,
-            checkpoint_count: s.checkpoint_count as usize,
-        }).collect())
+        Ok(sessions.into_iter().map(cli_session_metadata).collect())
     }
 
     async fn list_checkpoints(&self, project_path: &PathBuf, session_id: &str) -> CliResult<Vec<CheckpointMetadata>> {
@@ -915,6 +993,9 @@ impl CheckpointAccess for AbkCheckpointAccess {
             iteration: c.iteration as usize,
             description: c.description,
             tags: c.tags,
+            compressed_size: c.compressed_size,
+            uncompressed_size: c.uncompressed_size,
+            shadow_commit: c.shadow_commit,
         }).collect())
     }
 
@@ -959,6 +1040,9 @@ impl CheckpointAccess for AbkCheckpointAccess {
                 iteration: 1,
                 description: Some(format!("Checkpoint {} for session {}", checkpoint_id, session_id)),
                 tags: vec!["resume".to_string()],
+                compressed_size: 0,
+                uncompressed_size: 0,
+                shadow_commit: None,
             },
             agent_state: crate::cli::adapters::checkpoint::AgentStateData {
                 current_mode: "confirm".to_string(),
@@ -985,9 +1069,141 @@ impl CheckpointAccess for AbkCheckpointAccess {
         Err(CliError::CheckpointError("Checkpoint deletion not implemented".to_string()))
     }
 
-    async fn get_checkpoint_diff(&self, _project_path: &PathBuf, _session_id: &str, _from_checkpoint_id: &str, _to_checkpoint_id: &str) -> CliResult<CheckpointDiff> {
-        // TODO: Implement checkpoint diff
-        Err(CliError::CheckpointError("Checkpoint diff not implemented".to_string()))
+    async fn get_checkpoint_diff(&self, project_path: &PathBuf, session_id: &str, from_checkpoint_id: &str, to_checkpoint_id: &str) -> CliResult<CheckpointDiff> {
+        let manager = self.get_configured_storage_manager().await?;
+
+        let project_storage = manager.get_project_storage(project_path).await
+            .map_err(|e| CliError::CheckpointError(format!("Failed to get project storage: {}", e)))?;
+
+        let session_storage = project_storage.create_session(session_id).await
+            .map_err(|e| CliError::CheckpointError(format!("Failed to get session storage: {}", e)))?;
+
+        let from = session_storage.load_checkpoint(from_checkpoint_id).await
+            .map_err(|e| CliError::CheckpointError(format!("Failed to load checkpoint {}: {}", from_checkpoint_id, e)))?;
+        let to = session_storage.load_checkpoint(to_checkpoint_id).await
+            .map_err(|e| CliError::CheckpointError(format!("Failed to load checkpoint {}: {}", to_checkpoint_id, e)))?;
+
+        // Whole-tree changes when both checkpoints have shadow git snapshots
+        let changed_files: Vec<ChangedFile> = match session_storage.diff_snapshots(from_checkpoint_id, to_checkpoint_id) {
+            Ok(diff) => diff.changes.into_iter().map(|c| ChangedFile {
+                path: c.path,
+                change: match c.change_type {
+                    crate::checkpoint::models::FileChangeType::Created => "added",
+                    crate::checkpoint::models::FileChangeType::Deleted => "deleted",
+                    _ => "modified",
+                }.to_string(),
+            }).collect(),
+            Err(_) => Vec::new(),
+        };
+        let files_diff = if from.metadata.shadow_commit.is_some() && to.metadata.shadow_commit.is_some() {
+            changed_files.len() as i32
+        } else {
+            to.file_system_state.modified_files.len() as i32 - from.file_system_state.modified_files.len() as i32
+        };
+
+        let step_from = from.agent_state.current_step.to_string();
+        let step_to = to.agent_state.current_step.to_string();
+        Ok(CheckpointDiff {
+            from_checkpoint_id: from_checkpoint_id.to_string(),
+            to_checkpoint_id: to_checkpoint_id.to_string(),
+            time_difference_seconds: (to.metadata.created_at - from.metadata.created_at).num_seconds(),
+            mode_changed: from.agent_state.current_mode != to.agent_state.current_mode,
+            mode_from: from.agent_state.current_mode,
+            mode_to: to.agent_state.current_mode,
+            step_changed: step_from != step_to,
+            step_from,
+            step_to,
+            messages_diff: to.conversation_state.messages.len() as i32 - from.conversation_state.messages.len() as i32,
+            tokens_diff: to.conversation_state.conversation_stats.total_tokens as i32
+                - from.conversation_state.conversation_stats.total_tokens as i32,
+            files_diff,
+            commands_diff: to.tool_state.executed_commands.len() as i32 - from.tool_state.executed_commands.len() as i32,
+            working_directory_changed: from.file_system_state.working_directory != to.file_system_state.working_directory,
+            changed_files,
+        })
+    }
+
+    async fn fork_session(&self, project_path: &PathBuf, session_id: &str, checkpoint_id: &str, description: Option<String>) -> CliResult<SessionMetadata> {
+        let manager = self.get_configured_storage_manager().await?;
+
+        let project_storage = manager.get_project_storage(project_path).await
+            .map_err(|e| CliError::CheckpointError(format!("Failed to get project storage: {}", e)))?;
+
+        let mut fork = project_storage.fork_session(session_id, checkpoint_id, description).await
+            .map_err(|e| CliError::CheckpointError(format!("Failed to fork session: {}", e)))?;
+
+        let metadata = fork.get_metadata().await
+            .map_err(|e| CliError::CheckpointError(format!("Failed to read forked session: {}", e)))?;
+
+        Ok(cli_session_metadata(metadata.clone()))
+    }
+
+    async fn reencrypt_checkpoints(&self, old_key_file: Option<&Path>) -> CliResult<ReencryptSummary> {
+        let manager = self.get_configured_storage_manager().await?;
+
+        let security = self.checkpoint_config.as_ref()
+            .map(|c| c.security.clone())
+            .unwrap_or_default();
+        let previous = crate::checkpoint::CheckpointCipher::previous_from_config(&security, old_key_file)
+            .map_err(|e| CliError::CheckpointError(format!("Failed to load previous encryption key: {}", e)))?;
+
+        let report = manager.reencrypt_all(previous.as_ref()).await
+            .map_err(|e| CliError::CheckpointError(format!("Failed to re-encrypt checkpoints: {}", e)))?;
+
+        Ok(ReencryptSummary {
+            sessions: report.sessions,
+            files: report.files,
+            events: report.events,
+            blobs: report.blobs,
+            remote_files: report.remote_files,
+            encrypted: manager.cipher().is_some(),
+        })
+    }
+
+    async fn export_project(&self, project_path: &PathBuf, output_path: &Path) -> CliResult<ProjectExportSummary> {
+        let manager = self.get_configured_storage_manager().await?;
+
+        let project_storage = manager.get_project_storage(project_path).await
+            .map_err(|e| CliError::CheckpointError(format!("Failed to get project storage: {}", e)))?;
+
+        let manifest = project_storage.export_project_data(output_path).await
+            .map_err(|e| CliError::CheckpointError(format!("Failed to export project: {}", e)))?;
+
+        Ok(ProjectExportSummary {
+            blobs: manifest.entries.iter().filter(|e| e.path.starts_with("blobs/")).count() as u32,
+            entries: manifest.entries.len() as u32,
+            sessions: manifest.sessions,
+        })
+    }
+
+    async fn import_project(&self, project_path: &PathBuf, input_path: &Path, on_collision: &str) -> CliResult<ProjectImportSummary> {
+        let policy = match on_collision {
+            "skip" => crate::checkpoint::SessionCollisionPolicy::Skip,
+            "rename" => crate::checkpoint::SessionCollisionPolicy::Rename,
+            "overwrite" => crate::checkpoint::SessionCollisionPolicy::Overwrite,
+            "fail" => crate::checkpoint::SessionCollisionPolicy::Fail,
+            other => {
+                return Err(CliError::ValidationError(format!(
+                    "Unknown collision policy '{}'. Use skip, rename, overwrite or fail",
+                    other
+                )))
+            }
+        };
+
+        let manager = self.get_configured_storage_manager().await?;
+
+        let project_storage = manager.get_project_storage(project_path).await
+            .map_err(|e| CliError::CheckpointError(format!("Failed to get project storage: {}", e)))?;
+
+        let report = project_storage.import_project_data_with_policy(input_path, policy).await
+            .map_err(|e| CliError::CheckpointError(format!("Failed to import project: {}", e)))?;
+
+        Ok(ProjectImportSummary {
+            imported: report.imported_sessions,
+            skipped: report.skipped_sessions,
+            renamed: report.renamed_sessions,
+            overwritten: report.overwritten_sessions,
+        })
     }
 }
 
@@ -1274,6 +1490,10 @@ async fn run_command<C: CommandContext>(ctx: &C, matches: &ArgMatches) -> CliRes
         cancel_token: None,
         on_checkpoint: None,
         run_context: ctx.run_context().cloned(),
+        // Ask on the terminal unless a TUI owns it
+        tool_approver: (!crate::observability::is_tui_mode()).then(|| {
+            std::sync::Arc::new(crate::cli::TerminalApprover::new()) as crate::orchestration::SharedApprover
+        }),
     };
 
     crate::cli::commands::run::execute_run(ctx, options).await.map(|_| ())
@@ -1714,12 +1934,51 @@ async fn checkpoints_command<C: CommandContext>(ctx: &C, matches: &ArgMatches) -
             confirm: true, // CLI commands should be confirmed
         };
         crate::cli::commands::checkpoints::delete_checkpoint(ctx, &checkpoint_access, opts).await
+    } else if let Some(spec) = matches.try_get_one::<String>("diff").ok().flatten() {
+        // Parse "session_id/from_checkpoint_id..to_checkpoint_id"
+        let parsed = spec.split_once('/').and_then(|(session_id, range)| {
+            range.split_once("..").map(|(from, to)| (session_id, from, to))
+        });
+        let Some((session_id, from, to)) = parsed else {
+            ctx.log_error("Invalid diff format. Use: session_id/from_checkpoint_id..to_checkpoint_id")?;
+            return Ok(());
+        };
+
+        let opts = crate::cli::commands::checkpoints::DiffOptions {
+            session_id: session_id.to_string(),
+            from_checkpoint_id: from.to_string(),
+            to_checkpoint_id: to.to_string(),
+        };
+        crate::cli::commands::checkpoints::diff_checkpoints(ctx, &checkpoint_access, opts).await
+    } else if matches.try_get_one::<bool>("reencrypt").ok().flatten().copied().unwrap_or(false) {
+        let opts = crate::cli::commands::checkpoints::ReencryptOptions {
+            old_key_file: matches.try_get_one::<String>("old-key-file").ok().flatten().map(PathBuf::from),
+        };
+        crate::cli::commands::checkpoints::reencrypt_checkpoints(ctx, &checkpoint_access, opts).await
+    } else if let Some(output) = matches.try_get_one::<String>("export-project").ok().flatten() {
+        let opts = crate::cli::commands::checkpoints::ProjectExportOptions {
+            project_path: std::env::current_dir()?,
+            output_path: PathBuf::from(output),
+        };
+        crate::cli::commands::checkpoints::export_project(ctx, &checkpoint_access, opts).await
+    } else if let Some(input) = matches.try_get_one::<String>("import-project").ok().flatten() {
+        let opts = crate::cli::commands::checkpoints::ProjectImportOptions {
+            project_path: std::env::current_dir()?,
+            input_path: PathBuf::from(input),
+            on_collision: matches
+                .try_get_one::<String>("on-collision")
+                .ok()
+                .flatten()
+                .cloned()
+                .unwrap_or_else(|| "skip".to_string()),
+        };
+        crate::cli::commands::checkpoints::import_project(ctx, &checkpoint_access, opts).await
     } else if matches.get_flag("clean") {
         ctx.log_warning("Checkpoint cleanup not yet implemented")?;
         ctx.log_info("Use individual checkpoint deletion with --delete instead");
         Ok(())
     } else {
-        ctx.log_info("Use --list, --show <session_id/checkpoint_id>, --delete <session_id/checkpoint_id>, or --clean flags");
+        ctx.log_info("Use --list, --show <session_id/checkpoint_id>, --delete <session_id/checkpoint_id>, --diff <session_id/from..to>, --reencrypt, --export-project <file>, --import-project <file> [--on-collision skip|rename|overwrite|fail], or --clean flags");
         Ok(())
     }
 }
@@ -1749,8 +2008,22 @@ async fn sessions_command<C: CommandContext>(ctx: &C, matches: &ArgMatches) -> C
             confirm: true,
         };
         crate::cli::commands::sessions::delete_session(ctx, &checkpoint_access, opts).await
+    } else if let Some(id) = matches.try_get_one::<String>("fork").ok().flatten() {
+        // Parse session_id/checkpoint_id from the format "session_id/checkpoint_id"
+        let parts: Vec<&str> = id.split('/').collect();
+        if parts.len() != 2 {
+            ctx.log_error("Invalid fork point format. Use: session_id/checkpoint_id")?;
+            return Ok(());
+        }
+
+        let opts = crate::cli::commands::sessions::ForkOptions {
+            session_id: parts[0].to_string(),
+            checkpoint_id: parts[1].to_string(),
+            description: matches.try_get_one::<String>("description").ok().flatten().cloned(),
+        };
+        crate::cli::commands::sessions::fork_session(ctx, &checkpoint_access, opts).await
     } else {
-        ctx.log_info("Use --list, --show <id>, or --delete <id> flags");
+        ctx.log_info("Use --list, --show <id>, --delete <id>, or --fork <session_id/checkpoint_id> flags");
         Ok(())
     }
 }
@@ -1786,7 +2059,7 @@ async fn extension_command<C: CommandContext>(ctx: &C, matches: &ArgMatches) ->
     if matches.get_flag("list") {
         crate::cli::commands::extension::list(ctx).await
     } else if let Some(path) = matches.get_one::<String>("install") {
-        crate::cli::commands::extension::install(ctx, path, None).await
+        crate::cli::commands::extension::install(ctx, path, None, matches.get_flag("yes")).await
     } else if let Some(name) = matches.get_one::<String>("remove") {
         crate::cli::commands::extension::remove(ctx, name).await
     } else if let Some(name) = matches.get_one::<String>("info") {
@@ -39,6 +39,7 @@ pub struct Configuration {
 /// Tool source configuration for unified registry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
+#[allow(clippy::large_enum_variant)] // Parsed once from config; boxing would complicate matching
 pub enum ToolSourceConfig {
     /// Native cats tool source
     Native {
@@ -50,7 +51,8 @@ pub enum ToolSourceConfig {
     Mcp {
         /// Server name/identifier
         name: String,
-        /// Server URL
+        /// Server URL (HTTP transport)
+        #[serde(default)]
         url: String,
         /// Optional authentication token (supports env var substitution)
         #[serde(default)]
@@ -58,6 +60,30 @@ pub enum ToolSourceConfig {
         /// Auto-initialize connection
         #[serde(default = "default_auto_init")]
         auto_init: bool,
+        /// HTTP transport: "http" (legacy) or "streamable-http"
+        #[serde(default = "default_transport")]
+        transport: String,
+        /// Executable to launch; when set the server uses the stdio transport
+        #[serde(default)]
+        command: Option<String>,
+        /// Command-line arguments (stdio transport)
+        #[serde(default)]
+        args: Vec<String>,
+        /// Extra environment variables (stdio transport)
+        #[serde(default)]
+        env: HashMap<String, String>,
+        /// Working directory (stdio transport)
+        #[serde(default)]
+        cwd: Option<String>,
+        /// Seconds to wait for the handshake and each response (stdio
+        /// transport, default 30)
+        #[serde(default)]
+        timeout_seconds: Option<u64>,
+    },
+    /// WASM extension with the tools capability
+    Extension {
+        /// Extension directory containing `extension.toml` (supports `~`)
+        path: String,
     },
 }
 
@@ -86,7 +112,8 @@ pub struct McpConfig {
     /// Enable MCP tool discovery
     #[serde(default)]
     pub enabled: bool,
-    /// Timeout for MCP server requests (seconds)
+    /// Timeout for MCP server requests (seconds); stdio servers fail a
+    /// handshake or request that takes longer
     #[serde(default = "default_mcp_timeout")]
     pub timeout_seconds: u64,
     /// Named credential definitions (shared across servers)
@@ -117,11 +144,29 @@ impl Default for McpConfig {
 pub struct McpServerConfig {
     /// Server identifier (used for logging and tool namespacing)
     pub name: String,
-    /// Server URL (SSE endpoint for HTTP transport)
+    /// Server URL (SSE endpoint for HTTP transport; unused for stdio)
+    #[serde(default)]
     pub url: String,
-    /// Transport type: "http" (SSE) or "stdio"
+    /// Transport type: "http" (legacy `{url}/message`), "streamable-http"
+    /// (MCP Streamable HTTP: SSE responses, session ids, notifications) or "stdio"
     #[serde(default = "default_transport")]
     pub transport: String,
+    /// Executable to launch (stdio transport)
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Command-line arguments (stdio transport)
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Extra environment variables for the server process (stdio transport).
+    /// Values support env var substitution.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Working directory for the server process (stdio transport)
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Per-server override of `mcp.timeout_seconds` (stdio transport)
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
     /// Optional static authentication token (supports env var substitution).
     /// Use `credentials` for dynamic token management.
     pub auth_token: Option<String>,
@@ -291,6 +336,15 @@ pub struct ToolsConfig {
     /// e.g. `["bash"]` = all tools except bash.
     #[serde(default)]
     pub disabled_tools: Vec<String>,
+    /// Maximum number of parallel-safe tool calls dispatched concurrently
+    /// within one assistant turn. `None` = 4; `1` disables parallel dispatch.
+    #[serde(default)]
+    pub max_parallel: Option<usize>,
+    /// Native tools treated as parallel-safe (read-only). `None` = the
+    /// built-in read-only set (`read`, `glob`, `grep`, `list`).
+    /// MCP tools are classified by their `readOnlyHint`/`idempotentHint` annotations.
+    #[serde(default)]
+    pub parallel_safe_tools: Option<Vec<String>>,
 }
 
 /// LLM provider configuration
@@ -302,6 +356,15 @@ pub struct LlmConfig {
     /// (e.g., session title generation). Falls back to main provider if absent.
     #[serde(default)]
     pub utility: Option<UtilityLlmConfig>,
+    /// Per-model prices used to estimate session cost, keyed by model name
+    /// (`[llm.pricing."gpt-4o-mini"]`). A key also matches model names it
+    /// prefixes, so `claude-sonnet-4-5` covers dated snapshots.
+    #[serde(default)]
+    pub pricing: HashMap<String, ModelPricing>,
+    /// Ordered provider/model backends with failover and routing rules.
+    /// Absent = the single provider selected by `LLM_PROVIDER`.
+    #[serde(default)]
+    pub routing: Option<RoutingConfig>,
 }
 
 impl Default for LlmConfig {
@@ -310,10 +373,103 @@ impl Default for LlmConfig {
             endpoint: "chat/completions".to_string(),
             enable_streaming: true,
             utility: None,
+            pricing: HashMap::new(),
+            routing: None,
+        }
+    }
+}
+
+/// Provider routing and failover (`[llm.routing]`)
+///
+/// Calls go to the first backend (or the one picked by a matching rule) and
+/// fail over down the list on rate limits, server errors and timeouts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutingConfig {
+    /// Backends in failover order (`[[llm.routing.backends]]`).
+    #[serde(default)]
+    pub backends: Vec<RoutingBackendConfig>,
+    /// Rules choosing the first backend for a call (`[[llm.routing.rules]]`).
+    /// The first matching rule wins. Default: none (always start at the first backend).
+    #[serde(default)]
+    pub rules: Vec<RoutingRuleConfig>,
+    /// Consecutive failures that open a backend's circuit breaker. Default: 3.
+    #[serde(default = "default_routing_failure_threshold")]
+    pub failure_threshold: u32,
+    /// Seconds an open circuit skips its backend before a trial call. Default: 60.
+    #[serde(default = "default_routing_cooldown_seconds")]
+    pub cooldown_seconds: u64,
+    /// Retries each backend makes on 429/5xx/network errors before the call
+    /// fails over, replacing `LLM_MAX_RETRIES` for routed providers. Default: 0.
+    #[serde(default)]
+    pub backend_max_retries: u32,
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            backends: Vec::new(),
+            rules: Vec::new(),
+            failure_threshold: default_routing_failure_threshold(),
+            cooldown_seconds: default_routing_cooldown_seconds(),
+            backend_max_retries: 0,
         }
     }
 }
 
+fn default_routing_failure_threshold() -> u32 {
+    3
+}
+
+fn default_routing_cooldown_seconds() -> u64 {
+    60
+}
+
+/// One routing backend: a provider and optionally a model.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoutingBackendConfig {
+    /// Name used by rules and fallback events. Default: "provider/model".
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Provider, as for `LLM_PROVIDER`. Default: `LLM_PROVIDER` (or the native OpenAI provider).
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// Model for every call on this backend. Default: the model the caller
+    /// requested, else the provider's default model.
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// Rule sending matching calls to a backend first (`[[llm.routing.rules]]`).
+///
+/// All conditions that are set must match.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoutingRuleConfig {
+    /// Backend name to start with.
+    pub backend: String,
+    /// Match calls with (`true`) or without (`false`) tools. Calls without
+    /// tools are summaries, titles and classification. Default: either.
+    #[serde(default)]
+    pub tools: Option<bool>,
+    /// Match calls whose `max_tokens` is at most this. Default: any.
+    #[serde(default)]
+    pub max_tokens_at_most: Option<u32>,
+    /// Match calls requesting this model. Default: any.
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// Price of one model in USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    /// Uncached prompt tokens
+    pub input: f64,
+    /// Completion tokens (including reasoning)
+    pub output: f64,
+    /// Prompt tokens served from cache. None = billed as `input`.
+    #[serde(default)]
+    pub cached_input: Option<f64>,
+}
+
 /// Configuration for utility LLM calls (session titles, summaries, etc.)
 ///
 /// When present in `[llm.utility]`, these settings override the main provider's
@@ -414,6 +570,70 @@ pub struct LoggingConfig {
     #[serde(default)]
     pub log_dir: String,
     pub log_level: String,
+    /// Structured span export (JSONL file and/or OTLP collector).
+    /// Absent = markdown log only.
+    #[serde(default)]
+    pub tracing: Option<TracingConfig>,
+    /// Prometheus metrics exposition (HTTP endpoint and/or textfile).
+    /// Absent = metrics are collected in memory only.
+    #[serde(default)]
+    pub metrics: Option<MetricsConfig>,
+}
+
+/// Structured tracing configuration (`[logging.tracing]`)
+///
+/// Records session → iteration → API call → tool call spans with durations,
+/// token counts, model, tool name and success.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracingConfig {
+    /// Write spans as JSON lines. Default: true.
+    #[serde(default = "default_tracing_jsonl")]
+    pub jsonl: bool,
+    /// JSONL file. Default: the markdown log path with a `.trace.jsonl` extension.
+    #[serde(default)]
+    pub jsonl_path: Option<String>,
+    /// OTLP/HTTP traces endpoint, e.g. "http://localhost:4318/v1/traces".
+    /// Requires the `observability-otlp` feature. Default: none.
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+    /// Extra headers for the OTLP endpoint (supports `${VAR}` values).
+    #[serde(default)]
+    pub otlp_headers: HashMap<String, String>,
+    /// `service.name` reported to the collector. Default: the agent name.
+    #[serde(default)]
+    pub service_name: Option<String>,
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        Self {
+            jsonl: default_tracing_jsonl(),
+            jsonl_path: None,
+            otlp_endpoint: None,
+            otlp_headers: HashMap::new(),
+            service_name: None,
+        }
+    }
+}
+
+fn default_tracing_jsonl() -> bool {
+    true
+}
+
+/// Metrics exposition configuration (`[logging.metrics]`)
+///
+/// Exposes API call, latency, tool, checkpoint and retry metrics in the
+/// Prometheus text format.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    /// Address for a `/metrics` HTTP endpoint, e.g. "127.0.0.1:9464".
+    /// Default: none (no endpoint).
+    #[serde(default)]
+    pub listen: Option<String>,
+    /// File the metrics are written to at the end of each run, for the node
+    /// exporter textfile collector. Supports `~`. Default: none.
+    #[serde(default)]
+    pub textfile: Option<String>,
 }
 
 /// Execution configuration
@@ -426,6 +646,249 @@ pub struct ExecutionConfig {
     pub max_history: u32,
     pub request_interval_seconds: u64,
     pub enable_dangerous_command_validation: bool,
+    /// Summarize older turns instead of only dropping them by count.
+    /// Absent = history is only limited by `max_history`.
+    #[serde(default)]
+    pub compaction: Option<CompactionConfig>,
+    /// Hard token, cost and wall-clock ceilings for a session.
+    /// Absent = only `max_iterations` bounds a run.
+    #[serde(default)]
+    pub budget: Option<BudgetConfig>,
+    /// Sandbox used by modes with `execution_backend = "sandbox"`.
+    /// Absent = defaults (no network, project dir writable, 300s CPU, 4 GiB).
+    #[serde(default)]
+    pub sandbox: Option<SandboxConfig>,
+    /// Context retrieved from `context` extensions and inserted into prompts.
+    /// Absent = no context injection.
+    #[serde(default)]
+    pub context: Option<ContextInjectionConfig>,
+}
+
+/// Context compaction configuration (`[execution.compaction]`)
+///
+/// When the conversation grows past `trigger_tokens`, older turns are
+/// summarized with the utility model (`[llm.utility]`, falling back to the
+/// main provider) and replaced by the summary.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompactionConfig {
+    /// Enable compaction. Default: true.
+    #[serde(default = "default_compaction_enabled")]
+    pub enabled: bool,
+    /// Compact when the conversation exceeds this many tokens. Default: 80000.
+    #[serde(default = "default_compaction_trigger_tokens")]
+    pub trigger_tokens: usize,
+    /// Most recent messages kept verbatim. Default: 20.
+    #[serde(default = "default_compaction_keep_recent")]
+    pub keep_recent_messages: usize,
+    /// Max tokens for the generated summary. Default: 2000.
+    #[serde(default = "default_compaction_summary_max_tokens")]
+    pub summary_max_tokens: u32,
+}
+
+impl Default for CompactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_compaction_enabled(),
+            trigger_tokens: default_compaction_trigger_tokens(),
+            keep_recent_messages: default_compaction_keep_recent(),
+            summary_max_tokens: default_compaction_summary_max_tokens(),
+        }
+    }
+}
+
+fn default_compaction_enabled() -> bool {
+    true
+}
+
+fn default_compaction_trigger_tokens() -> usize {
+    80_000
+}
+
+fn default_compaction_keep_recent() -> usize {
+    20
+}
+
+fn default_compaction_summary_max_tokens() -> u32 {
+    2000
+}
+
+/// Session budget configuration (`[execution.budget]`)
+///
+/// Each limit is optional. When one is reached the workflow takes that
+/// limit's action (`on_<limit>_exceeded`), falling back to `on_exceeded`.
+/// Cost is only known for models listed in `[llm.pricing]`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetConfig {
+    /// Max prompt + completion tokens across all LLM calls.
+    #[serde(default)]
+    pub max_total_tokens: Option<u64>,
+    /// Max estimated spend in USD.
+    #[serde(default)]
+    pub max_cost_usd: Option<f64>,
+    /// Max wall-clock time of a run, in seconds.
+    #[serde(default)]
+    pub max_duration_seconds: Option<u64>,
+    /// Action for any limit without its own override. Default: stop.
+    #[serde(default)]
+    pub on_exceeded: BudgetAction,
+    /// Action when `max_total_tokens` is reached.
+    #[serde(default)]
+    pub on_tokens_exceeded: Option<BudgetAction>,
+    /// Action when `max_cost_usd` is reached.
+    #[serde(default)]
+    pub on_cost_exceeded: Option<BudgetAction>,
+    /// Action when `max_duration_seconds` is reached.
+    #[serde(default)]
+    pub on_duration_exceeded: Option<BudgetAction>,
+}
+
+/// What the workflow does when a budget limit is reached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetAction {
+    /// Stop the run and write a final checkpoint.
+    #[default]
+    Stop,
+    /// Ask the tool approver whether to continue; stop if denied or
+    /// if no approver is set.
+    Approve,
+    /// Continue on the utility model (`[llm.utility].model`); stop if
+    /// none is configured.
+    UtilityModel,
+}
+
+impl std::fmt::Display for BudgetAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Stop => write!(f, "stop"),
+            Self::Approve => write!(f, "approve"),
+            Self::UtilityModel => write!(f, "utility_model"),
+        }
+    }
+}
+
+/// Command sandbox configuration (`[execution.sandbox]`)
+///
+/// Commands run under bubblewrap with only the system directories mounted,
+/// read-only, and a cleared environment. Only the project directory, a
+/// private `/tmp` and `writable_paths` can be written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxConfig {
+    /// bubblewrap binary, looked up on `PATH` unless it's a path. Default: "bwrap".
+    #[serde(default = "default_sandbox_bwrap_path")]
+    pub bwrap_path: String,
+    /// Extra writable paths (e.g. "~/.cargo/registry"). Default: none.
+    #[serde(default)]
+    pub writable_paths: Vec<String>,
+    /// Extra read-only paths (e.g. "~/.rustup"). Default: none.
+    #[serde(default)]
+    pub readable_paths: Vec<String>,
+    /// Environment variables passed through besides PATH, locale and TERM. Default: none.
+    #[serde(default)]
+    pub env_passthrough: Vec<String>,
+    /// Allow network access. Default: false.
+    #[serde(default)]
+    pub allow_network: bool,
+    /// CPU time limit per command in seconds, 0 = unlimited. Default: 300.
+    #[serde(default = "default_sandbox_cpu_seconds")]
+    pub cpu_seconds: u64,
+    /// Address-space limit per command in MiB, 0 = unlimited. Default: 4096.
+    #[serde(default = "default_sandbox_memory_mb")]
+    pub memory_mb: u64,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            bwrap_path: default_sandbox_bwrap_path(),
+            writable_paths: Vec::new(),
+            readable_paths: Vec::new(),
+            env_passthrough: Vec::new(),
+            allow_network: false,
+            cpu_seconds: default_sandbox_cpu_seconds(),
+            memory_mb: default_sandbox_memory_mb(),
+        }
+    }
+}
+
+fn default_sandbox_bwrap_path() -> String {
+    "bwrap".to_string()
+}
+
+fn default_sandbox_cpu_seconds() -> u64 {
+    300
+}
+
+fn default_sandbox_memory_mb() -> u64 {
+    4096
+}
+
+/// Context injection configuration (`[execution.context]`)
+///
+/// Extensions with the `context` capability are asked for context blocks
+/// before the first LLM call and, if `refresh_every` is set, every N
+/// iterations after that. Blocks are kept by priority until `max_tokens`
+/// is used up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextInjectionConfig {
+    /// Enable context injection. Default: true.
+    #[serde(default = "default_context_enabled")]
+    pub enabled: bool,
+    /// Extension directories to query (supports `~`). Default: every installed
+    /// extension with the `context` capability.
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// Re-query the extensions every N iterations, 0 = only before the first call. Default: 0.
+    #[serde(default)]
+    pub refresh_every: u32,
+    /// Token budget for all injected blocks together. Default: 4000.
+    #[serde(default = "default_context_max_tokens")]
+    pub max_tokens: usize,
+    /// Role of the injected message. Default: user.
+    #[serde(default)]
+    pub role: ContextRole,
+}
+
+impl Default for ContextInjectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_context_enabled(),
+            extensions: Vec::new(),
+            refresh_every: 0,
+            max_tokens: default_context_max_tokens(),
+            role: ContextRole::default(),
+        }
+    }
+}
+
+fn default_context_enabled() -> bool {
+    true
+}
+
+fn default_context_max_tokens() -> usize {
+    4000
+}
+
+/// Message role used for injected context.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextRole {
+    /// A system message placed after the leading system prompt.
+    System,
+    /// A user message appended to the conversation.
+    #[default]
+    User,
+}
+
+/// Where a mode runs shell commands.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionBackendKind {
+    /// Directly on the host.
+    #[default]
+    Host,
+    /// Inside the `[execution.sandbox]` bubblewrap sandbox.
+    Sandbox,
 }
 
 /// Modes configuration
@@ -436,11 +899,26 @@ pub struct ModesConfig {
     pub human: ModeConfig,
 }
 
+impl ModesConfig {
+    /// Look up the configuration for a mode by name ("confirm", "yolo", "human").
+    pub fn for_mode(&self, mode: &str) -> Option<&ModeConfig> {
+        match mode {
+            "confirm" => Some(&self.confirm),
+            "yolo" => Some(&self.yolo),
+            "human" => Some(&self.human),
+            _ => None,
+        }
+    }
+}
+
 /// Individual mode configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModeConfig {
     pub description: String,
     pub auto_execute: bool,
+    /// Where shell commands run in this mode. Default: host.
+    #[serde(default)]
+    pub execution_backend: ExecutionBackendKind,
 }
 
 /// Loads and manages TOML configuration.
@@ -526,6 +1004,8 @@ impl ConfigurationLoader {
             logging: LoggingConfig {
                 log_dir: String::new(),  // Empty string - Logger will use default /tmp/{ABK_AGENT_NAME}/
                 log_level: "INFO".to_string(),
+                tracing: None,
+                metrics: None,
             },
             execution: ExecutionConfig {
                 timeout_seconds: 120,
@@ -535,6 +1015,10 @@ impl ConfigurationLoader {
                 enable_dangerous_command_validation: true,
                 max_iterations: 100,
                 request_interval_seconds: 0,
+                compaction: None,
+                budget: None,
+                sandbox: None,
+                context: None,
             },
             tools: ToolsConfig {
                 open_file_window_size: Some(1000),
@@ -542,6 +1026,8 @@ impl ConfigurationLoader {
                 truncate_large_results: Some(true),
                 enabled_tools: None,
                 disabled_tools: vec![],
+                max_parallel: None,
+                parallel_safe_tools: None,
             },
             search_filtering: Some(SearchFilteringConfig::default()),
             llm: Some(LlmConfig::default()),
@@ -549,14 +1035,17 @@ impl ConfigurationLoader {
                 confirm: ModeConfig {
                     description: "Agent proposes actions and asks for confirmation".to_string(),
                     auto_execute: false,
+                    execution_backend: ExecutionBackendKind::Host,
                 },
                 yolo: ModeConfig {
                     description: "Actions run immediately without confirmation".to_string(),
                     auto_execute: true,
+                    execution_backend: ExecutionBackendKind::Host,
                 },
                 human: ModeConfig {
                     description: "Human enters commands directly".to_string(),
                     auto_execute: false,
+                    execution_backend: ExecutionBackendKind::Host,
                 },
             },
             mcp: None,
@@ -700,6 +1189,8 @@ mod tests {
         assert_eq!(config.execution.request_interval_seconds, 0);
         assert!(!config.modes.confirm.auto_execute);
         assert!(config.modes.yolo.auto_execute);
+        assert!(!config.modes.for_mode("human").unwrap().auto_execute);
+        assert!(config.modes.for_mode("unknown").is_none());
 
         // Test LLM defaults
         assert!(config.llm.is_some());
@@ -1051,6 +1542,207 @@ disabled_tools = ["bash"]
         assert!(config.tools.disabled_tools.is_empty());
     }
 
+    #[test]
+    fn test_parallel_tool_config() {
+        let config = ConfigurationLoader::get_default_config();
+        assert!(config.tools.max_parallel.is_none());
+        assert!(config.tools.parallel_safe_tools.is_none());
+
+        let tools: ToolsConfig = toml::from_str(
+            r#"
+max_parallel = 8
+parallel_safe_tools = ["read", "webfetch"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(tools.max_parallel, Some(8));
+        assert_eq!(
+            tools.parallel_safe_tools,
+            Some(vec!["read".to_string(), "webfetch".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_compaction_config_defaults() {
+        let config = ConfigurationLoader::get_default_config();
+        assert!(config.execution.compaction.is_none());
+
+        let compaction: CompactionConfig = toml::from_str("trigger_tokens = 5000").unwrap();
+        assert!(compaction.enabled);
+        assert_eq!(compaction.trigger_tokens, 5000);
+        assert_eq!(compaction.keep_recent_messages, 20);
+        assert_eq!(compaction.summary_max_tokens, 2000);
+    }
+
+    #[test]
+    fn test_sandbox_backend_per_mode() {
+        let modes: ModesConfig = toml::from_str(
+            r#"
+            [confirm]
+            description = "confirm"
+            auto_execute = false
+            [yolo]
+            description = "yolo"
+            auto_execute = true
+            execution_backend = "sandbox"
+            [human]
+            description = "human"
+            auto_execute = false
+            "#,
+        )
+        .unwrap();
+        assert_eq!(modes.confirm.execution_backend, ExecutionBackendKind::Host);
+        assert_eq!(modes.yolo.execution_backend, ExecutionBackendKind::Sandbox);
+
+        let sandbox: SandboxConfig = toml::from_str("writable_paths = [\"/var/cache\"]").unwrap();
+        assert_eq!(sandbox.bwrap_path, "bwrap");
+        assert!(!sandbox.allow_network);
+        assert_eq!(sandbox.cpu_seconds, 300);
+        assert_eq!(sandbox.memory_mb, 4096);
+    }
+
+    #[test]
+    fn test_budget_config() {
+        let config = ConfigurationLoader::get_default_config();
+        assert!(config.execution.budget.is_none());
+
+        let budget: BudgetConfig = toml::from_str(
+            r#"
+            max_total_tokens = 500000
+            max_cost_usd = 2.5
+            on_exceeded = "approve"
+            on_cost_exceeded = "utility_model"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(budget.max_total_tokens, Some(500_000));
+        assert_eq!(budget.max_cost_usd, Some(2.5));
+        assert!(budget.max_duration_seconds.is_none());
+        assert_eq!(budget.on_exceeded, BudgetAction::Approve);
+        assert_eq!(budget.on_cost_exceeded, Some(BudgetAction::UtilityModel));
+        assert!(budget.on_tokens_exceeded.is_none());
+
+        assert_eq!(BudgetConfig::default().on_exceeded, BudgetAction::Stop);
+    }
+
+    #[test]
+    fn test_tracing_config() {
+        let config = ConfigurationLoader::get_default_config();
+        assert!(config.logging.tracing.is_none());
+
+        let logging: LoggingConfig = toml::from_str(
+            r#"
+            log_level = "INFO"
+
+            [tracing]
+            otlp_endpoint = "http://localhost:4318/v1/traces"
+            otlp_headers = { "x-api-key" = "${OTLP_KEY}" }
+            "#,
+        )
+        .unwrap();
+        let tracing = logging.tracing.unwrap();
+        assert!(tracing.jsonl);
+        assert!(tracing.jsonl_path.is_none());
+        assert_eq!(tracing.otlp_endpoint.as_deref(), Some("http://localhost:4318/v1/traces"));
+        assert_eq!(tracing.otlp_headers["x-api-key"], "${OTLP_KEY}");
+    }
+
+    #[test]
+    fn test_metrics_config() {
+        let config = ConfigurationLoader::get_default_config();
+        assert!(config.logging.metrics.is_none());
+
+        let logging: LoggingConfig = toml::from_str(
+            r#"
+            log_level = "INFO"
+
+            [metrics]
+            textfile = "~/.trustee/metrics/agent.prom"
+            "#,
+        )
+        .unwrap();
+        let metrics = logging.metrics.unwrap();
+        assert!(metrics.listen.is_none());
+        assert_eq!(metrics.textfile.as_deref(), Some("~/.trustee/metrics/agent.prom"));
+    }
+
+    #[test]
+    fn test_context_injection_config() {
+        let config = ConfigurationLoader::get_default_config();
+        assert!(config.execution.context.is_none());
+
+        let context: ContextInjectionConfig = toml::from_str(
+            r#"
+            extensions = ["~/.trustee/extensions/repo-map"]
+            refresh_every = 5
+            role = "system"
+            "#,
+        )
+        .unwrap();
+        assert!(context.enabled);
+        assert_eq!(context.extensions, vec!["~/.trustee/extensions/repo-map"]);
+        assert_eq!(context.refresh_every, 5);
+        assert_eq!(context.max_tokens, 4000);
+        assert_eq!(context.role, ContextRole::System);
+        assert_eq!(ContextInjectionConfig::default().role, ContextRole::User);
+    }
+
+    #[test]
+    fn test_llm_pricing_table() {
+        let llm: LlmConfig = toml::from_str(
+            r#"
+endpoint = "chat/completions"
+enable_streaming = true
+
+[pricing."gpt-4o-mini"]
+input = 0.15
+output = 0.6
+cached_input = 0.075
+"#,
+        )
+        .unwrap();
+        let pricing = llm.pricing["gpt-4o-mini"];
+        assert_eq!(pricing.output, 0.6);
+        assert_eq!(pricing.cached_input, Some(0.075));
+        assert!(LlmConfig::default().pricing.is_empty());
+    }
+
+    #[test]
+    fn test_llm_routing_config() {
+        assert!(LlmConfig::default().routing.is_none());
+
+        let llm: LlmConfig = toml::from_str(
+            r#"
+endpoint = "chat/completions"
+enable_streaming = true
+
+[routing]
+cooldown_seconds = 30
+
+[[routing.backends]]
+name = "strong"
+provider = "anthropic"
+model = "claude-sonnet-4-5"
+
+[[routing.backends]]
+name = "cheap"
+model = "gpt-4o-mini"
+
+[[routing.rules]]
+backend = "cheap"
+tools = false
+"#,
+        )
+        .unwrap();
+        let routing = llm.routing.unwrap();
+        assert_eq!(routing.failure_threshold, 3);
+        assert_eq!(routing.cooldown_seconds, 30);
+        assert_eq!(routing.backends.len(), 2);
+        assert!(routing.backends[1].provider.is_none());
+        assert_eq!(routing.rules[0].backend, "cheap");
+        assert_eq!(routing.rules[0].tools, Some(false));
+    }
+
     #[test]
     fn test_tool_filtering_empty_allowlist() {
         use std::fs;
@@ -38,11 +38,45 @@ impl EnvironmentLoader {
 
     /// Get LLM provider selection from environment.
     ///
-    /// This is the ONLY provider-related env var the host cares about.
+    /// This is the only provider *selection* the host makes; the cassette
+    /// paths below only wrap or replace the selected provider.
     /// Returns the provider name (e.g., "openai", "anthropic", "tanbal") or None to use default.
     pub fn llm_provider(&self) -> Option<String> {
         env::var("LLM_PROVIDER").ok()
     }
+
+    /// Cassette file to record provider calls to (`LLM_RECORD`).
+    ///
+    /// Requires the `provider-replay` feature.
+    pub fn llm_record_path(&self) -> Option<String> {
+        env::var("LLM_RECORD").ok().filter(|p| !p.is_empty())
+    }
+
+    /// Cassette file to replay provider calls from instead of calling a
+    /// provider (`LLM_REPLAY`).
+    ///
+    /// Requires the `provider-replay` feature.
+    pub fn llm_replay_path(&self) -> Option<String> {
+        env::var("LLM_REPLAY").ok().filter(|p| !p.is_empty())
+    }
+
+    /// How replayed requests are matched to recorded calls
+    /// (`LLM_REPLAY_MATCH`: `hash`, the default, or `sequential`).
+    ///
+    /// Requires the `provider-replay` feature.
+    pub fn llm_replay_match(&self) -> Option<String> {
+        env::var("LLM_REPLAY_MATCH").ok().filter(|m| !m.is_empty())
+    }
+
+    /// Whether replayed streams keep their recorded chunk timing
+    /// (`LLM_REPLAY_REALTIME=1` or `true`).
+    ///
+    /// Requires the `provider-replay` feature.
+    pub fn llm_replay_realtime(&self) -> bool {
+        env::var("LLM_REPLAY_REALTIME")
+            .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false)
+    }
 }
 
 impl Default for EnvironmentLoader {
@@ -68,6 +102,23 @@ mod tests {
         env::remove_var("LLM_PROVIDER");
     }
 
+    #[test]
+    fn test_llm_replay_settings() {
+        env::remove_var("LLM_REPLAY_MATCH");
+        env::remove_var("LLM_REPLAY_REALTIME");
+        let env_loader = EnvironmentLoader::default();
+        assert_eq!(env_loader.llm_replay_match(), None);
+        assert!(!env_loader.llm_replay_realtime());
+
+        env::set_var("LLM_REPLAY_MATCH", "sequential");
+        env::set_var("LLM_REPLAY_REALTIME", "true");
+        assert_eq!(env_loader.llm_replay_match(), Some("sequential".to_string()));
+        assert!(env_loader.llm_replay_realtime());
+
+        env::remove_var("LLM_REPLAY_MATCH");
+        env::remove_var("LLM_REPLAY_REALTIME");
+    }
+
     #[test]
     fn test_env_file_loading() {
         // Test that EnvironmentLoader can be created
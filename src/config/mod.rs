@@ -26,8 +26,9 @@ pub mod environment;
 
 // Re-export main types for convenience
 pub use self::config::{
-    AgentConfig, Configuration, ConfigurationLoader, ExecutionConfig, ExchangeConfig, LlmConfig, LoggingConfig,
-    McpConfig, McpCredentialConfig, McpServerConfig, ModeConfig, ModesConfig,
-    SearchFilteringConfig, ToolSourceConfig, ToolsConfig,
+    AgentConfig, BudgetAction, BudgetConfig, CompactionConfig, Configuration, ContextInjectionConfig, ContextRole, ConfigurationLoader, ExecutionBackendKind, ExecutionConfig, ExchangeConfig, LlmConfig, LoggingConfig,
+    McpConfig, McpCredentialConfig, McpServerConfig, ModeConfig, ModelPricing, ModesConfig,
+    RoutingBackendConfig, RoutingConfig, RoutingRuleConfig,
+    MetricsConfig, SandboxConfig, SearchFilteringConfig, ToolSourceConfig, ToolsConfig, TracingConfig, UtilityLlmConfig,
 };
 pub use self::environment::EnvironmentLoader;
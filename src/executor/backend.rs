@@ -0,0 +1,261 @@
+//! Execution backends - where and how shell commands run.
+//!
+//! [`HostBackend`] runs commands directly with `sh -c` (PowerShell on
+//! Windows). [`BubblewrapBackend`] runs them inside a
+//! [bubblewrap](https://github.com/containers/bubblewrap) sandbox: only the
+//! system directories ([`SANDBOX_SYSTEM_DIRS`]) are mounted, read-only, so
+//! home directories and agent configuration stay hidden; the project
+//! directory (plus any configured paths) is writable, `/tmp` is private, the
+//! environment is cleared except for [`SANDBOX_ENV`] (API keys are not
+//! passed on), the network is unshared unless allowed, and CPU time and
+//! address space are capped with `ulimit` before the command starts.
+
+use anyhow::{anyhow, Result};
+use std::ffi::OsString;
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::process::Command as TokioCommand;
+
+use super::ExecutionResult;
+
+/// Host directories mounted read-only in the sandbox, when they exist.
+pub const SANDBOX_SYSTEM_DIRS: &[&str] = &[
+    "/usr",
+    "/bin",
+    "/sbin",
+    "/lib",
+    "/lib32",
+    "/lib64",
+    "/libx32",
+    "/etc",
+    "/opt",
+    "/nix/store",
+];
+
+/// Environment variables passed into the sandbox, when set.
+pub const SANDBOX_ENV: &[&str] = &[
+    "PATH", "LANG", "LANGUAGE", "LC_ALL", "LC_CTYPE", "TERM", "TZ", "USER", "LOGNAME",
+];
+
+/// Builds the process that runs a shell command.
+pub trait ExecutionBackend: Send + Sync + Debug {
+    /// Short backend name used in logs and error messages.
+    fn name(&self) -> &str;
+
+    /// Build the process running `command` in `working_dir`.
+    ///
+    /// `project_dir` is the agent's project root; sandboxing backends use it
+    /// as the writable area. Errors are meant to be shown to the model, so
+    /// they should say what is wrong and how to fix it.
+    fn command(&self, command: &str, working_dir: &Path, project_dir: &Path) -> Result<TokioCommand>;
+
+    /// Explain a failed command whose output suggests it hit one of the
+    /// backend's restrictions.
+    fn explain_failure(&self, _result: &ExecutionResult, _project_dir: &Path) -> Option<String> {
+        None
+    }
+}
+
+/// Shared backend handle.
+pub type SharedBackend = Arc<dyn ExecutionBackend>;
+
+/// Runs commands directly on the host.
+#[derive(Debug, Clone, Default)]
+pub struct HostBackend;
+
+impl ExecutionBackend for HostBackend {
+    fn name(&self) -> &str {
+        "host"
+    }
+
+    fn command(&self, command: &str, working_dir: &Path, _project_dir: &Path) -> Result<TokioCommand> {
+        // Use PowerShell on Windows to avoid CMD quoting pitfalls:
+        // CMD expands %VAR%, has no single-quote support, and mangles backslash-escaped quotes.
+        // PowerShell treats % as a literal character and has predictable quoting rules.
+        let mut cmd = if cfg!(target_os = "windows") {
+            let mut c = TokioCommand::new("powershell.exe");
+            c.arg("-NoProfile").arg("-Command");
+            c
+        } else {
+            let mut c = TokioCommand::new("sh");
+            c.arg("-c");
+            c
+        };
+        cmd.arg(command).current_dir(working_dir);
+        Ok(cmd)
+    }
+}
+
+/// Restrictions applied by a sandboxing backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SandboxPolicy {
+    /// Paths writable in addition to the project directory
+    pub writable_paths: Vec<PathBuf>,
+    /// Paths readable in addition to the system directories (e.g. a toolchain)
+    pub readable_paths: Vec<PathBuf>,
+    /// Environment variables passed through in addition to [`SANDBOX_ENV`]
+    pub env_passthrough: Vec<String>,
+    /// Keep the host network namespace
+    pub allow_network: bool,
+    /// CPU time limit per command in seconds (None = unlimited)
+    pub cpu_seconds: Option<u64>,
+    /// Address-space limit per command in MiB (None = unlimited)
+    pub memory_mb: Option<u64>,
+}
+
+impl Default for SandboxPolicy {
+    fn default() -> Self {
+        Self {
+            writable_paths: Vec::new(),
+            readable_paths: Vec::new(),
+            env_passthrough: Vec::new(),
+            allow_network: false,
+            cpu_seconds: Some(300),
+            memory_mb: Some(4096),
+        }
+    }
+}
+
+/// Runs commands inside a bubblewrap (`bwrap`) sandbox. Linux only.
+#[derive(Debug, Clone)]
+pub struct BubblewrapBackend {
+    bwrap: PathBuf,
+    policy: SandboxPolicy,
+}
+
+impl BubblewrapBackend {
+    /// Create a backend using the `bwrap` binary at `bwrap` (a bare name is looked up on `PATH`).
+    pub fn new(bwrap: impl Into<PathBuf>, policy: SandboxPolicy) -> Self {
+        Self {
+            bwrap: bwrap.into(),
+            policy,
+        }
+    }
+
+    /// The policy enforced by this backend.
+    pub fn policy(&self) -> &SandboxPolicy {
+        &self.policy
+    }
+
+    /// bwrap arguments for running `command`; paths must be absolute.
+    pub(crate) fn args(&self, command: &str, working_dir: &Path, project_dir: &Path) -> Vec<OsString> {
+        let mut args: Vec<OsString> = Vec::new();
+        for dir in SANDBOX_SYSTEM_DIRS {
+            args.extend(["--ro-bind-try", dir, dir].map(OsString::from));
+        }
+        if self.policy.allow_network {
+            // /etc/resolv.conf often links here
+            args.extend(["--ro-bind-try", "/run/systemd/resolve", "/run/systemd/resolve"].map(OsString::from));
+        }
+        args.extend(["--dev", "/dev", "--proc", "/proc", "--tmpfs", "/tmp"].map(OsString::from));
+        for path in &self.policy.readable_paths {
+            args.extend(["--ro-bind-try".into(), path.into(), path.into()]);
+        }
+        // Binds come after the /tmp tmpfs so projects under /tmp stay visible
+        args.extend(["--bind".into(), project_dir.into(), project_dir.into()]);
+        // Hooks and config in .git run on the host the next time git does
+        let git_dir = project_dir.join(".git");
+        args.extend(["--ro-bind-try".into(), git_dir.clone().into(), git_dir.into()]);
+        for path in &self.policy.writable_paths {
+            args.extend(["--bind-try".into(), path.into(), path.into()]);
+        }
+        args.extend(["--unshare-pid", "--unshare-ipc", "--unshare-uts"].map(OsString::from));
+        if !self.policy.allow_network {
+            args.push("--unshare-net".into());
+        }
+        args.extend(["--die-with-parent", "--new-session", "--clearenv"].map(OsString::from));
+        let names = SANDBOX_ENV
+            .iter()
+            .copied()
+            .chain(self.policy.env_passthrough.iter().map(String::as_str));
+        for name in names {
+            if let Some(value) = std::env::var_os(name) {
+                args.extend(["--setenv".into(), name.into(), value]);
+            }
+        }
+        args.extend(["--setenv", "HOME", "/tmp"].map(OsString::from));
+        args.extend(["--chdir".into(), working_dir.into()]);
+
+        let mut script = Vec::new();
+        if let Some(cpu) = self.policy.cpu_seconds {
+            script.push(format!("ulimit -t {}", cpu));
+        }
+        if let Some(mb) = self.policy.memory_mb {
+            script.push(format!("ulimit -v {}", mb * 1024));
+        }
+        script.push("exec /bin/sh -c \"$1\"".to_string());
+
+        args.extend(["--", "/bin/sh", "-c"].map(OsString::from));
+        args.extend([script.join(" && ").into(), "sh".into(), command.into()]);
+        args
+    }
+
+    /// Resolve the bwrap binary, searching `PATH` for bare names.
+    fn resolve_bwrap(&self) -> Option<PathBuf> {
+        if self.bwrap.components().count() > 1 {
+            return self.bwrap.is_file().then(|| self.bwrap.clone());
+        }
+        let path = std::env::var_os("PATH")?;
+        std::env::split_paths(&path)
+            .map(|dir| dir.join(&self.bwrap))
+            .find(|candidate| candidate.is_file())
+    }
+}
+
+impl ExecutionBackend for BubblewrapBackend {
+    fn name(&self) -> &str {
+        "sandbox"
+    }
+
+    fn command(&self, command: &str, working_dir: &Path, project_dir: &Path) -> Result<TokioCommand> {
+        if !cfg!(target_os = "linux") {
+            return Err(anyhow!(
+                "Sandboxed execution is only supported on Linux. Set the mode's execution_backend to \"host\" to run commands unsandboxed."
+            ));
+        }
+        let bwrap = self.resolve_bwrap().ok_or_else(|| {
+            anyhow!(
+                "Sandbox unavailable: bubblewrap ('{}') was not found. Install bubblewrap or set the mode's execution_backend to \"host\".",
+                self.bwrap.display()
+            )
+        })?;
+
+        let project_dir = project_dir
+            .canonicalize()
+            .map_err(|e| anyhow!("Sandbox error: project directory {} is not accessible: {}", project_dir.display(), e))?;
+        let working_dir = working_dir
+            .canonicalize()
+            .map_err(|e| anyhow!("Sandbox error: working directory {} is not accessible: {}", working_dir.display(), e))?;
+
+        let mut cmd = TokioCommand::new(bwrap);
+        cmd.args(self.args(command, &working_dir, &project_dir));
+        Ok(cmd)
+    }
+
+    fn explain_failure(&self, result: &ExecutionResult, project_dir: &Path) -> Option<String> {
+        let output = format!("{}\n{}", result.stdout, result.stderr);
+        let note = if output.contains("Read-only file system") {
+            format!(
+                "Writes are only allowed inside the project directory ({}) and /tmp.",
+                project_dir.display()
+            )
+        } else if !self.policy.allow_network
+            && ["Could not resolve host", "Temporary failure in name resolution", "Network is unreachable"]
+                .iter()
+                .any(|s| output.contains(s))
+        {
+            "Network access is disabled for commands in this mode.".to_string()
+        } else if output.contains("CPU time limit exceeded") {
+            format!("The command exceeded the CPU time limit of {}s.", self.policy.cpu_seconds.unwrap_or(0))
+        } else if ["Cannot allocate memory", "out of memory", "MemoryError"]
+            .iter()
+            .any(|s| output.contains(s))
+        {
+            format!("The command exceeded the memory limit of {} MiB.", self.policy.memory_mb.unwrap_or(0))
+        } else {
+            return None;
+        };
+        Some(format!("[sandbox] {}", note))
+    }
+}
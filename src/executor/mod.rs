@@ -1,11 +1,21 @@
 //! Command execution module with timeout handling for ABK agents.
+//!
+//! Commands run through a pluggable [`ExecutionBackend`]: directly on the
+//! host by default, or inside a bubblewrap sandbox ([`BubblewrapBackend`]).
+
+mod backend;
+
+pub use backend::{
+    BubblewrapBackend, ExecutionBackend, HostBackend, SandboxPolicy, SharedBackend, SANDBOX_ENV,
+    SANDBOX_SYSTEM_DIRS,
+};
 
 use anyhow::Result;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
+use std::sync::Arc;
 use std::time::Duration;
-use tokio::process::Command as TokioCommand;
 use tokio::time::timeout;
 
 /// Execution result containing command output and metadata.
@@ -25,6 +35,7 @@ pub struct CommandExecutor {
     last_command: Option<String>,
     enable_validation: bool,
     last_result: Option<ExecutionResult>,
+    backend: SharedBackend,
 }
 
 impl CommandExecutor {
@@ -40,9 +51,26 @@ impl CommandExecutor {
             last_command: None,
             enable_validation,
             last_result: None,
+            backend: Arc::new(HostBackend),
         }
     }
 
+    /// Use `backend` to run commands instead of the host shell.
+    pub fn with_backend(mut self, backend: SharedBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Replace the execution backend (e.g. when the agent switches modes).
+    pub fn set_backend(&mut self, backend: SharedBackend) {
+        self.backend = backend;
+    }
+
+    /// Get the execution backend.
+    pub fn backend(&self) -> &SharedBackend {
+        &self.backend
+    }
+
     /// Execute bash command with timeout.
     ///
     /// # Arguments
@@ -55,27 +83,40 @@ impl CommandExecutor {
         &mut self,
         command: &str,
         timeout_override: Option<u64>,
+    ) -> Result<ExecutionResult> {
+        let working_dir = self.working_dir.clone();
+        self.execute_command_in(command, &working_dir, timeout_override).await
+    }
+
+    /// Execute a command in `working_dir` instead of the executor's working directory.
+    ///
+    /// The executor's working directory stays the project directory handed
+    /// to the backend, so a sandbox keeps restricting writes to it.
+    pub async fn execute_command_in(
+        &mut self,
+        command: &str,
+        working_dir: &Path,
+        timeout_override: Option<u64>,
     ) -> Result<ExecutionResult> {
         let timeout_secs = timeout_override.unwrap_or(self.timeout_seconds);
         self.last_command = Some(command.to_string());
 
-        // Use PowerShell on Windows to avoid CMD quoting pitfalls:
-        // CMD expands %VAR%, has no single-quote support, and mangles backslash-escaped quotes.
-        // PowerShell treats % as a literal character and has predictable quoting rules.
-        let mut cmd = if cfg!(target_os = "windows") {
-            let mut c = TokioCommand::new("powershell.exe");
-            c.arg("-NoProfile").arg("-Command");
-            c
-        } else {
-            let mut c = TokioCommand::new("sh");
-            c.arg("-c");
-            c
+        let mut cmd = match self.backend.command(command, working_dir, &self.working_dir) {
+            Ok(cmd) => cmd,
+            Err(e) => {
+                self.last_result = Some(ExecutionResult {
+                    stdout: String::new(),
+                    stderr: e.to_string(),
+                    return_code: -1,
+                    success: false,
+                });
+                return Err(e);
+            }
         };
-        cmd.arg(command)
-            .current_dir(&self.working_dir)
-            .stdout(Stdio::piped())
+        cmd.stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .stdin(Stdio::null());
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
 
         let timeout_duration = Duration::from_secs(timeout_secs);
 
@@ -86,12 +127,20 @@ impl CommandExecutor {
                 let return_code = output.status.code().unwrap_or(-1);
                 let success = output.status.success();
 
-                let result = ExecutionResult {
+                let mut result = ExecutionResult {
                     stdout,
                     stderr,
                     return_code,
                     success,
                 };
+                if !success {
+                    if let Some(note) = self.backend.explain_failure(&result, &self.working_dir) {
+                        if !result.stderr.is_empty() && !result.stderr.ends_with('\n') {
+                            result.stderr.push('\n');
+                        }
+                        result.stderr.push_str(&note);
+                    }
+                }
 
                 self.last_result = Some(result.clone());
                 Ok(result)
@@ -51,3 +51,147 @@ async fn test_working_directory() {
     // The output should contain some path
     assert!(!result.stdout.trim().is_empty());
 }
+
+#[test]
+fn test_bubblewrap_args() {
+    let backend = BubblewrapBackend::new(
+        "bwrap",
+        SandboxPolicy {
+            writable_paths: vec![PathBuf::from("/var/cache/build")],
+            readable_paths: vec![PathBuf::from("/opt/toolchain")],
+            ..SandboxPolicy::default()
+        },
+    );
+    let args: Vec<String> = backend
+        .args("make test", Path::new("/work/proj/src"), Path::new("/work/proj"))
+        .into_iter()
+        .map(|a| a.into_string().unwrap())
+        .collect();
+    let joined = args.join(" ");
+
+    assert!(joined.starts_with("--ro-bind-try /usr /usr "));
+    assert!(!joined.contains("--ro-bind / /"));
+    assert!(joined.contains("--ro-bind-try /opt/toolchain /opt/toolchain"));
+    assert!(joined.contains("--bind /work/proj /work/proj --ro-bind-try /work/proj/.git /work/proj/.git"));
+    assert!(joined.contains("--bind-try /var/cache/build /var/cache/build"));
+    assert!(joined.contains("--unshare-net"));
+    assert!(joined.contains("--chdir /work/proj/src"));
+    assert!(joined.contains("--clearenv"));
+    assert!(joined.contains("--setenv HOME /tmp"));
+    // Only allowlisted variables are passed in
+    for pair in args.windows(2).filter(|w| w[0] == "--setenv") {
+        assert!(
+            pair[1] == "HOME" || SANDBOX_ENV.contains(&pair[1].as_str()),
+            "{}",
+            pair[1]
+        );
+    }
+    assert_eq!(
+        &args[args.len() - 3..],
+        &[
+            "ulimit -t 300 && ulimit -v 4194304 && exec /bin/sh -c \"$1\"".to_string(),
+            "sh".to_string(),
+            "make test".to_string(),
+        ]
+    );
+
+    let networked = BubblewrapBackend::new(
+        "bwrap",
+        SandboxPolicy {
+            allow_network: true,
+            cpu_seconds: None,
+            memory_mb: None,
+            ..SandboxPolicy::default()
+        },
+    );
+    let args = networked.args("true", Path::new("/p"), Path::new("/p"));
+    assert!(!args.iter().any(|a| a == "--unshare-net"));
+    assert!(args.iter().any(|a| a == "exec /bin/sh -c \"$1\""));
+}
+
+#[tokio::test]
+async fn test_missing_sandbox_is_reported() {
+    let backend = BubblewrapBackend::new("/nonexistent/bwrap", SandboxPolicy::default());
+    let mut executor = CommandExecutor::default().with_backend(Arc::new(backend));
+    assert_eq!(executor.backend().name(), "sandbox");
+
+    let err = executor.execute_command("echo hi", None).await.unwrap_err();
+    if cfg!(target_os = "linux") {
+        assert!(err.to_string().contains("Sandbox unavailable"));
+    }
+    assert!(!executor.last_result().unwrap().success);
+}
+
+#[test]
+fn test_sandbox_explains_restrictions() {
+    let backend = BubblewrapBackend::new("bwrap", SandboxPolicy::default());
+    let failed = |stderr: &str| ExecutionResult {
+        stdout: String::new(),
+        stderr: stderr.to_string(),
+        return_code: 1,
+        success: false,
+    };
+
+    let note = backend
+        .explain_failure(&failed("touch: cannot touch '/etc/x': Read-only file system"), Path::new("/p"))
+        .unwrap();
+    assert!(note.contains("/p"));
+    let note = backend
+        .explain_failure(&failed("curl: (6) Could not resolve host: example.com"), Path::new("/p"))
+        .unwrap();
+    assert!(note.contains("Network access is disabled"));
+    assert!(backend.explain_failure(&failed("make: *** [test] Error 2"), Path::new("/p")).is_none());
+}
+
+/// Whether bwrap is installed and allowed to create namespaces here.
+fn bwrap_usable() -> bool {
+    std::process::Command::new("bwrap")
+        .args(["--ro-bind", "/", "/", "true"])
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+#[tokio::test]
+async fn test_sandbox_blocks_outside_writes_network_and_secrets() {
+    if !bwrap_usable() || !Path::new("/bin/bash").exists() {
+        eprintln!("skipping: bubblewrap is not usable here");
+        return;
+    }
+    let project = tempfile::TempDir::new().unwrap();
+    let outside = tempfile::TempDir::new_in(std::env::current_dir().unwrap()).unwrap();
+    let run = |backend: &BubblewrapBackend, command: String| {
+        let mut cmd = backend
+            .command(&command, project.path(), project.path())
+            .unwrap();
+        async move { cmd.output().await.unwrap() }
+    };
+    let sandbox = BubblewrapBackend::new("bwrap", SandboxPolicy::default());
+
+    let inside = run(&sandbox, "echo ok > inside.txt".to_string()).await;
+    assert!(inside.status.success());
+    assert!(project.path().join("inside.txt").exists());
+
+    let escaped = outside.path().join("escaped.txt");
+    let write = run(&sandbox, format!("echo no > '{}'", escaped.display())).await;
+    assert!(!write.status.success());
+    assert!(!escaped.exists());
+
+    // Variables set by cargo for this test process are not passed in
+    let env = run(&sandbox, "printenv CARGO_PKG_NAME".to_string()).await;
+    assert!(!env.status.success());
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let connect = format!("/bin/bash -c 'echo hi > /dev/tcp/127.0.0.1/{}'", port);
+    assert!(!run(&sandbox, connect.clone()).await.status.success());
+
+    let networked = BubblewrapBackend::new(
+        "bwrap",
+        SandboxPolicy {
+            allow_network: true,
+            ..SandboxPolicy::default()
+        },
+    );
+    assert!(run(&networked, connect).await.status.success());
+}
@@ -4,10 +4,11 @@
 //! extension WASM components using wasmtime's component model.
 
 use super::error::{ExtensionError, ExtensionResult};
+use super::sandbox::ExtensionSandbox;
 use std::sync::Arc;
 use tracing::debug;
 use wasmtime::component::{Component, Linker, ResourceTable};
-use wasmtime::{Engine, Store};
+use wasmtime::{Engine, Store, StoreLimits};
 use wasmtime_wasi::WasiCtx;
 use wasmtime_wasi::WasiCtxBuilder;
 use wasmtime_wasi::WasiView;
@@ -74,6 +75,24 @@ mod provider_extension_bindings {
     });
 }
 
+// Generate bindings for tools-only extensions (async, called from tool sources)
+mod tools_extension_bindings {
+    wasmtime::component::bindgen!({
+        path: "wit/extension",
+        world: "tools-extension",
+        async: true,
+    });
+}
+
+// Generate bindings for context-only extensions (async, called from the workflow loop)
+mod context_extension_bindings {
+    wasmtime::component::bindgen!({
+        path: "wit/extension",
+        world: "context-extension",
+        async: true,
+    });
+}
+
 // Re-export generated types at module level
 
 /// Core interface types
@@ -108,25 +127,73 @@ pub mod lifecycle_only {
     pub use super::lifecycle_extension_bindings::exports::abk::extension::lifecycle as lifecycle;
 }
 
+/// Tools-only extension types (from tools-extension world)
+pub mod tools_only {
+    #![allow(missing_docs)]
+    pub use super::tools_extension_bindings::exports::abk::extension::core as core;
+    pub use super::tools_extension_bindings::exports::abk::extension::tools as tools;
+}
+
+/// Context-only extension types (from context-extension world)
+pub mod context_only {
+    #![allow(missing_docs)]
+    pub use super::context_extension_bindings::exports::abk::extension::core as core;
+    pub use super::context_extension_bindings::exports::abk::extension::context as context;
+}
+
 /// WASI state for extension execution
 pub struct ExtensionState {
     /// WASI context
     ctx: WasiCtx,
     /// Resource table for WASI
     table: ResourceTable,
+    /// Memory cap enforced by the store
+    limits: StoreLimits,
+    /// Fuel granted before each call
+    fuel_per_call: u64,
 }
 
 impl ExtensionState {
-    /// Create a new extension state with default WASI context
+    /// Create a new extension state with no host access and default limits
     pub fn new() -> Self {
+        let sandbox = ExtensionSandbox::default();
         Self {
             ctx: WasiCtxBuilder::new()
                 .inherit_stdout()
                 .inherit_stderr()
                 .build(),
             table: ResourceTable::new(),
+            limits: sandbox.store_limits(),
+            fuel_per_call: sandbox.fuel(),
         }
     }
+
+    /// Create an extension state granting the permissions and limits of `sandbox`
+    pub fn sandboxed(sandbox: &ExtensionSandbox) -> ExtensionResult<Self> {
+        Ok(Self {
+            ctx: sandbox.wasi_ctx()?,
+            table: ResourceTable::new(),
+            limits: sandbox.store_limits(),
+            fuel_per_call: sandbox.fuel(),
+        })
+    }
+}
+
+/// Create a store that enforces the state's memory cap and starts with a full fuel tank
+fn sandboxed_store(engine: &Engine, state: ExtensionState) -> Store<ExtensionState> {
+    let mut store = Store::new(engine, state);
+    store.limiter(|state| &mut state.limits);
+    fueled(&mut store);
+    store
+}
+
+/// Refill the store's fuel before a call into the extension
+///
+/// Engines without fuel metering reject `set_fuel`; they simply run unmetered.
+fn fueled(store: &mut Store<ExtensionState>) -> &mut Store<ExtensionState> {
+    let fuel = store.data().fuel_per_call;
+    let _ = store.set_fuel(fuel);
+    store
 }
 
 impl Default for ExtensionState {
@@ -159,6 +226,15 @@ impl ExtensionInstance {
     /// Note: The engine must be configured WITHOUT async_support for this to work,
     /// since we use synchronous bindgen for the extension world.
     pub fn new(engine: &Arc<Engine>, component: &Component) -> ExtensionResult<Self> {
+        Self::with_sandbox(engine, component, &ExtensionSandbox::default())
+    }
+
+    /// Instantiate an extension with the permissions and limits of `sandbox`
+    pub fn with_sandbox(
+        engine: &Arc<Engine>,
+        component: &Component,
+        sandbox: &ExtensionSandbox,
+    ) -> ExtensionResult<Self> {
         debug!(target: "abk::extension", "Creating linker for extension");
         let mut linker = Linker::new(engine);
 
@@ -170,8 +246,8 @@ impl ExtensionInstance {
 
         // Create store with state
         debug!(target: "abk::extension", "Creating store");
-        let state = ExtensionState::new();
-        let mut store = Store::new(engine, state);
+        let state = ExtensionState::sandboxed(sandbox)?;
+        let mut store = sandboxed_store(engine, state);
 
         // Instantiate
         debug!(target: "abk::extension", "Instantiating extension component");
@@ -195,7 +271,7 @@ impl ExtensionInstance {
     pub fn get_metadata(&mut self) -> ExtensionResult<core::ExtensionMetadata> {
         self.bindings
             .abk_extension_core()
-            .call_get_metadata(&mut self.store)
+            .call_get_metadata(fueled(&mut self.store))
             .map_err(|e| ExtensionError::CallError(format!("get_metadata failed: {}", e)))
     }
 
@@ -203,7 +279,7 @@ impl ExtensionInstance {
     pub fn list_capabilities(&mut self) -> ExtensionResult<Vec<String>> {
         self.bindings
             .abk_extension_core()
-            .call_list_capabilities(&mut self.store)
+            .call_list_capabilities(fueled(&mut self.store))
             .map_err(|e| ExtensionError::CallError(format!("list_capabilities failed: {}", e)))
     }
 
@@ -211,7 +287,7 @@ impl ExtensionInstance {
     pub fn init(&mut self) -> ExtensionResult<()> {
         self.bindings
             .abk_extension_core()
-            .call_init(&mut self.store)
+            .call_init(fueled(&mut self.store))
             .map_err(|e| ExtensionError::CallError(format!("init call failed: {}", e)))?
             .map_err(|e| ExtensionError::InitError(e))
     }
@@ -222,7 +298,7 @@ impl ExtensionInstance {
     pub fn load_template(&mut self, template_name: &str) -> ExtensionResult<String> {
         self.bindings
             .abk_extension_lifecycle()
-            .call_load_template(&mut self.store, template_name)
+            .call_load_template(fueled(&mut self.store), template_name)
             .map_err(|e| ExtensionError::CallError(format!("load_template failed: {}", e)))?
             .map_err(|e| {
                 ExtensionError::LifecycleError(format!("{}: {:?}", e.message, e.code))
@@ -245,7 +321,7 @@ impl ExtensionInstance {
 
         self.bindings
             .abk_extension_lifecycle()
-            .call_render_template(&mut self.store, content, &vars)
+            .call_render_template(fueled(&mut self.store), content, &vars)
             .map_err(|e| ExtensionError::CallError(format!("render_template failed: {}", e)))
     }
 
@@ -254,7 +330,7 @@ impl ExtensionInstance {
         let sections = self
             .bindings
             .abk_extension_lifecycle()
-            .call_extract_sections(&mut self.store, content)
+            .call_extract_sections(fueled(&mut self.store), content)
             .map_err(|e| ExtensionError::CallError(format!("extract_sections failed: {}", e)))?;
 
         Ok(sections.into_iter().map(|s| (s.name, s.content)).collect())
@@ -264,7 +340,7 @@ impl ExtensionInstance {
     pub fn validate_template_variables(&mut self, content: &str) -> ExtensionResult<Vec<String>> {
         self.bindings
             .abk_extension_lifecycle()
-            .call_validate_template_variables(&mut self.store, content)
+            .call_validate_template_variables(fueled(&mut self.store), content)
             .map_err(|e| {
                 ExtensionError::CallError(format!("validate_template_variables failed: {}", e))
             })
@@ -274,7 +350,7 @@ impl ExtensionInstance {
     pub fn get_task_template_name(&mut self, task_type: &str) -> ExtensionResult<String> {
         self.bindings
             .abk_extension_lifecycle()
-            .call_get_task_template_name(&mut self.store, task_type)
+            .call_get_task_template_name(fueled(&mut self.store), task_type)
             .map_err(|e| ExtensionError::CallError(format!("get_task_template_name failed: {}", e)))
     }
 
@@ -286,7 +362,7 @@ impl ExtensionInstance {
         let result = self
             .bindings
             .abk_extension_lifecycle()
-            .call_classify_task(&mut self.store, task_description)
+            .call_classify_task(fueled(&mut self.store), task_description)
             .map_err(|e| ExtensionError::CallError(format!("classify_task failed: {}", e)))?
             .map_err(|e| {
                 ExtensionError::LifecycleError(format!("{}: {:?}", e.message, e.code))
@@ -299,7 +375,7 @@ impl ExtensionInstance {
     pub fn load_useful_commands(&mut self) -> ExtensionResult<String> {
         self.bindings
             .abk_extension_lifecycle()
-            .call_load_useful_commands(&mut self.store)
+            .call_load_useful_commands(fueled(&mut self.store))
             .map_err(|e| ExtensionError::CallError(format!("load_useful_commands failed: {}", e)))?
             .map_err(|e| {
                 ExtensionError::LifecycleError(format!("{}: {:?}", e.message, e.code))
@@ -311,7 +387,7 @@ impl ExtensionInstance {
         let vars = self
             .bindings
             .abk_extension_lifecycle()
-            .call_get_system_info_variables(&mut self.store)
+            .call_get_system_info_variables(fueled(&mut self.store))
             .map_err(|e| {
                 ExtensionError::CallError(format!("get_system_info_variables failed: {}", e))
             })?;
@@ -325,7 +401,7 @@ impl ExtensionInstance {
     pub fn get_provider_metadata(&mut self) -> ExtensionResult<String> {
         self.bindings
             .abk_extension_provider()
-            .call_get_provider_metadata(&mut self.store)
+            .call_get_provider_metadata(fueled(&mut self.store))
             .map_err(|e| ExtensionError::CallError(format!("get_provider_metadata failed: {}", e)))
     }
 
@@ -346,7 +422,7 @@ impl ExtensionInstance {
 
         self.bindings
             .abk_extension_provider()
-            .call_format_request(&mut self.store, &msgs, config, tools)
+            .call_format_request(fueled(&mut self.store), &msgs, config, tools)
             .map_err(|e| ExtensionError::CallError(format!("format_request failed: {}", e)))?
             .map_err(|e| ExtensionError::ProviderError(format_provider_error(
                 e.message, e.code, e.http_status, e.response_body, e.is_retryable, e.retry_after
@@ -361,7 +437,7 @@ impl ExtensionInstance {
     ) -> ExtensionResult<provider::AssistantMessage> {
         self.bindings
             .abk_extension_provider()
-            .call_parse_response(&mut self.store, body, model)
+            .call_parse_response(fueled(&mut self.store), body, model)
             .map_err(|e| ExtensionError::CallError(format!("parse_response failed: {}", e)))?
             .map_err(|e| ExtensionError::ProviderError(format_provider_error(
                 e.message, e.code, e.http_status, e.response_body, e.is_retryable, e.retry_after
@@ -375,7 +451,7 @@ impl ExtensionInstance {
     ) -> ExtensionResult<Option<provider::ContentDelta>> {
         self.bindings
             .abk_extension_provider()
-            .call_handle_stream_chunk(&mut self.store, chunk)
+            .call_handle_stream_chunk(fueled(&mut self.store), chunk)
             .map_err(|e| ExtensionError::CallError(format!("handle_stream_chunk failed: {}", e)))
     }
 
@@ -383,7 +459,7 @@ impl ExtensionInstance {
     pub fn get_api_url(&mut self, base_url: &str, model: &str) -> ExtensionResult<String> {
         self.bindings
             .abk_extension_provider()
-            .call_get_api_url(&mut self.store, base_url, model)
+            .call_get_api_url(fueled(&mut self.store), base_url, model)
             .map_err(|e| ExtensionError::CallError(format!("get_api_url failed: {}", e)))
     }
 
@@ -391,7 +467,7 @@ impl ExtensionInstance {
     pub fn supports_streaming(&mut self, model: &str) -> ExtensionResult<bool> {
         self.bindings
             .abk_extension_provider()
-            .call_supports_streaming(&mut self.store, model)
+            .call_supports_streaming(fueled(&mut self.store), model)
             .map_err(|e| ExtensionError::CallError(format!("supports_streaming failed: {}", e)))
     }
 
@@ -410,7 +486,7 @@ impl ExtensionInstance {
         self.bindings
             .abk_extension_provider()
             .call_format_request_from_json(
-                &mut self.store,
+                fueled(&mut self.store),
                 messages_json,
                 model,
                 tools_json,
@@ -437,6 +513,15 @@ pub struct ProviderExtensionInstance {
 impl ProviderExtensionInstance {
     /// Instantiate a provider-only extension from a loaded component
     pub async fn new(engine: &Arc<Engine>, component: &Component) -> ExtensionResult<Self> {
+        Self::with_sandbox(engine, component, &ExtensionSandbox::default()).await
+    }
+
+    /// Instantiate a provider-only extension with the permissions and limits of `sandbox`
+    pub async fn with_sandbox(
+        engine: &Arc<Engine>,
+        component: &Component,
+        sandbox: &ExtensionSandbox,
+    ) -> ExtensionResult<Self> {
         let mut linker = Linker::new(engine);
 
         // Add WASI to linker (async version for async bindings)
@@ -445,8 +530,8 @@ impl ProviderExtensionInstance {
         })?;
 
         // Create store with state
-        let state = ExtensionState::new();
-        let mut store = Store::new(engine, state);
+        let state = ExtensionState::sandboxed(sandbox)?;
+        let mut store = sandboxed_store(engine, state);
 
         // Instantiate using provider-extension world (async)
         let bindings = provider_extension_bindings::ProviderExtension::instantiate_async(&mut store, component, &linker)
@@ -462,7 +547,7 @@ impl ProviderExtensionInstance {
     pub async fn get_metadata(&mut self) -> ExtensionResult<provider_only::core::ExtensionMetadata> {
         self.bindings
             .abk_extension_core()
-            .call_get_metadata(&mut self.store)
+            .call_get_metadata(fueled(&mut self.store))
             .await
             .map_err(|e| ExtensionError::CallError(format!("get_metadata failed: {}", e)))
     }
@@ -471,7 +556,7 @@ impl ProviderExtensionInstance {
     pub async fn list_capabilities(&mut self) -> ExtensionResult<Vec<String>> {
         self.bindings
             .abk_extension_core()
-            .call_list_capabilities(&mut self.store)
+            .call_list_capabilities(fueled(&mut self.store))
             .await
             .map_err(|e| ExtensionError::CallError(format!("list_capabilities failed: {}", e)))
     }
@@ -480,7 +565,7 @@ impl ProviderExtensionInstance {
     pub async fn init(&mut self) -> ExtensionResult<()> {
         self.bindings
             .abk_extension_core()
-            .call_init(&mut self.store)
+            .call_init(fueled(&mut self.store))
             .await
             .map_err(|e| ExtensionError::CallError(format!("init call failed: {}", e)))?
             .map_err(|e| ExtensionError::InitError(e))
@@ -492,7 +577,7 @@ impl ProviderExtensionInstance {
     pub async fn get_provider_metadata(&mut self) -> ExtensionResult<String> {
         self.bindings
             .abk_extension_provider()
-            .call_get_provider_metadata(&mut self.store)
+            .call_get_provider_metadata(fueled(&mut self.store))
             .await
             .map_err(|e| ExtensionError::CallError(format!("get_provider_metadata failed: {}", e)))
     }
@@ -514,7 +599,7 @@ impl ProviderExtensionInstance {
 
         self.bindings
             .abk_extension_provider()
-            .call_format_request(&mut self.store, &msgs, config, tools)
+            .call_format_request(fueled(&mut self.store), &msgs, config, tools)
             .await
             .map_err(|e| ExtensionError::CallError(format!("format_request failed: {}", e)))?
             .map_err(|e| ExtensionError::ProviderError(format_provider_error(
@@ -530,7 +615,7 @@ impl ProviderExtensionInstance {
     ) -> ExtensionResult<provider_only::provider::AssistantMessage> {
         self.bindings
             .abk_extension_provider()
-            .call_parse_response(&mut self.store, body, model)
+            .call_parse_response(fueled(&mut self.store), body, model)
             .await
             .map_err(|e| ExtensionError::CallError(format!("parse_response failed: {}", e)))?
             .map_err(|e| ExtensionError::ProviderError(format_provider_error(
@@ -545,7 +630,7 @@ impl ProviderExtensionInstance {
     ) -> ExtensionResult<Option<provider_only::provider::ContentDelta>> {
         self.bindings
             .abk_extension_provider()
-            .call_handle_stream_chunk(&mut self.store, chunk)
+            .call_handle_stream_chunk(fueled(&mut self.store), chunk)
             .await
             .map_err(|e| ExtensionError::CallError(format!("handle_stream_chunk failed: {}", e)))
     }
@@ -554,7 +639,7 @@ impl ProviderExtensionInstance {
     pub async fn get_api_url(&mut self, base_url: &str, model: &str) -> ExtensionResult<String> {
         self.bindings
             .abk_extension_provider()
-            .call_get_api_url(&mut self.store, base_url, model)
+            .call_get_api_url(fueled(&mut self.store), base_url, model)
             .await
             .map_err(|e| ExtensionError::CallError(format!("get_api_url failed: {}", e)))
     }
@@ -563,7 +648,7 @@ impl ProviderExtensionInstance {
     pub async fn supports_streaming(&mut self, model: &str) -> ExtensionResult<bool> {
         self.bindings
             .abk_extension_provider()
-            .call_supports_streaming(&mut self.store, model)
+            .call_supports_streaming(fueled(&mut self.store), model)
             .await
             .map_err(|e| ExtensionError::CallError(format!("supports_streaming failed: {}", e)))
     }
@@ -583,7 +668,7 @@ impl ProviderExtensionInstance {
         self.bindings
             .abk_extension_provider()
             .call_format_request_from_json(
-                &mut self.store,
+                fueled(&mut self.store),
                 messages_json,
                 model,
                 tools_json,
@@ -614,6 +699,15 @@ impl LifecycleExtensionInstance {
     /// Note: The engine must be configured WITHOUT async_support for this to work,
     /// since we use synchronous bindgen for lifecycle extensions.
     pub fn new(engine: &Arc<Engine>, component: &Component) -> ExtensionResult<Self> {
+        Self::with_sandbox(engine, component, &ExtensionSandbox::default())
+    }
+
+    /// Instantiate a lifecycle-only extension with the permissions and limits of `sandbox`
+    pub fn with_sandbox(
+        engine: &Arc<Engine>,
+        component: &Component,
+        sandbox: &ExtensionSandbox,
+    ) -> ExtensionResult<Self> {
         debug!(target: "abk::extension", "LifecycleExtensionInstance: Creating linker");
         let mut linker = Linker::new(engine);
 
@@ -625,8 +719,8 @@ impl LifecycleExtensionInstance {
 
         // Create store with state
         debug!(target: "abk::extension", "LifecycleExtensionInstance: Creating store");
-        let state = ExtensionState::new();
-        let mut store = Store::new(engine, state);
+        let state = ExtensionState::sandboxed(sandbox)?;
+        let mut store = sandboxed_store(engine, state);
 
         // Instantiate using lifecycle-extension world (sync)
         debug!(target: "abk::extension", "LifecycleExtensionInstance: Instantiating component");
@@ -644,7 +738,7 @@ impl LifecycleExtensionInstance {
     pub fn get_metadata(&mut self) -> ExtensionResult<lifecycle_only::core::ExtensionMetadata> {
         self.bindings
             .abk_extension_core()
-            .call_get_metadata(&mut self.store)
+            .call_get_metadata(fueled(&mut self.store))
             .map_err(|e| ExtensionError::CallError(format!("get_metadata failed: {}", e)))
     }
 
@@ -652,7 +746,7 @@ impl LifecycleExtensionInstance {
     pub fn list_capabilities(&mut self) -> ExtensionResult<Vec<String>> {
         self.bindings
             .abk_extension_core()
-            .call_list_capabilities(&mut self.store)
+            .call_list_capabilities(fueled(&mut self.store))
             .map_err(|e| ExtensionError::CallError(format!("list_capabilities failed: {}", e)))
     }
 
@@ -660,7 +754,7 @@ impl LifecycleExtensionInstance {
     pub fn init(&mut self) -> ExtensionResult<()> {
         self.bindings
             .abk_extension_core()
-            .call_init(&mut self.store)
+            .call_init(fueled(&mut self.store))
             .map_err(|e| ExtensionError::CallError(format!("init call failed: {}", e)))?
             .map_err(|e| ExtensionError::InitError(e))
     }
@@ -671,7 +765,7 @@ impl LifecycleExtensionInstance {
     pub fn load_template(&mut self, template_name: &str) -> ExtensionResult<String> {
         self.bindings
             .abk_extension_lifecycle()
-            .call_load_template(&mut self.store, template_name)
+            .call_load_template(fueled(&mut self.store), template_name)
             .map_err(|e| ExtensionError::CallError(format!("load_template failed: {}", e)))?
             .map_err(|e| {
                 ExtensionError::LifecycleError(format!("{}: {:?}", e.message, e.code))
@@ -694,7 +788,7 @@ impl LifecycleExtensionInstance {
 
         self.bindings
             .abk_extension_lifecycle()
-            .call_render_template(&mut self.store, content, &vars)
+            .call_render_template(fueled(&mut self.store), content, &vars)
             .map_err(|e| ExtensionError::CallError(format!("render_template failed: {}", e)))
     }
 
@@ -703,7 +797,7 @@ impl LifecycleExtensionInstance {
         let sections = self
             .bindings
             .abk_extension_lifecycle()
-            .call_extract_sections(&mut self.store, content)
+            .call_extract_sections(fueled(&mut self.store), content)
             .map_err(|e| ExtensionError::CallError(format!("extract_sections failed: {}", e)))?;
 
         Ok(sections.into_iter().map(|s| (s.name, s.content)).collect())
@@ -713,7 +807,7 @@ impl LifecycleExtensionInstance {
     pub fn validate_template_variables(&mut self, content: &str) -> ExtensionResult<Vec<String>> {
         self.bindings
             .abk_extension_lifecycle()
-            .call_validate_template_variables(&mut self.store, content)
+            .call_validate_template_variables(fueled(&mut self.store), content)
             .map_err(|e| {
                 ExtensionError::CallError(format!("validate_template_variables failed: {}", e))
             })
@@ -723,7 +817,7 @@ impl LifecycleExtensionInstance {
     pub fn get_task_template_name(&mut self, task_type: &str) -> ExtensionResult<String> {
         self.bindings
             .abk_extension_lifecycle()
-            .call_get_task_template_name(&mut self.store, task_type)
+            .call_get_task_template_name(fueled(&mut self.store), task_type)
             .map_err(|e| ExtensionError::CallError(format!("get_task_template_name failed: {}", e)))
     }
 
@@ -735,7 +829,7 @@ impl LifecycleExtensionInstance {
         let result = self
             .bindings
             .abk_extension_lifecycle()
-            .call_classify_task(&mut self.store, task_description)
+            .call_classify_task(fueled(&mut self.store), task_description)
             .map_err(|e| ExtensionError::CallError(format!("classify_task failed: {}", e)))?
             .map_err(|e| {
                 ExtensionError::LifecycleError(format!("{}: {:?}", e.message, e.code))
@@ -748,7 +842,7 @@ impl LifecycleExtensionInstance {
     pub fn load_useful_commands(&mut self) -> ExtensionResult<String> {
         self.bindings
             .abk_extension_lifecycle()
-            .call_load_useful_commands(&mut self.store)
+            .call_load_useful_commands(fueled(&mut self.store))
             .map_err(|e| ExtensionError::CallError(format!("load_useful_commands failed: {}", e)))?
             .map_err(|e| {
                 ExtensionError::LifecycleError(format!("{}: {:?}", e.message, e.code))
@@ -760,7 +854,7 @@ impl LifecycleExtensionInstance {
         let vars = self
             .bindings
             .abk_extension_lifecycle()
-            .call_get_system_info_variables(&mut self.store)
+            .call_get_system_info_variables(fueled(&mut self.store))
             .map_err(|e| {
                 ExtensionError::CallError(format!("get_system_info_variables failed: {}", e))
             })?;
@@ -786,3 +880,160 @@ mod tests {
         drop(state);
     }
 }
+
+/// An instantiated tools-only extension
+pub struct ToolsExtensionInstance {
+    /// Store with extension state
+    store: Store<ExtensionState>,
+    /// Bindings to the extension's exports (tools-extension world)
+    bindings: tools_extension_bindings::ToolsExtension,
+}
+
+impl ToolsExtensionInstance {
+    /// Instantiate a tools-only extension from a loaded component
+    pub async fn new(engine: &Arc<Engine>, component: &Component) -> ExtensionResult<Self> {
+        Self::with_sandbox(engine, component, &ExtensionSandbox::default()).await
+    }
+
+    /// Instantiate a tools-only extension with the permissions and limits of `sandbox`
+    pub async fn with_sandbox(
+        engine: &Arc<Engine>,
+        component: &Component,
+        sandbox: &ExtensionSandbox,
+    ) -> ExtensionResult<Self> {
+        let mut linker = Linker::new(engine);
+
+        // Add WASI to linker (async version for async bindings)
+        wasmtime_wasi::add_to_linker_async(&mut linker).map_err(|e| {
+            ExtensionError::WasmLoadError(format!("Failed to add WASI to linker: {}", e))
+        })?;
+
+        let state = ExtensionState::sandboxed(sandbox)?;
+        let mut store = sandboxed_store(engine, state);
+
+        let bindings = tools_extension_bindings::ToolsExtension::instantiate_async(&mut store, component, &linker)
+            .await
+            .map_err(|e| {
+                ExtensionError::WasmLoadError(format!("Failed to instantiate tools extension: {}", e))
+            })?;
+
+        Ok(Self { store, bindings })
+    }
+
+    /// Get extension metadata via core interface
+    pub async fn get_metadata(&mut self) -> ExtensionResult<tools_only::core::ExtensionMetadata> {
+        self.bindings
+            .abk_extension_core()
+            .call_get_metadata(fueled(&mut self.store))
+            .await
+            .map_err(|e| ExtensionError::CallError(format!("get_metadata failed: {}", e)))
+    }
+
+    /// Initialize the extension via core interface
+    pub async fn init(&mut self) -> ExtensionResult<()> {
+        self.bindings
+            .abk_extension_core()
+            .call_init(fueled(&mut self.store))
+            .await
+            .map_err(|e| ExtensionError::CallError(format!("init call failed: {}", e)))?
+            .map_err(ExtensionError::InitError)
+    }
+
+    // ===== Tools Interface Methods =====
+
+    /// List the tools this extension provides (tools capability)
+    pub async fn list_tools(&mut self) -> ExtensionResult<Vec<tools_only::tools::ToolSchema>> {
+        self.bindings
+            .abk_extension_tools()
+            .call_list_tools(fueled(&mut self.store))
+            .await
+            .map_err(|e| ExtensionError::CallError(format!("list_tools failed: {}", e)))
+    }
+
+    /// Execute a tool with JSON-encoded arguments (tools capability)
+    pub async fn execute_tool(
+        &mut self,
+        name: &str,
+        arguments: &str,
+    ) -> ExtensionResult<tools_only::tools::ToolOutput> {
+        self.bindings
+            .abk_extension_tools()
+            .call_execute_tool(fueled(&mut self.store), name, arguments)
+            .await
+            .map_err(|e| ExtensionError::CallError(format!("execute_tool '{}' failed: {}", name, e)))
+    }
+}
+
+/// An instantiated context-only extension
+pub struct ContextExtensionInstance {
+    /// Store with extension state
+    store: Store<ExtensionState>,
+    /// Bindings to the extension's exports (context-extension world)
+    bindings: context_extension_bindings::ContextExtension,
+}
+
+impl ContextExtensionInstance {
+    /// Instantiate a context-only extension from a loaded component
+    pub async fn new(engine: &Arc<Engine>, component: &Component) -> ExtensionResult<Self> {
+        Self::with_sandbox(engine, component, &ExtensionSandbox::default()).await
+    }
+
+    /// Instantiate a context-only extension with the permissions and limits of `sandbox`
+    pub async fn with_sandbox(
+        engine: &Arc<Engine>,
+        component: &Component,
+        sandbox: &ExtensionSandbox,
+    ) -> ExtensionResult<Self> {
+        let mut linker = Linker::new(engine);
+
+        // Add WASI to linker (async version for async bindings)
+        wasmtime_wasi::add_to_linker_async(&mut linker).map_err(|e| {
+            ExtensionError::WasmLoadError(format!("Failed to add WASI to linker: {}", e))
+        })?;
+
+        let state = ExtensionState::sandboxed(sandbox)?;
+        let mut store = sandboxed_store(engine, state);
+
+        let bindings = context_extension_bindings::ContextExtension::instantiate_async(&mut store, component, &linker)
+            .await
+            .map_err(|e| {
+                ExtensionError::WasmLoadError(format!("Failed to instantiate context extension: {}", e))
+            })?;
+
+        Ok(Self { store, bindings })
+    }
+
+    /// Get extension metadata via core interface
+    pub async fn get_metadata(&mut self) -> ExtensionResult<context_only::core::ExtensionMetadata> {
+        self.bindings
+            .abk_extension_core()
+            .call_get_metadata(fueled(&mut self.store))
+            .await
+            .map_err(|e| ExtensionError::CallError(format!("get_metadata failed: {}", e)))
+    }
+
+    /// Initialize the extension via core interface
+    pub async fn init(&mut self) -> ExtensionResult<()> {
+        self.bindings
+            .abk_extension_core()
+            .call_init(fueled(&mut self.store))
+            .await
+            .map_err(|e| ExtensionError::CallError(format!("init call failed: {}", e)))?
+            .map_err(ExtensionError::InitError)
+    }
+
+    // ===== Context Interface Methods =====
+
+    /// Retrieve context blocks for a request (context capability)
+    pub async fn get_context(
+        &mut self,
+        request: &context_only::context::ContextRequest,
+    ) -> ExtensionResult<Vec<context_only::context::ContextBlock>> {
+        self.bindings
+            .abk_extension_context()
+            .call_get_context(fueled(&mut self.store), request)
+            .await
+            .map_err(|e| ExtensionError::CallError(format!("get_context failed: {}", e)))?
+            .map_err(ExtensionError::CallFailed)
+    }
+}
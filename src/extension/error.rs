@@ -51,6 +51,9 @@ pub enum ExtensionError {
 
     /// Provider capability error
     ProviderError(String),
+
+    /// A declared permission could not be granted
+    PermissionError(String),
 }
 
 impl fmt::Display for ExtensionError {
@@ -102,6 +105,9 @@ impl fmt::Display for ExtensionError {
             ExtensionError::ProviderError(msg) => {
                 write!(f, "Provider error: {}", msg)
             }
+            ExtensionError::PermissionError(msg) => {
+                write!(f, "Extension permission error: {}", msg)
+            }
         }
     }
 }
@@ -9,7 +9,7 @@ use wasmtime::component::Component;
 use wasmtime::Engine;
 
 /// Current host API version
-pub const HOST_API_VERSION: &str = "0.3.0";
+pub const HOST_API_VERSION: &str = "0.4.0";
 
 /// WASM extension loader using wasmtime
 pub struct ExtensionLoader {
@@ -23,6 +23,7 @@ impl ExtensionLoader {
         let mut config = wasmtime::Config::new();
         config.wasm_component_model(true);
         config.async_support(true);
+        super::sandbox::configure_engine(&mut config);
 
         let engine = Engine::new(&config).map_err(|e| {
             ExtensionError::WasmLoadError(format!("Failed to create WASM engine: {}", e))
@@ -3,6 +3,7 @@
 //! Handles parsing of `extension.toml` manifest files that describe extensions.
 
 use super::error::{ExtensionError, ExtensionResult};
+use super::sandbox::{ExtensionLimits, ExtensionPermissions};
 use serde::Deserialize;
 use std::path::Path;
 
@@ -27,6 +28,14 @@ pub struct ExtensionManifest {
     #[serde(default)]
     pub provider: Option<ProviderConfig>,
 
+    /// Host resources the extension may access (none by default)
+    #[serde(default)]
+    pub permissions: ExtensionPermissions,
+
+    /// Memory and CPU limits for the extension
+    #[serde(default)]
+    pub limits: ExtensionLimits,
+
     /// Extension-specific settings (arbitrary TOML)
     #[serde(default = "default_settings")]
     pub settings: toml::Value,
@@ -85,11 +94,11 @@ pub struct Capabilities {
     #[serde(default)]
     pub provider: bool,
 
-    /// Provides tools functionality (future)
+    /// Provides tools (see the `tools` WIT interface)
     #[serde(default)]
     pub tools: bool,
 
-    /// Provides context functionality (future)
+    /// Provides retrieved context (see the `context` WIT interface)
     #[serde(default)]
     pub context: bool,
 }
@@ -220,6 +229,7 @@ impl ExtensionManifest {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::extension::sandbox::DirAccess;
 
     const VALID_MANIFEST: &str = r#"
 [extension]
@@ -360,5 +370,39 @@ path = "extension.wasm"
         assert!(!manifest.capabilities.provider);
         assert!(!manifest.capabilities.tools);
         assert!(!manifest.capabilities.context);
+        assert!(manifest.permissions.is_empty());
+        assert_eq!(manifest.limits, ExtensionLimits::default());
+    }
+
+    #[test]
+    fn test_parse_permissions() {
+        let content = format!(
+            "{}{}",
+            VALID_MANIFEST,
+            r#"
+[permissions]
+env = ["OPENAI_API_KEY", "MYEXT_*"]
+network = ["api.openai.com"]
+
+[[permissions.dirs]]
+path = "~/.cache/test-extension"
+access = "read_write"
+
+[[permissions.dirs]]
+path = "templates"
+
+[limits]
+memory_mb = 64
+"#
+        );
+        let manifest = ExtensionManifest::from_str(&content).unwrap();
+
+        assert_eq!(manifest.permissions.env, vec!["OPENAI_API_KEY", "MYEXT_*"]);
+        assert_eq!(manifest.permissions.network, vec!["api.openai.com"]);
+        assert_eq!(manifest.permissions.dirs.len(), 2);
+        assert_eq!(manifest.permissions.dirs[0].access, DirAccess::ReadWrite);
+        assert_eq!(manifest.permissions.dirs[1].access, DirAccess::Read);
+        assert_eq!(manifest.limits.memory_mb, 64);
+        assert_eq!(manifest.limits.fuel, ExtensionLimits::default().fuel);
     }
 }
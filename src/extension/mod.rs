@@ -9,6 +9,8 @@
 //! - **`extension.toml`** - Manifest file describing the extension
 //! - **WASM Component Model** - Extensions are WASM components implementing WIT interfaces
 //! - **Capability-based architecture** - Extensions declare what capabilities they provide
+//! - **Capability-scoped sandbox** - Extensions only see the env vars, directories and
+//!   network hosts their manifest declares
 //!
 //! # Example
 //!
@@ -31,14 +33,18 @@ mod loader;
 mod manifest;
 mod registry;
 mod bindings;
+mod sandbox;
 
 pub use error::{ExtensionError, ExtensionResult};
 pub use loader::ExtensionLoader;
 pub use manifest::{Capabilities, ExtensionInfo, ExtensionManifest, LibInfo};
 pub use registry::{ExtensionRegistry, LoadedExtension};
-pub use bindings::{ExtensionInstance, ExtensionState, ProviderExtensionInstance, LifecycleExtensionInstance};
+pub use sandbox::{
+    configure_engine, DirAccess, DirPermission, ExtensionLimits, ExtensionPermissions, ExtensionSandbox,
+};
+pub use bindings::{ExtensionInstance, ExtensionState, ProviderExtensionInstance, LifecycleExtensionInstance, ToolsExtensionInstance, ContextExtensionInstance};
 // Re-export generated WIT types for external use
-pub use bindings::{core, lifecycle, provider, provider_only, lifecycle_only};
+pub use bindings::{core, lifecycle, provider, provider_only, lifecycle_only, tools_only, context_only};
 
 use std::path::{Path, PathBuf};
 
@@ -6,6 +6,7 @@ use super::bindings::{ExtensionInstance, ProviderExtensionInstance};
 use super::error::{ExtensionError, ExtensionResult};
 use super::loader::{ExtensionLoader, LoadedWasm};
 use super::manifest::ExtensionManifest;
+use super::sandbox::ExtensionSandbox;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
@@ -180,8 +181,9 @@ impl ExtensionRegistry {
             })?;
 
             // Create instance
+            let sandbox = self.sandbox(id)?;
             let instance =
-                ExtensionInstance::new(loaded_wasm.engine(), loaded_wasm.component())?;
+                ExtensionInstance::with_sandbox(loaded_wasm.engine(), loaded_wasm.component(), &sandbox)?;
 
             self.instances.insert(id.to_string(), instance);
         }
@@ -226,8 +228,13 @@ impl ExtensionRegistry {
             })?;
 
             // Create provider-only instance (async)
-            let instance =
-                ProviderExtensionInstance::new(loaded_wasm.engine(), loaded_wasm.component()).await?;
+            let sandbox = self.sandbox(id)?;
+            let instance = ProviderExtensionInstance::with_sandbox(
+                loaded_wasm.engine(),
+                loaded_wasm.component(),
+                &sandbox,
+            )
+            .await?;
 
             self.provider_instances.insert(id.to_string(), instance);
         }
@@ -245,6 +252,17 @@ impl ExtensionRegistry {
         self.provider_instances.get_mut(id)
     }
 
+    /// Sandbox declared by an extension's manifest
+    pub fn sandbox(&self, id: &str) -> ExtensionResult<ExtensionSandbox> {
+        let manifest = self.manifests.get(id).ok_or_else(|| {
+            ExtensionError::ExtensionNotFound(format!("Extension '{}' not registered", id))
+        })?;
+        let extension_dir = self.extension_paths.get(id).ok_or_else(|| {
+            ExtensionError::ExtensionNotFound(format!("Extension path for '{}' not found", id))
+        })?;
+        Ok(ExtensionSandbox::from_manifest(manifest, extension_dir))
+    }
+
     /// Get loaded WASM component by ID
     pub fn get_wasm(&self, id: &str) -> Option<&LoadedWasm> {
         self.loaded.get(id)
@@ -337,10 +355,26 @@ mod tests {
             },
             lifecycle: None,
             provider: None,
+            permissions: Default::default(),
+            limits: Default::default(),
             settings: toml::Value::Table(Default::default()),
         }
     }
 
+    #[test]
+    fn test_registry_sandbox_from_manifest() {
+        let mut registry = ExtensionRegistry::new();
+        let mut manifest = create_test_manifest("test", false, true);
+        manifest.permissions.env = vec!["TEST_API_KEY".to_string()];
+        registry.register(manifest, PathBuf::from("/extensions/test"));
+
+        let sandbox = registry.sandbox("test").unwrap();
+        assert!(sandbox.permissions.allows_env("TEST_API_KEY"));
+        assert!(!sandbox.permissions.allows_env("HOME"));
+        assert_eq!(sandbox.base_dir, PathBuf::from("/extensions/test"));
+        assert!(registry.sandbox("missing").is_err());
+    }
+
     #[test]
     fn test_registry_register() {
         let mut registry = ExtensionRegistry::new();
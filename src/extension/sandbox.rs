@@ -0,0 +1,393 @@
+//! Capability-scoped WASI sandbox
+//!
+//! Extensions get nothing from the host unless their manifest asks for it:
+//! no environment variables, no filesystem and no network. Permissions are
+//! declared in `extension.toml`:
+//!
+//! ```toml
+//! [permissions]
+//! env = ["MY_PROVIDER_API_KEY", "MY_PROVIDER_*"]
+//! network = ["api.example.com", "localhost:8080"]
+//!
+//! [[permissions.dirs]]
+//! path = "~/.cache/my-extension"
+//! access = "read_write"
+//!
+//! [limits]
+//! memory_mb = 256
+//! fuel = 10000000000
+//! ```
+//!
+//! [`ExtensionSandbox`] turns that declaration into a minimal `WasiCtx`
+//! plus a memory cap and a fuel budget that is refilled before every call
+//! into the extension, so a runaway guest traps instead of hanging the host.
+
+use super::error::{ExtensionError, ExtensionResult};
+use super::manifest::ExtensionManifest;
+use serde::Deserialize;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use wasmtime::{StoreLimits, StoreLimitsBuilder};
+use wasmtime_wasi::{DirPerms, FilePerms, SocketAddrUse, WasiCtx, WasiCtxBuilder};
+
+/// Host resources an extension may access (`[permissions]`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct ExtensionPermissions {
+    /// Environment variables passed through; a trailing `*` matches a prefix
+    #[serde(default)]
+    pub env: Vec<String>,
+
+    /// Host directories preopened for the extension
+    #[serde(default)]
+    pub dirs: Vec<DirPermission>,
+
+    /// Hosts the extension may connect to (`host` or `host:port`)
+    #[serde(default)]
+    pub network: Vec<String>,
+}
+
+/// A preopened directory (`[[permissions.dirs]]`)
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DirPermission {
+    /// Host path; `~` expands to the home directory and relative paths
+    /// resolve against the extension directory
+    pub path: String,
+
+    /// Path the extension sees (defaults to the resolved host path)
+    #[serde(default)]
+    pub guest_path: Option<String>,
+
+    /// Read-only or read-write access
+    #[serde(default)]
+    pub access: DirAccess,
+}
+
+/// Access granted to a preopened directory
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DirAccess {
+    /// List and read files
+    #[default]
+    Read,
+    /// Read, create, modify and delete files
+    ReadWrite,
+}
+
+/// Resource limits for an extension instance (`[limits]`)
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ExtensionLimits {
+    /// Maximum linear memory in MiB
+    #[serde(default = "default_memory_mb")]
+    pub memory_mb: u64,
+
+    /// Fuel (roughly, wasm instructions) available to each call
+    #[serde(default = "default_fuel")]
+    pub fuel: u64,
+}
+
+fn default_memory_mb() -> u64 {
+    256
+}
+
+fn default_fuel() -> u64 {
+    10_000_000_000
+}
+
+impl Default for ExtensionLimits {
+    fn default() -> Self {
+        Self {
+            memory_mb: default_memory_mb(),
+            fuel: default_fuel(),
+        }
+    }
+}
+
+impl ExtensionPermissions {
+    /// Check whether no host access is requested
+    pub fn is_empty(&self) -> bool {
+        self.env.is_empty() && self.dirs.is_empty() && self.network.is_empty()
+    }
+
+    /// Check whether environment variable `name` is passed through
+    pub fn allows_env(&self, name: &str) -> bool {
+        self.env.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == pattern,
+        })
+    }
+
+    /// Check whether connecting to `addr` is allowed
+    ///
+    /// Host names are resolved on each check (without blocking the runtime),
+    /// so DNS changes are picked up.
+    pub async fn allows_connect(&self, addr: SocketAddr) -> bool {
+        allows_connect(&self.network, addr).await
+    }
+}
+
+/// Permissions, limits and base directory for one extension
+#[derive(Debug, Clone, Default)]
+pub struct ExtensionSandbox {
+    /// Host resources the extension may access
+    pub permissions: ExtensionPermissions,
+    /// Memory and fuel limits
+    pub limits: ExtensionLimits,
+    /// Directory relative permission paths resolve against
+    pub base_dir: PathBuf,
+}
+
+impl ExtensionSandbox {
+    /// Sandbox declared by `manifest` for an extension installed at `extension_dir`
+    pub fn from_manifest(manifest: &ExtensionManifest, extension_dir: &Path) -> Self {
+        Self {
+            permissions: manifest.permissions.clone(),
+            limits: manifest.limits.clone(),
+            base_dir: extension_dir.to_path_buf(),
+        }
+    }
+
+    /// Build a WASI context granting exactly the declared permissions
+    ///
+    /// Stdout and stderr are inherited so extensions can log. Read-write
+    /// directories are created if missing; missing read-only directories
+    /// are an error.
+    pub fn wasi_ctx(&self) -> ExtensionResult<WasiCtx> {
+        let mut builder = WasiCtxBuilder::new();
+        builder.inherit_stdout().inherit_stderr();
+
+        for (key, value) in std::env::vars() {
+            if self.permissions.allows_env(&key) {
+                builder.env(&key, &value);
+            }
+        }
+
+        for dir in &self.permissions.dirs {
+            let host_path = self.resolve_path(&dir.path);
+            let guest_path = dir
+                .guest_path
+                .clone()
+                .unwrap_or_else(|| host_path.display().to_string());
+            let (dir_perms, file_perms) = match dir.access {
+                DirAccess::Read => (DirPerms::READ, FilePerms::READ),
+                DirAccess::ReadWrite => {
+                    std::fs::create_dir_all(&host_path)?;
+                    (DirPerms::all(), FilePerms::all())
+                }
+            };
+            builder
+                .preopened_dir(&host_path, guest_path, dir_perms, file_perms)
+                .map_err(|e| {
+                    ExtensionError::PermissionError(format!(
+                        "Cannot open directory {}: {}",
+                        host_path.display(),
+                        e
+                    ))
+                })?;
+        }
+
+        if !self.permissions.network.is_empty() {
+            let hosts = Arc::new(self.permissions.network.clone());
+            builder.allow_ip_name_lookup(true);
+            builder.socket_addr_check(move |addr, usage| {
+                let hosts = Arc::clone(&hosts);
+                Box::pin(async move {
+                    let outgoing = matches!(
+                        usage,
+                        SocketAddrUse::TcpConnect
+                            | SocketAddrUse::UdpConnect
+                            | SocketAddrUse::UdpOutgoingDatagram
+                    );
+                    outgoing && allows_connect(&hosts, addr).await
+                })
+            });
+        }
+
+        Ok(builder.build())
+    }
+
+    /// Memory cap for the extension's store
+    pub fn store_limits(&self) -> StoreLimits {
+        StoreLimitsBuilder::new()
+            .memory_size((self.limits.memory_mb * 1024 * 1024) as usize)
+            .build()
+    }
+
+    /// Fuel available to each call into the extension
+    pub fn fuel(&self) -> u64 {
+        self.limits.fuel
+    }
+
+    /// Human-readable list of the requested permissions and limits
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.permissions.is_empty() {
+            lines.push("No host access (no environment, filesystem or network)".to_string());
+        }
+        if !self.permissions.env.is_empty() {
+            lines.push(format!("Environment variables: {}", self.permissions.env.join(", ")));
+        }
+        for dir in &self.permissions.dirs {
+            let access = match dir.access {
+                DirAccess::Read => "read-only",
+                DirAccess::ReadWrite => "read-write",
+            };
+            lines.push(format!(
+                "Directory ({}): {}",
+                access,
+                self.resolve_path(&dir.path).display()
+            ));
+        }
+        if !self.permissions.network.is_empty() {
+            lines.push(format!("Network: {}", self.permissions.network.join(", ")));
+        }
+        lines.push(format!(
+            "Limits: {} MiB memory, {} fuel per call",
+            self.limits.memory_mb, self.limits.fuel
+        ));
+        lines
+    }
+
+    fn resolve_path(&self, path: &str) -> PathBuf {
+        let expanded = match path.strip_prefix("~") {
+            Some(rest) => match crate::get_home_dir() {
+                Ok(home) => PathBuf::from(format!("{}{}", home, rest)),
+                Err(_) => PathBuf::from(path),
+            },
+            None => PathBuf::from(path),
+        };
+        if expanded.is_absolute() {
+            expanded
+        } else {
+            self.base_dir.join(expanded)
+        }
+    }
+}
+
+/// Enable the engine features the sandbox relies on (fuel metering)
+///
+/// Stores created on such an engine must be given fuel before each call;
+/// see [`ExtensionSandbox::fuel`].
+pub fn configure_engine(config: &mut wasmtime::Config) {
+    config.consume_fuel(true);
+}
+
+/// Split `host`, `host:port` or `[v6]:port` into host and optional port
+fn split_host_port(entry: &str) -> (&str, Option<u16>) {
+    if let Some(rest) = entry.strip_prefix('[') {
+        if let Some((host, port)) = rest.split_once(']') {
+            return (host, port.strip_prefix(':').and_then(|p| p.parse().ok()));
+        }
+    }
+    match entry.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => match port.parse() {
+            Ok(port) => (host, Some(port)),
+            Err(_) => (entry, None),
+        },
+        _ => (entry, None),
+    }
+}
+
+async fn allows_connect(hosts: &[String], addr: SocketAddr) -> bool {
+    for entry in hosts {
+        let (host, port) = split_host_port(entry);
+        if port.is_some_and(|p| p != addr.port()) {
+            continue;
+        }
+        let allowed = match host.parse::<IpAddr>() {
+            Ok(ip) => ip == addr.ip(),
+            Err(_) => tokio::net::lookup_host((host, addr.port()))
+                .await
+                .map(|mut resolved| resolved.any(|r| r.ip() == addr.ip()))
+                .unwrap_or(false),
+        };
+        if allowed {
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_patterns() {
+        let permissions = ExtensionPermissions {
+            env: vec!["OPENAI_API_KEY".to_string(), "MYEXT_*".to_string()],
+            ..Default::default()
+        };
+
+        assert!(permissions.allows_env("OPENAI_API_KEY"));
+        assert!(permissions.allows_env("MYEXT_BASE_URL"));
+        assert!(!permissions.allows_env("OPENAI_API_KEY_2"));
+        assert!(!permissions.allows_env("AWS_SECRET_ACCESS_KEY"));
+        assert!(!ExtensionPermissions::default().allows_env("HOME"));
+    }
+
+    #[tokio::test]
+    async fn test_network_hosts() {
+        let permissions = ExtensionPermissions {
+            network: vec!["127.0.0.1:8080".to_string(), "[::1]".to_string(), "localhost:11434".to_string()],
+            ..Default::default()
+        };
+
+        assert!(permissions.allows_connect("127.0.0.1:8080".parse().unwrap()).await);
+        assert!(!permissions.allows_connect("127.0.0.1:22".parse().unwrap()).await);
+        assert!(permissions.allows_connect("[::1]:443".parse().unwrap()).await);
+        assert!(!permissions.allows_connect("10.0.0.1:8080".parse().unwrap()).await);
+        // Host names are resolved asynchronously
+        assert!(permissions.allows_connect("127.0.0.1:11434".parse().unwrap()).await);
+        assert!(!permissions.allows_connect("10.0.0.1:11434".parse().unwrap()).await);
+
+        assert_eq!(split_host_port("api.example.com"), ("api.example.com", None));
+        assert_eq!(split_host_port("localhost:11434"), ("localhost", Some(11434)));
+        assert_eq!(split_host_port("[::1]:443"), ("::1", Some(443)));
+    }
+
+    #[test]
+    fn test_wasi_ctx_and_describe() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let sandbox = ExtensionSandbox {
+            permissions: ExtensionPermissions {
+                dirs: vec![
+                    DirPermission {
+                        path: "cache".to_string(),
+                        guest_path: Some("/cache".to_string()),
+                        access: DirAccess::ReadWrite,
+                    },
+                    DirPermission {
+                        path: dir.path().display().to_string(),
+                        guest_path: None,
+                        access: DirAccess::Read,
+                    },
+                ],
+                ..Default::default()
+            },
+            limits: ExtensionLimits::default(),
+            base_dir: dir.path().to_path_buf(),
+        };
+
+        sandbox.wasi_ctx().unwrap();
+        assert!(dir.path().join("cache").is_dir());
+
+        let lines = sandbox.describe();
+        assert_eq!(lines[0], format!("Directory (read-write): {}", dir.path().join("cache").display()));
+        assert_eq!(lines[2], "Limits: 256 MiB memory, 10000000000 fuel per call");
+
+        let missing = ExtensionSandbox {
+            permissions: ExtensionPermissions {
+                dirs: vec![DirPermission {
+                    path: "does-not-exist".to_string(),
+                    guest_path: None,
+                    access: DirAccess::Read,
+                }],
+                ..Default::default()
+            },
+            base_dir: dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        assert!(matches!(missing.wasi_ctx(), Err(ExtensionError::PermissionError(_))));
+    }
+}
@@ -254,7 +254,7 @@ pub type LifecyclePlugin = WasmLifecycle;
 
 #[cfg(feature = "extension")]
 fn create_standalone_instance(extension_dir: &PathBuf) -> Result<LifecycleExtensionInstance> {
-    use crate::extension::ExtensionManifest;
+    use crate::extension::{ExtensionManifest, ExtensionSandbox};
     use std::sync::Arc;
     use wasmtime::component::Component;
     use wasmtime::{Config, Engine};
@@ -267,6 +267,7 @@ fn create_standalone_instance(extension_dir: &PathBuf) -> Result<LifecycleExtens
 
     let mut config = Config::new();
     config.wasm_component_model(true);
+    crate::extension::configure_engine(&mut config);
     debug!("Creating WASM engine (sync mode)...");
     let engine = Arc::new(Engine::new(&config)
         .context("Failed to create WASM engine for lifecycle")?);
@@ -283,7 +284,8 @@ fn create_standalone_instance(extension_dir: &PathBuf) -> Result<LifecycleExtens
     debug!("WASM component parsed successfully");
 
     debug!("Creating LifecycleExtensionInstance...");
-    let instance = LifecycleExtensionInstance::new(&engine, &component)
+    let sandbox = ExtensionSandbox::from_manifest(&manifest, extension_dir);
+    let instance = LifecycleExtensionInstance::with_sandbox(&engine, &component, &sandbox)
         .with_context(|| "Failed to create lifecycle extension instance from WASM component")?;
     debug!("LifecycleExtensionInstance created successfully");
 
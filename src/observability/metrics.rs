@@ -0,0 +1,400 @@
+//! Metrics - counters and histograms with Prometheus text exposition
+//!
+//! A process-wide [`MetricsRegistry`] (see [`global`]) collects:
+//!
+//! | Metric | Type | Labels |
+//! |--------|------|--------|
+//! | `abk_api_calls_total` | counter | provider, model, status |
+//! | `abk_llm_request_duration_seconds` | histogram | provider, model |
+//! | `abk_llm_time_to_first_token_seconds` | histogram | provider, model |
+//! | `abk_tool_executions_total` | counter | tool, status |
+//! | `abk_tool_duration_seconds` | histogram | tool |
+//! | `abk_checkpoint_write_duration_seconds` | histogram | |
+//! | `abk_checkpoint_write_bytes` | histogram | |
+//! | `abk_http_retries_total` | counter | reason |
+//! | `abk_provider_fallbacks_total` | counter | from, to, reason |
+//!
+//! The registry renders the Prometheus text format, which can be served on
+//! an HTTP endpoint ([`serve`]) or written to a textfile for the node
+//! exporter's textfile collector ([`MetricsRegistry::write_textfile`]).
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+/// Buckets for latencies in seconds (10ms .. 5min).
+pub const LATENCY_BUCKETS: &[f64] = &[
+    0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0,
+];
+
+/// Buckets for sizes in bytes (1 KiB .. 64 MiB).
+pub const BYTES_BUCKETS: &[f64] = &[
+    1024.0, 8192.0, 65536.0, 262144.0, 1048576.0, 4194304.0, 16777216.0, 67108864.0,
+];
+
+type Labels = Vec<(String, String)>;
+
+#[derive(Debug, Clone)]
+enum Series {
+    Counter(f64),
+    Histogram {
+        bounds: &'static [f64],
+        /// Non-cumulative count per bucket, plus one for `+Inf`
+        counts: Vec<u64>,
+        sum: f64,
+        count: u64,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Family {
+    help: &'static str,
+    series: BTreeMap<Labels, Series>,
+}
+
+/// Counters and histograms keyed by metric name and labels.
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    families: Mutex<BTreeMap<&'static str, Family>>,
+}
+
+impl MetricsRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `by` to a counter.
+    pub fn inc_counter(&self, name: &'static str, help: &'static str, labels: &[(&str, &str)], by: f64) {
+        self.update(name, help, labels, || Series::Counter(0.0), |series| {
+            if let Series::Counter(value) = series {
+                *value += by;
+            }
+        });
+    }
+
+    /// Record `value` in a histogram with upper bounds `bounds`.
+    pub fn observe(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        bounds: &'static [f64],
+        labels: &[(&str, &str)],
+        value: f64,
+    ) {
+        let new = || Series::Histogram {
+            bounds,
+            counts: vec![0; bounds.len() + 1],
+            sum: 0.0,
+            count: 0,
+        };
+        self.update(name, help, labels, new, |series| {
+            if let Series::Histogram { bounds, counts, sum, count } = series {
+                let bucket = bounds.iter().position(|b| value <= *b).unwrap_or(bounds.len());
+                counts[bucket] += 1;
+                *sum += value;
+                *count += 1;
+            }
+        });
+    }
+
+    /// Current value of a counter, if it was ever incremented.
+    pub fn counter_value(&self, name: &str, labels: &[(&str, &str)]) -> Option<f64> {
+        let families = self.families.lock().unwrap_or_else(|e| e.into_inner());
+        match families.get(name)?.series.get(&owned_labels(labels))? {
+            Series::Counter(value) => Some(*value),
+            Series::Histogram { .. } => None,
+        }
+    }
+
+    /// Render all metrics in the Prometheus text exposition format (0.0.4).
+    pub fn render(&self) -> String {
+        let families = self.families.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+        for (name, family) in families.iter() {
+            let kind = match family.series.values().next() {
+                Some(Series::Histogram { .. }) => "histogram",
+                _ => "counter",
+            };
+            let _ = writeln!(out, "# HELP {} {}", name, family.help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+            for (labels, series) in &family.series {
+                match series {
+                    Series::Counter(value) => {
+                        let _ = writeln!(out, "{}{} {}", name, format_labels(labels, None), value);
+                    }
+                    Series::Histogram { bounds, counts, sum, count } => {
+                        let mut cumulative = 0;
+                        for (i, bucket) in counts.iter().enumerate() {
+                            cumulative += bucket;
+                            let le = bounds.get(i).map(|b| b.to_string()).unwrap_or_else(|| "+Inf".to_string());
+                            let _ = writeln!(
+                                out,
+                                "{}_bucket{} {}",
+                                name,
+                                format_labels(labels, Some(&le)),
+                                cumulative
+                            );
+                        }
+                        let _ = writeln!(out, "{}_sum{} {}", name, format_labels(labels, None), sum);
+                        let _ = writeln!(out, "{}_count{} {}", name, format_labels(labels, None), count);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Atomically write [`render`](Self::render) output to `path`.
+    pub fn write_textfile(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create metrics directory: {}", parent.display()))?;
+        }
+        let tmp = path.with_extension("prom.tmp");
+        std::fs::write(&tmp, self.render())
+            .with_context(|| format!("Failed to write metrics file: {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to write metrics file: {}", path.display()))?;
+        Ok(())
+    }
+
+    fn update(
+        &self,
+        name: &'static str,
+        help: &'static str,
+        labels: &[(&str, &str)],
+        new: impl FnOnce() -> Series,
+        apply: impl FnOnce(&mut Series),
+    ) {
+        let mut families = self.families.lock().unwrap_or_else(|e| e.into_inner());
+        let family = families.entry(name).or_insert_with(|| Family {
+            help,
+            series: BTreeMap::new(),
+        });
+        apply(family.series.entry(owned_labels(labels)).or_insert_with(new));
+    }
+}
+
+fn owned_labels(labels: &[(&str, &str)]) -> Labels {
+    labels.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+fn format_labels(labels: &Labels, le: Option<&str>) -> String {
+    let mut parts: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+        .collect();
+    if let Some(le) = le {
+        parts.push(format!("le=\"{}\"", le));
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", parts.join(","))
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Process-wide registry used by the agent, providers and checkpoint storage.
+pub fn global() -> &'static MetricsRegistry {
+    static REGISTRY: OnceLock<MetricsRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(MetricsRegistry::new)
+}
+
+/// Record a finished LLM API call. `status` is "ok" or "error".
+pub fn record_api_call(provider: &str, model: &str, status: &str, duration: Duration) {
+    let registry = global();
+    registry.inc_counter(
+        "abk_api_calls_total",
+        "LLM API calls by provider, model and status",
+        &[("provider", provider), ("model", model), ("status", status)],
+        1.0,
+    );
+    registry.observe(
+        "abk_llm_request_duration_seconds",
+        "LLM API call latency in seconds",
+        LATENCY_BUCKETS,
+        &[("provider", provider), ("model", model)],
+        duration.as_secs_f64(),
+    );
+}
+
+/// Record the time until the first streamed chunk of a response.
+pub fn record_time_to_first_token(provider: &str, model: &str, duration: Duration) {
+    global().observe(
+        "abk_llm_time_to_first_token_seconds",
+        "Time from request to first streamed chunk in seconds",
+        LATENCY_BUCKETS,
+        &[("provider", provider), ("model", model)],
+        duration.as_secs_f64(),
+    );
+}
+
+/// Record a finished tool execution.
+pub fn record_tool_execution(tool: &str, success: bool, duration: Duration) {
+    let registry = global();
+    registry.inc_counter(
+        "abk_tool_executions_total",
+        "Tool executions by tool and status",
+        &[("tool", tool), ("status", if success { "ok" } else { "error" })],
+        1.0,
+    );
+    registry.observe(
+        "abk_tool_duration_seconds",
+        "Tool execution latency in seconds",
+        LATENCY_BUCKETS,
+        &[("tool", tool)],
+        duration.as_secs_f64(),
+    );
+}
+
+/// Record a checkpoint write of `bytes` bytes.
+pub fn record_checkpoint_write(duration: Duration, bytes: u64) {
+    let registry = global();
+    registry.observe(
+        "abk_checkpoint_write_duration_seconds",
+        "Checkpoint write latency in seconds",
+        LATENCY_BUCKETS,
+        &[],
+        duration.as_secs_f64(),
+    );
+    registry.observe(
+        "abk_checkpoint_write_bytes",
+        "Bytes written per checkpoint",
+        BYTES_BUCKETS,
+        &[],
+        bytes as f64,
+    );
+}
+
+/// Record an HTTP retry. `reason` is "rate_limited", "server_error" or "network".
+pub fn record_http_retry(reason: &str) {
+    global().inc_counter(
+        "abk_http_retries_total",
+        "HTTP request retries by reason",
+        &[("reason", reason)],
+        1.0,
+    );
+}
+
+/// Record a routing fallback from backend `from` to backend `to`.
+pub fn record_provider_fallback(from: &str, to: &str, reason: &str) {
+    global().inc_counter(
+        "abk_provider_fallbacks_total",
+        "Routing provider fallbacks between backends",
+        &[("from", from), ("to", to), ("reason", reason)],
+        1.0,
+    );
+}
+
+/// Serve the global registry at `http://<addr>/metrics` from a background thread.
+///
+/// Returns the bound address (useful with port 0).
+pub fn serve(addr: &str) -> Result<SocketAddr> {
+    let listener = TcpListener::bind(addr).with_context(|| format!("Failed to bind metrics endpoint {}", addr))?;
+    let local_addr = listener.local_addr()?;
+    std::thread::Builder::new()
+        .name("abk-metrics".to_string())
+        .spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let _ = respond(stream);
+            }
+        })
+        .context("Failed to start metrics endpoint")?;
+    Ok(local_addr)
+}
+
+fn respond(mut stream: std::net::TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+
+    let (status, content_type, body) = if path == "/metrics" || path.starts_with("/metrics?") {
+        ("200 OK", "text/plain; version=0.0.4", global().render())
+    } else {
+        ("404 Not Found", "text/plain", "Not Found\n".to_string())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_counter_rendering() {
+        let registry = MetricsRegistry::new();
+        let labels = [("provider", "openai"), ("model", "gpt-4o"), ("status", "ok")];
+        registry.inc_counter("abk_api_calls_total", "LLM API calls", &labels, 1.0);
+        registry.inc_counter("abk_api_calls_total", "LLM API calls", &labels, 1.0);
+
+        assert_eq!(registry.counter_value("abk_api_calls_total", &labels), Some(2.0));
+        let text = registry.render();
+        assert!(text.contains("# TYPE abk_api_calls_total counter"));
+        assert!(text.contains("abk_api_calls_total{provider=\"openai\",model=\"gpt-4o\",status=\"ok\"} 2"));
+    }
+
+    #[test]
+    fn test_histogram_rendering() {
+        let registry = MetricsRegistry::new();
+        for value in [0.02, 0.3, 400.0] {
+            registry.observe("abk_tool_duration_seconds", "Tool latency", LATENCY_BUCKETS, &[("tool", "bash")], value);
+        }
+
+        let text = registry.render();
+        assert!(text.contains("# TYPE abk_tool_duration_seconds histogram"));
+        assert!(text.contains("abk_tool_duration_seconds_bucket{tool=\"bash\",le=\"0.01\"} 0"));
+        assert!(text.contains("abk_tool_duration_seconds_bucket{tool=\"bash\",le=\"0.05\"} 1"));
+        assert!(text.contains("abk_tool_duration_seconds_bucket{tool=\"bash\",le=\"0.5\"} 2"));
+        assert!(text.contains("abk_tool_duration_seconds_bucket{tool=\"bash\",le=\"+Inf\"} 3"));
+        assert!(text.contains("abk_tool_duration_seconds_count{tool=\"bash\"} 3"));
+    }
+
+    #[test]
+    fn test_write_textfile() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("agent.prom");
+        let registry = MetricsRegistry::new();
+        registry.inc_counter("abk_http_retries_total", "Retries", &[("reason", "rate\"limited")], 1.0);
+
+        registry.write_textfile(&path).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("abk_http_retries_total{reason=\"rate\\\"limited\"} 1"));
+        assert!(!path.with_extension("prom.tmp").exists());
+    }
+
+    #[test]
+    fn test_serve_endpoint() {
+        record_http_retry("network");
+        let addr = serve("127.0.0.1:0").unwrap();
+
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        write!(stream, "GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("abk_http_retries_total{reason=\"network\"}"));
+    }
+}
@@ -25,10 +25,20 @@
 //! ```
 
 pub mod logger;
+pub mod metrics;
+pub mod trace;
 
 // Re-export main types for convenience
 pub use logger::Logger;
 
+// Re-export structured tracing types
+pub use trace::{JsonlExporter, Span, SpanExporter, SpanKind, SpanRecord, SpanStatus, Tracer};
+#[cfg(feature = "observability-otlp")]
+pub use trace::OtlpHttpExporter;
+
+// Re-export the metrics registry
+pub use metrics::MetricsRegistry;
+
 // Re-export standalone tee-write functions for components without Logger reference
 pub use logger::{tee_print, tee_println, tee_eprint, tee_eprintln};
 
@@ -0,0 +1,760 @@
+//! Structured tracing - spans for session → iteration → API call → tool call
+//!
+//! The markdown [`Logger`](super::Logger) is meant for humans. A [`Tracer`]
+//! records the same run as nested spans with durations and attributes
+//! (model, token counts, tool name, success) and hands each finished span to
+//! its exporters:
+//!
+//! - [`JsonlExporter`] appends one JSON object per span to a file
+//! - `OtlpHttpExporter` (feature `observability-otlp`) posts spans to an
+//!   OpenTelemetry collector using OTLP/HTTP with JSON encoding, in batches
+//!
+//! Call [`Tracer::flush`] before exiting so buffered spans are delivered.
+//!
+//! Session and iteration spans are parents: while one is open, spans started
+//! on the same tracer become its children. API and tool call spans are leaves.
+//!
+//! ```no_run
+//! use abk::observability::{JsonlExporter, SpanKind, Tracer};
+//!
+//! let tracer = Tracer::new(vec![Box::new(JsonlExporter::new("/tmp/agent.trace.jsonl").unwrap())]);
+//! let session = tracer.span(SpanKind::Session, "session");
+//! let mut call = tracer.span(SpanKind::ApiCall, "api_call");
+//! call.set_attribute("llm.model", "gpt-4o");
+//! call.end();
+//! session.end();
+//! ```
+
+use std::fs::{File, OpenOptions};
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Map, Value};
+
+/// Level of a span in the run hierarchy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanKind {
+    /// One workflow run
+    Session,
+    /// One iteration of the workflow loop
+    Iteration,
+    /// One LLM API call
+    ApiCall,
+    /// One tool execution
+    ToolCall,
+}
+
+impl SpanKind {
+    /// Name used in exported records.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Session => "session",
+            Self::Iteration => "iteration",
+            Self::ApiCall => "api_call",
+            Self::ToolCall => "tool_call",
+        }
+    }
+
+    /// Whether spans started while this one is open become its children.
+    fn is_parent(&self) -> bool {
+        matches!(self, Self::Session | Self::Iteration)
+    }
+}
+
+/// Outcome of a span.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum SpanStatus {
+    /// Not set
+    #[default]
+    Unset,
+    /// Completed successfully
+    Ok,
+    /// Failed with a message
+    Error(String),
+}
+
+/// A finished span.
+#[derive(Debug, Clone)]
+pub struct SpanRecord {
+    /// 32 hex chars, shared by all spans of a session
+    pub trace_id: String,
+    /// 16 hex chars
+    pub span_id: String,
+    /// Enclosing session or iteration span
+    pub parent_span_id: Option<String>,
+    /// Span name
+    pub name: String,
+    /// Level in the run hierarchy
+    pub kind: SpanKind,
+    /// Start time
+    pub start_time: DateTime<Utc>,
+    /// End time
+    pub end_time: DateTime<Utc>,
+    /// Attributes (model, tokens, tool name, ...)
+    pub attributes: Map<String, Value>,
+    /// Outcome
+    pub status: SpanStatus,
+}
+
+impl SpanRecord {
+    /// Span duration in milliseconds.
+    pub fn duration_ms(&self) -> i64 {
+        (self.end_time - self.start_time).num_milliseconds()
+    }
+
+    /// Flat JSON representation written by [`JsonlExporter`].
+    pub fn to_json(&self) -> Value {
+        let (status, error) = match &self.status {
+            SpanStatus::Unset => ("unset", None),
+            SpanStatus::Ok => ("ok", None),
+            SpanStatus::Error(message) => ("error", Some(message.clone())),
+        };
+        json!({
+            "trace_id": self.trace_id,
+            "span_id": self.span_id,
+            "parent_span_id": self.parent_span_id,
+            "name": self.name,
+            "kind": self.kind.as_str(),
+            "start_time": self.start_time.to_rfc3339(),
+            "end_time": self.end_time.to_rfc3339(),
+            "duration_ms": self.duration_ms(),
+            "status": status,
+            "error": error,
+            "attributes": self.attributes,
+        })
+    }
+}
+
+/// Receives finished spans.
+///
+/// Exporters must not block for long or fail the run; report problems on
+/// stderr and drop the span instead.
+pub trait SpanExporter: Send + Sync {
+    /// Export one finished span.
+    fn export(&self, span: &SpanRecord);
+
+    /// Deliver spans buffered so far; resolves once they were sent.
+    fn flush(&self) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        Box::pin(std::future::ready(()))
+    }
+}
+
+/// Appends spans as JSON lines to a file.
+pub struct JsonlExporter {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl JsonlExporter {
+    /// Open (or create) `path` for appending.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create trace directory: {}", parent.display()))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open trace file: {}", path.display()))?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Path of the trace file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl SpanExporter for JsonlExporter {
+    fn export(&self, span: &SpanRecord) {
+        let Ok(mut file) = self.file.lock() else {
+            return;
+        };
+        if let Err(e) = writeln!(file, "{}", span.to_json()) {
+            super::tee_eprintln(&format!("Warning: Failed to write trace span: {}", e));
+        }
+    }
+
+    fn flush(&self) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+        Box::pin(std::future::ready(()))
+    }
+}
+
+/// Most spans sent in one OTLP request
+#[cfg(feature = "observability-otlp")]
+const OTLP_MAX_BATCH: usize = 512;
+
+/// Longest a finished span waits before its batch is sent
+#[cfg(feature = "observability-otlp")]
+const OTLP_EXPORT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Longest [`SpanExporter::flush`] waits for the collector
+#[cfg(feature = "observability-otlp")]
+const OTLP_FLUSH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Posts spans to an OpenTelemetry collector (OTLP/HTTP, JSON encoding).
+///
+/// Finished spans are queued on a channel and sent in batches of up to
+/// 512 spans, at least every 5 seconds, by a background task started on the
+/// tokio runtime of the first span or flush. [`flush`](SpanExporter::flush)
+/// sends whatever is queued and waits (up to 10 seconds) for the collector;
+/// dropping the exporter sends the rest without waiting.
+#[cfg(feature = "observability-otlp")]
+pub struct OtlpHttpExporter {
+    target: OtlpTarget,
+    sender: tokio::sync::mpsc::UnboundedSender<OtlpCommand>,
+    receiver: Mutex<Option<tokio::sync::mpsc::UnboundedReceiver<OtlpCommand>>>,
+}
+
+#[cfg(feature = "observability-otlp")]
+enum OtlpCommand {
+    Span(Box<SpanRecord>),
+    Flush(tokio::sync::oneshot::Sender<()>),
+}
+
+/// Where and how batches are posted
+#[cfg(feature = "observability-otlp")]
+#[derive(Clone)]
+struct OtlpTarget {
+    endpoint: String,
+    headers: Vec<(String, String)>,
+    service_name: String,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "observability-otlp")]
+impl OtlpHttpExporter {
+    /// Export to `endpoint`, e.g. `http://localhost:4318/v1/traces`.
+    pub fn new(endpoint: impl Into<String>, service_name: impl Into<String>) -> Self {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        Self {
+            target: OtlpTarget {
+                endpoint: endpoint.into(),
+                headers: Vec::new(),
+                service_name: service_name.into(),
+                client: reqwest::Client::new(),
+            },
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+        }
+    }
+
+    /// Add a header sent with every request (e.g. an API key).
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.target.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Start the batching task if it isn't running and a runtime is available.
+    fn ensure_worker(&self) {
+        let mut receiver = self.receiver.lock().unwrap_or_else(|e| e.into_inner());
+        if receiver.is_none() {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        if let Some(receiver) = receiver.take() {
+            runtime.spawn(self.target.clone().run(receiver));
+        }
+    }
+}
+
+#[cfg(feature = "observability-otlp")]
+impl OtlpTarget {
+    /// Collect queued spans into batches until every sender is gone.
+    async fn run(self, mut receiver: tokio::sync::mpsc::UnboundedReceiver<OtlpCommand>) {
+        let mut pending: Vec<SpanRecord> = Vec::new();
+        let mut deadline = tokio::time::Instant::now();
+        loop {
+            let command = if pending.is_empty() {
+                receiver.recv().await
+            } else {
+                match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                    Ok(command) => command,
+                    Err(_) => {
+                        self.send(&mut pending).await;
+                        continue;
+                    }
+                }
+            };
+            match command {
+                Some(OtlpCommand::Span(span)) => {
+                    if pending.is_empty() {
+                        deadline = tokio::time::Instant::now() + OTLP_EXPORT_INTERVAL;
+                    }
+                    pending.push(*span);
+                    if pending.len() >= OTLP_MAX_BATCH {
+                        self.send(&mut pending).await;
+                    }
+                }
+                Some(OtlpCommand::Flush(done)) => {
+                    self.send(&mut pending).await;
+                    let _ = done.send(());
+                }
+                None => {
+                    self.send(&mut pending).await;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Post `spans` as one request and clear them; failures are reported and the spans dropped.
+    async fn send(&self, spans: &mut Vec<SpanRecord>) {
+        if spans.is_empty() {
+            return;
+        }
+        let body = otlp_request(&self.service_name, spans);
+        spans.clear();
+        let mut request = self.client.post(&self.endpoint).json(&body);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        match request.send().await {
+            Ok(response) if !response.status().is_success() => super::tee_eprintln(&format!(
+                "Warning: OTLP export failed: HTTP {}",
+                response.status()
+            )),
+            Ok(_) => {}
+            Err(e) => super::tee_eprintln(&format!("Warning: OTLP export failed: {}", e)),
+        }
+    }
+}
+
+#[cfg(feature = "observability-otlp")]
+impl SpanExporter for OtlpHttpExporter {
+    fn export(&self, span: &SpanRecord) {
+        // Queued even outside a runtime; sent once the task starts
+        let _ = self.sender.send(OtlpCommand::Span(Box::new(span.clone())));
+        self.ensure_worker();
+    }
+
+    fn flush(&self) -> Pin<Box<dyn Future<Output = ()> + Send + '_>> {
+        self.ensure_worker();
+        let (done, delivered) = tokio::sync::oneshot::channel();
+        let queued = self.sender.send(OtlpCommand::Flush(done)).is_ok()
+            && self.receiver.lock().is_ok_and(|r| r.is_none());
+        Box::pin(async move {
+            if queued && tokio::time::timeout(OTLP_FLUSH_TIMEOUT, delivered).await.is_err() {
+                super::tee_eprintln("Warning: OTLP export did not finish before the flush timeout");
+            }
+        })
+    }
+}
+
+/// Build an OTLP `ExportTraceServiceRequest` in its JSON encoding.
+pub fn otlp_request(service_name: &str, spans: &[SpanRecord]) -> Value {
+    let spans: Vec<Value> = spans.iter().map(otlp_span).collect();
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [otlp_attribute("service.name", &json!(service_name))]
+            },
+            "scopeSpans": [{
+                "scope": { "name": "abk", "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }]
+        }]
+    })
+}
+
+fn otlp_span(span: &SpanRecord) -> Value {
+    let mut attributes = vec![otlp_attribute("abk.span.kind", &json!(span.kind.as_str()))];
+    attributes.extend(span.attributes.iter().map(|(k, v)| otlp_attribute(k, v)));
+
+    // Status codes: 0 = unset, 1 = ok, 2 = error
+    let status = match &span.status {
+        SpanStatus::Unset => json!({ "code": 0 }),
+        SpanStatus::Ok => json!({ "code": 1 }),
+        SpanStatus::Error(message) => json!({ "code": 2, "message": message }),
+    };
+    // Span kinds: 1 = internal, 3 = client
+    let kind = if span.kind == SpanKind::ApiCall { 3 } else { 1 };
+
+    let mut value = json!({
+        "traceId": span.trace_id,
+        "spanId": span.span_id,
+        "name": span.name,
+        "kind": kind,
+        "startTimeUnixNano": unix_nanos(&span.start_time).to_string(),
+        "endTimeUnixNano": unix_nanos(&span.end_time).to_string(),
+        "attributes": attributes,
+        "status": status,
+    });
+    if let Some(ref parent) = span.parent_span_id {
+        value["parentSpanId"] = json!(parent);
+    }
+    value
+}
+
+fn otlp_attribute(key: &str, value: &Value) -> Value {
+    let value = match value {
+        Value::Bool(b) => json!({ "boolValue": b }),
+        Value::Number(n) if n.is_i64() || n.is_u64() => json!({ "intValue": n.to_string() }),
+        Value::Number(n) => json!({ "doubleValue": n.as_f64() }),
+        Value::String(s) => json!({ "stringValue": s }),
+        other => json!({ "stringValue": other.to_string() }),
+    };
+    json!({ "key": key, "value": value })
+}
+
+fn unix_nanos(time: &DateTime<Utc>) -> i64 {
+    time.timestamp_nanos_opt().unwrap_or_default()
+}
+
+struct TracerInner {
+    exporters: Vec<Box<dyn SpanExporter>>,
+    /// Open parent spans as (trace id, span id), innermost last
+    parents: Mutex<Vec<(String, String)>>,
+}
+
+/// Creates spans and sends finished ones to its exporters.
+///
+/// Cloning is cheap; clones share exporters and the open parent spans.
+/// A disabled tracer hands out spans that record nothing.
+#[derive(Clone, Default)]
+pub struct Tracer {
+    inner: Option<Arc<TracerInner>>,
+}
+
+impl Tracer {
+    /// Tracer exporting to `exporters`.
+    pub fn new(exporters: Vec<Box<dyn SpanExporter>>) -> Self {
+        if exporters.is_empty() {
+            return Self::disabled();
+        }
+        Self {
+            inner: Some(Arc::new(TracerInner {
+                exporters,
+                parents: Mutex::new(Vec::new()),
+            })),
+        }
+    }
+
+    /// Tracer that records nothing.
+    pub fn disabled() -> Self {
+        Self { inner: None }
+    }
+
+    /// Whether spans are exported.
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Deliver spans the exporters have buffered.
+    ///
+    /// Call at the end of a run; the tracer stays usable afterwards.
+    pub async fn flush(&self) {
+        if let Some(ref inner) = self.inner {
+            for exporter in &inner.exporters {
+                exporter.flush().await;
+            }
+        }
+    }
+
+    /// Start a span, child of the innermost open session or iteration span.
+    pub fn span(&self, kind: SpanKind, name: impl Into<String>) -> Span {
+        let Some(ref inner) = self.inner else {
+            return Span {
+                inner: None,
+                record: None,
+                started: Instant::now(),
+            };
+        };
+
+        let span_id = random_hex(8);
+        let (trace_id, parent_span_id) = {
+            let mut parents = inner.parents.lock().unwrap_or_else(|e| e.into_inner());
+            let (trace_id, parent) = match parents.last() {
+                Some((trace_id, parent)) => (trace_id.clone(), Some(parent.clone())),
+                None => (random_hex(16), None),
+            };
+            if kind.is_parent() {
+                parents.push((trace_id.clone(), span_id.clone()));
+            }
+            (trace_id, parent)
+        };
+
+        let now = Utc::now();
+        Span {
+            inner: Some(Arc::clone(inner)),
+            record: Some(SpanRecord {
+                trace_id,
+                span_id,
+                parent_span_id,
+                name: name.into(),
+                kind,
+                start_time: now,
+                end_time: now,
+                attributes: Map::new(),
+                status: SpanStatus::Unset,
+            }),
+            started: Instant::now(),
+        }
+    }
+}
+
+/// An open span. Ends (and is exported) on [`Span::end`] or when dropped.
+pub struct Span {
+    inner: Option<Arc<TracerInner>>,
+    record: Option<SpanRecord>,
+    started: Instant,
+}
+
+impl Span {
+    /// Set an attribute; ignored on spans of a disabled tracer.
+    pub fn set_attribute(&mut self, key: &str, value: impl Into<Value>) {
+        if let Some(ref mut record) = self.record {
+            record.attributes.insert(key.to_string(), value.into());
+        }
+    }
+
+    /// Mark the span as successful.
+    pub fn set_ok(&mut self) {
+        if let Some(ref mut record) = self.record {
+            record.status = SpanStatus::Ok;
+        }
+    }
+
+    /// Mark the span as failed.
+    pub fn set_error(&mut self, message: impl Into<String>) {
+        if let Some(ref mut record) = self.record {
+            record.status = SpanStatus::Error(message.into());
+        }
+    }
+
+    /// Set the start time to `started`, for spans recorded after the fact.
+    pub fn set_started(&mut self, started: Instant) {
+        if let Some(ref mut record) = self.record {
+            let shift = chrono::Duration::from_std(self.started.saturating_duration_since(started))
+                .unwrap_or_default();
+            record.start_time -= shift;
+        }
+        self.started = started;
+    }
+
+    /// Time since the span started.
+    pub fn elapsed(&self) -> std::time::Duration {
+        self.started.elapsed()
+    }
+
+    /// End the span and export it.
+    pub fn end(mut self) {
+        self.finish();
+    }
+
+    fn finish(&mut self) {
+        let (Some(inner), Some(mut record)) = (self.inner.take(), self.record.take()) else {
+            return;
+        };
+        let elapsed = chrono::Duration::from_std(self.started.elapsed()).unwrap_or_default();
+        record.end_time = record.start_time + elapsed;
+
+        if record.kind.is_parent() {
+            let mut parents = inner.parents.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(pos) = parents.iter().rposition(|(_, id)| *id == record.span_id) {
+                parents.truncate(pos);
+            }
+        }
+        for exporter in &inner.exporters {
+            exporter.export(&record);
+        }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Random lowercase hex id of `bytes` bytes.
+fn random_hex(bytes: usize) -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut out = String::with_capacity(bytes * 2);
+    while out.len() < bytes * 2 {
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+        hasher.write_u128(Utc::now().timestamp_nanos_opt().unwrap_or_default() as u128);
+        out.push_str(&format!("{:016x}", hasher.finish()));
+    }
+    out.truncate(bytes * 2);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Collector {
+        spans: Mutex<Vec<SpanRecord>>,
+    }
+
+    impl SpanExporter for Arc<Collector> {
+        fn export(&self, span: &SpanRecord) {
+            self.spans.lock().unwrap().push(span.clone());
+        }
+    }
+
+    #[test]
+    fn test_span_hierarchy() {
+        let collector = Arc::new(Collector::default());
+        let tracer = Tracer::new(vec![Box::new(Arc::clone(&collector))]);
+
+        let session = tracer.span(SpanKind::Session, "session");
+        let iteration = tracer.span(SpanKind::Iteration, "iteration");
+        let mut call = tracer.span(SpanKind::ApiCall, "api_call");
+        call.set_attribute("llm.model", "gpt-4o");
+        call.set_ok();
+        call.end();
+        drop(iteration);
+        let tool = tracer.span(SpanKind::ToolCall, "tool_call");
+        drop(tool);
+        session.end();
+
+        let spans = collector.spans.lock().unwrap();
+        let [call, iteration, tool, session] = &spans[..] else {
+            panic!("expected 4 spans, got {}", spans.len());
+        };
+        assert_eq!(session.parent_span_id, None);
+        assert_eq!(iteration.parent_span_id.as_ref(), Some(&session.span_id));
+        assert_eq!(call.parent_span_id.as_ref(), Some(&iteration.span_id));
+        assert_eq!(tool.parent_span_id.as_ref(), Some(&session.span_id));
+        assert!(spans.iter().all(|s| s.trace_id == session.trace_id));
+        assert_eq!(session.trace_id.len(), 32);
+        assert_eq!(call.span_id.len(), 16);
+        assert_eq!(call.attributes["llm.model"], "gpt-4o");
+        assert_eq!(call.status, SpanStatus::Ok);
+        assert!(session.end_time >= call.end_time);
+    }
+
+    #[test]
+    fn test_disabled_tracer_records_nothing() {
+        let tracer = Tracer::disabled();
+        assert!(!tracer.is_enabled());
+        let mut span = tracer.span(SpanKind::Session, "session");
+        span.set_attribute("key", 1);
+        span.end();
+    }
+
+    #[test]
+    fn test_jsonl_exporter() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trace.jsonl");
+        let tracer = Tracer::new(vec![Box::new(JsonlExporter::new(&path).unwrap())]);
+
+        let mut span = tracer.span(SpanKind::ToolCall, "tool_call");
+        span.set_attribute("tool.name", "bash");
+        span.set_error("exit code 1");
+        span.end();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let line: Value = serde_json::from_str(content.lines().next().unwrap()).unwrap();
+        assert_eq!(line["kind"], "tool_call");
+        assert_eq!(line["status"], "error");
+        assert_eq!(line["error"], "exit code 1");
+        assert_eq!(line["attributes"]["tool.name"], "bash");
+        assert!(line["duration_ms"].as_i64().unwrap() >= 0);
+    }
+
+    /// Accept OTLP posts and return the endpoint and the received bodies.
+    #[cfg(feature = "observability-otlp")]
+    fn collector() -> (String, Arc<Mutex<Vec<Value>>>) {
+        use std::io::{BufRead, BufReader, Read};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/v1/traces", listener.local_addr().unwrap());
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&bodies);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { return };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some(len) = line.to_lowercase().strip_prefix("content-length:") {
+                        content_length = len.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0u8; content_length];
+                let _ = reader.read_exact(&mut body);
+                received.lock().unwrap().push(serde_json::from_slice(&body).unwrap());
+                let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}");
+            }
+        });
+        (endpoint, bodies)
+    }
+
+    #[cfg(feature = "observability-otlp")]
+    #[tokio::test]
+    async fn test_otlp_exporter_batches_until_flush() {
+        let (endpoint, bodies) = collector();
+        let tracer = Tracer::new(vec![Box::new(OtlpHttpExporter::new(endpoint, "trustee"))]);
+
+        let session = tracer.span(SpanKind::Session, "session");
+        for _ in 0..3 {
+            tracer.span(SpanKind::ToolCall, "tool_call").end();
+        }
+        session.end();
+        assert!(bodies.lock().unwrap().is_empty());
+
+        tracer.flush().await;
+        let bodies = bodies.lock().unwrap();
+        assert_eq!(bodies.len(), 1);
+        let spans = bodies[0]["resourceSpans"][0]["scopeSpans"][0]["spans"].as_array().unwrap();
+        assert_eq!(spans.len(), 4);
+        assert_eq!(spans[3]["name"], "session");
+    }
+
+    #[test]
+    fn test_otlp_request_encoding() {
+        let now = Utc::now();
+        let mut attributes = Map::new();
+        attributes.insert("llm.total_tokens".to_string(), json!(42));
+        attributes.insert("tool.success".to_string(), json!(true));
+        let span = SpanRecord {
+            trace_id: "0".repeat(32),
+            span_id: "1".repeat(16),
+            parent_span_id: Some("2".repeat(16)),
+            name: "api_call".to_string(),
+            kind: SpanKind::ApiCall,
+            start_time: now,
+            end_time: now + chrono::Duration::milliseconds(5),
+            attributes,
+            status: SpanStatus::Error("timeout".to_string()),
+        };
+
+        let request = otlp_request("trustee", &[span]);
+        let resource = &request["resourceSpans"][0];
+        assert_eq!(resource["resource"]["attributes"][0]["value"]["stringValue"], "trustee");
+        let span = &resource["scopeSpans"][0]["spans"][0];
+        assert_eq!(span["parentSpanId"], "2".repeat(16));
+        assert_eq!(span["kind"], 3);
+        assert_eq!(span["status"]["code"], 2);
+        let attributes = span["attributes"].as_array().unwrap();
+        assert!(attributes.contains(&json!({"key": "llm.total_tokens", "value": {"intValue": "42"}})));
+        assert!(attributes.contains(&json!({"key": "tool.success", "value": {"boolValue": true}})));
+    }
+}
@@ -11,7 +11,15 @@ use tokio_util::sync::CancellationToken;
 use umf::GenerateResult;
 use std::collections::HashMap;
 
+use super::approval::{ApprovalDecision, ApprovalGate, ApprovalRequest, SharedApprover};
+use super::budget::{BudgetGuard, BudgetOverrun};
+use super::compaction::{transcript, CompactionOutcome, ContextCompactor};
+use super::context_injection::{ContextInjector, ContextRequest};
 use super::output::{OutputEvent, SharedSink};
+use super::user_input::UserInputQueue;
+use crate::config::BudgetAction;
+use crate::observability::{metrics, Span, SpanKind, Tracer};
+use crate::provider::UsageTotals;
 use umf::chatml::count_tokens_for_text;
 
 /// Tool execution result (re-export from tools module to avoid circular dependency)
@@ -67,8 +75,119 @@ pub trait AgentContext {
     // Tool execution
     async fn execute_tool_calls_structured(&mut self, tool_calls: Vec<umf::ToolCall>) 
         -> Result<Vec<ToolExecutionResult>>;
+
+    /// Execute tool calls, calling `on_complete` for each result as soon as it finishes.
+    ///
+    /// `on_complete` also receives the instant the call started running.
+    /// Results are returned in call order. The default executes the calls one
+    /// at a time via [`execute_tool_calls_structured`](Self::execute_tool_calls_structured);
+    /// agents that dispatch calls concurrently override it.
+    async fn execute_tool_calls_reporting(
+        &mut self,
+        tool_calls: Vec<umf::ToolCall>,
+        on_complete: &(dyn Fn(&ToolExecutionResult, std::time::Instant) + Send + Sync),
+    ) -> Result<Vec<ToolExecutionResult>> {
+        let mut results = Vec::with_capacity(tool_calls.len());
+        for tc in tool_calls {
+            let started = std::time::Instant::now();
+            let mut executed = self.execute_tool_calls_structured(vec![tc]).await?;
+            for result in &executed {
+                on_complete(result, started);
+            }
+            results.append(&mut executed);
+        }
+        Ok(results)
+    }
     fn generate_assistant_content_for_tools(&self, tool_calls: &[umf::ToolCall]) -> String;
     fn get_tool_schemas(&self) -> Vec<serde_json::Value>;
+
+    /// Bring tool sources up to date before tools are sent to the LLM
+    /// (e.g. reload MCP servers that announced `tools/list_changed`).
+    async fn refresh_tool_sources(&mut self) {}
+
+    /// Compactor used to summarize older turns once the context grows too large.
+    ///
+    /// The default `None` leaves the history to `limit_history` alone.
+    fn context_compactor_mut(&mut self) -> Option<&mut ContextCompactor> {
+        None
+    }
+
+    /// Called after the history was compacted (e.g. to record it in the session event log).
+    fn on_context_compacted(&mut self, _outcome: &CompactionOutcome) {}
+
+    /// Injector that inserts context from `context` extensions before LLM calls.
+    ///
+    /// The default `None` sends the conversation as is.
+    fn context_injector_mut(&mut self) -> Option<&mut ContextInjector> {
+        None
+    }
+
+    /// Queue of messages sent by the user while the workflow runs.
+    ///
+    /// Drained before every LLM call; the default `None` takes no mid-run input.
+    fn user_input_mut(&mut self) -> Option<&mut UserInputQueue> {
+        None
+    }
+
+    /// Tracer receiving session, iteration, API call and tool call spans.
+    ///
+    /// The default disabled tracer records nothing.
+    fn tracer(&self) -> Tracer {
+        Tracer::disabled()
+    }
+
+    /// Path the metrics registry is written to when a run ends, in the
+    /// Prometheus textfile format.
+    ///
+    /// The default `None` leaves metrics in memory (and on the HTTP endpoint
+    /// when one is configured).
+    fn metrics_textfile(&self) -> Option<std::path::PathBuf> {
+        None
+    }
+
+    /// Approval gate consulted before each tool call.
+    ///
+    /// Return `Some` when the active mode requires approval (`auto_execute = false`).
+    /// The default `None` executes every tool call without review, unless
+    /// [`tool_approval_required`](Self::tool_approval_required) says otherwise.
+    fn approval_gate_mut(&mut self) -> Option<&mut ApprovalGate> {
+        None
+    }
+
+    /// Whether the active mode requires approval before tool execution.
+    ///
+    /// When this is `true` but no approval gate is available, every tool
+    /// call is denied instead of running unreviewed.
+    fn tool_approval_required(&self) -> bool {
+        false
+    }
+
+    /// Token usage and estimated cost accumulated this session.
+    fn usage_totals(&self) -> UsageTotals {
+        UsageTotals::default()
+    }
+
+    /// Budget guard checked before every LLM call.
+    ///
+    /// The default `None` only bounds runs by `max_iterations`.
+    fn budget_guard_mut(&mut self) -> Option<&mut BudgetGuard> {
+        None
+    }
+
+    /// Approver asked whether to continue past a budget limit with
+    /// [`BudgetAction::Approve`]. Consulted regardless of the active mode;
+    /// `None` stops the run.
+    fn budget_approver(&self) -> Option<SharedApprover> {
+        None
+    }
+
+    /// Send subsequent LLM calls to the utility model.
+    ///
+    /// Returns `false` when no utility model is configured, which stops
+    /// the run instead.
+    fn switch_to_utility_model(&mut self) -> bool {
+        false
+    }
     
     // Lifecycle/templates
     async fn load_template(&self, name: &str) -> Result<String>;
@@ -136,6 +255,19 @@ pub async fn run_workflow<A: AgentContext>(agent: &mut A, max_iterations: u32, c
         return Ok("Agent session not started. Call start_session() first.".to_string());
     }
 
+    let span = start_session_span(agent, false);
+    let usage_before = agent.usage_totals();
+    let result = run_workflow_loop(agent, max_iterations, cancel_token).await;
+    end_session_span(agent, span, &usage_before, &result);
+    write_metrics_textfile(agent);
+    agent.tracer().flush().await;
+    result
+}
+
+/// Iteration loop of [`run_workflow`].
+async fn run_workflow_loop<A: AgentContext>(agent: &mut A, max_iterations: u32, cancel_token: Option<CancellationToken>) -> Result<String> {
+    let tracer = agent.tracer();
+
     // Ensure conversation turn exists
     if agent.get_current_turn_id().is_none() {
         let turn_id = agent.start_conversation_turn();
@@ -152,7 +284,12 @@ pub async fn run_workflow<A: AgentContext>(agent: &mut A, max_iterations: u32, c
                 return stop_session(agent, "Cancelled by user").await;
             }
         }
+        if let Some(reason) = enforce_budget(agent).await {
+            return stop_session(agent, &reason).await;
+        }
         agent.set_current_iteration(iteration);
+        let mut iteration_span = tracer.span(SpanKind::Iteration, "iteration");
+        iteration_span.set_attribute("iteration", iteration);
 
         // Log iteration
         let context_tokens = agent.count_tokens();
@@ -168,10 +305,17 @@ pub async fn run_workflow<A: AgentContext>(agent: &mut A, max_iterations: u32, c
             send_checkpoint_resume_info(agent).await;
         }
 
+        inject_user_messages(agent);
+
+        // Summarize older turns before dropping anything by count
+        compact_context(agent).await;
+
         // Limit history
         let max_history = agent.max_history();
         agent.chat_formatter_mut().limit_history(max_history);
 
+        inject_context(agent).await;
+
         // Validate messages
         if !agent.validate_messages() {
             return Err(anyhow::anyhow!("Invalid message structure detected"));
@@ -202,6 +346,10 @@ pub async fn run_workflow<A: AgentContext>(agent: &mut A, max_iterations: u32, c
                 // streaming_enabled: false because we're inside the non-streaming
                 // run_workflow; LlmResponse event IS needed here.
                 handle_content_response(agent, response_text, reasoning, false).await?;
+                // Messages sent while the answer was generated reopen the task
+                if inject_user_messages(agent) > 0 {
+                    continue;
+                }
                 return stop_session(agent, "Task completed").await;
             }
         }
@@ -220,6 +368,19 @@ pub async fn run_workflow_streaming<A: AgentContext>(agent: &mut A, max_iteratio
         return run_workflow(agent, max_iterations, cancel_token).await;
     }
 
+    let span = start_session_span(agent, true);
+    let usage_before = agent.usage_totals();
+    let result = run_streaming_loop(agent, max_iterations, cancel_token).await;
+    end_session_span(agent, span, &usage_before, &result);
+    write_metrics_textfile(agent);
+    agent.tracer().flush().await;
+    result
+}
+
+/// Streaming loop of [`run_workflow_streaming`].
+async fn run_streaming_loop<A: AgentContext>(agent: &mut A, max_iterations: u32, cancel_token: Option<CancellationToken>) -> Result<String> {
+    let tracer = agent.tracer();
+
     agent.output_sink().emit(OutputEvent::WorkflowStarted {
         task_description: agent.initial_task_description().to_string(),
     });
@@ -235,6 +396,11 @@ pub async fn run_workflow_streaming<A: AgentContext>(agent: &mut A, max_iteratio
                 return stop_session(agent, "Cancelled by user").await;
             }
         }
+        if let Some(reason) = enforce_budget(agent).await {
+            return stop_session(agent, &reason).await;
+        }
+        let mut iteration_span = tracer.span(SpanKind::Iteration, "iteration");
+        iteration_span.set_attribute("iteration", agent.current_iteration());
 
         // Checkpoint
         if agent.should_checkpoint() {
@@ -246,7 +412,12 @@ pub async fn run_workflow_streaming<A: AgentContext>(agent: &mut A, max_iteratio
             send_checkpoint_resume_info(agent).await;
         }
 
+        inject_user_messages(agent);
+        compact_context(agent).await;
+        inject_context(agent).await;
+
         // Get tools
+        agent.refresh_tool_sources().await;
         let tools = get_tools_for_call(agent);
 
         // Log API call
@@ -274,7 +445,11 @@ pub async fn run_workflow_streaming<A: AgentContext>(agent: &mut A, max_iteratio
 
         // Make streaming API call
         let max_tokens = agent.max_tokens();
-        match agent.generate_with_provider(tools, max_tokens, true).await {
+        let call_span = start_api_call_span(agent, &tracer, true, tool_count, msg_tokens + tool_tokens);
+        let usage_before = agent.usage_totals();
+        let generated = agent.generate_with_provider(tools, max_tokens, true).await;
+        end_api_call_span(agent, call_span, &usage_before, &generated);
+        match generated {
             Ok(result) => {
                 stream_retry_count = 0; // Reset on success
                 agent.output_sink().emit(OutputEvent::Info {
@@ -303,6 +478,10 @@ pub async fn run_workflow_streaming<A: AgentContext>(agent: &mut A, max_iteratio
                         // LlmResponse event (the full text was already streamed
                         // chunk-by-chunk via StreamingChunk events in generate_with_provider).
                         handle_content_response(agent, response_text, reasoning, true).await?;
+                        // Messages sent while the answer streamed reopen the task
+                        if inject_user_messages(agent) > 0 {
+                            continue;
+                        }
                         return stop_session(agent, "Task completed").await;
                     }
                 }
@@ -351,9 +530,287 @@ pub async fn run_workflow_streaming<A: AgentContext>(agent: &mut A, max_iteratio
     }
 }
 
+/// Start the span covering one workflow run.
+fn start_session_span<A: AgentContext>(agent: &A, streaming: bool) -> Span {
+    let mut span = agent.tracer().span(SpanKind::Session, "session");
+    span.set_attribute("llm.provider", agent.provider_name());
+    span.set_attribute("llm.model", agent.default_model());
+    span.set_attribute("llm.streaming", streaming);
+    span.set_attribute("session.start_iteration", agent.current_iteration());
+    span
+}
+
+/// End the session span with the run's outcome and token usage.
+fn end_session_span<A: AgentContext>(agent: &A, mut span: Span, usage_before: &UsageTotals, result: &Result<String>) {
+    let usage = agent.usage_totals();
+    span.set_attribute("session.iterations", agent.current_iteration());
+    span.set_attribute("session.api_calls", usage.api_calls.saturating_sub(usage_before.api_calls));
+    set_token_attributes(&mut span, usage_before, &usage);
+    if let Some(cost) = usage.estimated_cost {
+        span.set_attribute("llm.estimated_cost_usd", cost - usage_before.estimated_cost.unwrap_or(0.0));
+    }
+    match result {
+        Ok(outcome) => {
+            span.set_attribute("session.outcome", outcome.as_str());
+            span.set_ok();
+        }
+        Err(e) => span.set_error(format!("{:#}", e)),
+    }
+    span.end();
+}
+
+/// Dump the metrics registry to the agent's textfile, if it has one.
+fn write_metrics_textfile<A: AgentContext>(agent: &A) {
+    if let Some(path) = agent.metrics_textfile() {
+        if let Err(e) = metrics::global().write_textfile(&path) {
+            agent.log_info(&format!("⚠️ Failed to write metrics textfile: {:#}", e));
+        }
+    }
+}
+
+/// Start the span for one LLM API call.
+fn start_api_call_span<A: AgentContext>(
+    agent: &A,
+    tracer: &Tracer,
+    streaming: bool,
+    tool_count: usize,
+    context_tokens: usize,
+) -> Span {
+    let mut span = tracer.span(SpanKind::ApiCall, "api_call");
+    span.set_attribute("llm.provider", agent.provider_name());
+    span.set_attribute("llm.model", agent.default_model());
+    span.set_attribute("llm.streaming", streaming);
+    span.set_attribute("llm.call_number", agent.api_call_count());
+    span.set_attribute("llm.tool_count", tool_count);
+    span.set_attribute("llm.context_tokens", context_tokens);
+    span
+}
+
+/// End an API call span with the tokens the provider reported for it, and
+/// record the call in the metrics registry.
+fn end_api_call_span<A: AgentContext>(
+    agent: &A,
+    mut span: Span,
+    usage_before: &UsageTotals,
+    result: &Result<GenerateResult>,
+) {
+    set_token_attributes(&mut span, usage_before, &agent.usage_totals());
+    metrics::record_api_call(
+        &agent.provider_name(),
+        &agent.default_model(),
+        if result.is_ok() { "ok" } else { "error" },
+        span.elapsed(),
+    );
+    match result {
+        Ok(GenerateResult::ToolCalls { calls, .. }) => {
+            span.set_attribute("llm.tool_calls", calls.len());
+            span.set_ok();
+        }
+        Ok(GenerateResult::Content { .. }) => span.set_ok(),
+        Err(e) => span.set_error(format!("{:#}", e)),
+    }
+    span.end();
+}
+
+fn set_token_attributes(span: &mut Span, before: &UsageTotals, after: &UsageTotals) {
+    let prompt = after.usage.prompt_tokens.saturating_sub(before.usage.prompt_tokens);
+    let completion = after.usage.completion_tokens.saturating_sub(before.usage.completion_tokens);
+    span.set_attribute("llm.prompt_tokens", prompt);
+    span.set_attribute("llm.completion_tokens", completion);
+    span.set_attribute("llm.total_tokens", prompt + completion);
+}
+
+/// Record a finished tool call that started at `started`.
+fn record_tool_span(tracer: &Tracer, result: &ToolExecutionResult, started: std::time::Instant) {
+    let mut span = tracer.span(SpanKind::ToolCall, "tool_call");
+    span.set_started(started);
+    span.set_attribute("tool.name", result.tool_name.as_str());
+    span.set_attribute("tool.call_id", result.tool_call_id.as_str());
+    span.set_attribute("tool.success", result.success);
+    if result.success {
+        span.set_ok();
+    } else {
+        span.set_error(result.content.chars().take(500).collect::<String>());
+    }
+    span.end();
+}
+
+/// Apply `[execution.budget]` before the next LLM call.
+///
+/// Returns the stop reason when a reached limit ends the run. Limits the
+/// workflow continues past are raised to their next multiple, so they are
+/// checked again as usage grows.
+async fn enforce_budget<A: AgentContext>(agent: &mut A) -> Option<String> {
+    let totals = agent.usage_totals();
+    if let Some(warning) = agent.budget_guard_mut()?.unreported_usage_warning(&totals) {
+        crate::observability::tee_eprintln(&format!("Warning: {}", warning));
+    }
+    loop {
+        let overrun = agent.budget_guard_mut()?.check(&totals)?;
+        agent.output_sink().emit(OutputEvent::BudgetExceeded {
+            limit: overrun.limit,
+            used: overrun.used,
+            maximum: overrun.maximum,
+            action: overrun.action,
+        });
+        agent.log_info(&format!("💸 {} → {}", overrun, overrun.action));
+
+        let continued = match overrun.action {
+            BudgetAction::Stop => false,
+            BudgetAction::Approve => approve_budget_overrun(agent, &overrun).await,
+            BudgetAction::UtilityModel => {
+                let switched = agent.switch_to_utility_model();
+                if switched {
+                    agent.log_info(&format!("🔀 Continuing on utility model {}", agent.default_model()));
+                }
+                switched
+            }
+        };
+        if !continued {
+            return Some(format!("Budget exceeded: {}", overrun));
+        }
+        agent.budget_guard_mut()?.extend(&overrun);
+    }
+}
+
+/// Ask the budget approver whether to continue past `overrun`.
+///
+/// The request is reported as a call to the pseudo-tool `budget` whose
+/// arguments describe the limit. Anything but a denial continues.
+async fn approve_budget_overrun<A: AgentContext>(agent: &mut A, overrun: &BudgetOverrun) -> bool {
+    let Some(approver) = agent.budget_approver() else {
+        return false;
+    };
+    let request = ApprovalRequest {
+        request_id: uuid::Uuid::new_v4().to_string(),
+        tool_call_id: String::new(),
+        tool_name: "budget".to_string(),
+        arguments: serde_json::json!({
+            "limit": overrun.limit.to_string(),
+            "used": overrun.used,
+            "maximum": overrun.maximum,
+        })
+        .to_string(),
+    };
+    !matches!(approver.review(&request).await, ApprovalDecision::Deny { .. })
+}
+
+/// Replace older turns with a summary when the context passed the compaction threshold.
+///
+/// Failures are logged and leave the history unchanged; compaction is then
+/// skipped until the context has grown by another retry step.
+async fn compact_context<A: AgentContext>(agent: &mut A) {
+    let context_tokens = agent.count_tokens();
+    let compactor = match agent.context_compactor_mut() {
+        Some(compactor) if compactor.should_compact(context_tokens) => compactor.clone(),
+        _ => return,
+    };
+
+    // Work on a copy so the provider can be borrowed while summarizing
+    let mut formatter = agent.chat_formatter_mut().clone();
+    match compactor.compact(agent.provider(), &mut formatter).await {
+        Ok(Some(outcome)) => {
+            if let Some(compactor) = agent.context_compactor_mut() {
+                compactor.record_success();
+            }
+            *agent.chat_formatter_mut() = formatter;
+            agent.output_sink().emit(OutputEvent::ContextCompacted {
+                summarized_messages: outcome.summarized_messages,
+                tokens_before: outcome.tokens_before,
+                tokens_after: outcome.tokens_after,
+            });
+            agent.log_info(&format!(
+                "🗜️ Context compacted: {} messages summarized ({} → {} tokens)",
+                outcome.summarized_messages, outcome.tokens_before, outcome.tokens_after
+            ));
+            agent.on_context_compacted(&outcome);
+        }
+        Ok(None) => {}
+        Err(e) => {
+            let retry_at = agent.context_compactor_mut().map(|c| c.record_failure(context_tokens));
+            let message = match retry_at {
+                Some(retry_at) => format!("Context compaction failed, retrying above {} tokens: {}", retry_at, e),
+                None => format!("Context compaction failed: {}", e),
+            };
+            let _ = agent.log_error(&message, None);
+        }
+    }
+}
+
+/// Append the messages the user queued since the last iteration to the
+/// conversation. Returns how many were added.
+fn inject_user_messages<A: AgentContext>(agent: &mut A) -> usize {
+    let messages = match agent.user_input_mut() {
+        Some(queue) => queue.drain(),
+        None => return 0,
+    };
+
+    for content in &messages {
+        agent.chat_formatter_mut().add_user_message(content.clone(), None);
+        agent.output_sink().emit(OutputEvent::UserMessageInjected { content: content.clone() });
+        agent.log_info(&format!("💬 User message injected: {}", content));
+    }
+    messages.len()
+}
+
+/// Messages of recent conversation passed to context providers.
+const CONTEXT_CONVERSATION_MESSAGES: usize = 10;
+
+/// Insert context from the configured providers when the injector is due.
+///
+/// Provider failures are logged; the call proceeds with whatever context
+/// the other providers returned.
+async fn inject_context<A: AgentContext>(agent: &mut A) {
+    let iteration = agent.current_iteration();
+    let injector = match agent.context_injector_mut() {
+        Some(injector) if injector.is_due(iteration) => injector.clone(),
+        _ => return,
+    };
+
+    let conversation = {
+        let messages = agent.chat_formatter_mut().get_messages();
+        transcript(&messages[messages.len().saturating_sub(CONTEXT_CONVERSATION_MESSAGES)..])
+    };
+    let request = ContextRequest {
+        task: agent.initial_task_description().to_string(),
+        conversation,
+        iteration,
+        working_directory: agent.working_dir().to_path_buf(),
+        max_tokens: injector.config().max_tokens,
+    };
+
+    let (blocks, errors) = injector.collect(&request).await;
+    for error in &errors {
+        let _ = agent.log_error(&format!("Context provider failed: {}", error), None);
+    }
+    if let Some(injector) = agent.context_injector_mut() {
+        injector.mark_injected(iteration);
+    }
+    // Keep the previous context rather than dropping it because of failures
+    if blocks.is_empty() && !errors.is_empty() {
+        return;
+    }
+
+    let selection = injector.select(blocks);
+    injector.apply(agent.chat_formatter_mut(), &selection.blocks);
+    if selection.blocks.is_empty() {
+        return;
+    }
+    let message = format!(
+        "📚 Injected {} context block(s) ({} tokens, {} dropped over budget)",
+        selection.blocks.len(),
+        selection.tokens,
+        selection.dropped
+    );
+    agent.output_sink().emit(OutputEvent::Info { message: message.clone() });
+    agent.log_info(&message);
+}
+
 /// Generate with retry logic
 async fn generate_with_retry<A: AgentContext>(agent: &mut A) -> Result<GenerateResult> {
     let mut last_error = None;
+    let tracer = agent.tracer();
+    agent.refresh_tool_sources().await;
     
     for attempt in 0..=agent.max_retries() {
         let tools = get_tools_for_call(agent);
@@ -384,7 +841,11 @@ async fn generate_with_retry<A: AgentContext>(agent: &mut A) -> Result<GenerateR
         ));
 
         // Call the agent's generate method
-        match agent.generate_with_provider(tools, max_tokens, streaming_enabled).await {
+        let call_span = start_api_call_span(agent, &tracer, streaming_enabled, tool_count, msg_tokens + tool_tokens);
+        let usage_before = agent.usage_totals();
+        let generated = agent.generate_with_provider(tools, max_tokens, streaming_enabled).await;
+        end_api_call_span(agent, call_span, &usage_before, &generated);
+        match generated {
             Ok(result) => return Ok(result),
             Err(e) => {
                 last_error = Some(e);
@@ -406,8 +867,16 @@ async fn handle_tool_calls<A: AgentContext>(
     reasoning: Option<String>,
     cancel_token: Option<&CancellationToken>,
 ) -> Result<()> {
-    let tool_names: Vec<String> = tool_calls.iter().map(|tc| tc.function.name.clone()).collect();
-    let hints: Vec<Option<String>> = tool_calls.iter().map(|tc| extract_hint(&tc.function.name, &tc.function.arguments)).collect();
+    // Ask for approval first so edited arguments are what gets recorded and executed
+    let (tool_calls, mut denied) = review_tool_calls(agent, tool_calls).await;
+    let approved: Vec<umf::ToolCall> = tool_calls
+        .iter()
+        .filter(|tc| !denied.contains_key(&tc.id))
+        .cloned()
+        .collect();
+
+    let tool_names: Vec<String> = approved.iter().map(|tc| tc.function.name.clone()).collect();
+    let hints: Vec<Option<String>> = approved.iter().map(|tc| extract_hint(&tc.function.name, &tc.function.arguments)).collect();
     agent.output_sink().emit(OutputEvent::ToolsExecuting {
         tool_names: tool_names.clone(),
         hints,
@@ -431,19 +900,40 @@ async fn handle_tool_calls<A: AgentContext>(
         agent.chat_formatter_mut().add_assistant_message_with_tool_calls(message_content, tool_calls.clone());
     }
 
-    // Execute tools
-    let results = agent.execute_tool_calls_structured(tool_calls).await?;
-
-    // Emit per-tool completion events
-    for result in &results {
-        agent.output_sink().emit(OutputEvent::ToolCompleted {
+    // Denied calls complete immediately; approved calls report as they finish.
+    // Tool spans and latency metrics run from each call's own start.
+    let sink = agent.output_sink().clone();
+    let tracer = agent.tracer();
+    let emit_completed = move |result: &ToolExecutionResult, started: std::time::Instant| {
+        sink.emit(OutputEvent::ToolCompleted {
             tool_name: result.tool_name.clone(),
             success: result.success,
             content: result.content.clone(),
             description: result.description.clone(),
         });
+        metrics::record_tool_execution(&result.tool_name, result.success, started.elapsed());
+        if tracer.is_enabled() {
+            record_tool_span(&tracer, result, started);
+        }
+    };
+    for tc in &tool_calls {
+        if let Some(result) = denied.get(&tc.id) {
+            emit_completed(result, std::time::Instant::now());
+        }
     }
 
+    // Execute approved tools, then merge denials back in the original call order
+    let executed = if approved.is_empty() {
+        Vec::new()
+    } else {
+        agent.execute_tool_calls_reporting(approved, &emit_completed).await?
+    };
+    let mut executed = executed.into_iter();
+    let results: Vec<ToolExecutionResult> = tool_calls
+        .iter()
+        .filter_map(|tc| denied.remove(&tc.id).or_else(|| executed.next()))
+        .collect();
+
     // Add tool messages to conversation history BEFORE checking cancellation.
     // This ensures that when ESC is pressed during a tool call (e.g. a file
     // write), the completed tool results are recorded in the conversation and
@@ -482,6 +972,91 @@ async fn handle_tool_calls<A: AgentContext>(
     Ok(())
 }
 
+/// Message returned to the model for tool calls that needed approval but
+/// had no approver to ask.
+const NO_APPROVER_MESSAGE: &str = "Tool call denied: the current mode requires approval before tools run, \
+but no approver is available to review it. The tool was not executed. Do not retry it; \
+continue without tools or tell the user to switch to a mode that runs tools automatically.";
+
+/// Run each tool call past the agent's approval gate.
+///
+/// Returns the tool calls with any edited arguments applied, plus synthetic
+/// failed results for denied calls keyed by tool call ID. Without a gate
+/// every call is approved unchanged, unless the mode requires approval, in
+/// which case every call is denied.
+async fn review_tool_calls<A: AgentContext>(
+    agent: &mut A,
+    tool_calls: Vec<umf::ToolCall>,
+) -> (Vec<umf::ToolCall>, HashMap<String, ToolExecutionResult>) {
+    let mut denied = HashMap::new();
+    if agent.approval_gate_mut().is_none() {
+        if agent.tool_approval_required() && !tool_calls.is_empty() {
+            agent.log_info(&format!("🚫 No tool approver available; denied {} tool call(s)", tool_calls.len()));
+            denied = deny_all(&tool_calls, NO_APPROVER_MESSAGE);
+        }
+        return (tool_calls, denied);
+    }
+
+    let mut reviewed = Vec::with_capacity(tool_calls.len());
+    for mut tc in tool_calls {
+        let request = ApprovalRequest {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            tool_call_id: tc.id.clone(),
+            tool_name: tc.function.name.clone(),
+            arguments: tc.function.arguments.clone(),
+        };
+        let decision = match agent.approval_gate_mut() {
+            Some(gate) => gate.review(request).await,
+            None => ApprovalDecision::Approve,
+        };
+
+        let denial = match decision {
+            ApprovalDecision::Approve | ApprovalDecision::ApproveForSession => None,
+            ApprovalDecision::Edit { arguments } => {
+                match serde_json::from_str::<serde_json::Value>(&arguments) {
+                    Ok(_) => {
+                        agent.log_info(&format!("✏️ Arguments for '{}' edited by user", tc.function.name));
+                        tc.function.arguments = arguments;
+                        None
+                    }
+                    Err(e) => Some(format!("Edited arguments are not valid JSON: {}", e)),
+                }
+            }
+            ApprovalDecision::Deny { reason } => Some(match reason {
+                Some(reason) => format!("Tool call denied by user: {}", reason),
+                None => "Tool call denied by user.".to_string(),
+            }),
+        };
+
+        if let Some(content) = denial {
+            agent.log_info(&format!("🚫 {} ({})", content, tc.function.name));
+            denied.insert(tc.id.clone(), denied_result(&tc, content));
+        }
+        reviewed.push(tc);
+    }
+
+    (reviewed, denied)
+}
+
+/// Failed result reporting a denied tool call to the model.
+fn denied_result(tc: &umf::ToolCall, content: impl Into<String>) -> ToolExecutionResult {
+    ToolExecutionResult {
+        tool_call_id: tc.id.clone(),
+        tool_name: tc.function.name.clone(),
+        content: content.into(),
+        success: false,
+        description: None,
+    }
+}
+
+/// Deny every tool call with the same message.
+fn deny_all(tool_calls: &[umf::ToolCall], content: &str) -> HashMap<String, ToolExecutionResult> {
+    tool_calls
+        .iter()
+        .map(|tc| (tc.id.clone(), denied_result(tc, content)))
+        .collect()
+}
+
 /// Send task-specific template after classification if not already sent
 async fn maybe_send_template<A: AgentContext>(agent: &mut A) -> Result<()> {
     // Only send if classification is done and template hasn't been sent yet
@@ -651,3 +1226,398 @@ fn extract_hint(name: &str, args_json: &str) -> Option<String> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn tool_call(id: &str, name: &str) -> umf::ToolCall {
+        umf::ToolCall {
+            id: id.to_string(),
+            r#type: "function".to_string(),
+            function: umf::FunctionCall {
+                name: name.to_string(),
+                arguments: "{}".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_deny_all_without_approver() {
+        let calls = vec![tool_call("call_1", "bash"), tool_call("call_2", "read")];
+        let denied = deny_all(&calls, NO_APPROVER_MESSAGE);
+
+        assert_eq!(denied.len(), 2);
+        for tc in &calls {
+            let result = &denied[&tc.id];
+            assert!(!result.success);
+            assert_eq!(result.tool_name, tc.function.name);
+            assert!(result.content.contains("no approver is available"));
+            assert!(result.content.contains("not executed"));
+        }
+    }
+
+    /// Minimal agent that sends its conversation to a provider and records
+    /// the tools it is asked to run.
+    struct TestAgent {
+        provider: Box<dyn crate::provider::LlmProvider>,
+        formatter: umf::chatml::ChatMLFormatter,
+        task: String,
+        running: bool,
+        iteration: u32,
+        api_calls: u32,
+        executed: Vec<String>,
+        turn_id: Option<String>,
+        sink: SharedSink,
+        user_input: Option<UserInputQueue>,
+    }
+
+    impl TestAgent {
+        fn new(provider: Box<dyn crate::provider::LlmProvider>, task: &str) -> Self {
+            let mut formatter = umf::chatml::ChatMLFormatter::new();
+            formatter.add_system_message("You fix bugs.".to_string(), None);
+            formatter.add_user_message(task.to_string(), None);
+            Self {
+                provider,
+                formatter,
+                task: task.to_string(),
+                running: true,
+                iteration: 1,
+                api_calls: 0,
+                executed: Vec::new(),
+                turn_id: None,
+                sink: super::super::output::noop_sink(),
+                user_input: None,
+            }
+        }
+    }
+
+    impl AgentContext for TestAgent {
+        fn is_running(&self) -> bool { self.running }
+        fn set_running(&mut self, running: bool) { self.running = running; }
+        fn current_iteration(&self) -> u32 { self.iteration }
+        fn set_current_iteration(&mut self, iteration: u32) { self.iteration = iteration; }
+        fn api_call_count(&self) -> u32 { self.api_calls }
+        fn increment_api_call_count(&mut self) { self.api_calls += 1; }
+        fn max_history(&self) -> usize { 100 }
+        fn max_tokens(&self) -> u32 { 1000 }
+        fn max_retries(&self) -> u32 { 0 }
+        fn request_interval_seconds(&self) -> Option<u64> { None }
+        fn enable_task_classification(&self) -> bool { false }
+        fn streaming_enabled(&self) -> bool { false }
+        fn chat_formatter_mut(&mut self) -> &mut umf::chatml::ChatMLFormatter { &mut self.formatter }
+        fn count_tokens(&self) -> usize { self.formatter.count_tokens() }
+        fn validate_messages(&self) -> bool { true }
+        fn to_openai_messages(&self) -> Vec<serde_json::Value> { Vec::new() }
+        fn provider(&self) -> &dyn crate::provider::LlmProvider { self.provider.as_ref() }
+        fn provider_name(&self) -> String { self.provider.provider_name().to_string() }
+        fn default_model(&self) -> String { self.provider.default_model() }
+
+        async fn generate_with_provider(
+            &mut self,
+            tools: Option<Vec<umf::Tool>>,
+            max_tokens: u32,
+            _streaming: bool,
+        ) -> Result<GenerateResult> {
+            use crate::provider::{ChatMLAdapter, GenerateConfig, GenerateResponse, ToolAdapter};
+            let config = GenerateConfig {
+                max_tokens: Some(max_tokens),
+                tools: tools.as_ref().map(|t| ToolAdapter::tools_to_internal(t)),
+                ..GenerateConfig::new()
+            };
+            let messages = ChatMLAdapter::to_internal(&self.formatter)?;
+            Ok(match self.provider.generate(messages, &config).await? {
+                GenerateResponse::Content { text, reasoning } => GenerateResult::Content { text, reasoning },
+                GenerateResponse::ToolCalls { calls, reasoning } => GenerateResult::ToolCalls {
+                    calls: calls
+                        .into_iter()
+                        .map(|inv| umf::ToolCall {
+                            id: inv.id,
+                            r#type: "function".to_string(),
+                            function: umf::FunctionCall {
+                                name: inv.name,
+                                arguments: inv.arguments.to_string(),
+                            },
+                        })
+                        .collect(),
+                    content: None,
+                    reasoning,
+                },
+            })
+        }
+
+        async fn execute_tool_calls_structured(&mut self, tool_calls: Vec<umf::ToolCall>) -> Result<Vec<ToolExecutionResult>> {
+            Ok(tool_calls
+                .into_iter()
+                .map(|tc| {
+                    self.executed.push(format!("{} {}", tc.function.name, tc.function.arguments));
+                    ToolExecutionResult {
+                        tool_call_id: tc.id,
+                        tool_name: tc.function.name,
+                        content: "fn main() {}".to_string(),
+                        success: true,
+                        description: None,
+                    }
+                })
+                .collect())
+        }
+
+        fn generate_assistant_content_for_tools(&self, _tool_calls: &[umf::ToolCall]) -> String { String::new() }
+        fn get_tool_schemas(&self) -> Vec<serde_json::Value> {
+            vec![serde_json::json!({
+                "type": "function",
+                "function": {"name": "read_file", "description": "Read a file", "parameters": {"type": "object"}}
+            })]
+        }
+        fn user_input_mut(&mut self) -> Option<&mut UserInputQueue> { self.user_input.as_mut() }
+        async fn load_template(&self, _name: &str) -> Result<String> { Ok(String::new()) }
+        async fn render_template(&self, template: &str, _variables: &[(String, String)]) -> Result<String> { Ok(template.to_string()) }
+        fn log_workflow_iteration(&self, _iteration: u32, _context: Option<&str>) -> Result<()> { Ok(()) }
+        fn log_llm_interaction(&self, _messages: &[serde_json::Value], _response: &str, _model: &str) -> Result<()> { Ok(()) }
+        fn log_llm_response(&self, _response: &str, _model: Option<&str>) -> Result<()> { Ok(()) }
+        fn log_error(&self, _message: &str, _context: Option<&str>) -> Result<()> { Ok(()) }
+        fn log_completion(&self, _reason: &str) -> Result<()> { Ok(()) }
+        fn log_info(&self, _message: &str) {}
+        fn log_tee(&self, _message: &str) {}
+        async fn format_error(&self, _error_type: &str, message: &str, _context: &HashMap<String, serde_json::Value>) -> Result<String> { Ok(message.to_string()) }
+        async fn create_workflow_checkpoint(&mut self, _iteration: u32) -> Result<()> { Ok(()) }
+        fn should_checkpoint(&self) -> bool { false }
+        async fn finalize_checkpoint_session(&mut self) -> Result<()> { Ok(()) }
+        fn classification_done(&self) -> bool { true }
+        fn set_classification_done(&mut self, _done: bool) {}
+        fn classified_task_type(&self) -> Option<String> { None }
+        fn set_classified_task_type(&mut self, _task_type: Option<String>) {}
+        fn template_sent(&self) -> bool { true }
+        fn set_template_sent(&mut self, _sent: bool) {}
+        fn initial_task_description(&self) -> &str { &self.task }
+        fn working_dir(&self) -> &std::path::Path { std::path::Path::new(".") }
+        fn get_current_turn_id(&self) -> Option<&String> { self.turn_id.as_ref() }
+        fn start_conversation_turn(&mut self) -> String {
+            self.turn_id = Some("turn-1".to_string());
+            "turn-1".to_string()
+        }
+        fn end_conversation_turn(&mut self) { self.turn_id = None; }
+        fn output_sink(&self) -> &SharedSink { &self.sink }
+        fn take_on_checkpoint_sender(&mut self) -> Option<tokio::sync::mpsc::UnboundedSender<Option<crate::cli::ResumeInfo>>> { None }
+        fn restore_on_checkpoint_sender(&mut self, _sender: Option<tokio::sync::mpsc::UnboundedSender<Option<crate::cli::ResumeInfo>>>) {}
+        async fn create_final_checkpoint_and_get_resume_info(&mut self) -> Option<crate::cli::ResumeInfo> { None }
+        fn parse_response(&self, _response: &str) -> (Option<String>, Option<String>, bool) { (None, None, false) }
+        fn extract_tool_calls(&self, _response: &str) -> Result<Vec<umf::ToolCall>> { Ok(Vec::new()) }
+    }
+
+    /// Sink that keeps every event it receives.
+    #[derive(Default)]
+    struct CaptureSink {
+        events: Mutex<Vec<OutputEvent>>,
+    }
+
+    impl super::super::output::OutputSink for CaptureSink {
+        fn emit(&self, event: OutputEvent) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    /// Provider that queues a user message while answering its first call
+    /// and records the user turns each call was sent.
+    ///
+    /// The first call asks to read a file when `tool_call_first` is set and
+    /// answers with content otherwise; later calls always answer.
+    struct SteeredProvider {
+        calls: std::sync::atomic::AtomicUsize,
+        tool_call_first: bool,
+        sender: super::super::user_input::UserInputSender,
+        message: &'static str,
+        user_turns: UserTurns,
+    }
+
+    /// User turns a provider was sent, per call.
+    type UserTurns = Arc<Mutex<Vec<Vec<String>>>>;
+
+    #[async_trait::async_trait]
+    impl crate::provider::LlmProvider for SteeredProvider {
+        async fn generate(
+            &self,
+            messages: Vec<crate::provider::InternalMessage>,
+            _config: &crate::provider::GenerateConfig,
+        ) -> Result<crate::provider::GenerateResponse> {
+            use crate::provider::{GenerateResponse, ToolInvocation};
+            let user_turns = messages
+                .iter()
+                .filter(|m| matches!(m.role, umf::MessageRole::User))
+                .filter_map(|m| match m.content {
+                    umf::MessageContent::Text(ref text) => Some(text.clone()),
+                    _ => None,
+                })
+                .collect();
+            self.user_turns.lock().unwrap().push(user_turns);
+
+            let call = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if call > 0 {
+                return Ok(GenerateResponse::Content { text: "Done.".to_string(), reasoning: None });
+            }
+            assert!(self.sender.send(self.message));
+            Ok(if self.tool_call_first {
+                GenerateResponse::ToolCalls {
+                    calls: vec![ToolInvocation {
+                        id: "call_1".to_string(),
+                        name: "read_file".to_string(),
+                        arguments: serde_json::json!({"path": "src/main.rs"}),
+                        provider_metadata: HashMap::new(),
+                    }],
+                    reasoning: None,
+                }
+            } else {
+                GenerateResponse::Content { text: "Fixed the bug.".to_string(), reasoning: None }
+            })
+        }
+
+        async fn generate_stream(
+            &self,
+            _messages: Vec<crate::provider::InternalMessage>,
+            _config: &crate::provider::GenerateConfig,
+        ) -> Result<crate::provider::StreamingResponse> {
+            anyhow::bail!("not used")
+        }
+
+        fn provider_name(&self) -> &str { "steered" }
+        fn default_model(&self) -> String { "steered-1".to_string() }
+    }
+
+    /// Agent whose provider queues `message` during the first call, with the
+    /// sink its events go to and the user turns its provider is sent.
+    fn steered_agent(tool_call_first: bool, message: &'static str) -> (TestAgent, Arc<CaptureSink>, UserTurns) {
+        let (queue, sender) = UserInputQueue::new();
+        let user_turns = UserTurns::default();
+        let provider = SteeredProvider {
+            calls: Default::default(),
+            tool_call_first,
+            sender,
+            message,
+            user_turns: user_turns.clone(),
+        };
+        let sink = Arc::new(CaptureSink::default());
+        let mut agent = TestAgent::new(Box::new(provider), "Fix the bug");
+        agent.sink = sink.clone();
+        agent.user_input = Some(queue);
+        (agent, sink, user_turns)
+    }
+
+    fn injected(sink: &CaptureSink) -> Vec<String> {
+        sink.events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|event| match event {
+                OutputEvent::UserMessageInjected { content } => Some(content.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_queued_message_reaches_the_next_llm_call() {
+        let (mut agent, sink, user_turns) = steered_agent(true, "use the v2 API instead");
+        let result = run_workflow(&mut agent, 5, None).await.unwrap();
+
+        assert_eq!(result, "Session completed: Task completed");
+        assert_eq!(agent.executed, vec![r#"read_file {"path":"src/main.rs"}"#]);
+        assert_eq!(
+            *user_turns.lock().unwrap(),
+            vec![
+                vec!["Fix the bug".to_string()],
+                vec!["Fix the bug".to_string(), "use the v2 API instead".to_string()],
+            ]
+        );
+        assert_eq!(injected(&sink), vec!["use the v2 API instead"]);
+    }
+
+    #[tokio::test]
+    async fn test_message_after_final_answer_reopens_the_loop() {
+        let (mut agent, sink, user_turns) = steered_agent(false, "also add a test");
+        let result = run_workflow(&mut agent, 5, None).await.unwrap();
+
+        assert_eq!(result, "Session completed: Task completed");
+        let turns = user_turns.lock().unwrap().clone();
+        assert_eq!(turns.len(), 2);
+        assert_eq!(turns[1], vec!["Fix the bug".to_string(), "also add a test".to_string()]);
+        assert_eq!(injected(&sink), vec!["also add a test"]);
+
+        // The streaming loop reopens the same way
+        let (mut agent, sink, user_turns) = steered_agent(false, "also add a test");
+        run_workflow_streaming(&mut agent, 5, None).await.unwrap();
+        assert_eq!(user_turns.lock().unwrap().len(), 2);
+        assert_eq!(injected(&sink), vec!["also add a test"]);
+    }
+
+    /// Live provider stand-in: asks to read a file, then finishes.
+    #[cfg(feature = "provider-replay")]
+    struct ScriptedProvider {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[cfg(feature = "provider-replay")]
+    #[async_trait::async_trait]
+    impl crate::provider::LlmProvider for ScriptedProvider {
+        async fn generate(
+            &self,
+            _messages: Vec<crate::provider::InternalMessage>,
+            _config: &crate::provider::GenerateConfig,
+        ) -> Result<crate::provider::GenerateResponse> {
+            use crate::provider::{GenerateResponse, ToolInvocation};
+            Ok(match self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+                0 => GenerateResponse::ToolCalls {
+                    calls: vec![ToolInvocation {
+                        id: "call_1".to_string(),
+                        name: "read_file".to_string(),
+                        arguments: serde_json::json!({"path": "src/main.rs"}),
+                        provider_metadata: HashMap::new(),
+                    }],
+                    reasoning: None,
+                },
+                _ => GenerateResponse::Content { text: "Fixed the bug.".to_string(), reasoning: None },
+            })
+        }
+
+        async fn generate_stream(
+            &self,
+            _messages: Vec<crate::provider::InternalMessage>,
+            _config: &crate::provider::GenerateConfig,
+        ) -> Result<crate::provider::StreamingResponse> {
+            anyhow::bail!("not used")
+        }
+
+        fn provider_name(&self) -> &str { "scripted" }
+        fn default_model(&self) -> String { "scripted-1".to_string() }
+    }
+
+    #[cfg(feature = "provider-replay")]
+    #[tokio::test]
+    async fn test_run_workflow_replays_recorded_session() {
+        use crate::provider::{RecordingProvider, ReplayMatch, ReplayProvider};
+
+        let dir = tempfile::tempdir().unwrap();
+        let cassette = dir.path().join("session.json");
+
+        // Record a live run
+        let live = ScriptedProvider { calls: Default::default() };
+        let recorder = RecordingProvider::new(Box::new(live), &cassette).unwrap();
+        let mut agent = TestAgent::new(Box::new(recorder), "Fix the bug (started 10:00)");
+        let recorded = run_workflow(&mut agent, 5, None).await.unwrap();
+        assert_eq!(recorded, "Session completed: Task completed");
+        assert_eq!(agent.executed, vec![r#"read_file {"path":"src/main.rs"}"#]);
+
+        // The prompt changed, so hash matching finds nothing
+        let replay = ReplayProvider::from_file(&cassette).unwrap();
+        let mut agent = TestAgent::new(Box::new(replay), "Fix the bug (started 11:00)");
+        assert!(run_workflow(&mut agent, 5, None).await.is_err());
+
+        // Sequential replay reproduces the run offline
+        let replay = ReplayProvider::from_file(&cassette).unwrap().with_match(ReplayMatch::Sequential);
+        let mut agent = TestAgent::new(Box::new(replay), "Fix the bug (started 11:00)");
+        let replayed = run_workflow(&mut agent, 5, None).await.unwrap();
+        assert_eq!(replayed, recorded);
+        assert_eq!(agent.executed, vec![r#"read_file {"path":"src/main.rs"}"#]);
+        assert_eq!(agent.provider_name(), "scripted");
+    }
+}
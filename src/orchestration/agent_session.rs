@@ -157,6 +157,7 @@ where
     is_running: bool,
     current_iteration: u32,
     api_call_count: u32,
+    usage_tracker: crate::provider::UsageTracker,
     
     // Classification state
     classification_done: bool,
@@ -248,6 +249,7 @@ where
             is_running: false,
             current_iteration: 1,
             api_call_count: 0,
+            usage_tracker: crate::provider::UsageTracker::default(),
             classification_done: false,
             classified_task_type: None,
             template_sent: false,
@@ -268,6 +270,17 @@ where
         self.template_provider = Some(provider);
         self
     }
+
+    /// Set the price table used to estimate session cost (optional)
+    pub fn with_price_table(mut self, prices: crate::provider::PriceTable) -> Self {
+        self.usage_tracker = crate::provider::UsageTracker::new(prices);
+        self
+    }
+
+    /// Token usage and estimated cost accumulated this session
+    pub fn token_usage(&self) -> &crate::provider::UsageTotals {
+        self.usage_tracker.totals()
+    }
     
     /// Start a session
     pub async fn start_session(
@@ -655,11 +668,14 @@ where
         } else {
             self.provider.generate(messages, &config).await?
         };
+        let model = self.provider.default_model();
+        self.usage_tracker.record(&model, self.provider.take_usage());
 
         match response {
             GenerateResponse::Content { text, reasoning } => Ok(GenerateResult::Content { text, reasoning }),
             GenerateResponse::ToolCalls { calls: invocations, reasoning } => {
                 let tool_calls = ToolAdapter::invocations_to_tool_calls(&invocations)?;
+                let reasoning = crate::provider::thinking::history_reasoning(reasoning, &invocations);
                 Ok(GenerateResult::ToolCalls { calls: tool_calls, content: None, reasoning })
             }
         }
@@ -707,18 +723,15 @@ where
                     provider_metadata: std::collections::HashMap::new(),
                 });
             }
+            let reasoning = crate::provider::thinking::detach(&accumulated.reasoning, &mut invocations);
             Ok(crate::provider::GenerateResponse::ToolCalls {
                 calls: invocations,
-                reasoning: if accumulated.reasoning.is_empty() { None } else { Some(accumulated.reasoning) },
+                reasoning,
             })
         } else {
             Ok(crate::provider::GenerateResponse::Content {
                 text: accumulated.text,
-                reasoning: if accumulated.reasoning.is_empty() {
-                    None
-                } else {
-                    Some(accumulated.reasoning)
-                },
+                reasoning: crate::provider::thinking::detach(&accumulated.reasoning, &mut []),
             })
         }
     }
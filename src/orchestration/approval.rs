@@ -0,0 +1,294 @@
+//! Tool approval - gate tool execution behind a user decision
+//!
+//! When the active mode has `auto_execute = false` (by default `confirm` and
+//! `human`), the orchestration loop asks a [`ToolApprover`] about every tool
+//! call before dispatching it. Approvers can be synchronous (a terminal
+//! prompt) or asynchronous (a TUI answering an
+//! [`OutputEvent::ApprovalRequested`] through an [`ApprovalResponder`]).
+
+use async_trait::async_trait;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+use super::output::{OutputEvent, SharedSink};
+
+/// A pending tool call awaiting a decision.
+#[derive(Debug, Clone)]
+pub struct ApprovalRequest {
+    /// Unique ID for this approval request
+    pub request_id: String,
+    /// ID of the tool call being reviewed
+    pub tool_call_id: String,
+    /// Name of the tool to execute
+    pub tool_name: String,
+    /// Tool arguments as a JSON string
+    pub arguments: String,
+}
+
+/// Decision returned by a [`ToolApprover`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApprovalDecision {
+    /// Execute this tool call
+    Approve,
+    /// Execute this tool call and every later call to the same tool in this session
+    ApproveForSession,
+    /// Skip this tool call; the reason is reported back to the model
+    Deny {
+        /// Optional explanation shown to the model
+        reason: Option<String>,
+    },
+    /// Execute this tool call with replacement arguments (JSON string)
+    Edit {
+        /// Replacement arguments
+        arguments: String,
+    },
+}
+
+/// Decides whether a tool call may run.
+///
+/// Implementations must be `Send + Sync` so a single approver can be shared
+/// between the workflow loop and the front-end answering requests.
+#[async_trait]
+pub trait ToolApprover: Send + Sync {
+    /// Review a single tool call.
+    async fn review(&self, request: &ApprovalRequest) -> ApprovalDecision;
+}
+
+/// Shared approver handle.
+pub type SharedApprover = Arc<dyn ToolApprover>;
+
+/// Approver that accepts every tool call.
+#[derive(Debug, Clone, Default)]
+pub struct AutoApprover;
+
+#[async_trait]
+impl ToolApprover for AutoApprover {
+    async fn review(&self, _request: &ApprovalRequest) -> ApprovalDecision {
+        ApprovalDecision::Approve
+    }
+}
+
+type PendingMap = Arc<Mutex<HashMap<String, oneshot::Sender<ApprovalDecision>>>>;
+
+/// Approver that publishes requests through an output sink and waits for
+/// an [`ApprovalResponder`] to answer them.
+///
+/// This is the approver to use from TUIs and web front-ends: the sink
+/// receives [`OutputEvent::ApprovalRequested`], and the UI calls
+/// [`ApprovalResponder::respond`] whenever the user makes a choice.
+pub struct ChannelApprover {
+    sink: SharedSink,
+    pending: PendingMap,
+}
+
+impl ChannelApprover {
+    /// Create an approver bound to `sink`, plus the responder used to answer it.
+    pub fn new(sink: SharedSink) -> (Self, ApprovalResponder) {
+        let pending: PendingMap = Arc::new(Mutex::new(HashMap::new()));
+        let responder = ApprovalResponder {
+            pending: pending.clone(),
+        };
+        (Self { sink, pending }, responder)
+    }
+}
+
+#[async_trait]
+impl ToolApprover for ChannelApprover {
+    async fn review(&self, request: &ApprovalRequest) -> ApprovalDecision {
+        let (tx, rx) = oneshot::channel();
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.insert(request.request_id.clone(), tx);
+        }
+
+        self.sink.emit(OutputEvent::ApprovalRequested {
+            request_id: request.request_id.clone(),
+            tool_call_id: request.tool_call_id.clone(),
+            tool_name: request.tool_name.clone(),
+            arguments: request.arguments.clone(),
+        });
+
+        match rx.await {
+            Ok(decision) => decision,
+            Err(_) => {
+                // Responder dropped or request discarded - never run unapproved tools
+                ApprovalDecision::Deny {
+                    reason: Some("Approval request was abandoned".to_string()),
+                }
+            }
+        }
+    }
+}
+
+/// Handle used by front-ends to answer [`ChannelApprover`] requests.
+#[derive(Clone)]
+pub struct ApprovalResponder {
+    pending: PendingMap,
+}
+
+impl ApprovalResponder {
+    /// Answer a pending request.
+    ///
+    /// Returns `false` if no request with `request_id` is waiting.
+    pub fn respond(&self, request_id: &str, decision: ApprovalDecision) -> bool {
+        let sender = self
+            .pending
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.remove(request_id));
+        match sender {
+            Some(tx) => tx.send(decision).is_ok(),
+            None => false,
+        }
+    }
+
+    /// IDs of requests still waiting for an answer.
+    pub fn pending_requests(&self) -> Vec<String> {
+        self.pending
+            .lock()
+            .map(|pending| pending.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Approval state owned by an agent: the approver plus tools the user
+/// approved for the rest of the session.
+pub struct ApprovalGate {
+    approver: SharedApprover,
+    session_approved: HashSet<String>,
+}
+
+impl ApprovalGate {
+    /// Create a gate around an approver.
+    pub fn new(approver: SharedApprover) -> Self {
+        Self {
+            approver,
+            session_approved: HashSet::new(),
+        }
+    }
+
+    /// Review a tool call, short-circuiting tools already approved for the session.
+    pub async fn review(&mut self, request: ApprovalRequest) -> ApprovalDecision {
+        if self.session_approved.contains(&request.tool_name) {
+            return ApprovalDecision::Approve;
+        }
+        let decision = self.approver.review(&request).await;
+        if decision == ApprovalDecision::ApproveForSession {
+            self.session_approved.insert(request.tool_name);
+        }
+        decision
+    }
+
+    /// The underlying approver, for decisions that bypass session approvals.
+    pub fn approver(&self) -> &SharedApprover {
+        &self.approver
+    }
+
+    /// Check whether a tool was approved for the rest of the session.
+    pub fn is_approved_for_session(&self, tool_name: &str) -> bool {
+        self.session_approved.contains(tool_name)
+    }
+
+    /// Forget all session-wide approvals.
+    pub fn reset_session(&mut self) {
+        self.session_approved.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orchestration::output::OutputSink;
+
+    struct CaptureSink {
+        events: Mutex<Vec<OutputEvent>>,
+    }
+
+    impl OutputSink for CaptureSink {
+        fn emit(&self, event: OutputEvent) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    fn request(id: &str, tool: &str) -> ApprovalRequest {
+        ApprovalRequest {
+            request_id: id.to_string(),
+            tool_call_id: format!("call_{}", id),
+            tool_name: tool.to_string(),
+            arguments: "{}".to_string(),
+        }
+    }
+
+    struct CountingApprover {
+        calls: Mutex<u32>,
+        decision: ApprovalDecision,
+    }
+
+    #[async_trait]
+    impl ToolApprover for CountingApprover {
+        async fn review(&self, _request: &ApprovalRequest) -> ApprovalDecision {
+            *self.calls.lock().unwrap() += 1;
+            self.decision.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gate_remembers_session_approval() {
+        let approver = Arc::new(CountingApprover {
+            calls: Mutex::new(0),
+            decision: ApprovalDecision::ApproveForSession,
+        });
+        let mut gate = ApprovalGate::new(approver.clone());
+
+        assert_eq!(gate.review(request("1", "bash")).await, ApprovalDecision::ApproveForSession);
+        assert_eq!(gate.review(request("2", "bash")).await, ApprovalDecision::Approve);
+        assert!(gate.is_approved_for_session("bash"));
+        assert_eq!(*approver.calls.lock().unwrap(), 1);
+
+        gate.reset_session();
+        assert!(!gate.is_approved_for_session("bash"));
+    }
+
+    #[tokio::test]
+    async fn test_channel_approver_round_trip() {
+        let sink = Arc::new(CaptureSink { events: Mutex::new(Vec::new()) });
+        let (approver, responder) = ChannelApprover::new(sink.clone());
+
+        let handle = tokio::spawn(async move { approver.review(&request("r1", "write")).await });
+
+        // Wait until the request is registered
+        while responder.pending_requests().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        assert!(!responder.respond("missing", ApprovalDecision::Approve));
+        assert!(responder.respond(
+            "r1",
+            ApprovalDecision::Deny { reason: Some("no".to_string()) }
+        ));
+
+        let decision = handle.await.unwrap();
+        assert_eq!(decision, ApprovalDecision::Deny { reason: Some("no".to_string()) });
+
+        let events = sink.events.lock().unwrap();
+        assert!(matches!(
+            &events[0],
+            OutputEvent::ApprovalRequested { request_id, tool_name, .. }
+                if request_id == "r1" && tool_name == "write"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_channel_approver_denies_when_responder_dropped() {
+        let sink = Arc::new(CaptureSink { events: Mutex::new(Vec::new()) });
+        let (approver, responder) = ChannelApprover::new(sink);
+
+        let pending = responder.pending.clone();
+        let handle = tokio::spawn(async move { approver.review(&request("r2", "bash")).await });
+        while responder.pending_requests().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        pending.lock().unwrap().clear();
+
+        assert!(matches!(handle.await.unwrap(), ApprovalDecision::Deny { .. }));
+    }
+}
@@ -0,0 +1,274 @@
+//! Budget guards - hard token, cost and wall-clock ceilings for a session
+//!
+//! `max_iterations` bounds how many LLM calls a run makes, but not what they
+//! cost. A [`BudgetGuard`] built from `[execution.budget]` is checked before
+//! every LLM call; once a limit is reached the workflow takes the configured
+//! [`BudgetAction`]: stop with a final checkpoint, ask the approver whether
+//! to continue, or carry on with the utility model.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::config::{BudgetAction, BudgetConfig};
+use crate::provider::UsageTotals;
+
+/// A budget dimension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BudgetLimit {
+    /// Prompt + completion tokens (`max_total_tokens`)
+    Tokens,
+    /// Estimated USD spend (`max_cost_usd`)
+    Cost,
+    /// Wall-clock seconds (`max_duration_seconds`)
+    Duration,
+}
+
+impl BudgetLimit {
+    fn format_amount(&self, amount: f64) -> String {
+        match self {
+            Self::Tokens => format!("{} tokens", amount as u64),
+            Self::Cost => format!("${:.4}", amount),
+            Self::Duration => format!("{}s", amount as u64),
+        }
+    }
+}
+
+impl fmt::Display for BudgetLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tokens => write!(f, "token"),
+            Self::Cost => write!(f, "cost"),
+            Self::Duration => write!(f, "time"),
+        }
+    }
+}
+
+/// A limit that was reached, with the action configured for it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetOverrun {
+    /// Which limit was reached
+    pub limit: BudgetLimit,
+    /// Amount used so far (tokens, USD or seconds)
+    pub used: f64,
+    /// Configured maximum, in the same unit
+    pub maximum: f64,
+    /// What the workflow should do about it
+    pub action: BudgetAction,
+}
+
+impl fmt::Display for BudgetOverrun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} budget reached ({} of {})",
+            self.limit,
+            self.limit.format_amount(self.used),
+            self.limit.format_amount(self.maximum)
+        )
+    }
+}
+
+/// Checks session usage against `[execution.budget]`.
+///
+/// The wall clock starts at the first [`check`](Self::check). A limit the
+/// workflow decided to continue past is [`extend`](Self::extend)ed to the
+/// next multiple of its configured maximum, so it trips again later.
+#[derive(Debug, Clone)]
+pub struct BudgetGuard {
+    config: BudgetConfig,
+    started: Option<Instant>,
+    raised: HashMap<BudgetLimit, f64>,
+    warned_unreported: bool,
+}
+
+impl BudgetGuard {
+    /// Create a guard from `[execution.budget]`.
+    pub fn new(config: BudgetConfig) -> Self {
+        Self {
+            config,
+            started: None,
+            raised: HashMap::new(),
+            warned_unreported: false,
+        }
+    }
+
+    /// Time since the first check.
+    pub fn elapsed(&self) -> Duration {
+        self.started.map(|s| s.elapsed()).unwrap_or_default()
+    }
+
+    /// Return the first reached limit, if any.
+    pub fn check(&mut self, totals: &UsageTotals) -> Option<BudgetOverrun> {
+        let started = *self.started.get_or_insert_with(Instant::now);
+        let elapsed = started.elapsed().as_secs_f64();
+
+        let candidates = [
+            (
+                BudgetLimit::Tokens,
+                totals.usage.total_tokens() as f64,
+                self.config.max_total_tokens.map(|m| m as f64),
+                self.config.on_tokens_exceeded,
+            ),
+            (
+                BudgetLimit::Cost,
+                totals.estimated_cost.unwrap_or(0.0),
+                self.config.max_cost_usd,
+                self.config.on_cost_exceeded,
+            ),
+            (
+                BudgetLimit::Duration,
+                elapsed,
+                self.config.max_duration_seconds.map(|m| m as f64),
+                self.config.on_duration_exceeded,
+            ),
+        ];
+
+        candidates
+            .into_iter()
+            .find_map(|(limit, used, maximum, action)| {
+                let maximum = self.raised.get(&limit).copied().or(maximum)?;
+                (used >= maximum).then(|| BudgetOverrun {
+                    limit,
+                    used,
+                    maximum,
+                    action: action.unwrap_or(self.config.on_exceeded),
+                })
+            })
+    }
+
+    /// Warn once when a token or cost limit is set but some calls reported
+    /// no usage, since those limits then undercount.
+    pub fn unreported_usage_warning(&mut self, totals: &UsageTotals) -> Option<String> {
+        let usage_limited = self.config.max_total_tokens.is_some() || self.config.max_cost_usd.is_some();
+        if self.warned_unreported || !usage_limited || totals.unreported_calls == 0 {
+            return None;
+        }
+        self.warned_unreported = true;
+        Some(format!(
+            "{} of {} LLM calls reported no token usage; token and cost budgets may be exceeded unnoticed",
+            totals.unreported_calls, totals.api_calls
+        ))
+    }
+
+    /// Continue past `overrun`: raise its limit to the next multiple of the
+    /// configured maximum above the amount used.
+    ///
+    /// A zero maximum has no multiples and stops being enforced.
+    pub fn extend(&mut self, overrun: &BudgetOverrun) {
+        let step = match overrun.limit {
+            BudgetLimit::Tokens => self.config.max_total_tokens.map(|m| m as f64),
+            BudgetLimit::Cost => self.config.max_cost_usd,
+            BudgetLimit::Duration => self.config.max_duration_seconds.map(|m| m as f64),
+        }
+        .unwrap_or(overrun.maximum);
+        let next = if step > 0.0 {
+            ((overrun.used / step).floor() + 1.0) * step
+        } else {
+            f64::INFINITY
+        };
+        self.raised.insert(overrun.limit, next);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::TokenUsage;
+
+    fn totals(tokens: u64, cost: Option<f64>) -> UsageTotals {
+        UsageTotals {
+            usage: TokenUsage {
+                prompt_tokens: tokens,
+                ..Default::default()
+            },
+            api_calls: 1,
+            estimated_cost: cost,
+            unreported_calls: 0,
+        }
+    }
+
+    #[test]
+    fn test_limits_use_their_own_action() {
+        let mut guard = BudgetGuard::new(BudgetConfig {
+            max_total_tokens: Some(1000),
+            max_cost_usd: Some(0.5),
+            on_exceeded: BudgetAction::Approve,
+            on_cost_exceeded: Some(BudgetAction::UtilityModel),
+            ..Default::default()
+        });
+
+        assert!(guard.check(&totals(999, Some(0.1))).is_none());
+
+        let overrun = guard.check(&totals(1000, Some(0.6))).unwrap();
+        assert_eq!(overrun.limit, BudgetLimit::Tokens);
+        assert_eq!(overrun.action, BudgetAction::Approve);
+        assert_eq!(overrun.to_string(), "token budget reached (1000 tokens of 1000 tokens)");
+
+        guard.extend(&overrun);
+        let overrun = guard.check(&totals(1000, Some(0.6))).unwrap();
+        assert_eq!(overrun.limit, BudgetLimit::Cost);
+        assert_eq!(overrun.action, BudgetAction::UtilityModel);
+
+        guard.extend(&overrun);
+        assert!(guard.check(&totals(1999, Some(0.9))).is_none());
+    }
+
+    #[test]
+    fn test_extended_limits_trip_again() {
+        let mut guard = BudgetGuard::new(BudgetConfig {
+            max_total_tokens: Some(1000),
+            on_exceeded: BudgetAction::Approve,
+            ..Default::default()
+        });
+
+        let overrun = guard.check(&totals(1200, None)).unwrap();
+        guard.extend(&overrun);
+        assert!(guard.check(&totals(1999, None)).is_none());
+
+        let overrun = guard.check(&totals(2000, None)).unwrap();
+        assert_eq!(overrun.maximum, 2000.0);
+        assert_eq!(overrun.to_string(), "token budget reached (2000 tokens of 2000 tokens)");
+
+        // A large jump skips straight past the intermediate multiples
+        let overrun = guard.check(&totals(4500, None)).unwrap();
+        guard.extend(&overrun);
+        assert!(guard.check(&totals(4999, None)).is_none());
+        assert_eq!(guard.check(&totals(5000, None)).unwrap().maximum, 5000.0);
+    }
+
+    #[test]
+    fn test_duration_and_unpriced_models() {
+        let mut guard = BudgetGuard::new(BudgetConfig {
+            max_cost_usd: Some(0.01),
+            max_duration_seconds: Some(0),
+            ..Default::default()
+        });
+
+        // No pricing means no cost, so only the clock trips
+        let overrun = guard.check(&totals(1_000_000, None)).unwrap();
+        assert_eq!(overrun.limit, BudgetLimit::Duration);
+        assert_eq!(overrun.action, BudgetAction::Stop);
+    }
+
+    #[test]
+    fn test_warns_once_about_unreported_usage() {
+        let mut unreported = totals(0, None);
+        unreported.unreported_calls = 1;
+
+        let mut guard = BudgetGuard::new(BudgetConfig {
+            max_duration_seconds: Some(60),
+            ..Default::default()
+        });
+        assert!(guard.unreported_usage_warning(&unreported).is_none());
+
+        let mut guard = BudgetGuard::new(BudgetConfig {
+            max_total_tokens: Some(1000),
+            ..Default::default()
+        });
+        assert!(guard.unreported_usage_warning(&totals(10, None)).is_none());
+        let warning = guard.unreported_usage_warning(&unreported).unwrap();
+        assert!(warning.starts_with("1 of 1 LLM calls reported no token usage"));
+        assert!(guard.unreported_usage_warning(&unreported).is_none());
+    }
+}
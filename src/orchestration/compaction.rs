@@ -0,0 +1,423 @@
+//! Context compaction - summarize older turns instead of dropping them
+//!
+//! `ChatMLFormatter::limit_history` keeps the conversation bounded by
+//! discarding old messages, which on long runs loses the task context. A
+//! [`ContextCompactor`] instead replaces the older part of the history with
+//! an LLM-written summary once the conversation passes a token threshold:
+//!
+//! ```text
+//! [system] [task] [m1 .. mN] [recent messages]
+//!     -> [system] [task] [summary of m1 .. mN] [recent messages]
+//! ```
+//!
+//! The summary is merged into the neighbouring user turn, so the history
+//! never holds two user messages in a row. The split point never separates
+//! an assistant tool call from its tool results, so the compacted history
+//! remains valid for every provider.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use umf::chatml::{ChatMLFormatter, ChatMLMessage, MessageRole};
+
+use crate::config::{CompactionConfig, UtilityLlmConfig};
+use crate::provider::{GenerateConfig, GenerateResponse, InternalMessage, LlmProvider};
+
+/// Prefix of the user message that carries the summary.
+pub const SUMMARY_PREFIX: &str = "[Summary of earlier conversation]";
+
+/// Tool results longer than this are truncated in the summarization transcript.
+const MAX_TRANSCRIPT_RESULT_CHARS: usize = 2000;
+
+/// After a failed summarization, compaction is retried once the context grew
+/// by `trigger_tokens / RETRY_STEP_DIVISOR` tokens.
+const RETRY_STEP_DIVISOR: usize = 4;
+
+const SUMMARY_SYSTEM_PROMPT: &str = "You compress the history of a software engineering \
+agent's session so it can continue working with less context. Write a concise summary that \
+preserves: the overall task and any requirements the user stated, decisions made and why, \
+files read or modified (with paths), commands run and their important results, errors \
+encountered, and what remains to be done. Output only the summary.";
+
+/// Result of a compaction pass.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompactionOutcome {
+    /// Messages in the history before compaction
+    pub messages_before: usize,
+    /// Messages in the history after compaction
+    pub messages_after: usize,
+    /// Number of messages replaced by the summary
+    pub summarized_messages: usize,
+    /// Conversation tokens before compaction
+    pub tokens_before: usize,
+    /// Conversation tokens after compaction
+    pub tokens_after: usize,
+    /// The generated summary
+    pub summary: String,
+}
+
+/// Summarizes older turns with the utility model when the context grows too large.
+#[derive(Debug, Clone)]
+pub struct ContextCompactor {
+    config: CompactionConfig,
+    utility: Option<UtilityLlmConfig>,
+    /// Context size below which compaction is skipped after a failure
+    retry_at_tokens: Option<usize>,
+}
+
+impl ContextCompactor {
+    /// Create a compactor from `[execution.compaction]` and optional `[llm.utility]` settings.
+    pub fn new(config: CompactionConfig, utility: Option<UtilityLlmConfig>) -> Self {
+        Self { config, utility, retry_at_tokens: None }
+    }
+
+    /// Compaction settings.
+    pub fn config(&self) -> &CompactionConfig {
+        &self.config
+    }
+
+    /// Whether a conversation of `context_tokens` tokens should be compacted.
+    ///
+    /// After [`record_failure`](Self::record_failure) this stays `false`
+    /// until the context has grown by another retry step.
+    pub fn should_compact(&self, context_tokens: usize) -> bool {
+        self.config.enabled
+            && context_tokens > self.config.trigger_tokens
+            && !matches!(self.retry_at_tokens, Some(retry_at) if context_tokens < retry_at)
+    }
+
+    /// Back off after summarizing a context of `context_tokens` tokens failed.
+    ///
+    /// Returns the context size at which compaction is tried again.
+    pub fn record_failure(&mut self, context_tokens: usize) -> usize {
+        let step = (self.config.trigger_tokens / RETRY_STEP_DIVISOR).max(1);
+        let retry_at = context_tokens.saturating_add(step);
+        self.retry_at_tokens = Some(retry_at);
+        retry_at
+    }
+
+    /// Clear the back-off after a successful compaction.
+    pub fn record_success(&mut self) {
+        self.retry_at_tokens = None;
+    }
+
+    /// Range of `messages` to replace with a summary, or `None` if there is
+    /// nothing worth summarizing.
+    ///
+    /// Leading system messages and the first user message (the task) are
+    /// kept, as are the most recent `keep_recent_messages` messages. The end
+    /// of the range moves back so tool results stay with their tool call.
+    pub fn plan(&self, messages: &[ChatMLMessage]) -> Option<std::ops::Range<usize>> {
+        let mut start = messages
+            .iter()
+            .take_while(|m| m.role == MessageRole::System)
+            .count();
+        if messages.get(start).is_some_and(|m| m.role == MessageRole::User) {
+            start += 1;
+        }
+
+        let mut end = messages.len().saturating_sub(self.config.keep_recent_messages.max(1));
+        while end > start && messages[end].role == MessageRole::Tool {
+            end -= 1;
+        }
+
+        // A single message is not worth an LLM call
+        if end <= start + 1 {
+            None
+        } else {
+            Some(start..end)
+        }
+    }
+
+    /// Summarize `messages` with `provider`, using the utility model if configured.
+    pub async fn summarize(&self, provider: &dyn LlmProvider, messages: &[ChatMLMessage]) -> Result<String> {
+        let mut gen_config = GenerateConfig::new().with_max_tokens(self.config.summary_max_tokens);
+        if let Some(ref utility) = self.utility {
+            if let Some(ref model) = utility.model {
+                gen_config = gen_config.with_model(model);
+            }
+            gen_config = gen_config.with_temperature(utility.temperature);
+        } else {
+            gen_config = gen_config.with_temperature(0.3);
+        }
+        gen_config.enable_streaming = false;
+
+        let request = vec![
+            InternalMessage::system(SUMMARY_SYSTEM_PROMPT),
+            InternalMessage::user(transcript(messages)),
+        ];
+
+        match provider.generate(request, &gen_config).await? {
+            GenerateResponse::Content { text, reasoning } => {
+                // Thinking models may leave `text` empty and answer in `reasoning`
+                let summary = if text.trim().is_empty() { reasoning.unwrap_or_default() } else { text };
+                let summary = summary.trim().to_string();
+                if summary.is_empty() {
+                    Err(anyhow!("Summarization returned an empty response"))
+                } else {
+                    Ok(summary)
+                }
+            }
+            GenerateResponse::ToolCalls { .. } => Err(anyhow!("Summarization returned tool calls instead of text")),
+        }
+    }
+
+    /// Compact `formatter` in place if it exceeds the token threshold.
+    ///
+    /// Returns `Ok(None)` when no compaction was needed or possible.
+    pub async fn compact(
+        &self,
+        provider: &dyn LlmProvider,
+        formatter: &mut ChatMLFormatter,
+    ) -> Result<Option<CompactionOutcome>> {
+        let tokens_before = formatter.count_tokens();
+        if !self.should_compact(tokens_before) {
+            return Ok(None);
+        }
+        let messages = formatter.get_messages().clone();
+        let Some(range) = self.plan(&messages) else {
+            return Ok(None);
+        };
+
+        let summary = self.summarize(provider, &messages[range.clone()]).await?;
+        let outcome = apply_summary(formatter, &messages, range, summary);
+        Ok(Some(CompactionOutcome { tokens_before, ..outcome }))
+    }
+}
+
+/// Replace `messages[range]` with a summary and load the result into `formatter`.
+///
+/// The summary is prepended to the following user message, or appended to
+/// the preceding one (usually the task), and only becomes a message of its
+/// own when neither neighbour is a user message.
+pub(crate) fn apply_summary(
+    formatter: &mut ChatMLFormatter,
+    messages: &[ChatMLMessage],
+    range: std::ops::Range<usize>,
+    summary: String,
+) -> CompactionOutcome {
+    let summary_text = format!("{}\n\n{}", SUMMARY_PREFIX, summary);
+    let mut compacted = messages[..range.start].to_vec();
+    let mut recent = messages[range.end..].to_vec();
+    match recent.first_mut() {
+        Some(next) if next.role == MessageRole::User => {
+            next.content = format!("{}\n\n{}", summary_text, next.content);
+        }
+        _ => match compacted.last_mut() {
+            Some(prev) if prev.role == MessageRole::User => {
+                prev.content = format!("{}\n\n{}", prev.content, summary_text);
+            }
+            _ => compacted.push(ChatMLMessage::new(MessageRole::User, summary_text, None)),
+        },
+    }
+    compacted.extend(recent);
+
+    replace_messages(formatter, &compacted);
+
+    CompactionOutcome {
+        messages_before: messages.len(),
+        messages_after: compacted.len(),
+        summarized_messages: range.len(),
+        tokens_before: 0,
+        tokens_after: formatter.count_tokens(),
+        summary,
+    }
+}
+
+/// Rebuild `formatter` from `messages`.
+pub(crate) fn replace_messages(formatter: &mut ChatMLFormatter, messages: &[ChatMLMessage]) {
+    formatter.clear();
+    for m in messages {
+        match m.role {
+            MessageRole::System => {
+                formatter.add_system_message(m.content.clone(), m.name.clone());
+            }
+            MessageRole::User => {
+                formatter.add_user_message(m.content.clone(), m.name.clone());
+            }
+            MessageRole::Assistant => match (&m.reasoning_content, &m.tool_calls) {
+                (Some(reasoning), tool_calls) => {
+                    formatter.add_assistant_message_with_reasoning(
+                        m.content.clone(),
+                        reasoning.clone(),
+                        tool_calls.clone(),
+                    );
+                }
+                (None, Some(tool_calls)) => {
+                    formatter.add_assistant_message_with_tool_calls(m.content.clone(), tool_calls.clone());
+                }
+                (None, None) => {
+                    formatter.add_assistant_message(m.content.clone(), m.name.clone());
+                }
+            },
+            MessageRole::Tool => {
+                formatter.add_tool_message(
+                    m.content.clone(),
+                    m.tool_call_id.clone().unwrap_or_default(),
+                    m.name.clone().unwrap_or_default(),
+                );
+            }
+        }
+    }
+}
+
+/// Render messages as a plain-text transcript for the summarizer.
+pub(crate) fn transcript(messages: &[ChatMLMessage]) -> String {
+    let mut out = String::new();
+    for m in messages {
+        match m.role {
+            MessageRole::Tool => {
+                let name = m.name.as_deref().unwrap_or("tool");
+                out.push_str(&format!("[tool result: {}]\n{}\n\n", name, truncate(&m.content)));
+            }
+            _ => {
+                if !m.content.is_empty() {
+                    out.push_str(&format!("[{}]\n{}\n\n", m.role, m.content));
+                }
+                for call in m.tool_calls.iter().flatten() {
+                    out.push_str(&format!(
+                        "[tool call: {}] {}\n\n",
+                        call.function.name,
+                        truncate(&call.function.arguments)
+                    ));
+                }
+            }
+        }
+    }
+    out
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= MAX_TRANSCRIPT_RESULT_CHARS {
+        return text.to_string();
+    }
+    let head: String = text.chars().take(MAX_TRANSCRIPT_RESULT_CHARS).collect();
+    format!("{}... [truncated]", head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::StreamingResponse;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    struct SummaryProvider {
+        requests: Mutex<Vec<Vec<InternalMessage>>>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for SummaryProvider {
+        async fn generate(&self, messages: Vec<InternalMessage>, _config: &GenerateConfig) -> Result<GenerateResponse> {
+            self.requests.lock().unwrap().push(messages);
+            Ok(GenerateResponse::Content { text: "did things".to_string(), reasoning: None })
+        }
+
+        async fn generate_stream(&self, _messages: Vec<InternalMessage>, _config: &GenerateConfig) -> Result<StreamingResponse> {
+            Err(anyhow!("not supported"))
+        }
+
+        fn provider_name(&self) -> &str {
+            "summary"
+        }
+
+        fn default_model(&self) -> String {
+            "summary-model".to_string()
+        }
+    }
+
+    fn tool_call(id: &str) -> umf::ToolCall {
+        umf::ToolCall {
+            id: id.to_string(),
+            r#type: "function".to_string(),
+            function: umf::FunctionCall { name: "read".to_string(), arguments: "{}".to_string() },
+        }
+    }
+
+    /// system, task, then `turns` x (assistant tool call, tool result)
+    fn conversation(turns: usize) -> ChatMLFormatter {
+        let mut f = ChatMLFormatter::new();
+        f.add_system_message("system".to_string(), Some("agent".to_string()));
+        f.add_user_message("the task".to_string(), None);
+        for i in 0..turns {
+            let id = format!("call_{}", i);
+            f.add_assistant_message_with_tool_calls(String::new(), vec![tool_call(&id)]);
+            f.add_tool_message("x".repeat(200), id, "read".to_string());
+        }
+        f
+    }
+
+    fn compactor(trigger_tokens: usize, keep_recent_messages: usize) -> ContextCompactor {
+        ContextCompactor::new(
+            CompactionConfig { trigger_tokens, keep_recent_messages, ..Default::default() },
+            None,
+        )
+    }
+
+    #[test]
+    fn test_plan_keeps_tool_results_with_their_call() {
+        let f = conversation(5);
+        let messages = f.get_messages();
+
+        // keep 3 => naive split lands on a tool result and must move back
+        let range = compactor(0, 3).plan(messages).unwrap();
+        assert_eq!(range.start, 2);
+        assert_eq!(messages[range.end].role, MessageRole::Assistant);
+
+        assert!(compactor(0, 100).plan(messages).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_compact_replaces_older_turns() {
+        let provider = SummaryProvider { requests: Mutex::new(Vec::new()) };
+        let mut f = conversation(6);
+
+        let outcome = compactor(10, 4).compact(&provider, &mut f).await.unwrap().unwrap();
+        assert_eq!(outcome.messages_before, 14);
+        assert_eq!(outcome.messages_after, 6);
+        assert_eq!(outcome.summarized_messages, 8);
+        assert!(outcome.tokens_after < outcome.tokens_before);
+
+        // The summary joins the task rather than following it as a second user message
+        let messages = f.get_messages();
+        assert_eq!(messages[1].content, format!("the task\n\n{}\n\ndid things", SUMMARY_PREFIX));
+        assert_eq!(messages[2].role, MessageRole::Assistant);
+        assert!(f.validate_messages());
+
+        let requests = provider.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+
+        // Below the threshold nothing happens
+        let mut small = conversation(1);
+        assert!(compactor(1_000_000, 4).compact(&provider, &mut small).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_summary_is_merged_into_the_following_user_turn() {
+        let mut f = conversation(2);
+        f.add_user_message("also update the docs".to_string(), None);
+        let messages = f.get_messages().clone();
+
+        let outcome = apply_summary(&mut f, &messages, 2..6, "did things".to_string());
+        assert_eq!(outcome.messages_after, 3);
+        let merged = f.get_messages();
+        assert_eq!(merged[1].content, "the task");
+        assert_eq!(
+            merged[2].content,
+            format!("{}\n\ndid things\n\nalso update the docs", SUMMARY_PREFIX)
+        );
+    }
+
+    #[test]
+    fn test_failed_compaction_backs_off_until_the_context_grows() {
+        let mut compactor = compactor(1000, 4);
+        assert!(compactor.should_compact(1200));
+
+        assert_eq!(compactor.record_failure(1200), 1450);
+        assert!(!compactor.should_compact(1200));
+        assert!(!compactor.should_compact(1449));
+        assert!(compactor.should_compact(1450));
+
+        compactor.record_success();
+        assert!(compactor.should_compact(1200));
+    }
+}
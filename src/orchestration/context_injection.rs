@@ -0,0 +1,427 @@
+//! Context injection - insert retrieved context into the conversation
+//!
+//! A [`ContextProvider`] returns context blocks (repository maps, docs
+//! snippets, ticket text, ...) for the task at hand. The [`ContextInjector`]
+//! built from `[execution.context]` queries its providers before the first
+//! LLM call and, if `refresh_every` is set, every N iterations after that.
+//! Blocks are kept by priority until the token budget is used up and sent as
+//! a single message; a refresh replaces the previous one:
+//!
+//! ```text
+//! [system] [task] [m1 .. mN] [Retrieved context]
+//! ```
+//!
+//! Providers are usually WASM extensions with the `context` capability
+//! (see [`ExtensionContextProvider`], requires the `extension` feature).
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use umf::chatml::{count_tokens_for_text, ChatMLFormatter, ChatMLMessage, MessageRole};
+
+use super::compaction::replace_messages;
+use crate::config::{ContextInjectionConfig, ContextRole};
+
+/// Prefix of the message that carries injected context.
+pub const CONTEXT_PREFIX: &str = "[Retrieved context]";
+
+/// What the agent is working on, passed to every provider.
+#[derive(Debug, Clone)]
+pub struct ContextRequest {
+    /// Initial task description
+    pub task: String,
+    /// Plain-text transcript of the most recent messages
+    pub conversation: String,
+    /// Current workflow iteration
+    pub iteration: u32,
+    /// Agent working directory
+    pub working_directory: PathBuf,
+    /// Token budget for all blocks together
+    pub max_tokens: usize,
+}
+
+/// A block of retrieved context.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextBlock {
+    /// Short title shown above the block
+    pub title: String,
+    /// Block content
+    pub content: String,
+    /// Higher priority blocks are kept first when over budget
+    pub priority: u32,
+}
+
+/// Source of context blocks.
+#[async_trait]
+pub trait ContextProvider: Send + Sync {
+    /// Identifier used in logs (e.g., "ext-repo-map").
+    fn name(&self) -> &str;
+
+    /// Return context blocks relevant to `request`.
+    async fn provide(&self, request: &ContextRequest) -> Result<Vec<ContextBlock>>;
+}
+
+/// Shared context provider.
+pub type SharedContextProvider = Arc<dyn ContextProvider>;
+
+/// Blocks chosen for injection.
+#[derive(Debug, Clone, Default)]
+pub struct ContextSelection {
+    /// Blocks that fit the budget, highest priority first
+    pub blocks: Vec<ContextBlock>,
+    /// Tokens used by the selected blocks
+    pub tokens: usize,
+    /// Blocks left out because they did not fit
+    pub dropped: usize,
+}
+
+/// Queries context providers and inserts their blocks into the conversation.
+#[derive(Clone)]
+pub struct ContextInjector {
+    config: ContextInjectionConfig,
+    providers: Vec<SharedContextProvider>,
+    /// Iteration of the last injection in this session
+    last_injected: Option<u32>,
+}
+
+impl ContextInjector {
+    /// Create an injector from `[execution.context]` settings.
+    pub fn new(config: ContextInjectionConfig, providers: Vec<SharedContextProvider>) -> Self {
+        Self {
+            config,
+            providers,
+            last_injected: None,
+        }
+    }
+
+    /// Context injection settings.
+    pub fn config(&self) -> &ContextInjectionConfig {
+        &self.config
+    }
+
+    /// Number of providers queried.
+    pub fn provider_count(&self) -> usize {
+        self.providers.len()
+    }
+
+    /// Whether context should be (re)injected before the call in `iteration`.
+    pub fn is_due(&self, iteration: u32) -> bool {
+        if !self.config.enabled || self.providers.is_empty() {
+            return false;
+        }
+        match self.last_injected {
+            None => true,
+            Some(last) => {
+                self.config.refresh_every > 0 && iteration >= last + self.config.refresh_every
+            }
+        }
+    }
+
+    /// Record that context was injected in `iteration`.
+    pub fn mark_injected(&mut self, iteration: u32) {
+        self.last_injected = Some(iteration);
+    }
+
+    /// Query all providers. Failing providers are reported but don't stop the others.
+    pub async fn collect(&self, request: &ContextRequest) -> (Vec<ContextBlock>, Vec<String>) {
+        let mut blocks = Vec::new();
+        let mut errors = Vec::new();
+        for provider in &self.providers {
+            match provider.provide(request).await {
+                Ok(mut provided) => blocks.append(&mut provided),
+                Err(e) => errors.push(format!("{}: {}", provider.name(), e)),
+            }
+        }
+        (blocks, errors)
+    }
+
+    /// Keep the highest priority blocks that fit `max_tokens`.
+    ///
+    /// Blocks of equal priority keep the order the providers returned them in.
+    pub fn select(&self, mut blocks: Vec<ContextBlock>) -> ContextSelection {
+        blocks.sort_by_key(|b| std::cmp::Reverse(b.priority));
+
+        let mut selection = ContextSelection::default();
+        for block in blocks {
+            let tokens = count_tokens_for_text(&render_block(&block));
+            if selection.tokens + tokens > self.config.max_tokens {
+                selection.dropped += 1;
+                continue;
+            }
+            selection.tokens += tokens;
+            selection.blocks.push(block);
+        }
+        selection
+    }
+
+    /// Replace any previously injected context in `formatter` with `blocks`.
+    ///
+    /// System context goes after the leading system messages; user context
+    /// is appended to the conversation.
+    pub fn apply(&self, formatter: &mut ChatMLFormatter, blocks: &[ContextBlock]) {
+        let mut messages: Vec<ChatMLMessage> = formatter
+            .get_messages()
+            .iter()
+            .filter(|m| !is_context_message(m))
+            .cloned()
+            .collect();
+
+        if !blocks.is_empty() {
+            let content = render(blocks);
+            match self.config.role {
+                ContextRole::System => {
+                    let at = messages
+                        .iter()
+                        .take_while(|m| m.role == MessageRole::System)
+                        .count();
+                    messages.insert(at, ChatMLMessage::new(MessageRole::System, content, None));
+                }
+                ContextRole::User => {
+                    messages.push(ChatMLMessage::new(MessageRole::User, content, None));
+                }
+            }
+        }
+
+        replace_messages(formatter, &messages);
+    }
+}
+
+/// Whether `message` carries injected context.
+fn is_context_message(message: &ChatMLMessage) -> bool {
+    matches!(message.role, MessageRole::System | MessageRole::User)
+        && message.content.starts_with(CONTEXT_PREFIX)
+}
+
+fn render_block(block: &ContextBlock) -> String {
+    format!("## {}\n{}", block.title, block.content.trim_end())
+}
+
+/// Render blocks as the content of the injected message.
+fn render(blocks: &[ContextBlock]) -> String {
+    let body: Vec<String> = blocks.iter().map(render_block).collect();
+    format!("{}\n\n{}", CONTEXT_PREFIX, body.join("\n\n"))
+}
+
+/// Context provider backed by a WASM extension with the `context` capability.
+#[cfg(feature = "extension")]
+pub struct ExtensionContextProvider {
+    name: String,
+    instance: tokio::sync::Mutex<crate::extension::ContextExtensionInstance>,
+}
+
+#[cfg(feature = "extension")]
+impl ExtensionContextProvider {
+    /// Load a context extension from its directory.
+    ///
+    /// The extension runs inside the sandbox declared by its manifest.
+    pub async fn load(extension_dir: &std::path::Path) -> Result<Self> {
+        use crate::extension::{ContextExtensionInstance, ExtensionLoader, ExtensionManifest, ExtensionSandbox};
+
+        let manifest = ExtensionManifest::from_file(&extension_dir.join("extension.toml"))?;
+        if !manifest.capabilities.context {
+            anyhow::bail!(
+                "Extension '{}' does not declare the context capability",
+                manifest.extension.id
+            );
+        }
+
+        let loader = ExtensionLoader::new()?;
+        let wasm = loader.load_wasm(&extension_dir.join(&manifest.lib.path))?;
+        let sandbox = ExtensionSandbox::from_manifest(&manifest, extension_dir);
+
+        let mut instance =
+            ContextExtensionInstance::with_sandbox(&wasm.engine, &wasm.component, &sandbox).await?;
+        instance.init().await?;
+
+        Ok(Self {
+            name: format!("ext-{}", manifest.extension.id),
+            instance: tokio::sync::Mutex::new(instance),
+        })
+    }
+
+    /// Directories of all installed extensions with the `context` capability.
+    pub fn discover(extensions_dir: &std::path::Path) -> Vec<PathBuf> {
+        let Ok(entries) = std::fs::read_dir(extensions_dir) else {
+            return Vec::new();
+        };
+        let mut dirs: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|dir| {
+                crate::extension::ExtensionManifest::from_file(&dir.join("extension.toml"))
+                    .map(|m| m.capabilities.context)
+                    .unwrap_or(false)
+            })
+            .collect();
+        dirs.sort();
+        dirs
+    }
+}
+
+#[cfg(feature = "extension")]
+#[async_trait]
+impl ContextProvider for ExtensionContextProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn provide(&self, request: &ContextRequest) -> Result<Vec<ContextBlock>> {
+        use crate::extension::context_only::context as wit;
+
+        let request = wit::ContextRequest {
+            task: request.task.clone(),
+            conversation: request.conversation.clone(),
+            iteration: request.iteration,
+            working_directory: request.working_directory.display().to_string(),
+            max_tokens: u32::try_from(request.max_tokens).unwrap_or(u32::MAX),
+        };
+        let blocks = self.instance.lock().await.get_context(&request).await?;
+        Ok(blocks
+            .into_iter()
+            .map(|b| ContextBlock {
+                title: b.title,
+                content: b.content,
+                priority: b.priority,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticProvider {
+        blocks: Vec<ContextBlock>,
+    }
+
+    #[async_trait]
+    impl ContextProvider for StaticProvider {
+        fn name(&self) -> &str {
+            "static"
+        }
+
+        async fn provide(&self, _request: &ContextRequest) -> Result<Vec<ContextBlock>> {
+            Ok(self.blocks.clone())
+        }
+    }
+
+    struct FailingProvider;
+
+    #[async_trait]
+    impl ContextProvider for FailingProvider {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        async fn provide(&self, _request: &ContextRequest) -> Result<Vec<ContextBlock>> {
+            Err(anyhow::anyhow!("index missing"))
+        }
+    }
+
+    fn block(title: &str, content: &str, priority: u32) -> ContextBlock {
+        ContextBlock {
+            title: title.to_string(),
+            content: content.to_string(),
+            priority,
+        }
+    }
+
+    fn injector(config: ContextInjectionConfig) -> ContextInjector {
+        ContextInjector::new(
+            config,
+            vec![Arc::new(StaticProvider { blocks: vec![block("Map", "src/lib.rs", 1)] })],
+        )
+    }
+
+    fn request() -> ContextRequest {
+        ContextRequest {
+            task: "fix the bug".to_string(),
+            conversation: String::new(),
+            iteration: 1,
+            working_directory: PathBuf::from("."),
+            max_tokens: 4000,
+        }
+    }
+
+    #[test]
+    fn test_is_due() {
+        let mut once = injector(ContextInjectionConfig::default());
+        assert!(once.is_due(1));
+        once.mark_injected(1);
+        assert!(!once.is_due(10));
+
+        let mut refreshing = injector(ContextInjectionConfig {
+            refresh_every: 3,
+            ..Default::default()
+        });
+        refreshing.mark_injected(2);
+        assert!(!refreshing.is_due(4));
+        assert!(refreshing.is_due(5));
+
+        let empty = ContextInjector::new(ContextInjectionConfig::default(), Vec::new());
+        assert!(!empty.is_due(1));
+    }
+
+    #[test]
+    fn test_select_keeps_priority_within_budget() {
+        let injector = injector(ContextInjectionConfig {
+            max_tokens: count_tokens_for_text(&render_block(&block("Docs", "short", 5))) + 1,
+            ..Default::default()
+        });
+
+        let selection = injector.select(vec![
+            block("Map", &"src/lib.rs\n".repeat(200), 1),
+            block("Docs", "short", 5),
+        ]);
+
+        assert_eq!(selection.blocks, vec![block("Docs", "short", 5)]);
+        assert_eq!(selection.dropped, 1);
+        assert!(selection.tokens <= injector.config().max_tokens);
+    }
+
+    #[tokio::test]
+    async fn test_collect_reports_failing_providers() {
+        let injector = ContextInjector::new(
+            ContextInjectionConfig::default(),
+            vec![
+                Arc::new(FailingProvider),
+                Arc::new(StaticProvider { blocks: vec![block("Ticket", "ABK-1", 0)] }),
+            ],
+        );
+
+        let (blocks, errors) = injector.collect(&request()).await;
+
+        assert_eq!(blocks, vec![block("Ticket", "ABK-1", 0)]);
+        assert_eq!(errors, vec!["failing: index missing".to_string()]);
+    }
+
+    #[test]
+    fn test_apply_replaces_previous_context() {
+        let mut formatter = ChatMLFormatter::new();
+        formatter.add_system_message("system prompt".to_string(), None);
+        formatter.add_user_message("fix the bug".to_string(), None);
+
+        let user = injector(ContextInjectionConfig::default());
+        user.apply(&mut formatter, &[block("Map", "old", 0)]);
+        formatter.add_assistant_message("looking".to_string(), None);
+        user.apply(&mut formatter, &[block("Map", "new", 0)]);
+
+        let messages = formatter.get_messages();
+        let injected: Vec<_> = messages.iter().filter(|m| is_context_message(m)).collect();
+        assert_eq!(injected.len(), 1);
+        assert!(injected[0].content.contains("new"));
+        assert_eq!(messages.last().unwrap().role, MessageRole::User);
+
+        let system = injector(ContextInjectionConfig {
+            role: ContextRole::System,
+            ..Default::default()
+        });
+        system.apply(&mut formatter, &[block("Map", "sys", 0)]);
+        let messages = formatter.get_messages();
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[1].role, MessageRole::System);
+        assert!(messages[1].content.starts_with(CONTEXT_PREFIX));
+    }
+}
@@ -33,6 +33,11 @@ pub mod tools;
 pub mod agent_session;  // Deprecated - use agent_orchestration
 pub mod agent_orchestration;
 pub mod output;  // OutputSink foundation (Workstream A)
+pub mod approval;
+pub mod compaction;
+pub mod context_injection;
+pub mod budget;
+pub mod user_input;
 
 // Re-export main types
 pub use runtime::{
@@ -52,6 +57,29 @@ pub use agent_orchestration::{
 // Re-export output sink types
 pub use output::{OutputEvent, OutputSink, StdoutSink, NoopSink, SharedSink};
 
+// Re-export context compaction types
+pub use compaction::{CompactionOutcome, ContextCompactor};
+
+// Re-export context injection types
+pub use context_injection::{
+    ContextBlock, ContextInjector, ContextProvider, ContextRequest, ContextSelection,
+    SharedContextProvider,
+};
+#[cfg(feature = "extension")]
+pub use context_injection::ExtensionContextProvider;
+
+// Re-export budget guard types
+pub use budget::{BudgetGuard, BudgetLimit, BudgetOverrun};
+
+// Re-export tool approval types
+pub use approval::{
+    ApprovalDecision, ApprovalGate, ApprovalRequest, ApprovalResponder, AutoApprover,
+    ChannelApprover, SharedApprover, ToolApprover,
+};
+
+// Re-export mid-run user input types
+pub use user_input::{UserInputQueue, UserInputSender};
+
 // Re-export sophisticated session types (DEPRECATED)
 pub use agent_session::{
     AgentSession, SessionConfig, TemplateProvider, ClassificationHandler,
@@ -70,6 +70,80 @@ pub enum OutputEvent {
         description: Option<String>,
     },
 
+    /// A long-running tool reported progress (MCP `notifications/progress`)
+    ToolProgress {
+        /// Name of the running tool
+        tool_name: String,
+        /// Progress so far
+        progress: f64,
+        /// Total, if known
+        total: Option<f64>,
+        /// Status message from the server
+        message: Option<String>,
+    },
+
+    /// Older turns were replaced by an LLM-written summary
+    ContextCompacted {
+        /// Number of messages replaced by the summary
+        summarized_messages: usize,
+        /// Conversation tokens before compaction
+        tokens_before: usize,
+        /// Conversation tokens after compaction
+        tokens_after: usize,
+    },
+
+    /// An LLM call reported token usage
+    TokenUsage {
+        /// Usage of this call
+        usage: crate::provider::TokenUsage,
+        /// Session totals including this call
+        totals: crate::provider::UsageTotals,
+    },
+
+    /// A session budget limit (`[execution.budget]`) was reached
+    BudgetExceeded {
+        /// Which limit was reached
+        limit: super::budget::BudgetLimit,
+        /// Amount used so far (tokens, USD or seconds)
+        used: f64,
+        /// Configured maximum, in the same unit
+        maximum: f64,
+        /// Action taken in response
+        action: crate::config::BudgetAction,
+    },
+
+    /// A message queued by the user mid-run was added to the conversation
+    UserMessageInjected {
+        /// The message text
+        content: String,
+    },
+
+    /// The routing provider moved a call to another backend
+    ProviderFallback {
+        /// Backend that failed
+        from: String,
+        /// Backend tried next
+        to: String,
+        /// Why the call moved (e.g., "rate limited")
+        reason: String,
+    },
+
+    /// A tool call is waiting for user approval.
+    ///
+    /// Front-ends answer it through
+    /// [`ApprovalResponder::respond`](super::approval::ApprovalResponder::respond)
+    /// using the same `request_id`.
+    ApprovalRequested {
+        /// ID to pass back when answering
+        request_id: String,
+        /// ID of the tool call under review
+        tool_call_id: String,
+        /// Name of the tool awaiting approval
+        tool_name: String,
+        /// Tool arguments as a JSON string
+        arguments: String,
+    },
+
     /// An error occurred
     Error {
         message: String,
@@ -126,6 +200,52 @@ impl std::fmt::Display for OutputEvent {
                     None => write!(f, "Tool: {}\n{}: {}", tool_name, status, content),
                 }
             }
+            Self::ToolProgress { tool_name, progress, total, message } => {
+                match total {
+                    Some(total) => write!(f, "⏳ {} {}/{}", tool_name, progress, total)?,
+                    None => write!(f, "⏳ {} {}", tool_name, progress)?,
+                }
+                match message {
+                    Some(msg) => write!(f, " — {}", msg),
+                    None => Ok(()),
+                }
+            }
+            Self::ContextCompacted { summarized_messages, tokens_before, tokens_after } => {
+                write!(f, "🗜️  Context compacted: {} messages summarized | {} → {} tokens", summarized_messages, tokens_before, tokens_after)
+            }
+            Self::TokenUsage { usage, totals } => {
+                write!(
+                    f,
+                    "📊 Tokens: {} in ({} cached) / {} out | session: {} tokens, {} calls",
+                    usage.prompt_tokens,
+                    usage.cached_tokens,
+                    usage.completion_tokens,
+                    totals.usage.total_tokens(),
+                    totals.api_calls
+                )?;
+                if let Some(cost) = totals.estimated_cost {
+                    write!(f, ", ${:.4}", cost)?;
+                }
+                Ok(())
+            }
+            Self::BudgetExceeded { limit, used, maximum, action } => {
+                let overrun = super::budget::BudgetOverrun {
+                    limit: *limit,
+                    used: *used,
+                    maximum: *maximum,
+                    action: *action,
+                };
+                write!(f, "💸 {} → {}", overrun, action)
+            }
+            Self::UserMessageInjected { content } => {
+                write!(f, "💬 User message: {}", content)
+            }
+            Self::ProviderFallback { from, to, reason } => {
+                write!(f, "🔀 Provider fallback: {} → {} ({})", from, to, reason)
+            }
+            Self::ApprovalRequested { tool_name, arguments, .. } => {
+                write!(f, "⏸️  Approval required: {} {}", tool_name, arguments)
+            }
             Self::Error { message, context } => {
                 if let Some(ctx) = context {
                     write!(f, "❌ Error: {} — {}", message, ctx)
@@ -0,0 +1,74 @@
+//! User input - messages sent into a running workflow
+//!
+//! A front-end (TUI, web UI) keeps a [`UserInputSender`] while the agent owns
+//! the matching [`UserInputQueue`]. The orchestration loop drains the queue
+//! between iterations, appends each message to the conversation as a user
+//! turn and reports it with [`OutputEvent::UserMessageInjected`], so the
+//! agent can be steered without cancelling and resuming the run.
+//!
+//! [`OutputEvent::UserMessageInjected`]: super::output::OutputEvent::UserMessageInjected
+
+use tokio::sync::mpsc;
+
+/// Receiving end of the user input channel, drained by the workflow loop.
+#[derive(Debug)]
+pub struct UserInputQueue {
+    receiver: mpsc::UnboundedReceiver<String>,
+}
+
+impl UserInputQueue {
+    /// Create a queue and the sender a front-end uses to fill it.
+    pub fn new() -> (Self, UserInputSender) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { receiver }, UserInputSender { sender })
+    }
+
+    /// Take every message queued so far, in the order it was sent.
+    ///
+    /// Blank messages are dropped. Never waits for new messages.
+    pub fn drain(&mut self) -> Vec<String> {
+        let mut messages = Vec::new();
+        while let Ok(message) = self.receiver.try_recv() {
+            if !message.trim().is_empty() {
+                messages.push(message);
+            }
+        }
+        messages
+    }
+}
+
+/// Handle for sending messages into a running workflow.
+#[derive(Debug, Clone)]
+pub struct UserInputSender {
+    sender: mpsc::UnboundedSender<String>,
+}
+
+impl UserInputSender {
+    /// Queue a message for the next iteration.
+    ///
+    /// Returns `false` when the agent holding the queue has been dropped.
+    pub fn send(&self, message: impl Into<String>) -> bool {
+        self.sender.send(message.into()).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drain_returns_messages_in_order() {
+        let (mut queue, sender) = UserInputQueue::new();
+        assert!(queue.drain().is_empty());
+
+        assert!(sender.send("use the v2 API instead"));
+        assert!(sender.clone().send("   "));
+        assert!(sender.send("and add a test"));
+
+        assert_eq!(queue.drain(), vec!["use the v2 API instead", "and add a test"]);
+        assert!(queue.drain().is_empty());
+
+        drop(queue);
+        assert!(!sender.send("too late"));
+    }
+}
@@ -7,7 +7,7 @@ use umf::chatml::{ChatMLFormatter, ChatMLMessage, MessageRole as ChatMLRole};
 use crate::provider::types::internal::{
     ContentBlock, InternalMessage, MessageContent, MessageRole,
 };
-use crate::provider::ToolCall;
+use crate::provider::{thinking, ToolCall};
 use anyhow::Result;
 
 /// Adapter for converting between ChatML and internal message formats
@@ -57,7 +57,7 @@ impl ChatMLAdapter {
                 ));
             }
             
-            let mut metadata = std::collections::HashMap::new();
+            let mut metadata = Self::assistant_metadata(msg);
             if let Some(ref name) = msg.name {
                 metadata.insert("name".to_string(), name.clone());
             }
@@ -84,7 +84,7 @@ impl ChatMLAdapter {
             })
         } else {
             // Simple text message
-            let mut metadata = std::collections::HashMap::new();
+            let mut metadata = Self::assistant_metadata(msg);
             if let Some(ref name) = msg.name {
                 metadata.insert("name".to_string(), name.clone());
             }
@@ -99,6 +99,18 @@ impl ChatMLAdapter {
         }
     }
 
+    /// Metadata for an assistant message: its signed thinking blocks, if any
+    fn assistant_metadata(msg: &ChatMLMessage) -> std::collections::HashMap<String, String> {
+        let mut metadata = std::collections::HashMap::new();
+        if let Some(blocks) = msg.reasoning_content.as_deref().and_then(|r| thinking::split(r).1) {
+            metadata.insert(
+                thinking::METADATA_KEY.to_string(),
+                serde_json::Value::Array(blocks).to_string(),
+            );
+        }
+        metadata
+    }
+
     /// Convert ChatML role to internal role
     fn convert_role(role: &ChatMLRole) -> MessageRole {
         match role {
@@ -0,0 +1,231 @@
+//! Native Rust Anthropic provider — message conversion utilities.
+
+use crate::provider::thinking;
+use crate::provider::types::InternalMessage;
+use serde_json::{json, Value};
+
+/// Convert `InternalMessage` array → Anthropic Messages API `(system, messages)`.
+///
+/// System messages are lifted out into top-level `system` text blocks. Tool
+/// results become `tool_result` blocks inside a user turn, and consecutive
+/// turns with the same role are merged so the conversation alternates the way
+/// the API expects.
+pub fn messages_to_anthropic(messages: &[InternalMessage]) -> (Vec<Value>, Vec<Value>) {
+    let mut system = Vec::new();
+    let mut result: Vec<Value> = Vec::new();
+
+    for msg in messages {
+        let (role, blocks) = match msg.role {
+            umf::MessageRole::System => {
+                let text = message_text(msg);
+                if !text.is_empty() {
+                    system.push(json!({"type": "text", "text": text}));
+                }
+                continue;
+            }
+
+            umf::MessageRole::User => ("user", user_blocks(msg)),
+
+            umf::MessageRole::Assistant => ("assistant", assistant_blocks(msg)),
+
+            umf::MessageRole::Tool => ("user", tool_result_blocks(msg)),
+        };
+
+        if blocks.is_empty() {
+            continue;
+        }
+        push_turn(&mut result, role, blocks);
+    }
+
+    (system, result)
+}
+
+/// Append content blocks, merging into the previous turn when the role repeats.
+fn push_turn(result: &mut Vec<Value>, role: &str, mut blocks: Vec<Value>) {
+    if let Some(last) = result.last_mut() {
+        if last["role"] == role {
+            if let Some(content) = last["content"].as_array_mut() {
+                // tool_result blocks must lead the user turn that carries them
+                let results: Vec<Value> = blocks
+                    .iter()
+                    .filter(|b| b["type"] == "tool_result")
+                    .cloned()
+                    .collect();
+                blocks.retain(|b| b["type"] != "tool_result");
+                let insert_at = content
+                    .iter()
+                    .position(|b| b["type"] != "tool_result")
+                    .unwrap_or(content.len());
+                content.splice(insert_at..insert_at, results);
+                content.extend(blocks);
+                return;
+            }
+        }
+    }
+
+    result.push(json!({
+        "role": role,
+        "content": blocks,
+    }));
+}
+
+/// Flatten a message to plain text (text form or text blocks).
+fn message_text(msg: &InternalMessage) -> String {
+    if let Some(text) = msg.text() {
+        text.to_string()
+    } else if let Some(blocks) = msg.blocks() {
+        blocks
+            .iter()
+            .filter_map(|b| b.as_text())
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        String::new()
+    }
+}
+
+fn text_block(text: &str) -> Option<Value> {
+    // The API rejects empty text blocks
+    if text.is_empty() {
+        None
+    } else {
+        Some(json!({"type": "text", "text": text}))
+    }
+}
+
+fn user_blocks(msg: &InternalMessage) -> Vec<Value> {
+    if let Some(text) = msg.text() {
+        return text_block(text).into_iter().collect();
+    }
+
+    msg.blocks()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|block| match block {
+            umf::ContentBlock::Text { text } => text_block(text),
+            umf::ContentBlock::Image { source } => Some(image_block(source)),
+            umf::ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+            } => Some(json!({
+                "type": "tool_result",
+                "tool_use_id": tool_use_id,
+                "content": content,
+            })),
+            umf::ContentBlock::ToolUse { .. } => None,
+        })
+        .collect()
+}
+
+fn assistant_blocks(msg: &InternalMessage) -> Vec<Value> {
+    // Signed thinking blocks go back first and unchanged
+    let mut blocks: Vec<Value> = msg
+        .metadata
+        .get(thinking::METADATA_KEY)
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default();
+
+    if let Some(text) = msg.text() {
+        blocks.extend(text_block(text));
+        return blocks;
+    }
+
+    let content = msg.blocks().unwrap_or_default();
+    blocks.extend(content.iter().filter_map(|block| match block {
+        umf::ContentBlock::Text { text } => text_block(text),
+        umf::ContentBlock::ToolUse { id, name, input } => Some(json!({
+            "type": "tool_use",
+            "id": id,
+            "name": name,
+            // tool_use.input must be an object, never null
+            "input": if input.is_object() { input.clone() } else { json!({}) },
+        })),
+        _ => None,
+    }));
+    blocks
+}
+
+fn tool_result_blocks(msg: &InternalMessage) -> Vec<Value> {
+    // ChatMLAdapter wraps tool output as Blocks(vec![ToolResult{...}]),
+    // older callers use plain text plus `tool_call_id`.
+    if let Some(blocks) = msg.blocks() {
+        let results: Vec<Value> = blocks
+            .iter()
+            .filter_map(|b| {
+                b.as_tool_result().map(|(id, content)| {
+                    json!({
+                        "type": "tool_result",
+                        "tool_use_id": id,
+                        "content": content,
+                    })
+                })
+            })
+            .collect();
+        if !results.is_empty() {
+            return results;
+        }
+    }
+
+    vec![json!({
+        "type": "tool_result",
+        "tool_use_id": msg.tool_call_id.clone().unwrap_or_default(),
+        "content": message_text(msg),
+    })]
+}
+
+fn image_block(source: &umf::ImageSource) -> Value {
+    match source {
+        umf::ImageSource::Base64 { media_type, data } => json!({
+            "type": "image",
+            "source": {"type": "base64", "media_type": media_type, "data": data},
+        }),
+        umf::ImageSource::Url { url } => json!({
+            "type": "image",
+            "source": {"type": "url", "url": url},
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::types::internal::ContentBlock;
+
+    #[test]
+    fn test_system_and_tool_turns() {
+        let msgs = vec![
+            InternalMessage::system("You are terse."),
+            InternalMessage::user("What's the weather?"),
+            InternalMessage::assistant_with_tools(
+                "",
+                vec![
+                    ContentBlock::tool_use("call_1", "weather", json!({"city": "Oslo"})),
+                    ContentBlock::tool_use("call_2", "weather", json!({"city": "Rome"})),
+                ],
+            ),
+            InternalMessage::tool_result("call_1", "weather", "rain"),
+            InternalMessage::tool_result("call_2", "weather", "sun"),
+            InternalMessage::user("Thanks"),
+        ];
+
+        let (system, turns) = messages_to_anthropic(&msgs);
+
+        assert_eq!(system, vec![json!({"type": "text", "text": "You are terse."})]);
+        assert_eq!(turns.len(), 3);
+        assert_eq!(turns[0]["role"], "user");
+
+        // Empty assistant text is dropped, both tool_use blocks kept
+        let assistant = turns[1]["content"].as_array().unwrap();
+        assert_eq!(assistant.len(), 2);
+        assert_eq!(assistant[0]["type"], "tool_use");
+        assert_eq!(assistant[1]["input"]["city"], "Rome");
+
+        // Both results and the follow-up text share one user turn, results first
+        let user = turns[2]["content"].as_array().unwrap();
+        assert_eq!(turns[2]["role"], "user");
+        assert_eq!(user.len(), 3);
+        assert_eq!(user[0]["tool_use_id"], "call_1");
+        assert_eq!(user[1]["tool_use_id"], "call_2");
+        assert_eq!(user[2]["text"], "Thanks");
+    }
+}
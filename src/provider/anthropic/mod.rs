@@ -0,0 +1,502 @@
+//! Native Rust Anthropic provider (no wasmtime dependency).
+//!
+//! Implements `LlmProvider` against the Messages API using pure Rust + `reqwest`.
+//! Activated when `LLM_PROVIDER=anthropic`.
+
+mod messages;
+mod tools;
+mod request;
+mod response;
+mod sse;
+
+use crate::provider::openai::HttpClient;
+use crate::provider::traits::{GenerateResponse, LlmProvider, StreamingResponse};
+use crate::provider::types::{GenerateConfig, InternalMessage};
+use crate::provider::usage::TokenUsage;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+/// Conditional debug macro
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if std::env::var("RUST_LOG").map(|v| v.to_lowercase().contains("debug")).unwrap_or(false) {
+            crate::observability::tee_eprintln(&format!("[DEBUG ANTHROPIC-NATIVE] {}", format!($($arg)*)));
+        }
+    };
+}
+
+/// Native Rust Anthropic Messages API provider.
+///
+/// Reads configuration from environment variables:
+/// - `ANTHROPIC_API_KEY` (required)
+/// - `ANTHROPIC_BASE_URL` (default: `https://api.anthropic.com`)
+/// - `ANTHROPIC_DEFAULT_MODEL` (default: `claude-sonnet-4-5`)
+/// - `ANTHROPIC_VERSION` (default: `2023-06-01`)
+/// - `ANTHROPIC_THINKING_BUDGET` (optional; enables extended thinking)
+/// - `ANTHROPIC_PROMPT_CACHING` (default: `true`; `false` drops `cache_control` markers)
+pub struct AnthropicProvider {
+    http: HttpClient,
+    api_key: Option<String>,
+    base_url: Option<String>,
+    /// Usage of the last completed call, see [`LlmProvider::take_usage`]
+    usage: Arc<Mutex<Option<TokenUsage>>>,
+}
+
+impl AnthropicProvider {
+    /// Create a new native Anthropic provider.
+    pub fn new() -> Result<Self> {
+        let http = HttpClient::new()?;
+        Ok(Self {
+            http,
+            api_key: None,
+            base_url: None,
+            usage: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Retry failed requests at most `max_retries` times instead of
+    /// `LLM_MAX_RETRIES` (see [`HttpClient::with_max_retries`]).
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.http = self.http.with_max_retries(max_retries);
+        self
+    }
+
+    /// Override the base URL instead of reading `ANTHROPIC_BASE_URL`.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Override the API key instead of reading `ANTHROPIC_API_KEY`.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Get the API key (explicit override, then env).
+    fn api_key(&self) -> Result<String> {
+        match &self.api_key {
+            Some(key) => Ok(key.clone()),
+            None => std::env::var("ANTHROPIC_API_KEY").context("ANTHROPIC_API_KEY not set"),
+        }
+    }
+
+    /// Get the base URL (explicit override, then env, then `https://api.anthropic.com`).
+    fn base_url(&self) -> String {
+        self.base_url
+            .clone()
+            .or_else(|| std::env::var("ANTHROPIC_BASE_URL").ok())
+            .unwrap_or_else(|| "https://api.anthropic.com".to_string())
+    }
+
+    /// Build the messages endpoint URL.
+    fn api_url(&self) -> String {
+        format!("{}/v1/messages", self.base_url().trim_end_matches('/'))
+    }
+
+    /// Auth and version headers sent with every request.
+    fn headers(&self) -> Result<Vec<(&'static str, String)>> {
+        let version =
+            std::env::var("ANTHROPIC_VERSION").unwrap_or_else(|_| "2023-06-01".to_string());
+        Ok(vec![
+            ("x-api-key", self.api_key()?),
+            ("anthropic-version", version),
+        ])
+    }
+
+    /// Request options read from env.
+    fn request_options() -> request::RequestOptions {
+        request::RequestOptions {
+            thinking_budget: std::env::var("ANTHROPIC_THINKING_BUDGET")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .filter(|&n: &u32| n > 0),
+            prompt_caching: std::env::var("ANTHROPIC_PROMPT_CACHING")
+                .map(|v| v != "false" && v != "0")
+                .unwrap_or(true),
+        }
+    }
+
+    /// Convert messages and config into a request body.
+    fn build_body(&self, msgs: &[InternalMessage], config: &GenerateConfig, stream: bool) -> (String, Value) {
+        let model = config
+            .model
+            .clone()
+            .unwrap_or_else(|| self.default_model());
+
+        // Convert messages
+        let (system, anthropic_messages) = messages::messages_to_anthropic(msgs);
+
+        // Convert tools
+        let anthropic_tools: Option<Vec<Value>> = config
+            .tools
+            .as_ref()
+            .map(|t| tools::tools_to_anthropic(t));
+
+        let anthropic_tool_choice = config
+            .tool_choice
+            .as_ref()
+            .map(tools::tool_choice_to_anthropic);
+
+        let body = request::build_request_body(
+            &system,
+            &anthropic_messages,
+            &model,
+            stream,
+            Some(config.temperature),
+            config.max_tokens,
+            anthropic_tools.as_deref(),
+            anthropic_tool_choice.as_ref(),
+            &Self::request_options(),
+        );
+
+        (model, body)
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for AnthropicProvider {
+    fn provider_name(&self) -> &str {
+        "anthropic"
+    }
+
+    fn default_model(&self) -> String {
+        std::env::var("ANTHROPIC_DEFAULT_MODEL")
+            .unwrap_or_else(|_| "claude-sonnet-4-5".to_string())
+    }
+
+    fn take_usage(&self) -> Option<TokenUsage> {
+        self.usage.lock().ok()?.take()
+    }
+
+    async fn generate(
+        &self,
+        msgs: Vec<InternalMessage>,
+        config: &GenerateConfig,
+    ) -> Result<GenerateResponse> {
+        let (model, body) = self.build_body(&msgs, config, false);
+        let body_str = serde_json::to_string(&body)?;
+        let headers = self.headers()?;
+        let url = self.api_url();
+
+        debug!("generate() POST {} model={}", url, model);
+
+        let resp = self
+            .http
+            .post_with_retry_headers(&url, body_str, &headers, false)
+            .await?;
+
+        let resp_text = resp.text().await?;
+        let (response, usage) = response::parse_response(&resp_text)?;
+        if let Ok(mut slot) = self.usage.lock() {
+            *slot = usage;
+        }
+        Ok(response)
+    }
+
+    async fn generate_stream(
+        &self,
+        msgs: Vec<InternalMessage>,
+        config: &GenerateConfig,
+    ) -> Result<StreamingResponse> {
+        use futures_util::StreamExt;
+
+        let (model, body) = self.build_body(&msgs, config, true);
+        let body_str = serde_json::to_string(&body)?;
+        let headers = self.headers()?;
+        let url = self.api_url();
+
+        debug!("generate_stream() POST {} model={}", url, model);
+
+        let resp = self
+            .http
+            .post_with_retry_headers(&url, body_str, &headers, true)
+            .await?;
+
+        let byte_stream = resp.bytes_stream();
+
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let usage_slot = Arc::clone(&self.usage);
+
+        tokio::spawn(async move {
+            let mut byte_stream = byte_stream;
+            // Buffer raw bytes so multi-byte characters split across chunks survive
+            let mut buffer: Vec<u8> = Vec::new();
+            let mut state = sse::StreamState::default();
+
+            'read: while let Some(chunk_result) = byte_stream.next().await {
+                match chunk_result {
+                    Ok(bytes) => {
+                        buffer.extend(bytes.iter().filter(|&&b| b != b'\r'));
+
+                        // Process complete SSE events (separated by \n\n)
+                        while let Some(event_end) = buffer.windows(2).position(|w| w == b"\n\n") {
+                            let event: Vec<u8> = buffer.drain(..event_end + 2).collect();
+                            let event = String::from_utf8_lossy(&event);
+
+                            for line in event.lines() {
+                                let Some(data) = line.strip_prefix("data:") else {
+                                    continue;
+                                };
+                                match sse::parse_sse_event(data, &mut state) {
+                                    Ok(chunks) => {
+                                        for chunk in chunks {
+                                            match &chunk {
+                                                // Sent once usage is published, below
+                                                crate::provider::StreamChunk::Done => break 'read,
+                                                crate::provider::StreamChunk::Text(t) => {
+                                                    crate::observability::append_to_global_log(t);
+                                                }
+                                                crate::provider::StreamChunk::Reasoning(r)
+                                                    if !crate::provider::thinking::is_trailer(r) =>
+                                                {
+                                                    crate::observability::append_to_global_log(
+                                                        &crate::observability::strip_ansi(r),
+                                                    );
+                                                }
+                                                _ => {}
+                                            }
+                                            let _ = tx.send(Ok(chunk));
+                                        }
+                                    }
+                                    Err(e) => {
+                                        let _ = tx.send(Err(e));
+                                        return;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        crate::observability::tee_eprintln(&format!(
+                            "\n⚠️  Stream byte error: {}\n",
+                            e
+                        ));
+                        let _ = tx.send(Err(anyhow::anyhow!("Stream error: {}", e)));
+                        return;
+                    }
+                }
+            }
+
+            // Publish usage before Done so callers can take it once the stream ends
+            if let Ok(mut slot) = usage_slot.lock() {
+                *slot = state.usage;
+            }
+            let _ = tx.send(Ok(crate::provider::StreamChunk::Done));
+        });
+
+        Ok(Box::pin(futures_util::stream::unfold(
+            rx,
+            |mut rx| async move { rx.recv().await.map(|item| (item, rx)) },
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::StreamChunk;
+    use futures_util::StreamExt;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    /// Serve one canned HTTP response on a local port; yields the raw request
+    /// (headers, body) once it has been received.
+    fn mock_server(
+        content_type: &'static str,
+        response_body: String,
+    ) -> (String, mpsc::Receiver<(String, Value)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut headers = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(len) = line.to_lowercase().strip_prefix("content-length:") {
+                    content_length = len.trim().parse().unwrap();
+                }
+                headers.push_str(&line);
+            }
+            let mut body = vec![0u8; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let mut stream = stream;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                content_type,
+                response_body.len(),
+                response_body
+            )
+            .unwrap();
+            tx.send((headers, serde_json::from_slice(&body).unwrap())).unwrap();
+        });
+
+        (base_url, rx)
+    }
+
+    fn provider(base_url: &str) -> AnthropicProvider {
+        AnthropicProvider::new()
+            .unwrap()
+            .with_base_url(base_url)
+            .with_api_key("test-key")
+    }
+
+    #[tokio::test]
+    async fn test_generate_against_mock_server() {
+        let reply = serde_json::json!({
+            "type": "message",
+            "role": "assistant",
+            "content": [
+                {"type": "thinking", "thinking": "Simple greeting.", "signature": "s"},
+                {"type": "text", "text": "Hello!"}
+            ],
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 20, "output_tokens": 8}
+        });
+        let (base_url, rx) = mock_server("application/json", reply.to_string());
+
+        let msgs = vec![
+            InternalMessage::system("Be brief."),
+            InternalMessage::user("Hi"),
+        ];
+        let config = GenerateConfig::new().with_model("claude-test");
+        let provider = provider(&base_url);
+        let response = provider.generate(msgs, &config).await.unwrap();
+        assert_eq!(provider.take_usage().unwrap().total_tokens(), 28);
+        assert!(provider.take_usage().is_none());
+
+        match response {
+            GenerateResponse::Content { text, reasoning } => {
+                assert_eq!(text, "Hello!");
+                // The signed block of a finished turn is not surfaced
+                assert_eq!(reasoning.as_deref(), Some("Simple greeting."));
+            }
+            other => panic!("expected content, got {:?}", other),
+        }
+
+        let (headers, body) = rx.recv().unwrap();
+        let headers = headers.to_lowercase();
+        assert!(headers.starts_with("post /v1/messages "));
+        assert!(headers.contains("x-api-key: test-key"));
+        assert!(headers.contains("anthropic-version:"));
+        assert_eq!(body["model"], "claude-test");
+        assert_eq!(body["system"][0]["text"], "Be brief.");
+        assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(body["messages"][0]["role"], "user");
+    }
+
+    #[tokio::test]
+    async fn test_generate_stream_against_mock_server() {
+        let events = [
+            ("message_start", r#"{"type":"message_start","message":{"id":"msg_1","content":[]}}"#),
+            ("content_block_start", r#"{"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#),
+            ("content_block_delta", r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Lo"}}"#),
+            ("content_block_start", r#"{"type":"content_block_start","index":1,"content_block":{"type":"tool_use","id":"toolu_1","name":"ls","input":{}}}"#),
+            ("content_block_delta", r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"{}"}}"#),
+            ("message_stop", r#"{"type":"message_stop"}"#),
+        ];
+        let sse_body: String = events
+            .iter()
+            .map(|(event, data)| format!("event: {}\r\ndata: {}\r\n\r\n", event, data))
+            .collect();
+        let (base_url, rx) = mock_server("text/event-stream", sse_body);
+
+        let msgs = vec![InternalMessage::user("List files")];
+        let mut stream = provider(&base_url)
+            .generate_stream(msgs, &GenerateConfig::new())
+            .await
+            .unwrap();
+
+        let mut chunks = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.unwrap();
+            let done = matches!(chunk, StreamChunk::Done);
+            chunks.push(chunk);
+            if done {
+                break;
+            }
+        }
+
+        assert!(matches!(&chunks[0], StreamChunk::Text(t) if t == "Lo"));
+        assert!(matches!(&chunks[1], StreamChunk::ToolCallDelta { index: 1, id: Some(_), .. }));
+        assert!(matches!(&chunks[2], StreamChunk::ToolCallDelta { arguments_delta: Some(_), .. }));
+        assert!(matches!(chunks[3], StreamChunk::Done));
+
+        let (_, body) = rx.recv().unwrap();
+        assert_eq!(body["stream"], true);
+    }
+
+    #[tokio::test]
+    async fn test_thinking_is_replayed_after_tool_use() {
+        use umf::chatml::ChatMLFormatter;
+
+        // Turn 1 streams a signed thinking block, then a tool call
+        let events = [
+            r#"{"type":"message_start","message":{"id":"msg_1","content":[]}}"#,
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"thinking","thinking":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"Need the "}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"file."}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"signature_delta","signature":"sig-1"}}"#,
+            r#"{"type":"content_block_stop","index":0}"#,
+            r#"{"type":"content_block_start","index":1,"content_block":{"type":"tool_use","id":"toolu_1","name":"read_file","input":{}}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"{\"path\":\"a.rs\"}"}}"#,
+            r#"{"type":"message_stop"}"#,
+        ];
+        let sse_body: String = events.iter().map(|data| format!("data: {}\n\n", data)).collect();
+        let (base_url, _rx) = mock_server("text/event-stream", sse_body);
+        let mut stream = provider(&base_url)
+            .generate_stream(vec![InternalMessage::user("Read a.rs")], &GenerateConfig::new())
+            .await
+            .unwrap();
+        let mut accumulator = umf::StreamingAccumulator::new();
+        while let Some(chunk) = stream.next().await {
+            if accumulator.process_chunk(chunk.unwrap()) {
+                break;
+            }
+        }
+        let turn = accumulator.finish();
+        assert_eq!(crate::provider::thinking::split(&turn.reasoning).0, "Need the file.");
+
+        // The agent keeps the turn in its ChatML history and answers the call
+        let mut history = ChatMLFormatter::new();
+        history.add_user_message("Read a.rs".to_string(), None);
+        history.add_assistant_message_with_reasoning(String::new(), turn.reasoning, Some(turn.tool_calls));
+        history.add_tool_message("fn main() {}".to_string(), "toolu_1".to_string(), "read_file".to_string());
+        let msgs = crate::provider::ChatMLAdapter::to_internal(&history).unwrap();
+
+        // Turn 2 must echo the thinking block, signature included, before the tool_use
+        let reply = serde_json::json!({
+            "type": "message",
+            "role": "assistant",
+            "content": [{"type": "text", "text": "It is empty."}],
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 30, "output_tokens": 4}
+        });
+        let (base_url, rx) = mock_server("application/json", reply.to_string());
+        provider(&base_url).generate(msgs, &GenerateConfig::new()).await.unwrap();
+
+        let (_, body) = rx.recv().unwrap();
+        let assistant = body["messages"][1]["content"].as_array().unwrap();
+        assert_eq!(
+            assistant[0],
+            serde_json::json!({"type": "thinking", "thinking": "Need the file.", "signature": "sig-1"})
+        );
+        assert_eq!(assistant[1]["type"], "tool_use");
+        assert_eq!(assistant[1]["input"]["path"], "a.rs");
+        assert_eq!(assistant.len(), 2);
+        assert_eq!(body["messages"][2]["content"][0]["type"], "tool_result");
+    }
+}
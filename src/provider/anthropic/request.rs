@@ -0,0 +1,143 @@
+//! Native Rust Anthropic provider — request body builder.
+
+use serde_json::{json, Value};
+
+/// `max_tokens` is mandatory for the Messages API; used when the config has none.
+pub const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Request options that have no equivalent in `GenerateConfig`.
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    /// Extended-thinking token budget (`None` = thinking disabled)
+    pub thinking_budget: Option<u32>,
+    /// Whether to place `cache_control` breakpoints on the stable prefix
+    pub prompt_caching: bool,
+}
+
+/// Build the Anthropic Messages API request body.
+#[allow(clippy::too_many_arguments)]
+pub fn build_request_body(
+    system: &[Value],
+    messages: &[Value],
+    model: &str,
+    stream: bool,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+    tools: Option<&[Value]>,
+    tool_choice: Option<&Value>,
+    options: &RequestOptions,
+) -> Value {
+    let mut max_tokens = max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+
+    let mut body = json!({
+        "model": model,
+        "messages": messages,
+        "stream": stream,
+    });
+
+    if !system.is_empty() {
+        body["system"] = json!(system);
+    }
+
+    if let Some(budget) = options.thinking_budget {
+        // The budget counts against max_tokens and must leave room for the answer
+        if max_tokens <= budget {
+            max_tokens = budget + DEFAULT_MAX_TOKENS;
+        }
+        body["thinking"] = json!({"type": "enabled", "budget_tokens": budget});
+    } else if let Some(temp) = temperature {
+        // Thinking requires the default temperature, so only send it otherwise
+        body["temperature"] = json!(temp);
+    }
+
+    body["max_tokens"] = json!(max_tokens);
+
+    if let Some(tools) = tools {
+        if !tools.is_empty() {
+            body["tools"] = json!(tools);
+        }
+    }
+
+    if let Some(tc) = tool_choice {
+        body["tool_choice"] = tc.clone();
+    }
+
+    if options.prompt_caching {
+        apply_cache_control(&mut body);
+    }
+
+    body
+}
+
+/// Mark the end of the tools, the system prompt and the conversation so far as
+/// cache breakpoints. Three of the four breakpoints the API allows; the next
+/// request then reads everything up to the previous turn from cache.
+pub fn apply_cache_control(body: &mut Value) {
+    let marker = json!({"type": "ephemeral"});
+
+    if let Some(last) = body
+        .get_mut("tools")
+        .and_then(|t| t.as_array_mut())
+        .and_then(|t| t.last_mut())
+    {
+        last["cache_control"] = marker.clone();
+    }
+
+    if let Some(last) = body
+        .get_mut("system")
+        .and_then(|s| s.as_array_mut())
+        .and_then(|s| s.last_mut())
+    {
+        last["cache_control"] = marker.clone();
+    }
+
+    if let Some(last) = body
+        .get_mut("messages")
+        .and_then(|m| m.as_array_mut())
+        .and_then(|m| m.last_mut())
+        .and_then(|m| m.get_mut("content"))
+        .and_then(|c| c.as_array_mut())
+        .and_then(|c| c.last_mut())
+    {
+        last["cache_control"] = marker;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thinking_and_cache_markers() {
+        let system = vec![json!({"type": "text", "text": "sys"})];
+        let messages = vec![json!({
+            "role": "user",
+            "content": [{"type": "text", "text": "hi"}],
+        })];
+        let tools = vec![json!({"name": "a"}), json!({"name": "b"})];
+        let options = RequestOptions {
+            thinking_budget: Some(2048),
+            prompt_caching: true,
+        };
+
+        let body = build_request_body(
+            &system,
+            &messages,
+            "claude-sonnet-4-5",
+            false,
+            Some(0.2),
+            Some(1024),
+            Some(&tools),
+            None,
+            &options,
+        );
+
+        assert_eq!(body["thinking"]["budget_tokens"], 2048);
+        assert!(body["max_tokens"].as_u64().unwrap() > 2048);
+        assert!(body.get("temperature").is_none());
+        assert!(body["tools"][0].get("cache_control").is_none());
+        assert_eq!(body["tools"][1]["cache_control"]["type"], "ephemeral");
+        assert_eq!(body["system"][0]["cache_control"]["type"], "ephemeral");
+        assert_eq!(body["messages"][0]["content"][0]["cache_control"]["type"], "ephemeral");
+    }
+}
@@ -0,0 +1,155 @@
+//! Native Rust Anthropic provider — response parsing.
+
+use crate::provider::thinking;
+use crate::provider::traits::{GenerateResponse, ToolInvocation};
+use crate::provider::usage::TokenUsage;
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+/// Parse a non-streaming Messages API response into `GenerateResponse` plus
+/// the reported token usage.
+///
+/// `thinking` blocks are joined into `reasoning`; any `tool_use` block turns
+/// the response into `ToolCalls`, and the signed `thinking`/`redacted_thinking`
+/// blocks are [attached](thinking::attach) to its first call so they can be
+/// sent back unchanged.
+pub fn parse_response(response_body: &str) -> Result<(GenerateResponse, Option<TokenUsage>)> {
+    let json: Value =
+        serde_json::from_str(response_body).context("Failed to parse Anthropic response JSON")?;
+
+    if json.get("type").and_then(|v| v.as_str()) == Some("error") {
+        return Err(api_error("Anthropic API error", &json));
+    }
+
+    let usage = json.get("usage").and_then(TokenUsage::from_anthropic);
+
+    let content = json
+        .get("content")
+        .and_then(|c| c.as_array())
+        .context("No content in Anthropic response")?;
+
+    let mut text_parts = Vec::new();
+    let mut thinking_parts = Vec::new();
+    let mut signed_blocks = Vec::new();
+    let mut calls = Vec::new();
+
+    for block in content {
+        match block.get("type").and_then(|v| v.as_str()) {
+            Some("text") => {
+                if let Some(text) = block.get("text").and_then(|v| v.as_str()) {
+                    text_parts.push(text.to_string());
+                }
+            }
+            Some("thinking") => {
+                if let Some(thinking) = block.get("thinking").and_then(|v| v.as_str()) {
+                    thinking_parts.push(thinking.to_string());
+                }
+                signed_blocks.push(block.clone());
+            }
+            Some("redacted_thinking") => signed_blocks.push(block.clone()),
+            Some("tool_use") => {
+                let str_field = |name: &str| {
+                    block
+                        .get(name)
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .to_string()
+                };
+                calls.push(ToolInvocation {
+                    id: str_field("id"),
+                    name: str_field("name"),
+                    arguments: block.get("input").cloned().unwrap_or_default(),
+                    provider_metadata: std::collections::HashMap::new(),
+                });
+            }
+            // Unknown block types carry nothing we surface
+            _ => {}
+        }
+    }
+
+    let reasoning = Some(thinking_parts.join("\n")).filter(|r| !r.is_empty());
+
+    if !calls.is_empty() {
+        thinking::attach(&mut calls, signed_blocks);
+        return Ok((GenerateResponse::ToolCalls { calls, reasoning }, usage));
+    }
+
+    let response = GenerateResponse::Content {
+        text: text_parts.join(""),
+        reasoning,
+    };
+    Ok((response, usage))
+}
+
+/// Extract `error.message` from an error payload, falling back to the raw JSON.
+pub fn error_message(json: &Value) -> String {
+    json.get("error")
+        .and_then(|e| {
+            let kind = e.get("type").and_then(|v| v.as_str()).unwrap_or("error");
+            e.get("message")
+                .and_then(|v| v.as_str())
+                .map(|m| format!("{}: {}", kind, m))
+        })
+        .unwrap_or_else(|| json.to_string())
+}
+
+/// Error for an Anthropic `error` payload, structured as an [`HttpError`]
+/// when the error type is a rate limit or server-side failure.
+///
+/// [`HttpError`]: crate::provider::HttpError
+pub fn api_error(context: &str, json: &Value) -> anyhow::Error {
+    use crate::provider::{HttpError, HttpErrorKind};
+
+    let message = format!("{}: {}", context, error_message(json));
+    let kind = json.pointer("/error/type").and_then(|v| v.as_str());
+    match kind {
+        Some("rate_limit_error") => HttpError::new(HttpErrorKind::RateLimited, Some(429), message).into(),
+        Some("overloaded_error") => HttpError::new(HttpErrorKind::Server, Some(529), message).into(),
+        Some("api_error") => HttpError::new(HttpErrorKind::Server, Some(500), message).into(),
+        _ => anyhow::anyhow!(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_thinking_and_tool_use() {
+        let body = r#"{
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "content": [
+                {"type": "thinking", "thinking": "Need the file.", "signature": "sig"},
+                {"type": "text", "text": "Reading it."},
+                {"type": "tool_use", "id": "toolu_1", "name": "read_file", "input": {"path": "a.rs"}}
+            ],
+            "stop_reason": "tool_use",
+            "usage": {"input_tokens": 10, "cache_read_input_tokens": 90, "output_tokens": 5}
+        }"#;
+
+        let (response, usage) = parse_response(body).unwrap();
+        let usage = usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 100);
+        assert_eq!(usage.cached_tokens, 90);
+
+        match response {
+            GenerateResponse::ToolCalls { calls, reasoning } => {
+                assert_eq!(calls.len(), 1);
+                assert_eq!(calls[0].id, "toolu_1");
+                assert_eq!(calls[0].arguments["path"], "a.rs");
+                assert_eq!(reasoning.as_deref(), Some("Need the file."));
+                let blocks = thinking::from_metadata(&calls[0].provider_metadata).unwrap();
+                assert_eq!(blocks[0]["signature"], "sig");
+            }
+            other => panic!("expected tool calls, got {:?}", other),
+        }
+
+        let err = parse_response(
+            r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("overloaded_error: Overloaded"));
+    }
+}
@@ -0,0 +1,205 @@
+//! Native Rust Anthropic provider — SSE streaming parser.
+//!
+//! The Messages API streams typed events (`message_start`,
+//! `content_block_start`, `content_block_delta`, `content_block_stop`,
+//! `message_delta`, `message_stop`, `ping`, `error`). Every event repeats its
+//! type inside the JSON payload, so only the `data:` line is needed.
+
+use crate::provider::thinking;
+use crate::provider::usage::TokenUsage;
+use crate::provider::StreamChunk;
+use anyhow::Result;
+use serde_json::{json, Value};
+
+/// State carried across the events of one stream.
+#[derive(Debug, Default)]
+pub struct StreamState {
+    /// Latest reported usage
+    pub usage: Option<TokenUsage>,
+    /// Thinking blocks seen so far, by content-block index
+    thinking: Vec<(usize, Value)>,
+}
+
+impl StreamState {
+    fn thinking_block(&mut self, index: usize) -> Option<&mut Value> {
+        self.thinking
+            .iter_mut()
+            .find(|(i, _)| *i == index)
+            .map(|(_, block)| block)
+    }
+}
+
+/// Parse a single SSE event payload (the `data: …` line content) into zero or more `StreamChunk`s.
+///
+/// Tool calls keep the content-block index, so a `tool_use` at index 1 after a
+/// text block at index 0 is a sparse index for the accumulator. An `error`
+/// event is returned as `Err`. Prompt usage arrives with `message_start` and
+/// the running output count with `message_delta`; both update `state.usage`.
+/// Thinking blocks are rebuilt with their signatures and sent as one
+/// [`thinking::trailer`] reasoning chunk just before `Done`.
+pub fn parse_sse_event(data: &str, state: &mut StreamState) -> Result<Vec<StreamChunk>> {
+    let json: Value = match serde_json::from_str(data.trim()) {
+        Ok(json) => json,
+        Err(_) => return Ok(Vec::new()),
+    };
+
+    let index = json.get("index").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+    let mut chunks = Vec::new();
+
+    match json.get("type").and_then(|v| v.as_str()).unwrap_or("") {
+        "content_block_start" => {
+            let block = json.get("content_block").unwrap_or(&Value::Null);
+            match block.get("type").and_then(|v| v.as_str()) {
+                Some("tool_use") => {
+                    chunks.push(StreamChunk::ToolCallDelta {
+                        index,
+                        id: block.get("id").and_then(|v| v.as_str()).map(String::from),
+                        name: block.get("name").and_then(|v| v.as_str()).map(String::from),
+                        arguments_delta: None,
+                    });
+                }
+                Some("text") => {
+                    if let Some(text) = block.get("text").and_then(|v| v.as_str()) {
+                        if !text.is_empty() {
+                            chunks.push(StreamChunk::Text(text.to_string()));
+                        }
+                    }
+                }
+                Some("thinking") => {
+                    state.thinking.push((index, json!({"type": "thinking", "thinking": "", "signature": ""})));
+                }
+                Some("redacted_thinking") => state.thinking.push((index, block.clone())),
+                _ => {}
+            }
+        }
+
+        "content_block_delta" => {
+            let delta = json.get("delta").unwrap_or(&Value::Null);
+            let field = |name: &str| {
+                delta
+                    .get(name)
+                    .and_then(|v| v.as_str())
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_string())
+            };
+            match delta.get("type").and_then(|v| v.as_str()) {
+                Some("text_delta") => {
+                    if let Some(text) = field("text") {
+                        chunks.push(StreamChunk::Text(text));
+                    }
+                }
+                Some("thinking_delta") => {
+                    if let Some(thinking) = field("thinking") {
+                        if let Some(block) = state.thinking_block(index) {
+                            let text = format!("{}{}", block["thinking"].as_str().unwrap_or(""), thinking);
+                            block["thinking"] = Value::String(text);
+                        }
+                        chunks.push(StreamChunk::Reasoning(thinking));
+                    }
+                }
+                Some("signature_delta") => {
+                    if let (Some(signature), Some(block)) = (field("signature"), state.thinking_block(index)) {
+                        let full = format!("{}{}", block["signature"].as_str().unwrap_or(""), signature);
+                        block["signature"] = Value::String(full);
+                    }
+                }
+                Some("input_json_delta") => {
+                    if let Some(partial) = field("partial_json") {
+                        chunks.push(StreamChunk::ToolCallDelta {
+                            index,
+                            id: None,
+                            name: None,
+                            arguments_delta: Some(partial),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        "message_start" => {
+            if let Some(reported) = json.pointer("/message/usage").and_then(TokenUsage::from_anthropic) {
+                state.usage = Some(reported);
+            }
+        }
+
+        "message_delta" => {
+            if let Some(output) = json.pointer("/usage/output_tokens").and_then(|v| v.as_u64()) {
+                state.usage.get_or_insert_with(TokenUsage::default).completion_tokens = output;
+            }
+        }
+
+        "message_stop" => {
+            if !state.thinking.is_empty() {
+                let blocks: Vec<Value> = state.thinking.drain(..).map(|(_, block)| block).collect();
+                chunks.push(StreamChunk::Reasoning(thinking::trailer(&blocks)));
+            }
+            chunks.push(StreamChunk::Done);
+        }
+
+        "error" => {
+            return Err(super::response::api_error("Anthropic stream error", &json));
+        }
+
+        // content_block_stop, ping
+        _ => {}
+    }
+
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stream_events() {
+        let events = [
+            r#"{"type":"message_start","message":{"id":"msg_1","content":[],"usage":{"input_tokens":12,"output_tokens":1}}}"#,
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"thinking","thinking":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"hmm"}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"signature_delta","signature":"x"}}"#,
+            r#"{"type":"content_block_start","index":1,"content_block":{"type":"tool_use","id":"toolu_1","name":"ls","input":{}}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"{\"dir\":"}}"#,
+            r#"{"type":"ping"}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"tool_use"},"usage":{"output_tokens":42}}"#,
+            r#"{"type":"message_stop"}"#,
+        ];
+
+        let mut state = StreamState::default();
+        let chunks: Vec<StreamChunk> = events
+            .iter()
+            .flat_map(|e| parse_sse_event(e, &mut state).unwrap())
+            .collect();
+
+        assert_eq!(chunks.len(), 5);
+        assert!(matches!(&chunks[0], StreamChunk::Reasoning(r) if r == "hmm"));
+        assert!(matches!(
+            &chunks[1],
+            StreamChunk::ToolCallDelta { index: 1, id: Some(id), name: Some(name), arguments_delta: None }
+                if id == "toolu_1" && name == "ls"
+        ));
+        assert!(matches!(
+            &chunks[2],
+            StreamChunk::ToolCallDelta { index: 1, arguments_delta: Some(a), .. } if a == "{\"dir\":"
+        ));
+        // The signed block follows the readable deltas as a trailer
+        let StreamChunk::Reasoning(trailer) = &chunks[3] else {
+            panic!("expected a thinking trailer, got {:?}", chunks[3]);
+        };
+        assert!(thinking::is_trailer(trailer));
+        let (_, blocks) = thinking::split(trailer);
+        assert_eq!(
+            blocks.unwrap(),
+            vec![json!({"type": "thinking", "thinking": "hmm", "signature": "x"})]
+        );
+        assert!(matches!(chunks[4], StreamChunk::Done));
+
+        let usage = state.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 12);
+        assert_eq!(usage.completion_tokens, 42);
+
+        let error = r#"{"type":"error","error":{"type":"api_error","message":"boom"}}"#;
+        assert!(parse_sse_event(error, &mut StreamState::default()).is_err());
+    }
+}
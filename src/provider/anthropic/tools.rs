@@ -0,0 +1,31 @@
+//! Native Rust Anthropic provider — tools conversion utilities.
+
+use crate::provider::types::tools::{InternalToolDefinition, ToolChoice};
+use serde_json::{json, Value};
+
+/// Convert `InternalToolDefinition` array → Anthropic tools JSON array.
+pub fn tools_to_anthropic(tools: &[InternalToolDefinition]) -> Vec<Value> {
+    tools
+        .iter()
+        .map(|t| {
+            json!({
+                "name": t.name,
+                "description": t.description,
+                "input_schema": t.parameters,
+            })
+        })
+        .collect()
+}
+
+/// Convert `ToolChoice` → Anthropic tool_choice value.
+pub fn tool_choice_to_anthropic(choice: &ToolChoice) -> Value {
+    match choice {
+        ToolChoice::Auto => json!({"type": "auto"}),
+        ToolChoice::Required => json!({"type": "any"}),
+        ToolChoice::None => json!({"type": "none"}),
+        ToolChoice::Specific { name } => json!({
+            "type": "tool",
+            "name": name,
+        }),
+    }
+}
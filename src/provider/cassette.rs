@@ -0,0 +1,871 @@
+//! Record/replay providers for running agents without a live LLM.
+//!
+//! [`RecordingProvider`] wraps any [`LlmProvider`] and appends every
+//! request/response pair (including streamed chunks and their timing) to a
+//! JSON cassette file. [`ReplayProvider`] serves a cassette back, matching
+//! requests on a hash of their normalized messages, model and tool names.
+//!
+//! With the `provider-replay` feature, [`ProviderFactory`](super::ProviderFactory)
+//! wraps the configured provider when `LLM_RECORD=<path>` is set and replays
+//! instead of calling any provider when `LLM_REPLAY=<path>` is set, so whole
+//! `run_workflow` runs can be replayed in CI. `LLM_REPLAY_MATCH=sequential`
+//! selects [`ReplayMatch::Sequential`] and `LLM_REPLAY_REALTIME=1` keeps the
+//! recorded chunk timing.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use abk::provider::{ReplayProvider, RecordingProvider};
+//!
+//! // Record a live session
+//! let live = ProviderFactory::create(&env).await?;
+//! let provider = RecordingProvider::new(live, "tests/cassettes/fix-bug.json")?;
+//!
+//! // Later, offline
+//! let provider = ReplayProvider::from_file("tests/cassettes/fix-bug.json")?;
+//! ```
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::provider::thinking;
+use crate::provider::traits::{GenerateResponse, LlmProvider, StreamChunk, StreamingResponse, ToolInvocation};
+use crate::provider::types::{GenerateConfig, InternalMessage};
+use crate::provider::usage::TokenUsage;
+
+/// Current cassette format version.
+pub const CASSETTE_VERSION: u32 = 1;
+
+/// A recorded session: request/response pairs in call order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cassette {
+    /// Format version
+    pub version: u32,
+    /// Name of the recorded provider
+    pub provider: String,
+    /// Default model of the recorded provider
+    pub model: String,
+    /// Recorded calls, in order
+    pub interactions: Vec<Interaction>,
+}
+
+/// One recorded provider call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    /// Hash of the normalized request (see [`request_key`])
+    pub key: String,
+    /// Whether the call was streamed
+    pub streaming: bool,
+    /// Normalized request, kept for diffing when replay fails to match
+    pub request: Value,
+    /// What the provider returned
+    pub response: RecordedResponse,
+    /// Usage the provider reported for the call
+    #[serde(default)]
+    pub usage: Option<TokenUsage>,
+    /// Wall-clock duration of the call
+    pub duration_ms: u64,
+}
+
+/// A recorded provider response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecordedResponse {
+    /// Non-streaming text response
+    Content {
+        /// Response text
+        text: String,
+        /// Reasoning content
+        reasoning: Option<String>,
+    },
+    /// Non-streaming tool calls
+    ToolCalls {
+        /// Tool invocations
+        calls: Vec<RecordedToolCall>,
+        /// Reasoning content
+        reasoning: Option<String>,
+    },
+    /// Streamed chunks with their offsets from the start of the call
+    Stream {
+        /// Chunks in arrival order
+        events: Vec<StreamEvent>,
+    },
+    /// The call failed before producing a response
+    Error {
+        /// Error message
+        message: String,
+    },
+}
+
+/// A recorded tool invocation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedToolCall {
+    /// Tool call ID
+    pub id: String,
+    /// Tool name
+    pub name: String,
+    /// Parsed arguments
+    pub arguments: Value,
+    /// Provider-specific metadata
+    #[serde(default)]
+    pub provider_metadata: std::collections::HashMap<String, String>,
+}
+
+/// A streamed chunk or stream error.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamEvent {
+    /// Milliseconds since the call started
+    pub offset_ms: u64,
+    /// Chunk, or `None` for an error
+    #[serde(default)]
+    pub chunk: Option<StreamChunk>,
+    /// Error message when the stream failed
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl Cassette {
+    /// Create an empty cassette for `provider`.
+    pub fn new(provider: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            version: CASSETTE_VERSION,
+            provider: provider.into(),
+            model: model.into(),
+            interactions: Vec::new(),
+        }
+    }
+
+    /// Load a cassette from a JSON file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read cassette: {}", path.display()))?;
+        let cassette: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid cassette: {}", path.display()))?;
+        if cassette.version > CASSETTE_VERSION {
+            anyhow::bail!(
+                "Cassette {} has version {}, newest supported is {}",
+                path.display(),
+                cassette.version,
+                CASSETTE_VERSION
+            );
+        }
+        Ok(cassette)
+    }
+
+    /// Atomically write the cassette as pretty JSON.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create cassette directory: {}", parent.display()))?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write cassette: {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to write cassette: {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Normalized form of a request: messages, requested model and tool names.
+///
+/// Volatile settings (temperature, token limits, request IDs, streaming) are
+/// left out so that they don't affect matching.
+pub fn normalize_request(messages: &[InternalMessage], config: &GenerateConfig) -> Value {
+    let mut tools: Vec<&str> = config
+        .tools
+        .iter()
+        .flatten()
+        .map(|t| t.name.as_str())
+        .collect();
+    tools.sort_unstable();
+
+    serde_json::json!({
+        "model": config.model,
+        "messages": normalize_value(serde_json::to_value(messages).unwrap_or(Value::Null)),
+        "tools": tools,
+    })
+}
+
+/// Hash of [`normalize_request`], used to match replayed requests.
+pub fn request_key(messages: &[InternalMessage], config: &GenerateConfig) -> String {
+    let mut canonical = String::new();
+    write_canonical(&normalize_request(messages, config), &mut canonical);
+    let digest = Sha256::digest(canonical.as_bytes());
+    digest.iter().take(16).fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{:02x}", b);
+        out
+    })
+}
+
+/// Trim strings and collapse whitespace runs so formatting noise doesn't
+/// change the key.
+fn normalize_value(value: Value) -> Value {
+    match value {
+        Value::String(s) => Value::String(s.split_whitespace().collect::<Vec<_>>().join(" ")),
+        Value::Array(items) => Value::Array(items.into_iter().map(normalize_value).collect()),
+        Value::Object(map) => Value::Object(map.into_iter().map(|(k, v)| (k, normalize_value(v))).collect()),
+        other => other,
+    }
+}
+
+/// Serialize with sorted object keys, independent of map ordering.
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(&map[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+fn record_response(response: &GenerateResponse) -> RecordedResponse {
+    match response {
+        GenerateResponse::Content { text, reasoning } => RecordedResponse::Content {
+            text: text.clone(),
+            reasoning: reasoning.clone(),
+        },
+        GenerateResponse::ToolCalls { calls, reasoning } => RecordedResponse::ToolCalls {
+            calls: calls
+                .iter()
+                .map(|c| RecordedToolCall {
+                    id: c.id.clone(),
+                    name: c.name.clone(),
+                    arguments: c.arguments.clone(),
+                    provider_metadata: c.provider_metadata.clone(),
+                })
+                .collect(),
+            reasoning: reasoning.clone(),
+        },
+    }
+}
+
+/// Rebuild a non-streaming response, accumulating recorded chunks if needed.
+fn replay_response(response: &RecordedResponse) -> Result<GenerateResponse> {
+    match response {
+        RecordedResponse::Content { text, reasoning } => Ok(GenerateResponse::Content {
+            text: text.clone(),
+            reasoning: reasoning.clone(),
+        }),
+        RecordedResponse::ToolCalls { calls, reasoning } => Ok(GenerateResponse::ToolCalls {
+            calls: calls
+                .iter()
+                .map(|c| ToolInvocation {
+                    id: c.id.clone(),
+                    name: c.name.clone(),
+                    arguments: c.arguments.clone(),
+                    provider_metadata: c.provider_metadata.clone(),
+                })
+                .collect(),
+            reasoning: reasoning.clone(),
+        }),
+        RecordedResponse::Stream { events } => {
+            let mut accumulator = umf::StreamingAccumulator::new();
+            for event in events {
+                if let Some(ref error) = event.error {
+                    anyhow::bail!("{}", error);
+                }
+                if let Some(ref chunk) = event.chunk {
+                    if accumulator.process_chunk(chunk.clone()) {
+                        break;
+                    }
+                }
+            }
+            let accumulated = accumulator.finish();
+            if accumulated.tool_calls.is_empty() {
+                let reasoning = thinking::detach(&accumulated.reasoning, &mut []);
+                Ok(GenerateResponse::Content { text: accumulated.text, reasoning })
+            } else {
+                let mut calls: Vec<ToolInvocation> = accumulated
+                    .tool_calls
+                    .into_iter()
+                    .map(|c| ToolInvocation {
+                        id: c.id,
+                        arguments: serde_json::from_str(&c.function.arguments)
+                            .unwrap_or(Value::String(c.function.arguments)),
+                        name: c.function.name,
+                        provider_metadata: Default::default(),
+                    })
+                    .collect();
+                let reasoning = thinking::detach(&accumulated.reasoning, &mut calls);
+                Ok(GenerateResponse::ToolCalls { calls, reasoning })
+            }
+        }
+        RecordedResponse::Error { message } => Err(anyhow::anyhow!("{}", message)),
+    }
+}
+
+/// Rebuild stream events, splitting non-streaming responses into chunks.
+fn replay_events(response: &RecordedResponse) -> Result<Vec<StreamEvent>> {
+    let chunk = |chunk| StreamEvent { offset_ms: 0, chunk: Some(chunk), error: None };
+    let mut events = Vec::new();
+    match response {
+        RecordedResponse::Stream { events } => return Ok(events.clone()),
+        RecordedResponse::Error { message } => anyhow::bail!("{}", message),
+        RecordedResponse::Content { text, reasoning } => {
+            if let Some(reasoning) = reasoning {
+                events.push(chunk(StreamChunk::Reasoning(reasoning.clone())));
+            }
+            events.push(chunk(StreamChunk::Text(text.clone())));
+        }
+        RecordedResponse::ToolCalls { calls, reasoning } => {
+            if let Some(reasoning) = reasoning {
+                events.push(chunk(StreamChunk::Reasoning(reasoning.clone())));
+            }
+            for (index, call) in calls.iter().enumerate() {
+                events.push(chunk(StreamChunk::ToolCallDelta {
+                    index,
+                    id: Some(call.id.clone()),
+                    name: Some(call.name.clone()),
+                    arguments_delta: Some(call.arguments.to_string()),
+                }));
+            }
+        }
+    }
+    events.push(chunk(StreamChunk::Done));
+    Ok(events)
+}
+
+/// Cassette being written by a [`RecordingProvider`].
+struct Recorder {
+    path: PathBuf,
+    cassette: Cassette,
+    /// Usage taken from the inner provider, handed out by `take_usage`
+    pending_usage: Option<TokenUsage>,
+}
+
+impl Recorder {
+    fn push(&mut self, interaction: Interaction) {
+        self.pending_usage = interaction.usage;
+        self.cassette.interactions.push(interaction);
+        if let Err(e) = self.cassette.save(&self.path) {
+            crate::observability::tee_eprintln(&format!("Warning: Failed to save cassette: {:#}", e));
+        }
+    }
+}
+
+/// Provider wrapper that records every call to a cassette file.
+///
+/// The cassette is rewritten after each call, so a crashed session still
+/// leaves a usable recording. An existing file at `path` is replaced.
+pub struct RecordingProvider {
+    inner: Arc<dyn LlmProvider>,
+    recorder: Arc<Mutex<Recorder>>,
+}
+
+impl RecordingProvider {
+    /// Wrap `inner`, recording to `path`.
+    pub fn new(inner: Box<dyn LlmProvider>, path: impl Into<PathBuf>) -> Result<Self> {
+        let inner: Arc<dyn LlmProvider> = Arc::from(inner);
+        let path = path.into();
+        let cassette = Cassette::new(inner.provider_name(), inner.default_model());
+        cassette.save(&path)?;
+        Ok(Self {
+            inner,
+            recorder: Arc::new(Mutex::new(Recorder {
+                path,
+                cassette,
+                pending_usage: None,
+            })),
+        })
+    }
+
+    /// Number of calls recorded so far.
+    pub fn interaction_count(&self) -> usize {
+        self.lock().cassette.interactions.len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Recorder> {
+        self.recorder.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Collects a live stream's chunks and records them when the stream
+/// completes or is dropped.
+struct StreamRecorder {
+    inner: Arc<dyn LlmProvider>,
+    recorder: Arc<Mutex<Recorder>>,
+    key: String,
+    request: Value,
+    started: Instant,
+    events: Vec<StreamEvent>,
+    finished: bool,
+}
+
+impl StreamRecorder {
+    fn observe(&mut self, item: &Result<StreamChunk>) {
+        let offset_ms = self.started.elapsed().as_millis() as u64;
+        match item {
+            Ok(chunk) => {
+                self.events.push(StreamEvent { offset_ms, chunk: Some(chunk.clone()), error: None });
+                if matches!(chunk, StreamChunk::Done) {
+                    self.finish();
+                }
+            }
+            Err(e) => {
+                self.events.push(StreamEvent { offset_ms, chunk: None, error: Some(format!("{:#}", e)) });
+                self.finish();
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        let interaction = Interaction {
+            key: std::mem::take(&mut self.key),
+            streaming: true,
+            request: self.request.take(),
+            response: RecordedResponse::Stream { events: std::mem::take(&mut self.events) },
+            usage: self.inner.take_usage(),
+            duration_ms: self.started.elapsed().as_millis() as u64,
+        };
+        self.recorder.lock().unwrap_or_else(|e| e.into_inner()).push(interaction);
+    }
+}
+
+impl Drop for StreamRecorder {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for RecordingProvider {
+    async fn generate(&self, messages: Vec<InternalMessage>, config: &GenerateConfig) -> Result<GenerateResponse> {
+        let key = request_key(&messages, config);
+        let request = normalize_request(&messages, config);
+        let started = Instant::now();
+        let result = self.inner.generate(messages, config).await;
+
+        let response = match result {
+            Ok(ref response) => record_response(response),
+            Err(ref e) => RecordedResponse::Error { message: format!("{:#}", e) },
+        };
+        self.lock().push(Interaction {
+            key,
+            streaming: false,
+            request,
+            response,
+            usage: self.inner.take_usage(),
+            duration_ms: started.elapsed().as_millis() as u64,
+        });
+        result
+    }
+
+    async fn generate_stream(&self, messages: Vec<InternalMessage>, config: &GenerateConfig) -> Result<StreamingResponse> {
+        let key = request_key(&messages, config);
+        let request = normalize_request(&messages, config);
+        let started = Instant::now();
+
+        let stream = match self.inner.generate_stream(messages, config).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                self.lock().push(Interaction {
+                    key,
+                    streaming: true,
+                    request,
+                    response: RecordedResponse::Error { message: format!("{:#}", e) },
+                    usage: None,
+                    duration_ms: started.elapsed().as_millis() as u64,
+                });
+                return Err(e);
+            }
+        };
+
+        let mut recorder = StreamRecorder {
+            inner: self.inner.clone(),
+            recorder: self.recorder.clone(),
+            key,
+            request,
+            started,
+            events: Vec::new(),
+            finished: false,
+        };
+        Ok(Box::pin(stream.map(move |item| {
+            recorder.observe(&item);
+            item
+        })))
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+
+    fn default_model(&self) -> String {
+        self.inner.default_model()
+    }
+
+    fn take_usage(&self) -> Option<TokenUsage> {
+        self.lock().pending_usage.take().or_else(|| self.inner.take_usage())
+    }
+
+    fn take_fallbacks(&self) -> Vec<crate::provider::ProviderFallback> {
+        self.inner.take_fallbacks()
+    }
+}
+
+/// How a [`ReplayProvider`] picks the interaction for a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplayMatch {
+    /// First unused interaction whose request hash matches (default).
+    /// Identical requests are served in recorded order.
+    #[default]
+    Hash,
+    /// Next interaction in recorded order, ignoring the request. For
+    /// prompts with content that changes between runs (timestamps, paths).
+    Sequential,
+}
+
+impl std::str::FromStr for ReplayMatch {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "hash" => Ok(Self::Hash),
+            "sequential" => Ok(Self::Sequential),
+            other => anyhow::bail!("Unknown replay match mode '{}' (expected 'hash' or 'sequential')", other),
+        }
+    }
+}
+
+/// Provider that serves responses from a cassette.
+///
+/// Streaming and non-streaming calls can be served from either kind of
+/// recording. Unmatched requests fail with the request hash so the
+/// cassette can be inspected or re-recorded.
+pub struct ReplayProvider {
+    cassette: Cassette,
+    match_mode: ReplayMatch,
+    realtime: bool,
+    used: Mutex<Vec<bool>>,
+    last_usage: Mutex<Option<TokenUsage>>,
+}
+
+impl ReplayProvider {
+    /// Replay the cassette at `path`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(Cassette::load(path.as_ref())?))
+    }
+
+    /// Replay an in-memory cassette.
+    pub fn new(cassette: Cassette) -> Self {
+        let used = vec![false; cassette.interactions.len()];
+        Self {
+            cassette,
+            match_mode: ReplayMatch::default(),
+            realtime: false,
+            used: Mutex::new(used),
+            last_usage: Mutex::new(None),
+        }
+    }
+
+    /// Set how requests are matched to interactions.
+    pub fn with_match(mut self, match_mode: ReplayMatch) -> Self {
+        self.match_mode = match_mode;
+        self
+    }
+
+    /// Reproduce the recorded chunk timing for streamed responses.
+    /// By default chunks are yielded immediately.
+    pub fn with_realtime(mut self, realtime: bool) -> Self {
+        self.realtime = realtime;
+        self
+    }
+
+    /// Number of interactions not yet served.
+    pub fn remaining(&self) -> usize {
+        self.used.lock().unwrap_or_else(|e| e.into_inner()).iter().filter(|u| !**u).count()
+    }
+
+    fn next_interaction(&self, messages: &[InternalMessage], config: &GenerateConfig) -> Result<&Interaction> {
+        let key = request_key(messages, config);
+        let mut used = self.used.lock().unwrap_or_else(|e| e.into_inner());
+        let position = match self.match_mode {
+            ReplayMatch::Hash => self
+                .cassette
+                .interactions
+                .iter()
+                .enumerate()
+                .position(|(i, interaction)| !used[i] && interaction.key == key),
+            ReplayMatch::Sequential => used.iter().position(|u| !*u),
+        };
+        let Some(index) = position else {
+            anyhow::bail!(
+                "No recorded interaction matches request {} ({} of {} interactions unused)",
+                key,
+                used.iter().filter(|u| !**u).count(),
+                used.len()
+            );
+        };
+        used[index] = true;
+
+        let interaction = &self.cassette.interactions[index];
+        *self.last_usage.lock().unwrap_or_else(|e| e.into_inner()) = interaction.usage;
+        Ok(interaction)
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for ReplayProvider {
+    async fn generate(&self, messages: Vec<InternalMessage>, config: &GenerateConfig) -> Result<GenerateResponse> {
+        replay_response(&self.next_interaction(&messages, config)?.response)
+    }
+
+    async fn generate_stream(&self, messages: Vec<InternalMessage>, config: &GenerateConfig) -> Result<StreamingResponse> {
+        let events = replay_events(&self.next_interaction(&messages, config)?.response)?;
+        let realtime = self.realtime;
+        let started = Instant::now();
+
+        Ok(Box::pin(futures_util::stream::iter(events).then(move |event| async move {
+            if realtime {
+                let due = started + Duration::from_millis(event.offset_ms);
+                tokio::time::sleep_until(due.into()).await;
+            }
+            match (event.chunk, event.error) {
+                (_, Some(error)) => Err(anyhow::anyhow!("{}", error)),
+                (Some(chunk), None) => Ok(chunk),
+                (None, None) => Ok(StreamChunk::Done),
+            }
+        })))
+    }
+
+    fn provider_name(&self) -> &str {
+        &self.cassette.provider
+    }
+
+    fn default_model(&self) -> String {
+        self.cassette.model.clone()
+    }
+
+    fn take_usage(&self) -> Option<TokenUsage> {
+        self.last_usage.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::types::InternalToolDefinition;
+
+    /// Provider returning a tool call, then text, reporting usage for each.
+    struct ScriptedProvider {
+        calls: Mutex<usize>,
+        usage: Mutex<Option<TokenUsage>>,
+    }
+
+    impl ScriptedProvider {
+        fn new() -> Self {
+            Self { calls: Mutex::new(0), usage: Mutex::new(None) }
+        }
+
+        fn next(&self) -> usize {
+            let mut calls = self.calls.lock().unwrap();
+            *calls += 1;
+            *self.usage.lock().unwrap() = Some(TokenUsage {
+                prompt_tokens: 100 * *calls as u64,
+                completion_tokens: 10,
+                ..Default::default()
+            });
+            *calls
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for ScriptedProvider {
+        async fn generate(&self, _messages: Vec<InternalMessage>, _config: &GenerateConfig) -> Result<GenerateResponse> {
+            Ok(match self.next() {
+                1 => GenerateResponse::ToolCalls {
+                    calls: vec![ToolInvocation {
+                        id: "call_1".to_string(),
+                        name: "read_file".to_string(),
+                        arguments: serde_json::json!({"path": "src/lib.rs"}),
+                        provider_metadata: Default::default(),
+                    }],
+                    reasoning: None,
+                },
+                _ => GenerateResponse::Content { text: "done".to_string(), reasoning: None },
+            })
+        }
+
+        async fn generate_stream(&self, _messages: Vec<InternalMessage>, _config: &GenerateConfig) -> Result<StreamingResponse> {
+            self.next();
+            let chunks = vec![
+                Ok(StreamChunk::Reasoning("thinking".to_string())),
+                Ok(StreamChunk::Text("Hello".to_string())),
+                Ok(StreamChunk::Text(" world".to_string())),
+                Ok(StreamChunk::Done),
+            ];
+            Ok(Box::pin(futures_util::stream::iter(chunks)))
+        }
+
+        fn provider_name(&self) -> &str {
+            "scripted"
+        }
+
+        fn default_model(&self) -> String {
+            "scripted-1".to_string()
+        }
+
+        fn take_usage(&self) -> Option<TokenUsage> {
+            self.usage.lock().unwrap().take()
+        }
+    }
+
+    fn config() -> GenerateConfig {
+        GenerateConfig {
+            tools: Some(vec![InternalToolDefinition::new("read_file", "Read a file", serde_json::json!({}))]),
+            ..GenerateConfig::new()
+        }
+    }
+
+    #[test]
+    fn test_request_key_normalization() {
+        let a = vec![InternalMessage::system("You are helpful."), InternalMessage::user("Fix  the bug\n")];
+        let b = vec![InternalMessage::system("You are helpful."), InternalMessage::user("Fix the bug")];
+        let c = vec![InternalMessage::system("You are helpful."), InternalMessage::user("Fix another bug")];
+
+        let mut other_settings = config();
+        other_settings.temperature = 0.0;
+        other_settings.x_request_id = Some("turn-2".to_string());
+
+        assert_eq!(request_key(&a, &config()), request_key(&b, &other_settings));
+        assert_ne!(request_key(&a, &config()), request_key(&c, &config()));
+        assert_ne!(request_key(&a, &config()), request_key(&a, &GenerateConfig::new()));
+    }
+
+    #[test]
+    fn test_replay_match_from_str() {
+        assert_eq!("hash".parse::<ReplayMatch>().unwrap(), ReplayMatch::Hash);
+        assert_eq!("Sequential".parse::<ReplayMatch>().unwrap(), ReplayMatch::Sequential);
+        assert!("random".parse::<ReplayMatch>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+        let first = vec![InternalMessage::user("Read lib.rs")];
+        let second = vec![InternalMessage::user("Read lib.rs"), InternalMessage::assistant("Read it")];
+
+        let recorder = RecordingProvider::new(Box::new(ScriptedProvider::new()), &path).unwrap();
+        recorder.generate(first.clone(), &config()).await.unwrap();
+        assert_eq!(recorder.take_usage().unwrap().prompt_tokens, 100);
+        recorder.generate(second.clone(), &config()).await.unwrap();
+        assert_eq!(recorder.interaction_count(), 2);
+
+        // Served out of order, matched by request hash
+        let replay = ReplayProvider::from_file(&path).unwrap();
+        assert_eq!(replay.provider_name(), "scripted");
+        match replay.generate(second, &config()).await.unwrap() {
+            GenerateResponse::Content { text, .. } => assert_eq!(text, "done"),
+            other => panic!("unexpected response: {:?}", other),
+        }
+        assert_eq!(replay.take_usage().unwrap().prompt_tokens, 200);
+        match replay.generate(first.clone(), &config()).await.unwrap() {
+            GenerateResponse::ToolCalls { calls, .. } => {
+                assert_eq!(calls[0].id, "call_1");
+                assert_eq!(calls[0].arguments["path"], "src/lib.rs");
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+        assert_eq!(replay.remaining(), 0);
+
+        let err = replay.generate(first, &config()).await.unwrap_err();
+        assert!(err.to_string().contains("No recorded interaction matches"));
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay_stream() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stream.json");
+        let messages = vec![InternalMessage::user("Say hello")];
+
+        let recorder = RecordingProvider::new(Box::new(ScriptedProvider::new()), &path).unwrap();
+        let chunks: Vec<_> = recorder.generate_stream(messages.clone(), &config()).await.unwrap().collect().await;
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(recorder.take_usage().unwrap().completion_tokens, 10);
+
+        let cassette = Cassette::load(&path).unwrap();
+        assert!(cassette.interactions[0].streaming);
+        assert!(matches!(cassette.interactions[0].response, RecordedResponse::Stream { ref events } if events.len() == 4));
+
+        // Replayed as a stream
+        let replay = ReplayProvider::from_file(&path).unwrap().with_match(ReplayMatch::Sequential);
+        let replayed: Vec<_> = replay.generate_stream(messages.clone(), &config()).await.unwrap().collect().await;
+        assert!(matches!(replayed[1], Ok(StreamChunk::Text(ref t)) if t == "Hello"));
+        assert!(matches!(replayed[3], Ok(StreamChunk::Done)));
+
+        // And accumulated for a non-streaming call
+        let replay = ReplayProvider::from_file(&path).unwrap();
+        match replay.generate(messages, &config()).await.unwrap() {
+            GenerateResponse::Content { text, reasoning } => {
+                assert_eq!(text, "Hello world");
+                assert_eq!(reasoning.as_deref(), Some("thinking"));
+            }
+            other => panic!("unexpected response: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_replayed_as_stream() {
+        let mut cassette = Cassette::new("scripted", "scripted-1");
+        cassette.interactions.push(Interaction {
+            key: String::new(),
+            streaming: false,
+            request: Value::Null,
+            response: RecordedResponse::ToolCalls {
+                calls: vec![RecordedToolCall {
+                    id: "call_1".to_string(),
+                    name: "read_file".to_string(),
+                    arguments: serde_json::json!({"path": "a.rs"}),
+                    provider_metadata: Default::default(),
+                }],
+                reasoning: None,
+            },
+            usage: None,
+            duration_ms: 0,
+        });
+
+        let replay = ReplayProvider::new(cassette).with_match(ReplayMatch::Sequential);
+        let stream = replay.generate_stream(vec![], &config()).await.unwrap();
+        let mut accumulator = umf::StreamingAccumulator::new();
+        for chunk in stream.collect::<Vec<_>>().await {
+            accumulator.process_chunk(chunk.unwrap());
+        }
+        let accumulated = accumulator.finish();
+        assert_eq!(accumulated.tool_calls[0].function.name, "read_file");
+        assert_eq!(accumulated.tool_calls[0].function.arguments, r#"{"path":"a.rs"}"#);
+    }
+}
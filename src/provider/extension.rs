@@ -7,10 +7,11 @@ use crate::config::EnvironmentLoader;
 use crate::extension::ExtensionManager;
 use crate::provider::traits::{GenerateResponse, LlmProvider, StreamingResponse, ToolInvocation};
 use crate::provider::types::{GenerateConfig, InternalMessage};
+use crate::provider::usage::TokenUsage;
+use crate::provider::openai::HttpClient;
 use anyhow::{Context, Result};
 use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
 use tokio::sync::Mutex;
 
 /// Conditional debug macro
@@ -30,8 +31,8 @@ pub struct ExtensionProvider {
     /// Provider name (extension ID)
     name: String,
 
-    /// HTTP client for API calls
-    client: reqwest::Client,
+    /// HTTP client for API calls (shared retry/backoff with the native providers)
+    http: HttpClient,
 
     /// Environment loader
     env: EnvironmentLoader,
@@ -41,6 +42,9 @@ pub struct ExtensionProvider {
 
     /// Cached metadata (JSON string)
     metadata: Option<String>,
+
+    /// Usage of the last completed call, see [`LlmProvider::take_usage`]
+    usage: Arc<std::sync::Mutex<Option<TokenUsage>>>,
 }
 
 impl ExtensionProvider {
@@ -98,35 +102,25 @@ impl ExtensionProvider {
 
         debug!("ExtensionProvider created: {}", name);
 
-        let timeout_secs = std::env::var("LLM_TIMEOUT_SECONDS")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(120);
-
-        // pool_idle_timeout must be >= streaming timeout (600s) to prevent the
-        // connection pool from reclaiming idle connections during slow LLM
-        // streaming responses.  Configurable via LLM_POOL_IDLE_SECONDS env var.
-        let pool_idle_secs = std::env::var("LLM_POOL_IDLE_SECONDS")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(600);
-
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(timeout_secs))
-            .connect_timeout(Duration::from_secs(30))
-            .pool_idle_timeout(Duration::from_secs(pool_idle_secs))
-            .build()
-            .context("Failed to create HTTP client")?;
+        let http = HttpClient::new()?;
 
         Ok(Self {
             name,
-            client,
+            http,
             env,
             manager: Arc::new(Mutex::new(manager)),
             metadata: None,
+            usage: Arc::new(std::sync::Mutex::new(None)),
         })
     }
 
+    /// Retry failed requests at most `max_retries` times instead of
+    /// `LLM_MAX_RETRIES` (see [`HttpClient::with_max_retries`]).
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.http = self.http.with_max_retries(max_retries);
+        self
+    }
+
     /// Get provider configuration from environment
     fn get_config(&self) -> Result<crate::extension::provider_only::provider::Config> {
         // Read from environment (OPENAI_BASE_URL, OPENAI_API_KEY, OPENAI_DEFAULT_MODEL)
@@ -145,84 +139,6 @@ impl ExtensionProvider {
             default_model,
         })
     }
-
-    fn max_retries() -> u32 {
-        std::env::var("LLM_MAX_RETRIES")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(3)
-    }
-
-    async fn make_request_with_retry(
-        &self,
-        api_url: &str,
-        request_body: String,
-        api_key: &str,
-        stream: bool,
-    ) -> Result<reqwest::Response> {
-        let max_retries = Self::max_retries();
-        let mut last_error = None;
-
-        for attempt in 0..=max_retries {
-            let mut request = self.client
-                .post(api_url)
-                .header("Authorization", format!("Bearer {}", api_key))
-                .header("Content-Type", "application/json")
-                .body(request_body.clone());
-
-            if stream {
-                request = request
-                    .header("Accept", "text/event-stream")
-                    // Override client timeout for streaming — LLM responses can take minutes
-                    .timeout(Duration::from_secs(600));
-            }
-
-            match request.send().await {
-                Ok(resp) => {
-                    let status = resp.status();
-
-                    if status.is_success() {
-                        return Ok(resp);
-                    }
-
-                    if status.as_u16() == 429 {
-                        let retry_after = resp.headers()
-                            .get("retry-after")
-                            .and_then(|v| v.to_str().ok())
-                            .and_then(|v| v.parse::<u64>().ok())
-                            .unwrap_or(60);
-
-                        debug!("Rate limited (429), waiting {}s (attempt {}/{})", retry_after, attempt, max_retries);
-                        tokio::time::sleep(Duration::from_secs(retry_after)).await;
-                        last_error = Some(anyhow::anyhow!("Rate limited (429)"));
-                        continue;
-                    }
-
-                    if status.is_server_error() {
-                        let body = resp.text().await.unwrap_or_default();
-                        debug!("Server error {}: {}, retrying (attempt {}/{})", status, body, attempt, max_retries);
-                        tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
-                        last_error = Some(anyhow::anyhow!("Server error {}: {}", status, body));
-                        continue;
-                    }
-
-                    let body = resp.text().await.unwrap_or_default();
-                    return Err(anyhow::anyhow!("API error {}: {}", status, body));
-                }
-                Err(e) => {
-                    if e.is_timeout() || e.is_connect() {
-                        debug!("Network error: {}, retrying (attempt {}/{})", e, attempt, max_retries);
-                        tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
-                        last_error = Some(e.into());
-                        continue;
-                    }
-                    return Err(e.into());
-                }
-            }
-        }
-
-        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Max retries exceeded")))
-    }
 }
 
 #[async_trait::async_trait]
@@ -231,6 +147,10 @@ impl LlmProvider for ExtensionProvider {
         &self.name
     }
 
+    fn take_usage(&self) -> Option<TokenUsage> {
+        self.usage.lock().ok()?.take()
+    }
+
     fn default_model(&self) -> String {
         std::env::var("OPENAI_DEFAULT_MODEL")
             .unwrap_or_else(|_| "gpt-4o-mini".to_string())
@@ -283,10 +203,15 @@ impl LlmProvider for ExtensionProvider {
 
         debug!("ExtensionProvider: POST {}", api_url);
 
-        let response = self.make_request_with_retry(&api_url, request_body, &provider_config.api_key, false).await?;
+        let response = self.http.post_with_retry(&api_url, request_body, &provider_config.api_key, false).await?;
 
         let response_body = response.text().await?;
 
+        // The extension interface doesn't return usage, so read it from the raw body
+        if let Ok(mut slot) = self.usage.lock() {
+            *slot = TokenUsage::from_response_body(&response_body);
+        }
+
         // Parse response using extension (async)
         let assistant_msg = {
             let mut manager = self.manager.lock().await;
@@ -432,17 +357,25 @@ impl LlmProvider for ExtensionProvider {
 
         debug!("ExtensionProvider streaming: POST {}", api_url);
 
-        let response = self.make_request_with_retry(&api_url, request_body, &provider_config.api_key, true).await?;
+        let response = self.http.post_with_retry(&api_url, request_body, &provider_config.api_key, true).await?;
 
         let byte_stream = response.bytes_stream();
         let manager = Arc::clone(&self.manager);
         let name = self.name.clone();
+        let usage_slot = Arc::clone(&self.usage);
 
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
 
         tokio::spawn(async move {
             let mut byte_stream = byte_stream;
             let mut line_buffer = String::new();
+            let mut usage = None;
+            // Publish usage before Done so callers can take it once the stream ends
+            let publish_usage = |usage: Option<TokenUsage>| {
+                if let Ok(mut slot) = usage_slot.lock() {
+                    *slot = usage;
+                }
+            };
 
             while let Some(chunk_result) = byte_stream.next().await {
                 match chunk_result {
@@ -461,12 +394,14 @@ impl LlmProvider for ExtensionProvider {
 
                             // Check for [DONE]
                             if event.contains("data: [DONE]") {
+                                publish_usage(usage);
                                 let _ = tx.send(Ok(StreamChunk::Done));
                                 return;
                             }
 
                             // Process through extension (async)
                             if event.contains("data: ") {
+                                TokenUsage::update_from_sse_event(&event, &mut usage);
                                 // Debug: log raw SSE event before sending to WASM
                                 debug!("[EXTENSION PROVIDER] Raw SSE event to WASM: {}", event);
                                 
@@ -511,6 +446,7 @@ impl LlmProvider for ExtensionProvider {
                                                 }
                                             }
                                             "done" => {
+                                                publish_usage(usage);
                                                 let _ = tx.send(Ok(StreamChunk::Done));
                                                 return;
                                             }
@@ -539,6 +475,7 @@ impl LlmProvider for ExtensionProvider {
                     }
                 }
             }
+            publish_usage(usage);
         });
 
         Ok(Box::pin(futures_util::stream::unfold(rx, |mut rx| async move {
@@ -5,15 +5,18 @@
 //!
 //! Dispatch logic:
 //! - `openai-unofficial` (or unset) → native Rust `OpenAIProvider` (no wasmtime)
+//! - `anthropic` → native Rust `AnthropicProvider` (no wasmtime)
 //! - `openai-unofficial-wasm` → WASM `ExtensionProvider`
 //! - Any other name → `ExtensionProvider` (WASM extension system)
+//! - `[llm.routing]` backends → `RoutingProvider` over one of the above per backend
 
-use crate::config::EnvironmentLoader;
-use crate::provider::LlmProvider;
+use crate::config::{EnvironmentLoader, RoutingConfig};
+use crate::provider::{Backend, LlmProvider, RoutingProvider};
 use crate::provider::openai::OpenAIProvider;
+use crate::provider::anthropic::AnthropicProvider;
 #[cfg(feature = "extension")]
 use crate::provider::extension::ExtensionProvider;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::path::PathBuf;
 
 macro_rules! debug {
@@ -32,16 +35,107 @@ impl ProviderFactory {
     ///
     /// Dispatch:
     /// - `openai-unofficial` or unset → native Rust `OpenAIProvider`
+    /// - `anthropic` → native Rust `AnthropicProvider`
     /// - `openai-unofficial-wasm` or any other → `ExtensionProvider` (WASM)
+    ///
+    /// With the `provider-replay` feature, `LLM_REPLAY=<cassette>` replays a
+    /// recording instead of creating a provider (matched as set by
+    /// `LLM_REPLAY_MATCH`, paced by `LLM_REPLAY_REALTIME`), and
+    /// `LLM_RECORD=<cassette>` records every call of the created provider.
     pub async fn create(env: &EnvironmentLoader) -> Result<Box<dyn LlmProvider>> {
+        Self::create_with_routing(env, None).await
+    }
+
+    /// Create a provider, routing across the backends of `[llm.routing]`
+    /// when it lists any (see [`RoutingProvider`]).
+    pub async fn create_with_routing(
+        env: &EnvironmentLoader,
+        routing: Option<&RoutingConfig>,
+    ) -> Result<Box<dyn LlmProvider>> {
+        #[cfg(feature = "provider-replay")]
+        if let Some(path) = env.llm_replay_path() {
+            debug!("Factory - replaying cassette: {}", path);
+            return Ok(Box::new(Self::create_replay(&path, env)?));
+        }
+
+        let provider = match routing.filter(|r| !r.backends.is_empty()) {
+            Some(routing) => Self::create_router(routing, env).await?,
+            None => Self::create_provider(env).await?,
+        };
+
+        #[cfg(feature = "provider-replay")]
+        if let Some(path) = env.llm_record_path() {
+            debug!("Factory - recording to cassette: {}", path);
+            return Ok(Box::new(crate::provider::RecordingProvider::new(provider, path)?));
+        }
+
+        Ok(provider)
+    }
+
+    /// Replay the cassette at `path` with the match mode and pacing from `env`.
+    #[cfg(feature = "provider-replay")]
+    fn create_replay(path: &str, env: &EnvironmentLoader) -> Result<crate::provider::ReplayProvider> {
+        let mut replay = crate::provider::ReplayProvider::from_file(path)?.with_realtime(env.llm_replay_realtime());
+        if let Some(mode) = env.llm_replay_match() {
+            replay = replay.with_match(mode.parse().context("Invalid LLM_REPLAY_MATCH")?);
+        }
+        Ok(replay)
+    }
+
+    /// Create the provider selected by `LLM_PROVIDER`.
+    async fn create_provider(env: &EnvironmentLoader) -> Result<Box<dyn LlmProvider>> {
         let provider_name = env.llm_provider().unwrap_or_else(|| "openai-unofficial".to_string());
+        Self::create_named(&provider_name, env, None).await
+    }
+
+    /// Create one provider per `[[llm.routing.backends]]` entry and route across them.
+    async fn create_router(routing: &RoutingConfig, env: &EnvironmentLoader) -> Result<Box<dyn LlmProvider>> {
+        let mut backends = Vec::new();
+        for backend in &routing.backends {
+            let provider_name = backend
+                .provider
+                .clone()
+                .or_else(|| env.llm_provider())
+                .unwrap_or_else(|| "openai-unofficial".to_string());
+            let name = backend.name.clone().unwrap_or_else(|| match backend.model {
+                Some(ref model) => format!("{}/{}", provider_name, model),
+                None => provider_name.clone(),
+            });
+
+            debug!("Factory - routing backend {} -> {}", name, provider_name);
+            let provider = Self::create_named(&provider_name, env, Some(routing.backend_max_retries))
+                .await
+                .with_context(|| format!("Failed to create routing backend '{}'", name))?;
+            backends.push(Backend::new(name, provider, backend.model.clone()));
+        }
+        Ok(Box::new(RoutingProvider::new(backends, routing)?))
+    }
 
+    /// Create the provider called `provider_name`, overriding its HTTP retry
+    /// count when `max_retries` is set.
+    async fn create_named(
+        provider_name: &str,
+        env: &EnvironmentLoader,
+        max_retries: Option<u32>,
+    ) -> Result<Box<dyn LlmProvider>> {
         debug!("Factory - provider_name: {}", provider_name);
 
         // Route to native Rust provider for the default / native case
         if provider_name == "openai-unofficial" {
             debug!("Factory - using native Rust OpenAIProvider");
-            let provider = OpenAIProvider::new()?;
+            let mut provider = OpenAIProvider::new()?;
+            if let Some(max_retries) = max_retries {
+                provider = provider.with_max_retries(max_retries);
+            }
+            return Ok(Box::new(provider));
+        }
+
+        if provider_name == "anthropic" {
+            debug!("Factory - using native Rust AnthropicProvider");
+            let mut provider = AnthropicProvider::new()?;
+            if let Some(max_retries) = max_retries {
+                provider = provider.with_max_retries(max_retries);
+            }
             return Ok(Box::new(provider));
         }
 
@@ -49,14 +143,14 @@ impl ProviderFactory {
         #[cfg(feature = "extension")]
         {
             debug!("Factory - using WASM ExtensionProvider for: {}", provider_name);
-            return Self::create_extension_provider(&provider_name, env).await;
+            return Self::create_extension_provider(provider_name, env, max_retries).await;
         }
 
         #[cfg(not(feature = "extension"))]
         {
             anyhow::bail!(
                 "Provider '{}' requires the 'extension' feature (WASM), which is not enabled. \
-                 Set LLM_PROVIDER=openai-unofficial for the native Rust provider.",
+                 Set LLM_PROVIDER=openai-unofficial or anthropic for a native Rust provider.",
                 provider_name
             );
         }
@@ -67,8 +161,9 @@ impl ProviderFactory {
     async fn create_extension_provider(
         provider_name: &str,
         env: &EnvironmentLoader,
+        max_retries: Option<u32>,
     ) -> Result<Box<dyn LlmProvider>> {
-        Self::create_extension_provider_with_agent_name(provider_name, env, None).await
+        Self::create_extension_provider_with_agent_name(provider_name, env, None, max_retries).await
     }
 
     /// Create an extension-based (WASM) provider with explicit agent name.
@@ -77,6 +172,7 @@ impl ProviderFactory {
         provider_name: &str,
         env: &EnvironmentLoader,
         agent_name: Option<&str>,
+        max_retries: Option<u32>,
     ) -> Result<Box<dyn LlmProvider>> {
         let agent_name = agent_name
             .map(|s| s.to_string())
@@ -115,13 +211,19 @@ impl ProviderFactory {
             }
             
             debug!("Factory - Creating extension provider from dev location");
-            let provider = ExtensionProvider::new(provider_name.to_string(), dev_extensions, env.clone()).await?;
+            let mut provider = ExtensionProvider::new(provider_name.to_string(), dev_extensions, env.clone()).await?;
+            if let Some(max_retries) = max_retries {
+                provider = provider.with_max_retries(max_retries);
+            }
             return Ok(Box::new(provider));
         }
 
         debug!("Factory - Creating extension provider from installed location");
         debug!("Factory - Using extension provider: {}", provider_name);
-        let provider = ExtensionProvider::new(provider_name.to_string(), extensions_dir, env.clone()).await?;
+        let mut provider = ExtensionProvider::new(provider_name.to_string(), extensions_dir, env.clone()).await?;
+        if let Some(max_retries) = max_retries {
+            provider = provider.with_max_retries(max_retries);
+        }
         Ok(Box::new(provider))
     }
 }
@@ -6,21 +6,32 @@
 pub mod traits;
 pub mod factory;
 pub mod types;
+pub mod usage;
 pub mod adapters;
 pub mod openai;
+pub mod anthropic;
+pub mod routing;
+pub mod thinking;
 #[cfg(feature = "provider-wasm")]
 pub mod wasm;
 #[cfg(feature = "extension")]
 pub mod extension;
+#[cfg(feature = "provider-replay")]
+pub mod cassette;
 
 // Re-export main types
-pub use traits::{LlmProvider, GenerateResponse, ToolInvocation, StreamingResponse};
+pub use traits::{LlmProvider, GenerateResponse, ProviderFallback, ToolInvocation, StreamingResponse};
 pub use factory::ProviderFactory;
+pub use usage::{PriceTable, TokenUsage, UsageTotals, UsageTracker};
 pub use types::{InternalMessage, GenerateConfig, InternalToolDefinition, ToolChoice, ToolResult};
 pub use adapters::{ChatMLAdapter, ToolAdapter};
-pub use openai::OpenAIProvider;
+pub use openai::{HttpError, HttpErrorKind, OpenAIProvider};
+pub use anthropic::AnthropicProvider;
+pub use routing::{Backend, RoutingProvider};
 #[cfg(feature = "extension")]
 pub use extension::ExtensionProvider;
+#[cfg(feature = "provider-replay")]
+pub use cassette::{Cassette, RecordingProvider, ReplayMatch, ReplayProvider};
 
 // Re-export streaming types from umf
 pub use umf::StreamChunk;
@@ -1,11 +1,88 @@
 //! Native Rust OpenAI provider — HTTP client with retry + backoff.
+//!
+//! Also shared by the native Anthropic provider.
 
 use anyhow::{Context, Result};
 use std::time::Duration;
 
+/// Kind of failure of an LLM API call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HttpErrorKind {
+    /// 429, or a rate-limit error reported by the API
+    RateLimited,
+    /// 5xx, or an overloaded/server error reported by the API
+    Server,
+    /// Any other non-success status; retrying elsewhere would fail the same way
+    Client,
+    /// The request timed out
+    Timeout,
+    /// The connection could not be established
+    Connect,
+}
+
+/// Structured error for a failed LLM API call.
+///
+/// Returned (inside `anyhow::Error`) by [`HttpClient`] and the other
+/// providers, so callers such as [`RoutingProvider`](crate::provider::RoutingProvider)
+/// can react to the kind of failure instead of the message text.
+#[derive(Debug)]
+pub struct HttpError {
+    /// What went wrong
+    pub kind: HttpErrorKind,
+    /// HTTP status, when the server answered
+    pub status: Option<u16>,
+    message: String,
+}
+
+impl HttpError {
+    /// Create an error of `kind` with a message.
+    pub fn new(kind: HttpErrorKind, status: Option<u16>, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            status,
+            message: message.into(),
+        }
+    }
+
+    /// Classify a non-success response by its status.
+    pub fn from_status(status: reqwest::StatusCode, body: &str) -> Self {
+        let code = Some(status.as_u16());
+        if status.as_u16() == 429 {
+            Self::new(HttpErrorKind::RateLimited, code, "Rate limited (429)")
+        } else if status.is_server_error() {
+            Self::new(HttpErrorKind::Server, code, format!("Server error {}: {}", status, body))
+        } else {
+            Self::new(HttpErrorKind::Client, code, format!("API error {}: {}", status, body))
+        }
+    }
+
+    /// Classify a transport error, or `None` if it is neither a timeout nor
+    /// a connection failure.
+    pub fn from_transport(error: &reqwest::Error) -> Option<Self> {
+        let kind = if error.is_timeout() {
+            HttpErrorKind::Timeout
+        } else if error.is_connect() {
+            HttpErrorKind::Connect
+        } else {
+            return None;
+        };
+        Some(Self::new(kind, None, format!("Network error: {}", error)))
+    }
+}
+
+impl std::fmt::Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for HttpError {}
+
 /// HTTP client wrapper with retry logic.
 pub struct HttpClient {
     client: reqwest::Client,
+    /// Overrides `LLM_MAX_RETRIES` when set
+    max_retries: Option<u32>,
 }
 
 macro_rules! debug {
@@ -39,7 +116,17 @@ impl HttpClient {
             .build()
             .context("Failed to create HTTP client")?;
 
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            max_retries: None,
+        })
+    }
+
+    /// Retry failed requests at most `max_retries` times, ignoring
+    /// `LLM_MAX_RETRIES`. `0` returns the first failure immediately.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
     }
 
     /// Reference to the inner reqwest client.
@@ -47,12 +134,14 @@ impl HttpClient {
         &self.client
     }
 
-    /// Max retries from env (default 3).
-    fn max_retries() -> u32 {
-        std::env::var("LLM_MAX_RETRIES")
-            .ok()
-            .and_then(|s| s.parse().ok())
-            .unwrap_or(3)
+    /// Max retries: the override, else from env (default 3).
+    fn max_retries(&self) -> u32 {
+        self.max_retries.unwrap_or_else(|| {
+            std::env::var("LLM_MAX_RETRIES")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(3)
+        })
     }
 
     /// POST with retry: 429 → Retry-After backoff; 5xx → exponential backoff.
@@ -63,17 +152,37 @@ impl HttpClient {
         api_key: &str,
         stream: bool,
     ) -> Result<reqwest::Response> {
-        let max_retries = Self::max_retries();
-        let mut last_error = None;
+        let auth = [("Authorization", format!("Bearer {}", api_key))];
+        self.post_with_retry_headers(url, body, &auth, stream).await
+    }
+
+    /// Same as [`post_with_retry`](Self::post_with_retry) but with caller-supplied
+    /// auth/version headers, for APIs that don't use bearer tokens.
+    pub async fn post_with_retry_headers(
+        &self,
+        url: &str,
+        body: String,
+        headers: &[(&str, String)],
+        stream: bool,
+    ) -> Result<reqwest::Response> {
+        let max_retries = self.max_retries();
+        let mut retry_reason = "";
 
         for attempt in 0..=max_retries {
+            if attempt > 0 {
+                crate::observability::metrics::record_http_retry(retry_reason);
+            }
+
             let mut request = self
                 .client
                 .post(url)
-                .header("Authorization", format!("Bearer {}", api_key))
                 .header("Content-Type", "application/json")
                 .body(body.clone());
 
+            for (name, value) in headers {
+                request = request.header(*name, value);
+            }
+
             if stream {
                 request = request
                     .header("Accept", "text/event-stream")
@@ -89,6 +198,9 @@ impl HttpClient {
                     }
 
                     if status.as_u16() == 429 {
+                        if attempt == max_retries {
+                            return Err(HttpError::from_status(status, "").into());
+                        }
                         let retry_after = resp
                             .headers()
                             .get("retry-after")
@@ -103,41 +215,109 @@ impl HttpClient {
                             max_retries
                         );
                         tokio::time::sleep(Duration::from_secs(retry_after)).await;
-                        last_error = Some(anyhow::anyhow!("Rate limited (429)"));
+                        retry_reason = "rate_limited";
                         continue;
                     }
 
-                    if status.is_server_error() {
-                        let error_body = resp.text().await.unwrap_or_default();
+                    let error_body = resp.text().await.unwrap_or_default();
+                    let error = HttpError::from_status(status, &error_body);
+                    if status.is_server_error() && attempt < max_retries {
                         debug!(
                             "Server error {}: {}, retrying (attempt {}/{})",
                             status, error_body, attempt, max_retries
                         );
                         tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
-                        last_error =
-                            Some(anyhow::anyhow!("Server error {}: {}", status, error_body));
+                        retry_reason = "server_error";
                         continue;
                     }
 
-                    // Client error (non-429) — no retry
-                    let error_body = resp.text().await.unwrap_or_default();
-                    return Err(anyhow::anyhow!("API error {}: {}", status, error_body));
+                    // Client error (non-429), or out of retries
+                    return Err(error.into());
                 }
                 Err(e) => {
-                    if e.is_timeout() || e.is_connect() {
-                        debug!(
-                            "Network error: {}, retrying (attempt {}/{})",
-                            e, attempt, max_retries
-                        );
-                        tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
-                        last_error = Some(e.into());
-                        continue;
+                    let Some(error) = HttpError::from_transport(&e) else {
+                        return Err(e.into());
+                    };
+                    if attempt == max_retries {
+                        return Err(error.into());
                     }
-                    return Err(e.into());
+                    debug!(
+                        "Network error: {}, retrying (attempt {}/{})",
+                        e, attempt, max_retries
+                    );
+                    tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
+                    retry_reason = "network";
+                    continue;
                 }
             }
         }
 
-        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Max retries exceeded")))
+        unreachable!("the last attempt always returns")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Answer every request with `status`; returns the base URL and a request counter.
+    fn failing_server(status: &'static str) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { return };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some(len) = line.to_lowercase().strip_prefix("content-length:") {
+                        content_length = len.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0u8; content_length];
+                let _ = reader.read_exact(&mut body);
+                counter.fetch_add(1, Ordering::SeqCst);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: 4\r\nConnection: close\r\n\r\nbusy",
+                    status
+                );
+            }
+        });
+
+        (base_url, requests)
+    }
+
+    #[tokio::test]
+    async fn test_zero_retries_returns_structured_error_at_once() {
+        let (base_url, requests) = failing_server("429 Too Many Requests");
+        let client = HttpClient::new().unwrap().with_max_retries(0);
+
+        let started = std::time::Instant::now();
+        let error = client.post_with_retry(&base_url, "{}".to_string(), "key", false).await.unwrap_err();
+        let error = error.downcast_ref::<HttpError>().unwrap();
+        assert_eq!(error.kind, HttpErrorKind::RateLimited);
+        assert_eq!(error.status, Some(429));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        // No Retry-After wait when there is no retry left
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        let (base_url, requests) = failing_server("503 Service Unavailable");
+        let error = client.post_with_retry(&base_url, "{}".to_string(), "key", false).await.unwrap_err();
+        let error = error.downcast_ref::<HttpError>().unwrap();
+        assert_eq!(error.kind, HttpErrorKind::Server);
+        assert_eq!(error.to_string(), "Server error 503 Service Unavailable: busy");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
     }
 }
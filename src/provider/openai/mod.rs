@@ -10,11 +10,13 @@ mod response;
 mod sse;
 mod client;
 
-pub use client::HttpClient;
+pub use client::{HttpClient, HttpError, HttpErrorKind};
 
 use crate::provider::traits::{GenerateResponse, LlmProvider, StreamingResponse};
 use crate::provider::types::{GenerateConfig, InternalMessage};
+use crate::provider::usage::TokenUsage;
 use anyhow::{Context, Result};
+use std::sync::{Arc, Mutex};
 
 /// Conditional debug macro
 macro_rules! debug {
@@ -31,15 +33,36 @@ macro_rules! debug {
 /// - `OPENAI_API_KEY` (required)
 /// - `OPENAI_BASE_URL` (default: `https://api.openai.com/v1`)
 /// - `OPENAI_DEFAULT_MODEL` (default: `gpt-4o-mini`)
+/// - `OPENAI_STREAM_USAGE` (default: `true`; `false` stops requesting usage
+///   on streams, for servers that reject `stream_options`)
 pub struct OpenAIProvider {
     http: HttpClient,
+    /// Usage of the last completed call, see [`LlmProvider::take_usage`]
+    usage: Arc<Mutex<Option<TokenUsage>>>,
 }
 
 impl OpenAIProvider {
     /// Create a new native OpenAI provider.
     pub fn new() -> Result<Self> {
         let http = HttpClient::new()?;
-        Ok(Self { http })
+        Ok(Self {
+            http,
+            usage: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Retry failed requests at most `max_retries` times instead of
+    /// `LLM_MAX_RETRIES` (see [`HttpClient::with_max_retries`]).
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.http = self.http.with_max_retries(max_retries);
+        self
+    }
+
+    /// Whether to ask for a final usage chunk on streams.
+    fn stream_usage() -> bool {
+        std::env::var("OPENAI_STREAM_USAGE")
+            .map(|v| v != "false" && v != "0")
+            .unwrap_or(true)
     }
 
     /// Get the API key from env.
@@ -75,6 +98,10 @@ impl LlmProvider for OpenAIProvider {
             .unwrap_or_else(|_| "gpt-4o-mini".to_string())
     }
 
+    fn take_usage(&self) -> Option<TokenUsage> {
+        self.usage.lock().ok()?.take()
+    }
+
     async fn generate(
         &self,
         msgs: Vec<InternalMessage>,
@@ -122,7 +149,11 @@ impl LlmProvider for OpenAIProvider {
             .await?;
 
         let resp_text = resp.text().await?;
-        response::parse_response(&resp_text)
+        let (response, usage) = response::parse_response(&resp_text)?;
+        if let Ok(mut slot) = self.usage.lock() {
+            *slot = usage;
+        }
+        Ok(response)
     }
 
     async fn generate_stream(
@@ -152,7 +183,7 @@ impl LlmProvider for OpenAIProvider {
             .map(|tc| tools::tool_choice_to_openai(tc));
 
         // Build request body
-        let body = request::build_request_body(
+        let mut body = request::build_request_body(
             &openai_messages,
             &model,
             true, // streaming
@@ -162,6 +193,10 @@ impl LlmProvider for OpenAIProvider {
             openai_tool_choice.as_ref(),
         );
 
+        if Self::stream_usage() {
+            body["stream_options"] = serde_json::json!({"include_usage": true});
+        }
+
         let body_str = serde_json::to_string(&body)?;
         let api_key = self.api_key()?;
         let url = self.api_url();
@@ -176,12 +211,14 @@ impl LlmProvider for OpenAIProvider {
         let byte_stream = resp.bytes_stream();
 
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let usage_slot = Arc::clone(&self.usage);
 
         tokio::spawn(async move {
             let mut byte_stream = byte_stream;
             let mut line_buffer = String::new();
+            let mut usage = None;
 
-            while let Some(chunk_result) = byte_stream.next().await {
+            'read: while let Some(chunk_result) = byte_stream.next().await {
                 match chunk_result {
                     Ok(bytes) => {
                         let text = String::from_utf8_lossy(&bytes).to_string();
@@ -199,9 +236,15 @@ impl LlmProvider for OpenAIProvider {
                             // Each SSE event has lines starting with "data: "
                             for line in event.lines() {
                                 if let Some(data) = line.strip_prefix("data: ") {
-                                    if let Some(chunks) = sse::parse_sse_event(data) {
+                                    // The usage chunk follows finish_reason, so Done
+                                    // is held back until [DONE] / end of stream.
+                                    if data.trim() == "[DONE]" {
+                                        break 'read;
+                                    }
+                                    if let Some(chunks) = sse::parse_sse_event(data, &mut usage) {
                                         for chunk in chunks {
                                             match &chunk {
+                                                crate::provider::StreamChunk::Done => continue,
                                                 crate::provider::StreamChunk::Text(t) => {
                                                     crate::observability::append_to_global_log(t);
                                                 }
@@ -230,7 +273,10 @@ impl LlmProvider for OpenAIProvider {
                 }
             }
 
-            // If the stream ended without an explicit [DONE], send one
+            // Publish usage before Done so callers can take it once the stream ends
+            if let Ok(mut slot) = usage_slot.lock() {
+                *slot = usage;
+            }
             let _ = tx.send(Ok(crate::provider::StreamChunk::Done));
         });
 
@@ -1,14 +1,18 @@
 //! Native Rust OpenAI provider — response parsing.
 
 use crate::provider::traits::{GenerateResponse, ToolInvocation};
+use crate::provider::usage::TokenUsage;
 use anyhow::{Context, Result};
 use serde_json::Value;
 
-/// Parse a non-streaming OpenAI chat-completions response into `GenerateResponse`.
-pub fn parse_response(response_body: &str) -> Result<GenerateResponse> {
+/// Parse a non-streaming OpenAI chat-completions response into `GenerateResponse`
+/// plus the reported token usage.
+pub fn parse_response(response_body: &str) -> Result<(GenerateResponse, Option<TokenUsage>)> {
     let json: Value =
         serde_json::from_str(response_body).context("Failed to parse OpenAI response JSON")?;
 
+    let usage = json.get("usage").and_then(TokenUsage::from_openai);
+
     let choice = json
         .get("choices")
         .and_then(|c| c.get(0))
@@ -61,7 +65,7 @@ pub fn parse_response(response_body: &str) -> Result<GenerateResponse> {
                 .collect();
 
             if !calls.is_empty() {
-                return Ok(GenerateResponse::ToolCalls { calls, reasoning });
+                return Ok((GenerateResponse::ToolCalls { calls, reasoning }, usage));
             }
         }
     }
@@ -73,5 +77,5 @@ pub fn parse_response(response_body: &str) -> Result<GenerateResponse> {
         .unwrap_or("")
         .to_string();
 
-    Ok(GenerateResponse::Content { text, reasoning })
+    Ok((GenerateResponse::Content { text, reasoning }, usage))
 }
@@ -1,12 +1,15 @@
 //! Native Rust OpenAI provider — SSE streaming parser.
 
+use crate::provider::usage::TokenUsage;
 use crate::provider::StreamChunk;
 
 /// Parse a single SSE event payload (the `data: …` line content) into zero or more `StreamChunk`s.
 ///
 /// Returns `None` if the event is a keep-alive comment or has no usable delta.
 /// Returns `Some(Vec::new())` if the JSON parsed but had no relevant content.
-pub fn parse_sse_event(data: &str) -> Option<Vec<StreamChunk>> {
+/// A `usage` object (sent last when `stream_options.include_usage` is set)
+/// is stored into `usage`.
+pub fn parse_sse_event(data: &str, usage: &mut Option<TokenUsage>) -> Option<Vec<StreamChunk>> {
     let trimmed = data.trim();
 
     // [DONE] sentinel
@@ -17,6 +20,10 @@ pub fn parse_sse_event(data: &str) -> Option<Vec<StreamChunk>> {
     // Try to parse as JSON
     let json: serde_json::Value = serde_json::from_str(trimmed).ok()?;
 
+    if let Some(reported) = json.get("usage").and_then(TokenUsage::from_openai) {
+        *usage = Some(reported);
+    }
+
     let delta = json.get("choices")
         .and_then(|c| c.get(0))
         .and_then(|c| c.get("delta"))?;
@@ -0,0 +1,661 @@
+//! Routing provider — one `LlmProvider` over several provider/model backends.
+//!
+//! Calls start at the first backend, or at the backend named by the first
+//! matching `[[llm.routing.rules]]` entry, and fail over down the list on
+//! rate limits, server errors and timeouts. Each backend has a circuit
+//! breaker: after `failure_threshold` consecutive failures it is skipped for
+//! `cooldown_seconds`, then gets a single trial call. Other calls keep
+//! skipping it until the trial succeeds or fails; a trial whose caller went
+//! away is given up after another cooldown.
+//!
+//! Streaming calls fail over when the request or the first chunk fails;
+//! once a chunk has been yielded the stream stays on its backend.
+//!
+//! Failures are classified by their [`HttpError`] kind, not their message.
+//! Routed providers are created with `backend_max_retries` (default 0) so a
+//! failing backend hands over at once instead of retrying on its own first.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use futures_util::StreamExt;
+
+use crate::config::{RoutingConfig, RoutingRuleConfig};
+use crate::provider::traits::{GenerateResponse, LlmProvider, ProviderFallback, StreamingResponse};
+use crate::provider::types::{GenerateConfig, InternalMessage};
+use crate::provider::usage::TokenUsage;
+use crate::provider::{HttpError, HttpErrorKind};
+
+/// A provider with the model to use on it.
+pub struct Backend {
+    name: String,
+    provider: Box<dyn LlmProvider>,
+    model: Option<String>,
+}
+
+impl Backend {
+    /// Create a backend. With `model = None` calls use the model the caller
+    /// requested, else the provider's default.
+    pub fn new(name: impl Into<String>, provider: Box<dyn LlmProvider>, model: Option<String>) -> Self {
+        Self {
+            name: name.into(),
+            provider,
+            model,
+        }
+    }
+
+    /// Backend name used by rules and fallback events.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Consecutive-failure circuit breaker for one backend.
+#[derive(Debug, Default)]
+struct CircuitBreaker {
+    failures: u32,
+    opened_at: Option<Instant>,
+    /// Start of the half-open trial call, while it is in flight
+    trial_started: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Closed, or open for at least `cooldown` with no trial call in flight
+    /// (half-open).
+    fn is_available(&self, cooldown: Duration) -> bool {
+        self.opened_at.is_none_or(|opened| {
+            opened.elapsed() >= cooldown && self.trial_started.is_none_or(|started| started.elapsed() >= cooldown)
+        })
+    }
+
+    /// Claim a call to the backend; a half-open breaker makes it the trial.
+    /// Returns `false` if the backend must be skipped.
+    fn begin_call(&mut self, cooldown: Duration) -> bool {
+        if !self.is_available(cooldown) {
+            return false;
+        }
+        if self.opened_at.is_some() {
+            self.trial_started = Some(Instant::now());
+        }
+        true
+    }
+
+    fn record_success(&mut self) {
+        self.failures = 0;
+        self.opened_at = None;
+        self.trial_started = None;
+    }
+
+    fn record_failure(&mut self, threshold: u32) {
+        self.failures += 1;
+        self.trial_started = None;
+        if self.failures >= threshold {
+            self.opened_at = Some(Instant::now());
+        }
+    }
+
+    /// The call ended without saying whether the backend recovered.
+    fn end_trial(&mut self) {
+        self.trial_started = None;
+    }
+}
+
+/// Why an error moves a call to the next backend, or `None` if it doesn't
+/// (client errors would fail the same way everywhere, and errors without an
+/// [`HttpError`] or transport cause are not provider failures).
+fn failover_reason(error: &anyhow::Error) -> Option<&'static str> {
+    error.chain().find_map(|cause| {
+        if let Some(e) = cause.downcast_ref::<HttpError>() {
+            return match e.kind {
+                HttpErrorKind::RateLimited => Some("rate limited"),
+                HttpErrorKind::Server => Some("server error"),
+                HttpErrorKind::Timeout => Some("timeout"),
+                HttpErrorKind::Connect => Some("connection failed"),
+                HttpErrorKind::Client => None,
+            };
+        }
+        let e = cause.downcast_ref::<reqwest::Error>()?;
+        if e.is_timeout() {
+            Some("timeout")
+        } else if e.is_connect() {
+            Some("connection failed")
+        } else {
+            None
+        }
+    })
+}
+
+/// Error for a call whose every candidate was taken by another call's
+/// half-open trial.
+fn no_backend_available() -> anyhow::Error {
+    anyhow::anyhow!("No routing backend is available: every candidate is waiting on a circuit breaker trial call")
+}
+
+fn rule_matches(rule: &RoutingRuleConfig, config: &GenerateConfig) -> bool {
+    let has_tools = config.tools.as_ref().is_some_and(|t| !t.is_empty());
+    rule.tools.is_none_or(|tools| tools == has_tools)
+        && rule
+            .max_tokens_at_most
+            .is_none_or(|max| config.max_tokens.is_some_and(|m| m <= max))
+        && rule.model.as_ref().is_none_or(|model| config.model.as_ref() == Some(model))
+}
+
+/// Provider that routes and fails over across [`Backend`]s.
+///
+/// `provider_name`, `default_model` and `take_usage` describe the backend
+/// that served the most recent call. Before the first call that is the first
+/// backend, whatever backend the rules would select.
+///
+/// # Example
+///
+/// ```ignore
+/// use abk::provider::{Backend, RoutingProvider};
+///
+/// let provider = RoutingProvider::new(
+///     vec![
+///         Backend::new("strong", Box::new(AnthropicProvider::new()?), Some("claude-sonnet-4-5".into())),
+///         Backend::new("cheap", Box::new(OpenAIProvider::new()?), Some("gpt-4o-mini".into())),
+///     ],
+///     &routing_config,
+/// )?;
+/// ```
+pub struct RoutingProvider {
+    backends: Vec<Backend>,
+    rules: Vec<(RoutingRuleConfig, usize)>,
+    failure_threshold: u32,
+    cooldown: Duration,
+    breakers: Mutex<Vec<CircuitBreaker>>,
+    /// Backend that served the most recent call
+    last_served: Mutex<usize>,
+    fallbacks: Mutex<Vec<ProviderFallback>>,
+}
+
+impl RoutingProvider {
+    /// Create a router over `backends` with the rules and breaker settings
+    /// from `config` (its `backends` list is not used).
+    ///
+    /// # Errors
+    /// Returns an error if `backends` is empty or a rule names an unknown backend.
+    pub fn new(backends: Vec<Backend>, config: &RoutingConfig) -> Result<Self> {
+        if backends.is_empty() {
+            anyhow::bail!("Routing provider needs at least one backend");
+        }
+
+        let rules = config
+            .rules
+            .iter()
+            .map(|rule| match backends.iter().position(|b| b.name == rule.backend) {
+                Some(index) => Ok((rule.clone(), index)),
+                None => Err(anyhow::anyhow!("Routing rule refers to unknown backend '{}'", rule.backend)),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let breakers = backends.iter().map(|_| CircuitBreaker::default()).collect();
+        Ok(Self {
+            backends,
+            rules,
+            failure_threshold: config.failure_threshold.max(1),
+            cooldown: Duration::from_secs(config.cooldown_seconds),
+            breakers: Mutex::new(breakers),
+            last_served: Mutex::new(0),
+            fallbacks: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Backends in failover order.
+    pub fn backends(&self) -> &[Backend] {
+        &self.backends
+    }
+
+    /// Backend indices to try for `config`: the rule-selected (or first)
+    /// backend, then the rest in order, skipping open circuits. If every
+    /// circuit is open, all backends are tried anyway and the second value
+    /// is `true`.
+    fn candidates(&self, config: &GenerateConfig) -> (Vec<usize>, bool) {
+        let start = self
+            .rules
+            .iter()
+            .find(|(rule, _)| rule_matches(rule, config))
+            .map(|(_, index)| *index)
+            .unwrap_or(0);
+        let order: Vec<usize> = std::iter::once(start)
+            .chain((0..self.backends.len()).filter(|i| *i != start))
+            .collect();
+
+        let breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        let available: Vec<usize> = order
+            .iter()
+            .copied()
+            .filter(|i| breakers[*i].is_available(self.cooldown))
+            .collect();
+        if available.is_empty() {
+            (order, true)
+        } else {
+            (available, false)
+        }
+    }
+
+    /// Claim a call to backend `index`, unless another call took its
+    /// half-open trial since the candidates were picked. With `all_open`
+    /// every backend is tried regardless.
+    fn begin_call(&self, index: usize, all_open: bool) -> bool {
+        all_open || self.breakers.lock().unwrap_or_else(|e| e.into_inner())[index].begin_call(self.cooldown)
+    }
+
+    fn backend_config(&self, index: usize, config: &GenerateConfig) -> GenerateConfig {
+        let mut config = config.clone();
+        if let Some(ref model) = self.backends[index].model {
+            config.model = Some(model.clone());
+        }
+        config
+    }
+
+    fn record_success(&self, index: usize) {
+        self.breakers.lock().unwrap_or_else(|e| e.into_inner())[index].record_success();
+        *self.last_served.lock().unwrap_or_else(|e| e.into_inner()) = index;
+    }
+
+    /// Record a failed call; returns the failover reason if the error is one.
+    fn record_failure(&self, index: usize, error: &anyhow::Error) -> Option<&'static str> {
+        let reason = failover_reason(error);
+        let breaker = &mut self.breakers.lock().unwrap_or_else(|e| e.into_inner())[index];
+        match reason {
+            Some(_) => breaker.record_failure(self.failure_threshold),
+            None => breaker.end_trial(),
+        }
+        reason
+    }
+
+    fn record_fallback(&self, from: usize, to: usize, reason: &str) {
+        let fallback = ProviderFallback {
+            from: self.backends[from].name.clone(),
+            to: self.backends[to].name.clone(),
+            reason: reason.to_string(),
+        };
+        crate::observability::metrics::record_provider_fallback(&fallback.from, &fallback.to, reason);
+        self.fallbacks.lock().unwrap_or_else(|e| e.into_inner()).push(fallback);
+    }
+
+    fn served(&self) -> &Backend {
+        &self.backends[*self.last_served.lock().unwrap_or_else(|e| e.into_inner())]
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for RoutingProvider {
+    async fn generate(&self, messages: Vec<InternalMessage>, config: &GenerateConfig) -> Result<GenerateResponse> {
+        let (candidates, all_open) = self.candidates(config);
+        let mut failed: Option<(usize, &'static str)> = None;
+        let mut last_error = None;
+
+        for (position, &index) in candidates.iter().enumerate() {
+            if !self.begin_call(index, all_open) {
+                continue;
+            }
+            if let Some((from, reason)) = failed.take() {
+                self.record_fallback(from, index, reason);
+            }
+
+            let backend_config = self.backend_config(index, config);
+            match self.backends[index].provider.generate(messages.clone(), &backend_config).await {
+                Ok(response) => {
+                    self.record_success(index);
+                    return Ok(response);
+                }
+                Err(e) => match self.record_failure(index, &e) {
+                    Some(reason) if position + 1 < candidates.len() => {
+                        failed = Some((index, reason));
+                        last_error = Some(e);
+                    }
+                    _ => return Err(e),
+                },
+            }
+        }
+        Err(last_error.unwrap_or_else(no_backend_available))
+    }
+
+    async fn generate_stream(&self, messages: Vec<InternalMessage>, config: &GenerateConfig) -> Result<StreamingResponse> {
+        let (candidates, all_open) = self.candidates(config);
+        let mut failed: Option<(usize, &'static str)> = None;
+        let mut last_error = None;
+
+        for (position, &index) in candidates.iter().enumerate() {
+            if !self.begin_call(index, all_open) {
+                continue;
+            }
+            if let Some((from, reason)) = failed.take() {
+                self.record_fallback(from, index, reason);
+            }
+            let is_last = position + 1 == candidates.len();
+
+            let backend_config = self.backend_config(index, config);
+            let mut stream = match self.backends[index].provider.generate_stream(messages.clone(), &backend_config).await {
+                Ok(stream) => stream,
+                Err(e) => match self.record_failure(index, &e) {
+                    Some(reason) if !is_last => {
+                        failed = Some((index, reason));
+                        last_error = Some(e);
+                        continue;
+                    }
+                    _ => return Err(e),
+                },
+            };
+
+            // Errors like rate limits can surface as the first stream item
+            match stream.next().await {
+                Some(Err(e)) => match self.record_failure(index, &e) {
+                    Some(reason) if !is_last => {
+                        failed = Some((index, reason));
+                        last_error = Some(e);
+                        continue;
+                    }
+                    _ => return Ok(Box::pin(futures_util::stream::iter(Some(Err(e))).chain(stream))),
+                },
+                first => {
+                    self.record_success(index);
+                    return Ok(Box::pin(futures_util::stream::iter(first).chain(stream)));
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(no_backend_available))
+    }
+
+    fn provider_name(&self) -> &str {
+        self.served().provider.provider_name()
+    }
+
+    fn default_model(&self) -> String {
+        let backend = self.served();
+        backend.model.clone().unwrap_or_else(|| backend.provider.default_model())
+    }
+
+    fn take_usage(&self) -> Option<TokenUsage> {
+        self.served().provider.take_usage()
+    }
+
+    fn take_fallbacks(&self) -> Vec<ProviderFallback> {
+        std::mem::take(&mut *self.fallbacks.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::traits::StreamChunk;
+    use crate::provider::types::InternalToolDefinition;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Fails with an `HttpError` of kind `error` (if set), otherwise answers
+    /// with its name and the model.
+    struct TestProvider {
+        name: &'static str,
+        error: Option<HttpErrorKind>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl TestProvider {
+        fn boxed(name: &'static str, error: Option<HttpErrorKind>) -> (Box<dyn LlmProvider>, Arc<AtomicUsize>) {
+            let calls = Arc::new(AtomicUsize::new(0));
+            (Box::new(Self { name, error, calls: calls.clone() }), calls)
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for TestProvider {
+        async fn generate(&self, _messages: Vec<InternalMessage>, config: &GenerateConfig) -> Result<GenerateResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.error {
+                Some(kind) => Err(HttpError::new(kind, None, format!("{:?}", kind)).into()),
+                None => Ok(GenerateResponse::Content {
+                    text: format!("{}:{}", self.name, config.model.as_deref().unwrap_or("default")),
+                    reasoning: None,
+                }),
+            }
+        }
+
+        async fn generate_stream(&self, _messages: Vec<InternalMessage>, _config: &GenerateConfig) -> Result<StreamingResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let first = match self.error {
+                Some(kind) => Err(HttpError::new(kind, None, format!("{:?}", kind)).into()),
+                None => Ok(StreamChunk::Text(self.name.to_string())),
+            };
+            Ok(Box::pin(futures_util::stream::iter(vec![first, Ok(StreamChunk::Done)])))
+        }
+
+        fn provider_name(&self) -> &str {
+            self.name
+        }
+
+        fn default_model(&self) -> String {
+            format!("{}-model", self.name)
+        }
+    }
+
+    fn text(response: GenerateResponse) -> String {
+        match response {
+            GenerateResponse::Content { text, .. } => text,
+            other => panic!("unexpected response: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failover_on_rate_limit() {
+        let (primary, _) = TestProvider::boxed("primary", Some(HttpErrorKind::RateLimited));
+        let (secondary, _) = TestProvider::boxed("secondary", None);
+        let router = RoutingProvider::new(
+            vec![
+                Backend::new("primary", primary, None),
+                Backend::new("secondary", secondary, Some("backup-model".to_string())),
+            ],
+            &RoutingConfig::default(),
+        )
+        .unwrap();
+
+        let response = router.generate(vec![], &GenerateConfig::new()).await.unwrap();
+        assert_eq!(text(response), "secondary:backup-model");
+        assert_eq!(router.default_model(), "backup-model");
+        assert_eq!(
+            router.take_fallbacks(),
+            vec![ProviderFallback {
+                from: "primary".to_string(),
+                to: "secondary".to_string(),
+                reason: "rate limited".to_string(),
+            }]
+        );
+        assert!(router.take_fallbacks().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_client_error_does_not_fail_over() {
+        let (primary, _) = TestProvider::boxed("primary", Some(HttpErrorKind::Client));
+        let (secondary, secondary_calls) = TestProvider::boxed("secondary", None);
+        let router = RoutingProvider::new(
+            vec![Backend::new("primary", primary, None), Backend::new("secondary", secondary, None)],
+            &RoutingConfig::default(),
+        )
+        .unwrap();
+
+        assert!(router.generate(vec![], &GenerateConfig::new()).await.is_err());
+        assert_eq!(secondary_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_failover_reason_uses_error_kind() {
+        let wrapped = anyhow::Error::from(HttpError::new(HttpErrorKind::Timeout, None, "slow"))
+            .context("Failed to call backend");
+        assert_eq!(failover_reason(&wrapped), Some("timeout"));
+
+        // The message text alone never triggers failover
+        assert_eq!(failover_reason(&anyhow::anyhow!("Server error 503: rate limited (429)")), None);
+        let client = HttpError::new(HttpErrorKind::Client, Some(400), "API error 400: server error");
+        assert_eq!(failover_reason(&client.into()), None);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_skips_failing_backend() {
+        let (primary, primary_calls) = TestProvider::boxed("primary", Some(HttpErrorKind::Server));
+        let (secondary, _) = TestProvider::boxed("secondary", None);
+        let config = RoutingConfig {
+            failure_threshold: 2,
+            cooldown_seconds: 3600,
+            ..Default::default()
+        };
+        let router = RoutingProvider::new(
+            vec![Backend::new("primary", primary, None), Backend::new("secondary", secondary, None)],
+            &config,
+        )
+        .unwrap();
+
+        for _ in 0..4 {
+            router.generate(vec![], &GenerateConfig::new()).await.unwrap();
+        }
+        // Open after two failures; the remaining calls go straight to the secondary
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 2);
+        assert_eq!(router.take_fallbacks().len(), 2);
+    }
+
+    #[test]
+    fn test_half_open_breaker_allows_one_trial() {
+        let cooldown = Duration::from_secs(60);
+        let cooled = || Instant::now().checked_sub(cooldown).unwrap();
+        let mut breaker = CircuitBreaker::default();
+        breaker.record_failure(1);
+        assert!(!breaker.begin_call(cooldown));
+
+        // One trial after the cool-down; concurrent calls skip the backend
+        breaker.opened_at = Some(cooled());
+        assert!(breaker.begin_call(cooldown));
+        assert!(!breaker.begin_call(cooldown));
+
+        // A failed trial reopens the circuit for another cool-down
+        breaker.record_failure(1);
+        assert!(!breaker.begin_call(cooldown));
+
+        // A trial whose caller went away is given up after another cool-down
+        breaker.opened_at = Some(cooled());
+        assert!(breaker.begin_call(cooldown));
+        breaker.trial_started = Some(cooled());
+        assert!(breaker.begin_call(cooldown));
+
+        // A successful trial closes the circuit
+        breaker.record_success();
+        assert!(breaker.begin_call(cooldown));
+        assert!(breaker.begin_call(cooldown));
+    }
+
+    /// Answers after `delay`.
+    struct SlowProvider {
+        delay: Duration,
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for SlowProvider {
+        async fn generate(&self, _messages: Vec<InternalMessage>, _config: &GenerateConfig) -> Result<GenerateResponse> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            Ok(GenerateResponse::Content { text: "slow".to_string(), reasoning: None })
+        }
+
+        async fn generate_stream(&self, _messages: Vec<InternalMessage>, _config: &GenerateConfig) -> Result<StreamingResponse> {
+            anyhow::bail!("not used")
+        }
+
+        fn provider_name(&self) -> &str {
+            "slow"
+        }
+
+        fn default_model(&self) -> String {
+            "slow-model".to_string()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_half_open_backend_gets_a_single_trial_call() {
+        let primary_calls = Arc::new(AtomicUsize::new(0));
+        let primary = SlowProvider {
+            delay: Duration::from_millis(200),
+            calls: primary_calls.clone(),
+        };
+        let (secondary, secondary_calls) = TestProvider::boxed("secondary", None);
+        let config = RoutingConfig {
+            failure_threshold: 1,
+            cooldown_seconds: 60,
+            ..Default::default()
+        };
+        let router = RoutingProvider::new(
+            vec![Backend::new("primary", Box::new(primary), None), Backend::new("secondary", secondary, None)],
+            &config,
+        )
+        .unwrap();
+        {
+            let mut breakers = router.breakers.lock().unwrap();
+            breakers[0].record_failure(1);
+            breakers[0].opened_at = Instant::now().checked_sub(Duration::from_secs(60));
+        }
+
+        let call = GenerateConfig::new();
+        let (trial, other) = tokio::join!(router.generate(vec![], &call), async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            router.generate(vec![], &call).await
+        });
+        assert_eq!(text(trial.unwrap()), "slow");
+        assert_eq!(text(other.unwrap()), "secondary:default");
+        assert_eq!(primary_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(secondary_calls.load(Ordering::SeqCst), 1);
+
+        // The trial succeeded, so the primary serves calls again
+        assert_eq!(text(router.generate(vec![], &GenerateConfig::new()).await.unwrap()), "slow");
+    }
+
+    #[tokio::test]
+    async fn test_rule_routes_calls_without_tools() {
+        let (strong, _) = TestProvider::boxed("strong", None);
+        let (cheap, _) = TestProvider::boxed("cheap", None);
+        let config = RoutingConfig {
+            rules: vec![RoutingRuleConfig {
+                backend: "cheap".to_string(),
+                tools: Some(false),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let router = RoutingProvider::new(
+            vec![Backend::new("strong", strong, None), Backend::new("cheap", cheap, None)],
+            &config,
+        )
+        .unwrap();
+
+        let summary = router.generate(vec![], &GenerateConfig::new()).await.unwrap();
+        assert_eq!(text(summary), "cheap:default");
+
+        let mut main_turn = GenerateConfig::new();
+        main_turn.tools = Some(vec![InternalToolDefinition::new("bash", "Run a command", serde_json::json!({}))]);
+        assert_eq!(text(router.generate(vec![], &main_turn).await.unwrap()), "strong:default");
+
+        let unknown = RoutingConfig {
+            rules: vec![RoutingRuleConfig { backend: "missing".to_string(), ..Default::default() }],
+            ..Default::default()
+        };
+        let (provider, _) = TestProvider::boxed("strong", None);
+        assert!(RoutingProvider::new(vec![Backend::new("strong", provider, None)], &unknown).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stream_fails_over_on_first_chunk_error() {
+        let (primary, _) = TestProvider::boxed("primary", Some(HttpErrorKind::RateLimited));
+        let (secondary, _) = TestProvider::boxed("secondary", None);
+        let router = RoutingProvider::new(
+            vec![Backend::new("primary", primary, None), Backend::new("secondary", secondary, None)],
+            &RoutingConfig::default(),
+        )
+        .unwrap();
+
+        let chunks: Vec<_> = router.generate_stream(vec![], &GenerateConfig::new()).await.unwrap().collect().await;
+        assert!(matches!(chunks[0], Ok(StreamChunk::Text(ref t)) if t == "secondary"));
+        assert_eq!(router.provider_name(), "secondary");
+        assert_eq!(router.take_fallbacks().len(), 1);
+    }
+}
@@ -0,0 +1,147 @@
+//! Signed thinking blocks carried through the conversation history.
+//!
+//! Anthropic requires the `thinking` and `redacted_thinking` blocks of an
+//! assistant turn to be sent back unchanged, signatures included, when that
+//! turn is continued with tool results. The ChatML history only keeps a
+//! `reasoning_content` string per assistant message, so the raw blocks ride
+//! along at its end as a trailer:
+//!
+//! ```text
+//! <readable thinking text>
+//! <!--signed-thinking [{"type":"thinking","thinking":"…","signature":"…"}]-->
+//! ```
+//!
+//! [`split`] separates the readable text from the blocks again;
+//! [`ChatMLAdapter`](super::ChatMLAdapter) moves the blocks into the
+//! [`METADATA_KEY`] metadata entry of the internal message, where the
+//! Anthropic request builder picks them up.
+//!
+//! The trailer never leaves the history: `GenerateResponse::reasoning` holds
+//! only the readable text, and the blocks of a tool-calling turn travel in the
+//! [`METADATA_KEY`] entry of its first call's `provider_metadata` ([`detach`],
+//! [`history_reasoning`]). Checkpoints keep them in
+//! `ChatMessage::thinking_blocks`. Blocks of a turn that ends with text are
+//! dropped, since the API ignores thinking from earlier turns.
+
+use super::traits::ToolInvocation;
+use serde_json::Value;
+
+/// Metadata key holding the JSON array of signed blocks on an internal message
+pub const METADATA_KEY: &str = "thinking_blocks";
+
+const TRAILER_START: &str = "\n<!--signed-thinking ";
+const TRAILER_END: &str = "-->";
+
+/// Trailer appended to reasoning text to carry `blocks`
+pub fn trailer(blocks: &[Value]) -> String {
+    format!(
+        "{}{}{}",
+        TRAILER_START,
+        Value::Array(blocks.to_vec()),
+        TRAILER_END
+    )
+}
+
+/// Append the signed `blocks` to readable reasoning `text`
+pub fn encode(text: &str, blocks: &[Value]) -> String {
+    if blocks.is_empty() {
+        return text.to_string();
+    }
+    format!("{}{}", text, trailer(blocks))
+}
+
+/// Whether a streamed reasoning chunk is a trailer rather than readable text
+pub fn is_trailer(chunk: &str) -> bool {
+    chunk.starts_with(TRAILER_START)
+}
+
+/// Split reasoning into its readable text and the signed blocks, if any
+pub fn split(reasoning: &str) -> (&str, Option<Vec<Value>>) {
+    let Some(start) = reasoning.rfind(TRAILER_START) else {
+        return (reasoning, None);
+    };
+    let encoded = reasoning[start + TRAILER_START.len()..].strip_suffix(TRAILER_END);
+    match encoded.and_then(|json| serde_json::from_str(json).ok()) {
+        Some(blocks) => (&reasoning[..start], Some(blocks)),
+        None => (reasoning, None),
+    }
+}
+
+/// Signed blocks stored under [`METADATA_KEY`] in `metadata`
+pub fn from_metadata(metadata: &std::collections::HashMap<String, String>) -> Option<Vec<Value>> {
+    metadata
+        .get(METADATA_KEY)
+        .and_then(|json| serde_json::from_str(json).ok())
+}
+
+/// Store the signed `blocks` of a tool-calling turn on its first call
+pub fn attach(calls: &mut [ToolInvocation], blocks: Vec<Value>) {
+    if let (Some(first), false) = (calls.first_mut(), blocks.is_empty()) {
+        first
+            .provider_metadata
+            .insert(METADATA_KEY.to_string(), Value::Array(blocks).to_string());
+    }
+}
+
+/// Readable part of streamed `reasoning`, with any signed blocks moved onto
+/// `calls` (and dropped when there are none)
+pub fn detach(reasoning: &str, calls: &mut [ToolInvocation]) -> Option<String> {
+    let (text, blocks) = split(reasoning);
+    if let Some(blocks) = blocks {
+        attach(calls, blocks);
+    }
+    Some(text.to_string()).filter(|t| !t.is_empty())
+}
+
+/// Reasoning to keep in the ChatML history for a tool-calling turn: the
+/// readable `reasoning` plus the trailer for any blocks attached to `calls`
+pub fn history_reasoning(reasoning: Option<String>, calls: &[ToolInvocation]) -> Option<String> {
+    match calls.first().and_then(|call| from_metadata(&call.provider_metadata)) {
+        Some(blocks) => Some(encode(reasoning.as_deref().unwrap_or_default(), &blocks)),
+        None => reasoning,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_round_trip() {
+        let blocks = vec![
+            json!({"type": "thinking", "thinking": "Need the file.", "signature": "sig"}),
+            json!({"type": "redacted_thinking", "data": "opaque"}),
+        ];
+        let reasoning = encode("Need the file.", &blocks);
+        assert_eq!(split(&reasoning), ("Need the file.", Some(blocks)));
+        assert!(is_trailer(&trailer(&[json!({})])));
+
+        assert_eq!(split("plain reasoning"), ("plain reasoning", None));
+        assert_eq!(encode("plain", &[]), "plain");
+        let broken = "text\n<!--signed-thinking not json-->";
+        assert_eq!(split(broken), (broken, None));
+    }
+
+    #[test]
+    fn test_blocks_move_between_reasoning_and_call_metadata() {
+        let blocks = vec![json!({"type": "thinking", "thinking": "Need the file.", "signature": "sig"})];
+        let mut calls = vec![ToolInvocation {
+            id: "toolu_1".to_string(),
+            name: "read_file".to_string(),
+            arguments: json!({}),
+            provider_metadata: Default::default(),
+        }];
+
+        let readable = detach(&encode("Need the file.", &blocks), &mut calls);
+        assert_eq!(readable.as_deref(), Some("Need the file."));
+        assert_eq!(from_metadata(&calls[0].provider_metadata), Some(blocks.clone()));
+
+        let history = history_reasoning(readable, &calls).unwrap();
+        assert_eq!(split(&history), ("Need the file.", Some(blocks.clone())));
+
+        // Redacted-only turns have no readable text but keep their blocks
+        assert_eq!(detach(&trailer(&blocks), &mut []), None);
+        assert_eq!(history_reasoning(None, &calls), Some(encode("", &blocks)));
+    }
+}
@@ -6,6 +6,7 @@
 
 use crate::provider::types::generate::GenerateConfig;
 use crate::provider::types::internal::InternalMessage;
+use crate::provider::usage::TokenUsage;
 use anyhow::Result;
 use futures_util::Stream;
 use std::pin::Pin;
@@ -48,6 +49,17 @@ pub struct ToolInvocation {
     pub provider_metadata: std::collections::HashMap<String, String>,
 }
 
+/// A call that moved from one backend to another (see `RoutingProvider`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderFallback {
+    /// Backend that failed
+    pub from: String,
+    /// Backend tried next
+    pub to: String,
+    /// Why `from` was abandoned (e.g., "rate limited")
+    pub reason: String,
+}
+
 /// Type alias for streaming response
 pub type StreamingResponse = Pin<Box<dyn Stream<Item = Result<StreamChunk>> + Send>>;
 
@@ -127,4 +139,25 @@ pub trait LlmProvider: Send + Sync {
     /// # Returns
     /// Model identifier (e.g., "gpt-4o-mini", "claude-sonnet-4-5")
     fn default_model(&self) -> String;
+
+    /// Take the token usage reported for the most recent completed call
+    ///
+    /// For streaming calls the usage is available once the stream has yielded
+    /// `StreamChunk::Done`. Taking it clears it, so each call is counted once.
+    ///
+    /// # Returns
+    /// `None` if the provider doesn't report usage (the default)
+    fn take_usage(&self) -> Option<TokenUsage> {
+        None
+    }
+
+    /// Take the backend fallbacks that happened since the last call
+    ///
+    /// Only providers composed of several backends report fallbacks.
+    ///
+    /// # Returns
+    /// An empty list for single-backend providers (the default)
+    fn take_fallbacks(&self) -> Vec<ProviderFallback> {
+        Vec::new()
+    }
 }
@@ -0,0 +1,305 @@
+//! Token usage reporting and cost estimation.
+//!
+//! Providers parse the `usage` object of each response into a [`TokenUsage`]
+//! and hand it out through [`LlmProvider::take_usage`](crate::provider::LlmProvider::take_usage).
+//! A [`UsageTracker`] accumulates those per session and prices them with a
+//! [`PriceTable`] built from `[llm.pricing]`.
+
+use crate::config::ModelPricing;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::ops::AddAssign;
+
+/// Token counts reported for one or more LLM calls.
+///
+/// Follows the OpenAI convention: `prompt_tokens` includes `cached_tokens`
+/// and `completion_tokens` includes `reasoning_tokens`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    /// Input tokens, cached or not
+    pub prompt_tokens: u64,
+    /// Output tokens, including reasoning
+    pub completion_tokens: u64,
+    /// Input tokens read from the provider's prompt cache
+    pub cached_tokens: u64,
+    /// Output tokens spent on reasoning/thinking
+    pub reasoning_tokens: u64,
+}
+
+impl TokenUsage {
+    /// Prompt plus completion tokens.
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    /// Parse an OpenAI chat-completions `usage` object.
+    pub fn from_openai(usage: &Value) -> Option<Self> {
+        let count = |v: Option<&Value>| v.and_then(|v| v.as_u64()).unwrap_or(0);
+        usage.as_object()?;
+
+        Some(Self {
+            prompt_tokens: count(usage.get("prompt_tokens")),
+            completion_tokens: count(usage.get("completion_tokens")),
+            cached_tokens: count(usage.pointer("/prompt_tokens_details/cached_tokens")),
+            reasoning_tokens: count(usage.pointer("/completion_tokens_details/reasoning_tokens")),
+        })
+    }
+
+    /// Parse an Anthropic Messages API `usage` object.
+    ///
+    /// Anthropic reports cache reads and writes separately from
+    /// `input_tokens`; both are folded into `prompt_tokens` here.
+    pub fn from_anthropic(usage: &Value) -> Option<Self> {
+        let count = |key: &str| usage.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+        usage.as_object()?;
+
+        let cached_tokens = count("cache_read_input_tokens");
+        Some(Self {
+            prompt_tokens: count("input_tokens") + count("cache_creation_input_tokens") + cached_tokens,
+            completion_tokens: count("output_tokens"),
+            cached_tokens,
+            reasoning_tokens: 0,
+        })
+    }
+
+    /// Parse a `usage` object in either OpenAI or Anthropic shape.
+    ///
+    /// Used for providers whose wire format isn't known up front, such as
+    /// WASM and extension providers.
+    pub fn from_any(usage: &Value) -> Option<Self> {
+        if usage.get("input_tokens").is_some() || usage.get("output_tokens").is_some() {
+            Self::from_anthropic(usage)
+        } else {
+            Self::from_openai(usage)
+        }
+    }
+
+    /// Usage reported in a raw (non-streaming) response body, if any.
+    pub fn from_response_body(body: &str) -> Option<Self> {
+        let json: Value = serde_json::from_str(body).ok()?;
+        json.get("usage").and_then(Self::from_any)
+    }
+
+    /// Fold the usage carried by one raw SSE event into `usage`.
+    ///
+    /// Handles the OpenAI final usage chunk, Anthropic's `message_start`
+    /// (input counts) and `message_delta` (cumulative output count).
+    pub fn update_from_sse_event(event: &str, usage: &mut Option<Self>) {
+        for data in event.lines().filter_map(|line| line.strip_prefix("data: ")) {
+            let Ok(json) = serde_json::from_str::<Value>(data) else {
+                continue;
+            };
+            if let Some(start) = json.pointer("/message/usage").and_then(Self::from_any) {
+                *usage = Some(start);
+            } else if let Some(reported) = json.get("usage").filter(|u| !u.is_null()) {
+                match (reported.get("input_tokens"), reported.get("output_tokens"), usage.as_mut()) {
+                    // Anthropic message_delta only carries the output count
+                    (None, Some(output), Some(current)) => {
+                        current.completion_tokens = output.as_u64().unwrap_or(current.completion_tokens);
+                    }
+                    _ => {
+                        if let Some(parsed) = Self::from_any(reported) {
+                            *usage = Some(parsed);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cached_tokens += other.cached_tokens;
+        self.reasoning_tokens += other.reasoning_tokens;
+    }
+}
+
+/// Model name → price lookup.
+#[derive(Debug, Clone, Default)]
+pub struct PriceTable {
+    prices: HashMap<String, ModelPricing>,
+}
+
+impl PriceTable {
+    /// Build a table from `[llm.pricing]` entries.
+    pub fn new(prices: HashMap<String, ModelPricing>) -> Self {
+        Self { prices }
+    }
+
+    /// Price for `model`: exact match first, then the longest key that prefixes it.
+    pub fn lookup(&self, model: &str) -> Option<&ModelPricing> {
+        self.prices.get(model).or_else(|| {
+            self.prices
+                .iter()
+                .filter(|(key, _)| model.starts_with(key.as_str()))
+                .max_by_key(|(key, _)| key.len())
+                .map(|(_, price)| price)
+        })
+    }
+
+    /// Estimated USD cost of `usage` on `model`, if the model is priced.
+    pub fn cost(&self, model: &str, usage: &TokenUsage) -> Option<f64> {
+        let price = self.lookup(model)?;
+        let cached = usage.cached_tokens.min(usage.prompt_tokens);
+        let uncached = usage.prompt_tokens - cached;
+
+        let scaled = uncached as f64 * price.input
+            + cached as f64 * price.cached_input.unwrap_or(price.input)
+            + usage.completion_tokens as f64 * price.output;
+        Some(scaled / 1_000_000.0)
+    }
+}
+
+/// Running usage totals for a session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    /// Summed token counts
+    pub usage: TokenUsage,
+    /// Number of LLM calls made
+    pub api_calls: u32,
+    /// Summed cost of priced calls (None until a priced model reports usage)
+    pub estimated_cost: Option<f64>,
+    /// Calls whose provider reported no usage, so the totals undercount
+    #[serde(default)]
+    pub unreported_calls: u32,
+}
+
+/// Accumulates provider usage for one session.
+#[derive(Debug, Clone, Default)]
+pub struct UsageTracker {
+    prices: PriceTable,
+    totals: UsageTotals,
+}
+
+impl UsageTracker {
+    /// Create a tracker that prices calls with `prices`.
+    pub fn new(prices: PriceTable) -> Self {
+        Self {
+            prices,
+            totals: UsageTotals::default(),
+        }
+    }
+
+    /// Record one LLM call. `usage` is `None` when the provider doesn't report it.
+    ///
+    /// Returns the estimated cost of this call, if the model is priced.
+    pub fn record(&mut self, model: &str, usage: Option<TokenUsage>) -> Option<f64> {
+        self.totals.api_calls += 1;
+        let Some(usage) = usage else {
+            self.totals.unreported_calls += 1;
+            return None;
+        };
+        self.totals.usage += usage;
+
+        let cost = self.prices.cost(model, &usage)?;
+        *self.totals.estimated_cost.get_or_insert(0.0) += cost;
+        Some(cost)
+    }
+
+    /// Totals so far.
+    pub fn totals(&self) -> &UsageTotals {
+        &self.totals
+    }
+
+    /// Restore totals, e.g. after resuming a session from a checkpoint.
+    pub fn restore(&mut self, totals: UsageTotals) {
+        self.totals = totals;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_provider_usage() {
+        let openai = TokenUsage::from_openai(&json!({
+            "prompt_tokens": 1200,
+            "completion_tokens": 300,
+            "prompt_tokens_details": {"cached_tokens": 1000},
+            "completion_tokens_details": {"reasoning_tokens": 120}
+        }))
+        .unwrap();
+        assert_eq!(openai.cached_tokens, 1000);
+        assert_eq!(openai.reasoning_tokens, 120);
+        assert_eq!(openai.total_tokens(), 1500);
+
+        let anthropic = TokenUsage::from_anthropic(&json!({
+            "input_tokens": 50,
+            "cache_creation_input_tokens": 100,
+            "cache_read_input_tokens": 1000,
+            "output_tokens": 20
+        }))
+        .unwrap();
+        assert_eq!(anthropic.prompt_tokens, 1150);
+        assert_eq!(anthropic.cached_tokens, 1000);
+
+        assert!(TokenUsage::from_openai(&Value::Null).is_none());
+    }
+
+    #[test]
+    fn test_parse_usage_of_unknown_format() {
+        let body = r#"{"choices": [], "usage": {"prompt_tokens": 10, "completion_tokens": 5}}"#;
+        assert_eq!(TokenUsage::from_response_body(body).unwrap().total_tokens(), 15);
+        let body = r#"{"content": [], "usage": {"input_tokens": 7, "output_tokens": 3}}"#;
+        assert_eq!(TokenUsage::from_response_body(body).unwrap().prompt_tokens, 7);
+        assert!(TokenUsage::from_response_body("not json").is_none());
+
+        // Anthropic stream: input counts at start, cumulative output in the delta
+        let mut usage = None;
+        TokenUsage::update_from_sse_event(
+            "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":40,\"output_tokens\":1}}}",
+            &mut usage,
+        );
+        TokenUsage::update_from_sse_event(
+            "event: message_delta\ndata: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":25}}",
+            &mut usage,
+        );
+        let usage = usage.unwrap();
+        assert_eq!((usage.prompt_tokens, usage.completion_tokens), (40, 25));
+
+        // OpenAI final usage chunk; earlier chunks carry `"usage": null`
+        let mut usage = None;
+        TokenUsage::update_from_sse_event(r#"data: {"choices":[{"delta":{}}],"usage":null}"#, &mut usage);
+        assert!(usage.is_none());
+        TokenUsage::update_from_sse_event(
+            r#"data: {"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":8}}"#,
+            &mut usage,
+        );
+        assert_eq!(usage.unwrap().total_tokens(), 20);
+    }
+
+    #[test]
+    fn test_tracker_prices_by_model_prefix() {
+        let mut prices = HashMap::new();
+        prices.insert(
+            "claude-sonnet-4-5".to_string(),
+            ModelPricing { input: 3.0, output: 15.0, cached_input: Some(0.3) },
+        );
+        let mut tracker = UsageTracker::new(PriceTable::new(prices));
+
+        let usage = TokenUsage {
+            prompt_tokens: 2_000_000,
+            completion_tokens: 100_000,
+            cached_tokens: 1_000_000,
+            reasoning_tokens: 0,
+        };
+        let cost = tracker.record("claude-sonnet-4-5-20250929", Some(usage)).unwrap();
+        assert!((cost - (3.0 + 0.3 + 1.5)).abs() < 1e-9);
+
+        // Unpriced model and unreported usage still count as calls
+        assert!(tracker.record("gpt-4o", Some(usage)).is_none());
+        assert!(tracker.record("gpt-4o", None).is_none());
+
+        let totals = tracker.totals();
+        assert_eq!(totals.api_calls, 3);
+        assert_eq!(totals.unreported_calls, 1);
+        assert_eq!(totals.usage.prompt_tokens, 4_000_000);
+        assert!((totals.estimated_cost.unwrap() - cost).abs() < 1e-9);
+    }
+}
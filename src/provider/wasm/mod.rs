@@ -20,11 +20,20 @@
 //! - format-request: Formats request based on backend (auto-detected from model)
 //! - parse-response: Parses response based on backend
 //! - build-tanbal-headers: Custom headers for multi-LLM providers
+//!
+//! ## Sandboxing
+//!
+//! Each call runs in a fresh store built from the provider's
+//! [`ExtensionSandbox`]: permissions and limits come from an `extension.toml`
+//! next to the `.wasm` file, and a provider without one gets no host access.
+//! Providers used to inherit the whole host environment; one that declares no
+//! `env` permission still sees the variables its metadata lists in `env_vars`.
 
 use crate::config::EnvironmentLoader;
+use crate::extension::ExtensionSandbox;
 use crate::provider::{
     GenerateConfig, GenerateResponse, InternalMessage, LlmProvider,
-    ToolInvocation,
+    TokenUsage, ToolInvocation,
 };
 use anyhow::{Context, Result};
 use reqwest;
@@ -33,7 +42,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::OnceCell;
 use wasmtime::component::*;
-use wasmtime::{Engine};
+use wasmtime::{Engine, StoreLimits};
 use wasmtime_wasi::{WasiCtx, WasiView};
 
 /// Conditional debug macro - only prints if RUST_LOG is set to debug
@@ -48,6 +57,7 @@ macro_rules! debug {
 struct ComponentState {
     ctx: WasiCtx,
     table: wasmtime::component::ResourceTable,
+    limits: StoreLimits,
 }
 
 impl WasiView for ComponentState {
@@ -99,6 +109,12 @@ pub struct WasmProvider {
     
     /// Configuration overrides (takes precedence over environment variables)
     config_overrides: std::collections::HashMap<String, String>,
+
+    /// WASI permissions and resource limits for every call
+    sandbox: ExtensionSandbox,
+
+    /// Usage of the last completed call, see [`LlmProvider::take_usage`]
+    usage: Arc<std::sync::Mutex<Option<TokenUsage>>>,
 }
 
 impl WasmProvider {
@@ -130,6 +146,7 @@ impl WasmProvider {
         let mut config = wasmtime::Config::new();
         config.wasm_component_model(true);
         config.async_support(true);
+        crate::extension::configure_engine(&mut config);
         let engine = Engine::new(&config)?;
         
         // Load WASM component from file
@@ -137,6 +154,20 @@ impl WasmProvider {
             .with_context(|| format!("Failed to load WASM component from {}", wasm_path.display()))?;
         
         let engine = Arc::new(engine);
+
+        // Permissions come from an extension.toml next to the module, if any
+        let provider_dir = wasm_path.parent().map(PathBuf::from).unwrap_or_default();
+        let manifest_path = provider_dir.join("extension.toml");
+        let sandbox = if manifest_path.exists() {
+            let manifest = crate::extension::ExtensionManifest::from_file(&manifest_path)
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            ExtensionSandbox::from_manifest(&manifest, &provider_dir)
+        } else {
+            ExtensionSandbox {
+                base_dir: provider_dir,
+                ..Default::default()
+            }
+        };
         
         // Metadata will be loaded on first use
         Ok(Self {
@@ -148,27 +179,36 @@ impl WasmProvider {
             engine,
             component,
             config_overrides,
+            sandbox,
+            usage: Arc::new(std::sync::Mutex::new(None)),
         })
     }
+
+    /// Replace the sandbox the provider's WASM runs in
+    pub fn with_sandbox(mut self, sandbox: ExtensionSandbox) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
     
+    /// Sandbox for calls into the provider
+    ///
+    /// Without an `env` permission (no `extension.toml`, or one written before
+    /// `[permissions]` existed) the provider gets the variables named in its
+    /// metadata's `env_vars`, which it used to read from the inherited host
+    /// environment, once the metadata is loaded.
+    fn call_sandbox(&self) -> ExtensionSandbox {
+        let mut sandbox = self.sandbox.clone();
+        if sandbox.permissions.env.is_empty() {
+            if let Some(metadata) = self.metadata.get() {
+                sandbox.permissions.env = metadata_env_names(metadata);
+            }
+        }
+        sandbox
+    }
+
     /// Create a configured store and linker with WASI support
     fn create_store_and_linker(&self) -> Result<(wasmtime::Store<ComponentState>, Linker<ComponentState>)> {
-        // Create WASI context
-        let wasi_ctx = wasmtime_wasi::WasiCtxBuilder::new().inherit_env().build();
-        let state = ComponentState {
-            ctx: wasi_ctx,
-            table: wasmtime::component::ResourceTable::new(),
-        };
-        
-        // Create store with WASI state
-        let store = wasmtime::Store::new(&*self.engine, state);
-        
-        // Create and configure linker with WASI
-        let mut linker = Linker::new(&*self.engine);
-        wasmtime_wasi::add_to_linker_async(&mut linker)
-            .context("Failed to add WASI to linker")?;
-        
-        Ok((store, linker))
+        create_store_and_linker(&self.engine, &self.call_sandbox())
     }
     
     /// Load provider metadata from WASM get-provider-metadata export (async, called on first use)
@@ -306,6 +346,10 @@ impl LlmProvider for WasmProvider {
     fn provider_name(&self) -> &str {
         &self.name
     }
+
+    fn take_usage(&self) -> Option<TokenUsage> {
+        self.usage.lock().ok()?.take()
+    }
     
     fn default_model(&self) -> String {
         // Try to get the actual model from environment variables
@@ -439,7 +483,12 @@ impl LlmProvider for WasmProvider {
         let response_body = response.text().await?;
         
         if !status.is_success() {
-            anyhow::bail!("API error {}: {}", status, response_body);
+            return Err(crate::provider::HttpError::from_status(status, &response_body).into());
+        }
+
+        // The WASM interface doesn't return usage, so read it from the raw body
+        if let Ok(mut slot) = self.usage.lock() {
+            *slot = TokenUsage::from_response_body(&response_body);
         }
         
         // Parse response using WASM - it will detect backend from model string
@@ -668,7 +717,7 @@ impl LlmProvider for WasmProvider {
         
         if !status.is_success() {
             let error_body = response.text().await?;
-            anyhow::bail!("API error {}: {}", status, error_body);
+            return Err(crate::provider::HttpError::from_status(status, &error_body).into());
         }
         
         // Create streaming response
@@ -678,6 +727,8 @@ impl LlmProvider for WasmProvider {
         // Clone necessary data for the stream closure
         let component = self.component.clone();
         let engine = self.engine.clone();
+        let sandbox = self.call_sandbox();
+        let usage_slot = Arc::clone(&self.usage);
         
         // Buffer for incomplete SSE lines across HTTP chunks
         let line_buffer = std::sync::Arc::new(tokio::sync::Mutex::new(String::new()));
@@ -689,6 +740,13 @@ impl LlmProvider for WasmProvider {
         tokio::spawn(async move {
             use futures_util::StreamExt;
             let mut byte_stream = byte_stream;
+            let mut usage = None;
+            // Publish usage before Done so callers can take it once the stream ends
+            let publish_usage = |usage: Option<TokenUsage>| {
+                if let Ok(mut slot) = usage_slot.lock() {
+                    *slot = usage;
+                }
+            };
             
             while let Some(chunk_result) = byte_stream.next().await {
                 match chunk_result {
@@ -715,6 +773,7 @@ impl LlmProvider for WasmProvider {
                             
                             // Check for [DONE] marker (can be in "data: [DONE]" format)
                             if event.contains("data: [DONE]") {
+                                publish_usage(usage);
                                 let _ = tx.send(Ok(crate::provider::StreamChunk::Done));
                                 return; // End the stream
                             }
@@ -723,8 +782,9 @@ impl LlmProvider for WasmProvider {
                             // For OpenAI: "data: {...}"
                             // For Anthropic: "event: xxx\ndata: {...}"
                             if event.contains("data: ") {
+                                TokenUsage::update_from_sse_event(&event, &mut usage);
                                 // Call WASM to handle stream chunk (pass complete event including event: line if present)
-                                if let Ok(delta) = process_stream_chunk(&component, &engine, &event).await {
+                                if let Ok(delta) = process_stream_chunk(&component, &engine, &sandbox, &event).await {
                                     // Handle reasoning delta (from thinking models like GLM, Qwen)
                                     if let Some(reasoning) = delta.reasoning {
                                         // Log reasoning to file only; display is handled by OutputSink
@@ -779,6 +839,8 @@ impl LlmProvider for WasmProvider {
                     }
                 }
             }
+            // Streams without a [DONE] marker (e.g. Anthropic) just end
+            publish_usage(usage);
         });
         
         // Convert channel receiver to stream using futures_util
@@ -790,24 +852,54 @@ impl LlmProvider for WasmProvider {
     }
 }
 
-/// Process a single stream chunk through WASM
-async fn process_stream_chunk(
-    component: &wasmtime::component::Component,
-    engine: &Arc<wasmtime::Engine>,
-    chunk: &str,
-) -> Result<exports::abk::provider::adapter::ContentDelta> {
-    // Create store and linker
-    let wasi_ctx = wasmtime_wasi::WasiCtxBuilder::new().inherit_env().build();
+/// Environment variable names listed in provider metadata's `env_vars`
+fn metadata_env_names(metadata: &Value) -> Vec<String> {
+    metadata["env_vars"]
+        .as_object()
+        .map(|vars| {
+            vars.values()
+                .filter_map(|spec| spec["name"].as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Create a sandboxed store (WASI context, memory cap, fuel) and a WASI linker
+fn create_store_and_linker(
+    engine: &Engine,
+    sandbox: &ExtensionSandbox,
+) -> Result<(wasmtime::Store<ComponentState>, Linker<ComponentState>)> {
+    // Create WASI context granting only the declared permissions
+    let wasi_ctx = sandbox.wasi_ctx().map_err(|e| anyhow::anyhow!("{}", e))?;
     let state = ComponentState {
         ctx: wasi_ctx,
         table: wasmtime::component::ResourceTable::new(),
+        limits: sandbox.store_limits(),
     };
+
+    // Create store with WASI state
     let mut store = wasmtime::Store::new(engine, state);
-    
-    let mut linker = wasmtime::component::Linker::new(engine);
+    store.limiter(|state| &mut state.limits);
+    store.set_fuel(sandbox.fuel()).context("Failed to set WASM fuel")?;
+
+    // Create and configure linker with WASI
+    let mut linker = Linker::new(engine);
     wasmtime_wasi::add_to_linker_async(&mut linker)
         .context("Failed to add WASI to linker")?;
-    
+
+    Ok((store, linker))
+}
+
+/// Process a single stream chunk through WASM
+async fn process_stream_chunk(
+    component: &wasmtime::component::Component,
+    engine: &Arc<wasmtime::Engine>,
+    sandbox: &ExtensionSandbox,
+    chunk: &str,
+) -> Result<exports::abk::provider::adapter::ContentDelta> {
+    // Create store and linker
+    let (mut store, linker) = create_store_and_linker(engine, sandbox)?;
+
     // Instantiate component
     let instance = Provider::instantiate_async(&mut store, component, &linker)
         .await
@@ -826,4 +918,22 @@ async fn process_stream_chunk(
 mod tests {
     // WASM provider tests should be done at integration test level
     // with actual WASM files and runtime environment
+
+    use super::*;
+
+    #[test]
+    fn test_metadata_env_names() {
+        let metadata = json!({
+            "name": "openai",
+            "env_vars": {
+                "api_key": {"name": "OPENAI_API_KEY", "required": true},
+                "base_url": {"name": "OPENAI_BASE_URL", "default": "https://api.openai.com/v1"},
+                "broken": {"required": false}
+            }
+        });
+        let mut names = metadata_env_names(&metadata);
+        names.sort();
+        assert_eq!(names, vec!["OPENAI_API_KEY", "OPENAI_BASE_URL"]);
+        assert!(metadata_env_names(&json!({})).is_empty());
+    }
 }
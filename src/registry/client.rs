@@ -3,9 +3,16 @@
 //! This module provides an async client to connect to MCP servers
 //! and fetch available tools using the JSON-RPC protocol.
 
+use super::notification::{McpNotification, McpProgress, NotificationHub};
+use super::sse::SseDecoder;
+use super::stdio::{server_error, McpStdioConfig, StdioConnection};
 use super::{RegistryError, RegistryResult};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
 use umf::McpTool;
 
 #[cfg(feature = "registry-mcp-token")]
@@ -16,8 +23,14 @@ use pep::token_provider::{TokenProvider, TokenProviderEnum, StaticTokenProvider}
 pub struct McpServerConfig {
     /// Server name/identifier
     pub name: String,
-    /// Server base URL (e.g., "http://127.0.0.1:8000/pdt")
+    /// Server base URL (e.g., "http://127.0.0.1:8000/pdt"); unused for stdio servers
     pub url: String,
+    /// Subprocess launch settings. When set, the server is spoken to over
+    /// stdin/stdout instead of HTTP.
+    pub stdio: Option<McpStdioConfig>,
+    /// Use the Streamable HTTP transport (POST to `url` itself, SSE
+    /// responses, `Mcp-Session-Id`) instead of the legacy `{url}/message`.
+    pub streamable_http: bool,
     /// Authentication token (static, resolved at creation time).
     /// When `registry-mcp-token` feature is enabled, use `with_token_provider()`
     /// for dynamic token management.
@@ -33,12 +46,27 @@ impl McpServerConfig {
         Self {
             name: name.into(),
             url: url.into(),
+            stdio: None,
+            streamable_http: false,
             auth_token: None,
             #[cfg(feature = "registry-mcp-token")]
             token_provider: None,
         }
     }
 
+    /// Create a configuration for a server launched as a subprocess (stdio transport).
+    pub fn stdio(name: impl Into<String>, stdio: McpStdioConfig) -> Self {
+        let mut config = Self::new(name, "");
+        config.stdio = Some(stdio);
+        config
+    }
+
+    /// Use the Streamable HTTP transport.
+    pub fn with_streamable_http(mut self) -> Self {
+        self.streamable_http = true;
+        self
+    }
+
     /// Set a static authentication token.
     pub fn with_auth(mut self, token: impl Into<String>) -> Self {
         self.auth_token = Some(token.into());
@@ -62,7 +90,9 @@ impl McpServerConfig {
 #[derive(Debug, Serialize)]
 struct JsonRpcRequest {
     jsonrpc: String,
-    id: u64,
+    /// `None` for notifications
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<u64>,
     method: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     params: Option<Value>,
@@ -120,9 +150,42 @@ struct ToolsListResult {
     tools: Vec<McpToolResponse>,
 }
 
+/// Header carrying the Streamable HTTP session id.
+const SESSION_HEADER: &str = "Mcp-Session-Id";
+
+/// Outcome of POSTing one JSON-RPC message over HTTP.
+enum Exchange {
+    /// The matching JSON-RPC response
+    Response(Box<JsonRpcResponse>),
+    /// No response body expected (notification accepted)
+    Accepted,
+    /// The server no longer knows our session id (HTTP 404)
+    SessionExpired,
+}
+
 /// MCP Client for communicating with MCP servers.
+///
+/// HTTP servers are reached through the legacy `{url}/message` endpoint or,
+/// with [`McpServerConfig::with_streamable_http`], the Streamable HTTP
+/// transport: responses may be plain JSON or a `text/event-stream`, and the
+/// `Mcp-Session-Id` issued by `initialize` is sent with every later request.
+///
+/// Stdio servers are spawned on first use and kept running for the
+/// lifetime of the client (restarted if they exit). Call
+/// [`shutdown`](Self::shutdown) to stop them cleanly; dropping the client
+/// kills them.
+///
+/// Server notifications from every transport are published through
+/// [`subscribe`](Self::subscribe).
 pub struct McpClient {
     http_client: reqwest::Client,
+    stdio_connections: tokio::sync::Mutex<HashMap<String, Arc<StdioConnection>>>,
+    /// Streamable HTTP session ids by server name
+    sessions: Mutex<HashMap<String, String>>,
+    /// Standalone SSE streams opened after initialization, by server name
+    listeners: Mutex<HashMap<String, tokio::task::JoinHandle<()>>>,
+    hub: Arc<NotificationHub>,
+    next_id: AtomicU64,
 }
 
 impl Default for McpClient {
@@ -131,11 +194,82 @@ impl Default for McpClient {
     }
 }
 
+impl Drop for McpClient {
+    fn drop(&mut self) {
+        if let Ok(listeners) = self.listeners.lock() {
+            for handle in listeners.values() {
+                handle.abort();
+            }
+        }
+    }
+}
+
 impl McpClient {
     /// Create a new MCP client.
     pub fn new() -> Self {
         Self {
             http_client: reqwest::Client::new(),
+            stdio_connections: tokio::sync::Mutex::new(HashMap::new()),
+            sessions: Mutex::new(HashMap::new()),
+            listeners: Mutex::new(HashMap::new()),
+            hub: Arc::new(NotificationHub::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Subscribe to notifications from all servers this client talks to.
+    pub fn subscribe(&self) -> broadcast::Receiver<McpNotification> {
+        self.hub.subscribe()
+    }
+
+    /// Take the servers that sent `notifications/tools/list_changed` since the last call.
+    pub fn take_changed_servers(&self) -> Vec<String> {
+        self.hub.take_changed()
+    }
+
+    /// Mark a server's tool list as up to date.
+    pub fn clear_tools_changed(&self, server: &str) {
+        self.hub.clear_changed(server);
+    }
+
+    /// Current Streamable HTTP session id for a server, if one was issued.
+    pub fn session_id(&self, server: &str) -> Option<String> {
+        self.sessions.lock().ok().and_then(|s| s.get(server).cloned())
+    }
+
+    fn forget_session(&self, server: &str) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.remove(server);
+        }
+    }
+
+    /// Get the running process for a stdio server, starting it if needed.
+    async fn stdio_connection(
+        &self,
+        config: &McpServerConfig,
+        stdio: &McpStdioConfig,
+    ) -> RegistryResult<Arc<StdioConnection>> {
+        let mut connections = self.stdio_connections.lock().await;
+        if let Some(connection) = connections.get(&config.name) {
+            if connection.is_alive() {
+                return Ok(connection.clone());
+            }
+        }
+        let connection = Arc::new(StdioConnection::start(&config.name, stdio, self.hub.clone()).await?);
+        connections.insert(config.name.clone(), connection.clone());
+        Ok(connection)
+    }
+
+    /// Stop all stdio server processes and SSE streams started by this client.
+    pub async fn shutdown(&self) {
+        if let Ok(mut listeners) = self.listeners.lock() {
+            for (_, handle) in listeners.drain() {
+                handle.abort();
+            }
+        }
+        let connections: Vec<_> = self.stdio_connections.lock().await.drain().map(|(_, c)| c).collect();
+        for connection in connections {
+            connection.shutdown().await;
         }
     }
 
@@ -199,18 +333,21 @@ impl McpClient {
         }
     }
 
-    /// Fetch tools from an MCP server.
-    pub async fn fetch_tools(&self, config: &McpServerConfig) -> RegistryResult<Vec<McpTool>> {
-        let message_url = format!("{}/message", config.url.trim_end_matches('/'));
-
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: 1,
-            method: "tools/list".to_string(),
-            params: Some(json!({})),
-        };
+    /// HTTP endpoint for JSON-RPC messages.
+    fn endpoint(config: &McpServerConfig) -> String {
+        let base = config.url.trim_end_matches('/');
+        if config.streamable_http {
+            base.to_string()
+        } else {
+            format!("{}/message", base)
+        }
+    }
 
-        let mut http_request = self.http_client.post(&message_url).json(&request);
+    /// Attach the session id and credentials to an HTTP request.
+    async fn authorize(&self, mut http_request: reqwest::RequestBuilder, config: &McpServerConfig) -> reqwest::RequestBuilder {
+        if let Some(session) = self.session_id(&config.name) {
+            http_request = http_request.header(SESSION_HEADER, session);
+        }
 
         #[cfg(feature = "registry-mcp-token")]
         self.apply_auth(&mut http_request, config).await;
@@ -218,148 +355,286 @@ impl McpClient {
         #[cfg(not(feature = "registry-mcp-token"))]
         self.apply_auth_static(&mut http_request, config);
 
+        http_request
+    }
+
+    /// POST one JSON-RPC message.
+    ///
+    /// With `expect_response = false` (notifications) the body is not read.
+    async fn post_message(
+        &self,
+        config: &McpServerConfig,
+        message: &JsonRpcRequest,
+        context: &str,
+        expect_response: bool,
+    ) -> RegistryResult<Exchange> {
+        let had_session = self.session_id(&config.name).is_some();
+        let http_request = self
+            .http_client
+            .post(Self::endpoint(config))
+            .header(reqwest::header::ACCEPT, "application/json, text/event-stream")
+            .json(message);
+        let http_request = self.authorize(http_request, config).await;
+
         let response = http_request
             .send()
             .await
-            .map_err(|e| RegistryError::McpServerError {
-                server: config.name.clone(),
-                message: format!("HTTP request failed: {}", e),
-            })?;
+            .map_err(|e| server_error(&config.name, format!("{} request failed: {}", context, e)))?;
 
-        if !response.status().is_success() {
-            return Err(RegistryError::McpServerError {
-                server: config.name.clone(),
-                message: format!("HTTP {} - {}", response.status(), response.status().as_str()),
-            });
+        if let Some(session) = response
+            .headers()
+            .get(SESSION_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            if let Ok(mut sessions) = self.sessions.lock() {
+                sessions.insert(config.name.clone(), session.to_string());
+            }
         }
 
-        let rpc_response: JsonRpcResponse =
-            response
-                .json()
-                .await
-                .map_err(|e| RegistryError::McpServerError {
-                    server: config.name.clone(),
-                    message: format!("Failed to parse JSON response: {}", e),
-                })?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND && had_session {
+            return Ok(Exchange::SessionExpired);
+        }
+        if !expect_response || status == reqwest::StatusCode::ACCEPTED {
+            // Nothing waits on a notification's reply, so a rejection is only logged
+            if !status.is_success() {
+                crate::observability::tee_eprintln(&format!(
+                    "Warning: MCP server '{}' rejected {}: HTTP {}",
+                    config.name,
+                    context.to_lowercase(),
+                    status
+                ));
+            }
+            return Ok(Exchange::Accepted);
+        }
+        if !status.is_success() {
+            return Err(server_error(&config.name, format!("{} failed: HTTP {}", context, status)));
+        }
+
+        let is_event_stream = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.starts_with("text/event-stream"));
+
+        let rpc_response = if is_event_stream {
+            self.read_event_stream(config, response, message.id, context).await?
+        } else {
+            response.json().await.map_err(|e| {
+                server_error(&config.name, format!("Failed to parse {} response: {}", context.to_lowercase(), e))
+            })?
+        };
+        Ok(Exchange::Response(Box::new(rpc_response)))
+    }
+
+    /// Read a `text/event-stream` response until the reply to `id` arrives,
+    /// publishing any notifications sent before it.
+    async fn read_event_stream(
+        &self,
+        config: &McpServerConfig,
+        mut response: reqwest::Response,
+        id: Option<u64>,
+        context: &str,
+    ) -> RegistryResult<JsonRpcResponse> {
+        let mut decoder = SseDecoder::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| server_error(&config.name, format!("{} stream failed: {}", context, e)))?
+        {
+            for data in decoder.feed(&chunk) {
+                let Ok(message) = serde_json::from_str::<Value>(&data) else {
+                    continue;
+                };
+                if message.get("method").is_some() {
+                    self.hub.publish_message(&config.name, &message);
+                    continue;
+                }
+                if id.is_none() || message.get("id").and_then(|v| v.as_u64()) == id {
+                    return serde_json::from_value(message).map_err(|e| {
+                        server_error(&config.name, format!("Failed to parse {} response: {}", context.to_lowercase(), e))
+                    });
+                }
+            }
+        }
+        Err(server_error(&config.name, format!("{} stream ended without a response", context)))
+    }
+
+    /// Send a JSON-RPC request over HTTP and return its `result`.
+    ///
+    /// If the server reports our session as expired, a new session is
+    /// initialized and the request retried once.
+    async fn http_request(
+        &self,
+        config: &McpServerConfig,
+        method: &str,
+        params: Value,
+        context: &str,
+    ) -> RegistryResult<Value> {
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(self.next_id.fetch_add(1, Ordering::SeqCst)),
+            method: method.to_string(),
+            params: Some(params),
+        };
+
+        let mut exchange = self.post_message(config, &request, context, true).await?;
+        if let Exchange::SessionExpired = exchange {
+            self.forget_session(&config.name);
+            Box::pin(self.initialize(config)).await?;
+            exchange = self.post_message(config, &request, context, true).await?;
+        }
+
+        let rpc_response = match exchange {
+            Exchange::Response(rpc_response) => *rpc_response,
+            Exchange::Accepted | Exchange::SessionExpired => {
+                return Err(server_error(&config.name, format!("{} failed: no response", context)));
+            }
+        };
 
         if let Some(error) = rpc_response.error {
-            return Err(RegistryError::McpServerError {
-                server: config.name.clone(),
-                message: format!("MCP error: {}", error.message),
-            });
+            return Err(server_error(&config.name, format!("{} error: {}", context, error.message)));
         }
 
-        let result = rpc_response
+        rpc_response
             .result
-            .ok_or_else(|| RegistryError::McpServerError {
-                server: config.name.clone(),
-                message: "No result in response".to_string(),
-            })?;
+            .ok_or_else(|| server_error(&config.name, format!("No result in {} response", context.to_lowercase())))
+    }
 
-        let tools_result: ToolsListResult =
-            serde_json::from_value(result).map_err(|e| RegistryError::McpServerError {
-                server: config.name.clone(),
-                message: format!("Failed to parse tools list: {}", e),
-            })?;
+    /// Send a request over the server's transport and return its `result`.
+    async fn send_request(
+        &self,
+        config: &McpServerConfig,
+        method: &str,
+        params: Value,
+        context: &str,
+    ) -> RegistryResult<Value> {
+        if let Some(ref stdio) = config.stdio {
+            return self
+                .stdio_connection(config, stdio)
+                .await?
+                .request(method, Some(params))
+                .await;
+        }
+        self.http_request(config, method, params, context).await
+    }
 
-        let tools = tools_result
-            .tools
-            .into_iter()
-            .map(|t| {
-                let mut tool = McpTool::from_schema(t.name, t.description, t.input_schema);
+    /// Open the standalone SSE stream on which a Streamable HTTP server
+    /// pushes notifications such as `tools/list_changed`.
+    ///
+    /// Servers that don't offer one answer `405`; the listener then just ends.
+    /// Other failures are logged, since tool list updates stop arriving.
+    async fn start_listener(&self, config: &McpServerConfig) {
+        if let Ok(listeners) = self.listeners.lock() {
+            if listeners.get(&config.name).is_some_and(|h| !h.is_finished()) {
+                return;
+            }
+        }
 
-                if let Some(annotations) = t.annotations {
-                    if let Some(title) = annotations.title {
-                        tool = tool.with_title(title);
-                    }
-                    if let Some(read_only) = annotations.read_only_hint {
-                        tool = tool.with_read_only_hint(read_only);
-                    }
-                    if let Some(destructive) = annotations.destructive_hint {
-                        tool = tool.with_destructive_hint(destructive);
-                    }
-                    if let Some(idempotent) = annotations.idempotent_hint {
-                        tool = tool.with_idempotent_hint(idempotent);
-                    }
-                    if let Some(open_world) = annotations.open_world_hint {
-                        tool = tool.with_open_world_hint(open_world);
+        let http_request = self
+            .http_client
+            .get(Self::endpoint(config))
+            .header(reqwest::header::ACCEPT, "text/event-stream");
+        let http_request = self.authorize(http_request, config).await;
+        let hub = self.hub.clone();
+        let server = config.name.clone();
+
+        let handle = tokio::spawn(async move {
+            let warn = |message: String| {
+                crate::observability::tee_eprintln(&format!(
+                    "Warning: MCP notification stream for '{}' {}",
+                    server, message
+                ));
+            };
+            let mut response = match http_request.send().await {
+                Ok(response) => response,
+                Err(e) => return warn(format!("request failed: {}", e)),
+            };
+            let status = response.status();
+            if status == reqwest::StatusCode::METHOD_NOT_ALLOWED {
+                return;
+            }
+            if !status.is_success() {
+                return warn(format!("failed: HTTP {}", status));
+            }
+            let mut decoder = SseDecoder::new();
+            loop {
+                match response.chunk().await {
+                    Ok(Some(chunk)) => {
+                        for data in decoder.feed(&chunk) {
+                            if let Ok(message) = serde_json::from_str::<Value>(&data) {
+                                hub.publish_message(&server, &message);
+                            }
+                        }
                     }
+                    Ok(None) => break,
+                    Err(e) => return warn(format!("broke off: {}", e)),
                 }
+            }
+        });
 
-                tool
-            })
-            .collect();
+        if let Ok(mut listeners) = self.listeners.lock() {
+            if let Some(old) = listeners.insert(config.name.clone(), handle) {
+                old.abort();
+            }
+        }
+    }
 
-        Ok(tools)
+    /// Fetch tools from an MCP server.
+    pub async fn fetch_tools(&self, config: &McpServerConfig) -> RegistryResult<Vec<McpTool>> {
+        let result = self
+            .send_request(config, "tools/list", json!({}), "Tools list")
+            .await?;
+        parse_tools_list(&config.name, result)
     }
 
     /// Initialize connection with an MCP server.
+    ///
+    /// For stdio servers this starts the process, which performs the handshake.
+    /// For Streamable HTTP servers it also records the session id and opens
+    /// the notification stream.
     pub async fn initialize(&self, config: &McpServerConfig) -> RegistryResult<()> {
-        let message_url = format!("{}/message", config.url.trim_end_matches('/'));
+        if let Some(ref stdio) = config.stdio {
+            return self.stdio_connection(config, stdio).await.map(|_| ());
+        }
 
-        let init_request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: 1,
-            method: "initialize".to_string(),
-            params: Some(json!({
+        self.http_request(
+            config,
+            "initialize",
+            json!({
                 "protocolVersion": "2024-11-05",
                 "capabilities": {},
                 "clientInfo": {
                     "name": "abk-mcp-client",
                     "version": env!("CARGO_PKG_VERSION")
                 }
-            })),
-        };
-
-        let mut http_request = self.http_client.post(&message_url).json(&init_request);
-
-        #[cfg(feature = "registry-mcp-token")]
-        self.apply_auth(&mut http_request, config).await;
-
-        #[cfg(not(feature = "registry-mcp-token"))]
-        self.apply_auth_static(&mut http_request, config);
-
-        let response = http_request
-            .send()
-            .await
-            .map_err(|e| RegistryError::McpServerError {
-                server: config.name.clone(),
-                message: format!("Initialize request failed: {}", e),
-            })?;
-
-        if !response.status().is_success() {
-            return Err(RegistryError::McpServerError {
-                server: config.name.clone(),
-                message: format!("Initialize failed: HTTP {}", response.status()),
-            });
-        }
+            }),
+            "Initialize",
+        )
+        .await?;
 
         // Send initialized notification
-        let initialized_request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: 2,
-            method: "initialized".to_string(),
-            params: None,
+        let initialized = if config.streamable_http {
+            JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                id: None,
+                method: "notifications/initialized".to_string(),
+                params: None,
+            }
+        } else {
+            JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                id: Some(self.next_id.fetch_add(1, Ordering::SeqCst)),
+                method: "initialized".to_string(),
+                params: None,
+            }
         };
+        self.post_message(config, &initialized, "Initialized notification", false)
+            .await?;
 
-        let mut http_request = self
-            .http_client
-            .post(&message_url)
-            .json(&initialized_request);
-
-        #[cfg(feature = "registry-mcp-token")]
-        self.apply_auth(&mut http_request, config).await;
-
-        #[cfg(not(feature = "registry-mcp-token"))]
-        self.apply_auth_static(&mut http_request, config);
-
-        let _ = http_request
-            .send()
-            .await
-            .map_err(|e| RegistryError::McpServerError {
-                server: config.name.clone(),
-                message: format!("Initialized notification failed: {}", e),
-            })?;
+        if config.streamable_http {
+            self.start_listener(config).await;
+        }
 
         Ok(())
     }
@@ -383,94 +658,117 @@ impl McpClient {
         tool_name: &str,
         arguments: Value,
     ) -> RegistryResult<McpToolCallResult> {
-        let message_url = format!("{}/message", config.url.trim_end_matches('/'));
-
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0".to_string(),
-            id: 1,
-            method: "tools/call".to_string(),
-            params: Some(json!({
-                "name": tool_name,
-                "arguments": arguments
-            })),
-        };
-
-        let mut http_request = self.http_client.post(&message_url).json(&request);
-
-        #[cfg(feature = "registry-mcp-token")]
-        self.apply_auth(&mut http_request, config).await;
-
-        #[cfg(not(feature = "registry-mcp-token"))]
-        self.apply_auth_static(&mut http_request, config);
-
-        let response = http_request
-            .send()
+        self.call_tool_with_progress(config, tool_name, arguments, &|_| {})
             .await
-            .map_err(|e| RegistryError::McpServerError {
-                server: config.name.clone(),
-                message: format!("Tool call request failed: {}", e),
-            })?;
+    }
 
-        if !response.status().is_success() {
-            return Err(RegistryError::McpServerError {
-                server: config.name.clone(),
-                message: format!("Tool call failed: HTTP {}", response.status()),
-            });
+    /// Call a tool, reporting `notifications/progress` updates for this call
+    /// through `on_progress` while it runs.
+    pub async fn call_tool_with_progress(
+        &self,
+        config: &McpServerConfig,
+        tool_name: &str,
+        arguments: Value,
+        on_progress: &(dyn Fn(&McpProgress) + Send + Sync),
+    ) -> RegistryResult<McpToolCallResult> {
+        use futures_util::future::{select, Either};
+
+        let token = format!("abk-{}", self.next_id.fetch_add(1, Ordering::SeqCst));
+        let params = json!({
+            "name": tool_name,
+            "arguments": arguments,
+            "_meta": { "progressToken": token }
+        });
+
+        let mut notifications = self.hub.subscribe();
+        let mut call = Box::pin(self.send_request(config, "tools/call", params, "Tool call"));
+        loop {
+            match select(call.as_mut(), Box::pin(notifications.recv())).await {
+                Either::Left((result, _)) => return result.map(parse_call_result),
+                Either::Right((Ok(McpNotification::Progress { progress_token, progress, .. }), _)) => {
+                    if progress_token == token {
+                        on_progress(&progress);
+                    }
+                }
+                Either::Right((Err(broadcast::error::RecvError::Closed), _)) => {
+                    return call.await.map(parse_call_result);
+                }
+                Either::Right(_) => {}
+            }
         }
+    }
+}
 
-        let rpc_response: JsonRpcResponse =
-            response
-                .json()
-                .await
-                .map_err(|e| RegistryError::McpServerError {
-                    server: config.name.clone(),
-                    message: format!("Failed to parse tool call response: {}", e),
-                })?;
-
-        if let Some(error) = rpc_response.error {
-            return Err(RegistryError::McpServerError {
-                server: config.name.clone(),
-                message: format!("Tool call error: {}", error.message),
-            });
-        }
+/// Parse a `tools/list` result into MCP tools.
+fn parse_tools_list(server: &str, result: Value) -> RegistryResult<Vec<McpTool>> {
+    let tools_result: ToolsListResult =
+        serde_json::from_value(result).map_err(|e| RegistryError::McpServerError {
+            server: server.to_string(),
+            message: format!("Failed to parse tools list: {}", e),
+        })?;
+
+    let tools = tools_result
+        .tools
+        .into_iter()
+        .map(|t| {
+            let mut tool = McpTool::from_schema(t.name, t.description, t.input_schema);
+
+            if let Some(annotations) = t.annotations {
+                if let Some(title) = annotations.title {
+                    tool = tool.with_title(title);
+                }
+                if let Some(read_only) = annotations.read_only_hint {
+                    tool = tool.with_read_only_hint(read_only);
+                }
+                if let Some(destructive) = annotations.destructive_hint {
+                    tool = tool.with_destructive_hint(destructive);
+                }
+                if let Some(idempotent) = annotations.idempotent_hint {
+                    tool = tool.with_idempotent_hint(idempotent);
+                }
+                if let Some(open_world) = annotations.open_world_hint {
+                    tool = tool.with_open_world_hint(open_world);
+                }
+            }
 
-        let result = rpc_response
-            .result
-            .ok_or_else(|| RegistryError::McpServerError {
-                server: config.name.clone(),
-                message: "No result in tool call response".to_string(),
-            })?;
+            tool
+        })
+        .collect();
 
-        let is_error = result
-            .get("isError")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-
-        let content = result
-            .get("content")
-            .cloned()
-            .unwrap_or_else(|| json!([]));
-
-        let text_content = if let Some(arr) = content.as_array() {
-            arr.iter()
-                .filter_map(|item| {
-                    if item.get("type").and_then(|t| t.as_str()) == Some("text") {
-                        item.get("text").and_then(|t| t.as_str()).map(String::from)
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<_>>()
-                .join("\n")
-        } else {
-            content.to_string()
-        };
+    Ok(tools)
+}
 
-        Ok(McpToolCallResult {
-            content: text_content,
-            is_error,
-            raw_content: content,
-        })
+/// Parse a `tools/call` result, joining its text content.
+fn parse_call_result(result: Value) -> McpToolCallResult {
+    let is_error = result
+        .get("isError")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let content = result
+        .get("content")
+        .cloned()
+        .unwrap_or_else(|| json!([]));
+
+    let text_content = if let Some(arr) = content.as_array() {
+        arr.iter()
+            .filter_map(|item| {
+                if item.get("type").and_then(|t| t.as_str()) == Some("text") {
+                    item.get("text").and_then(|t| t.as_str()).map(String::from)
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        content.to_string()
+    };
+
+    McpToolCallResult {
+        content: text_content,
+        is_error,
+        raw_content: content,
     }
 }
 
@@ -508,7 +806,7 @@ mod tests {
     fn test_json_rpc_request_serialization() {
         let request = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
-            id: 1,
+            id: Some(1),
             method: "tools/list".to_string(),
             params: Some(json!({})),
         };
@@ -37,6 +37,18 @@ pub enum RegistryError {
         /// Error message.
         message: String,
     },
+
+    /// An MCP server did not answer a request in time.
+    #[cfg(feature = "registry-mcp")]
+    #[error("MCP server '{server}' did not answer '{method}' within {seconds}s")]
+    McpTimeout {
+        /// Name of the MCP server.
+        server: String,
+        /// JSON-RPC method that timed out.
+        method: String,
+        /// Timeout that elapsed, in seconds.
+        seconds: u64,
+    },
 }
 
 /// Result type for registry operations.
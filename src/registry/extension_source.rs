@@ -0,0 +1,264 @@
+//! Extension Tool Source - wraps WASM extensions with the tools capability.
+//!
+//! This module provides `ExtensionToolSource` which implements `ToolSourceProvider`
+//! by calling the `tools` WIT interface of an installed extension. Tools run
+//! inside the extension's capability sandbox (see `extension::ExtensionSandbox`).
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+use super::{ToolDescriptor, ToolResult, ToolSourceProvider};
+use crate::extension::tools_only::tools::ToolSchema;
+use crate::extension::{ExtensionLoader, ExtensionManifest, ExtensionSandbox, ToolsExtensionInstance};
+
+/// Parse a tool's JSON Schema string, falling back to an empty object schema.
+fn parse_parameters(tool: &str, parameters: &str) -> serde_json::Value {
+    match serde_json::from_str(parameters) {
+        Ok(value @ serde_json::Value::Object(_)) => value,
+        _ => {
+            tracing::warn!(
+                target: "abk::extension",
+                "Tool '{}' has an invalid parameter schema, using an empty object schema",
+                tool
+            );
+            serde_json::json!({"type": "object", "properties": {}})
+        }
+    }
+}
+
+/// Convert the schemas reported by an extension into tool descriptors.
+fn descriptors_from_schemas(source_name: &str, schemas: &[ToolSchema]) -> Vec<ToolDescriptor> {
+    schemas
+        .iter()
+        .map(|s| {
+            ToolDescriptor::new(
+                &s.name,
+                &s.description,
+                parse_parameters(&s.name, &s.parameters),
+                source_name,
+            )
+        })
+        .collect()
+}
+
+/// A tool source backed by a sandboxed WASM extension.
+///
+/// The extension must declare `tools = true` under `[capabilities]` and
+/// export the `tools-extension` world. Tool schemas are fetched once at load
+/// time; calls are serialized through a single extension instance.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use abk::registry::{ExtensionToolSource, ToolSourceProvider};
+///
+/// let source = ExtensionToolSource::load(Path::new("extensions/jira")).await?;
+///
+/// // Get all tool descriptors
+/// let tools = source.tool_descriptors();
+///
+/// // Execute a tool
+/// let result = source.execute("jira_search", json!({"jql": "project = ABK"})).await?;
+/// ```
+pub struct ExtensionToolSource {
+    /// Source identifier (e.g., "ext-jira")
+    name: String,
+    /// Extension instance, one call at a time
+    instance: Mutex<ToolsExtensionInstance>,
+    /// Cached tool descriptors
+    descriptors: Vec<ToolDescriptor>,
+    /// Tool name to index map for fast lookup
+    tool_index: HashMap<String, usize>,
+}
+
+impl ExtensionToolSource {
+    /// Load a tools extension from its directory.
+    ///
+    /// # Arguments
+    /// * `extension_dir` - Directory containing `extension.toml` and the WASM file
+    ///
+    /// # Errors
+    /// Returns an error if the manifest is invalid, the extension does not
+    /// declare the tools capability, or instantiation/initialization fails.
+    pub async fn load(extension_dir: &Path) -> anyhow::Result<Self> {
+        let manifest = ExtensionManifest::from_file(&extension_dir.join("extension.toml"))?;
+        if !manifest.capabilities.tools {
+            anyhow::bail!(
+                "Extension '{}' does not declare the tools capability",
+                manifest.extension.id
+            );
+        }
+
+        let loader = ExtensionLoader::new()?;
+        let wasm = loader.load_wasm(&extension_dir.join(&manifest.lib.path))?;
+        let sandbox = ExtensionSandbox::from_manifest(&manifest, extension_dir);
+
+        let mut instance =
+            ToolsExtensionInstance::with_sandbox(&wasm.engine, &wasm.component, &sandbox).await?;
+        instance.init().await?;
+        let schemas = instance.list_tools().await?;
+
+        Ok(Self::with_instance(
+            format!("ext-{}", manifest.extension.id),
+            instance,
+            &schemas,
+        ))
+    }
+
+    /// Load every installed extension with the tools capability.
+    ///
+    /// Extensions that fail to load are logged and skipped so that one broken
+    /// extension does not take down the others.
+    ///
+    /// # Arguments
+    /// * `extensions_dir` - Directory with one subdirectory per extension
+    pub async fn discover(extensions_dir: &Path) -> anyhow::Result<Vec<Self>> {
+        let mut sources = Vec::new();
+        if !extensions_dir.is_dir() {
+            return Ok(sources);
+        }
+
+        let mut dirs: Vec<_> = std::fs::read_dir(extensions_dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.join("extension.toml").is_file())
+            .collect();
+        dirs.sort();
+
+        for dir in dirs {
+            let is_tools = ExtensionManifest::from_file(&dir.join("extension.toml"))
+                .map(|m| m.capabilities.tools)
+                .unwrap_or(false);
+            if !is_tools {
+                continue;
+            }
+            match Self::load(&dir).await {
+                Ok(source) => sources.push(source),
+                Err(e) => tracing::warn!(
+                    target: "abk::extension",
+                    "Skipping tools extension {:?}: {}",
+                    dir,
+                    e
+                ),
+            }
+        }
+
+        Ok(sources)
+    }
+
+    /// Create a source from an already-initialized instance.
+    fn with_instance(name: String, instance: ToolsExtensionInstance, schemas: &[ToolSchema]) -> Self {
+        let descriptors = descriptors_from_schemas(&name, schemas);
+        let tool_index = descriptors
+            .iter()
+            .enumerate()
+            .map(|(i, d)| (d.name.clone(), i))
+            .collect();
+
+        Self {
+            name,
+            instance: Mutex::new(instance),
+            descriptors,
+            tool_index,
+        }
+    }
+}
+
+#[async_trait]
+impl ToolSourceProvider for ExtensionToolSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn tool_descriptors(&self) -> Vec<ToolDescriptor> {
+        self.descriptors.clone()
+    }
+
+    async fn execute(&self, tool_name: &str, args: serde_json::Value) -> anyhow::Result<ToolResult> {
+        if !self.has_tool(tool_name) {
+            anyhow::bail!("Tool '{}' not found in source '{}'", tool_name, self.name);
+        }
+
+        let arguments = serde_json::to_string(&args)?;
+        let output = self
+            .instance
+            .lock()
+            .await
+            .execute_tool(tool_name, &arguments)
+            .await?;
+
+        Ok(if output.success {
+            ToolResult::success(output.content)
+        } else {
+            ToolResult::failure(output.content)
+        })
+    }
+
+    fn has_tool(&self, name: &str) -> bool {
+        self.tool_index.contains_key(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(name: &str, parameters: &str) -> ToolSchema {
+        ToolSchema {
+            name: name.to_string(),
+            description: format!("{} tool", name),
+            parameters: parameters.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_descriptors_from_schemas() {
+        let schemas = vec![
+            schema("echo", r#"{"type":"object","properties":{"text":{"type":"string"}}}"#),
+            schema("broken", "not json"),
+        ];
+
+        let descriptors = descriptors_from_schemas("ext-test", &schemas);
+
+        assert_eq!(descriptors.len(), 2);
+        assert_eq!(descriptors[0].name, "echo");
+        assert_eq!(descriptors[0].source, "ext-test");
+        assert_eq!(descriptors[0].parameters["properties"]["text"]["type"], "string");
+        assert_eq!(descriptors[1].parameters["type"], "object");
+    }
+
+    #[tokio::test]
+    async fn test_load_requires_tools_capability() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("no-tools");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(
+            dir.join("extension.toml"),
+            r#"
+[extension]
+id = "no-tools"
+name = "No Tools"
+version = "0.1.0"
+api_version = "0.4.0"
+description = "Lifecycle only"
+
+[lib]
+kind = "rust"
+path = "extension.wasm"
+
+[capabilities]
+lifecycle = true
+"#,
+        )
+        .unwrap();
+
+        let err = ExtensionToolSource::load(&dir).await.err().unwrap();
+        assert!(err.to_string().contains("tools capability"));
+
+        // Discovery skips extensions without the tools capability
+        let sources = ExtensionToolSource::discover(root.path()).await.unwrap();
+        assert!(sources.is_empty());
+    }
+}
@@ -6,7 +6,13 @@
 use std::sync::Arc;
 
 use crate::config::ToolSourceConfig;
-use super::{BoxedToolSource, UnifiedRegistry, NativeToolSource, McpToolSource, McpServerConfig};
+#[cfg(feature = "registry-extension")]
+use super::ToolSourceProvider;
+use super::{BoxedToolSource, UnifiedRegistry};
+#[cfg(feature = "agent")]
+use super::NativeToolSource;
+#[cfg(feature = "registry-mcp")]
+use super::{McpToolSource, McpServerConfig, McpStdioConfig};
 
 /// Build a UnifiedRegistry from configuration.
 ///
@@ -33,6 +39,7 @@ use super::{BoxedToolSource, UnifiedRegistry, NativeToolSource, McpToolSource, M
 /// // Get all tools
 /// let tools = registry.all_schemas();
 /// ```
+#[cfg(all(feature = "agent", feature = "registry-mcp"))]
 pub async fn build_registry_from_config(
     configs: &[ToolSourceConfig],
     open_window_size: usize,
@@ -53,18 +60,101 @@ pub async fn build_registry_from_config(
     Ok(registry)
 }
 
+/// Build the registry of tools contributed by WASM extensions.
+///
+/// Loads every `type = "extension"` entry of `[[tool_sources]]`, then every
+/// installed extension in `extensions_dir` whose manifest declares the tools
+/// capability. An extension listed in both places is registered once.
+/// Extensions that fail to load are logged and skipped.
+///
+/// # Arguments
+/// * `configs` - List of tool source configurations; non-extension entries are ignored
+/// * `extensions_dir` - Directory with one subdirectory per installed extension
+#[cfg(feature = "registry-extension")]
+pub async fn build_extension_registry(
+    configs: &[ToolSourceConfig],
+    extensions_dir: Option<&std::path::Path>,
+) -> UnifiedRegistry {
+    let mut registry = UnifiedRegistry::new();
+    let mut names = std::collections::HashSet::new();
+
+    for config in configs {
+        if !matches!(config, ToolSourceConfig::Extension { .. }) {
+            continue;
+        }
+        match build_source_from_config(config, 0).await {
+            Ok(source) => {
+                if names.insert(source.name().to_string()) {
+                    registry.add_source(source);
+                }
+            }
+            Err(e) => tracing::warn!(
+                target: "abk::extension",
+                "Skipping tools extension {:?}: {}",
+                config,
+                e
+            ),
+        }
+    }
+
+    if let Some(dir) = extensions_dir {
+        match super::ExtensionToolSource::discover(dir).await {
+            Ok(sources) => {
+                for source in sources {
+                    if names.insert(source.name().to_string()) {
+                        registry.add_source(Arc::new(source));
+                    }
+                }
+            }
+            Err(e) => tracing::warn!(
+                target: "abk::extension",
+                "Failed to scan extensions in {:?}: {}",
+                dir,
+                e
+            ),
+        }
+    }
+
+    registry
+}
+
 /// Build a single tool source from configuration.
+#[cfg_attr(not(feature = "agent"), allow(unused_variables))]
 async fn build_source_from_config(
     config: &ToolSourceConfig,
     open_window_size: usize,
 ) -> anyhow::Result<BoxedToolSource> {
     match config {
+        #[cfg(feature = "agent")]
         ToolSourceConfig::Native { toolset } => {
             let source = NativeToolSource::new(toolset, open_window_size);
             Ok(Arc::new(source))
         }
-        ToolSourceConfig::Mcp { name, url, auth_token, auto_init } => {
-            let mut server_config = McpServerConfig::new(name, url);
+        #[cfg(not(feature = "agent"))]
+        ToolSourceConfig::Native { toolset } => {
+            anyhow::bail!("Native tool source {:?} requires the agent feature", toolset)
+        }
+        #[cfg(feature = "registry-mcp")]
+        ToolSourceConfig::Mcp { name, url, auth_token, auto_init, transport, command, args, env, cwd, timeout_seconds } => {
+            let mut server_config = match command {
+                Some(command) => {
+                    let mut stdio = McpStdioConfig::new(command).with_args(args.iter().cloned());
+                    for (key, value) in env {
+                        stdio = stdio.with_env(key, resolve_env_var(value));
+                    }
+                    if let Some(cwd) = cwd {
+                        stdio = stdio.with_cwd(cwd);
+                    }
+                    if let Some(seconds) = timeout_seconds {
+                        stdio = stdio.with_timeout(std::time::Duration::from_secs(*seconds));
+                    }
+                    McpServerConfig::stdio(name, stdio)
+                }
+                None if transport == "streamable-http" => {
+                    McpServerConfig::new(name, url).with_streamable_http()
+                }
+                None => McpServerConfig::new(name, url),
+            };
             if let Some(token) = auth_token {
                 // Resolve environment variable references
                 let resolved = resolve_env_var(token);
@@ -74,6 +164,30 @@ async fn build_source_from_config(
             let source = McpToolSource::new(server_config, *auto_init).await?;
             Ok(Arc::new(source))
         }
+        #[cfg(not(feature = "registry-mcp"))]
+        ToolSourceConfig::Mcp { name, .. } => {
+            anyhow::bail!("MCP tool source {:?} requires the registry-mcp feature", name)
+        }
+        #[cfg(feature = "registry-extension")]
+        ToolSourceConfig::Extension { path } => {
+            let dir = match path.strip_prefix('~') {
+                Some(rest) => std::path::PathBuf::from(format!(
+                    "{}{}",
+                    crate::get_home_dir().map_err(anyhow::Error::msg)?,
+                    rest
+                )),
+                None => std::path::PathBuf::from(path),
+            };
+            let source = super::ExtensionToolSource::load(&dir).await?;
+            Ok(Arc::new(source))
+        }
+        #[cfg(not(feature = "registry-extension"))]
+        ToolSourceConfig::Extension { path } => {
+            anyhow::bail!(
+                "Extension tool source {:?} requires the registry-extension feature",
+                path
+            )
+        }
     }
 }
 
@@ -81,6 +195,7 @@ async fn build_source_from_config(
 ///
 /// Supports patterns like `${VAR_NAME}` and replaces them with
 /// the corresponding environment variable value.
+#[cfg(feature = "registry-mcp")]
 fn resolve_env_var(value: &str) -> String {
     if value.starts_with("${") && value.ends_with('}') {
         let var_name = &value[2..value.len() - 1];
@@ -94,6 +209,7 @@ fn resolve_env_var(value: &str) -> String {
 mod tests {
     use super::*;
 
+    #[cfg(feature = "registry-mcp")]
     #[test]
     fn test_resolve_env_var() {
         std::env::set_var("TEST_TOOL_VAR", "test_value");
@@ -108,6 +224,7 @@ mod tests {
         std::env::remove_var("TEST_TOOL_VAR");
     }
 
+    #[cfg(all(feature = "agent", feature = "registry-mcp"))]
     #[tokio::test]
     async fn test_build_registry_empty() {
         let registry = build_registry_from_config(&[], 2000).await.unwrap();
@@ -117,6 +234,7 @@ mod tests {
         assert!(registry.has_tool("bash"));
     }
 
+    #[cfg(all(feature = "agent", feature = "registry-mcp"))]
     #[tokio::test]
     async fn test_build_registry_native() {
         let configs = vec![ToolSourceConfig::Native {
@@ -129,4 +247,46 @@ mod tests {
         assert!(registry.has_tool("bash"));
         assert!(registry.has_tool("read"));
     }
+
+    #[cfg(feature = "registry-extension")]
+    #[tokio::test]
+    async fn test_build_extension_registry_skips_unusable_extensions() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("notes");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(
+            dir.join("extension.toml"),
+            r#"
+[extension]
+id = "notes"
+name = "Notes"
+version = "0.1.0"
+api_version = "0.4.0"
+description = "Declares tools but ships no component"
+
+[lib]
+kind = "rust"
+path = "extension.wasm"
+
+[capabilities]
+tools = true
+"#,
+        )
+        .unwrap();
+
+        let configs = vec![
+            ToolSourceConfig::Native {
+                toolset: "opencode".to_string(),
+            },
+            ToolSourceConfig::Extension {
+                path: dir.to_string_lossy().into_owned(),
+            },
+        ];
+        let registry = build_extension_registry(&configs, Some(root.path())).await;
+
+        // Native sources are not part of the extension registry, and the
+        // broken extension is skipped instead of failing agent startup
+        assert_eq!(registry.source_count(), 0);
+        assert!(!registry.has_tool("bash"));
+    }
 }
@@ -4,10 +4,13 @@
 //! by connecting to MCP (Model Context Protocol) servers for remote tool execution.
 
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 
 use async_trait::async_trait;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
 
-use super::{McpClient, McpServerConfig};
+use super::{McpClient, McpNotification, McpServerConfig};
 use super::{ToolDescriptor, ToolSourceProvider};
 use crate::registry::provider::ToolResult as ProviderResult;
 
@@ -35,11 +38,51 @@ fn schema_to_json(schema: &umf::McpInputSchema) -> serde_json::Value {
     serde_json::Value::Object(map)
 }
 
+/// Cached tool list of one MCP server.
+#[derive(Default)]
+struct ToolState {
+    /// Cached tool descriptors
+    descriptors: Vec<ToolDescriptor>,
+    /// Tool name to index map for fast lookup
+    tool_index: HashMap<String, usize>,
+}
+
+impl ToolState {
+    fn from_tools(source_name: &str, tools: &[umf::McpTool]) -> Self {
+        let descriptors: Vec<ToolDescriptor> = tools
+            .iter()
+            .map(|t| {
+                ToolDescriptor::new(
+                    &t.name,
+                    t.description.as_deref().unwrap_or(&t.name),
+                    schema_to_json(&t.input_schema),
+                    source_name,
+                )
+            })
+            .collect();
+
+        let tool_index = descriptors
+            .iter()
+            .enumerate()
+            .map(|(i, d)| (d.name.clone(), i))
+            .collect();
+
+        Self {
+            descriptors,
+            tool_index,
+        }
+    }
+}
+
 /// A tool source that connects to MCP servers.
 ///
 /// This provides tools from MCP servers for remote execution. The source
 /// fetches tool schemas from the server and executes tools via JSON-RPC.
 ///
+/// When created inside a tokio runtime, the source also listens for
+/// `notifications/tools/list_changed` from its server and refreshes the
+/// cached tool list automatically.
+///
 /// # Example
 ///
 /// ```rust,ignore
@@ -61,12 +104,12 @@ pub struct McpToolSource {
     name: String,
     /// Server configuration
     config: McpServerConfig,
-    /// HTTP client for MCP communication
-    client: McpClient,
-    /// Cached tool descriptors
-    descriptors: Vec<ToolDescriptor>,
-    /// Tool name to index map for fast lookup
-    tool_index: HashMap<String, usize>,
+    /// Client for MCP communication (HTTP or stdio)
+    client: Arc<McpClient>,
+    /// Cached tools, shared with the change watcher
+    state: Arc<RwLock<ToolState>>,
+    /// Task refreshing `state` on `list_changed` notifications
+    watcher: Option<JoinHandle<()>>,
 }
 
 impl McpToolSource {
@@ -85,7 +128,6 @@ impl McpToolSource {
     /// Returns an error if the server is unreachable or returns invalid data.
     pub async fn new(config: McpServerConfig, auto_init: bool) -> anyhow::Result<Self> {
         let client = McpClient::new();
-        let name = format!("mcp-{}", config.name);
 
         // Fetch tools from server
         let mcp_tools = if auto_init {
@@ -94,33 +136,7 @@ impl McpToolSource {
             client.fetch_tools(&config).await?
         };
 
-        // Convert to descriptors
-        let descriptors: Vec<ToolDescriptor> = mcp_tools
-            .iter()
-            .map(|t| {
-                ToolDescriptor::new(
-                    &t.name,
-                    t.description.as_deref().unwrap_or(&t.name),
-                    schema_to_json(&t.input_schema),
-                    &name,
-                )
-            })
-            .collect();
-
-        // Build index
-        let tool_index: HashMap<String, usize> = descriptors
-            .iter()
-            .enumerate()
-            .map(|(i, d)| (d.name.clone(), i))
-            .collect();
-
-        Ok(Self {
-            name,
-            config,
-            client,
-            descriptors,
-            tool_index,
-        })
+        Ok(Self::with_client(config, client, &mcp_tools))
     }
 
     /// Create an MCP tool source from pre-fetched tools.
@@ -130,40 +146,29 @@ impl McpToolSource {
         config: McpServerConfig,
         tools: Vec<umf::McpTool>,
     ) -> Self {
-        let client = McpClient::new();
-        let name = format!("mcp-{}", config.name);
-
-        let descriptors: Vec<ToolDescriptor> = tools
-            .iter()
-            .map(|t| {
-                ToolDescriptor::new(
-                    &t.name,
-                    t.description.as_deref().unwrap_or(&t.name),
-                    schema_to_json(&t.input_schema),
-                    &name,
-                )
-            })
-            .collect();
+        Self::with_client(config, McpClient::new(), &tools)
+    }
 
-        let tool_index: HashMap<String, usize> = descriptors
-            .iter()
-            .enumerate()
-            .map(|(i, d)| (d.name.clone(), i))
-            .collect();
+    fn with_client(config: McpServerConfig, client: McpClient, tools: &[umf::McpTool]) -> Self {
+        let name = format!("mcp-{}", config.name);
+        let client = Arc::new(client);
+        let state = Arc::new(RwLock::new(ToolState::from_tools(&name, tools)));
+        let watcher = spawn_watcher(client.clone(), config.clone(), name.clone(), state.clone());
 
         Self {
             name,
             config,
             client,
-            descriptors,
-            tool_index,
+            state,
+            watcher,
         }
     }
 
     /// Refresh tools from the server.
     ///
-    /// This re-fetches the tool list from the MCP server.
-    pub async fn refresh(&mut self, auto_init: bool) -> anyhow::Result<usize> {
+    /// This re-fetches the tool list from the MCP server. It runs automatically
+    /// when the server sends `notifications/tools/list_changed`.
+    pub async fn refresh(&self, auto_init: bool) -> anyhow::Result<usize> {
         let mcp_tools = if auto_init {
             self.client.fetch_tools_with_init(&self.config).await?
         } else {
@@ -171,25 +176,8 @@ impl McpToolSource {
         };
 
         let count = mcp_tools.len();
-
-        self.descriptors = mcp_tools
-            .iter()
-            .map(|t| {
-                ToolDescriptor::new(
-                    &t.name,
-                    t.description.as_deref().unwrap_or(&t.name),
-                    schema_to_json(&t.input_schema),
-                    &self.name,
-                )
-            })
-            .collect();
-
-        self.tool_index = self
-            .descriptors
-            .iter()
-            .enumerate()
-            .map(|(i, d)| (d.name.clone(), i))
-            .collect();
+        store_tools(&self.state, ToolState::from_tools(&self.name, &mcp_tools));
+        self.client.clear_tools_changed(&self.config.name);
 
         Ok(count)
     }
@@ -201,10 +189,72 @@ impl McpToolSource {
 
     /// Get the number of tools from this source.
     pub fn tool_count(&self) -> usize {
-        self.descriptors.len()
+        self.state.read().map(|s| s.descriptors.len()).unwrap_or(0)
+    }
+
+    /// Subscribe to notifications (progress, list changes) from the server.
+    pub fn subscribe(&self) -> broadcast::Receiver<McpNotification> {
+        self.client.subscribe()
+    }
+
+    /// Stop the server process if this source uses the stdio transport.
+    pub async fn shutdown(&self) {
+        if let Some(watcher) = &self.watcher {
+            watcher.abort();
+        }
+        self.client.shutdown().await;
+    }
+}
+
+impl Drop for McpToolSource {
+    fn drop(&mut self) {
+        if let Some(watcher) = self.watcher.take() {
+            watcher.abort();
+        }
+    }
+}
+
+fn store_tools(state: &RwLock<ToolState>, tools: ToolState) {
+    if let Ok(mut state) = state.write() {
+        *state = tools;
     }
 }
 
+/// Re-fetch the tool list whenever the server announces a change.
+///
+/// Returns `None` outside a tokio runtime; callers then refresh manually.
+fn spawn_watcher(
+    client: Arc<McpClient>,
+    config: McpServerConfig,
+    source_name: String,
+    state: Arc<RwLock<ToolState>>,
+) -> Option<JoinHandle<()>> {
+    let runtime = tokio::runtime::Handle::try_current().ok()?;
+    let mut notifications = client.subscribe();
+
+    Some(runtime.spawn(async move {
+        loop {
+            match notifications.recv().await {
+                Ok(McpNotification::ToolsListChanged { server }) if server == config.name => {}
+                // Missed notifications may have included a list change
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+            match client.fetch_tools(&config).await {
+                Ok(tools) => {
+                    store_tools(&state, ToolState::from_tools(&source_name, &tools));
+                    client.clear_tools_changed(&config.name);
+                }
+                Err(e) => crate::observability::tee_eprintln(&format!(
+                    "Warning: Failed to refresh tools from MCP server '{}': {}",
+                    config.name, e
+                )),
+            }
+        }
+    }))
+}
+
 #[async_trait]
 impl ToolSourceProvider for McpToolSource {
     fn name(&self) -> &str {
@@ -212,7 +262,10 @@ impl ToolSourceProvider for McpToolSource {
     }
 
     fn tool_descriptors(&self) -> Vec<ToolDescriptor> {
-        self.descriptors.clone()
+        self.state
+            .read()
+            .map(|s| s.descriptors.clone())
+            .unwrap_or_default()
     }
 
     async fn execute(
@@ -230,7 +283,10 @@ impl ToolSourceProvider for McpToolSource {
     }
 
     fn has_tool(&self, name: &str) -> bool {
-        self.tool_index.contains_key(name)
+        self.state
+            .read()
+            .map(|s| s.tool_index.contains_key(name))
+            .unwrap_or(false)
     }
 }
 
@@ -318,4 +374,44 @@ mod tests {
         assert!(source.has_tool("test_tool"));
         assert_eq!(source.tool_count(), 1);
     }
+
+    /// Stdio server whose `tools/call` adds a tool and announces the change.
+    const GROWING_SERVER: &str = r#"
+tools='{"name":"grow","description":"Grow","inputSchema":{"type":"object"}}'
+while IFS= read -r line; do
+  id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+  [ -z "$id" ] && continue
+  case "$line" in
+    *'"tools/list"'*) printf '{"jsonrpc":"2.0","id":%s,"result":{"tools":[%s]}}\n' "$id" "$tools" ;;
+    *'"tools/call"'*)
+      tools="$tools"',{"name":"extra","description":"Extra","inputSchema":{"type":"object"}}'
+      printf '{"jsonrpc":"2.0","method":"notifications/tools/list_changed"}\n'
+      printf '{"jsonrpc":"2.0","id":%s,"result":{"content":[{"type":"text","text":"ok"}]}}\n' "$id" ;;
+    *) printf '{"jsonrpc":"2.0","id":%s,"result":{}}\n' "$id" ;;
+  esac
+done
+"#;
+
+    #[tokio::test]
+    async fn test_list_changed_refreshes_tools() {
+        use crate::registry::McpStdioConfig;
+
+        let config = McpServerConfig::stdio(
+            "growing",
+            McpStdioConfig::new("sh").with_args(["-c", GROWING_SERVER]),
+        );
+        let source = McpToolSource::new(config, true).await.unwrap();
+        assert_eq!(source.tool_count(), 1);
+
+        source.execute("grow", serde_json::json!({})).await.unwrap();
+
+        let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !source.has_tool("extra") && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(source.has_tool("extra"));
+        assert_eq!(source.tool_count(), 2);
+
+        source.shutdown().await;
+    }
 }
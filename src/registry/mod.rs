@@ -12,11 +12,13 @@
 //! - `UnifiedRegistry` - aggregates all tool sources into a single interface
 //! - `NativeToolSource` - wraps cats::ToolRegistry for in-process tools
 //! - `McpToolSource` - wraps MCP server connections for remote tools
+//! - `ExtensionToolSource` - wraps sandboxed WASM extensions with the tools capability
 //!
 //! ## Features
 //!
 //! - `registry` - Core registry functionality with native tool support
 //! - `registry-mcp` - Additional support for MCP tool registration
+//! - `registry-extension` - Tools contributed by WASM extensions
 //!
 //! ## Usage
 //!
@@ -51,7 +53,10 @@ pub use registry::ToolRegistry;
 pub use source_kind::ToolSource;
 pub use provider::{ToolSourceProvider, ToolDescriptor, ToolResult, BoxedToolSource};
 pub use unified::UnifiedRegistry;
+#[cfg(all(feature = "agent", feature = "registry-mcp"))]
 pub use factory::build_registry_from_config;
+#[cfg(feature = "registry-extension")]
+pub use factory::build_extension_registry;
 
 // MCP support (requires registry-mcp feature)
 #[cfg(feature = "registry-mcp")]
@@ -63,6 +68,20 @@ mod client;
 #[cfg(feature = "registry-mcp")]
 pub use client::{McpClient, McpServerConfig, McpToolCallResult};
 
+// Stdio transport for subprocess MCP servers (requires registry-mcp feature)
+#[cfg(feature = "registry-mcp")]
+mod stdio;
+#[cfg(feature = "registry-mcp")]
+pub use stdio::{McpStdioConfig, DEFAULT_STDIO_TIMEOUT};
+
+// Server notifications and Streamable HTTP (requires registry-mcp feature)
+#[cfg(feature = "registry-mcp")]
+mod notification;
+#[cfg(feature = "registry-mcp")]
+mod sse;
+#[cfg(feature = "registry-mcp")]
+pub use notification::{McpNotification, McpProgress};
+
 // Native tool source (requires cats feature)
 #[cfg(feature = "agent")]
 mod native_source;
@@ -74,3 +93,9 @@ pub use native_source::NativeToolSource;
 mod mcp_source;
 #[cfg(feature = "registry-mcp")]
 pub use mcp_source::McpToolSource;
+
+// WASM extension tool source (requires registry-extension feature)
+#[cfg(feature = "registry-extension")]
+mod extension_source;
+#[cfg(feature = "registry-extension")]
+pub use extension_source::ExtensionToolSource;
@@ -0,0 +1,198 @@
+//! Server-to-client MCP notifications.
+//!
+//! Notifications arrive on whatever channel the transport offers: the stdout
+//! of a stdio server, the `text/event-stream` body of a Streamable HTTP
+//! response, or the standalone SSE stream opened after initialization. All of
+//! them are published through one [`NotificationHub`] per [`McpClient`](super::McpClient).
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+/// Buffered notifications per subscriber before the oldest are dropped.
+const CHANNEL_CAPACITY: usize = 64;
+
+/// A notification received from an MCP server.
+#[derive(Debug, Clone, PartialEq)]
+pub enum McpNotification {
+    /// `notifications/tools/list_changed` - the server's tool list changed
+    ToolsListChanged {
+        /// Server that sent the notification
+        server: String,
+    },
+    /// `notifications/progress` - progress of a long-running request
+    Progress {
+        /// Server that sent the notification
+        server: String,
+        /// Token from the request's `_meta.progressToken`
+        progress_token: String,
+        /// Progress so far
+        progress: McpProgress,
+    },
+    /// Any other notification
+    Other {
+        /// Server that sent the notification
+        server: String,
+        /// JSON-RPC method name
+        method: String,
+        /// Notification parameters
+        params: Option<Value>,
+    },
+}
+
+/// Progress reported by a long-running MCP tool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct McpProgress {
+    /// Progress value (increases monotonically)
+    pub progress: f64,
+    /// Total, if known
+    pub total: Option<f64>,
+    /// Human-readable status message
+    pub message: Option<String>,
+}
+
+impl McpNotification {
+    /// Parse a JSON-RPC notification message (`{"method": ..., "params": ...}`).
+    ///
+    /// Returns `None` for requests and responses.
+    pub fn from_message(server: &str, message: &Value) -> Option<Self> {
+        if message.get("id").is_some() {
+            return None;
+        }
+        let method = message.get("method")?.as_str()?;
+        let params = message.get("params").cloned();
+
+        let notification = match method {
+            "notifications/tools/list_changed" => Self::ToolsListChanged {
+                server: server.to_string(),
+            },
+            "notifications/progress" => {
+                let p = params.as_ref()?;
+                let progress_token = match p.get("progressToken")? {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                Self::Progress {
+                    server: server.to_string(),
+                    progress_token,
+                    progress: McpProgress {
+                        progress: p.get("progress").and_then(|v| v.as_f64()).unwrap_or(0.0),
+                        total: p.get("total").and_then(|v| v.as_f64()),
+                        message: p.get("message").and_then(|v| v.as_str()).map(String::from),
+                    },
+                }
+            }
+            _ => Self::Other {
+                server: server.to_string(),
+                method: method.to_string(),
+                params,
+            },
+        };
+        Some(notification)
+    }
+}
+
+/// Fan-out point for notifications, plus the set of servers whose tool
+/// list changed since it was last checked.
+pub(crate) struct NotificationHub {
+    tx: broadcast::Sender<McpNotification>,
+    changed: Mutex<HashSet<String>>,
+}
+
+impl NotificationHub {
+    pub(crate) fn new() -> Self {
+        let (tx, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            tx,
+            changed: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Publish a raw JSON-RPC message if it is a notification.
+    pub(crate) fn publish_message(&self, server: &str, message: &Value) {
+        if let Some(notification) = McpNotification::from_message(server, message) {
+            self.publish(notification);
+        }
+    }
+
+    pub(crate) fn publish(&self, notification: McpNotification) {
+        if let McpNotification::ToolsListChanged { ref server } = notification {
+            if let Ok(mut changed) = self.changed.lock() {
+                changed.insert(server.clone());
+            }
+        }
+        // No subscribers is fine
+        let _ = self.tx.send(notification);
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<McpNotification> {
+        self.tx.subscribe()
+    }
+
+    /// Take the servers whose tool list changed since the last call.
+    pub(crate) fn take_changed(&self) -> Vec<String> {
+        self.changed
+            .lock()
+            .map(|mut changed| changed.drain().collect())
+            .unwrap_or_default()
+    }
+
+    /// Clear the changed flag for one server (after it was refreshed).
+    pub(crate) fn clear_changed(&self, server: &str) {
+        if let Ok(mut changed) = self.changed.lock() {
+            changed.remove(server);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_notifications() {
+        let changed = json!({"jsonrpc": "2.0", "method": "notifications/tools/list_changed"});
+        assert_eq!(
+            McpNotification::from_message("s", &changed),
+            Some(McpNotification::ToolsListChanged { server: "s".to_string() })
+        );
+
+        let progress = json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/progress",
+            "params": {"progressToken": 7, "progress": 2, "total": 4, "message": "half"}
+        });
+        assert_eq!(
+            McpNotification::from_message("s", &progress),
+            Some(McpNotification::Progress {
+                server: "s".to_string(),
+                progress_token: "7".to_string(),
+                progress: McpProgress {
+                    progress: 2.0,
+                    total: Some(4.0),
+                    message: Some("half".to_string()),
+                },
+            })
+        );
+
+        let response = json!({"jsonrpc": "2.0", "id": 1, "result": {}});
+        assert_eq!(McpNotification::from_message("s", &response), None);
+    }
+
+    #[test]
+    fn test_hub_tracks_changed_servers() {
+        let hub = NotificationHub::new();
+        let mut rx = hub.subscribe();
+
+        hub.publish_message("a", &json!({"method": "notifications/tools/list_changed"}));
+        hub.publish_message("b", &json!({"method": "notifications/tools/list_changed"}));
+        hub.clear_changed("b");
+
+        assert!(matches!(rx.try_recv(), Ok(McpNotification::ToolsListChanged { .. })));
+        assert_eq!(hub.take_changed(), vec!["a".to_string()]);
+        assert!(hub.take_changed().is_empty());
+    }
+}
@@ -0,0 +1,58 @@
+//! Minimal `text/event-stream` parser for Streamable HTTP responses.
+//!
+//! Only the `data` field matters for MCP: each event carries one JSON-RPC
+//! message. Event ids, retry hints and comments are ignored.
+
+/// Incremental SSE decoder: feed body chunks, get complete event payloads.
+#[derive(Debug, Default)]
+pub(crate) struct SseDecoder {
+    buffer: Vec<u8>,
+}
+
+impl SseDecoder {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a body chunk and return the `data` of every event it completed.
+    pub(crate) fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        // Buffer bytes so multi-byte characters split across chunks survive
+        self.buffer.extend(chunk.iter().filter(|&&b| b != b'\r'));
+
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            if let Some(data) = event_data(&String::from_utf8_lossy(&block)) {
+                events.push(data);
+            }
+        }
+        events
+    }
+}
+
+/// Join the `data:` lines of one event block.
+fn event_data(block: &str) -> Option<String> {
+    let lines: Vec<&str> = block
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|data| data.strip_prefix(' ').unwrap_or(data))
+        .collect();
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decoder_handles_split_chunks() {
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.feed(b"event: message\ndata: {\"a\":").is_empty());
+        let events = decoder.feed(b"1}\n\n: keep-alive\n\ndata: x\r\ndata: y\r\n\r\n");
+        assert_eq!(events, vec!["{\"a\":1}".to_string(), "x\ny".to_string()]);
+    }
+}
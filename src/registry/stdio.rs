@@ -0,0 +1,435 @@
+//! Stdio transport for MCP servers.
+//!
+//! The server is launched as a subprocess and speaks newline-delimited
+//! JSON-RPC over its stdin/stdout. A background task reads stdout and routes
+//! each response to the request waiting on its id, so concurrent tool calls
+//! share a single process. The `initialize` handshake and every request fail
+//! with [`RegistryError::McpTimeout`] when the server does not answer within
+//! the configured timeout.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{oneshot, Mutex as AsyncMutex};
+
+use super::notification::NotificationHub;
+use super::{RegistryError, RegistryResult};
+
+/// How long `shutdown` waits for the server to exit after closing its stdin.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// Default time to wait for the server to answer a request.
+pub const DEFAULT_STDIO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Subprocess launch settings for a stdio MCP server.
+#[derive(Debug, Clone)]
+pub struct McpStdioConfig {
+    /// Executable to launch
+    pub command: String,
+    /// Command-line arguments
+    pub args: Vec<String>,
+    /// Extra environment variables (added to the inherited environment)
+    pub env: HashMap<String, String>,
+    /// Working directory (defaults to the current directory)
+    pub cwd: Option<PathBuf>,
+    /// How long to wait for the `initialize` handshake and for each response
+    pub timeout: Duration,
+}
+
+impl Default for McpStdioConfig {
+    fn default() -> Self {
+        Self {
+            command: String::new(),
+            args: Vec::new(),
+            env: HashMap::new(),
+            cwd: None,
+            timeout: DEFAULT_STDIO_TIMEOUT,
+        }
+    }
+}
+
+impl McpStdioConfig {
+    /// Create a launch config for `command` with no arguments.
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the command-line arguments.
+    pub fn with_args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add an environment variable.
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set the working directory.
+    pub fn with_cwd(mut self, cwd: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(cwd.into());
+        self
+    }
+
+    /// Set the request timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// Responses awaited by in-flight requests, keyed by JSON-RPC id.
+type PendingMap = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value, String>>>>>;
+
+/// Server stdin, shared with the reader task so it can answer server requests.
+type SharedStdin = Arc<AsyncMutex<Option<ChildStdin>>>;
+
+/// A running stdio MCP server process.
+pub(crate) struct StdioConnection {
+    server: String,
+    child: AsyncMutex<Child>,
+    stdin: SharedStdin,
+    pending: PendingMap,
+    closed: Arc<AtomicBool>,
+    next_id: AtomicU64,
+    timeout: Duration,
+}
+
+impl StdioConnection {
+    /// Spawn the server and perform the `initialize` handshake.
+    ///
+    /// A server that does not answer `initialize` within the configured
+    /// timeout is killed and an error is returned.
+    ///
+    /// Notifications written by the server are published to `hub`.
+    pub(crate) async fn start(
+        server: &str,
+        config: &McpStdioConfig,
+        hub: Arc<NotificationHub>,
+    ) -> RegistryResult<Self> {
+        let mut command = Command::new(&config.command);
+        command
+            .args(&config.args)
+            .envs(&config.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            // Server logs would corrupt the TUI; stdout carries the protocol
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+        if let Some(ref cwd) = config.cwd {
+            command.current_dir(cwd);
+        }
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| server_error(server, format!("Failed to spawn '{}': {}", config.command, e)))?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| server_error(server, "Server stdin unavailable"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| server_error(server, "Server stdout unavailable"))?;
+
+        let stdin: SharedStdin = Arc::new(AsyncMutex::new(Some(stdin)));
+        let pending: PendingMap = Arc::new(Mutex::new(HashMap::new()));
+        let closed = Arc::new(AtomicBool::new(false));
+        tokio::spawn(read_loop(
+            server.to_string(),
+            BufReader::new(stdout),
+            pending.clone(),
+            stdin.clone(),
+            closed.clone(),
+            hub,
+        ));
+
+        let connection = Self {
+            server: server.to_string(),
+            child: AsyncMutex::new(child),
+            stdin,
+            pending,
+            closed,
+            next_id: AtomicU64::new(1),
+            timeout: config.timeout,
+        };
+
+        connection
+            .request(
+                "initialize",
+                Some(json!({
+                    "protocolVersion": "2024-11-05",
+                    "capabilities": {},
+                    "clientInfo": {
+                        "name": "abk-mcp-client",
+                        "version": env!("CARGO_PKG_VERSION")
+                    }
+                })),
+            )
+            .await?;
+        connection.notify("notifications/initialized", None).await?;
+
+        Ok(connection)
+    }
+
+    /// Check whether the server's stdout is still open.
+    pub(crate) fn is_alive(&self) -> bool {
+        !self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Send a request and wait for its result.
+    pub(crate) async fn request(&self, method: &str, params: Option<Value>) -> RegistryResult<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.insert(id, tx);
+        }
+
+        let mut message = json!({ "jsonrpc": "2.0", "id": id, "method": method });
+        if let Some(params) = params {
+            message["params"] = params;
+        }
+        if let Err(e) = self.send(&message).await {
+            if let Ok(mut pending) = self.pending.lock() {
+                pending.remove(&id);
+            }
+            return Err(e);
+        }
+
+        match tokio::time::timeout(self.timeout, rx).await {
+            Ok(Ok(Ok(result))) => Ok(result),
+            Ok(Ok(Err(message))) => Err(server_error(&self.server, format!("MCP error: {}", message))),
+            Ok(Err(_)) => Err(server_error(&self.server, "Server process exited")),
+            Err(_) => {
+                if let Ok(mut pending) = self.pending.lock() {
+                    pending.remove(&id);
+                }
+                Err(RegistryError::McpTimeout {
+                    server: self.server.clone(),
+                    method: method.to_string(),
+                    seconds: self.timeout.as_secs(),
+                })
+            }
+        }
+    }
+
+    /// Send a notification (no response expected).
+    pub(crate) async fn notify(&self, method: &str, params: Option<Value>) -> RegistryResult<()> {
+        let mut message = json!({ "jsonrpc": "2.0", "method": method });
+        if let Some(params) = params {
+            message["params"] = params;
+        }
+        self.send(&message).await
+    }
+
+    /// Close stdin and wait for the server to exit, killing it after a grace period.
+    pub(crate) async fn shutdown(&self) {
+        self.stdin.lock().await.take();
+        let mut child = self.child.lock().await;
+        if tokio::time::timeout(SHUTDOWN_GRACE, child.wait()).await.is_err() {
+            let _ = child.kill().await;
+        }
+    }
+
+    async fn send(&self, message: &Value) -> RegistryResult<()> {
+        write_message(&self.stdin, message)
+            .await
+            .map_err(|e| server_error(&self.server, format!("Failed to write to server: {}", e)))
+    }
+}
+
+async fn write_message(stdin: &SharedStdin, message: &Value) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    let mut guard = stdin.lock().await;
+    let stdin = guard
+        .as_mut()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "stdin closed"))?;
+    stdin.write_all(&line).await?;
+    stdin.flush().await
+}
+
+/// Route server messages until stdout closes, then fail all pending requests.
+async fn read_loop<R: AsyncRead + Unpin>(
+    server: String,
+    stdout: BufReader<R>,
+    pending: PendingMap,
+    stdin: SharedStdin,
+    closed: Arc<AtomicBool>,
+    hub: Arc<NotificationHub>,
+) {
+    let mut lines = stdout.lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        // Ignore anything that is not JSON (some servers print banners to stdout)
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+
+        if let Some(method) = message.get("method").and_then(|m| m.as_str()) {
+            // Server-to-client request: answer ping, reject everything else
+            if let Some(id) = message.get("id") {
+                let reply = if method == "ping" {
+                    json!({ "jsonrpc": "2.0", "id": id, "result": {} })
+                } else {
+                    json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": -32601, "message": format!("Method not found: {}", method) }
+                    })
+                };
+                let _ = write_message(&stdin, &reply).await;
+            } else {
+                hub.publish_message(&server, &message);
+            }
+            continue;
+        }
+
+        let Some(id) = message.get("id").and_then(|id| id.as_u64()) else {
+            continue;
+        };
+        let Some(tx) = pending.lock().ok().and_then(|mut p| p.remove(&id)) else {
+            continue;
+        };
+        let outcome = match message.get("error") {
+            Some(error) => Err(error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("unknown error")
+                .to_string()),
+            None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+        };
+        let _ = tx.send(outcome);
+    }
+
+    closed.store(true, Ordering::SeqCst);
+    if let Ok(mut pending) = pending.lock() {
+        // Dropping the senders wakes every waiter with an error
+        pending.clear();
+    }
+}
+
+pub(super) fn server_error(server: &str, message: impl Into<String>) -> RegistryError {
+    RegistryError::McpServerError {
+        server: server.to_string(),
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal MCP server: answers initialize, tools/list and tools/call.
+    const FAKE_SERVER: &str = r#"
+while IFS= read -r line; do
+  id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+  [ -z "$id" ] && continue
+  case "$line" in
+    *'"initialize"'*) printf '{"jsonrpc":"2.0","id":%s,"result":{"protocolVersion":"2024-11-05","capabilities":{}}}\n' "$id" ;;
+    *'"tools/list"'*) printf '{"jsonrpc":"2.0","id":%s,"result":{"tools":[{"name":"echo","description":"Echo","inputSchema":{"type":"object"}}]}}\n' "$id" ;;
+    *'"tools/call"'*) printf '{"jsonrpc":"2.0","id":%s,"result":{"content":[{"type":"text","text":"pong"}]}}\n' "$id" ;;
+    *) printf '{"jsonrpc":"2.0","id":%s,"error":{"code":-32601,"message":"nope"}}\n' "$id" ;;
+  esac
+done
+"#;
+
+    fn fake_server() -> McpStdioConfig {
+        McpStdioConfig::new("sh").with_args(["-c", FAKE_SERVER])
+    }
+
+    #[test]
+    fn test_stdio_config_builder() {
+        let config = McpStdioConfig::new("npx")
+            .with_args(["-y", "server"])
+            .with_env("TOKEN", "x")
+            .with_cwd("/tmp");
+        assert_eq!(config.command, "npx");
+        assert_eq!(config.args, vec!["-y", "server"]);
+        assert_eq!(config.env.get("TOKEN"), Some(&"x".to_string()));
+        assert_eq!(config.cwd, Some(PathBuf::from("/tmp")));
+    }
+
+    #[tokio::test]
+    async fn test_stdio_request_round_trip() {
+        let conn = StdioConnection::start("fake", &fake_server(), Arc::new(NotificationHub::new())).await.unwrap();
+        assert!(conn.is_alive());
+
+        let tools = conn.request("tools/list", Some(json!({}))).await.unwrap();
+        assert_eq!(tools["tools"][0]["name"], "echo");
+
+        let err = conn.request("resources/list", None).await.unwrap_err();
+        assert!(err.to_string().contains("nope"));
+
+        conn.shutdown().await;
+        assert!(conn.request("tools/list", None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_client_over_stdio() {
+        use crate::registry::{McpClient, McpServerConfig};
+
+        let client = McpClient::new();
+        let config = McpServerConfig::stdio("fake", fake_server());
+
+        let tools = client.fetch_tools(&config).await.unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "echo");
+
+        let result = client.call_tool(&config, "echo", json!({})).await.unwrap();
+        assert_eq!(result.content, "pong");
+        assert!(!result.is_error);
+
+        client.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_stdio_timeouts() {
+        // Never answers anything
+        let silent = McpStdioConfig::new("sh")
+            .with_args(["-c", "cat > /dev/null"])
+            .with_timeout(Duration::from_millis(200));
+        let result = StdioConnection::start("silent", &silent, Arc::new(NotificationHub::new())).await;
+        match result {
+            Err(RegistryError::McpTimeout { server, method, .. }) => {
+                assert_eq!(server, "silent");
+                assert_eq!(method, "initialize");
+            }
+            other => panic!("expected a timeout, got {:?}", other.map(|_| ())),
+        }
+
+        // Answers initialize, then hangs on tools/call
+        let script = FAKE_SERVER.replace("*'\"tools/call\"'*) printf", "*'\"tools/call\"'*) continue; printf");
+        let hanging = McpStdioConfig::new("sh")
+            .with_args(["-c", script.as_str()])
+            .with_timeout(Duration::from_millis(300));
+        let conn = StdioConnection::start("hanging", &hanging, Arc::new(NotificationHub::new())).await.unwrap();
+        let err = conn.request("tools/call", Some(json!({}))).await.unwrap_err();
+        assert!(matches!(err, RegistryError::McpTimeout { .. }));
+        assert!(err.to_string().contains("did not answer 'tools/call'"));
+        // The connection stays usable
+        assert!(conn.request("tools/list", None).await.is_ok());
+        conn.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_stdio_spawn_failure() {
+        let config = McpStdioConfig::new("/nonexistent/mcp-server");
+        let result = StdioConnection::start("missing", &config, Arc::new(NotificationHub::new())).await;
+        assert!(matches!(result, Err(RegistryError::McpServerError { .. })));
+    }
+}